@@ -1,3 +1,5 @@
+use data_pipeline::config::ApiConfig;
+use data_pipeline::fetcher::extract_products;
 use serde_json::Value;
 use std::fs;
 use std::collections::HashSet;
@@ -5,6 +7,10 @@ use std::collections::HashSet;
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("=== API DATA COMPARISON TOOL ===\n");
     
+    // Products are located the way the pipeline does, with KraveMart's data_path
+    let config = ApiConfig::from_file("src/configs/krave_mart.toml")?;
+    let data_path = config.response.data_path.as_ref();
+
     // Read the saved JSON file
     let saved_json = fs::read_to_string("krave_mart_api_response.json")?;
     let saved_data: Value = serde_json::from_str(&saved_json)?;
@@ -13,16 +19,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut saved_product_ids = HashSet::new();
     let mut saved_products = Vec::new();
     
-    if let Some(data_array) = saved_data.get("data").and_then(|d| d.as_array()) {
-        for data_item in data_array {
-            if let Some(l2_products) = data_item.get("l2_products").and_then(|l| l.as_array()) {
-                for product in l2_products {
-                    if let Some(product_id) = product.get("product_id").and_then(|id| id.as_u64()) {
-                        saved_product_ids.insert(product_id);
-                        saved_products.push((product_id, product.clone()));
-                    }
-                }
-            }
+    for product in extract_products(&saved_data, data_path) {
+        if let Some(product_id) = product.get("product_id").and_then(|id| id.as_u64()) {
+            saved_product_ids.insert(product_id);
+            saved_products.push((product_id, product));
         }
     }
     
@@ -44,16 +44,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         let mut live_product_ids = HashSet::new();
         let mut live_products = Vec::new();
         
-        if let Some(data_array) = live_data.get("data").and_then(|d| d.as_array()) {
-            for data_item in data_array {
-                if let Some(l2_products) = data_item.get("l2_products").and_then(|l| l.as_array()) {
-                    for product in l2_products {
-                        if let Some(product_id) = product.get("product_id").and_then(|id| id.as_u64()) {
-                            live_product_ids.insert(product_id);
-                            live_products.push((product_id, product.clone()));
-                        }
-                    }
-                }
+        for product in extract_products(&live_data, data_path) {
+            if let Some(product_id) = product.get("product_id").and_then(|id| id.as_u64()) {
+                live_product_ids.insert(product_id);
+                live_products.push((product_id, product));
             }
         }
         
@@ -1,3 +1,5 @@
+use data_pipeline::config::ApiConfig;
+use data_pipeline::fetcher::extract_products;
 use serde_json::Value;
 use std::fs;
 
@@ -13,43 +15,24 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         println!("API Response Count field: {}", count);
     }
     
-    // Navigate to the data array
-    let mut total_krave_mart_product_arrays = 0;
-    let mut total_products = 0;
+    // Locate the products exactly like the fetcher, with KraveMart's data_path
+    let config = ApiConfig::from_file("src/configs/krave_mart.toml")?;
+    let products = extract_products(&data, config.response.data_path.as_ref());
+    let total_products = products.len();
 
-    if let Some(data_array) = data.get("data").and_then(|d| d.as_array()) {
-        println!("Number of data items: {}", data_array.len());
-
-        for (index, data_item) in data_array.iter().enumerate() {
-            if let Some(krave_mart_products) = data_item.get("l2_products").and_then(|l| l.as_array()) {
-                total_krave_mart_product_arrays += 1;
-                let products_in_this_array = krave_mart_products.len();
-                total_products += products_in_this_array;
-
-                println!("Data item {}: {} products in KraveMart products array", index, products_in_this_array);
-                
-                // Show some details about the first few products
-                if index == 0 && products_in_this_array > 0 {
-                    println!("  Sample products from first KraveMart products array:");
-                    for (i, product) in krave_mart_products.iter().take(3).enumerate() {
-                        if let Some(name) = product.get("name").and_then(|n| n.as_str()) {
-                            if let Some(product_id) = product.get("product_id") {
-                                println!("    Product {}: ID={}, Name=\"{}\"", i + 1, product_id, name);
-                            }
-                        }
-                    }
-                    if products_in_this_array > 3 {
-                        println!("    ... and {} more products", products_in_this_array - 3);
-                    }
-                }
-            } else {
-                println!("Data item {}: No KraveMart products array found", index);
+    println!("Sample products:");
+    for (i, product) in products.iter().take(3).enumerate() {
+        if let Some(name) = product.get("name").and_then(|n| n.as_str()) {
+            if let Some(product_id) = product.get("product_id") {
+                println!("    Product {}: ID={}, Name=\"{}\"", i + 1, product_id, name);
             }
         }
     }
+    if total_products > 3 {
+        println!("    ... and {} more products", total_products - 3);
+    }
 
     println!("\n=== SUMMARY ===");
-    println!("Total number of KraveMart product arrays: {}", total_krave_mart_product_arrays);
     println!("Total number of individual products: {}", total_products);
     
     // Verify against the count field
@@ -67,24 +50,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut products_with_fallback_prices = 0;
     let mut products_with_no_prices = 0;
     
-    if let Some(data_array) = data.get("data").and_then(|d| d.as_array()) {
-        for data_item in data_array.iter() {
-            if let Some(krave_mart_products) = data_item.get("l2_products").and_then(|l| l.as_array()) {
-                for product in krave_mart_products.iter() {
-                    let has_cost_price = product.get("cost_price").is_some() && !product.get("cost_price").unwrap().is_null();
-                    let has_mrp = product.get("mrp").is_some() && !product.get("mrp").unwrap().is_null();
-                    let has_special_price = product.get("special_price").is_some() && !product.get("special_price").unwrap().is_null();
-                    let has_product_price = product.get("product_price").is_some() && !product.get("product_price").unwrap().is_null();
-                    
-                    if has_cost_price || has_mrp {
-                        products_with_primary_prices += 1;
-                    } else if has_special_price || has_product_price {
-                        products_with_fallback_prices += 1;
-                    } else {
-                        products_with_no_prices += 1;
-                    }
-                }
-            }
+    for product in &products {
+        let has_cost_price = product.get("cost_price").is_some() && !product.get("cost_price").unwrap().is_null();
+        let has_mrp = product.get("mrp").is_some() && !product.get("mrp").unwrap().is_null();
+        let has_special_price = product.get("special_price").is_some() && !product.get("special_price").unwrap().is_null();
+        let has_product_price = product.get("product_price").is_some() && !product.get("product_price").unwrap().is_null();
+
+        if has_cost_price || has_mrp {
+            products_with_primary_prices += 1;
+        } else if has_special_price || has_product_price {
+            products_with_fallback_prices += 1;
+        } else {
+            products_with_no_prices += 1;
         }
     }
     
@@ -98,31 +75,24 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut failed_extractions = 0;
     let mut failed_products = Vec::new();
 
-    if let Some(data_array) = data.get("data").and_then(|d| d.as_array()) {
-        for (data_index, data_item) in data_array.iter().enumerate() {
-            if let Some(krave_mart_products) = data_item.get("l2_products").and_then(|l| l.as_array()) {
-                for (product_index, product) in krave_mart_products.iter().enumerate() {
-                    // Simulate the pipeline's extract_fields_directly logic
-                    let product_id = product.get("product_id").and_then(|v| v.as_u64());
-                    let name = product.get("name").and_then(|v| v.as_str()).unwrap_or("").to_string();
-
-                    if product_id.is_none() {
-                        failed_extractions += 1;
-                        failed_products.push(format!("Data[{}].KraveMart_products[{}]: Missing product_id", data_index, product_index));
-                        continue;
-                    }
+    for (product_index, product) in products.iter().enumerate() {
+        // Simulate the pipeline's extract_fields_directly logic
+        let product_id = product.get("product_id").and_then(|v| v.as_u64());
+        let name = product.get("name").and_then(|v| v.as_str()).unwrap_or("").to_string();
 
-                    if name.is_empty() {
-                        failed_extractions += 1;
-                        failed_products.push(format!("Data[{}].KraveMart_products[{}]: Missing/empty name (product_id: {})",
-                            data_index, product_index, product_id.unwrap()));
-                        continue;
-                    }
+        if product_id.is_none() {
+            failed_extractions += 1;
+            failed_products.push(format!("Product {}: Missing product_id", product_index));
+            continue;
+        }
 
-                    successful_extractions += 1;
-                }
-            }
+        if name.is_empty() {
+            failed_extractions += 1;
+            failed_products.push(format!("Product {}: Missing/empty name (product_id: {})", product_index, product_id.unwrap()));
+            continue;
         }
+
+        successful_extractions += 1;
     }
 
     println!("Successful extractions: {}", successful_extractions);
@@ -1,9 +1,10 @@
 use anyhow::{Context, Result};
-use config::MinioConfig;
+use chrono::NaiveDate;
+use config::{MinioConfig, SourcesConfig};
 use dotenv;
 use polars::prelude::*;
 use processor::{FieldClassifier, JsonFlattener, RuleNormalizer};
-use storage::MinioStorage;
+use storage::{MinioStorage, RawSnapshot};
 use tracing::{error, info, warn};
 use tracing_subscriber;
 
@@ -27,8 +28,46 @@ async fn main() -> Result<()> {
 
     info!("🚀 Starting Multi-Source Data Pipeline (Processing from S3/MinIO Storage)");
 
-    // Define all available sources
-    let sources = vec!["krave_mart", "bazaar_app"];
+    // Reprocess the raw snapshot from a specific day instead of the latest one
+    let args: Vec<String> = std::env::args().collect();
+    let snapshot_date = args
+        .iter()
+        .position(|arg| arg == "--date")
+        .and_then(|pos| args.get(pos + 1))
+        .map(|s| NaiveDate::parse_from_str(s, "%Y-%m-%d"))
+        .transpose()
+        .context("--date must be in YYYY-MM-DD format")?;
+    // Or one specific raw object
+    let snapshot_key = args
+        .iter()
+        .position(|arg| arg == "--key")
+        .and_then(|pos| args.get(pos + 1))
+        .cloned();
+
+    let snapshot = match (snapshot_date, snapshot_key) {
+        (Some(_), Some(_)) => return Err(anyhow::anyhow!("--date and --key cannot be combined")),
+        (Some(date), None) => {
+            info!("📅 Processing raw snapshots from {}", date);
+            RawSnapshot::Date(date)
+        }
+        (None, Some(key)) => {
+            info!("📅 Processing raw snapshot {}", key);
+            RawSnapshot::Key(key)
+        }
+        (None, None) => RawSnapshot::Latest,
+    };
+
+    // Every enabled source, unless one is picked with --source
+    let source_filter = args
+        .iter()
+        .position(|arg| arg == "--source")
+        .and_then(|pos| args.get(pos + 1));
+    let sources_config = SourcesConfig::from_file("src/configs/sources.toml")?;
+    let sources: Vec<&str> = sources_config
+        .select(source_filter.map(String::as_str))?
+        .into_iter()
+        .map(|source| source.name.as_str())
+        .collect();
 
     // Load MinIO configuration
     let minio_config = MinioConfig::from_file("src/configs/minio.toml")
@@ -49,7 +88,7 @@ async fn main() -> Result<()> {
     // Initialize processing components
     let flattener = JsonFlattener::new();
     let classifier = FieldClassifier::new();
-    let normalizer = RuleNormalizer;
+    let normalizer = RuleNormalizer::new();
 
     // Ensure bucket exists
     storage.ensure_bucket().await?;
@@ -58,11 +97,12 @@ async fn main() -> Result<()> {
     let mut total_products = 0;
     let mut successful_sources = 0;
 
-    for source_name in &sources {
+    for source_name in sources.iter().filter(|name| snapshot.applies_to(name)) {
         info!("\n=== Processing Source from Storage: {} ===", source_name);
 
         match process_source_from_storage(
             source_name,
+            &snapshot,
             &storage,
             &flattener,
             &classifier,
@@ -104,6 +144,7 @@ async fn main() -> Result<()> {
 
 async fn process_source_from_storage(
     source_name: &str,
+    snapshot: &RawSnapshot,
     storage: &MinioStorage,
     flattener: &JsonFlattener,
     classifier: &FieldClassifier,
@@ -112,8 +153,9 @@ async fn process_source_from_storage(
     info!("Loading raw data from storage for {}", source_name);
 
     // Load raw data from S3/MinIO storage
+    let raw_file = storage.resolve_raw_file(source_name, snapshot).await?;
     let raw_data = storage
-        .load_latest_raw_data(source_name)
+        .load_raw_file(&raw_file)
         .await
         .with_context(|| format!("Failed to load raw data for {} from storage", source_name))?;
 
@@ -130,7 +172,7 @@ async fn process_source_from_storage(
 
     // Process data through pipeline
     info!("Processing {} products through pipeline", products_count);
-    let mut df = flattener.flatten_to_dataframe(&raw_data)?;
+    let mut df = flattener.flatten_to_dataframe(&raw_data)?.dataframe;
     info!("Flattened to DataFrame with {} rows", df.height());
 
     // Apply ML classification
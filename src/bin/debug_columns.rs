@@ -1,14 +1,6 @@
 use serde_json::json;
 use anyhow::Result;
-
-#[path = "../processor/json_flattener.rs"]
-mod json_flattener;
-
-#[path = "../processor/field_classifier.rs"]
-mod field_classifier;
-
-use json_flattener::JsonFlattener;
-use field_classifier::FieldClassifier;
+use data_pipeline::processor::{JsonFlattener, FieldClassifier};
 
 fn main() -> Result<()> {
     println!("=== DEBUGGING COLUMN MAPPING ISSUE ===\n");
@@ -35,7 +27,7 @@ fn main() -> Result<()> {
     
     // Step 1: Flatten to DataFrame
     println!("\n2. After JSON flattening:");
-    let mut df = flattener.flatten_to_dataframe(&sample_data)?;
+    let mut df = flattener.flatten_to_dataframe(&sample_data)?.dataframe;
     
     println!("   Columns: {:?}", df.get_column_names());
     
@@ -1,18 +1,6 @@
 use serde_json::json;
 use anyhow::Result;
-
-#[path = "../processor/json_flattener.rs"]
-mod json_flattener;
-
-#[path = "../processor/field_classifier.rs"]
-mod field_classifier;
-
-#[path = "../processor/rule_normalizer.rs"]
-mod rule_normalizer;
-
-use json_flattener::JsonFlattener;
-use field_classifier::FieldClassifier;
-use rule_normalizer::RuleNormalizer;
+use data_pipeline::processor::{JsonFlattener, FieldClassifier, RuleNormalizer, NAME_ORIGINAL_FIELD};
 
 fn main() -> Result<()> {
     println!("=== TESTING DATA CLEANING IMPROVEMENTS ===\n");
@@ -69,11 +57,11 @@ fn main() -> Result<()> {
     // Initialize pipeline components
     let flattener = JsonFlattener::new();
     let classifier = FieldClassifier::new();
-    let normalizer = RuleNormalizer;
+    let normalizer = RuleNormalizer::new();
     
     // Run the full pipeline
     println!("Running full pipeline with data cleaning...\n");
-    let mut df = flattener.flatten_to_dataframe(&test_data)?;
+    let mut df = flattener.flatten_to_dataframe(&test_data)?.dataframe;
     
     println!("1. After JSON flattening:");
     println!("{}", df.head(Some(2)));
@@ -97,13 +85,13 @@ fn main() -> Result<()> {
         println!("   Values: {:?}", discount_col);
     }
     
-    // Check name column (should be cleaned)
-    if let Ok(name_col) = df.column("name") {
+    // Check name column (should be cleaned, with the original kept next to it)
+    if let (Ok(name_col), Ok(original_col)) = (df.column("name"), df.column(NAME_ORIGINAL_FIELD)) {
         println!("\n✅ Cleaned names:");
-        if let Ok(names) = name_col.str() {
-            for (i, name_opt) in names.into_iter().enumerate() {
-                if let Some(name) = name_opt {
-                    println!("   Product {}: \"{}\"", i + 1, name);
+        if let (Ok(names), Ok(originals)) = (name_col.str(), original_col.str()) {
+            for (i, (name_opt, original_opt)) in names.into_iter().zip(originals).enumerate() {
+                if let (Some(name), Some(original)) = (name_opt, original_opt) {
+                    println!("   Product {}: \"{}\" (from \"{}\")", i + 1, name, original);
                 }
             }
         }
@@ -140,6 +128,7 @@ fn main() -> Result<()> {
     println!("   - mrp: f64 (numeric)");
     println!("   - discount: f64 (numeric) ← NEW!");
     println!("   - name: string (cleaned)");
+    println!("   - name_original: string (as fetched)");
     println!("   - units_of_mass: string (extracted)");
     
     Ok(())
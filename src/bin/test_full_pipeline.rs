@@ -1,20 +1,10 @@
+use data_pipeline::config::ApiConfig;
+use data_pipeline::fetcher::extract_products;
+use data_pipeline::processor::{JsonFlattener, FieldClassifier, RuleNormalizer, quality_report};
 use serde_json::Value;
 use std::fs;
 use anyhow::Result;
 
-#[path = "../processor/json_flattener.rs"]
-mod json_flattener;
-
-#[path = "../processor/field_classifier.rs"]
-mod field_classifier;
-
-#[path = "../processor/rule_normalizer.rs"]
-mod rule_normalizer;
-
-use json_flattener::JsonFlattener;
-use field_classifier::FieldClassifier;
-use rule_normalizer::RuleNormalizer;
-
 fn main() -> Result<()> {
     println!("=== FULL PIPELINE TEST ===\n");
     
@@ -22,29 +12,20 @@ fn main() -> Result<()> {
     let json_content = fs::read_to_string("krave_mart_api_response.json")?;
     let data: Value = serde_json::from_str(&json_content)?;
     
-    // Simulate the HttpFetcher.extract_products logic exactly
-    let mut all_products = Vec::new();
-
-    if let Some(data_array) = data.get("data").and_then(|d| d.as_array()) {
-        for data_item in data_array {
-            if let Some(krave_mart_products) = data_item.get("l2_products").and_then(|l| l.as_array()) {
-                for product in krave_mart_products {
-                    all_products.push(product.clone());
-                }
-            }
-        }
-    }
+    // Locate the products exactly like the fetcher, with KraveMart's data_path
+    let config = ApiConfig::from_file("src/configs/krave_mart.toml")?;
+    let all_products = extract_products(&data, config.response.data_path.as_ref());
     
     println!("Extracted {} products from API response", all_products.len());
     
     // Initialize components exactly like main.rs
     let flattener = JsonFlattener::new();
     let classifier = FieldClassifier::new();
-    let normalizer = RuleNormalizer;
+    let normalizer = RuleNormalizer::new();
     
     // Process data exactly like main.rs
     println!("\n1. Flattening to DataFrame...");
-    let mut df = flattener.flatten_to_dataframe(&all_products)?;
+    let mut df = flattener.flatten_to_dataframe(&all_products)?.dataframe;
     println!("   Rows after flattening: {}", df.height());
     
     // Apply ML classification
@@ -76,23 +57,9 @@ fn main() -> Result<()> {
         println!("{}", sample);
     }
     
-    // Check for any null values in key columns
+    // Missing values are nulls, so completeness reflects what the sources sent
     println!("\n=== DATA QUALITY CHECK ===");
-    
-    if let Ok(cost_price_col) = df.column("cost_price") {
-        let null_count = cost_price_col.null_count();
-        println!("cost_price: {} null values out of {}", null_count, cost_price_col.len());
-    }
-    
-    if let Ok(mrp_col) = df.column("mrp") {
-        let null_count = mrp_col.null_count();
-        println!("mrp: {} null values out of {}", null_count, mrp_col.len());
-    }
-    
-    if let Ok(name_col) = df.column("name") {
-        let null_count = name_col.null_count();
-        println!("name: {} null values out of {}", null_count, name_col.len());
-    }
+    print!("{}", quality_report(&df));
     
     Ok(())
 }
@@ -1,12 +1,49 @@
 use anyhow::{Context, Result};
-use config::MinioConfig;
+use config::{BatchConfig, MinioConfig, choose_batch_size};
 use dotenv;
+use polars::prelude::*;
 use processor::{FieldClassifier, JsonFlattener, RuleNormalizer};
+use serde_json::{Value, json};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use storage::MinioStorage;
 use tracing::{info, warn};
 use tracing_subscriber;
 use std::time::Instant;
 
+/// System allocator that tracks current and peak heap usage
+struct PeakTrackingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK_ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for PeakTrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            let now = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK_ALLOCATED.fetch_max(now, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: PeakTrackingAllocator = PeakTrackingAllocator;
+
+/// Peak heap growth in bytes while running `f`
+fn peak_heap_during<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let baseline = ALLOCATED.load(Ordering::Relaxed);
+    PEAK_ALLOCATED.store(baseline, Ordering::Relaxed);
+    let result = f();
+    (result, PEAK_ALLOCATED.load(Ordering::Relaxed).saturating_sub(baseline))
+}
+
 mod config {
     pub use data_pipeline::config::*;
 }
@@ -27,6 +64,11 @@ async fn main() -> Result<()> {
 
     info!("🧪 Testing Memory-Efficient Data Pipeline");
 
+    if std::env::args().any(|arg| arg == "--synthetic") {
+        // No storage needed: compare peak memory of both batched paths
+        return compare_synthetic_peak_memory();
+    }
+
     // Load MinIO configuration
     let minio_config = MinioConfig::from_file("src/configs/minio.toml")
         .context("Failed to load MinIO configuration")?;
@@ -38,7 +80,8 @@ async fn main() -> Result<()> {
     // Initialize processing components
     let flattener = JsonFlattener::new();
     let classifier = FieldClassifier::new();
-    let normalizer = RuleNormalizer;
+    let normalizer = RuleNormalizer::new();
+    let batching = BatchConfig::from_file("src/configs/batching.toml")?;
 
     // Test sources with different sizes
     let test_sources = vec![
@@ -58,6 +101,7 @@ async fn main() -> Result<()> {
             &flattener,
             &classifier,
             &normalizer,
+            &batching,
         ).await {
             Ok((products_count, processing_method)) => {
                 let duration = start_time.elapsed();
@@ -84,6 +128,7 @@ async fn test_memory_efficient_processing(
     flattener: &JsonFlattener,
     classifier: &FieldClassifier,
     normalizer: &RuleNormalizer,
+    batching: &BatchConfig,
 ) -> Result<(usize, String)> {
     // Get metadata first to determine processing approach
     let (file_path, total_products) = storage.get_latest_raw_data_info(source_name).await
@@ -97,14 +142,11 @@ async fn test_memory_efficient_processing(
     }
 
     // Determine batch size based on dataset size
-    let (batch_size, processing_method) = if total_products <= 500 {
-        (total_products, "Very small dataset - standard processing")
-    } else if total_products <= 5000 {
-        (500, "Small-medium dataset - batched processing (500 per batch)")
-    } else if total_products <= 50000 {
-        (2000, "Medium dataset - batched processing (2K per batch)")
+    let batch_size = choose_batch_size(total_products, batching);
+    let processing_method = if batch_size >= total_products {
+        "Small dataset - standard processing".to_string()
     } else {
-        (5000, "Large dataset - batched processing (5K per batch)")
+        format!("Large dataset - batched processing ({} per batch)", batch_size)
     };
 
     info!("🔧 Processing method: {}", processing_method);
@@ -115,12 +157,12 @@ async fn test_memory_efficient_processing(
         // Small dataset - use original method
         info!("📥 Loading all data at once...");
         let raw_data = storage.load_latest_raw_data(source_name).await?;
-        flattener.flatten_to_dataframe(&raw_data)?
+        flattener.flatten_to_dataframe(&raw_data)?.dataframe
     } else {
         // Large dataset - use batched processing
         info!("📥 Streaming data in batches of {}...", batch_size);
         let batches = storage.stream_latest_raw_data_batched(source_name, batch_size).await?;
-        flattener.flatten_to_dataframe_batched(batches)?
+        flattener.flatten_to_dataframe_batched(batches)?.dataframe
     };
 
     let flattening_duration = processing_start.elapsed();
@@ -145,5 +187,71 @@ async fn test_memory_efficient_processing(
     info!("📈 Processing rate: {:.0} products/second", 
           total_products as f64 / total_processing_time.as_secs_f64());
 
-    Ok((total_products, processing_method.to_string()))
+    Ok((total_products, processing_method))
+}
+
+/// Synthetic KraveMart-like batches, generated lazily like a streamed raw file
+fn synthetic_batches(total: usize, batch_size: usize) -> impl Iterator<Item = Result<Vec<Value>>> {
+    (0..total).step_by(batch_size).map(move |start| {
+        Ok((start..(start + batch_size).min(total))
+            .map(|i| {
+                json!({
+                    "product_id": i,
+                    "name": format!("Synthetic Product {} - 1 Kg", i),
+                    "special_price": format!("{}.00", 100 + i % 500),
+                    "product_price": format!("{}.00", 150 + i % 500),
+                    "sku": format!("SKU{:08}", i),
+                    "sku_percent_off": format!("{}% off", i % 40),
+                    "description": "Synthetic product used to measure peak memory",
+                    "default_image": format!("https://cdn.example.com/products/{}.png", i),
+                    "categories": [{"category_name": "Fruits & Vegetables"}],
+                    "inventories": {"quantity": i % 20}
+                })
+            })
+            .collect())
+    })
+}
+
+/// Flatten a 200k-row synthetic dataset through the old vstack path and the
+/// streaming Parquet path and check the streaming one peaks lower
+fn compare_synthetic_peak_memory() -> Result<()> {
+    const ROWS: usize = 200_000;
+    const BATCH_SIZE: usize = 5_000;
+
+    let flattener = JsonFlattener::new();
+
+    let (vstacked, vstack_peak) = peak_heap_during(|| -> Result<usize> {
+        let mut df = flattener.flatten_to_dataframe_batched(synthetic_batches(ROWS, BATCH_SIZE))?.dataframe;
+        let mut buf = Vec::new();
+        ParquetWriter::new(&mut buf).finish(&mut df)?;
+        Ok(buf.len())
+    });
+    let vstacked = vstacked?;
+
+    let (streamed, streamed_peak) = peak_heap_during(|| -> Result<usize> {
+        let mut buf = Vec::new();
+        let summary = flattener.flatten_batched_to_parquet(
+            synthetic_batches(ROWS, BATCH_SIZE),
+            &mut buf,
+            |_| Ok(()),
+        )?;
+        anyhow::ensure!(summary.rows == ROWS, "expected {} rows, wrote {}", ROWS, summary.rows);
+        Ok(buf.len())
+    });
+    let streamed = streamed?;
+
+    let mb = |bytes: usize| bytes as f64 / (1024.0 * 1024.0);
+    info!("📦 vstack + write:   peak {:.1} MB, parquet {:.1} MB", mb(vstack_peak), mb(vstacked));
+    info!("📦 streamed batches: peak {:.1} MB, parquet {:.1} MB", mb(streamed_peak), mb(streamed));
+
+    // Coarse check: streaming should need well under the vstack path's peak
+    anyhow::ensure!(
+        streamed_peak * 4 < vstack_peak * 3,
+        "streamed peak {:.1} MB is not measurably below vstack peak {:.1} MB",
+        mb(streamed_peak),
+        mb(vstack_peak)
+    );
+    info!("✅ Streaming Parquet output lowered peak memory by {:.0}%",
+          100.0 * (1.0 - streamed_peak as f64 / vstack_peak as f64));
+    Ok(())
 }
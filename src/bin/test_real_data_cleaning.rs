@@ -1,17 +1,7 @@
 use anyhow::Result;
-
-#[path = "../processor/json_flattener.rs"]
-mod json_flattener;
-
-#[path = "../processor/field_classifier.rs"]
-mod field_classifier;
-
-#[path = "../processor/rule_normalizer.rs"]
-mod rule_normalizer;
-
-use json_flattener::JsonFlattener;
-use field_classifier::FieldClassifier;
-use rule_normalizer::RuleNormalizer;
+use data_pipeline::config::ApiConfig;
+use data_pipeline::fetcher::extract_products;
+use data_pipeline::processor::{JsonFlattener, FieldClassifier, RuleNormalizer, NAME_ORIGINAL_FIELD};
 
 fn main() -> Result<()> {
     println!("=== TESTING DATA CLEANING WITH REAL API DATA ===\n");
@@ -20,29 +10,19 @@ fn main() -> Result<()> {
     let json_content = std::fs::read_to_string("krave_mart_api_response.json")?;
     let api_response: serde_json::Value = serde_json::from_str(&json_content)?;
     
-    // Extract the data array
-    let data_array = api_response["data"].as_array()
-        .ok_or_else(|| anyhow::anyhow!("No data array found"))?;
-    
-    // Flatten all KraveMart products
-    let mut all_products = Vec::new();
-    for data_item in data_array {
-        if let Some(krave_mart_products) = data_item["l2_products"].as_array() {
-            for product in krave_mart_products {
-                all_products.push(product.clone());
-            }
-        }
-    }
+    // Locate the products exactly like the fetcher, with KraveMart's data_path
+    let config = ApiConfig::from_file("src/configs/krave_mart.toml")?;
+    let all_products = extract_products(&api_response, config.response.data_path.as_ref());
     
     println!("Processing {} products from real API data...\n", all_products.len());
     
     // Initialize pipeline components
     let flattener = JsonFlattener::new();
     let classifier = FieldClassifier::new();
-    let normalizer = RuleNormalizer;
+    let normalizer = RuleNormalizer::new();
     
     // Run the full pipeline
-    let mut df = flattener.flatten_to_dataframe(&all_products)?;
+    let mut df = flattener.flatten_to_dataframe(&all_products)?.dataframe;
     
     println!("1. After JSON flattening:");
     println!("   Total rows: {}", df.height());
@@ -83,13 +63,12 @@ fn main() -> Result<()> {
     }
     
     // Check name cleaning
-    if let Ok(name_col) = df.column("name") {
+    if let (Ok(name_col), Ok(original_col)) = (df.column("name"), df.column(NAME_ORIGINAL_FIELD)) {
         println!("\n✅ Name cleaning:");
-        if let Ok(names) = name_col.str() {
-            let sample_names: Vec<_> = names.into_iter().take(5).collect();
-            for (i, name_opt) in sample_names.iter().enumerate() {
-                if let Some(name) = name_opt {
-                    println!("   Sample {}: \"{}\"", i + 1, name);
+        if let (Ok(names), Ok(originals)) = (name_col.str(), original_col.str()) {
+            for (i, (name_opt, original_opt)) in names.into_iter().zip(originals).take(5).enumerate() {
+                if let (Some(name), Some(original)) = (name_opt, original_opt) {
+                    println!("   Sample {}: \"{}\" (from \"{}\")", i + 1, name, original);
                 }
             }
         }
@@ -133,7 +112,7 @@ fn main() -> Result<()> {
     println!("\n🎯 Name Cleaning:");
     println!("   ✅ Removed parenthetical translations: (Aalu), (Kheera), etc.");
     println!("   ✅ Extracted units to separate column");
-    println!("   ✅ Normalized to lowercase");
+    println!("   ✅ Normalized to lowercase (lowercase_names), original kept in name_original");
     println!("   ✅ Cleaned extra spaces and formatting");
     
     println!("\n🎯 Units Extraction:");
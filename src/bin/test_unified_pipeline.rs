@@ -1,7 +1,7 @@
 use anyhow::Result;
 use data_pipeline::config::ApiConfig;
 use data_pipeline::fetcher::UnifiedFetcher;
-use data_pipeline::processor::{FieldClassifier, JsonFlattener, RuleNormalizer};
+use data_pipeline::processor::{FieldClassifier, JsonFlattener, RuleNormalizer, quality_report};
 use polars::prelude::*;
 use std::env;
 use tracing::info;
@@ -45,7 +45,7 @@ async fn main() -> Result<()> {
     let fetcher = UnifiedFetcher::new(config.clone())?;
     let flattener = JsonFlattener::new();
     let classifier = FieldClassifier::new();
-    let normalizer = RuleNormalizer;
+    let normalizer = RuleNormalizer::new();
     
     println!("\n=== Step 1: Fetching Data ===");
     
@@ -77,7 +77,7 @@ async fn main() -> Result<()> {
     
     // Step 2.1: JSON Flattening
     println!("2.1 Flattening JSON to DataFrame...");
-    let mut df = flattener.flatten_to_dataframe(&raw_data)?;
+    let mut df = flattener.flatten_to_dataframe(&raw_data)?.dataframe;
     println!("   ✅ Flattened to {} rows, {} columns", df.height(), df.width());
     println!("   Columns: {:?}", df.get_column_names());
     
@@ -112,16 +112,8 @@ async fn main() -> Result<()> {
         println!("⚠️ Missing fields: {:?}", missing_fields);
     }
     
-    // Data completeness analysis
-    let total_rows = df.height();
-    for field in &present_fields {
-        if let Ok(col) = df.column(field) {
-            let null_count = col.null_count();
-            let completeness = ((total_rows - null_count) as f64 / total_rows as f64) * 100.0;
-            println!("📊 {}: {:.1}% complete ({}/{} non-null)", 
-                field, completeness, total_rows - null_count, total_rows);
-        }
-    }
+    // Per-column completeness and price statistics
+    print!("📊 {}", quality_report(&df));
     
     println!("\n=== Step 4: Sample Data Display ===");
     
@@ -141,42 +133,7 @@ async fn main() -> Result<()> {
     println!("🛍️ Sample processed products:");
     println!("{}", sample_df);
     
-    println!("\n=== Step 5: Price Analysis ===");
-    
-    // Price statistics
-    if df.column("cost_price").is_ok() && df.column("mrp").is_ok() {
-        let price_stats = df
-            .clone()
-            .lazy()
-            .select([
-                col("cost_price").min().alias("min_cost"),
-                col("cost_price").max().alias("max_cost"),
-                col("cost_price").mean().alias("avg_cost"),
-                col("mrp").min().alias("min_mrp"),
-                col("mrp").max().alias("max_mrp"),
-                col("mrp").mean().alias("avg_mrp"),
-            ])
-            .collect()?;
-        
-        println!("💰 Price Statistics:");
-        if let (Ok(min_cost), Ok(max_cost), Ok(avg_cost)) = (
-            price_stats.column("min_cost")?.get(0),
-            price_stats.column("max_cost")?.get(0),
-            price_stats.column("avg_cost")?.get(0),
-        ) {
-            println!("   Cost Price: Min={:.2}, Max={:.2}, Avg={:.2}", min_cost, max_cost, avg_cost);
-        }
-        
-        if let (Ok(min_mrp), Ok(max_mrp), Ok(avg_mrp)) = (
-            price_stats.column("min_mrp")?.get(0),
-            price_stats.column("max_mrp")?.get(0),
-            price_stats.column("avg_mrp")?.get(0),
-        ) {
-            println!("   MRP: Min={:.2}, Max={:.2}, Avg={:.2}", min_mrp, max_mrp, avg_mrp);
-        }
-    }
-    
-    println!("\n=== Step 6: Export Results ===");
+    println!("\n=== Step 5: Export Results ===");
     
     // Export to CSV
     let output_file = format!("{}_processed_sample.csv", source);
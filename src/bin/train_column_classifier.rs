@@ -0,0 +1,61 @@
+use anyhow::{Context, Result};
+use data_pipeline::processor::{ColumnModel, parse_labeled_csv};
+
+/// Train the column classifier consulted by `FieldClassifier` and save it
+/// for `data-pipeline --column-model`.
+///
+/// Usage: train_column_classifier [labels.csv] [model.json] [--threshold 0.6]
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let threshold = args
+        .iter()
+        .position(|arg| arg == "--threshold")
+        .and_then(|pos| args.get(pos + 1))
+        .map(|s| s.parse::<f32>())
+        .transpose()
+        .context("--threshold must be a number between 0 and 1")?;
+    let paths: Vec<&String> = args
+        .iter()
+        .enumerate()
+        .filter(|(i, arg)| {
+            *arg != "--threshold" && (*i == 0 || args[i - 1] != "--threshold")
+        })
+        .map(|(_, arg)| arg)
+        .collect();
+
+    let labels_path = paths
+        .first()
+        .map(|s| s.as_str())
+        .unwrap_or("src/configs/column_labels.csv");
+    let model_path = paths
+        .get(1)
+        .map(|s| s.as_str())
+        .unwrap_or("column_model.json");
+
+    let csv = std::fs::read_to_string(labels_path)
+        .with_context(|| format!("Failed to read labeled columns from {}", labels_path))?;
+    let examples = parse_labeled_csv(&csv)?;
+    println!("🧠 Training column classifier on {} labeled columns from {}", examples.len(), labels_path);
+
+    let mut model = ColumnModel::train(&examples)?;
+    if let Some(threshold) = threshold {
+        model = model.with_confidence_threshold(threshold);
+    }
+
+    // Training accuracy is only a sanity check, the fixture is small
+    let correct = examples
+        .iter()
+        .filter(|e| model.classify(&e.column_name, &e.sample_values).as_deref() == Some(e.label.as_str()))
+        .count();
+    println!(
+        "✅ Labels: {} | {}/{} training columns classified above the {:.2} threshold",
+        model.labels().join(", "),
+        correct,
+        examples.len(),
+        model.confidence_threshold()
+    );
+
+    model.save(model_path)?;
+    println!("💾 Saved model to {}", model_path);
+    Ok(())
+}
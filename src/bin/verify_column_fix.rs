@@ -1,18 +1,6 @@
 use serde_json::json;
 use anyhow::Result;
-
-#[path = "../processor/json_flattener.rs"]
-mod json_flattener;
-
-#[path = "../processor/field_classifier.rs"]
-mod field_classifier;
-
-#[path = "../processor/rule_normalizer.rs"]
-mod rule_normalizer;
-
-use json_flattener::JsonFlattener;
-use field_classifier::FieldClassifier;
-use rule_normalizer::RuleNormalizer;
+use data_pipeline::processor::{JsonFlattener, FieldClassifier, RuleNormalizer};
 
 fn main() -> Result<()> {
     println!("=== VERIFYING COLUMN MAPPING FIX ===\n");
@@ -42,11 +30,11 @@ fn main() -> Result<()> {
     // Initialize pipeline components
     let flattener = JsonFlattener::new();
     let classifier = FieldClassifier::new();
-    let normalizer = RuleNormalizer;
+    let normalizer = RuleNormalizer::new();
     
     // Run the full pipeline
     println!("Running full pipeline...");
-    let mut df = flattener.flatten_to_dataframe(&test_data)?;
+    let mut df = flattener.flatten_to_dataframe(&test_data)?.dataframe;
     
     println!("\n1. After JSON flattening:");
     println!("   Columns: {:?}", df.get_column_names());
@@ -86,7 +74,8 @@ fn main() -> Result<()> {
     if let (Ok(sku_col), Ok(discount_col)) = (df.column("sku"), df.column("discount")) {
         // Check that SKU values are in SKU column (not discount)
         let sku_values = sku_col.str().unwrap().into_no_null_iter().collect::<Vec<_>>();
-        let discount_values = discount_col.str().unwrap().into_no_null_iter().collect::<Vec<_>>();
+        // Discounts are numeric percentages once normalized
+        let discount_values = discount_col.f64().unwrap().into_no_null_iter().collect::<Vec<_>>();
         
         println!("\nSKU values: {:?}", sku_values);
         println!("Discount values: {:?}", discount_values);
@@ -101,7 +90,7 @@ fn main() -> Result<()> {
         
         // Verify discount values don't contain SKU patterns
         for discount_val in &discount_values {
-            if !discount_val.contains("%") && !discount_val.contains("off") && !discount_val.is_empty() {
+            if !(0.0..=100.0).contains(discount_val) {
                 println!("❌ Discount column contains non-discount value: {}", discount_val);
                 success = false;
             }
@@ -0,0 +1,789 @@
+use anyhow::Result;
+use chrono::NaiveDate;
+use clap::{ArgGroup, Args, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::config::{SourceType, SourcesConfig};
+use crate::processor::DedupStrategy;
+use crate::storage::OutputFormat;
+
+/// Every source the pipeline can run, in the config dir
+pub const SOURCES_FILE: &str = "sources.toml";
+
+/// Source configs, rule files and schemas, hashed into clean file metadata
+pub const DEFAULT_CONFIG_DIR: &str = "src/configs";
+
+/// `--max-drop` when not given: share of raw products, in percent, that may
+/// go missing before processing warns about it
+pub const DEFAULT_MAX_DROP_PCT: f64 = 5.0;
+
+/// Seconds to wait before running the sources that failed a second time
+pub const DEFAULT_RETRY_DELAY_SECS: u64 = 30;
+
+/// Multi-source grocery price pipeline: fetches product data from store APIs
+/// and HTML pages, stores the raw JSON and processes it into clean Parquet
+/// and a merged dataset
+#[derive(Parser, Debug)]
+#[command(name = "data-pipeline", version, args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Deprecated alias of `process`
+    #[arg(short = 's', long = "from-storage", hide = true)]
+    pub from_storage: bool,
+
+    #[command(flatten)]
+    pub run: RunArgs,
+
+    /// With the deprecated `--from-storage`, see `process`
+    #[command(flatten)]
+    pub snapshot: SnapshotArgs,
+
+    #[command(flatten)]
+    pub logging: LogArgs,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Fetch every source and store its raw JSON, without processing it
+    Fetch(FetchArgs),
+    /// Process the stored raw JSON into clean Parquet and the merged dataset
+    Process(ProcessArgs),
+    /// Fetch, store and process every source end to end
+    Run(RunArgs),
+    /// Compare the two latest clean snapshots of each source, or those of two --date days
+    Diff(DiffArgs),
+    /// Re-run normalization on the latest clean snapshot of each source
+    Reprocess(ReprocessArgs),
+    /// Collect a product's prices from every clean snapshot
+    History(HistoryArgs),
+    /// Delete the stored raw dumps of a day, or every object under a key prefix
+    Cleanup(CleanupArgs),
+    /// Diagnose the storage setup stage by stage
+    CheckStorage(ConfigArgs),
+    /// Load and check every source config in sources.toml without fetching anything
+    ValidateConfig(ConfigArgs),
+    /// Keep running, fetching and processing each source on its schedule in sources.toml
+    Serve(ServeArgs),
+}
+
+/// How log lines are written, for every subcommand
+#[derive(Args, Debug, Clone, Default)]
+pub struct LogArgs {
+    /// Format of the log lines on stdout and in --log-dir files
+    #[arg(long, value_enum, default_value_t = LogFormat::Pretty, global = true)]
+    pub log_format: LogFormat,
+
+    /// Also write each source's log lines to `<DIR>/<source>.log`, rolled daily
+    #[arg(long, value_name = "DIR", global = true)]
+    pub log_dir: Option<String>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Pretty,
+    /// One JSON object per line, with the source, category and page spans
+    Json,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ConfigArgs {
+    /// Directory of the source and pipeline configs
+    #[arg(long, default_value = DEFAULT_CONFIG_DIR)]
+    pub config_dir: String,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct SourceArgs {
+    /// Only this source of sources.toml instead of every enabled one
+    #[arg(long)]
+    pub source: Option<String>,
+
+    #[command(flatten)]
+    pub config: ConfigArgs,
+}
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct FetchOptions {
+    /// Store raw dumps even when they match the latest one
+    #[arg(long)]
+    pub force: bool,
+
+    /// Only fetch these categories, by config key or name, e.g. `fruits_veg,beverages`
+    #[arg(long, visible_alias = "limit-categories")]
+    pub categories: Option<String>,
+
+    /// Stop each store after fetching this many products across its categories
+    #[arg(long, value_name = "N")]
+    pub limit: Option<usize>,
+}
+
+/// Sinks processed data is written to besides storage
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SinkKind {
+    /// The database in `<config-dir>/database.toml`
+    Db,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ProcessingOptions {
+    /// Abort a source when more than this percentage of its products fail extraction
+    #[arg(long, value_name = "PCT", value_parser = parse_percentage)]
+    pub fail_on_errors: Option<f64>,
+
+    /// Warn when more than this percentage of raw products is missing from the clean output
+    #[arg(long, value_name = "PCT", value_parser = parse_percentage, default_value_t = DEFAULT_MAX_DROP_PCT)]
+    pub max_drop: f64,
+
+    /// Fail sources exceeding --max-drop instead of warning
+    #[arg(long)]
+    pub strict: bool,
+
+    /// How repeated product_ids within a source are collapsed after normalization
+    #[arg(long, value_parser = |s: &str| s.parse::<DedupStrategy>())]
+    pub dedup: Option<DedupStrategy>,
+
+    /// Formats clean data is stored in besides Parquet, overriding output.toml, e.g. `arrow,csv`
+    #[arg(long, value_delimiter = ',', value_parser = |s: &str| s.parse::<OutputFormat>())]
+    pub output_format: Option<Vec<OutputFormat>>,
+
+    /// Also upsert each source's processed products into a database
+    #[arg(long, value_enum)]
+    pub sink: Option<SinkKind>,
+
+    /// Keep each product's source JSON in a `_raw` column
+    #[arg(long)]
+    pub keep_raw_json: bool,
+
+    /// Write the `_raw` column to the stored Parquet (implies --keep-raw-json)
+    #[arg(long)]
+    pub include_raw_in_parquet: bool,
+
+    /// Store the full JSON of products that failed extraction as `rejected/<source>/<date>.json`
+    #[arg(long)]
+    pub store_rejected: bool,
+
+    /// Trained column classifier consulted when the heuristics leave a column unmapped
+    #[arg(long, value_name = "PATH")]
+    pub column_model: Option<String>,
+
+    /// Print how each source's columns would be classified, then stop before storing anything
+    #[arg(long)]
+    pub explain_classification: bool,
+
+    /// Leave products the source reports as out of stock or disabled out of the clean output
+    #[arg(long)]
+    pub drop_out_of_stock: bool,
+
+    /// Do not write the merged dataset
+    #[arg(long)]
+    pub skip_merge: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct JobsArgs {
+    /// Sources fetched and processed at the same time
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+    pub jobs: u16,
+}
+
+impl Default for JobsArgs {
+    fn default() -> Self {
+        JobsArgs { jobs: 1 }
+    }
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct RetryArgs {
+    /// Do not run the sources that failed a second time at the end of the run
+    #[arg(long)]
+    pub no_retry: bool,
+
+    /// Seconds to wait before retrying the sources that failed
+    #[arg(long, value_name = "SECS", default_value_t = DEFAULT_RETRY_DELAY_SECS, conflicts_with = "no_retry")]
+    pub retry_delay: u64,
+}
+
+impl RetryArgs {
+    /// How long to wait before the retry, `None` with `--no-retry`
+    pub fn delay(&self) -> Option<Duration> {
+        (!self.no_retry).then(|| Duration::from_secs(self.retry_delay))
+    }
+}
+
+impl Default for RetryArgs {
+    fn default() -> Self {
+        RetryArgs { no_retry: false, retry_delay: DEFAULT_RETRY_DELAY_SECS }
+    }
+}
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct MetricsArgs {
+    /// Serve Prometheus metrics on `http://<ADDR>/metrics` while the run lasts, e.g. 0.0.0.0:9184
+    #[arg(long, value_name = "ADDR")]
+    pub metrics_addr: Option<SocketAddr>,
+
+    /// Push the run's metrics to this Prometheus Pushgateway when it ends, e.g. http://pushgateway:9091
+    #[arg(long, value_name = "URL")]
+    pub push_gateway: Option<String>,
+}
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct DryRunArgs {
+    /// Fetch and process as usual but store nothing, logging what would have been stored
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct SnapshotArgs {
+    /// Process the raw snapshot fetched on this day (YYYY-MM-DD) instead of the latest one
+    #[arg(long, value_parser = parse_date)]
+    pub date: Option<NaiveDate>,
+
+    /// Process one raw object, e.g. `raw/krave_mart_1242164/20250915-101500.json`
+    #[arg(long, conflicts_with = "date")]
+    pub key: Option<String>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct FetchArgs {
+    #[command(flatten)]
+    pub sources: SourceArgs,
+
+    #[command(flatten)]
+    pub fetch: FetchOptions,
+
+    #[command(flatten)]
+    pub jobs: JobsArgs,
+
+    #[command(flatten)]
+    pub retry: RetryArgs,
+
+    #[command(flatten)]
+    pub dry_run: DryRunArgs,
+
+    #[command(flatten)]
+    pub metrics: MetricsArgs,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ProcessArgs {
+    #[command(flatten)]
+    pub sources: SourceArgs,
+
+    #[command(flatten)]
+    pub snapshot: SnapshotArgs,
+
+    #[command(flatten)]
+    pub processing: ProcessingOptions,
+
+    #[command(flatten)]
+    pub jobs: JobsArgs,
+
+    #[command(flatten)]
+    pub retry: RetryArgs,
+
+    #[command(flatten)]
+    pub dry_run: DryRunArgs,
+
+    #[command(flatten)]
+    pub metrics: MetricsArgs,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct RunArgs {
+    #[command(flatten)]
+    pub sources: SourceArgs,
+
+    #[command(flatten)]
+    pub fetch: FetchOptions,
+
+    #[command(flatten)]
+    pub processing: ProcessingOptions,
+
+    #[command(flatten)]
+    pub jobs: JobsArgs,
+
+    #[command(flatten)]
+    pub retry: RetryArgs,
+
+    #[command(flatten)]
+    pub dry_run: DryRunArgs,
+
+    #[command(flatten)]
+    pub metrics: MetricsArgs,
+
+    /// Keep comparing against the last snapshot that passed the anomaly checks
+    #[arg(long)]
+    pub keep_baseline_on_anomaly: bool,
+
+    /// Fetch and process without storage, printing the merged result
+    #[arg(long)]
+    pub stdout: bool,
+
+    /// Fetch and process without storage, writing the merged result to this CSV file
+    #[arg(long, value_name = "PATH")]
+    pub csv: Option<String>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct DiffArgs {
+    #[command(flatten)]
+    pub sources: SourceArgs,
+
+    /// Compare the snapshots of two days (YYYY-MM-DD); give it twice or not at all
+    #[arg(long = "date", value_parser = parse_date)]
+    pub dates: Vec<NaiveDate>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ReprocessArgs {
+    #[command(flatten)]
+    pub sources: SourceArgs,
+
+    /// Also re-run column classification
+    #[arg(long)]
+    pub reclassify: bool,
+
+    #[command(flatten)]
+    pub processing: ProcessingOptions,
+
+    #[command(flatten)]
+    pub dry_run: DryRunArgs,
+
+    #[command(flatten)]
+    pub metrics: MetricsArgs,
+}
+
+#[derive(Args, Debug, Clone)]
+#[command(group(ArgGroup::new("product").required(true).args(["product_id", "name"])))]
+pub struct HistoryArgs {
+    #[command(flatten)]
+    pub sources: SourceArgs,
+
+    /// Follow the product with this ID
+    #[arg(long)]
+    pub product_id: Option<String>,
+
+    /// Follow products whose name contains this text, ignoring case
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// Only the snapshots of the last this many days
+    #[arg(long)]
+    pub days: Option<u64>,
+
+    /// Write the history to a .csv or .parquet file instead of printing it
+    #[arg(long, value_name = "PATH")]
+    pub output: Option<String>,
+}
+
+#[derive(Args, Debug, Clone)]
+#[command(group(ArgGroup::new("target").required(true).args(["date", "prefix"])))]
+pub struct CleanupArgs {
+    #[command(flatten)]
+    pub sources: SourceArgs,
+
+    /// Delete the raw dumps fetched on this day (YYYY-MM-DD)
+    #[arg(long, value_parser = parse_date)]
+    pub date: Option<NaiveDate>,
+
+    /// Delete every object whose key starts with this prefix
+    #[arg(long, conflicts_with = "source")]
+    pub prefix: Option<String>,
+
+    /// List what would be deleted without deleting it
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ServeArgs {
+    #[command(flatten)]
+    pub config: ConfigArgs,
+
+    /// Keep the state of every scheduled source in this JSON file
+    #[arg(long, value_name = "PATH")]
+    pub status_file: Option<String>,
+
+    #[command(flatten)]
+    pub fetch: FetchOptions,
+
+    #[command(flatten)]
+    pub processing: ProcessingOptions,
+
+    #[command(flatten)]
+    pub dry_run: DryRunArgs,
+
+    /// Serve Prometheus metrics of every run on `http://<ADDR>/metrics`, e.g. 0.0.0.0:9184
+    #[arg(long, value_name = "ADDR")]
+    pub metrics_addr: Option<SocketAddr>,
+}
+
+impl ServeArgs {
+    /// The `run` of `source` at one of its scheduled times. The run itself
+    /// only holds `source`, so the merged dataset is rebuilt from the latest
+    /// clean snapshot of every source instead of from the run's frames.
+    pub fn run_of(&self, source: &str) -> PipelineArgs {
+        let sources = SourceArgs {
+            source: Some(source.to_string()),
+            config: self.config.clone(),
+        };
+        PipelineArgs {
+            fetch: self.fetch.clone(),
+            processing: ProcessingOptions {
+                skip_merge: true,
+                ..self.processing.clone()
+            },
+            merge_latest: !self.processing.skip_merge,
+            dry_run: self.dry_run.dry_run,
+            ..PipelineArgs::new(PipelineMode::Run, sources)
+        }
+    }
+}
+
+impl Cli {
+    /// Parse the command line, with `--help` listing the sources of the
+    /// default config dir
+    pub fn parse_with_sources() -> Self {
+        let command = Cli::command().after_help(sources_help(DEFAULT_CONFIG_DIR));
+        Cli::from_arg_matches(&command.get_matches()).unwrap_or_else(|e| e.exit())
+    }
+
+    /// The subcommand to run. Invocations without one keep working for now:
+    /// `--from-storage` (or `-s`) means `process`, and anything else `run`.
+    pub fn into_command(self) -> Result<Command> {
+        if let Some(command) = self.command {
+            return Ok(command);
+        }
+        if !self.from_storage {
+            if self.snapshot.date.is_some() || self.snapshot.key.is_some() {
+                warn!("--date and --key only apply with `process`, ignoring them");
+            }
+            return Ok(Command::Run(self.run));
+        }
+
+        warn!("--from-storage is deprecated and will be removed, use `data-pipeline process`");
+        let run = self.run;
+        if run.stdout || run.csv.is_some() {
+            return Err(anyhow::anyhow!("--stdout and --csv fetch from the APIs and cannot be combined with --from-storage"));
+        }
+        Ok(Command::Process(ProcessArgs {
+            sources: run.sources,
+            snapshot: self.snapshot,
+            processing: run.processing,
+            jobs: run.jobs,
+            retry: run.retry,
+            dry_run: run.dry_run,
+            metrics: run.metrics,
+        }))
+    }
+}
+
+impl Default for ProcessingOptions {
+    fn default() -> Self {
+        ProcessingOptions {
+            fail_on_errors: None,
+            max_drop: DEFAULT_MAX_DROP_PCT,
+            strict: false,
+            dedup: None,
+            output_format: None,
+            sink: None,
+            keep_raw_json: false,
+            include_raw_in_parquet: false,
+            store_rejected: false,
+            column_model: None,
+            explain_classification: false,
+            drop_out_of_stock: false,
+            skip_merge: false,
+        }
+    }
+}
+
+/// Which half of the pipeline `fetch`, `process`, `run` and `reprocess` run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PipelineMode {
+    Fetch,
+    Process,
+    Run,
+    Reprocess,
+}
+
+impl PipelineMode {
+    /// The `stage` label of the mode's metrics
+    pub fn stage(self) -> &'static str {
+        match self {
+            PipelineMode::Fetch => "fetch",
+            PipelineMode::Process => "process",
+            PipelineMode::Run => "run",
+            PipelineMode::Reprocess => "reprocess",
+        }
+    }
+}
+
+/// The arguments of the pipeline subcommands in one place, with the ones a
+/// subcommand does not take left at their defaults
+#[derive(Debug, Clone)]
+pub struct PipelineArgs {
+    pub mode: PipelineMode,
+    pub sources: SourceArgs,
+    pub fetch: FetchOptions,
+    pub processing: ProcessingOptions,
+    pub snapshot: SnapshotArgs,
+    /// Sources run at the same time; `reprocess` runs them one by one
+    pub jobs: usize,
+    /// Wait before running failed sources again, `None` to not retry them
+    pub retry: Option<Duration>,
+    /// Store nothing, see `MinioStorage::into_dry_run`
+    pub dry_run: bool,
+    pub metrics: MetricsArgs,
+    /// Merge the latest clean snapshot of every source once the run is done,
+    /// see `ServeArgs::run_of`
+    pub merge_latest: bool,
+    pub keep_baseline_on_anomaly: bool,
+    pub stdout: bool,
+    pub csv: Option<String>,
+    pub reclassify: bool,
+}
+
+impl PipelineArgs {
+    fn new(mode: PipelineMode, sources: SourceArgs) -> Self {
+        PipelineArgs {
+            mode,
+            sources,
+            fetch: FetchOptions::default(),
+            processing: ProcessingOptions::default(),
+            snapshot: SnapshotArgs::default(),
+            jobs: usize::from(JobsArgs::default().jobs),
+            retry: RetryArgs::default().delay(),
+            dry_run: false,
+            metrics: MetricsArgs::default(),
+            merge_latest: false,
+            keep_baseline_on_anomaly: false,
+            stdout: false,
+            csv: None,
+            reclassify: false,
+        }
+    }
+}
+
+impl From<FetchArgs> for PipelineArgs {
+    fn from(args: FetchArgs) -> Self {
+        PipelineArgs {
+            fetch: args.fetch,
+            jobs: usize::from(args.jobs.jobs),
+            retry: args.retry.delay(),
+            dry_run: args.dry_run.dry_run,
+            metrics: args.metrics,
+            ..PipelineArgs::new(PipelineMode::Fetch, args.sources)
+        }
+    }
+}
+
+impl From<ProcessArgs> for PipelineArgs {
+    fn from(args: ProcessArgs) -> Self {
+        PipelineArgs {
+            snapshot: args.snapshot,
+            processing: args.processing,
+            jobs: usize::from(args.jobs.jobs),
+            retry: args.retry.delay(),
+            dry_run: args.dry_run.dry_run,
+            metrics: args.metrics,
+            ..PipelineArgs::new(PipelineMode::Process, args.sources)
+        }
+    }
+}
+
+impl From<RunArgs> for PipelineArgs {
+    fn from(args: RunArgs) -> Self {
+        PipelineArgs {
+            fetch: args.fetch,
+            processing: args.processing,
+            jobs: usize::from(args.jobs.jobs),
+            retry: args.retry.delay(),
+            dry_run: args.dry_run.dry_run,
+            metrics: args.metrics,
+            keep_baseline_on_anomaly: args.keep_baseline_on_anomaly,
+            stdout: args.stdout,
+            csv: args.csv,
+            ..PipelineArgs::new(PipelineMode::Run, args.sources)
+        }
+    }
+}
+
+impl From<ReprocessArgs> for PipelineArgs {
+    fn from(args: ReprocessArgs) -> Self {
+        PipelineArgs {
+            processing: args.processing,
+            reclassify: args.reclassify,
+            dry_run: args.dry_run.dry_run,
+            metrics: args.metrics,
+            ..PipelineArgs::new(PipelineMode::Reprocess, args.sources)
+        }
+    }
+}
+
+/// The `--help` epilogue: the sources of `config_dir`, or where they are
+/// listed when the file cannot be read
+fn sources_help(config_dir: &str) -> String {
+    let path = format!("{}/{}", config_dir, SOURCES_FILE);
+    let sources = match SourcesConfig::from_file(&path) {
+        Ok(config) => {
+            let lines: Vec<String> = config
+                .sources
+                .iter()
+                .map(|source| {
+                    let kind = match source.source_type {
+                        SourceType::Json => "JSON API",
+                        SourceType::Html => "HTML pages",
+                    };
+                    let disabled = if source.enabled { "" } else { ", disabled" };
+                    format!("  {:<12} {}{}", source.name, kind, disabled)
+                })
+                .collect();
+            format!("Sources (from {}):\n{}", path, lines.join("\n"))
+        }
+        Err(_) => format!("Sources are listed in <config-dir>/{}", SOURCES_FILE),
+    };
+    format!("{}\n\nWithout a subcommand the pipeline runs end to end, as `run` does.", sources)
+}
+
+/// A percentage such as `5` or `2.5%`, between 0 and 100
+fn parse_percentage(value: &str) -> Result<f64, String> {
+    let pct: f64 = value
+        .trim()
+        .trim_end_matches('%')
+        .parse()
+        .map_err(|_| format!("expected a percentage, got '{}'", value))?;
+    if !(0.0..=100.0).contains(&pct) {
+        return Err(format!("must be between 0 and 100, got {}", pct));
+    }
+    Ok(pct)
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| format!("expected a YYYY-MM-DD date, got '{}'", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    fn parse(args: &[&str]) -> Result<Command> {
+        let cli = Cli::try_parse_from(std::iter::once("data-pipeline").chain(args.iter().copied()))?;
+        cli.into_command()
+    }
+
+    #[test]
+    fn test_subcommands() {
+        let Command::Process(process) = parse(&["process", "--source", "naheed", "--date", "2025-09-15", "--jobs", "4"]).unwrap() else {
+            panic!("expected process");
+        };
+        assert_eq!(process.sources.source.as_deref(), Some("naheed"));
+        assert_eq!(process.snapshot.date, NaiveDate::from_ymd_opt(2025, 9, 15));
+        assert_eq!(process.jobs.jobs, 4);
+        assert_eq!(process.processing.max_drop, DEFAULT_MAX_DROP_PCT);
+
+        let Command::Run(run) = parse(&["run", "--output-format", "arrow,csv", "--max-drop", "2.5%"]).unwrap() else {
+            panic!("expected run");
+        };
+        assert_eq!(run.processing.output_format, Some(vec![OutputFormat::Arrow, OutputFormat::Csv]));
+        assert_eq!(run.processing.max_drop, 2.5);
+        assert_eq!(run.jobs.jobs, 1);
+        assert_eq!(PipelineArgs::from(run).retry, Some(Duration::from_secs(DEFAULT_RETRY_DELAY_SECS)));
+        let Command::Fetch(fetch) = parse(&["fetch", "--no-retry", "--metrics-addr", "0.0.0.0:9184", "--push-gateway", "http://pushgateway:9091"]).unwrap() else {
+            panic!("expected fetch");
+        };
+        let fetch = PipelineArgs::from(fetch);
+        assert_eq!(fetch.retry, None);
+        assert_eq!(fetch.metrics.metrics_addr, Some("0.0.0.0:9184".parse().unwrap()));
+        assert_eq!(fetch.metrics.push_gateway.as_deref(), Some("http://pushgateway:9091"));
+
+        let Command::Diff(diff) = parse(&["diff", "--date", "2025-09-14", "--date", "2025-09-15"]).unwrap() else {
+            panic!("expected diff");
+        };
+        assert_eq!(diff.dates.len(), 2);
+        let Command::CheckStorage(config) = parse(&["check-storage", "--config-dir", "/etc/pipeline"]).unwrap() else {
+            panic!("expected check-storage");
+        };
+        assert_eq!(config.config_dir, "/etc/pipeline");
+        assert!(matches!(parse(&["validate-config"]).unwrap(), Command::ValidateConfig(_)));
+
+        let Command::Serve(serve) = parse(&["serve", "--status-file", "/tmp/status.json", "--dry-run"]).unwrap() else {
+            panic!("expected serve");
+        };
+        assert_eq!(serve.metrics_addr, None);
+        let run = serve.run_of("naheed");
+        assert_eq!((run.mode, run.sources.source.as_deref(), run.dry_run), (PipelineMode::Run, Some("naheed"), true));
+        assert!(run.processing.skip_merge && run.merge_latest);
+        let Command::Serve(serve) = parse(&["serve", "--skip-merge"]).unwrap() else {
+            panic!("expected serve");
+        };
+        assert!(!serve.run_of("naheed").merge_latest);
+    }
+
+    #[test]
+    fn test_invalid_arguments_error() {
+        assert!(parse(&["run", "--no-such-flag"]).is_err());
+        assert!(parse(&["--no-such-flag"]).is_err());
+        assert!(parse(&["run", "--max-drop", "150"]).is_err());
+        assert!(parse(&["run", "--jobs", "0"]).is_err());
+        assert!(parse(&["run", "--no-retry", "--retry-delay", "5"]).is_err());
+        assert!(parse(&["run", "--metrics-addr", "localhost"]).is_err());
+        assert!(parse(&["serve", "--push-gateway", "http://pushgateway:9091"]).is_err());
+        assert!(parse(&["process", "--date", "15/09/2025"]).is_err());
+        assert!(parse(&["process", "--date", "2025-09-15", "--key", "raw/naheed/x.json"]).is_err());
+        assert!(parse(&["history"]).is_err());
+        assert!(parse(&["cleanup", "--source", "naheed"]).is_err());
+        assert!(parse(&["fetch", "--date", "2025-09-15"]).is_err());
+    }
+
+    #[test]
+    fn test_legacy_invocations() {
+        let Command::Run(run) = parse(&["--source", "dealcart", "--force"]).unwrap() else {
+            panic!("expected run");
+        };
+        assert_eq!(run.sources.source.as_deref(), Some("dealcart"));
+        assert!(run.fetch.force);
+
+        for flag in ["-s", "--from-storage"] {
+            let Command::Process(process) = parse(&[flag, "--source", "naheed", "--date", "2025-09-15"]).unwrap() else {
+                panic!("expected process for {}", flag);
+            };
+            assert_eq!(process.sources.source.as_deref(), Some("naheed"));
+            assert!(process.snapshot.date.is_some());
+        }
+        assert!(parse(&["-s", "--stdout"]).is_err());
+        // Top-level flags do not mix with a subcommand
+        assert!(parse(&["--source", "naheed", "fetch"]).is_err());
+    }
+
+    #[test]
+    fn test_log_flags() {
+        let parse_logging = |args: &[&str]| Cli::try_parse_from(std::iter::once("data-pipeline").chain(args.iter().copied())).map(|cli| cli.logging);
+        let logging = parse_logging(&["fetch", "--log-format", "json", "--log-dir", "logs"]).unwrap();
+        assert_eq!((logging.log_format, logging.log_dir.as_deref()), (LogFormat::Json, Some("logs")));
+        assert_eq!(parse_logging(&["serve"]).unwrap().log_format, LogFormat::Pretty);
+        assert_eq!(parse_logging(&["--log-format", "json"]).unwrap().log_format, LogFormat::Json);
+        assert!(parse_logging(&["run", "--log-format", "xml"]).is_err());
+    }
+
+    #[test]
+    fn test_help_lists_every_source() {
+        let help = sources_help(DEFAULT_CONFIG_DIR);
+        let sources = SourcesConfig::from_file(&format!("{}/{}", DEFAULT_CONFIG_DIR, SOURCES_FILE)).unwrap();
+        for name in sources.names() {
+            assert!(help.contains(name), "{} missing from --help", name);
+        }
+        assert!(help.contains("naheed       HTML pages"));
+        assert!(sources_help("/nonexistent").contains("<config-dir>/sources.toml"));
+        Cli::command().debug_assert();
+    }
+}
@@ -0,0 +1,79 @@
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+use crate::processor::anomaly_detector::{
+    AnomalyDetector, DEFAULT_MAX_MEDIAN_PRICE_CHANGE_PCT, DEFAULT_MAX_NULL_RATE_INCREASE,
+    DEFAULT_MAX_PRODUCT_COUNT_CHANGE_PCT,
+};
+
+/// How far a source's snapshot may drift from the previous one before the
+/// source is reported as suspect
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnomalyConfig {
+    /// Product count change, in percent of the previous snapshot
+    pub max_product_count_change_pct: f64,
+    /// Median cost_price change, in percent of the previous snapshot
+    pub max_median_price_change_pct: f64,
+    /// Rise of any column's null rate, in percentage points
+    pub max_null_rate_increase: f64,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        AnomalyConfig {
+            max_product_count_change_pct: DEFAULT_MAX_PRODUCT_COUNT_CHANGE_PCT,
+            max_median_price_change_pct: DEFAULT_MAX_MEDIAN_PRICE_CHANGE_PCT,
+            max_null_rate_increase: DEFAULT_MAX_NULL_RATE_INCREASE,
+        }
+    }
+}
+
+impl AnomalyConfig {
+    pub fn from_file(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read anomaly config file: {}", path))?;
+        let config: AnomalyConfig = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse anomaly config file: {}", path))?;
+        config
+            .validate()
+            .with_context(|| format!("Invalid anomaly config in {}", path))?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let thresholds = [
+            ("max_product_count_change_pct", self.max_product_count_change_pct),
+            ("max_median_price_change_pct", self.max_median_price_change_pct),
+            ("max_null_rate_increase", self.max_null_rate_increase),
+        ];
+        for (name, value) in thresholds {
+            if !(value >= 0.0 && value.is_finite()) {
+                return Err(anyhow!("{} must be a non-negative number, got {}", name, value));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn detector(&self) -> AnomalyDetector {
+        AnomalyDetector::new()
+            .with_max_product_count_change(self.max_product_count_change_pct)
+            .with_max_median_price_change(self.max_median_price_change_pct)
+            .with_max_null_rate_increase(self.max_null_rate_increase)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_anomaly_config() {
+        let config = AnomalyConfig::from_file("src/configs/anomaly.toml").unwrap();
+        assert!(config.validate().is_ok());
+
+        let negative: AnomalyConfig = toml::from_str("max_null_rate_increase = -5\n").unwrap();
+        assert!(negative.validate().is_err());
+        assert_eq!(toml::from_str::<AnomalyConfig>("").unwrap(), AnomalyConfig::default());
+    }
+}
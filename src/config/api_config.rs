@@ -1,5 +1,13 @@
+use anyhow::{Context, anyhow};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use serde_json::Value;
+
+use crate::config::CategoryFilter;
+use crate::fetcher::client::DEFAULT_MAX_RESPONSE_BYTES;
+use crate::fetcher::extraction::check_data_path;
+use crate::processor::json_flattener::{FieldExtractionRules, FieldPath, NumberFormat};
+use crate::processor::rule_normalizer::ColumnTransform;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApiConfig {
@@ -9,26 +17,151 @@ pub struct ApiConfig {
     pub pagination: PaginationConfig,
     pub fields: FieldConfig,
     pub categories: HashMap<String, CategoryConfig>,
+    /// `[[transforms]]` applied to the source's columns after normalizing
+    #[serde(default)]
+    pub transforms: Vec<ColumnTransform>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApiSection {
     pub name: String,
     pub base_url: String,
+    #[serde(default)]
     pub auth_token: String,
+    /// Environment variable to load `auth_token` from when the config is
+    /// read, taking precedence over a literal `auth_token`
+    #[serde(default)]
+    pub auth_token_env: Option<String>,
+    /// Store / warehouse IDs to fetch, each stored separately. Empty means the
+    /// single default store baked into the category URLs.
+    #[serde(default)]
+    pub stores: Vec<String>,
+    /// Browser fingerprint to emulate, e.g. "chrome120" (default firefox136)
+    #[serde(default)]
+    pub emulation: Option<String>,
+    /// Explicit User-Agent header overriding the emulated browser's
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    /// Currency of products whose prices do not name one, e.g. "PKR" or "AED"
+    #[serde(default)]
+    pub currency: Option<String>,
+    /// Separators prices are written with: `us` ("1,234.50", default) or
+    /// `eu` ("1.234,50")
+    #[serde(default)]
+    pub number_format: NumberFormat,
+    /// Largest response body read before the request fails (default 256 MiB)
+    #[serde(default = "default_max_response_bytes")]
+    pub max_response_bytes: u64,
+    /// `include_categories` / `exclude_categories` lists
+    #[serde(flatten)]
+    pub category_filter: CategoryFilter,
+}
+
+fn default_max_response_bytes() -> u64 {
+    DEFAULT_MAX_RESPONSE_BYTES
 }
 
+/// KraveMart store used when no `stores` are configured
+const DEFAULT_KRAVEMART_STORE: &str = "1242164";
+/// Dealcart warehouse used when no `stores` are configured
+const DEFAULT_DEALCART_WAREHOUSE: &str = "1";
+/// `productChannel` sent in POST bodies of sources opting into defaults
+const DEFAULT_PRODUCT_CHANNEL: &str = "WEB_APP";
+/// Page size sent in POST bodies of sources opting into defaults
+const DEFAULT_PAGE_SIZE: i32 = 20;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PaginationConfig {
     pub r#type: String,
     pub page_param: Option<String>,
     pub limit_param: Option<String>,
     pub default_limit: Option<u32>,
+    /// Dotted path to the total product count in the first page's body,
+    /// e.g. "count"; the number of pages follows from the page size
+    #[serde(default)]
+    pub total_path: Option<String>,
+    /// Dotted path to the number of pages in the first page's body, e.g.
+    /// "meta.total_pages"; takes precedence over `total_path`
+    #[serde(default)]
+    pub total_pages_path: Option<String>,
+}
+
+impl PaginationConfig {
+    /// Pages to fetch according to the first page's body. Pages are as large
+    /// as `default_limit`, or as the first page when that is smaller, since
+    /// servers may cap the page size below the limit asked for. `None` when
+    /// neither path is configured or found, in which case pages are fetched
+    /// until they come back empty.
+    pub fn total_pages(&self, first_page: &Value, first_page_len: usize) -> Option<u32> {
+        if let Some(pages) = self.total_pages_path.as_deref().and_then(|path| count_at(first_page, path)) {
+            return Some(pages);
+        }
+
+        let total = self.total_path.as_deref().and_then(|path| count_at(first_page, path))?;
+        let first_page_len = first_page_len as u32;
+        let page_size = match self.default_limit {
+            Some(limit) if first_page_len > 0 => limit.min(first_page_len),
+            Some(limit) => limit,
+            None => first_page_len,
+        };
+        if page_size == 0 {
+            return (total == 0).then_some(1);
+        }
+        Some(total.div_ceil(page_size).max(1))
+    }
+
+    /// Query string of a `page` request: `page_param` (`page` by default) and,
+    /// when both are configured, `limit_param` set to `default_limit`
+    pub fn page_query(&self, page: u32) -> String {
+        let mut query = format!("{}={}", self.page_param.as_deref().unwrap_or("page"), page);
+        if let (Some(limit_param), Some(limit)) = (&self.limit_param, self.default_limit) {
+            query.push_str(&format!("&{}={}", limit_param, limit));
+        }
+        query
+    }
+}
+
+/// `pagination.type` values: `none` fetches each category URL once, `page`
+/// follows `?page=N` and `post_body` sends the page in the POST body
+const PAGINATION_TYPES: [&str; 3] = ["none", "page", "post_body"];
+
+/// A non-negative count at a dotted path, given as a number or a numeric string
+fn count_at(body: &Value, path: &str) -> Option<u32> {
+    let value = path.split('.').try_fold(body, |value, key| value.get(key))?;
+    match value {
+        Value::Number(number) => number.as_u64().and_then(|count| u32::try_from(count).ok()),
+        Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FieldConfig {
     pub target_fields: Vec<String>,
+    /// Canonical field -> ordered JSON paths, see `FieldPath` for the syntax
+    #[serde(default)]
+    pub extraction: HashMap<String, Vec<String>>,
+    /// Canonical field -> value used when none of its paths match
+    #[serde(default)]
+    pub defaults: HashMap<String, String>,
+    /// Path to a product's variants array, e.g. "variants"; each variant
+    /// becomes its own row
+    #[serde(default)]
+    pub variants_path: Option<String>,
+    /// JSON Schema each raw product is checked against before flattening
+    #[serde(default)]
+    pub schema_path: Option<String>,
+    /// Set to false to skip schema validation without removing `schema_path`
+    #[serde(default = "default_validate_schema")]
+    pub validate_schema: bool,
+    /// Paths to a product's stock or availability fields, e.g.
+    /// `["is_enabled", "availableStock"]`, read into an `in_stock` column
+    #[serde(default)]
+    pub in_stock_fields: Vec<String>,
+}
+
+fn default_validate_schema() -> bool {
+    true
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,44 +177,125 @@ pub struct RequestConfig {
     pub method: String, // "GET" or "POST"
     pub endpoint: Option<String>, // For POST requests
     pub authorization: Option<String>, // Bearer token, etc.
+    /// Whether the credential is sent as a header (the default) or a query parameter
+    #[serde(default)]
+    pub auth_location: AuthLocation,
+    /// Header or query parameter carrying the credential, e.g. "X-API-Key";
+    /// "Authorization" or "api_key" when unset
+    #[serde(default)]
+    pub auth_name: Option<String>,
+    /// Environment variable holding the credential instead of `authorization`,
+    /// so secrets stay out of the committed config
+    #[serde(default)]
+    pub auth_token_env: Option<String>,
     pub headers: HashMap<String, String>, // Additional headers
     pub product_channel: Option<String>, // For POST requests
     pub category_field: Option<String>, // Field name for category in POST body
     pub page_size: Option<i32>, // Items per page
     pub graphql_query: Option<String>, // GraphQL query for GraphQL APIs
     pub graphql_variables: Option<HashMap<String, serde_json::Value>>, // GraphQL variables
+    /// POST body sources must set `product_channel` and `page_size` unless
+    /// this opts into "WEB_APP" and 20
+    #[serde(default)]
+    pub use_post_defaults: bool,
+}
+
+/// Where a request carries its credential
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthLocation {
+    #[default]
+    Header,
+    Query,
+}
+
+/// The credential sent with every request of a source, see `ApiConfig::auth`
+#[derive(Clone, PartialEq, Eq)]
+pub struct ApiAuth {
+    pub location: AuthLocation,
+    /// Header or query parameter name
+    pub name: String,
+    pub value: String,
+}
+
+// Keeps the secret out of logs and error messages
+impl std::fmt::Debug for ApiAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApiAuth")
+            .field("location", &self.location)
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResponseConfig {
-    pub data_path: Option<String>, // Path to extract products, e.g., "data[].l2_products[]"
+    /// Path to extract products, e.g., "data[].l2_products[]", or a list of
+    /// paths whose products are concatenated. A `*` segment goes through
+    /// every value of an object, e.g. "data.*.products[]" for products keyed
+    /// by category ID.
+    pub data_path: Option<DataPath>,
+}
+
+/// `response.data_path`: a single path or several
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum DataPath {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl DataPath {
+    pub fn paths(&self) -> &[String] {
+        match self {
+            DataPath::One(path) => std::slice::from_ref(path),
+            DataPath::Many(paths) => paths,
+        }
+    }
 }
 
 impl ApiConfig {
     pub fn from_file(path: &str) -> Result<Self, anyhow::Error> {
         let content = std::fs::read_to_string(path)?;
-        let config: ApiConfig = toml::from_str(&content)?;
+        let mut config: ApiConfig = toml::from_str(&content)?;
+        config.load_auth_token(|var| std::env::var(var).ok());
         Ok(config)
     }
 
+    /// Replace `auth_token` with the value `env` has for `auth_token_env`,
+    /// if it has one
+    fn load_auth_token(&mut self, env: impl Fn(&str) -> Option<String>) {
+        if let Some(token) = self.api.auth_token_env.as_deref().and_then(env) {
+            self.api.auth_token = token;
+        }
+    }
+
     pub fn build_category_urls(&self) -> Vec<(String, String)> {
+        self.build_category_urls_for_store(None)
+    }
+
+    /// Category URLs for one store / warehouse, or the default one if `None`
+    pub fn build_category_urls_for_store(&self, store: Option<&str>) -> Vec<(String, String)> {
         let mut urls = Vec::new();
 
-        for (key, category) in &self.categories {
+        for (key, category) in self.selected_categories() {
             if let Some(ref category_ids) = category.category_ids {
                 // KraveMart pattern: multiple category IDs
                 let url = format!(
-                    "{}/api/v2/es/categories/{}/products/1242164",
-                    self.api.base_url, category_ids
+                    "{}/api/v2/es/categories/{}/products/{}",
+                    self.api.base_url,
+                    category_ids,
+                    store.unwrap_or(DEFAULT_KRAVEMART_STORE)
                 );
                 urls.push((key.clone(), url));
             } else if let Some(ref category_id) = category.category_id {
                 // Dealcart pattern: single category ID with endpoint and query params
                 if let Some(ref endpoint) = self.request.endpoint {
                     let url = format!(
-                        "{}{}?warehouse_id=1&limit={}&category_id={}",
+                        "{}{}?warehouse_id={}&limit={}&category_id={}",
                         self.api.base_url,
                         endpoint,
+                        store.unwrap_or(DEFAULT_DEALCART_WAREHOUSE),
                         self.pagination.default_limit.unwrap_or(2000),
                         category_id
                     );
@@ -94,14 +308,222 @@ impl ApiConfig {
     }
 
     pub fn get_category_slugs(&self) -> Vec<(String, String)> {
-        self.categories
-            .iter()
+        self.selected_categories()
+            .into_iter()
             .filter_map(|(key, category)| {
                 category.core_category_slug.as_ref().map(|slug| (key.clone(), slug.clone()))
             })
             .collect()
     }
 
+    /// Categories passing the include / exclude lists
+    pub fn selected_categories(&self) -> Vec<(&String, &CategoryConfig)> {
+        self.categories
+            .iter()
+            .filter(|(key, category)| self.api.category_filter.allows(key, &category.name))
+            .collect()
+    }
+
+    /// Only fetch these categories (matched by key or name), e.g. from `--categories`
+    pub fn restrict_categories(&mut self, categories: &[String]) {
+        self.api.category_filter.restrict_to(categories);
+    }
+
+    /// Name raw and clean files are stored under for a store, e.g. `krave_mart_1242164`
+    pub fn storage_name_for_store(&self, store: Option<&str>) -> String {
+        match store {
+            Some(store) => format!("{}_{}", self.api.name, store),
+            None => self.api.name.clone(),
+        }
+    }
+
+    /// Storage names for every configured store (just the API name if there are none)
+    pub fn storage_names(&self) -> Vec<String> {
+        if self.api.stores.is_empty() {
+            vec![self.api.name.clone()]
+        } else {
+            self.api
+                .stores
+                .iter()
+                .map(|store| self.storage_name_for_store(Some(store)))
+                .collect()
+        }
+    }
+
+    /// Field extraction rules for `JsonFlattener`, if the config defines any
+    pub fn extraction_rules(&self) -> Result<FieldExtractionRules, anyhow::Error> {
+        FieldExtractionRules::from_config(&self.fields.extraction, &self.fields.defaults)
+    }
+
+    /// Parsed `[fields] variants_path`, if the source has variants
+    pub fn variants_path(&self) -> Result<Option<FieldPath>, anyhow::Error> {
+        self.fields
+            .variants_path
+            .as_deref()
+            .map(FieldPath::parse)
+            .transpose()
+    }
+
+    /// Parsed `[fields] in_stock_fields`, empty when the source reports no stock
+    pub fn in_stock_paths(&self) -> Result<Vec<FieldPath>, anyhow::Error> {
+        self.fields.in_stock_fields.iter().map(|path| FieldPath::parse(path)).collect()
+    }
+
+    /// Schema to validate raw products against, unless validation is disabled
+    pub fn schema_path(&self) -> Option<&str> {
+        self.fields
+            .schema_path
+            .as_deref()
+            .filter(|_| self.fields.validate_schema)
+    }
+
+    /// Check the config before anything is fetched: a known method, some
+    /// categories, and what each method needs to build its requests
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        self.validate_with_env(|var| std::env::var(var).ok())
+    }
+
+    /// `validate`, reading `request.auth_token_env` from `env` instead of
+    /// the process environment
+    pub fn validate_with_env(&self, env: impl Fn(&str) -> Option<String>) -> Result<(), anyhow::Error> {
+        let name = &self.api.name;
+        if self.categories.is_empty() {
+            return Err(anyhow!("{} has no categories configured", name));
+        }
+        if self.api.max_response_bytes == 0 {
+            return Err(anyhow!("{} api.max_response_bytes must be over 0", name));
+        }
+
+        match self.request.method.as_str() {
+            "GET" => {
+                for (key, category) in &self.categories {
+                    if category.category_ids.is_none() && category.category_id.is_none() {
+                        return Err(anyhow!("{} category '{}' needs category_ids or category_id", name, key));
+                    }
+                    if category.category_ids.is_none() && self.request.endpoint.is_none() {
+                        return Err(anyhow!(
+                            "{} category '{}' uses category_id, which needs request.endpoint",
+                            name,
+                            key
+                        ));
+                    }
+                }
+                if self.build_category_urls().is_empty() {
+                    return Err(anyhow!("{} yields no category URLs, check include_categories / exclude_categories", name));
+                }
+            }
+            "POST" if self.request.graphql_query.is_some() => {
+                if let Some((key, _)) = self.categories.iter().find(|(_, category)| category.category_id.is_none()) {
+                    return Err(anyhow!("{} GraphQL category '{}' needs category_id", name, key));
+                }
+            }
+            "POST" => {
+                if let Some((key, _)) = self
+                    .categories
+                    .iter()
+                    .find(|(_, category)| category.core_category_slug.is_none())
+                {
+                    return Err(anyhow!("{} POST category '{}' needs core_category_slug", name, key));
+                }
+                if self.request.endpoint.is_none() {
+                    return Err(anyhow!("{} POST request needs request.endpoint", name));
+                }
+                if !self.request.use_post_defaults {
+                    let missing: Vec<&str> = [
+                        ("product_channel", self.request.product_channel.is_none()),
+                        ("page_size", self.request.page_size.is_none()),
+                    ]
+                    .into_iter()
+                    .filter(|(_, missing)| *missing)
+                    .map(|(field, _)| field)
+                    .collect();
+                    if !missing.is_empty() {
+                        return Err(anyhow!(
+                            "{} POST request is missing {}; set them or use_post_defaults = true",
+                            name,
+                            missing.join(" and ")
+                        ));
+                    }
+                }
+                if self.post_page_size() <= 0 {
+                    return Err(anyhow!("{} page_size must be greater than 0", name));
+                }
+            }
+            other => return Err(anyhow!("{} has unknown request method '{}', expected GET or POST", name, other)),
+        }
+
+        if !PAGINATION_TYPES.contains(&self.pagination.r#type.as_str()) {
+            return Err(anyhow!(
+                "{} has unknown pagination type '{}', expected one of: {}",
+                name,
+                self.pagination.r#type,
+                PAGINATION_TYPES.join(", ")
+            ));
+        }
+        if let Some(data_path) = &self.response.data_path {
+            for path in data_path.paths() {
+                check_data_path(path).with_context(|| format!("{} has an invalid response.data_path", name))?;
+            }
+        }
+
+        if let Some(var) = &self.api.auth_token_env
+            && self.api.auth_token.is_empty()
+        {
+            return Err(anyhow!("{} needs an auth token, but ${} is not set and auth_token is empty", name, var));
+        }
+        if self.request.authorization.is_some() && self.request.auth_token_env.is_some() {
+            return Err(anyhow!("{} sets both authorization and auth_token_env, keep one", name));
+        }
+        if let Some(var) = &self.request.auth_token_env
+            && env(var).is_none()
+        {
+            return Err(anyhow!("{} reads its credential from ${}, which is not set", name, var));
+        }
+        if self.request.auth_name.as_deref().is_some_and(|auth_name| auth_name.trim().is_empty()) {
+            return Err(anyhow!("{} auth_name must not be empty", name));
+        }
+        Ok(())
+    }
+
+    /// The credential to send, from `auth_token_env` or `authorization`;
+    /// `None` when the source needs none. Errors when `auth_token_env` names
+    /// a variable that is not set.
+    pub fn auth(&self) -> Result<Option<ApiAuth>, anyhow::Error> {
+        self.auth_with_env(|var| std::env::var(var).ok())
+    }
+
+    /// `auth`, reading `auth_token_env` from `env` instead of the process
+    /// environment
+    pub fn auth_with_env(&self, env: impl Fn(&str) -> Option<String>) -> Result<Option<ApiAuth>, anyhow::Error> {
+        let request = &self.request;
+        let value = match &request.auth_token_env {
+            Some(var) => env(var)
+                .ok_or_else(|| anyhow!("{} reads its credential from ${}, which is not set", self.api.name, var))?,
+            None => match &request.authorization {
+                Some(authorization) => authorization.clone(),
+                None => return Ok(None),
+            },
+        };
+        let name = request.auth_name.clone().unwrap_or_else(|| {
+            match request.auth_location {
+                AuthLocation::Header => "Authorization",
+                AuthLocation::Query => "api_key",
+            }
+            .to_string()
+        });
+        Ok(Some(ApiAuth { location: request.auth_location, name, value }))
+    }
+
+    /// `productChannel` sent in POST bodies
+    pub fn post_product_channel(&self) -> &str {
+        self.request.product_channel.as_deref().unwrap_or(DEFAULT_PRODUCT_CHANNEL)
+    }
+
+    /// Page size sent in POST bodies
+    pub fn post_page_size(&self) -> i32 {
+        self.request.page_size.unwrap_or(DEFAULT_PAGE_SIZE)
+    }
+
     pub fn build_request_url(&self) -> String {
         if let Some(ref endpoint) = self.request.endpoint {
             format!("{}{}", self.api.base_url, endpoint)
@@ -110,3 +532,287 @@ impl ApiConfig {
         }
     }
 }
+
+/// Credentials the committed configs read from the environment, for tests
+/// to pass as `env` instead of setting process variables
+#[cfg(test)]
+pub(crate) fn test_credentials(var: &str) -> Option<String> {
+    (var == "KRAVE_MART_TOKEN").then(|| "Bearer test-token".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::JsonFlattener;
+    use serde_json::json;
+
+    fn assert_rules_match_builtin(config_path: &str, samples: &[serde_json::Value]) {
+        let config = ApiConfig::from_file(config_path).unwrap();
+        let rules = config.extraction_rules().unwrap();
+        assert!(!rules.is_empty(), "{} should ship extraction rules", config_path);
+
+        let builtin = JsonFlattener::new();
+        let configured = JsonFlattener::new().with_rules(rules);
+        for sample in samples {
+            assert_eq!(
+                configured.extract_fields_directly(sample).unwrap(),
+                builtin.extract_fields_directly(sample).unwrap(),
+                "{} rules differ from built-in extraction for {}",
+                config_path,
+                sample
+            );
+        }
+    }
+
+    #[test]
+    fn test_krave_mart_rules_match_builtin() {
+        assert_rules_match_builtin(
+            "src/configs/krave_mart.toml",
+            &[
+                json!({
+                    "store_id": 1242164,
+                    "sku": "BNDL7002230",
+                    "categories": [{"category_name": "Fruits & Vegetables", "category_id": 4960}],
+                    "product_price": "390.00",
+                    "special_price": "234.00",
+                    "sku_percent_off": "40% off",
+                    "product_id": 103922,
+                    "name": "Kfresh Potatoes (Aalu) - 3 Kg",
+                    "mrp": null,
+                    "cost_price": null
+                }),
+                json!({
+                    "product_id": 123,
+                    "name": "Test Product",
+                    "cost_price": 100.0,
+                    "mrp": 150.0,
+                    "sku": "TEST123",
+                    "sku_percent_off": 20,
+                    "categories": [{"category_name": " Dairy "}, {"category_name": "Milk"}]
+                }),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_store_urls_and_storage_names() {
+        let mut config = ApiConfig::from_file("src/configs/krave_mart.toml").unwrap();
+        config.api.stores.clear();
+        assert_eq!(config.storage_names(), vec!["krave_mart".to_string()]);
+        let (_, url) = &config.build_category_urls()[0];
+        assert!(url.ends_with("/products/1242164"));
+
+        config.api.stores = vec!["1242164".to_string(), "1242165".to_string()];
+        assert_eq!(
+            config.storage_names(),
+            vec!["krave_mart_1242164".to_string(), "krave_mart_1242165".to_string()]
+        );
+        let (_, url) = &config.build_category_urls_for_store(Some("1242165"))[0];
+        assert!(url.ends_with("/products/1242165"));
+
+        let dealcart = ApiConfig::from_file("src/configs/dealcart.toml").unwrap();
+        let (_, url) = &dealcart.build_category_urls_for_store(Some("7"))[0];
+        assert!(url.contains("warehouse_id=7&"));
+    }
+
+    #[test]
+    fn test_category_filter_limits_urls_and_slugs() {
+        let mut config = ApiConfig::from_file("src/configs/krave_mart.toml").unwrap();
+        let all = config.build_category_urls().len();
+        assert!(all > 2);
+
+        config.api.category_filter.exclude_categories = vec!["Flash Deals".to_string()];
+        assert_eq!(config.build_category_urls().len(), all - 1);
+
+        config.restrict_categories(&["fruits_veg".to_string(), "beverages".to_string()]);
+        let mut keys: Vec<String> = config.build_category_urls().into_iter().map(|(key, _)| key).collect();
+        keys.sort();
+        assert_eq!(keys, vec!["beverages".to_string(), "fruits_veg".to_string()]);
+
+        let mut bazaar = ApiConfig::from_file("src/configs/bazaar_app.toml").unwrap();
+        let (key, _) = bazaar.get_category_slugs()[0].clone();
+        bazaar.restrict_categories(std::slice::from_ref(&key));
+        assert_eq!(bazaar.get_category_slugs().len(), 1);
+    }
+
+    #[test]
+    fn test_validate() {
+        for path in [
+            "src/configs/krave_mart.toml",
+            "src/configs/dealcart.toml",
+            "src/configs/bazaar_app.toml",
+            "src/configs/pandamart.toml",
+        ] {
+            ApiConfig::from_file(path).unwrap().validate_with_env(test_credentials).unwrap();
+        }
+
+        let mut bazaar = ApiConfig::from_file("src/configs/bazaar_app.toml").unwrap();
+        bazaar.request.page_size = None;
+        let error = bazaar.validate().unwrap_err().to_string();
+        assert!(error.contains("missing page_size"), "{}", error);
+        bazaar.request.use_post_defaults = true;
+        bazaar.validate().unwrap();
+        assert_eq!(bazaar.post_page_size(), 20);
+
+        bazaar.request.method = "PUT".to_string();
+        assert!(bazaar.validate().is_err());
+
+        let mut pandamart = ApiConfig::from_file("src/configs/pandamart.toml").unwrap();
+        pandamart.categories.values_mut().next().unwrap().category_id = None;
+        assert!(pandamart.validate().is_err());
+        pandamart.categories.clear();
+        assert!(pandamart.validate().unwrap_err().to_string().contains("no categories"));
+
+        let mut krave_mart = ApiConfig::from_file("src/configs/krave_mart.toml").unwrap();
+        krave_mart.request.authorization = Some("Bearer committed".to_string());
+        assert!(krave_mart.validate_with_env(test_credentials).unwrap_err().to_string().contains("both authorization and auth_token_env"));
+    }
+
+    #[test]
+    fn test_auth_token_from_env_or_literal() {
+        let env = |var: &str| (var == "DEALCART_TOKEN").then(|| "from-env".to_string());
+        let no_env = |_: &str| None;
+
+        // Neither the variable nor a literal token
+        let mut config = ApiConfig::from_file("src/configs/dealcart.toml").unwrap();
+        config.api.auth_token_env = Some("DEALCART_TOKEN".to_string());
+        config.load_auth_token(no_env);
+        assert!(config.validate().unwrap_err().to_string().contains("$DEALCART_TOKEN is not set and auth_token is empty"));
+
+        // Only the variable
+        config.load_auth_token(env);
+        assert_eq!(config.api.auth_token, "from-env");
+        config.validate().unwrap();
+
+        // Only a literal token, which the variable wins over once it is set
+        config.api.auth_token = "committed".to_string();
+        config.load_auth_token(no_env);
+        assert_eq!(config.api.auth_token, "committed");
+        config.validate().unwrap();
+        config.load_auth_token(env);
+        assert_eq!(config.api.auth_token, "from-env");
+    }
+
+    #[test]
+    fn test_auth_token_env_must_be_set() {
+        let mut config = ApiConfig::from_file("src/configs/dealcart.toml").unwrap();
+        config.request.auth_token_env = Some("TEST_API_CONFIG_AUTH_TOKEN".to_string());
+        assert!(config.validate_with_env(|_| None).unwrap_err().to_string().contains("$TEST_API_CONFIG_AUTH_TOKEN, which is not set"));
+        config.validate_with_env(|_| Some("from-env".to_string())).unwrap();
+    }
+
+    #[test]
+    fn test_auth_from_config_or_env() {
+        let mut config = ApiConfig::from_file("src/configs/dealcart.toml").unwrap();
+        assert_eq!(config.auth().unwrap(), None);
+
+        // A literal `authorization` goes in the Authorization header
+        config.request.authorization = Some("Bearer committed".to_string());
+        let auth = config.auth().unwrap().unwrap();
+        assert_eq!((auth.location, auth.name.as_str(), auth.value.as_str()), (AuthLocation::Header, "Authorization", "Bearer committed"));
+        assert!(!format!("{:?}", config.auth().unwrap()).contains("Bearer"));
+
+        config.request.authorization = None;
+        assert_eq!(config.auth().unwrap(), None);
+        config.request.auth_token_env = Some("TEST_API_CONFIG_API_KEY".to_string());
+        assert!(config.auth_with_env(|_| None).unwrap_err().to_string().contains("$TEST_API_CONFIG_API_KEY"));
+        let env = |var: &str| (var == "TEST_API_CONFIG_API_KEY").then(|| "s3cret".to_string());
+        config.request.auth_name = Some("X-API-Key".to_string());
+        let auth = config.auth_with_env(env).unwrap().unwrap();
+        assert_eq!((auth.name.as_str(), auth.value.as_str()), ("X-API-Key", "s3cret"));
+
+        let query: RequestConfig = toml::from_str(
+            "method = \"GET\"\nauth_location = \"query\"\nauth_token_env = \"TEST_API_CONFIG_API_KEY\"\n[headers]",
+        )
+        .unwrap();
+        config.request = query;
+        let auth = config.auth_with_env(env).unwrap().unwrap();
+        assert_eq!((auth.location, auth.name.as_str()), (AuthLocation::Query, "api_key"));
+    }
+
+    #[test]
+    fn test_total_pages_from_first_page() {
+        let pagination = |extra: &str| -> PaginationConfig {
+            toml::from_str(&format!("type = \"page\"\ndefault_limit = 100\n{}", extra)).unwrap()
+        };
+        let body = json!({"count": 250, "meta": {"total_pages": "4"}, "data": []});
+
+        assert_eq!(pagination("").total_pages(&body, 100), None);
+        assert_eq!(pagination(r#"total_path = "count""#).total_pages(&body, 100), Some(3));
+        assert_eq!(pagination(r#"total_pages_path = "meta.total_pages""#).total_pages(&body, 100), Some(4));
+        assert_eq!(
+            pagination("total_path = \"count\"\ntotal_pages_path = \"meta.total_pages\"").total_pages(&body, 100),
+            Some(4)
+        );
+        // Missing fields fall back to fetching until pages come back empty
+        assert_eq!(pagination(r#"total_path = "total""#).total_pages(&body, 100), None);
+        assert_eq!(pagination(r#"total_path = "count""#).total_pages(&json!({"count": 0}), 0), Some(1));
+
+        // A server paging at fewer products than asked for sets the page size
+        assert_eq!(pagination(r#"total_path = "count""#).total_pages(&body, 20), Some(13));
+
+        let mut unlimited = pagination(r#"total_path = "count""#);
+        unlimited.default_limit = None;
+        assert_eq!(unlimited.total_pages(&body, 60), Some(5));
+
+        assert_eq!(unlimited.page_query(2), "page=2");
+        let limited = pagination("page_param = \"p\"\nlimit_param = \"limit\"");
+        assert_eq!(limited.page_query(2), "p=2&limit=100");
+    }
+
+    #[test]
+    fn test_data_path_is_one_or_many() {
+        let one: ResponseConfig = toml::from_str(r#"data_path = "body.results""#).unwrap();
+        assert_eq!(one.data_path.unwrap().paths(), ["body.results".to_string()]);
+
+        let many: ResponseConfig = toml::from_str(r#"data_path = ["featured[]", "regular[]"]"#).unwrap();
+        assert_eq!(many.data_path.unwrap().paths(), ["featured[]".to_string(), "regular[]".to_string()]);
+
+        let none: ResponseConfig = toml::from_str("").unwrap();
+        assert!(none.data_path.is_none());
+    }
+
+    #[test]
+    fn test_shipped_extraction_rules_parse() {
+        for path in ["src/configs/bazaar_app.toml", "src/configs/dealcart.toml"] {
+            let rules = ApiConfig::from_file(path).unwrap().extraction_rules().unwrap();
+            assert!(!rules.is_empty(), "{} should ship extraction rules", path);
+        }
+
+        let mut bazaar_app = ApiConfig::from_file("src/configs/bazaar_app.toml").unwrap();
+        assert!(bazaar_app.variants_path().unwrap().is_some());
+        assert_eq!(bazaar_app.schema_path(), Some("src/configs/schemas/bazaar_app.schema.json"));
+        bazaar_app.fields.validate_schema = false;
+        assert_eq!(bazaar_app.schema_path(), None);
+        let dealcart = ApiConfig::from_file("src/configs/dealcart.toml").unwrap();
+        assert!(dealcart.variants_path().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_pandamart_rules_match_builtin() {
+        assert_rules_match_builtin(
+            "src/configs/pandamart.toml",
+            &[
+                json!({
+                    "id": "12345",
+                    "name": "Fresh Bananas",
+                    "price": 150.0,
+                    "original_price": 200.0,
+                    "discount_percentage": 25,
+                    "category_section": "Fresh Fruits"
+                }),
+                json!({
+                    "productID": "PM-778",
+                    "name": "Nestle Milkpak 1L",
+                    "price": 290,
+                    "originalPrice": "310",
+                    "attributes": [
+                        {"key": "baseUnit", "value": "ltr"},
+                        {"key": "sku", "value": "SKU-778"}
+                    ],
+                    "category_section": "Dairy"
+                }),
+            ],
+        );
+    }
+}
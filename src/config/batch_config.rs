@@ -0,0 +1,128 @@
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+/// One step of the batch size ladder: sources with at most `max_products`
+/// products are processed `batch_size` products at a time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchTier {
+    pub max_products: usize,
+    pub batch_size: usize,
+}
+
+/// How many raw products are flattened and processed at a time, depending on
+/// the size of the source. Smaller batches bound memory, larger ones are faster.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BatchConfig {
+    /// Sources with at most this many products are processed in one go
+    pub single_batch_max: usize,
+    /// Larger sources use the first tier they fit in, checked in order
+    pub tiers: Vec<BatchTier>,
+    /// Batch size for sources beyond the last tier
+    pub max_batch_size: usize,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        BatchConfig {
+            single_batch_max: 500,
+            tiers: vec![
+                BatchTier { max_products: 5_000, batch_size: 500 },
+                BatchTier { max_products: 50_000, batch_size: 2_000 },
+            ],
+            max_batch_size: 5_000,
+        }
+    }
+}
+
+impl BatchConfig {
+    pub fn from_file(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read batch config file: {}", path))?;
+        let config: BatchConfig = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse batch config file: {}", path))?;
+        config
+            .validate()
+            .with_context(|| format!("Invalid batch config in {}", path))?;
+        Ok(config)
+    }
+
+    /// Batch sizes must be positive and tiers ordered by growing source size
+    pub fn validate(&self) -> Result<()> {
+        if self.max_batch_size == 0 || self.tiers.iter().any(|tier| tier.batch_size == 0) {
+            return Err(anyhow!("Batch sizes must be greater than 0"));
+        }
+
+        let mut previous = self.single_batch_max;
+        for tier in &self.tiers {
+            if tier.max_products <= previous {
+                return Err(anyhow!(
+                    "Tier max_products must increase past single_batch_max ({}), got {} after {}",
+                    self.single_batch_max,
+                    tier.max_products,
+                    previous
+                ));
+            }
+            previous = tier.max_products;
+        }
+        Ok(())
+    }
+}
+
+/// Products per batch for a source of `total` products. Returns `total`
+/// itself when the source fits in a single batch.
+pub fn choose_batch_size(total: usize, config: &BatchConfig) -> usize {
+    if total <= config.single_batch_max {
+        return total;
+    }
+
+    config
+        .tiers
+        .iter()
+        .find(|tier| total <= tier.max_products)
+        .map_or(config.max_batch_size, |tier| tier.batch_size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_choose_batch_size() {
+        let config = BatchConfig::default();
+        assert_eq!(choose_batch_size(0, &config), 0);
+        assert_eq!(choose_batch_size(500, &config), 500);
+        assert_eq!(choose_batch_size(501, &config), 500);
+        assert_eq!(choose_batch_size(5_000, &config), 500);
+        assert_eq!(choose_batch_size(5_001, &config), 2_000);
+        assert_eq!(choose_batch_size(50_000, &config), 2_000);
+        assert_eq!(choose_batch_size(50_001, &config), 5_000);
+
+        let small = BatchConfig { single_batch_max: 100, tiers: vec![], max_batch_size: 50 };
+        assert_eq!(choose_batch_size(80, &small), 80);
+        assert_eq!(choose_batch_size(1_000, &small), 50);
+    }
+
+    #[test]
+    fn test_batch_config_file_and_validation() {
+        let shipped = BatchConfig::from_file("src/configs/batching.toml").unwrap();
+        assert_eq!(shipped, BatchConfig::default());
+
+        // Keys left out keep their defaults
+        let partial: BatchConfig = toml::from_str("max_batch_size = 10000").unwrap();
+        assert_eq!(partial.single_batch_max, 500);
+        assert_eq!(partial.max_batch_size, 10_000);
+
+        let zero = BatchConfig { max_batch_size: 0, ..BatchConfig::default() };
+        assert!(zero.validate().is_err());
+
+        let unordered = BatchConfig {
+            tiers: vec![
+                BatchTier { max_products: 50_000, batch_size: 2_000 },
+                BatchTier { max_products: 5_000, batch_size: 500 },
+            ],
+            ..BatchConfig::default()
+        };
+        assert!(unordered.validate().is_err());
+    }
+}
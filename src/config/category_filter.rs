@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+
+/// Allow / deny lists narrowing which categories of a source are fetched.
+///
+/// Entries match a category's config key or its display name, ignoring case.
+/// An empty include list means every category; excludes always win.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CategoryFilter {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include_categories: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_categories: Vec<String>,
+}
+
+impl CategoryFilter {
+    /// Whether the category with this key and display name should be fetched
+    pub fn allows(&self, key: &str, name: &str) -> bool {
+        let matches = |entry: &String| entry.eq_ignore_ascii_case(key) || entry.eq_ignore_ascii_case(name);
+
+        if self.exclude_categories.iter().any(matches) {
+            return false;
+        }
+        self.include_categories.is_empty() || self.include_categories.iter().any(matches)
+    }
+
+    /// Only fetch these categories, replacing the configured include list.
+    /// Used for `--categories` on the command line.
+    pub fn restrict_to(&mut self, categories: &[String]) {
+        if !categories.is_empty() {
+            self.include_categories = categories.to_vec();
+        }
+    }
+}
+
+/// Parse a comma-separated `--categories` value, dropping empty entries
+pub fn parse_category_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|entry| entry.trim().to_string())
+        .filter(|entry| !entry.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_include_and_exclude() {
+        let mut filter = CategoryFilter::default();
+        assert!(filter.allows("fruits_veg", "Fruits & Vegetables"));
+
+        filter.exclude_categories = vec!["flash_deals".to_string()];
+        assert!(!filter.allows("flash_deals", "Flash Deals"));
+        assert!(filter.allows("fruits_veg", "Fruits & Vegetables"));
+
+        filter.restrict_to(&parse_category_list(" fruits & vegetables, flash_deals ,"));
+        assert!(filter.allows("fruits_veg", "Fruits & Vegetables"));
+        assert!(!filter.allows("beverages", "Beverages"));
+        // Excludes still apply to explicitly requested categories
+        assert!(!filter.allows("flash_deals", "Flash Deals"));
+
+        filter.restrict_to(&[]);
+        assert_eq!(filter.include_categories.len(), 2);
+    }
+}
@@ -0,0 +1,170 @@
+use anyhow::Result;
+use std::fmt;
+
+use crate::config::{ApiConfig, HtmlConfig, SourceType, SourcesConfig};
+
+/// Outcome of loading and validating one config file
+#[derive(Debug, Clone)]
+pub struct ConfigCheck {
+    /// Source the config belongs to, or `sources` for `sources.toml` itself
+    pub name: String,
+    pub path: String,
+    /// Why the config is invalid, `None` when it passed
+    pub error: Option<String>,
+}
+
+/// Result of `check_configs`, one entry per config file in `sources.toml` order
+#[derive(Debug, Clone, Default)]
+pub struct ConfigReport {
+    pub checks: Vec<ConfigCheck>,
+}
+
+impl ConfigReport {
+    pub fn is_valid(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &ConfigCheck> {
+        self.checks.iter().filter(|check| check.error.is_some())
+    }
+}
+
+impl fmt::Display for ConfigReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Config validation:")?;
+        let width = self.checks.iter().map(|check| check.name.len()).max().unwrap_or(0);
+        for check in &self.checks {
+            match &check.error {
+                None => writeln!(f, "  ✅ {:<width$}  {}", check.name, check.path)?,
+                Some(error) => writeln!(f, "  ❌ {:<width$}  {}: {}", check.name, check.path, error)?,
+            }
+        }
+
+        match self.failures().count() {
+            0 => write!(f, "Result: all {} configs valid", self.checks.len()),
+            failed => write!(f, "Result: {} of {} configs invalid", failed, self.checks.len()),
+        }
+    }
+}
+
+/// Load `sources.toml` at `sources_path` and every source config it lists,
+/// enabled or not, running the same checks a run does before fetching plus
+/// the field paths its products are extracted with
+pub fn check_configs(sources_path: &str) -> ConfigReport {
+    check_configs_with_env(sources_path, |var| std::env::var(var).ok())
+}
+
+/// `check_configs`, reading the credentials sources take from the
+/// environment from `env` instead
+pub fn check_configs_with_env(sources_path: &str, env: impl Fn(&str) -> Option<String>) -> ConfigReport {
+    let mut report = ConfigReport::default();
+    let sources = match SourcesConfig::from_file(sources_path) {
+        Ok(sources) => sources,
+        Err(e) => {
+            report.checks.push(ConfigCheck { name: "sources".to_string(), path: sources_path.to_string(), error: Some(format!("{:#}", e)) });
+            return report;
+        }
+    };
+
+    for source in &sources.sources {
+        let result = match source.source_type {
+            SourceType::Json => check_api_config(&source.config_path, &env),
+            SourceType::Html => check_html_config(&source.config_path),
+        };
+        report.checks.push(ConfigCheck {
+            name: source.name.clone(),
+            path: source.config_path.clone(),
+            error: result.err().map(|e| format!("{:#}", e)),
+        });
+    }
+    report
+}
+
+fn check_api_config(path: &str, env: impl Fn(&str) -> Option<String>) -> Result<()> {
+    let config = ApiConfig::from_file(path)?;
+    config.validate_with_env(env)?;
+    config.extraction_rules()?;
+    config.variants_path()?;
+    config.in_stock_paths()?;
+    Ok(())
+}
+
+fn check_html_config(path: &str) -> Result<()> {
+    let config = HtmlConfig::from_file(path)?;
+    config.validate()?;
+    config.extraction_rules()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    /// Write `sources.toml` listing each fixture, written next to it from a
+    /// committed config with one edit applied
+    fn write_fixtures(dir: &Path, fixtures: &[(&str, &str, &str, &str, &str)]) -> String {
+        std::fs::create_dir_all(dir).unwrap();
+        let mut sources = String::new();
+        for (name, source_type, template, from, to) in fixtures {
+            let content = std::fs::read_to_string(format!("src/configs/{}", template)).unwrap();
+            assert!(content.contains(from), "{} does not contain {}", template, from);
+            std::fs::write(dir.join(format!("{}.toml", name)), content.replacen(from, to, 1)).unwrap();
+            sources.push_str(&format!("[[sources]]\nname = \"{}\"\ntype = \"{}\"\nconfig_path = \"{}.toml\"\n\n", name, source_type, name));
+        }
+        let path = dir.join("sources.toml");
+        std::fs::write(&path, sources).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_committed_configs_are_valid() {
+        let report = check_configs_with_env("src/configs/sources.toml", crate::config::api_config::test_credentials);
+        assert!(report.is_valid(), "{}", report);
+        assert_eq!(report.checks.len(), 5);
+        assert!(report.to_string().ends_with("Result: all 5 configs valid"));
+    }
+
+    #[test]
+    fn test_broken_configs_are_reported() {
+        let dir = std::env::temp_dir().join(format!("config_check_{}", uuid::Uuid::new_v4()));
+        let sources = write_fixtures(
+            &dir,
+            &[
+                ("valid", "json", "dealcart.toml", "", ""),
+                ("broken_toml", "json", "dealcart.toml", "[request]", "[request"),
+                ("unknown_pagination", "json", "dealcart.toml", "type = \"none\"", "type = \"cursor\""),
+                ("bad_data_path", "json", "dealcart.toml", "\"body.results\"", "\"body..results\""),
+                ("no_urls", "json", "dealcart.toml", "[request]", "include_categories = [\"nothing\"]\n\n[request]"),
+                ("no_endpoint", "json", "bazaar_app.toml", "endpoint =", "# endpoint ="),
+                ("bad_selector", "html", "naheed.toml", "\".product-item-info\"", "\"div[class=\""),
+                ("no_names", "html", "naheed.toml", "name_selectors = [", "name_selectors = []\nunused_selectors = ["),
+            ],
+        );
+        let report = check_configs(&sources);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let errors: Vec<(&str, &str)> =
+            report.checks.iter().map(|check| (check.name.as_str(), check.error.as_deref().unwrap_or("ok"))).collect();
+        let expected = [
+            ("valid", "ok"),
+            ("broken_toml", "TOML parse error"),
+            ("unknown_pagination", "unknown pagination type 'cursor'"),
+            ("bad_data_path", "empty segment in 'body..results'"),
+            ("no_urls", "yields no category URLs"),
+            ("no_endpoint", "needs request.endpoint"),
+            ("bad_selector", "invalid selector 'div[class='"),
+            ("no_names", "at least one selector in name_selectors"),
+        ];
+        assert_eq!(errors.len(), expected.len());
+        for ((name, error), (expected_name, expected_error)) in errors.iter().zip(expected) {
+            assert_eq!(*name, expected_name);
+            assert!(error.contains(expected_error), "{}: {}", name, error);
+        }
+        assert!(!report.is_valid());
+        assert!(report.to_string().ends_with("Result: 7 of 8 configs invalid"));
+
+        let missing = check_configs(dir.join("sources.toml").to_str().unwrap());
+        assert_eq!((missing.checks.len(), missing.checks[0].name.as_str()), (1, "sources"));
+    }
+}
@@ -0,0 +1,57 @@
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+/// Database the processed products are upserted into with `--sink db`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum DatabaseConfig {
+    /// A local SQLite file, created with its directory when missing
+    Sqlite { path: String },
+    /// A Postgres server; without `url` the connection string is read from
+    /// `DATABASE_URL`. Needs a build with `--features postgres`.
+    Postgres {
+        #[serde(default)]
+        url: Option<String>,
+    },
+}
+
+impl DatabaseConfig {
+    pub fn from_file(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read database config file: {}", path))?;
+        let config: DatabaseConfig = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse database config file: {}", path))?;
+        config
+            .validate()
+            .with_context(|| format!("Invalid database config in {}", path))?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            DatabaseConfig::Sqlite { path } if path.trim().is_empty() => {
+                Err(anyhow!("sqlite path must not be empty"))
+            }
+            DatabaseConfig::Postgres { url: Some(url) } if url.trim().is_empty() => {
+                Err(anyhow!("postgres url must not be empty, leave it out to use DATABASE_URL"))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_database_config() {
+        let config = DatabaseConfig::from_file("src/configs/database.toml").unwrap();
+        assert!(matches!(config, DatabaseConfig::Sqlite { .. }));
+
+        let postgres: DatabaseConfig = toml::from_str("kind = \"postgres\"\n").unwrap();
+        assert_eq!(postgres, DatabaseConfig::Postgres { url: None });
+        assert!(toml::from_str::<DatabaseConfig>("kind = \"mysql\"\n").is_err());
+        assert!(DatabaseConfig::Sqlite { path: " ".to_string() }.validate().is_err());
+    }
+}
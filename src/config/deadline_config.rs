@@ -0,0 +1,74 @@
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// How long a source may take to fetch, store and process before it is
+/// given up on, so one stuck source cannot hold up the rest of the run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeadlineConfig {
+    /// Deadline of every source, covering all of its stores; none when unset
+    pub source_deadline_seconds: Option<u64>,
+    /// Per-source deadlines overriding `source_deadline_seconds`
+    pub sources: HashMap<String, u64>,
+    /// Store the categories a source fetched before missing its deadline as
+    /// its raw dump, instead of dropping them
+    pub store_partial: bool,
+}
+
+impl DeadlineConfig {
+    pub fn from_file(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read deadline config file: {}", path))?;
+        let config: DeadlineConfig = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse deadline config file: {}", path))?;
+        config
+            .validate()
+            .with_context(|| format!("Invalid deadline config in {}", path))?;
+        Ok(config)
+    }
+
+    /// Deadlines must be at least a second
+    pub fn validate(&self) -> Result<()> {
+        if self.source_deadline_seconds == Some(0) {
+            return Err(anyhow!("source_deadline_seconds must be greater than 0"));
+        }
+        if let Some((source, _)) = self.sources.iter().find(|(_, seconds)| **seconds == 0) {
+            return Err(anyhow!("The deadline of {} must be greater than 0", source));
+        }
+        Ok(())
+    }
+
+    /// Time `source` may take, if it has a deadline
+    pub fn deadline_for(&self, source: &str) -> Option<Duration> {
+        self.sources
+            .get(source)
+            .copied()
+            .or(self.source_deadline_seconds)
+            .map(Duration::from_secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_deadlines() {
+        let config = DeadlineConfig::from_file("src/configs/deadlines.toml").unwrap();
+        assert!(config.deadline_for("krave_mart").is_some());
+
+        let config: DeadlineConfig = toml::from_str(
+            "source_deadline_seconds = 900\nstore_partial = true\n[sources]\nnaheed = 1800",
+        )
+        .unwrap();
+        assert_eq!(config.deadline_for("naheed"), Some(Duration::from_secs(1800)));
+        assert_eq!(config.deadline_for("dealcart"), Some(Duration::from_secs(900)));
+        assert!(config.store_partial);
+
+        assert_eq!(DeadlineConfig::default().deadline_for("dealcart"), None);
+        let zero: DeadlineConfig = toml::from_str("[sources]\nnaheed = 0").unwrap();
+        assert!(zero.validate().unwrap_err().to_string().contains("naheed"));
+    }
+}
@@ -1,6 +1,13 @@
+use anyhow::anyhow;
+use scraper::Selector;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::config::CategoryFilter;
+use crate::fetcher::client::DEFAULT_MAX_RESPONSE_BYTES;
+use crate::processor::json_flattener::{FieldExtractionRules, NumberFormat};
+use crate::processor::rule_normalizer::ColumnTransform;
+
 /// Configuration for HTML-based data sources (web scraping)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HtmlConfig {
@@ -8,6 +15,11 @@ pub struct HtmlConfig {
     pub scraping: ScrapingConfig,
     pub selectors: SelectorConfig,
     pub categories: HashMap<String, CategoryConfig>,
+    #[serde(default)]
+    pub fields: HtmlFieldConfig,
+    /// `[[transforms]]` applied to the source's columns after normalizing
+    #[serde(default)]
+    pub transforms: Vec<ColumnTransform>,
 }
 
 /// Basic site information
@@ -16,6 +28,19 @@ pub struct SiteConfig {
     pub name: String,
     pub base_url: String,
     pub user_agent: Option<String>,
+    /// Browser fingerprint to emulate, e.g. "chrome120" (default firefox136)
+    #[serde(default)]
+    pub emulation: Option<String>,
+    /// Currency of products whose prices do not name one, e.g. "PKR" or "AED"
+    #[serde(default)]
+    pub currency: Option<String>,
+    /// Separators prices are written with: `us` ("1,234.50", default) or
+    /// `eu` ("1.234,50")
+    #[serde(default)]
+    pub number_format: NumberFormat,
+    /// `include_categories` / `exclude_categories` lists
+    #[serde(flatten)]
+    pub category_filter: CategoryFilter,
 }
 
 /// Scraping behavior configuration
@@ -24,8 +49,37 @@ pub struct ScrapingConfig {
     pub delay_between_requests_ms: u64,
     pub max_pages_per_category: usize,
     pub max_retries: usize,
+    /// Base delay for exponential backoff between retries
+    #[serde(default = "default_backoff_base_ms")]
+    pub backoff_base_ms: u64,
+    /// Upper bound for a single backoff delay
+    #[serde(default = "default_backoff_max_ms")]
+    pub backoff_max_ms: u64,
     pub timeout_seconds: u64,
     pub respect_robots_txt: bool,
+    /// How many levels of subcategory links are followed below a category
+    /// with `discover_subcategories`
+    #[serde(default = "default_max_category_depth")]
+    pub max_category_depth: usize,
+    /// Largest page body read before the request fails (default 256 MiB)
+    #[serde(default = "default_max_response_bytes")]
+    pub max_response_bytes: u64,
+}
+
+fn default_max_response_bytes() -> u64 {
+    DEFAULT_MAX_RESPONSE_BYTES
+}
+
+fn default_max_category_depth() -> usize {
+    2
+}
+
+fn default_backoff_base_ms() -> u64 {
+    1000
+}
+
+fn default_backoff_max_ms() -> u64 {
+    30000
 }
 
 /// CSS selectors for extracting data
@@ -36,6 +90,22 @@ pub struct SelectorConfig {
     pub price_selectors: Vec<String>,
     pub category_selectors: Vec<String>,
     pub pagination_selectors: Vec<String>,
+    /// Links to subcategory pages, followed for categories with
+    /// `discover_subcategories`; matched elements that are not links are
+    /// searched for `a[href]`
+    #[serde(default)]
+    pub subcategory_selectors: Vec<String>,
+}
+
+/// Field extraction rules applied when flattening scraped products
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HtmlFieldConfig {
+    /// Canonical field -> ordered JSON paths, see `FieldPath` for the syntax
+    #[serde(default)]
+    pub extraction: HashMap<String, Vec<String>>,
+    /// Canonical field -> value used when none of its paths match
+    #[serde(default)]
+    pub defaults: HashMap<String, String>,
 }
 
 /// Category-specific configuration
@@ -44,6 +114,10 @@ pub struct CategoryConfig {
     pub name: String,
     pub base_url: String,
     pub enabled: bool,
+    /// Treat `base_url` as the root of a category tree: scrape the leaf
+    /// categories found through `subcategory_selectors` instead of the page itself
+    #[serde(default)]
+    pub discover_subcategories: bool,
 }
 
 impl HtmlConfig {
@@ -54,22 +128,75 @@ impl HtmlConfig {
         Ok(config)
     }
 
-    /// Get all enabled categories
+    /// Get all enabled categories that pass the include / exclude lists
     pub fn get_enabled_categories(&self) -> Vec<(&String, &CategoryConfig)> {
         self.categories
             .iter()
-            .filter(|(_, config)| config.enabled)
+            .filter(|(key, config)| config.enabled && self.site.category_filter.allows(key, &config.name))
             .collect()
     }
 
+    /// Only scrape these categories (matched by key or name), e.g. from `--categories`
+    pub fn restrict_categories(&mut self, categories: &[String]) {
+        self.site.category_filter.restrict_to(categories);
+    }
+
+    /// Field extraction rules for `JsonFlattener`, if the config defines any
+    pub fn extraction_rules(&self) -> Result<FieldExtractionRules, anyhow::Error> {
+        FieldExtractionRules::from_config(&self.fields.extraction, &self.fields.defaults)
+    }
+
     /// Build URLs for all categories
     pub fn build_category_urls(&self) -> Vec<(String, String)> {
-        self.categories
-            .iter()
-            .filter(|(_, config)| config.enabled)
+        self.get_enabled_categories()
+            .into_iter()
             .map(|(key, config)| (key.clone(), config.base_url.clone()))
             .collect()
     }
+
+    /// Check the config before anything is scraped: some categories, and
+    /// product, name and price selectors that all parse as CSS
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        let name = &self.site.name;
+        if self.categories.is_empty() {
+            return Err(anyhow!("{} has no categories configured", name));
+        }
+        if self.scraping.max_response_bytes == 0 {
+            return Err(anyhow!("{} scraping.max_response_bytes must be over 0", name));
+        }
+        let selectors = &self.selectors;
+        for (list, selectors, required) in [
+            ("product_selectors", &selectors.product_selectors, true),
+            ("name_selectors", &selectors.name_selectors, true),
+            ("price_selectors", &selectors.price_selectors, true),
+            ("category_selectors", &selectors.category_selectors, false),
+            ("pagination_selectors", &selectors.pagination_selectors, false),
+            ("subcategory_selectors", &selectors.subcategory_selectors, false),
+        ] {
+            if required && selectors.is_empty() {
+                return Err(anyhow!("{} needs at least one selector in {}", name, list));
+            }
+            for selector in selectors {
+                Selector::parse(selector).map_err(|e| anyhow!("{} {} has an invalid selector '{}': {}", name, list, selector, e))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ScrapingConfig {
+    /// Delay before the next retry: `backoff_base_ms * 2^attempt + jitter`,
+    /// clamped to `backoff_max_ms`
+    pub fn backoff_delay_ms(&self, attempt: u32, jitter_ms: u64) -> u64 {
+        let exponential = 2_u64
+            .checked_pow(attempt)
+            .and_then(|factor| self.backoff_base_ms.checked_mul(factor))
+            .unwrap_or(u64::MAX);
+
+        exponential
+            .saturating_add(jitter_ms)
+            .min(self.backoff_max_ms)
+    }
 }
 
 impl Default for ScrapingConfig {
@@ -78,8 +205,12 @@ impl Default for ScrapingConfig {
             delay_between_requests_ms: 2000,
             max_pages_per_category: 10,
             max_retries: 3,
+            backoff_base_ms: default_backoff_base_ms(),
+            backoff_max_ms: default_backoff_max_ms(),
             timeout_seconds: 30,
             respect_robots_txt: true,
+            max_category_depth: default_max_category_depth(),
+            max_response_bytes: default_max_response_bytes(),
         }
     }
 }
@@ -118,6 +249,7 @@ impl Default for SelectorConfig {
                 ".pager".to_string(),
                 ".page-numbers".to_string(),
             ],
+            subcategory_selectors: Vec::new(),
         }
     }
 }
@@ -137,6 +269,59 @@ mod tests {
         assert!(!selector_config.name_selectors.is_empty());
     }
 
+    #[test]
+    fn test_backoff_delay_is_clamped() {
+        let scraping_config = ScrapingConfig {
+            backoff_base_ms: 500,
+            backoff_max_ms: 5000,
+            ..ScrapingConfig::default()
+        };
+
+        assert_eq!(scraping_config.backoff_delay_ms(1, 0), 1000);
+        assert_eq!(scraping_config.backoff_delay_ms(2, 250), 2250);
+        assert_eq!(scraping_config.backoff_delay_ms(4, 0), 5000);
+        assert_eq!(scraping_config.backoff_delay_ms(80, 999), 5000);
+    }
+
+    #[test]
+    fn test_backoff_fields_default_when_missing() {
+        let scraping_config: ScrapingConfig = toml::from_str(
+            r#"
+            delay_between_requests_ms = 2000
+            max_pages_per_category = 5
+            max_retries = 4
+            timeout_seconds = 30
+            respect_robots_txt = true
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(scraping_config.max_retries, 4);
+        assert_eq!(scraping_config.backoff_base_ms, 1000);
+        assert_eq!(scraping_config.backoff_max_ms, 30000);
+        assert_eq!(scraping_config.max_category_depth, 2);
+        assert_eq!(scraping_config.max_response_bytes, DEFAULT_MAX_RESPONSE_BYTES);
+    }
+
+    #[test]
+    fn test_category_tree_config() {
+        let category: CategoryConfig = toml::from_str(
+            "name = \"Groceries\"\nbase_url = \"https://example.com/groceries\"\nenabled = true\ndiscover_subcategories = true",
+        )
+        .unwrap();
+        assert!(category.discover_subcategories);
+
+        let config = HtmlConfig::from_file("src/configs/naheed.toml").unwrap();
+        assert!(!config.selectors.subcategory_selectors.is_empty());
+    }
+
+    #[test]
+    fn test_naheed_extraction_rules_parse() {
+        let config = HtmlConfig::from_file("src/configs/naheed.toml").unwrap();
+        let rules = config.extraction_rules().unwrap();
+        assert!(!rules.is_empty());
+    }
+
     #[test]
     fn test_enabled_categories_filter() {
         let mut categories = HashMap::new();
@@ -144,11 +329,13 @@ mod tests {
             name: "Fresh Fruits".to_string(),
             base_url: "https://example.com/fruits".to_string(),
             enabled: true,
+            discover_subcategories: false,
         });
         categories.insert("disabled".to_string(), CategoryConfig {
             name: "Disabled Category".to_string(),
             base_url: "https://example.com/disabled".to_string(),
             enabled: false,
+            discover_subcategories: false,
         });
 
         let config = HtmlConfig {
@@ -156,14 +343,28 @@ mod tests {
                 name: "Test Site".to_string(),
                 base_url: "https://example.com".to_string(),
                 user_agent: None,
+                emulation: None,
+                currency: None,
+                number_format: NumberFormat::default(),
+                category_filter: CategoryFilter::default(),
             },
             scraping: ScrapingConfig::default(),
             selectors: SelectorConfig::default(),
             categories,
+            fields: HtmlFieldConfig::default(),
+            transforms: Vec::new(),
         };
 
         let enabled = config.get_enabled_categories();
         assert_eq!(enabled.len(), 1);
         assert_eq!(enabled[0].0, "fruits");
+
+        // Asking for a disabled category does not enable it
+        let mut config = config;
+        config.restrict_categories(&["disabled".to_string()]);
+        assert!(config.get_enabled_categories().is_empty());
+
+        config.restrict_categories(&["fresh fruits".to_string()]);
+        assert_eq!(config.build_category_urls().len(), 1);
     }
 }
@@ -0,0 +1,75 @@
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::processor::product_matcher::{DEFAULT_AMBIGUITY_MARGIN, DEFAULT_MATCH_THRESHOLD, ProductMatcher};
+
+/// How products of the merged dataset are matched across sources
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MatcherConfig {
+    /// Name similarity (0–1) from which two products are the same SKU
+    pub threshold: f64,
+    /// Products whose two best candidates in a source score within this of
+    /// each other are reported as ambiguous
+    pub ambiguity_margin: f64,
+    /// Category names of a store -> the canonical category products are
+    /// compared under, e.g. `"vegetables" = "fruits & vegetables"`
+    pub category_aliases: HashMap<String, String>,
+}
+
+impl Default for MatcherConfig {
+    fn default() -> Self {
+        MatcherConfig {
+            threshold: DEFAULT_MATCH_THRESHOLD,
+            ambiguity_margin: DEFAULT_AMBIGUITY_MARGIN,
+            category_aliases: HashMap::new(),
+        }
+    }
+}
+
+impl MatcherConfig {
+    pub fn from_file(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read matcher config file: {}", path))?;
+        let config: MatcherConfig = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse matcher config file: {}", path))?;
+        config
+            .validate()
+            .with_context(|| format!("Invalid matcher config in {}", path))?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if !(self.threshold > 0.0 && self.threshold <= 1.0) {
+            return Err(anyhow!("threshold must be in (0, 1], got {}", self.threshold));
+        }
+        if !(self.ambiguity_margin >= 0.0 && self.ambiguity_margin < 1.0) {
+            return Err(anyhow!("ambiguity_margin must be in [0, 1), got {}", self.ambiguity_margin));
+        }
+        Ok(())
+    }
+
+    pub fn matcher(&self) -> ProductMatcher {
+        ProductMatcher::new()
+            .with_threshold(self.threshold)
+            .with_ambiguity_margin(self.ambiguity_margin)
+            .with_category_aliases(self.category_aliases.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matcher_config() {
+        let config = MatcherConfig::from_file("src/configs/matcher.toml").unwrap();
+        assert!(config.validate().is_ok());
+        assert!(!config.category_aliases.is_empty());
+
+        let strict: MatcherConfig = toml::from_str("threshold = 1.5\n").unwrap();
+        assert!(strict.validate().is_err());
+        assert_eq!(toml::from_str::<MatcherConfig>("").unwrap(), MatcherConfig::default());
+    }
+}
@@ -11,18 +11,27 @@ pub struct MinioConfigFile {
 pub struct MinioSection {
     pub endpoint: String,
     pub bucket_name: String,
+    // Optional per-tier buckets, falling back to bucket_name
+    pub raw_bucket_name: Option<String>,
+    pub clean_bucket_name: Option<String>,
     pub region: Option<String>,
     pub path_style: Option<bool>,
     pub ssl: Option<bool>,
     // Optional environment variable names for customization
     pub env_access_key: Option<String>,
     pub env_secret_key: Option<String>,
+    /// Fall back to the AWS credential chain when the key variables are unset
+    pub credential_chain: Option<bool>,
+    /// Shared credentials file profile for the chain (default `AWS_PROFILE`, then "default")
+    pub aws_profile: Option<String>,
 }
 
 #[derive(Debug, Clone)]
 pub struct MinioConfig {
     pub endpoint: String,
     pub bucket_name: String,
+    pub raw_bucket_name: Option<String>,
+    pub clean_bucket_name: Option<String>,
     pub region: Option<String>,
     pub path_style: Option<bool>,
     pub ssl: Option<bool>,
@@ -32,6 +41,11 @@ pub struct MinioConfig {
     // Optional environment variable names for customization
     pub env_access_key: Option<String>,
     pub env_secret_key: Option<String>,
+    /// Resolve credentials through the AWS chain (environment, shared
+    /// credentials file, ECS / EC2 instance metadata) when the key
+    /// environment variables are unset
+    pub credential_chain: bool,
+    pub aws_profile: Option<String>,
 }
 
 impl MinioConfig {
@@ -70,6 +84,8 @@ impl MinioConfig {
         Self {
             endpoint: section.endpoint,
             bucket_name: section.bucket_name,
+            raw_bucket_name: section.raw_bucket_name,
+            clean_bucket_name: section.clean_bucket_name,
             region: section.region,
             path_style: section.path_style,
             ssl: section.ssl,
@@ -77,23 +93,16 @@ impl MinioConfig {
             secret_key: None,
             env_access_key: section.env_access_key,
             env_secret_key: section.env_secret_key,
+            credential_chain: section.credential_chain.unwrap_or(false),
+            aws_profile: section.aws_profile,
         }
     }
 
     pub fn load_credentials(&mut self) -> Result<()> {
         // Default environment variable names
-        let access_key_var = self.env_access_key.as_deref().unwrap_or("MINIO_ACCESS_KEY");
-        let secret_key_var = self.env_secret_key.as_deref().unwrap_or("MINIO_SECRET_KEY");
-
-        self.access_key = env::var(access_key_var)
-            .with_context(|| format!("Missing environment variable: {}", access_key_var))?
-            .into();
-
-        self.secret_key = env::var(secret_key_var)
-            .with_context(|| format!("Missing environment variable: {}", secret_key_var))?
-            .into();
-
-        Ok(())
+        let access_key_var = self.env_access_key.clone().unwrap_or_else(|| "MINIO_ACCESS_KEY".to_string());
+        let secret_key_var = self.env_secret_key.clone().unwrap_or_else(|| "MINIO_SECRET_KEY".to_string());
+        self.load_credentials_from(&access_key_var, &secret_key_var)
     }
 
     #[allow(dead_code)]
@@ -101,16 +110,34 @@ impl MinioConfig {
         // Environment variable names with custom prefix
         let access_key_var = format!("{}_ACCESS_KEY", prefix.to_uppercase());
         let secret_key_var = format!("{}_SECRET_KEY", prefix.to_uppercase());
+        self.load_credentials_from(&access_key_var, &secret_key_var)
+    }
 
-        self.access_key = env::var(&access_key_var)
-            .with_context(|| format!("Missing environment variable: {}", access_key_var))?
-            .into();
+    /// Static keys from the given variables, which take precedence over the
+    /// credential chain; with `credential_chain` unset both must exist
+    fn load_credentials_from(&mut self, access_key_var: &str, secret_key_var: &str) -> Result<()> {
+        self.access_key = None;
+        self.secret_key = None;
+        match (env::var(access_key_var), env::var(secret_key_var)) {
+            (Ok(access_key), Ok(secret_key)) => {
+                self.access_key = Some(access_key);
+                self.secret_key = Some(secret_key);
+                Ok(())
+            }
+            _ if self.credential_chain => Ok(()),
+            (Err(_), _) => Err(anyhow::anyhow!("Missing environment variable: {}", access_key_var)),
+            (_, Err(_)) => Err(anyhow::anyhow!("Missing environment variable: {}", secret_key_var)),
+        }
+    }
 
-        self.secret_key = env::var(&secret_key_var)
-            .with_context(|| format!("Missing environment variable: {}", secret_key_var))?
-            .into();
+    /// Whether credentials come from the AWS chain rather than static keys
+    pub fn uses_credential_chain(&self) -> bool {
+        self.credential_chain && self.access_key.is_none()
+    }
 
-        Ok(())
+    /// Profile the credential chain reads from `~/.aws/credentials`
+    pub fn aws_profile(&self) -> Option<String> {
+        self.aws_profile.clone().or_else(|| env::var("AWS_PROFILE").ok())
     }
 
     pub fn get_access_key(&self) -> Result<&str> {
@@ -125,12 +152,59 @@ impl MinioConfig {
             .ok_or_else(|| anyhow::anyhow!("Secret key not loaded"))
     }
 
-    #[allow(dead_code)]
     pub fn is_ssl(&self) -> bool {
         self.ssl
             .unwrap_or_else(|| self.endpoint.starts_with("https://"))
     }
 
+    /// The endpoint with a scheme matching `ssl`. Endpoints without a scheme
+    /// get `https://` or `http://` from the flag; an explicit scheme that
+    /// contradicts an explicit `ssl` is an error rather than a silent guess.
+    pub fn endpoint_url(&self) -> Result<String> {
+        let endpoint = self.endpoint.trim().trim_end_matches('/');
+        let scheme_ssl = match endpoint.split_once("://") {
+            Some(("https", _)) => true,
+            Some(("http", _)) => false,
+            Some((scheme, _)) => {
+                return Err(anyhow::anyhow!(
+                    "MinIO endpoint '{}' has unsupported scheme '{}', use http:// or https://",
+                    self.endpoint,
+                    scheme
+                ));
+            }
+            None => {
+                let scheme = if self.is_ssl() { "https" } else { "http" };
+                return Ok(format!("{}://{}", scheme, endpoint));
+            }
+        };
+
+        if let Some(ssl) = self.ssl
+            && ssl != scheme_ssl
+        {
+            return Err(anyhow::anyhow!(
+                "MinIO endpoint '{}' does not match ssl = {}; use {}:// or remove the ssl setting",
+                self.endpoint,
+                ssl,
+                if ssl { "https" } else { "http" }
+            ));
+        }
+        Ok(endpoint.to_string())
+    }
+
+    /// Whether the endpoint is Amazon S3 itself rather than MinIO or another
+    /// S3-compatible server
+    pub fn is_aws_endpoint(&self) -> bool {
+        let endpoint = self.endpoint.trim();
+        let host = endpoint
+            .split_once("://")
+            .map_or(endpoint, |(_, rest)| rest)
+            .split(['/', ':'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        host.ends_with(".amazonaws.com") || host.ends_with(".amazonaws.com.cn")
+    }
+
     pub fn is_path_style(&self) -> bool {
         self.path_style.unwrap_or(true)
     }
@@ -139,6 +213,16 @@ impl MinioConfig {
         self.region.as_deref().unwrap_or("us-east-1")
     }
 
+    /// Bucket for raw scraped/fetched JSON dumps
+    pub fn get_raw_bucket_name(&self) -> &str {
+        self.raw_bucket_name.as_deref().unwrap_or(&self.bucket_name)
+    }
+
+    /// Bucket for processed Parquet outputs
+    pub fn get_clean_bucket_name(&self) -> &str {
+        self.clean_bucket_name.as_deref().unwrap_or(&self.bucket_name)
+    }
+
     pub fn validate(&self) -> Result<()> {
         if self.endpoint.is_empty() {
             return Err(anyhow::anyhow!("MinIO endpoint cannot be empty"));
@@ -148,6 +232,20 @@ impl MinioConfig {
             return Err(anyhow::anyhow!("MinIO bucket name cannot be empty"));
         }
 
+        self.endpoint_url()?;
+
+        if self.get_raw_bucket_name().is_empty() {
+            return Err(anyhow::anyhow!("MinIO raw bucket name cannot be empty"));
+        }
+
+        if self.get_clean_bucket_name().is_empty() {
+            return Err(anyhow::anyhow!("MinIO clean bucket name cannot be empty"));
+        }
+
+        if self.uses_credential_chain() {
+            return Ok(());
+        }
+
         if self.access_key.is_none() {
             return Err(anyhow::anyhow!("MinIO access key not loaded"));
         }
@@ -165,13 +263,17 @@ impl Default for MinioConfig {
         Self {
             endpoint: "http://localhost:9000".to_string(),
             bucket_name: "data-pipeline".to_string(),
+            raw_bucket_name: None,
+            clean_bucket_name: None,
             region: Some("us-east-1".to_string()),
             path_style: Some(true),
-            ssl: Some(false),
+            ssl: None,
             access_key: None,
             secret_key: None,
             env_access_key: None,
             env_secret_key: None,
+            credential_chain: false,
+            aws_profile: None,
         }
     }
 }
@@ -201,6 +303,55 @@ mod tests {
         assert!(!config.is_ssl());
     }
 
+    #[test]
+    fn test_endpoint_scheme_follows_ssl() {
+        let mut config = MinioConfig::default();
+        assert_eq!(config.endpoint_url().unwrap(), "http://localhost:9000");
+
+        config.endpoint = "minio.example.com:9000/".to_string();
+        assert_eq!(config.endpoint_url().unwrap(), "http://minio.example.com:9000");
+        config.ssl = Some(true);
+        assert_eq!(config.endpoint_url().unwrap(), "https://minio.example.com:9000");
+
+        config.endpoint = "http://minio.example.com".to_string();
+        let err = config.endpoint_url().unwrap_err().to_string();
+        assert!(err.contains("does not match ssl = true"), "{}", err);
+
+        config.ssl = Some(false);
+        config.endpoint = "https://minio.example.com".to_string();
+        assert!(config.endpoint_url().is_err());
+
+        config.ssl = None;
+        assert_eq!(config.endpoint_url().unwrap(), "https://minio.example.com");
+        config.endpoint = "ftp://minio.example.com".to_string();
+        assert!(config.endpoint_url().unwrap_err().to_string().contains("unsupported scheme"));
+    }
+
+    #[test]
+    fn test_aws_endpoint_detection() {
+        let mut config = MinioConfig::default();
+        assert!(!config.is_aws_endpoint());
+
+        for endpoint in ["https://s3.amazonaws.com", "s3.eu-west-1.amazonaws.com", "https://S3.cn-north-1.amazonaws.com.cn/"] {
+            config.endpoint = endpoint.to_string();
+            assert!(config.is_aws_endpoint(), "{}", endpoint);
+        }
+        config.endpoint = "https://amazonaws.com.evil.example".to_string();
+        assert!(!config.is_aws_endpoint());
+    }
+
+    #[test]
+    fn test_tier_bucket_fallback() {
+        let mut config = MinioConfig::default();
+        assert_eq!(config.get_raw_bucket_name(), "data-pipeline");
+        assert_eq!(config.get_clean_bucket_name(), "data-pipeline");
+
+        config.raw_bucket_name = Some("pipeline-raw".to_string());
+        config.clean_bucket_name = Some("pipeline-clean".to_string());
+        assert_eq!(config.get_raw_bucket_name(), "pipeline-raw");
+        assert_eq!(config.get_clean_bucket_name(), "pipeline-clean");
+    }
+
     #[test]
     fn test_credentials_loading() {
         unsafe {
@@ -224,6 +375,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_credential_chain_fallback() {
+        let mut config = MinioConfig::default();
+        config.env_access_key = Some("TEST_CHAIN_ACCESS_KEY".to_string());
+        config.env_secret_key = Some("TEST_CHAIN_SECRET_KEY".to_string());
+
+        // Without the chain, missing variables are still an error
+        let err = config.load_credentials().unwrap_err().to_string();
+        assert!(err.contains("TEST_CHAIN_ACCESS_KEY"), "{}", err);
+        assert!(config.validate().is_err());
+
+        config.credential_chain = true;
+        config.load_credentials().unwrap();
+        assert!(config.uses_credential_chain());
+        assert!(config.validate().is_ok());
+
+        // Explicit environment variables take precedence over the chain
+        unsafe {
+            env::set_var("TEST_CHAIN_ACCESS_KEY", "chain_access");
+            env::set_var("TEST_CHAIN_SECRET_KEY", "chain_secret");
+        }
+        config.load_credentials().unwrap();
+        assert!(!config.uses_credential_chain());
+        assert_eq!(config.get_access_key().unwrap(), "chain_access");
+        assert_eq!(config.get_secret_key().unwrap(), "chain_secret");
+
+        unsafe {
+            env::remove_var("TEST_CHAIN_ACCESS_KEY");
+            env::remove_var("TEST_CHAIN_SECRET_KEY");
+        }
+    }
+
     #[tokio::test]
     #[ignore] // Run with --ignored flag for integration tests
     async fn test_minio_integration() {
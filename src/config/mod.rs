@@ -1,10 +1,34 @@
+pub mod anomaly_config;
 pub mod api_config;
+pub mod batch_config;
+pub mod category_filter;
+pub mod config_check;
+pub mod database_config;
+pub mod deadline_config;
 pub mod html_config;
+pub mod matcher_config;
 pub mod minio_config;
+pub mod normalizer_config;
+pub mod notify_config;
+pub mod output_config;
+pub mod rate_limit_config;
+pub mod sources_config;
 
-pub use api_config::ApiConfig;
+pub use anomaly_config::AnomalyConfig;
+pub use api_config::{ApiAuth, ApiConfig, AuthLocation};
+pub use batch_config::{BatchConfig, choose_batch_size};
+pub use category_filter::{CategoryFilter, parse_category_list};
+pub use config_check::check_configs;
+pub use database_config::DatabaseConfig;
+pub use deadline_config::DeadlineConfig;
 pub use html_config::HtmlConfig;
+pub use matcher_config::MatcherConfig;
 pub use minio_config::*;
+pub use normalizer_config::NormalizerConfig;
+pub use notify_config::NotifyConfig;
+pub use output_config::OutputConfig;
+pub use rate_limit_config::RateLimitConfig;
+pub use sources_config::{SourceSchedule, SourceType, SourcesConfig};
 
 // Re-export CategoryConfig with specific names to avoid ambiguity
 pub use html_config::CategoryConfig as HtmlCategoryConfig;
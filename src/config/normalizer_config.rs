@@ -0,0 +1,58 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::processor::rule_normalizer::{OutOfRangeDiscountAction, SwappedPriceAction};
+
+/// Name cleaning settings for `RuleNormalizer`, shared by all sources
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NormalizerConfig {
+    /// Display spelling of each brand, matched in names ignoring case
+    #[serde(default)]
+    pub known_brands: Vec<String>,
+    /// Parenthetical terms stripped from names, e.g. the "Aalu" in
+    /// "Potatoes (Aalu)", on top of any parenthetical without a quantity
+    #[serde(default)]
+    pub name_descriptors: Vec<String>,
+    /// Text columns whose placeholder values are stored as nulls; the
+    /// normalizer's default (`units_of_mass`) when unset
+    pub null_columns: Option<Vec<String>>,
+    /// Values treated as missing, ignoring case; "N/A", "" and "null" when unset
+    pub null_placeholders: Option<Vec<String>>,
+    /// Prices per 100 g / 100 ml / piece above this are dropped and flagged
+    pub max_price_per_unit: Option<f64>,
+    /// Markers stripped from price strings, e.g. "Rs." or "€"; the
+    /// normalizer's defaults when unset
+    pub currency_markers: Option<Vec<String>>,
+    /// Lowercase cleaned product names (default); `false` keeps the source's
+    /// casing. The uncleaned name is always kept in `name_original`.
+    pub lowercase_names: Option<bool>,
+    /// "swap" (default) or "flag" rows whose cost_price is above their mrp
+    pub swapped_prices: Option<SwappedPriceAction>,
+    /// "null" (default) or "clamp" discounts outside 0–100 that can't be
+    /// recomputed from the row's prices
+    pub out_of_range_discounts: Option<OutOfRangeDiscountAction>,
+}
+
+impl NormalizerConfig {
+    pub fn from_file(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read normalizer config file: {}", path))?;
+        toml::from_str(&content).with_context(|| format!("Failed to parse normalizer config file: {}", path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalizer_config() {
+        let config = NormalizerConfig::from_file("src/configs/normalizer.toml").unwrap();
+        assert!(config.known_brands.iter().any(|brand| brand == "Olper's"));
+        assert!(config.name_descriptors.iter().any(|term| term == "aalu"));
+        assert!(config.null_columns.unwrap().iter().any(|column| column == "units_of_mass"));
+        for entry in config.known_brands.iter().chain(&config.name_descriptors) {
+            assert!(!entry.trim().is_empty());
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Where the end-of-run summary is posted. The webhook URL usually embeds a
+/// secret, so the file is not checked in (see `notify.toml.example`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotifyConfig {
+    /// Slack-compatible incoming webhook; no notification is sent when unset
+    pub notify_webhook: Option<String>,
+}
+
+impl NotifyConfig {
+    pub fn from_file(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read notify config file: {}", path))?;
+        let mut config: NotifyConfig = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse notify config file: {}", path))?;
+
+        // An empty value disables notifications like a missing one
+        config.notify_webhook = config
+            .notify_webhook
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty());
+        if let Some(url) = &config.notify_webhook
+            && !(url.starts_with("https://") || url.starts_with("http://"))
+        {
+            return Err(anyhow!("notify_webhook in {} must be an http(s) URL, got '{}'", path, url));
+        }
+        Ok(config)
+    }
+
+    /// Like `from_file`, but a missing file leaves notifications off
+    pub fn from_optional_file(path: &str) -> Result<Self> {
+        if Path::new(path).exists() {
+            Self::from_file(path)
+        } else {
+            Ok(NotifyConfig::default())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notify_config_files() {
+        let dir = std::env::temp_dir().join(format!("notify_config_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let write = |name: &str, content: &str| {
+            let path = dir.join(name);
+            std::fs::write(&path, content).unwrap();
+            path.to_string_lossy().into_owned()
+        };
+
+        let missing = dir.join("missing.toml");
+        assert_eq!(NotifyConfig::from_optional_file(missing.to_str().unwrap()).unwrap().notify_webhook, None);
+        assert_eq!(NotifyConfig::from_file(&write("empty.toml", "notify_webhook = \" \"")).unwrap().notify_webhook, None);
+
+        let set = write("set.toml", "notify_webhook = \"https://hooks.slack.com/services/T0/B0/x\"");
+        assert_eq!(
+            NotifyConfig::from_optional_file(&set).unwrap().notify_webhook.as_deref(),
+            Some("https://hooks.slack.com/services/T0/B0/x")
+        );
+        assert!(NotifyConfig::from_file(&write("bad.toml", "notify_webhook = \"hooks.slack.com\"")).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
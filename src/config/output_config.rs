@@ -0,0 +1,79 @@
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+use crate::storage::{ArrowCompression, OutputFormat};
+
+/// Formats clean data is stored in besides Parquet, shared by every source
+/// and the merged dataset
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputConfig {
+    /// "parquet" (always written), "arrow", "csv" and "ndjson"
+    pub output_formats: Vec<OutputFormat>,
+    /// Field separator of CSV output, e.g. ";" for spreadsheets in locales
+    /// with decimal commas
+    pub csv_delimiter: char,
+    /// Compression of Arrow IPC output: "none" (default, memory-mappable),
+    /// "lz4" or "zstd"
+    pub arrow_compression: ArrowCompression,
+    /// Write null values as `null` in NDJSON output instead of leaving the
+    /// key out
+    pub ndjson_explicit_nulls: bool,
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        OutputConfig {
+            output_formats: vec![OutputFormat::Parquet],
+            csv_delimiter: ',',
+            arrow_compression: ArrowCompression::None,
+            ndjson_explicit_nulls: false,
+        }
+    }
+}
+
+impl OutputConfig {
+    pub fn from_file(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read output config file: {}", path))?;
+        let config: OutputConfig = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse output config file: {}", path))?;
+        config
+            .validate()
+            .with_context(|| format!("Invalid output config in {}", path))?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if !self.csv_delimiter.is_ascii() || matches!(self.csv_delimiter, '"' | '\n' | '\r') {
+            return Err(anyhow!("csv_delimiter must be a single ASCII character other than a quote or newline, got {:?}", self.csv_delimiter));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_config() {
+        let config = OutputConfig::from_file("src/configs/output.toml").unwrap();
+        assert!(config.output_formats.contains(&OutputFormat::Parquet));
+
+        let csv: OutputConfig = toml::from_str("output_formats = [\"parquet\", \"csv\"]\ncsv_delimiter = \";\"\n").unwrap();
+        assert_eq!(csv.output_formats, vec![OutputFormat::Parquet, OutputFormat::Csv]);
+        assert_eq!(csv.csv_delimiter, ';');
+        assert!(toml::from_str::<OutputConfig>("output_formats = [\"xlsx\"]\n").is_err());
+        let ndjson: OutputConfig = toml::from_str("output_formats = [\"jsonl\"]\nndjson_explicit_nulls = true\n").unwrap();
+        assert_eq!(ndjson.output_formats, vec![OutputFormat::Ndjson]);
+        assert!(ndjson.ndjson_explicit_nulls);
+        let arrow: OutputConfig = toml::from_str("output_formats = [\"arrow\"]\narrow_compression = \"zstd\"\n").unwrap();
+        assert_eq!(arrow.arrow_compression, ArrowCompression::Zstd);
+        assert!(toml::from_str::<OutputConfig>("arrow_compression = \"snappy\"\n").is_err());
+
+        let quote: OutputConfig = toml::from_str("csv_delimiter = '\"'\n").unwrap();
+        assert!(quote.validate().is_err());
+        assert_eq!(toml::from_str::<OutputConfig>("").unwrap(), OutputConfig::default());
+    }
+}
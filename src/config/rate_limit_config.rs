@@ -0,0 +1,81 @@
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Outbound request rates, per host. Every fetcher sharing a limiter stays
+/// under these together, however many requests they have in flight.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    /// Requests per second to any host without its own entry
+    pub requests_per_second: f64,
+    /// Requests per second by host name, e.g. `"www.naheed.pk" = 0.5`
+    pub hosts: HashMap<String, f64>,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        RateLimitConfig {
+            requests_per_second: 2.0,
+            hosts: HashMap::new(),
+        }
+    }
+}
+
+impl RateLimitConfig {
+    pub fn from_file(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read rate limit config file: {}", path))?;
+        let mut config: RateLimitConfig = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse rate limit config file: {}", path))?;
+        // Request hosts are compared lowercased
+        config.hosts = config
+            .hosts
+            .into_iter()
+            .map(|(host, rate)| (host.to_lowercase(), rate))
+            .collect();
+        config
+            .validate()
+            .with_context(|| format!("Invalid rate limit config in {}", path))?;
+        Ok(config)
+    }
+
+    /// Rates must be positive and finite
+    pub fn validate(&self) -> Result<()> {
+        let rates = std::iter::once(("requests_per_second", self.requests_per_second))
+            .chain(self.hosts.iter().map(|(host, rate)| (host.as_str(), *rate)));
+        for (name, rate) in rates {
+            if !rate.is_finite() || rate <= 0.0 {
+                return Err(anyhow!("Rate for {} must be greater than 0, got {}", name, rate));
+            }
+        }
+        Ok(())
+    }
+
+    /// Requests per second allowed to `host`
+    pub fn rate_for(&self, host: &str) -> f64 {
+        self.hosts
+            .get(host)
+            .copied()
+            .unwrap_or(self.requests_per_second)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limit_config() {
+        let config: RateLimitConfig = toml::from_str(
+            "requests_per_second = 4.0\n[hosts]\n\"www.naheed.pk\" = 0.5\n",
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.rate_for("www.naheed.pk"), 0.5);
+        assert_eq!(config.rate_for("api.krave.pk"), 4.0);
+
+        let zero: RateLimitConfig = toml::from_str("[hosts]\n\"www.naheed.pk\" = 0\n").unwrap();
+        assert!(zero.validate().is_err());
+    }
+}
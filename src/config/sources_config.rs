@@ -0,0 +1,283 @@
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// What a source's `sources.toml` entry should look like, shown when the
+/// file is missing or cannot be parsed
+const EXPECTED_FORMAT: &str = "\
+Expected one [[sources]] entry per source, e.g.
+
+[[sources]]
+name = \"krave_mart\"
+type = \"json\"                  # \"json\" for APIs, \"html\" for scraped pages
+config_path = \"krave_mart.toml\" # relative to the sources file
+enabled = true                 # optional, defaults to true
+interval_minutes = 360         # optional, how often `serve` runs it
+# cron = \"0 */6 * * *\"         # or when, in UTC";
+
+/// How a source is fetched
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceType {
+    /// A JSON API, configured by an `ApiConfig`
+    Json,
+    /// Scraped HTML pages, configured by an `HtmlConfig`
+    Html,
+}
+
+impl SourceType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SourceType::Json => "json",
+            SourceType::Html => "html",
+        }
+    }
+}
+
+/// One source of `sources.toml`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceEntry {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub source_type: SourceType,
+    /// The source's `ApiConfig` or `HtmlConfig`, relative to the sources file
+    /// unless absolute
+    pub config_path: String,
+    /// Disabled sources only run when picked with `--source`
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// How often `serve` runs the source, counted from its previous start
+    #[serde(default)]
+    pub interval_minutes: Option<u64>,
+    /// When `serve` runs the source, as a five-field cron expression in UTC,
+    /// e.g. "30 2 * * *" for 02:30 every day
+    #[serde(default)]
+    pub cron: Option<String>,
+}
+
+impl SourceEntry {
+    /// When `serve` runs the source; `None` when it has no schedule
+    pub fn schedule(&self) -> Result<Option<SourceSchedule>> {
+        match (self.interval_minutes, &self.cron) {
+            (Some(_), Some(_)) => Err(anyhow!("Source {} sets both interval_minutes and cron, keep one", self.name)),
+            (Some(0), None) => Err(anyhow!("interval_minutes of {} must be greater than 0", self.name)),
+            (Some(minutes), None) => Ok(Some(SourceSchedule::Every(Duration::from_secs(minutes * 60)))),
+            (None, Some(expression)) => expression
+                .parse()
+                .map(Some)
+                .with_context(|| format!("Invalid cron of {}", self.name)),
+            (None, None) => Ok(None),
+        }
+    }
+}
+
+/// When `serve` runs a source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceSchedule {
+    /// Every interval, starting as soon as `serve` does
+    Every(Duration),
+    Cron(Box<cron::Schedule>),
+}
+
+impl SourceSchedule {
+    /// When to run next, given the slot of the previous run if there was
+    /// one. Slots missed while a run was late are skipped rather than
+    /// caught up on.
+    pub fn next_run(&self, previous: Option<DateTime<Utc>>, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            SourceSchedule::Every(interval) => {
+                let Some(previous) = previous else {
+                    return Some(now);
+                };
+                let next = previous + chrono::Duration::from_std(*interval).ok()?;
+                Some(next.max(now))
+            }
+            SourceSchedule::Cron(schedule) => schedule.after(&previous.map_or(now, |previous| previous.max(now))).next(),
+        }
+    }
+}
+
+impl FromStr for SourceSchedule {
+    type Err = anyhow::Error;
+
+    /// A five-field cron expression (minute hour day month weekday)
+    fn from_str(expression: &str) -> Result<Self> {
+        let fields = expression.split_whitespace().count();
+        if fields != 5 {
+            return Err(anyhow!("expected 5 fields (minute hour day month weekday), got {} in '{}'", fields, expression));
+        }
+        // The cron crate counts seconds too
+        let schedule = cron::Schedule::from_str(&format!("0 {}", expression)).map_err(|e| anyhow!("'{}': {}", expression, e))?;
+        Ok(SourceSchedule::Cron(Box::new(schedule)))
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Every source the pipeline can run, so adding a store only takes a config
+/// file and an entry here
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourcesConfig {
+    /// Random delay of up to this many seconds added to each scheduled run,
+    /// so sources sharing a schedule do not all start at once
+    #[serde(default = "default_jitter_seconds")]
+    pub jitter_seconds: u64,
+    pub sources: Vec<SourceEntry>,
+}
+
+fn default_jitter_seconds() -> u64 {
+    60
+}
+
+impl SourcesConfig {
+    /// Load `path`, resolving each `config_path` against its directory
+    pub fn from_file(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read sources file: {}\n\n{}", path, EXPECTED_FORMAT))?;
+        let mut config: SourcesConfig = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse sources file: {}\n\n{}", path, EXPECTED_FORMAT))?;
+        config
+            .validate()
+            .with_context(|| format!("Invalid sources in {}", path))?;
+
+        let dir = Path::new(path).parent().unwrap_or(Path::new(""));
+        for source in &mut config.sources {
+            source.config_path = dir.join(&source.config_path).to_string_lossy().into_owned();
+        }
+        Ok(config)
+    }
+
+    /// Source names must be set and unique, and schedules valid
+    pub fn validate(&self) -> Result<()> {
+        if self.sources.is_empty() {
+            return Err(anyhow!("No sources are listed"));
+        }
+        let mut names = HashSet::new();
+        for source in &self.sources {
+            if source.name.trim().is_empty() {
+                return Err(anyhow!("Every source needs a name"));
+            }
+            if !names.insert(source.name.as_str()) {
+                return Err(anyhow!("Source {} is listed more than once", source.name));
+            }
+            source.schedule()?;
+        }
+        Ok(())
+    }
+
+    /// The sources to run: the one named, enabled or not, or every enabled one
+    pub fn select(&self, name: Option<&str>) -> Result<Vec<&SourceEntry>> {
+        let Some(name) = name else {
+            return Ok(self.sources.iter().filter(|source| source.enabled).collect());
+        };
+        match self.sources.iter().find(|source| source.name == name) {
+            Some(source) => Ok(vec![source]),
+            None => Err(anyhow!(
+                "Unknown source '{}', expected one of: {}",
+                name,
+                self.names().join(", ")
+            )),
+        }
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.sources.iter().map(|source| source.name.as_str()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_sources_file() {
+        let config = SourcesConfig::from_file("src/configs/sources.toml").unwrap();
+        assert_eq!(config.names(), vec!["krave_mart", "bazaar_app", "dealcart", "pandamart", "naheed"]);
+        assert_eq!(config.select(None).unwrap().len(), 5);
+
+        let naheed = config.select(Some("naheed")).unwrap()[0];
+        assert_eq!(naheed.source_type, SourceType::Html);
+        assert_eq!(naheed.config_path, "src/configs/naheed.toml");
+        assert!(Path::new(&naheed.config_path).exists());
+    }
+
+    #[test]
+    fn test_select_sources() {
+        let dir = std::env::temp_dir().join(format!("sources_config_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("sources.toml");
+        std::fs::write(
+            &path,
+            r#"
+            [[sources]]
+            name = "krave_mart"
+            type = "json"
+            config_path = "krave_mart.toml"
+
+            [[sources]]
+            name = "naheed"
+            type = "html"
+            config_path = "/etc/pipeline/naheed.toml"
+            enabled = false
+            "#,
+        )
+        .unwrap();
+
+        let config = SourcesConfig::from_file(path.to_str().unwrap()).unwrap();
+        let enabled = config.select(None).unwrap();
+        assert_eq!(enabled.len(), 1);
+        assert_eq!(enabled[0].config_path, dir.join("krave_mart.toml").to_string_lossy());
+
+        // A disabled source still runs when asked for by name
+        let naheed = config.select(Some("naheed")).unwrap();
+        assert_eq!(naheed[0].config_path, "/etc/pipeline/naheed.toml");
+        let unknown = config.select(Some("dealcart")).unwrap_err().to_string();
+        assert!(unknown.contains("expected one of: krave_mart, naheed"));
+
+        std::fs::write(&path, "[[sources]]\nname = \"naheed\"\ntype = \"xml\"\nconfig_path = \"naheed.toml\"").unwrap();
+        let invalid = format!("{:#}", SourcesConfig::from_file(path.to_str().unwrap()).unwrap_err());
+        assert!(invalid.contains("[[sources]]"));
+        let missing = format!("{:#}", SourcesConfig::from_file(dir.join("missing.toml").to_str().unwrap()).unwrap_err());
+        assert!(missing.contains("config_path = \"krave_mart.toml\""));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_schedules() {
+        let config = SourcesConfig::from_file("src/configs/sources.toml").unwrap();
+        let at = |time: &str| DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&Utc);
+        let now = at("2025-09-15T10:00:30Z");
+
+        // An interval runs straight away, then counts from the previous slot
+        let krave_mart = config.sources[0].schedule().unwrap().unwrap();
+        assert_eq!(krave_mart.next_run(None, now), Some(now));
+        assert_eq!(krave_mart.next_run(Some(at("2025-09-15T09:00:00Z")), now), Some(at("2025-09-15T15:00:00Z")));
+        // A slot missed while the daemon was busy is not caught up on
+        assert_eq!(krave_mart.next_run(Some(at("2025-09-15T01:00:00Z")), now), Some(now));
+
+        let naheed = config.select(Some("naheed")).unwrap()[0].schedule().unwrap().unwrap();
+        assert_eq!(naheed.next_run(None, now), Some(at("2025-09-16T02:00:00Z")));
+        assert_eq!(naheed.next_run(Some(at("2025-09-16T02:00:00Z")), at("2025-09-16T01:59:59Z")), Some(at("2025-09-17T02:00:00Z")));
+
+        let every_15 = "*/15 * * * *".parse::<SourceSchedule>().unwrap();
+        assert_eq!(every_15.next_run(None, now), Some(at("2025-09-15T10:15:00Z")));
+        assert!("0 */6 * * * *".parse::<SourceSchedule>().unwrap_err().to_string().contains("expected 5 fields"));
+        assert!("61 * * * *".parse::<SourceSchedule>().is_err());
+
+        let mut entry = config.sources[0].clone();
+        entry.cron = Some("0 * * * *".to_string());
+        assert!(entry.schedule().unwrap_err().to_string().contains("both interval_minutes and cron"));
+        entry.cron = None;
+        entry.interval_minutes = Some(0);
+        assert!(entry.schedule().is_err());
+        entry.interval_minutes = None;
+        assert_eq!(entry.schedule().unwrap(), None);
+    }
+}
@@ -0,0 +1,234 @@
+use anyhow::{Context, Result, anyhow};
+use serde_json::Value;
+use std::fmt;
+use tracing::debug;
+use wreq::{Client, Response};
+use wreq_util::Emulation;
+
+/// Browser fingerprint used when a source does not configure one
+pub const DEFAULT_EMULATION: Emulation = Emulation::Firefox136;
+
+/// Largest response body read when a source does not set `max_response_bytes`
+pub const DEFAULT_MAX_RESPONSE_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Emulation profiles selectable from config, by name
+const EMULATIONS: [(&str, Emulation); 16] = [
+    ("chrome120", Emulation::Chrome120),
+    ("chrome124", Emulation::Chrome124),
+    ("chrome128", Emulation::Chrome128),
+    ("chrome131", Emulation::Chrome131),
+    ("chrome133", Emulation::Chrome133),
+    ("chrome137", Emulation::Chrome137),
+    ("edge134", Emulation::Edge134),
+    ("firefox128", Emulation::Firefox128),
+    ("firefox133", Emulation::Firefox133),
+    ("firefox135", Emulation::Firefox135),
+    ("firefox136", Emulation::Firefox136),
+    ("firefox139", Emulation::Firefox139),
+    ("safari18", Emulation::Safari18),
+    ("safariios18.1.1", Emulation::SafariIos18_1_1),
+    ("okhttp5", Emulation::OkHttp5),
+    ("okhttp", Emulation::OkHttp5),
+];
+
+/// Resolve an emulation name such as `chrome120`, `firefox_136` or
+/// `Safari-18`. Case, spaces, `_` and `-` are ignored.
+pub fn parse_emulation(name: &str) -> Result<Emulation> {
+    let normalized: String = name
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter(|c| *c != '_' && *c != '-' && *c != ' ')
+        .collect();
+
+    EMULATIONS
+        .iter()
+        .find(|(key, _)| *key == normalized)
+        .map(|(_, emulation)| *emulation)
+        .ok_or_else(|| {
+            let supported: Vec<&str> = EMULATIONS.iter().map(|(key, _)| *key).collect();
+            anyhow!(
+                "Unknown emulation '{}'. Supported: {}",
+                name,
+                supported.join(", ")
+            )
+        })
+}
+
+/// HTTP client for a source: the configured emulation (or `DEFAULT_EMULATION`)
+/// with an optional explicit User-Agent overriding the emulated one.
+/// gzip, brotli and deflate bodies are decoded by the client.
+pub fn build_client(emulation: Option<&str>, user_agent: Option<&str>) -> Result<Client> {
+    let emulation = match emulation {
+        Some(name) => parse_emulation(name)?,
+        None => DEFAULT_EMULATION,
+    };
+
+    let mut builder = Client::builder()
+        .emulation(emulation)
+        .gzip(true)
+        .brotli(true)
+        .deflate(true);
+    if let Some(user_agent) = user_agent.map(str::trim).filter(|ua| !ua.is_empty()) {
+        builder = builder.user_agent(user_agent);
+    }
+
+    Ok(builder.build()?)
+}
+
+/// Turn a response body into text, failing with a clear error when it was
+/// not decompressed instead of passing garbage on to the HTML checks
+pub fn decode_body(body: &[u8], content_encoding: Option<&str>) -> Result<String> {
+    let encoding = content_encoding.unwrap_or("identity");
+    if looks_compressed(body) {
+        return Err(anyhow!(
+            "Response body is still compressed (Content-Encoding: {}, {} bytes)",
+            encoding,
+            body.len()
+        ));
+    }
+
+    let text = String::from_utf8_lossy(body).into_owned();
+    debug!(
+        "Decoded {} byte body (Content-Encoding: {}) to {} characters",
+        body.len(),
+        encoding,
+        text.chars().count()
+    );
+    Ok(text)
+}
+
+/// A response body larger than the source's `max_response_bytes`. The same
+/// request would return the same body, so this is not retried.
+#[derive(Debug)]
+pub struct ResponseTooLarge {
+    pub url: String,
+    pub max_bytes: u64,
+    /// The announced `Content-Length`, or the bytes read before giving up
+    pub bytes: u64,
+}
+
+impl fmt::Display for ResponseTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Response from {} is over max_response_bytes ({} > {} bytes)", self.url, self.bytes, self.max_bytes)
+    }
+}
+
+impl std::error::Error for ResponseTooLarge {}
+
+/// Whether `error` is, or was caused by, a `ResponseTooLarge`
+pub fn is_response_too_large(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| cause.is::<ResponseTooLarge>())
+}
+
+/// Read a response body of at most `max_bytes`, decompressed. A larger
+/// `Content-Length` fails before anything is read, and a body without one
+/// fails as soon as it grows past the cap, so it is never held in full.
+pub async fn read_body(mut response: Response, max_bytes: u64) -> Result<Vec<u8>> {
+    let url = response.url().to_string();
+    let mut body = BodyLimit::new(url, max_bytes);
+    body.check_length(response.content_length())?;
+    while let Some(chunk) = response.chunk().await.map_err(|e| anyhow!("Failed to read response body: {}", e))? {
+        body.push(&chunk)?;
+    }
+    Ok(body.bytes)
+}
+
+/// `read_body` parsed as JSON
+pub async fn read_json(response: Response, max_bytes: u64) -> Result<Value> {
+    let url = response.url().to_string();
+    let body = read_body(response, max_bytes).await?;
+    serde_json::from_slice(&body).with_context(|| format!("Invalid JSON in response from {}", url))
+}
+
+/// A body being read up to a size cap
+struct BodyLimit {
+    url: String,
+    max_bytes: u64,
+    bytes: Vec<u8>,
+}
+
+impl BodyLimit {
+    fn new(url: String, max_bytes: u64) -> Self {
+        BodyLimit { url, max_bytes, bytes: Vec::new() }
+    }
+
+    fn too_large(&self, bytes: u64) -> anyhow::Error {
+        ResponseTooLarge { url: self.url.clone(), max_bytes: self.max_bytes, bytes }.into()
+    }
+
+    fn check_length(&self, content_length: Option<u64>) -> Result<()> {
+        match content_length {
+            Some(length) if length > self.max_bytes => Err(self.too_large(length)),
+            _ => Ok(()),
+        }
+    }
+
+    fn push(&mut self, chunk: &[u8]) -> Result<()> {
+        let bytes = (self.bytes.len() + chunk.len()) as u64;
+        if bytes > self.max_bytes {
+            return Err(self.too_large(bytes));
+        }
+        self.bytes.extend_from_slice(chunk);
+        Ok(())
+    }
+}
+
+/// gzip or zlib magic bytes at the start of a body. Brotli has no magic
+/// number; undecoded brotli still fails the HTML validation.
+fn looks_compressed(body: &[u8]) -> bool {
+    matches!(body, [0x1f, 0x8b, ..] | [0x78, 0x01 | 0x5e | 0x9c | 0xda, ..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_emulation_names() {
+        assert_eq!(parse_emulation("chrome120").unwrap(), Emulation::Chrome120);
+        assert_eq!(parse_emulation("Firefox_136").unwrap(), Emulation::Firefox136);
+        assert_eq!(parse_emulation("safari-ios-18.1.1").unwrap(), Emulation::SafariIos18_1_1);
+
+        let err = parse_emulation("netscape4").unwrap_err().to_string();
+        assert!(err.contains("netscape4"));
+        assert!(err.contains("firefox136"));
+    }
+
+    #[test]
+    fn test_decode_body() {
+        let html = "<html><body>Naheed</body></html>";
+        assert_eq!(decode_body(html.as_bytes(), Some("gzip")).unwrap(), html);
+        assert_eq!(decode_body("<div>\u{20a8}</div>".as_bytes(), None).unwrap().chars().count(), 12);
+
+        // gzip of "<html></html>", as sent when decompression is off
+        let gzipped = [
+            0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xb3, 0xc9, 0x28, 0xc9,
+            0xcd, 0xb1, 0xb3, 0xd1, 0x07, 0x53, 0x00, 0x1f, 0x87, 0x1b, 0x60, 0x0d, 0x00, 0x00,
+            0x00,
+        ];
+        let err = decode_body(&gzipped, Some("gzip")).unwrap_err().to_string();
+        assert!(err.contains("still compressed"));
+        assert!(err.contains("gzip"));
+    }
+
+    #[test]
+    fn test_body_limit() {
+        let mut body = BodyLimit::new("https://api.example.com/products".to_string(), 10);
+        assert!(body.check_length(None).is_ok());
+        assert!(body.check_length(Some(10)).is_ok());
+        let err = body.check_length(Some(11)).unwrap_err();
+        assert!(is_response_too_large(&err));
+        assert!(err.to_string().contains("(11 > 10 bytes)"));
+
+        body.push(b"{\"a\":").unwrap();
+        body.push(b"[1]}").unwrap();
+        assert_eq!(body.bytes, b"{\"a\":[1]}");
+        body.push(b"\n").unwrap();
+        let err = body.push(b"\n").unwrap_err().context("Failed to fetch page 3");
+        assert!(is_response_too_large(&err));
+        assert_eq!(body.bytes.len(), 10);
+
+        assert!(!is_response_too_large(&anyhow!("HTTP error: 502 Bad Gateway")));
+    }
+}
@@ -0,0 +1,193 @@
+use anyhow::{Result, anyhow};
+use serde_json::Value;
+use tracing::warn;
+
+use crate::config::api_config::DataPath;
+
+/// Products in an API response: those at every path of `data_path`, in
+/// order, or without one the first common response layout that matches.
+/// `UnifiedFetcher` extracts every page with this, so tools reading saved
+/// responses see the products the pipeline would.
+pub fn extract_products(data: &Value, data_path: Option<&DataPath>) -> Vec<Value> {
+    match data_path {
+        Some(data_path) => data_path.paths().iter().flat_map(|path| extract_by_path(data, path)).collect(),
+        None => extract_by_common_patterns(data),
+    }
+}
+
+/// Products at one `response.data_path`, e.g. "data[].l2_products[]": dotted
+/// keys, where `key[]` goes through every element of an array and `*` through
+/// every value of an object. The values the path ends at are flattened when
+/// they are arrays.
+pub fn extract_by_path(data: &Value, path: &str) -> Vec<Value> {
+    let mut current = vec![data];
+
+    for part in path.split('.') {
+        current = if let Some(field) = part.strip_suffix("[]") {
+            // Array access: continue with every element, so nested arrays
+            // like "data[].l2_products[]" are flattened
+            current
+                .into_iter()
+                .flat_map(|value| path_children(value, field))
+                .filter_map(|value| value.as_array())
+                .flatten()
+                .collect()
+        } else {
+            // Object access
+            current.into_iter().flat_map(|value| path_children(value, part)).collect()
+        };
+    }
+
+    if path.ends_with("[]") {
+        return current.into_iter().cloned().collect();
+    }
+    current
+        .into_iter()
+        .filter_map(|value| value.as_array())
+        .flatten()
+        .cloned()
+        .collect()
+}
+
+/// Products of a response without a configured `data_path`: a top-level
+/// array, a `products` or `items` field, or Pandamart's GraphQL sections
+pub fn extract_by_common_patterns(data: &Value) -> Vec<Value> {
+    // Pattern 1: Direct array (BazaarApp style)
+    if let Some(products_array) = data.as_array() {
+        return products_array.clone();
+    }
+
+    // Pattern 2: Simple products field
+    if let Some(products) = data.get("products").and_then(|p| p.as_array()) {
+        return products.clone();
+    }
+
+    // Pattern 3: Items field
+    if let Some(items) = data.get("items").and_then(|i| i.as_array()) {
+        return items.clone();
+    }
+
+    // Pattern 4: Pandamart GraphQL style - data.categoryProductList.categoryProducts[].items[]
+    if let Some(category_products) = data
+        .get("data")
+        .and_then(|d| d.get("categoryProductList"))
+        .and_then(|cpl| cpl.get("categoryProducts"))
+        .and_then(|cp| cp.as_array())
+    {
+        let mut all_products = Vec::new();
+        for category in category_products {
+            if let Some(items) = category.get("items").and_then(|i| i.as_array()) {
+                // Add category name to each product for better tracking
+                let category_name = category
+                    .get("name")
+                    .and_then(|n| n.as_str())
+                    .unwrap_or("Unknown");
+                for mut item in items.clone() {
+                    if let Some(item_obj) = item.as_object_mut() {
+                        item_obj.insert(
+                            "category_section".to_string(),
+                            serde_json::Value::String(category_name.to_string()),
+                        );
+                    }
+                    all_products.push(item);
+                }
+            }
+        }
+        return all_products;
+    }
+
+    // If no pattern matches, return empty
+    warn!("No products found in response structure");
+    Vec::new()
+}
+
+/// Check the syntax of one `response.data_path`, see `extract_by_path`
+pub fn check_data_path(path: &str) -> Result<()> {
+    for part in path.split('.') {
+        let key = part.strip_suffix("[]").unwrap_or(part);
+        if key.is_empty() {
+            return Err(anyhow!("empty segment in '{}'", path));
+        }
+        if key.contains(['[', ']']) {
+            return Err(anyhow!("'{}' in '{}': only a trailing [] is supported", part, path));
+        }
+    }
+    Ok(())
+}
+
+/// `value[key]`, or for the `*` wildcard every value of an object in response
+/// order, e.g. "data.*.products[]" for products grouped under category IDs
+fn path_children<'a>(value: &'a Value, key: &str) -> Vec<&'a Value> {
+    match (key, value) {
+        ("*", Value::Object(object)) => object.values().collect(),
+        ("*", Value::Array(items)) => items.iter().collect(),
+        _ => value.get(key).into_iter().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn skus(products: Vec<Value>) -> Vec<String> {
+        products.iter().map(|product| product["sku"].as_str().unwrap().to_string()).collect()
+    }
+
+    #[test]
+    fn test_products_of_every_data_path_are_concatenated() {
+        let response = json!({
+            "data": [
+                {"l2_products": [{"sku": "A"}, {"sku": "B"}]},
+                {"krave_mart_products": [{"sku": "C"}]}
+            ],
+            "featured": [{"sku": "D"}]
+        });
+        let extract = |data_path: DataPath| skus(extract_products(&response, Some(&data_path)));
+
+        let krave_mart = DataPath::Many(vec!["data[].l2_products[]".to_string(), "data[].krave_mart_products[]".to_string()]);
+        assert_eq!(extract(krave_mart), vec!["A", "B", "C"]);
+        assert_eq!(extract(DataPath::One("data[].krave_mart_products[]".to_string())), vec!["C"]);
+        assert_eq!(extract(DataPath::Many(vec!["featured[]".to_string(), "data[].l2_products[]".to_string()])), vec!["D", "A", "B"]);
+    }
+
+    #[test]
+    fn test_wildcard_segments_iterate_object_values() {
+        let response = json!({
+            "data": {
+                "2417": {"products": [{"sku": "A"}, {"sku": "B"}]},
+                "2738": {"products": []},
+                "4355": {"products": [{"sku": "C"}], "banner": {}},
+                "meta": "not a category"
+            },
+            "sections": [
+                {"by_id": {"1": [{"sku": "D"}], "2": [{"sku": "E"}]}}
+            ]
+        });
+        let extract = |path: &str| skus(extract_by_path(&response, path));
+
+        assert_eq!(extract("data.*.products[]"), vec!["A", "B", "C"]);
+        assert_eq!(extract("data.*.products"), vec!["A", "B", "C"]);
+        // Composes with array access, before and after the wildcard
+        assert_eq!(extract("sections[].by_id.*[]"), vec!["D", "E"]);
+        assert_eq!(extract("sections[].by_id.*"), vec!["D", "E"]);
+        assert!(extract("data.*.missing[]").is_empty());
+    }
+
+    #[test]
+    fn test_common_patterns_without_a_data_path() {
+        let pandamart = json!({"data": {"categoryProductList": {"categoryProducts": [
+            {"name": "Fruits", "items": [{"sku": "A"}]},
+            {"name": "Dairy", "items": [{"sku": "B"}]}
+        ]}}});
+        let products = extract_products(&pandamart, None);
+        assert_eq!(skus(products.clone()), vec!["A", "B"]);
+        assert_eq!(products[1]["category_section"], "Dairy");
+
+        assert_eq!(skus(extract_products(&json!({"items": [{"sku": "C"}]}), None)), vec!["C"]);
+        assert!(extract_products(&json!({"unexpected": true}), None).is_empty());
+
+        assert!(check_data_path("data[].l2_products[]").is_ok());
+        assert!(check_data_path("data.[0]").is_err());
+    }
+}
@@ -0,0 +1,990 @@
+use anyhow::{Result, anyhow};
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use smartcore::ensemble::random_forest_classifier::{
+    RandomForestClassifier, RandomForestClassifierParameters,
+};
+use smartcore::linalg::basic::matrix::DenseMatrix;
+use std::collections::{HashMap, HashSet};
+use tracing::{debug, info, warn};
+
+use crate::config::html_config::SelectorConfig;
+
+/// Shared HTML product extraction used by `HtmlFetcher` and the standalone
+/// scraping binaries: rule-based extraction with an optional ML fallback
+pub struct ProductExtractor {
+    selectors: SelectorConfig,
+    price_patterns: Vec<Regex>,
+    exclusion_detector: ExclusionDetector,
+    ml_model: Option<ProductMLModel>,
+}
+
+/// ML model for product extraction
+pub struct ProductMLModel {
+    pub classifier: RandomForestClassifier<f32, i32, DenseMatrix<f32>, Vec<i32>>,
+    pub feature_extractor: FeatureExtractor,
+    pub confidence_threshold: f32,
+}
+
+/// Feature extractor for ML model
+#[derive(Clone)]
+pub struct FeatureExtractor {
+    pub price_patterns: Vec<Regex>,
+    pub name_patterns: Vec<Regex>,
+    /// Site-specific keywords, each adding one presence feature
+    pub keyword_features: Vec<String>,
+}
+
+/// Exclusion detector for filtering out non-product content
+pub struct ExclusionDetector {
+    pub excluded_sections: Vec<String>,
+    pub excluded_keywords: HashSet<String>,
+}
+
+/// Product candidate for ML classification
+#[derive(Debug, Clone)]
+pub struct ProductCandidate {
+    pub element_html: String,
+    pub text_content: String,
+    pub tag_name: String,
+    pub classes: Vec<String>,
+    pub attributes: HashMap<String, String>,
+    pub depth: usize,
+    pub parent_context: String,
+    pub has_price_text: bool,
+    pub has_link: bool,
+}
+
+/// Labelled HTML fragment used to train the ML fallback
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrainingExample {
+    pub html_fragment: String,
+    pub is_product: bool,
+    pub product_name: Option<String>,
+    pub product_price: Option<String>,
+    pub section_context: String,
+}
+
+/// Represents a scraped product from HTML
+#[derive(Debug, Clone)]
+pub struct ScrapedProduct {
+    pub name: String,
+    pub price: String,
+    pub product_id: String,
+    pub category: String,
+    pub url: Option<String>,
+    pub raw_html: String,
+}
+
+/// Price formats seen on Pakistani grocery sites (Rs. 140, PKR 140, ₨140, 140 Rs)
+pub fn default_price_patterns() -> Vec<Regex> {
+    vec![
+        Regex::new(r"Rs\.?\s*[\d,]+").unwrap(),
+        Regex::new(r"PKR\.?\s*[\d,]+").unwrap(),
+        Regex::new(r"₨\.?\s*[\d,]+").unwrap(),
+        Regex::new(r"\d+\s*Rs").unwrap(),
+    ]
+}
+
+impl ProductExtractor {
+    pub fn new(selectors: SelectorConfig) -> Self {
+        Self {
+            selectors,
+            price_patterns: default_price_patterns(),
+            exclusion_detector: ExclusionDetector::new_default(),
+            ml_model: None,
+        }
+    }
+
+    /// Use an ML model as fallback when the rule-based selectors find nothing
+    pub fn with_ml_model(mut self, model: ProductMLModel) -> Self {
+        self.ml_model = Some(model);
+        self
+    }
+
+    /// Extract products from HTML using configured selectors with ML fallback
+    pub fn extract_products(
+        &self,
+        html: &str,
+        category_name: &str,
+        source_url: Option<String>,
+    ) -> Result<Vec<ScrapedProduct>> {
+        // Primary: Use rule-based extraction
+        match self.extract_with_rules(html, category_name, source_url.clone()) {
+            Ok(products) if !products.is_empty() => {
+                info!("Rule-based extraction found {} products", products.len());
+                return Ok(products);
+            }
+            Ok(_) => info!("Rule-based extraction found no products, trying ML..."),
+            Err(e) => warn!("Rule-based extraction failed: {:?}, trying ML...", e),
+        }
+
+        // Secondary: Use ML-based extraction if available
+        if let Some(ref ml_model) = self.ml_model {
+            match self.extract_with_ml(html, category_name, source_url, ml_model) {
+                Ok(products) if !products.is_empty() => {
+                    info!("ML-based extraction found {} products", products.len());
+                    return Ok(products);
+                }
+                Ok(_) => info!("ML-based extraction found no products"),
+                Err(e) => warn!("ML-based extraction failed: {:?}", e),
+            }
+        }
+
+        // If both methods fail, return empty result
+        info!("No products found using available methods");
+        Ok(vec![])
+    }
+
+    /// Rule-based product extraction
+    fn extract_with_rules(
+        &self,
+        html: &str,
+        category_name: &str,
+        source_url: Option<String>,
+    ) -> Result<Vec<ScrapedProduct>> {
+        let document = Html::parse_document(html);
+
+        // Extract category from page if configured
+        let page_category = self.extract_category_from_page(&document)
+            .unwrap_or_else(|| category_name.to_string());
+
+        // Score every product selector by how many of its elements yield a
+        // valid product, so an early selector matching the wrong container
+        // (e.g. a promo grid) cannot starve extraction. Ties go to the
+        // selector configured first.
+        let mut best: Option<(&str, Vec<ScrapedProduct>)> = None;
+        for selector_str in &self.selectors.product_selectors {
+            let Ok(selector) = Selector::parse(selector_str) else {
+                warn!("Invalid product selector '{}'", selector_str);
+                continue;
+            };
+            let elements: Vec<_> = document.select(&selector).collect();
+            if elements.is_empty() {
+                continue;
+            }
+
+            let candidates = elements.len();
+            let selector_products: Vec<ScrapedProduct> = elements
+                .into_iter()
+                .filter_map(|element| self.extract_single_product(element, &page_category, source_url.clone()))
+                .collect();
+            info!(
+                "Selector '{}' yielded {} products from {} elements ({:.0}%)",
+                selector_str,
+                selector_products.len(),
+                candidates,
+                selector_products.len() as f64 / candidates as f64 * 100.0
+            );
+            if best.as_ref().is_none_or(|(_, products)| selector_products.len() > products.len()) {
+                best = Some((selector_str, selector_products));
+            }
+        }
+        if let Some((selector_str, _)) = &best {
+            info!("Using selector '{}'", selector_str);
+        }
+        let products = best.map(|(_, products)| products).unwrap_or_default();
+
+        // Filter out excluded products
+        let filtered_products = self.filter_excluded_products(products);
+        info!("Extracted {} products from HTML (after filtering)", filtered_products.len());
+        Ok(filtered_products)
+    }
+
+    /// ML-based product extraction
+    fn extract_with_ml(
+        &self,
+        html: &str,
+        category_name: &str,
+        source_url: Option<String>,
+        ml_model: &ProductMLModel,
+    ) -> Result<Vec<ScrapedProduct>> {
+        let candidates = self.find_product_candidates(html);
+        let mut products = Vec::new();
+
+        let document = Html::parse_document(html);
+        let page_category = self.extract_category_from_page(&document)
+            .unwrap_or_else(|| category_name.to_string());
+
+        for candidate in candidates {
+            if ml_model.is_product(&candidate)
+                && let Some(product) = self.candidate_to_product(&candidate, &page_category, source_url.clone())
+            {
+                products.push(product);
+            }
+        }
+
+        Ok(self.filter_excluded_products(products))
+    }
+
+    /// Extract category from page title or breadcrumb
+    fn extract_category_from_page(&self, document: &Html) -> Option<String> {
+        // Try configured category selectors
+        for selector_str in &self.selectors.category_selectors {
+            if let Ok(selector) = Selector::parse(selector_str)
+                && let Some(element) = document.select(&selector).next()
+            {
+                let category = element.text().collect::<Vec<_>>().join(" ").trim().to_string();
+                if !category.is_empty() {
+                    return Some(category);
+                }
+            }
+        }
+        None
+    }
+
+    /// Extract a single product from HTML element
+    fn extract_single_product(
+        &self,
+        element: ElementRef,
+        category: &str,
+        source_url: Option<String>,
+    ) -> Option<ScrapedProduct> {
+        // Debug: Log the element HTML for inspection
+        let element_html = element.html();
+        if element_html.len() > 200 {
+            debug!("Processing element: {}...", element_html.chars().take(200).collect::<String>());
+        } else {
+            debug!("Processing element: {}", element_html);
+        }
+
+        let name = match self.extract_product_name(element) {
+            Some(n) => {
+                debug!("✅ Extracted name: {}", n);
+                n
+            }
+            None => {
+                warn!("❌ Failed to extract product name");
+                return None;
+            }
+        };
+
+        let price = match self.extract_product_price(element) {
+            Some(p) => {
+                debug!("✅ Extracted price: {}", p);
+                p
+            }
+            None => {
+                warn!("❌ Failed to extract product price");
+                return None;
+            }
+        };
+
+        let product_id = match self.extract_product_id(element) {
+            Some(id) => {
+                debug!("✅ Extracted product_id: {}", id);
+                id
+            }
+            None => {
+                warn!("❌ Failed to extract product ID");
+                return None;
+            }
+        };
+
+        debug!("🎉 Successfully extracted product: {} (ID: {}, Price: {})", name, product_id, price);
+
+        Some(ScrapedProduct {
+            name,
+            price,
+            product_id,
+            category: category.to_string(),
+            url: source_url,
+            raw_html: element_html,
+        })
+    }
+
+    /// Extract product name using configured selectors
+    fn extract_product_name(&self, element: ElementRef) -> Option<String> {
+        debug!("🔍 Trying to extract product name with {} selectors", self.selectors.name_selectors.len());
+
+        for selector_str in &self.selectors.name_selectors {
+            debug!("  Trying name selector: {}", selector_str);
+            if let Ok(selector) = Selector::parse(selector_str) {
+                if let Some(name_element) = element.select(&selector).next() {
+                    let name = name_element.text().collect::<Vec<_>>().join(" ").trim().to_string();
+                    debug!("  Found text: '{}'", name);
+                    if !name.is_empty() && name.len() > 2 {
+                        debug!("  ✅ Valid name found: {}", name);
+                        return Some(name);
+                    }
+                } else {
+                    debug!("  ❌ No element found for selector: {}", selector_str);
+                }
+            } else {
+                warn!("  ❌ Invalid selector: {}", selector_str);
+            }
+        }
+
+        debug!("🔍 Trying fallback: extract from element text");
+        // Fallback: extract from element text
+        let text = element.text().collect::<Vec<_>>().join(" ");
+        debug!("  Element text: '{}'", text);
+        let lines: Vec<&str> = text.lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty())
+            .collect();
+
+        for line in lines {
+            debug!("  Checking line: '{}'", line);
+            if line.len() > 3 && !self.looks_like_price(line) {
+                debug!("  ✅ Valid fallback name found: {}", line);
+                return Some(line.to_string());
+            }
+        }
+
+        warn!("🔍 No valid product name found");
+        None
+    }
+
+    /// Extract product price using configured selectors and patterns
+    fn extract_product_price(&self, element: ElementRef) -> Option<String> {
+        debug!("💰 Trying to extract product price with {} selectors", self.selectors.price_selectors.len());
+
+        // Try configured price selectors
+        for selector_str in &self.selectors.price_selectors {
+            debug!("  Trying price selector: {}", selector_str);
+            if let Ok(selector) = Selector::parse(selector_str) {
+                if let Some(price_element) = element.select(&selector).next() {
+                    debug!("  Found price element");
+
+                    // Check for data-price-amount attribute first
+                    if let Some(price_amount) = price_element.value().attr("data-price-amount") {
+                        debug!("  ✅ Found data-price-amount: {}", price_amount);
+                        return Some(price_amount.to_string());
+                    }
+
+                    // Extract from text content
+                    let price_text = price_element.text().collect::<Vec<_>>().join(" ").trim().to_string();
+                    debug!("  Price element text: '{}'", price_text);
+                    if let Some(price) = self.extract_price_from_text(&price_text) {
+                        debug!("  ✅ Valid price found: {}", price);
+                        return Some(price);
+                    }
+                } else {
+                    debug!("  ❌ No element found for price selector: {}", selector_str);
+                }
+            } else {
+                warn!("  ❌ Invalid price selector: {}", selector_str);
+            }
+        }
+
+        debug!("💰 Trying fallback: search in all text for price patterns");
+        // Fallback: search in all text for price patterns
+        let all_text = element.text().collect::<Vec<_>>().join(" ");
+        debug!("  All element text: '{}'", all_text);
+        if let Some(price) = self.extract_price_from_text(&all_text) {
+            debug!("  ✅ Fallback price found: {}", price);
+            Some(price)
+        } else {
+            warn!("💰 No valid product price found");
+            None
+        }
+    }
+
+    /// Extract product ID from data attributes
+    fn extract_product_id(&self, element: ElementRef) -> Option<String> {
+        debug!("🆔 Trying to extract product ID");
+
+        // Look for data-product-id attribute
+        if let Some(product_id) = element.value().attr("data-product-id") {
+            debug!("  ✅ Found data-product-id on root element: {}", product_id);
+            return Some(product_id.to_string());
+        } else {
+            debug!("  ❌ No data-product-id on root element");
+        }
+
+        // Look in child elements for data-product-id
+        if let Ok(selector) = Selector::parse("[data-product-id]") {
+            if let Some(id_element) = element.select(&selector).next() {
+                if let Some(product_id) = id_element.value().attr("data-product-id") {
+                    debug!("  ✅ Found data-product-id in child element: {}", product_id);
+                    return Some(product_id.to_string());
+                }
+            } else {
+                debug!("  ❌ No child elements with data-product-id found");
+            }
+        }
+
+        warn!("🆔 No valid product ID found");
+        None
+    }
+
+    /// Extract price from text using regex patterns
+    pub fn extract_price_from_text(&self, text: &str) -> Option<String> {
+        self.price_patterns
+            .iter()
+            .find_map(|pattern| pattern.find(text))
+            .map(|price_match| price_match.as_str().trim().to_string())
+    }
+
+    /// Check if text looks like a price
+    pub fn looks_like_price(&self, text: &str) -> bool {
+        self.price_patterns.iter().any(|pattern| pattern.is_match(text))
+    }
+
+    /// Filter out excluded products
+    fn filter_excluded_products(&self, products: Vec<ScrapedProduct>) -> Vec<ScrapedProduct> {
+        products
+            .into_iter()
+            .filter(|product| !self.exclusion_detector.is_excluded(&product.name))
+            .collect()
+    }
+
+    /// Find product candidates for ML classification
+    pub fn find_product_candidates(&self, html: &str) -> Vec<ProductCandidate> {
+        let mut candidates = Vec::new();
+        let document = Html::parse_document(html);
+
+        // Look for potential product elements
+        let selectors = ["div", "article", "li", "section"];
+
+        for selector_str in &selectors {
+            if let Ok(selector) = Selector::parse(selector_str) {
+                for element in document.select(&selector) {
+                    let candidate = self.element_to_candidate(element);
+                    candidates.push(candidate);
+                }
+            }
+        }
+
+        candidates
+    }
+
+    /// Convert HTML element to product candidate
+    fn element_to_candidate(&self, element: ElementRef) -> ProductCandidate {
+        let text_content = element.text().collect::<Vec<_>>().join(" ");
+        let classes: Vec<String> = element.value().classes().map(|s| s.to_string()).collect();
+        let mut attributes = HashMap::new();
+
+        for attr in element.value().attrs() {
+            attributes.insert(attr.0.to_string(), attr.1.to_string());
+        }
+
+        ProductCandidate {
+            element_html: element.html(),
+            text_content: text_content.clone(),
+            tag_name: element.value().name().to_string(),
+            classes,
+            attributes,
+            depth: Self::calculate_depth(element),
+            parent_context: Self::get_parent_context(element),
+            has_price_text: self.looks_like_price(&text_content),
+            has_link: element.html().contains("<a"),
+        }
+    }
+
+    /// Calculate element depth in DOM
+    fn calculate_depth(element: ElementRef) -> usize {
+        let mut depth = 0;
+        let mut current = Some(element);
+
+        while let Some(elem) = current {
+            depth += 1;
+            current = elem.parent().and_then(ElementRef::wrap);
+        }
+
+        depth
+    }
+
+    /// Get parent context classes
+    fn get_parent_context(element: ElementRef) -> String {
+        if let Some(parent) = element.parent().and_then(ElementRef::wrap) {
+            parent.value().classes().collect::<Vec<_>>().join(" ")
+        } else {
+            String::new()
+        }
+    }
+
+    /// Convert candidate to product
+    fn candidate_to_product(
+        &self,
+        candidate: &ProductCandidate,
+        category: &str,
+        source_url: Option<String>,
+    ) -> Option<ScrapedProduct> {
+        let html = Html::parse_fragment(&candidate.element_html);
+
+        // The fragment root is a synthetic <html> node; skip whitespace text
+        // nodes and use the first real element as the product container
+        let element_ref = html.root_element().children().find_map(ElementRef::wrap)?;
+        self.extract_single_product(element_ref, category, source_url)
+    }
+}
+
+/// Training data generation for the ML fallback
+impl ProductExtractor {
+    /// Build labelled examples from product-like elements (positive) and
+    /// page chrome such as headers and menus (negative)
+    pub fn generate_training_data(&self, html_samples: &[String]) -> Vec<TrainingExample> {
+        let mut examples = Vec::new();
+
+        for html in html_samples {
+            let document = Html::parse_document(html);
+
+            // Extract positive examples from known product elements
+            if let Ok(selector) = Selector::parse("div[class*='product'], .product-item, .item") {
+                for element in document.select(&selector) {
+                    if let Some(example) = self.create_positive_example(element) {
+                        examples.push(example);
+                    }
+                }
+            }
+
+            // Generate negative examples
+            examples.extend(Self::create_negative_examples(&document));
+        }
+
+        examples
+    }
+
+    fn create_positive_example(&self, element: ElementRef) -> Option<TrainingExample> {
+        // Try to extract product info
+        let product_name = self.extract_product_name(element);
+        let product_price = self.extract_product_price(element);
+
+        // Only create example if we found some product-like content
+        if product_name.is_some() || product_price.is_some() {
+            Some(TrainingExample {
+                html_fragment: element.html(),
+                is_product: true,
+                product_name,
+                product_price,
+                section_context: "product-section".to_string(),
+            })
+        } else {
+            None
+        }
+    }
+
+    fn create_negative_examples(document: &Html) -> Vec<TrainingExample> {
+        let mut examples = Vec::new();
+
+        // Select non-product elements
+        let negative_selectors = [
+            "header",
+            "footer",
+            "nav",
+            ".navigation",
+            ".menu",
+            ".breadcrumb",
+            ".sidebar",
+            ".advertisement",
+        ];
+
+        for selector_str in &negative_selectors {
+            if let Ok(selector) = Selector::parse(selector_str) {
+                // Limit to avoid too many negatives
+                for element in document.select(&selector).take(2) {
+                    let text_content = element.text().collect::<Vec<_>>().join(" ");
+                    if !text_content.trim().is_empty() && text_content.len() > 10 {
+                        examples.push(TrainingExample {
+                            html_fragment: element.html(),
+                            is_product: false,
+                            product_name: None,
+                            product_price: None,
+                            section_context: selector_str.to_string(),
+                        });
+                    }
+                }
+            }
+        }
+
+        examples
+    }
+}
+
+impl ProductMLModel {
+    /// Train a random forest on labelled examples
+    pub fn train(examples: &[TrainingExample], feature_extractor: FeatureExtractor) -> Result<Self> {
+        if examples.is_empty() {
+            return Err(anyhow!("No training examples provided"));
+        }
+
+        let mut features = Vec::with_capacity(examples.len());
+        let mut labels = Vec::with_capacity(examples.len());
+        for example in examples {
+            features.push(feature_extractor.extract_features(&example.to_candidate()));
+            labels.push(if example.is_product { 1 } else { 0 });
+        }
+
+        let feature_matrix = DenseMatrix::from_2d_vec(&features);
+        let classifier = RandomForestClassifier::fit(
+            &feature_matrix,
+            &labels,
+            RandomForestClassifierParameters::default(),
+        )
+        .map_err(|e| anyhow!("Failed to train product classifier: {}", e))?;
+
+        Ok(Self {
+            classifier,
+            feature_extractor,
+            confidence_threshold: 0.7,
+        })
+    }
+
+    /// Whether the classifier labels a candidate as a product
+    pub fn is_product(&self, candidate: &ProductCandidate) -> bool {
+        let features = self.feature_extractor.extract_features(candidate);
+        let feature_matrix = DenseMatrix::from_2d_vec(&vec![features]);
+
+        matches!(
+            self.classifier.predict(&feature_matrix).as_deref(),
+            Ok([1, ..])
+        )
+    }
+}
+
+impl TrainingExample {
+    fn to_candidate(&self) -> ProductCandidate {
+        let document = Html::parse_fragment(&self.html_fragment);
+
+        ProductCandidate {
+            element_html: self.html_fragment.clone(),
+            text_content: document.root_element().text().collect::<Vec<_>>().join(" "),
+            tag_name: "div".to_string(), // Simplified
+            classes: vec![],
+            attributes: HashMap::new(),
+            depth: 3,
+            parent_context: self.section_context.clone(),
+            has_price_text: self.product_price.is_some(),
+            has_link: self.html_fragment.contains("<a"),
+        }
+    }
+}
+
+/// Implementation for ExclusionDetector
+impl ExclusionDetector {
+    pub fn new_default() -> Self {
+        let mut excluded_keywords = HashSet::new();
+        excluded_keywords.insert("advertisement".to_string());
+        excluded_keywords.insert("sponsored".to_string());
+        excluded_keywords.insert("banner".to_string());
+        excluded_keywords.insert("footer".to_string());
+        excluded_keywords.insert("header".to_string());
+        excluded_keywords.insert("navigation".to_string());
+        excluded_keywords.insert("menu".to_string());
+
+        Self {
+            excluded_sections: vec![
+                "header".to_string(),
+                "footer".to_string(),
+                "nav".to_string(),
+                "advertisement".to_string(),
+                "sidebar".to_string(),
+            ],
+            excluded_keywords,
+        }
+    }
+
+    /// Check if text belongs to an excluded section or contains an excluded keyword
+    pub fn is_excluded(&self, text: &str) -> bool {
+        let text_lower = text.to_lowercase();
+
+        self.excluded_sections
+            .iter()
+            .chain(self.excluded_keywords.iter())
+            .any(|term| text_lower.contains(&term.to_lowercase()))
+    }
+}
+
+/// Implementation for FeatureExtractor
+impl FeatureExtractor {
+    pub fn new() -> Self {
+        Self {
+            price_patterns: default_price_patterns(),
+            name_patterns: vec![
+                Regex::new(r"\b[A-Z][a-z]+(\s+[A-Z][a-z]+)*\b").unwrap(),
+                Regex::new(r"\d+\s*(kg|g|ml|l|pack|pcs)\b").unwrap(),
+            ],
+            keyword_features: Vec::new(),
+        }
+    }
+
+    /// Add one presence feature per keyword (matched case-insensitively)
+    pub fn with_keyword_features(mut self, keywords: &[&str]) -> Self {
+        self.keyword_features = keywords.iter().map(|k| k.to_lowercase()).collect();
+        self
+    }
+
+    pub fn extract_features(&self, candidate: &ProductCandidate) -> Vec<f32> {
+        let flag = |present: bool| if present { 1.0 } else { 0.0 };
+        let text = &candidate.text_content;
+
+        // Text-based features
+        let mut features = vec![
+            text.len() as f32,
+            text.chars().filter(|c| c.is_uppercase()).count() as f32,
+            text.chars().filter(|c| c.is_numeric()).count() as f32,
+            text.split_whitespace().count() as f32,
+        ];
+
+        // Price and name pattern matching
+        for pattern in self.price_patterns.iter().chain(&self.name_patterns) {
+            features.push(flag(pattern.is_match(text)));
+        }
+
+        // Structural features
+        features.push(candidate.depth as f32);
+        features.push(candidate.classes.len() as f32);
+        features.push(candidate.attributes.len() as f32);
+        features.push(flag(candidate.has_link));
+
+        // Context features
+        features.push(flag(candidate.parent_context.contains("product")));
+        features.push(flag(candidate.parent_context.contains("item")));
+        features.push(flag(candidate.parent_context.contains("grid")));
+
+        // Site-specific keyword features
+        if !self.keyword_features.is_empty() {
+            let text_lower = text.to_lowercase();
+            for keyword in &self.keyword_features {
+                features.push(flag(text_lower.contains(keyword)));
+            }
+        }
+
+        features
+    }
+}
+
+impl Default for FeatureExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Convert scraped products to JSON format for unified processing
+impl ScrapedProduct {
+    pub fn to_json(&self) -> Value {
+        serde_json::json!({
+            "name": self.name,
+            "price": self.price,
+            "product_id": self.product_id,
+            "category": self.category,
+            "url": self.url,
+            "source_type": "html"
+        })
+    }
+}
+
+/// Links matched by `selectors` on a category page, as (link text, absolute
+/// URL) pairs. Only links on the page's own site are kept, each once.
+pub fn extract_subcategory_links(html: &str, selectors: &[String], page_url: &str) -> Vec<(String, String)> {
+    let document = Html::parse_document(html);
+    let anchor = Selector::parse("a[href]").expect("valid anchor selector");
+    let mut seen = HashSet::new();
+    let mut links = Vec::new();
+
+    for selector_str in selectors {
+        let Ok(selector) = Selector::parse(selector_str) else {
+            warn!("Skipping invalid subcategory selector: {}", selector_str);
+            continue;
+        };
+        for element in document.select(&selector) {
+            let anchors: Vec<ElementRef> = if element.value().name() == "a" {
+                vec![element]
+            } else {
+                element.select(&anchor).collect()
+            };
+            for a in anchors {
+                let Some(url) = a.value().attr("href").and_then(|href| resolve_link(page_url, href)) else {
+                    continue;
+                };
+                if url == page_url || !seen.insert(url.clone()) {
+                    continue;
+                }
+                let text = a.text().collect::<Vec<_>>().join(" ").split_whitespace().collect::<Vec<_>>().join(" ");
+                links.push((if text.is_empty() { url.clone() } else { text }, url));
+            }
+        }
+    }
+    links
+}
+
+/// Absolute URL of `href` found on `page_url`, or `None` for links off the
+/// page's site and non-page links such as `mailto:` or bare fragments
+fn resolve_link(page_url: &str, href: &str) -> Option<String> {
+    let href = href.split('#').next()?.trim();
+    let (scheme, rest) = page_url.split_once("://")?;
+    let origin_end = rest.find('/').map_or(page_url.len(), |i| scheme.len() + 3 + i);
+    let origin = &page_url[..origin_end];
+
+    let url = if href.starts_with("http://") || href.starts_with("https://") {
+        href.to_string()
+    } else if let Some(host_relative) = href.strip_prefix("//") {
+        format!("{}://{}", scheme, host_relative)
+    } else if href.starts_with('/') {
+        format!("{}{}", origin, href)
+    } else if href.is_empty() || href.contains(':') {
+        return None;
+    } else {
+        let directory = page_url.rfind('/').filter(|i| *i >= origin_end).map_or(origin, |i| &page_url[..i]);
+        format!("{}/{}", directory, href)
+    };
+
+    let same_site = url.strip_prefix(origin).is_some_and(|path| path.is_empty() || path.starts_with(['/', '?']));
+    same_site.then_some(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_HTML: &str = r#"
+        <html><body>
+            <header><nav class="menu">Home Groceries Pharmacy Electronics</nav></header>
+            <div class="product-item" data-product-id="101">
+                <h3>Onion (Pyaaz) 1 KG</h3>
+                <span class="price">Rs. 140</span>
+            </div>
+            <div class="product-item" data-product-id="102">
+                <h3>Potato (Aloo) 1 KG</h3>
+                <span class="price">Rs. 100</span>
+            </div>
+            <div class="product-item" data-product-id="103">
+                <h3>Sponsored Banner Deal</h3>
+                <span class="price">Rs. 1</span>
+            </div>
+        </body></html>
+    "#;
+
+    #[test]
+    fn test_rule_based_extraction() {
+        let extractor = ProductExtractor::new(SelectorConfig::default());
+        let products = extractor
+            .extract_products(SAMPLE_HTML, "Fresh Vegetables", None)
+            .unwrap();
+
+        // The sponsored banner is dropped by the exclusion detector
+        assert_eq!(products.len(), 2);
+        assert_eq!(products[0].name, "Onion (Pyaaz) 1 KG");
+        assert_eq!(products[0].price, "Rs. 140");
+        assert_eq!(products[0].product_id, "101");
+        assert_eq!(products[1].product_id, "102");
+    }
+
+    #[test]
+    fn test_best_scoring_product_selector_wins() {
+        let html = r#"
+            <html><body>
+                <div class="promo"><h3>Weekend Offers</h3></div>
+                <div class="promo"><span>Free delivery</span></div>
+                <div class="promo" data-product-id="7">
+                    <h3>Cooking Oil 5L</h3>
+                    <span class="price">Rs. 2800</span>
+                </div>
+                <div class="product-card" data-product-id="201">
+                    <h3>Basmati Rice 5 KG</h3>
+                    <span class="price">Rs. 1900</span>
+                </div>
+                <div class="product-card" data-product-id="202">
+                    <h3>Daal Chana 1 KG</h3>
+                    <span class="price">Rs. 320</span>
+                </div>
+            </body></html>
+        "#;
+        let selectors = SelectorConfig {
+            product_selectors: vec![".promo".to_string(), ".product-card".to_string(), ".missing".to_string()],
+            ..SelectorConfig::default()
+        };
+        let products = ProductExtractor::new(selectors).extract_products(html, "Staples", None).unwrap();
+
+        // `.promo` matches first but yields one product, `.product-card` two
+        let ids: Vec<&str> = products.iter().map(|product| product.product_id.as_str()).collect();
+        assert_eq!(ids, vec!["201", "202"]);
+    }
+
+    #[test]
+    fn test_subcategory_links() {
+        let html = r#"
+            <ul class="categories-menu">
+                <li><a href="/groceries/fruits">Fresh
+                    Fruits</a></li>
+                <li><a href="vegetables#top">Vegetables</a></li>
+                <li><a href="https://www.example.com/groceries/fruits">Fruits again</a></li>
+                <li><a href="https://other.example.org/deals">Elsewhere</a></li>
+                <li><a href="mailto:help@example.com">Help</a></li>
+            </ul>
+            <a class="direct" href="//www.example.com/groceries/dairy"></a>
+        "#;
+        let selectors = vec![".categories-menu".to_string(), "a.direct".to_string(), "[".to_string()];
+        let links = extract_subcategory_links(html, &selectors, "https://www.example.com/groceries/all");
+        assert_eq!(
+            links,
+            vec![
+                ("Fresh Fruits".to_string(), "https://www.example.com/groceries/fruits".to_string()),
+                ("Vegetables".to_string(), "https://www.example.com/groceries/vegetables".to_string()),
+                (
+                    "https://www.example.com/groceries/dairy".to_string(),
+                    "https://www.example.com/groceries/dairy".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_price_helpers() {
+        let extractor = ProductExtractor::new(SelectorConfig::default());
+        assert_eq!(extractor.extract_price_from_text("Now Rs. 1,250 only"), Some("Rs. 1,250".to_string()));
+        assert!(extractor.looks_like_price("PKR 300"));
+        assert!(!extractor.looks_like_price("Fresh Milk"));
+    }
+
+    #[test]
+    fn test_candidate_to_product_skips_whitespace_nodes() {
+        let extractor = ProductExtractor::new(SelectorConfig::default());
+        let candidate = ProductCandidate {
+            element_html: "\n  <div class=\"product-item\" data-product-id=\"7\"><h3>Fresh Milk</h3><span class=\"price\">Rs. 220</span></div>".to_string(),
+            text_content: String::new(),
+            tag_name: "div".to_string(),
+            classes: vec![],
+            attributes: HashMap::new(),
+            depth: 3,
+            parent_context: String::new(),
+            has_price_text: true,
+            has_link: false,
+        };
+
+        let product = extractor.candidate_to_product(&candidate, "Dairy", None).unwrap();
+        assert_eq!(product.name, "Fresh Milk");
+        assert_eq!(product.product_id, "7");
+    }
+
+    #[test]
+    fn test_training_data_and_model() {
+        let extractor = ProductExtractor::new(SelectorConfig::default());
+        let examples = extractor.generate_training_data(&[SAMPLE_HTML.to_string()]);
+
+        assert!(examples.iter().any(|e| e.is_product));
+        assert!(examples.iter().any(|e| !e.is_product));
+
+        let feature_extractor = FeatureExtractor::new().with_keyword_features(&["kg", "local", "piece"]);
+        let model = ProductMLModel::train(&examples, feature_extractor).unwrap();
+        let candidates = extractor.find_product_candidates(SAMPLE_HTML);
+        assert!(candidates.iter().any(|c| model.is_product(c)));
+    }
+
+    #[test]
+    fn test_keyword_features_extend_vector() {
+        let candidate = ProductCandidate {
+            element_html: String::new(),
+            text_content: "Local Tomatoes 1 KG".to_string(),
+            tag_name: "div".to_string(),
+            classes: vec![],
+            attributes: HashMap::new(),
+            depth: 1,
+            parent_context: String::new(),
+            has_price_text: false,
+            has_link: false,
+        };
+
+        let base = FeatureExtractor::new().extract_features(&candidate);
+        let extended = FeatureExtractor::new()
+            .with_keyword_features(&["kg", "local", "piece"])
+            .extract_features(&candidate);
+
+        assert_eq!(extended.len(), base.len() + 3);
+        assert_eq!(&extended[base.len()..], &[1.0, 1.0, 0.0]);
+    }
+}
@@ -1,87 +1,95 @@
 use anyhow::{Result, anyhow};
+use async_trait::async_trait;
 use serde_json::Value;
+use std::collections::HashSet;
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::sleep;
-use tracing::{error, info, warn};
+use tracing::{Instrument, error, info, info_span, warn};
 use wreq::Client;
-use wreq_util::Emulation;
-use scraper::{Html, Selector, ElementRef};
-use regex::Regex;
-use std::collections::{HashMap, HashSet};
-use smartcore::ensemble::random_forest_classifier::RandomForestClassifier;
-use smartcore::linalg::basic::matrix::DenseMatrix;
 
 use crate::config::HtmlConfig;
+use crate::fetcher::{Fetcher, ProductLimit, RateLimiter, Shutdown, build_client, category_span, decode_body, is_response_too_large, read_body, SOURCE_CATEGORY_FIELD, merge_category_duplicates, take_within};
+use crate::config::HtmlCategoryConfig;
+use crate::fetcher::html_extraction::{ProductExtractor, ProductMLModel, ScrapedProduct, extract_subcategory_links};
+use crate::metrics::SourceMetrics;
+use crate::processor::{HtmlProcessor, RecordContext};
 
 /// HTML-based fetcher for web scraping data sources like Naheed store
 pub struct HtmlFetcher {
     client: Client,
     config: HtmlConfig,
-    ml_model: Option<ProductMLModel>,
-    exclusion_detector: ExclusionDetector,
-}
-
-/// ML model for product extraction
-pub struct ProductMLModel {
-    pub classifier: RandomForestClassifier<f32, i32, DenseMatrix<f32>, Vec<i32>>,
-    pub feature_extractor: FeatureExtractor,
-    pub confidence_threshold: f32,
-}
-
-/// Feature extractor for ML model
-pub struct FeatureExtractor {
-    pub price_patterns: Vec<Regex>,
-    pub name_patterns: Vec<Regex>,
-}
-
-/// Exclusion detector for filtering out non-product content
-pub struct ExclusionDetector {
-    pub excluded_sections: Vec<String>,
-    pub excluded_keywords: HashSet<String>,
-}
-
-/// Product candidate for ML classification
-#[derive(Debug, Clone)]
-pub struct ProductCandidate {
-    pub element_html: String,
-    pub text_content: String,
-    pub tag_name: String,
-    pub classes: Vec<String>,
-    pub attributes: HashMap<String, String>,
-    pub depth: usize,
-    pub parent_context: String,
-    pub has_price_text: bool,
-    pub has_link: bool,
-}
-
-/// Represents a scraped product from HTML
-#[derive(Debug, Clone)]
-pub struct ScrapedProduct {
-    pub name: String,
-    pub price: String,
-    pub product_id: String,
-    pub category: String,
-    pub url: Option<String>,
-    pub raw_html: String,
+    extractor: ProductExtractor,
+    /// Shared with the run's other fetchers; requests are unthrottled without one
+    rate_limiter: Option<Arc<RateLimiter>>,
+    /// Checked before each category, see `Shutdown::check`
+    shutdown: Option<Arc<Shutdown>>,
+    /// `--limit` on the products scraped across categories
+    limit: Option<ProductLimit>,
+    /// Counts every request by its response status
+    metrics: Option<SourceMetrics>,
 }
 
 impl HtmlFetcher {
     pub fn new(config: HtmlConfig) -> Result<Self> {
-        let client = Client::builder()
-            .emulation(Emulation::Firefox136)
-            .build()?;
+        let client = build_client(
+            config.site.emulation.as_deref(),
+            config.site.user_agent.as_deref(),
+        )?;
 
         Ok(HtmlFetcher {
             client,
+            extractor: ProductExtractor::new(config.selectors.clone()),
             config,
-            ml_model: None,
-            exclusion_detector: ExclusionDetector::new_default(),
+            rate_limiter: None,
+            shutdown: None,
+            limit: None,
+            metrics: None,
         })
     }
 
+    /// Acquire from `limiter` before every page request
+    pub fn with_rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Stop between categories once `shutdown` is requested
+    pub fn with_shutdown(mut self, shutdown: Arc<Shutdown>) -> Self {
+        self.shutdown = Some(shutdown);
+        self
+    }
+
+    /// Stop scraping once `max` products were scraped across categories
+    pub fn with_limit(mut self, max: usize) -> Self {
+        self.limit = Some(ProductLimit::new(max));
+        self
+    }
+
+    /// Count every request in `metrics`
+    pub fn with_metrics(mut self, metrics: SourceMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    fn limit_reached(&self) -> bool {
+        let reached = self.limit.as_ref().is_some_and(ProductLimit::is_reached);
+        if reached {
+            info!("Reached --limit for {}, not scraping further categories", self.config.site.name);
+        }
+        reached
+    }
+
+    fn check_shutdown(&self) -> Result<()> {
+        match &self.shutdown {
+            Some(shutdown) => shutdown.check(&self.config.site.name),
+            None => Ok(()),
+        }
+    }
+
     /// Initialize ML model for enhanced product extraction
     pub fn with_ml_model(mut self, model: ProductMLModel) -> Self {
-        self.ml_model = Some(model);
+        self.extractor = self.extractor.with_ml_model(model);
         self
     }
 
@@ -89,10 +97,25 @@ impl HtmlFetcher {
     pub async fn fetch_all_categories(&self) -> Result<Vec<ScrapedProduct>> {
         let mut all_products = Vec::new();
 
-        for (category_name, category_config) in &self.config.categories {
+        for (category_name, category_config) in self.config.get_enabled_categories() {
+            self.check_shutdown()?;
+            if self.limit_reached() {
+                break;
+            }
             info!("Scraping category: {}", category_name);
 
-            match self.scrape_category(category_name, category_config).await {
+            if category_config.discover_subcategories {
+                match self.scrape_category_tree(category_config).instrument(category_span(category_name)).await {
+                    Ok(products) => {
+                        info!("Scraped {} products from the {} category tree", products.len(), category_name);
+                        all_products.extend(products);
+                    }
+                    Err(e) => error!("Failed to scrape category tree {}: {}", category_name, e),
+                }
+                continue;
+            }
+
+            match self.scrape_category(category_name, category_config).instrument(category_span(category_name)).await {
                 Ok(products) => {
                     info!("Scraped {} products from {}", products.len(), category_name);
                     all_products.extend(products);
@@ -114,11 +137,86 @@ impl HtmlFetcher {
         Ok(all_products)
     }
 
+    /// Scrape every leaf category found below a root category
+    async fn scrape_category_tree(&self, root: &HtmlCategoryConfig) -> Result<Vec<ScrapedProduct>> {
+        let leaves = self.discover_leaf_categories(root).await?;
+        info!("Found {} leaf categories below {}", leaves.len(), root.name);
+
+        let mut all_products = Vec::new();
+        for leaf in &leaves {
+            self.check_shutdown()?;
+            if self.limit_reached() {
+                break;
+            }
+            match self.scrape_category(&leaf.name, leaf).instrument(category_span(&leaf.name)).await {
+                Ok(products) => {
+                    info!("Scraped {} products from {}", products.len(), leaf.name);
+                    all_products.extend(products);
+                }
+                Err(e) => warn!("Failed to scrape subcategory {}: {}", leaf.name, e),
+            }
+        }
+        Ok(all_products)
+    }
+
+    /// Follow `subcategory_selectors` links breadth-first from `root`, at most
+    /// `max_category_depth` levels down. Pages without further links, and the
+    /// pages at the depth limit, are the leaves.
+    async fn discover_leaf_categories(&self, root: &HtmlCategoryConfig) -> Result<Vec<HtmlCategoryConfig>> {
+        let selectors = &self.config.selectors.subcategory_selectors;
+        if selectors.is_empty() {
+            return Err(anyhow!("{} discovers subcategories but no subcategory_selectors are configured", root.name));
+        }
+
+        let leaf = |name: String, base_url: String| HtmlCategoryConfig {
+            name,
+            base_url,
+            enabled: true,
+            discover_subcategories: false,
+        };
+        let mut visited = HashSet::from([root.base_url.clone()]);
+        let mut frontier = vec![(root.name.clone(), root.base_url.clone())];
+        let mut leaves = Vec::new();
+
+        for depth in 0..=self.config.scraping.max_category_depth {
+            let mut next = Vec::new();
+            for (name, url) in frontier {
+                if depth == self.config.scraping.max_category_depth {
+                    leaves.push(leaf(name, url));
+                    continue;
+                }
+
+                let html = match self.fetch_page_with_retry(&url, self.config.scraping.max_retries).await {
+                    Ok(html) => html,
+                    Err(e) if depth == 0 => return Err(e),
+                    Err(e) => {
+                        warn!("Skipping subcategory {} ({}): {}", name, url, e);
+                        continue;
+                    }
+                };
+                let links: Vec<(String, String)> = extract_subcategory_links(&html, selectors, &url)
+                    .into_iter()
+                    .filter(|(_, link)| visited.insert(link.clone()))
+                    .collect();
+                if links.is_empty() {
+                    leaves.push(leaf(name, url));
+                } else {
+                    next.extend(links);
+                }
+            }
+            if next.is_empty() {
+                break;
+            }
+            frontier = next;
+        }
+        Ok(leaves)
+    }
+
     /// Scrape a specific category
     async fn scrape_category(
         &self,
         category_name: &str,
-        category_config: &crate::config::HtmlCategoryConfig,
+        category_config: &HtmlCategoryConfig,
     ) -> Result<Vec<ScrapedProduct>> {
         let mut all_products = Vec::new();
 
@@ -134,13 +232,18 @@ impl HtmlFetcher {
 
             info!("Scraping page {} of {}: {}", page, category_name, url);
 
-            match self.scrape_page(&url, category_name).await {
-                Ok(products) => {
+            match self.scrape_page(&url, category_name).instrument(info_span!("page", page)).await {
+                Ok(mut products) => {
                     if products.is_empty() {
                         info!("No products found on page {}, stopping pagination", page);
                         break;
                     }
+                    let reached = take_within(self.limit.as_ref(), &mut products);
                     all_products.extend(products);
+                    if reached {
+                        info!("Reached --limit, stopping pagination of {}", category_name);
+                        break;
+                    }
                 }
                 Err(e) => {
                     warn!("Failed to scrape page {} of {}: {}", page, category_name, e);
@@ -160,8 +263,8 @@ impl HtmlFetcher {
     }
 
     /// Scrape a single page
-    async fn scrape_page(&self, url: &str, category_name: &str) -> Result<Vec<ScrapedProduct>> {
-        let html = self.fetch_page_with_retry(url, 3).await?;
+    pub async fn scrape_page(&self, url: &str, category_name: &str) -> Result<Vec<ScrapedProduct>> {
+        let html = self.fetch_page_with_retry(url, self.config.scraping.max_retries).await?;
         self.extract_products_from_html(&html, category_name, Some(url.to_string()))
     }
 
@@ -172,13 +275,15 @@ impl HtmlFetcher {
         while attempts < max_retries {
             match self.fetch_page_smart(url).await {
                 Ok(html) => return Ok(html),
+                Err(e) if is_response_too_large(&e) => return Err(e),
                 Err(e) => {
                     attempts += 1;
                     if attempts < max_retries {
-                        // Exponential backoff with jitter
-                        let delay = Duration::from_millis(
-                            1000 * (2_u64.pow(attempts as u32)) + (rand::random::<u64>() % 1000)
-                        );
+                        // Exponential backoff with jitter, clamped to the configured max
+                        let delay = Duration::from_millis(self.config.scraping.backoff_delay_ms(
+                            attempts as u32,
+                            rand::random::<u64>() % 1000,
+                        ));
                         warn!("Attempt {} failed for {}, retrying in {:?}: {}", 
                               attempts, url, delay, e);
                         sleep(delay).await;
@@ -197,21 +302,27 @@ impl HtmlFetcher {
         // Random delay to mimic human behavior
         let delay = Duration::from_millis(500 + (rand::random::<u64>() % 2000));
         sleep(delay).await;
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire(url).await;
+        }
 
-        let response = self.client
-            .get(url)
-            .send()
-            .await
-            .map_err(|e| anyhow!("Network error: {}", e))?;
+        let response = self.client.get(url).send().await;
+        if let Some(metrics) = &self.metrics {
+            metrics.http_request(response.as_ref().ok().map(|response| response.status().as_u16()));
+        }
+        let response = response.map_err(|e| anyhow!("Network error: {}", e))?;
 
         if !response.status().is_success() {
             return Err(anyhow!("HTTP error: {}", response.status()));
         }
 
-        let html = response
-            .text()
-            .await
-            .map_err(|e| anyhow!("Failed to read response text: {}", e))?;
+        let content_encoding = response
+            .headers()
+            .get(wreq::header::CONTENT_ENCODING)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let body = read_body(response, self.config.scraping.max_response_bytes).await?;
+        let html = decode_body(&body, content_encoding.as_deref())?;
 
         if html.is_empty() {
             return Err(anyhow!("Empty HTML response"));
@@ -238,566 +349,39 @@ impl HtmlFetcher {
         category_name: &str,
         source_url: Option<String>,
     ) -> Result<Vec<ScrapedProduct>> {
-        // Primary: Use rule-based extraction
-        match self.extract_with_rules(html, category_name, source_url.clone()) {
-            Ok(products) if !products.is_empty() => {
-                info!("Rule-based extraction found {} products", products.len());
-                return Ok(products);
-            }
-            Ok(_) => info!("Rule-based extraction found no products, trying ML..."),
-            Err(e) => warn!("Rule-based extraction failed: {:?}, trying ML...", e),
-        }
-
-        // Secondary: Use ML-based extraction if available
-        if let Some(ref ml_model) = self.ml_model {
-            match self.extract_with_ml(html, category_name, source_url, ml_model) {
-                Ok(products) if !products.is_empty() => {
-                    info!("ML-based extraction found {} products", products.len());
-                    return Ok(products);
-                }
-                Ok(_) => info!("ML-based extraction found no products"),
-                Err(e) => warn!("ML-based extraction failed: {:?}", e),
-            }
-        }
-
-        // If both methods fail, return empty result
-        info!("No products found using available methods");
-        Ok(vec![])
-    }
-
-    /// Rule-based product extraction
-    fn extract_with_rules(
-        &self,
-        html: &str,
-        category_name: &str,
-        source_url: Option<String>,
-    ) -> Result<Vec<ScrapedProduct>> {
-        let document = Html::parse_document(html);
-        let mut products = Vec::new();
-
-        // Extract category from page if configured
-        let page_category = self.extract_category_from_page(&document)
-            .unwrap_or_else(|| category_name.to_string());
-
-        // Try each product selector
-        for selector_str in &self.config.selectors.product_selectors {
-            if let Ok(selector) = Selector::parse(selector_str) {
-                let elements: Vec<_> = document.select(&selector).collect();
-
-                if !elements.is_empty() {
-                    info!("Using selector '{}' found {} elements", selector_str, elements.len());
-
-                    for element in elements {
-                        if let Some(product) = self.extract_single_product(element, &page_category, source_url.clone()) {
-                            products.push(product);
-                        }
-                    }
-                    break; // Use first working selector
-                }
-            }
-        }
-
-        // Filter out excluded products
-        let filtered_products = self.filter_excluded_products(products)?;
-        info!("Extracted {} products from HTML (after filtering)", filtered_products.len());
-        Ok(filtered_products)
-    }
-
-    /// ML-based product extraction
-    fn extract_with_ml(
-        &self,
-        html: &str,
-        category_name: &str,
-        source_url: Option<String>,
-        ml_model: &ProductMLModel,
-    ) -> Result<Vec<ScrapedProduct>> {
-        let candidates = self.find_product_candidates(html);
-        let mut products = Vec::new();
-
-        let document = Html::parse_document(html);
-        let page_category = self.extract_category_from_page(&document)
-            .unwrap_or_else(|| category_name.to_string());
-
-        for candidate in candidates {
-            let features = ml_model.feature_extractor.extract_features(&candidate);
-            let feature_matrix = DenseMatrix::from_2d_vec(&vec![features]);
-
-            match ml_model.classifier.predict(&feature_matrix) {
-                Ok(predictions) => {
-                    if let Some(&prediction) = predictions.get(0) {
-                        if prediction == 1 {
-                            if let Some(product) = self.candidate_to_product(&candidate, &page_category, source_url.clone()) {
-                                products.push(product);
-                            }
-                        }
-                    }
-                }
-                Err(_) => continue,
-            }
-        }
-
-        Ok(products)
-    }
-
-    /// Extract category from page title or breadcrumb
-    fn extract_category_from_page(&self, document: &Html) -> Option<String> {
-        // Try configured category selectors
-        for selector_str in &self.config.selectors.category_selectors {
-            if let Ok(selector) = Selector::parse(selector_str) {
-                if let Some(element) = document.select(&selector).next() {
-                    let category = element.text().collect::<Vec<_>>().join(" ").trim().to_string();
-                    if !category.is_empty() {
-                        return Some(category);
-                    }
-                }
-            }
-        }
-        None
-    }
-
-    /// Extract a single product from HTML element
-    fn extract_single_product(
-        &self,
-        element: ElementRef,
-        category: &str,
-        source_url: Option<String>,
-    ) -> Option<ScrapedProduct> {
-        // Debug: Log the element HTML for inspection
-        let element_html = element.html();
-        if element_html.len() > 200 {
-            info!("Processing element: {}...", &element_html[..200]);
-        } else {
-            info!("Processing element: {}", element_html);
-        }
-
-        let name = match self.extract_product_name(element) {
-            Some(n) => {
-                info!("✅ Extracted name: {}", n);
-                n
-            }
-            None => {
-                warn!("❌ Failed to extract product name");
-                return None;
-            }
-        };
-
-        let price = match self.extract_product_price(element) {
-            Some(p) => {
-                info!("✅ Extracted price: {}", p);
-                p
-            }
-            None => {
-                warn!("❌ Failed to extract product price");
-                return None;
-            }
-        };
-
-        let product_id = match self.extract_product_id(element) {
-            Some(id) => {
-                info!("✅ Extracted product_id: {}", id);
-                id
-            }
-            None => {
-                warn!("❌ Failed to extract product ID");
-                return None;
-            }
-        };
-
-        info!("🎉 Successfully extracted product: {} (ID: {}, Price: {})", name, product_id, price);
-
-        Some(ScrapedProduct {
-            name,
-            price,
-            product_id,
-            category: category.to_string(),
-            url: source_url,
-            raw_html: element.html(),
-        })
-    }
-
-    /// Extract product name using configured selectors
-    fn extract_product_name(&self, element: ElementRef) -> Option<String> {
-        info!("🔍 Trying to extract product name with {} selectors", self.config.selectors.name_selectors.len());
-
-        for selector_str in &self.config.selectors.name_selectors {
-            info!("  Trying name selector: {}", selector_str);
-            if let Ok(selector) = Selector::parse(selector_str) {
-                if let Some(name_element) = element.select(&selector).next() {
-                    let name = name_element.text().collect::<Vec<_>>().join(" ").trim().to_string();
-                    info!("  Found text: '{}'", name);
-                    if !name.is_empty() && name.len() > 2 {
-                        info!("  ✅ Valid name found: {}", name);
-                        return Some(name);
-                    }
-                } else {
-                    info!("  ❌ No element found for selector: {}", selector_str);
-                }
-            } else {
-                warn!("  ❌ Invalid selector: {}", selector_str);
-            }
-        }
-
-        info!("🔍 Trying fallback: extract from element text");
-        // Fallback: extract from element text
-        let text = element.text().collect::<Vec<_>>().join(" ");
-        info!("  Element text: '{}'", text);
-        let lines: Vec<&str> = text.lines()
-            .map(|l| l.trim())
-            .filter(|l| !l.is_empty())
-            .collect();
-
-        for line in lines {
-            info!("  Checking line: '{}'", line);
-            if line.len() > 3 && !self.looks_like_price(line) {
-                info!("  ✅ Valid fallback name found: {}", line);
-                return Some(line.to_string());
-            }
-        }
-
-        warn!("🔍 No valid product name found");
-        None
+        self.extractor.extract_products(html, category_name, source_url)
     }
 
-    /// Extract product price using configured selectors and patterns
-    fn extract_product_price(&self, element: ElementRef) -> Option<String> {
-        info!("💰 Trying to extract product price with {} selectors", self.config.selectors.price_selectors.len());
-
-        // Try configured price selectors
-        for selector_str in &self.config.selectors.price_selectors {
-            info!("  Trying price selector: {}", selector_str);
-            if let Ok(selector) = Selector::parse(selector_str) {
-                if let Some(price_element) = element.select(&selector).next() {
-                    info!("  Found price element");
-
-                    // Check for data-price-amount attribute first
-                    if let Some(price_amount) = price_element.value().attr("data-price-amount") {
-                        info!("  ✅ Found data-price-amount: {}", price_amount);
-                        return Some(price_amount.to_string());
-                    }
-
-                    // Extract from text content
-                    let price_text = price_element.text().collect::<Vec<_>>().join(" ").trim().to_string();
-                    info!("  Price element text: '{}'", price_text);
-                    if let Some(price) = self.extract_price_from_text(&price_text) {
-                        info!("  ✅ Valid price found: {}", price);
-                        return Some(price);
-                    }
-                } else {
-                    info!("  ❌ No element found for price selector: {}", selector_str);
-                }
-            } else {
-                warn!("  ❌ Invalid price selector: {}", selector_str);
-            }
-        }
-
-        info!("💰 Trying fallback: search in all text for price patterns");
-        // Fallback: search in all text for price patterns
-        let all_text = element.text().collect::<Vec<_>>().join(" ");
-        info!("  All element text: '{}'", all_text);
-        if let Some(price) = self.extract_price_from_text(&all_text) {
-            info!("  ✅ Fallback price found: {}", price);
-            Some(price)
-        } else {
-            warn!("💰 No valid product price found");
-            None
-        }
-    }
-
-    /// Extract product ID from data attributes
-    fn extract_product_id(&self, element: ElementRef) -> Option<String> {
-        info!("🆔 Trying to extract product ID");
-
-        // Look for data-product-id attribute
-        if let Some(product_id) = element.value().attr("data-product-id") {
-            info!("  ✅ Found data-product-id on root element: {}", product_id);
-            return Some(product_id.to_string());
-        } else {
-            info!("  ❌ No data-product-id on root element");
-        }
-
-        // Look in child elements for data-product-id
-        if let Ok(selector) = Selector::parse("[data-product-id]") {
-            if let Some(id_element) = element.select(&selector).next() {
-                if let Some(product_id) = id_element.value().attr("data-product-id") {
-                    info!("  ✅ Found data-product-id in child element: {}", product_id);
-                    return Some(product_id.to_string());
-                }
-            } else {
-                info!("  ❌ No child elements with data-product-id found");
-            }
-        }
-
-        warn!("🆔 No valid product ID found");
-        None
-    }
-
-    /// Extract price from text using regex patterns
-    fn extract_price_from_text(&self, text: &str) -> Option<String> {
-        let price_patterns = [
-            Regex::new(r"Rs\.?\s*[\d,]+").unwrap(),
-            Regex::new(r"PKR\.?\s*[\d,]+").unwrap(),
-            Regex::new(r"₨\.?\s*[\d,]+").unwrap(),
-            Regex::new(r"\d+\s*Rs").unwrap(),
-        ];
-
-        for pattern in &price_patterns {
-            if let Some(captures) = pattern.captures(text) {
-                if let Some(price_match) = captures.get(0) {
-                    return Some(price_match.as_str().trim().to_string());
-                }
-            }
-        }
-        None
-    }
-
-    /// Check if text looks like a price
-    fn looks_like_price(&self, text: &str) -> bool {
-        let price_patterns = [
-            Regex::new(r"Rs\.?\s*[\d,]+").unwrap(),
-            Regex::new(r"PKR\.?\s*[\d,]+").unwrap(),
-            Regex::new(r"₨\.?\s*[\d,]+").unwrap(),
-            Regex::new(r"\d+\s*Rs").unwrap(),
-        ];
-
-        for pattern in &price_patterns {
-            if pattern.is_match(text) {
-                return true;
-            }
-        }
-        false
-    }
-
-    /// Filter out excluded products
-    fn filter_excluded_products(&self, products: Vec<ScrapedProduct>) -> Result<Vec<ScrapedProduct>> {
-        let filtered: Vec<ScrapedProduct> = products
-            .into_iter()
-            .filter(|product| !self.is_in_excluded_section(&product.name))
-            .collect();
-        Ok(filtered)
-    }
-
-    /// Check if product is in excluded section
-    fn is_in_excluded_section(&self, text: &str) -> bool {
-        let text_lower = text.to_lowercase();
-
-        // Check excluded sections
-        for section in &self.exclusion_detector.excluded_sections {
-            if text_lower.contains(&section.to_lowercase()) {
-                return true;
-            }
-        }
-
-        // Check excluded keywords
-        for keyword in &self.exclusion_detector.excluded_keywords {
-            if text_lower.contains(&keyword.to_lowercase()) {
-                return true;
-            }
-        }
-
-        false
-    }
-
-    /// Find product candidates for ML classification
-    fn find_product_candidates(&self, html: &str) -> Vec<ProductCandidate> {
-        let mut candidates = Vec::new();
-        let document = Html::parse_document(html);
-
-        // Look for potential product elements
-        let selectors = ["div", "article", "li", "section"];
-
-        for selector_str in &selectors {
-            if let Ok(selector) = Selector::parse(selector_str) {
-                for element in document.select(&selector) {
-                    let candidate = self.element_to_candidate(element);
-                    candidates.push(candidate);
-                }
-            }
-        }
-
-        candidates
-    }
-
-    /// Convert HTML element to product candidate
-    fn element_to_candidate(&self, element: ElementRef) -> ProductCandidate {
-        let text_content = element.text().collect::<Vec<_>>().join(" ");
-        let classes: Vec<String> = element.value().classes().map(|s| s.to_string()).collect();
-        let mut attributes = HashMap::new();
-
-        for attr in element.value().attrs() {
-            attributes.insert(attr.0.to_string(), attr.1.to_string());
-        }
-
-        ProductCandidate {
-            element_html: element.html(),
-            text_content: text_content.clone(),
-            tag_name: element.value().name().to_string(),
-            classes,
-            attributes,
-            depth: self.calculate_depth(element),
-            parent_context: self.get_parent_context(element),
-            has_price_text: self.looks_like_price(&text_content),
-            has_link: element.html().contains("<a"),
-        }
-    }
-
-    /// Calculate element depth in DOM
-    fn calculate_depth(&self, element: ElementRef) -> usize {
-        let mut depth = 0;
-        let mut current = Some(element);
-
-        while let Some(elem) = current {
-            depth += 1;
-            current = elem.parent().and_then(|p| ElementRef::wrap(p));
-        }
-
-        depth
-    }
-
-    /// Get parent context classes
-    fn get_parent_context(&self, element: ElementRef) -> String {
-        if let Some(parent) = element.parent().and_then(|p| ElementRef::wrap(p)) {
-            parent.value().classes().collect::<Vec<_>>().join(" ")
-        } else {
-            String::new()
-        }
-    }
-
-    /// Convert candidate to product
-    fn candidate_to_product(
-        &self,
-        candidate: &ProductCandidate,
-        category: &str,
-        source_url: Option<String>,
-    ) -> Option<ScrapedProduct> {
-        let html = Html::parse_fragment(&candidate.element_html);
-        let root = html.root_element();
-
-        if let Some(element_ref) = ElementRef::wrap(root.first_child()?) {
-            self.extract_single_product(element_ref, category, source_url)
-        } else {
-            None
-        }
+    /// Extractor shared with standalone scraping tools (training data, ML fallback)
+    pub fn extractor(&self) -> &ProductExtractor {
+        &self.extractor
     }
 }
 
-/// Implementation for ExclusionDetector
-impl ExclusionDetector {
-    pub fn new_default() -> Self {
-        let mut excluded_keywords = HashSet::new();
-        excluded_keywords.insert("advertisement".to_string());
-        excluded_keywords.insert("sponsored".to_string());
-        excluded_keywords.insert("banner".to_string());
-        excluded_keywords.insert("footer".to_string());
-        excluded_keywords.insert("header".to_string());
-        excluded_keywords.insert("navigation".to_string());
-        excluded_keywords.insert("menu".to_string());
-
-        Self {
-            excluded_sections: vec![
-                "header".to_string(),
-                "footer".to_string(),
-                "nav".to_string(),
-                "advertisement".to_string(),
-                "sidebar".to_string(),
-            ],
-            excluded_keywords,
-        }
+#[async_trait]
+impl Fetcher for HtmlFetcher {
+    fn source_name(&self) -> &str {
+        &self.config.site.name
     }
-}
 
-/// Implementation for FeatureExtractor
-impl FeatureExtractor {
-    pub fn new() -> Self {
-        Self {
-            price_patterns: vec![
-                Regex::new(r"Rs\.?\s*[\d,]+").unwrap(),
-                Regex::new(r"PKR\.?\s*[\d,]+").unwrap(),
-                Regex::new(r"₨\.?\s*[\d,]+").unwrap(),
-                Regex::new(r"\d+\s*Rs").unwrap(),
-            ],
-            name_patterns: vec![
-                Regex::new(r"\b[A-Z][a-z]+(\s+[A-Z][a-z]+)*\b").unwrap(),
-                Regex::new(r"\d+\s*(kg|g|ml|l|pack|pcs)\b").unwrap(),
-            ],
-        }
-    }
-
-    pub fn extract_features(&self, candidate: &ProductCandidate) -> Vec<f32> {
-        let mut features = Vec::new();
-
-        // Text-based features
-        features.push(candidate.text_content.len() as f32);
-        features.push(
-            candidate
-                .text_content
-                .chars()
-                .filter(|c| c.is_uppercase())
-                .count() as f32,
-        );
-        features.push(
-            candidate
-                .text_content
-                .chars()
-                .filter(|c| c.is_numeric())
-                .count() as f32,
-        );
-        features.push(candidate.text_content.split_whitespace().count() as f32);
-
-        // Price pattern matching
-        for pattern in &self.price_patterns {
-            features.push(if pattern.is_match(&candidate.text_content) {
-                1.0
-            } else {
-                0.0
-            });
-        }
-
-        // Name pattern matching
-        for pattern in &self.name_patterns {
-            features.push(if pattern.is_match(&candidate.text_content) {
-                1.0
-            } else {
-                0.0
-            });
+    async fn fetch_all_categories(&self) -> Result<Vec<Value>> {
+        let scraped_products = HtmlFetcher::fetch_all_categories(self).await?;
+
+        // Convert through HtmlProcessor so prices are cleaned and incomplete
+        // products are dropped before they reach the JSON pipeline
+        let products = HtmlProcessor::new()
+            .with_record_context(RecordContext::new(self.config.site.name.as_str()))
+            .with_number_format(self.config.site.number_format)
+            .process_scraped_products(scraped_products)?;
+
+        // The same product often shows up on several category pages; keep one
+        // copy that lists every category it was scraped from
+        let mut products = merge_category_duplicates(products);
+        for product in &mut products {
+            if let Some(categories) = product.get(SOURCE_CATEGORY_FIELD).cloned() {
+                product["category_name"] = categories;
+            }
         }
-
-        // Structural features
-        features.push(candidate.depth as f32);
-        features.push(candidate.classes.len() as f32);
-        features.push(candidate.attributes.len() as f32);
-        features.push(if candidate.has_link { 1.0 } else { 0.0 });
-
-        // Context features
-        features.push(if candidate.parent_context.contains("product") {
-            1.0
-        } else {
-            0.0
-        });
-        features.push(if candidate.parent_context.contains("item") {
-            1.0
-        } else {
-            0.0
-        });
-        features.push(if candidate.parent_context.contains("grid") {
-            1.0
-        } else {
-            0.0
-        });
-
-        features
-    }
-}
-
-/// Convert scraped products to JSON format for unified processing
-impl ScrapedProduct {
-    pub fn to_json(&self) -> Value {
-        serde_json::json!({
-            "name": self.name,
-            "price": self.price,
-            "product_id": self.product_id,
-            "category": self.category,
-            "url": self.url,
-            "source_type": "html"
-        })
+        Ok(products)
     }
 }
@@ -1,5 +1,19 @@
+pub mod client;
+pub mod extraction;
+pub mod html_extraction;
 pub mod html_fetcher;
+pub mod product_limit;
+pub mod rate_limiter;
+pub mod shutdown;
+pub mod source_fetcher;
 pub mod unified_fetcher;
 
+pub use client::{build_client, decode_body, is_response_too_large, read_body, read_json};
+pub use extraction::extract_products;
+pub use html_extraction::*;
 pub use html_fetcher::*;
-pub use unified_fetcher::UnifiedFetcher;
\ No newline at end of file
+pub use product_limit::{ProductLimit, take_within};
+pub use rate_limiter::RateLimiter;
+pub use shutdown::Shutdown;
+pub use source_fetcher::{Fetcher, SOURCE_CATEGORY_FIELD, category_span, merge_category_duplicates, tag_source_category};
+pub use unified_fetcher::UnifiedFetcher;
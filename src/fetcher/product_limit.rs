@@ -0,0 +1,76 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// `--limit`: how many products a fetcher may still fetch across its
+/// categories. Pagination stops as soon as it is reached, so a limited run
+/// only requests the pages it keeps.
+#[derive(Debug)]
+pub struct ProductLimit {
+    max: usize,
+    fetched: AtomicUsize,
+}
+
+impl ProductLimit {
+    pub fn new(max: usize) -> Self {
+        ProductLimit { max, fetched: AtomicUsize::new(0) }
+    }
+
+    pub fn is_reached(&self) -> bool {
+        self.fetched.load(Ordering::Relaxed) >= self.max
+    }
+
+    /// Count `products` against the limit, dropping those past it. Returns
+    /// whether the limit is reached, i.e. fetching should stop.
+    pub fn take<T>(&self, products: &mut Vec<T>) -> bool {
+        let fetched = self.fetched.load(Ordering::Relaxed);
+        products.truncate(self.max.saturating_sub(fetched));
+        self.fetched.fetch_add(products.len(), Ordering::Relaxed);
+        self.is_reached()
+    }
+}
+
+/// Whether `limit`, if any, stops fetching after `products`; see `ProductLimit::take`
+pub fn take_within(limit: Option<&ProductLimit>, products: &mut Vec<impl Sized>) -> bool {
+    limit.is_some_and(|limit| limit.take(products))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Page through `pages` the way the fetchers do, returning what was kept
+    /// and how many pages were requested
+    fn paginate(pages: &[usize], limit: &ProductLimit) -> (Vec<usize>, usize) {
+        let mut kept = Vec::new();
+        let mut requested = 0;
+        for &size in pages {
+            if limit.is_reached() {
+                break;
+            }
+            requested += 1;
+            let mut products: Vec<usize> = (0..size).map(|i| kept.len() + i).collect();
+            let reached = take_within(Some(limit), &mut products);
+            kept.extend(products);
+            if reached {
+                break;
+            }
+        }
+        (kept, requested)
+    }
+
+    #[test]
+    fn test_pagination_stops_at_the_limit() {
+        let limit = ProductLimit::new(45);
+        let (kept, requested) = paginate(&[20; 10], &limit);
+        assert_eq!(kept.len(), 45);
+        assert_eq!(requested, 3);
+
+        // Later categories of the same fetcher get nothing more
+        let (kept, requested) = paginate(&[20; 10], &limit);
+        assert_eq!((kept.len(), requested), (0, 0));
+
+        // Without a limit every page is fetched
+        let mut page = vec![1, 2, 3];
+        assert!(!take_within(None, &mut page));
+        assert_eq!(page.len(), 3);
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::{Instant, sleep_until};
+
+use crate::config::RateLimitConfig;
+
+/// Token bucket for one host. `tokens` goes negative while requests are
+/// queued, each waiting until its own token has refilled.
+struct Bucket {
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(rate: f64) -> Self {
+        // Allow a burst of up to one second's worth of requests
+        let capacity = rate.max(1.0);
+        Bucket { rate, capacity, tokens: capacity, updated: Instant::now() }
+    }
+
+    /// Take a token, returning when the request may be sent
+    fn reserve(&mut self, now: Instant) -> Instant {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.updated = now;
+        self.tokens -= 1.0;
+
+        if self.tokens >= 0.0 {
+            now
+        } else {
+            now + Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+}
+
+/// Rate limiter shared by every fetcher of a run, keyed on the request host
+/// so sources don't throttle each other
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        RateLimiter { config, buckets: Mutex::new(HashMap::new()) }
+    }
+
+    /// Wait until a request to `url` fits under its host's rate.
+    /// URLs without a host are not limited.
+    pub async fn acquire(&self, url: &str) {
+        let Some(host) = request_host(url) else {
+            return;
+        };
+
+        let ready_at = {
+            let mut buckets = self.buckets.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            buckets
+                .entry(host.clone())
+                .or_insert_with(|| Bucket::new(self.config.rate_for(&host)))
+                .reserve(Instant::now())
+        };
+        sleep_until(ready_at).await;
+    }
+}
+
+/// Lowercased host of an absolute http(s) URL, without port or credentials
+pub fn request_host(url: &str) -> Option<String> {
+    let (_, rest) = url.split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host_port = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+    let host = match host_port.strip_prefix('[') {
+        // IPv6 literal, e.g. [::1]:8080
+        Some(v6) => v6.split(']').next()?,
+        None => host_port.split(':').next()?,
+    };
+    (!host.is_empty()).then(|| host.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_host() {
+        assert_eq!(request_host("https://www.Naheed.pk/groceries?page=2").as_deref(), Some("www.naheed.pk"));
+        assert_eq!(request_host("http://user:pw@localhost:9000/path").as_deref(), Some("localhost"));
+        assert_eq!(request_host("http://[::1]:8080/").as_deref(), Some("::1"));
+        assert_eq!(request_host("/relative/path"), None);
+    }
+
+    #[tokio::test]
+    async fn test_requests_are_spaced_per_host() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            requests_per_second: 20.0,
+            hosts: HashMap::from([("slow.example".to_string(), 10.0)]),
+        });
+
+        // The burst allowance goes through at once
+        let start = Instant::now();
+        for _ in 0..10 {
+            limiter.acquire("https://slow.example/a").await;
+        }
+        assert!(start.elapsed() < Duration::from_millis(50));
+
+        // Past it, concurrent requests queue at 10 per second
+        let start = Instant::now();
+        futures::future::join_all((0..3).map(|_| limiter.acquire("https://slow.example/b"))).await;
+        assert!(start.elapsed() >= Duration::from_millis(250));
+
+        // Another host has its own bucket
+        let start = Instant::now();
+        limiter.acquire("https://fast.example/").await;
+        assert!(start.elapsed() < Duration::from_millis(50));
+    }
+}
@@ -0,0 +1,155 @@
+use anyhow::{Result, anyhow};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::Notify;
+use tracing::{error, warn};
+
+/// Exit code of a run stopped by a second Ctrl-C, as for a shell killed by SIGINT
+const FORCED_EXIT_CODE: i32 = 130;
+
+/// Shared by the pipeline and its fetchers. After the first Ctrl-C (or
+/// SIGTERM) no new source or category is started, while whatever is in
+/// flight finishes and is stored; a second one exits immediately.
+#[derive(Debug, Default)]
+pub struct Shutdown {
+    requested: AtomicBool,
+    /// Wakes `wait`ers once the shutdown is requested
+    notify: Notify,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handle Ctrl-C and SIGTERM in the background for the rest of the run
+    pub fn listen() -> Arc<Self> {
+        let shutdown = Arc::new(Self::new());
+        let handle = shutdown.clone();
+        tokio::spawn(async move {
+            let mut terminate = Terminate::listen();
+            loop {
+                tokio::select! {
+                    result = tokio::signal::ctrl_c() => {
+                        if result.is_err() {
+                            return;
+                        }
+                    }
+                    _ = terminate.recv() => {}
+                }
+                if handle.request() {
+                    warn!("Stop requested, finishing the current fetch; press Ctrl-C again to exit immediately");
+                } else {
+                    error!("Second stop request received, exiting without finishing the run");
+                    std::process::exit(FORCED_EXIT_CODE);
+                }
+            }
+        });
+        shutdown
+    }
+
+    /// Ask the run to stop, returning `false` if it already was
+    pub fn request(&self) -> bool {
+        let first = !self.requested.swap(true, Ordering::SeqCst);
+        if first {
+            self.notify.notify_waiters();
+        }
+        first
+    }
+
+    /// Resolve once a shutdown is requested
+    pub async fn wait(&self) {
+        let notified = self.notify.notified();
+        tokio::pin!(notified);
+        // Registered before checking, so a request in between is not missed
+        notified.as_mut().enable();
+        if self.is_requested() {
+            return;
+        }
+        notified.await;
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+
+    /// An error once a shutdown was requested, so a fetch stopped between
+    /// categories fails instead of being stored as a complete dump
+    pub fn check(&self, source_name: &str) -> Result<()> {
+        if self.is_requested() {
+            return Err(anyhow!("Shutdown requested before all categories of {} were fetched", source_name));
+        }
+        Ok(())
+    }
+}
+
+/// SIGTERM, as sent by `docker stop`, stops a run like Ctrl-C
+#[cfg(unix)]
+struct Terminate(Option<tokio::signal::unix::Signal>);
+
+#[cfg(unix)]
+impl Terminate {
+    fn listen() -> Self {
+        use tokio::signal::unix::{SignalKind, signal};
+        Terminate(signal(SignalKind::terminate()).ok())
+    }
+
+    async fn recv(&mut self) {
+        // Without a handler, or once it is closed, only Ctrl-C stops the run
+        if let Some(signal) = &mut self.0
+            && signal.recv().await.is_some()
+        {
+            return;
+        }
+        std::future::pending().await
+    }
+}
+
+#[cfg(not(unix))]
+struct Terminate;
+
+#[cfg(not(unix))]
+impl Terminate {
+    fn listen() -> Self {
+        Terminate
+    }
+
+    async fn recv(&mut self) {
+        std::future::pending().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_the_first_request_starts_the_shutdown() {
+        let shutdown = Shutdown::new();
+        assert!(!shutdown.is_requested());
+        assert!(shutdown.check("krave_mart").is_ok());
+
+        assert!(shutdown.request());
+        assert!(shutdown.is_requested());
+        assert!(!shutdown.request());
+
+        let error = shutdown.check("krave_mart").unwrap_err();
+        assert_eq!(error.to_string(), "Shutdown requested before all categories of krave_mart were fetched");
+    }
+
+    #[tokio::test]
+    async fn test_wait_resolves_once_requested() {
+        let shutdown = Arc::new(Shutdown::new());
+        let waiter = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { shutdown.wait().await }
+        });
+        tokio::task::yield_now().await;
+        assert!(!waiter.is_finished());
+
+        shutdown.request();
+        tokio::time::timeout(std::time::Duration::from_secs(1), waiter).await.unwrap().unwrap();
+        // Waiting after the request returns straight away
+        shutdown.wait().await;
+    }
+}
@@ -0,0 +1,120 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use tracing::{Span, info_span};
+
+pub use crate::processor::json_flattener::SOURCE_CATEGORY_FIELD;
+
+/// Common interface for every data source, so the pipeline can hold a
+/// `Box<dyn Fetcher>` and run a single processing path for all of them
+#[async_trait]
+pub trait Fetcher: Send + Sync {
+    /// Name the source's raw and clean files are stored under
+    fn source_name(&self) -> &str;
+
+    /// Fetch all enabled categories as JSON records ready for `JsonFlattener`
+    async fn fetch_all_categories(&self) -> Result<Vec<Value>>;
+
+    /// Like `fetch_all_categories`, but adds products to `products` as they
+    /// are fetched so what was fetched survives the fetch being dropped, e.g.
+    /// by a source deadline. Fetchers that cannot do so add everything at
+    /// the end, and products may still need `merge_category_duplicates`.
+    async fn fetch_categories_into(&self, products: &mut Vec<Value>) -> Result<()> {
+        products.extend(self.fetch_all_categories().await?);
+        Ok(())
+    }
+}
+
+/// Span around the fetch of one category, so its log lines (and those of
+/// its `page` spans) carry the category under `--log-format json`
+pub fn category_span(category_key: &str) -> Span {
+    info_span!("category", category = %category_key)
+}
+
+/// Stamp every product with the category it was fetched under, so the
+/// origin survives once all categories are merged into one list
+pub fn tag_source_category(products: &mut [Value], category: &str) {
+    for product in products {
+        if let Some(object) = product.as_object_mut() {
+            object.insert(SOURCE_CATEGORY_FIELD.to_string(), Value::String(category.to_string()));
+        }
+    }
+}
+
+/// Collapse products listed under several categories into one record whose
+/// `_source_category` is the comma-joined list of those categories.
+/// Products without an identifier are kept as-is.
+pub fn merge_category_duplicates(products: Vec<Value>) -> Vec<Value> {
+    let mut merged: Vec<Value> = Vec::with_capacity(products.len());
+    let mut index_by_id: HashMap<String, usize> = HashMap::new();
+
+    for product in products {
+        let Some(id) = product_identity(&product) else {
+            merged.push(product);
+            continue;
+        };
+
+        match index_by_id.get(&id) {
+            Some(&index) => {
+                let Some(category) = product.get(SOURCE_CATEGORY_FIELD).and_then(|v| v.as_str()) else {
+                    continue;
+                };
+                if let Some(existing) = merged[index].as_object_mut() {
+                    let joined = match existing.get(SOURCE_CATEGORY_FIELD).and_then(|v| v.as_str()) {
+                        Some(current) if current.split(", ").any(|c| c == category) => current.to_string(),
+                        Some(current) => format!("{}, {}", current, category),
+                        None => category.to_string(),
+                    };
+                    existing.insert(SOURCE_CATEGORY_FIELD.to_string(), Value::String(joined));
+                }
+            }
+            None => {
+                index_by_id.insert(id, merged.len());
+                merged.push(product);
+            }
+        }
+    }
+
+    merged
+}
+
+/// Identifier used to recognise the same product across categories
+fn product_identity(product: &Value) -> Option<String> {
+    ["product_id", "productID", "id", "sku"]
+        .iter()
+        .find_map(|key| match product.get(*key)? {
+            Value::String(s) if !s.is_empty() => Some(format!("{}={}", key, s)),
+            Value::Number(n) => Some(format!("{}={}", key, n)),
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_products_in_several_categories_are_merged() {
+        let mut fruits = vec![
+            json!({"product_id": 1, "name": "Banana"}),
+            json!({"product_id": 2, "name": "Apple"}),
+        ];
+        let mut deals = vec![
+            json!({"product_id": 1, "name": "Banana"}),
+            json!({"name": "No id"}),
+        ];
+        tag_source_category(&mut fruits, "Fresh Fruits");
+        tag_source_category(&mut deals, "Flash Deals");
+
+        let mut all = fruits;
+        all.extend(deals);
+        let merged = merge_category_duplicates(all);
+
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged[0][SOURCE_CATEGORY_FIELD], "Fresh Fruits, Flash Deals");
+        assert_eq!(merged[1][SOURCE_CATEGORY_FIELD], "Fresh Fruits");
+        assert_eq!(merged[2][SOURCE_CATEGORY_FIELD], "Flash Deals");
+    }
+}
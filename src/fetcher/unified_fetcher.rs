@@ -1,45 +1,181 @@
-use anyhow::{Result, anyhow};
+use anyhow::{Context, Result, anyhow};
+use async_trait::async_trait;
 use serde_json::Value;
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::sleep;
-use tracing::{error, info, warn};
-use wreq::{Client, Response};
-use wreq_util::Emulation;
+use tracing::{Instrument, error, info, info_span, warn};
+use wreq::{Client, RequestBuilder, Response};
 
-use crate::config::ApiConfig;
+use crate::config::{ApiAuth, ApiConfig, AuthLocation};
+use crate::fetcher::{
+    Fetcher, ProductLimit, RateLimiter, Shutdown, build_client, category_span, extract_products, is_response_too_large, merge_category_duplicates, read_json, tag_source_category,
+    take_within,
+};
+use crate::metrics::SourceMetrics;
+use crate::processor::RecordContext;
 
 pub struct UnifiedFetcher {
     client: Client,
     config: ApiConfig,
+    /// Credential sent with every request, resolved once from the config
+    auth: Option<ApiAuth>,
+    /// Store / warehouse this fetcher is bound to, `None` for the default one
+    store: Option<String>,
+    storage_name: String,
+    /// Shared with the run's other fetchers; requests are unthrottled without one
+    rate_limiter: Option<Arc<RateLimiter>>,
+    /// Checked before each category, see `Shutdown::check`
+    shutdown: Option<Arc<Shutdown>>,
+    /// `--limit` on the products fetched across categories
+    limit: Option<ProductLimit>,
+    /// Counts every request by its response status
+    metrics: Option<SourceMetrics>,
 }
 
 impl UnifiedFetcher {
     pub fn new(config: ApiConfig) -> Result<Self> {
-        let client = Client::builder().emulation(Emulation::Firefox136).build()?;
+        Self::new_with_env(config, |var| std::env::var(var).ok())
+    }
+
+    /// `new`, reading the credential from `env` instead of the process
+    /// environment, see `ApiConfig::auth_with_env`
+    pub fn new_with_env(config: ApiConfig, env: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let client = build_client(
+            config.api.emulation.as_deref(),
+            config.api.user_agent.as_deref(),
+        )?;
+        let storage_name = config.api.name.clone();
+        let auth = config.auth_with_env(env)?;
+
+        Ok(UnifiedFetcher {
+            client,
+            config,
+            auth,
+            store: None,
+            storage_name,
+            rate_limiter: None,
+            shutdown: None,
+            limit: None,
+            metrics: None,
+        })
+    }
+
+    /// Acquire from `limiter` before every request
+    pub fn with_rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Stop between categories once `shutdown` is requested
+    pub fn with_shutdown(mut self, shutdown: Arc<Shutdown>) -> Self {
+        self.shutdown = Some(shutdown);
+        self
+    }
 
-        Ok(UnifiedFetcher { client, config })
+    /// Stop fetching once `max` products were fetched across categories
+    pub fn with_limit(mut self, max: usize) -> Self {
+        self.limit = Some(ProductLimit::new(max));
+        self
+    }
+
+    /// Count every request in `metrics`
+    pub fn with_metrics(mut self, metrics: SourceMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    fn limit_reached(&self) -> bool {
+        let reached = self.limit.as_ref().is_some_and(ProductLimit::is_reached);
+        if reached {
+            info!("Reached --limit for {}, not fetching further categories", self.storage_name);
+        }
+        reached
+    }
+
+    fn check_shutdown(&self) -> Result<()> {
+        match &self.shutdown {
+            Some(shutdown) => shutdown.check(&self.storage_name),
+            None => Ok(()),
+        }
+    }
+
+    /// Fetcher bound to a single store, stored under `{api}_{store}`
+    pub fn for_store(config: ApiConfig, store: &str) -> Result<Self> {
+        let mut fetcher = Self::new(config)?;
+        fetcher.storage_name = fetcher.config.storage_name_for_store(Some(store));
+        fetcher.store = Some(store.to_string());
+        Ok(fetcher)
+    }
+
+    /// One fetcher per configured store, or a single default fetcher if none are configured
+    pub fn for_each_store(config: ApiConfig) -> Result<Vec<Self>> {
+        if config.api.stores.is_empty() {
+            return Ok(vec![Self::new(config)?]);
+        }
+
+        config
+            .api
+            .stores
+            .iter()
+            .map(|store| Self::for_store(config.clone(), store))
+            .collect()
     }
 
     pub async fn fetch_all_categories(&self) -> Result<Vec<Value>> {
         let mut all_data = Vec::new();
+        self.fetch_categories_into(&mut all_data).await?;
+
+        let fetched = all_data.len();
+        let all_data = merge_category_duplicates(all_data);
+        if all_data.len() < fetched {
+            info!(
+                "Merged {} products listed under several categories",
+                fetched - all_data.len()
+            );
+        }
+        Ok(all_data)
+    }
+
+    /// Add each category's products to `all_data` as soon as it is fetched,
+    /// so the categories fetched so far survive the fetch being cut short.
+    /// Products listed under several categories are not merged yet.
+    pub async fn fetch_categories_into(&self, all_data: &mut Vec<Value>) -> Result<()> {
+        // fetched_at is added from the raw key when processing, so unchanged
+        // dumps stay byte-identical and are not stored again
+        let context = RecordContext::new(self.storage_name.as_str());
+
+        if self.store.is_some() && self.config.request.method != "GET" {
+            warn!(
+                "Stores are only substituted into GET category URLs, fetching {} without a store",
+                self.config.api.name
+            );
+        }
 
         match self.config.request.method.as_str() {
             "GET" => {
-                let category_urls = self.config.build_category_urls();
+                let category_urls = self.config.build_category_urls_for_store(self.store.as_deref());
                 for (category_key, url) in category_urls {
+                    self.check_shutdown()?;
+                    if self.limit_reached() {
+                        break;
+                    }
                     info!("Fetching GET category: {}", category_key);
 
                     // Check if pagination is disabled
                     let data = if self.config.pagination.r#type == "none" {
-                        match self.fetch_get_single(&url).await {
-                            Ok(data) => data,
+                        match self.fetch_get_single(&url).instrument(category_span(&category_key)).await {
+                            Ok(mut data) => {
+                                take_within(self.limit.as_ref(), &mut data);
+                                data
+                            }
                             Err(e) => {
                                 error!("Failed to fetch category {}: {}", category_key, e);
                                 continue;
                             }
                         }
                     } else {
-                        match self.fetch_get_paginated(&url).await {
+                        match self.fetch_get_paginated(&url).instrument(category_span(&category_key)).await {
                             Ok(data) => data,
                             Err(e) => {
                                 error!("Failed to fetch category {}: {}", category_key, e);
@@ -49,6 +185,10 @@ impl UnifiedFetcher {
                     };
 
                     info!("Fetched {} products from {}", data.len(), category_key);
+                    let mut data = data;
+                    tag_source_category(&mut data, self.category_display_name(&category_key));
+                    context.for_category(&category_key).stamp(&mut data);
+                    self.tag_store(&mut data);
                     all_data.extend(data);
                 }
             }
@@ -56,12 +196,20 @@ impl UnifiedFetcher {
                 // Check if this is a GraphQL API
                 if self.config.request.graphql_query.is_some() {
                     // GraphQL API (like Pandamart)
-                    for (category_key, category) in &self.config.categories {
+                    for (category_key, category) in self.config.selected_categories() {
+                        self.check_shutdown()?;
+                        if self.limit_reached() {
+                            break;
+                        }
                         if let Some(ref category_id) = category.category_id {
                             info!("Fetching GraphQL category: {}", category_key);
-                            match self.fetch_graphql_single(category_id).await {
-                                Ok(data) => {
+                            match self.fetch_graphql_single(category_id).instrument(category_span(category_key)).await {
+                                Ok(mut data) => {
+                                    take_within(self.limit.as_ref(), &mut data);
                                     info!("Fetched {} products from {}", data.len(), category_key);
+                                    tag_source_category(&mut data, &category.name);
+                                    context.for_category(category_key).stamp(&mut data);
+                                    self.tag_store(&mut data);
                                     all_data.extend(data);
                                 }
                                 Err(e) => {
@@ -77,10 +225,17 @@ impl UnifiedFetcher {
                     // Regular POST API (like BazaarApp)
                     let category_slugs = self.config.get_category_slugs();
                     for (category_key, category_slug) in category_slugs {
+                        self.check_shutdown()?;
+                        if self.limit_reached() {
+                            break;
+                        }
                         info!("Fetching POST category: {}", category_key);
-                        match self.fetch_post_paginated(&category_slug).await {
-                            Ok(data) => {
+                        match self.fetch_post_paginated(&category_slug).instrument(category_span(&category_key)).await {
+                            Ok(mut data) => {
                                 info!("Fetched {} products from {}", data.len(), category_key);
+                                tag_source_category(&mut data, self.category_display_name(&category_key));
+                                context.for_category(&category_key).stamp(&mut data);
+                                self.tag_store(&mut data);
                                 all_data.extend(data);
                             }
                             Err(e) => {
@@ -98,7 +253,23 @@ impl UnifiedFetcher {
             }
         }
 
-        Ok(all_data)
+        Ok(())
+    }
+
+    /// Record the store products were fetched for, if the fetcher has one
+    fn tag_store(&self, products: &mut [Value]) {
+        if let Some(ref store) = self.store {
+            tag_store_id(products, store);
+        }
+    }
+
+    /// Configured display name of a category, falling back to its key
+    fn category_display_name<'a>(&'a self, category_key: &'a str) -> &'a str {
+        self.config
+            .categories
+            .get(category_key)
+            .map(|category| category.name.as_str())
+            .unwrap_or(category_key)
     }
 
     // Method for single GET requests (no pagination)
@@ -114,14 +285,9 @@ impl UnifiedFetcher {
         };
 
         // Parse JSON response
-        let data: Value = match response.json().await {
-            Ok(json) => json,
-            Err(e) => {
-                return Err(anyhow!("Failed to parse JSON response from {}: {}", url, e));
-            }
-        };
+        let data = self.read_json(response).await.with_context(|| format!("Failed to parse JSON response from {}", url))?;
 
-        let products = self.extract_products(&data)?;
+        let products = self.extract_products(&data);
         info!("Found {} products in single request", products.len());
 
         Ok(products)
@@ -133,8 +299,15 @@ impl UnifiedFetcher {
         let mut consecutive_empty_pages = 0;
         let max_consecutive_empty = 2; // Stop after 2 consecutive empty responses
         let max_pages = 50; // Safety limit to prevent infinite loops
+        // Read from the first page when the API reports its total
+        let mut total_pages: Option<u32> = None;
 
         loop {
+            if let Some(total) = total_pages
+                && page > total
+            {
+                break;
+            }
             // Safety check to prevent infinite loops
             if page > max_pages {
                 warn!(
@@ -144,11 +317,14 @@ impl UnifiedFetcher {
                 break;
             }
 
-            let paginated_url = format!("{}?page={}", url, page);
-            info!("Fetching GET page {} from: {}", page, paginated_url);
+            let paginated_url = format!("{}?{}", url, self.config.pagination.page_query(page));
+            match total_pages {
+                Some(total) => info!("Fetching GET page {}/{} from: {}", page, total, paginated_url),
+                None => info!("Fetching GET page {} from: {}", page, paginated_url),
+            }
 
             // Handle potential API errors gracefully
-            let response = match self.fetch_with_get(&paginated_url).await {
+            let response = match self.fetch_with_get(&paginated_url).instrument(info_span!("page", page)).await {
                 Ok(resp) => resp,
                 Err(e) => {
                     warn!(
@@ -167,8 +343,10 @@ impl UnifiedFetcher {
             };
 
             // Parse JSON response
-            let data: Value = match response.json().await {
+            let data: Value = match self.read_json(response).await {
                 Ok(json) => json,
+                // The next page would most likely be as large
+                Err(e) if is_response_too_large(&e) => return Err(e),
                 Err(e) => {
                     warn!(
                         "Failed to parse JSON response for page {} from {}: {}",
@@ -185,7 +363,17 @@ impl UnifiedFetcher {
                 }
             };
 
-            let products = self.extract_products(&data)?;
+            let products = self.extract_products(&data);
+
+            if page == 1
+                && let Some(total) = self.config.pagination.total_pages(&data, products.len())
+            {
+                if total > max_pages {
+                    warn!("{} reports {} pages, only fetching the first {}", url, total, max_pages);
+                }
+                info!("{} has {} pages", url, total);
+                total_pages = Some(total.min(max_pages));
+            }
 
             if products.is_empty() {
                 consecutive_empty_pages += 1;
@@ -205,7 +393,14 @@ impl UnifiedFetcher {
                 // Reset consecutive empty counter when we find products
                 consecutive_empty_pages = 0;
                 info!("Found {} products on page {}", products.len(), page);
+                let mut products = products;
+                let reached = take_within(self.limit.as_ref(), &mut products);
                 all_products.extend(products);
+                if reached {
+                    info!("Reached --limit, stopping pagination");
+                    page += 1;
+                    break;
+                }
             }
 
             page += 1;
@@ -245,7 +440,7 @@ impl UnifiedFetcher {
             let request_body = self.build_post_request_body(category_slug, page)?;
 
             // Handle potential API errors gracefully
-            let response = match self.fetch_with_post(&request_body).await {
+            let response = match self.fetch_with_post(&request_body).instrument(info_span!("page", page)).await {
                 Ok(resp) => resp,
                 Err(e) => {
                     warn!(
@@ -267,8 +462,10 @@ impl UnifiedFetcher {
             };
 
             // Parse JSON response
-            let data: Value = match response.json().await {
+            let data: Value = match self.read_json(response).await {
                 Ok(json) => json,
+                // The next page would most likely be as large
+                Err(e) if is_response_too_large(&e) => return Err(e),
                 Err(e) => {
                     warn!(
                         "Failed to parse JSON response for page {} of category {}: {}",
@@ -288,7 +485,7 @@ impl UnifiedFetcher {
                 }
             };
 
-            let products = self.extract_products(&data)?;
+            let products = self.extract_products(&data);
 
             if products.is_empty() {
                 consecutive_empty_pages += 1;
@@ -313,7 +510,14 @@ impl UnifiedFetcher {
                     page,
                     category_slug
                 );
+                let mut products = products;
+                let reached = take_within(self.limit.as_ref(), &mut products);
                 all_products.extend(products);
+                if reached {
+                    info!("Reached --limit, stopping pagination for category {}", category_slug);
+                    page += 1;
+                    break;
+                }
             }
 
             page += 1;
@@ -351,18 +555,12 @@ impl UnifiedFetcher {
         };
 
         // Parse JSON response
-        let data: Value = match response.json().await {
-            Ok(json) => json,
-            Err(e) => {
-                return Err(anyhow!(
-                    "Failed to parse GraphQL JSON response for category {}: {}",
-                    category_id,
-                    e
-                ));
-            }
-        };
+        let data = self
+            .read_json(response)
+            .await
+            .with_context(|| format!("Failed to parse GraphQL JSON response for category {}", category_id))?;
 
-        let products = self.extract_products(&data)?;
+        let products = self.extract_products(&data);
         info!(
             "Found {} products in GraphQL request for category {}",
             products.len(),
@@ -372,20 +570,23 @@ impl UnifiedFetcher {
         Ok(products)
     }
 
+    /// A response's JSON body, at most `api.max_response_bytes` of it
+    async fn read_json(&self, response: Response) -> Result<Value> {
+        read_json(response, self.config.api.max_response_bytes).await
+    }
+
     async fn fetch_with_get(&self, url: &str) -> Result<Response> {
         let mut request = self.client.get(url);
 
-        // Add authorization if configured
-        if let Some(ref auth) = self.config.request.authorization {
-            request = request.header("Authorization", auth);
-        }
+        request = self.authorize(request);
 
         // Add any additional headers
         for (key, value) in &self.config.request.headers {
             request = request.header(key, value);
         }
 
-        let response = request.send().await?;
+        self.wait_for_rate_limit(url).await;
+        let response = self.send(request).await?;
 
         if !response.status().is_success() {
             return Err(anyhow!("HTTP error: {}", response.status()));
@@ -403,17 +604,15 @@ impl UnifiedFetcher {
             .header("Content-Type", "application/json")
             .json(request_body);
 
-        // Add authorization if configured
-        if let Some(ref auth) = self.config.request.authorization {
-            request = request.header("Authorization", auth);
-        }
+        request = self.authorize(request);
 
         // Add any additional headers
         for (key, value) in &self.config.request.headers {
             request = request.header(key, value);
         }
 
-        let response = request.send().await?;
+        self.wait_for_rate_limit(&url).await;
+        let response = self.send(request).await?;
 
         if !response.status().is_success() {
             return Err(anyhow!("HTTP error: {}", response.status()));
@@ -422,13 +621,39 @@ impl UnifiedFetcher {
         Ok(response)
     }
 
+    /// Add the source's credential as a header or query parameter, if it has one
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.auth {
+            Some(auth) if auth.location == AuthLocation::Query => request.query(&[(auth.name.as_str(), auth.value.as_str())]),
+            Some(auth) => request.header(auth.name.as_str(), auth.value.as_str()),
+            None => request,
+        }
+    }
+
+    /// Send `request`, leaving the URL out of errors when it carries the
+    /// credential so the key never reaches the logs
+    async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let key_in_url = self.auth.as_ref().is_some_and(|auth| auth.location == AuthLocation::Query);
+        let response = request.send().await;
+        if let Some(metrics) = &self.metrics {
+            metrics.http_request(response.as_ref().ok().map(|response| response.status().as_u16()));
+        }
+        response.map_err(|e| if key_in_url { e.without_url() } else { e }.into())
+    }
+
+    async fn wait_for_rate_limit(&self, url: &str) {
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire(url).await;
+        }
+    }
+
     fn build_post_request_body(&self, category_slug: &str, page: i32) -> Result<Value> {
         // Build request body matching BazaarApp's expected structure
         let body = serde_json::json!({
-            "productChannel": self.config.request.product_channel.as_ref().unwrap_or(&"WEB_APP".to_string()),
+            "productChannel": self.config.post_product_channel(),
             "paginationRequestDTO": {
                 "page": page,
-                "size": self.config.request.page_size.unwrap_or(20)
+                "size": self.config.post_page_size()
             },
             "searchKey": "",
             "brandIds": [],
@@ -470,104 +695,89 @@ impl UnifiedFetcher {
         Ok(body)
     }
 
-    fn extract_products(&self, data: &Value) -> Result<Vec<Value>> {
-        // Try different extraction patterns based on configuration
-        if let Some(ref extraction_path) = self.config.response.data_path {
-            return self.extract_by_path(data, extraction_path);
-        }
-
-        // Fallback to common patterns
-        self.extract_by_common_patterns(data)
+    fn extract_products(&self, data: &Value) -> Vec<Value> {
+        extract_products(data, self.config.response.data_path.as_ref())
     }
+}
 
-    fn extract_by_path(&self, data: &Value, path: &str) -> Result<Vec<Value>> {
-        let path_parts: Vec<&str> = path.split('.').collect();
-        let mut current = data;
-
-        for part in path_parts {
-            if part.ends_with("[]") {
-                // Array access
-                let field = &part[..part.len() - 2];
-                if let Some(array) = current.get(field).and_then(|v| v.as_array()) {
-                    return Ok(array.clone());
-                } else {
-                    return Ok(Vec::new());
-                }
-            } else {
-                // Object access
-                current = current.get(part).unwrap_or(&Value::Null);
-            }
-        }
-
-        if let Some(array) = current.as_array() {
-            Ok(array.clone())
-        } else {
-            Ok(Vec::new())
+/// Add the store ID to every product so stores stay distinguishable after flattening
+fn tag_store_id(products: &mut [Value], store: &str) {
+    for product in products {
+        if let Some(object) = product.as_object_mut() {
+            object.insert("store_id".to_string(), Value::String(store.to_string()));
         }
     }
+}
 
-    fn extract_by_common_patterns(&self, data: &Value) -> Result<Vec<Value>> {
-        // Pattern 1: Direct array (BazaarApp style)
-        if let Some(products_array) = data.as_array() {
-            return Ok(products_array.clone());
-        }
+#[async_trait]
+impl Fetcher for UnifiedFetcher {
+    fn source_name(&self) -> &str {
+        &self.storage_name
+    }
 
-        // Pattern 2: KraveMart style - data[].l2_products[]
-        if let Some(data_array) = data.get("data").and_then(|d| d.as_array()) {
-            let mut all_products = Vec::new();
-            for item in data_array {
-                if let Some(l2_products) = item.get("l2_products").and_then(|p| p.as_array()) {
-                    all_products.extend(l2_products.clone());
-                } else if let Some(krave_mart_products) =
-                    item.get("krave_mart_products").and_then(|p| p.as_array())
-                {
-                    all_products.extend(krave_mart_products.clone());
-                }
-            }
-            return Ok(all_products);
-        }
+    async fn fetch_all_categories(&self) -> Result<Vec<Value>> {
+        UnifiedFetcher::fetch_all_categories(self).await
+    }
 
-        // Pattern 3: Simple products field
-        if let Some(products) = data.get("products").and_then(|p| p.as_array()) {
-            return Ok(products.clone());
-        }
+    async fn fetch_categories_into(&self, products: &mut Vec<Value>) -> Result<()> {
+        UnifiedFetcher::fetch_categories_into(self, products).await
+    }
+}
 
-        // Pattern 4: Items field
-        if let Some(items) = data.get("items").and_then(|i| i.as_array()) {
-            return Ok(items.clone());
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::api_config::test_credentials;
+    use serde_json::json;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_extracts_with_the_configured_data_path() {
+        let fetcher = UnifiedFetcher::new_with_env(ApiConfig::from_file("src/configs/krave_mart.toml").unwrap(), test_credentials).unwrap();
+        let response = json!({
+            "data": [
+                {"l2_products": [{"sku": "A"}, {"sku": "B"}]},
+                {"krave_mart_products": [{"sku": "C"}]}
+            ],
+            "featured": [{"sku": "D"}]
+        });
+        let products = fetcher.extract_products(&response);
+        let skus: Vec<&str> = products.iter().map(|product| product["sku"].as_str().unwrap()).collect();
+        assert_eq!(skus, vec!["A", "B", "C"]);
+    }
 
-        // Pattern 5: Pandamart GraphQL style - data.categoryProductList.categoryProducts[].items[]
-        if let Some(category_products) = data
-            .get("data")
-            .and_then(|d| d.get("categoryProductList"))
-            .and_then(|cpl| cpl.get("categoryProducts"))
-            .and_then(|cp| cp.as_array())
-        {
-            let mut all_products = Vec::new();
-            for category in category_products {
-                if let Some(items) = category.get("items").and_then(|i| i.as_array()) {
-                    // Add category name to each product for better tracking
-                    let category_name = category
-                        .get("name")
-                        .and_then(|n| n.as_str())
-                        .unwrap_or("Unknown");
-                    for mut item in items.clone() {
-                        if let Some(item_obj) = item.as_object_mut() {
-                            item_obj.insert(
-                                "category_section".to_string(),
-                                serde_json::Value::String(category_name.to_string()),
-                            );
-                        }
-                        all_products.push(item);
-                    }
+    #[tokio::test]
+    async fn test_pages_smaller_than_the_limit_are_all_fetched() {
+        // 5 products, served 2 to a page whatever limit is asked for
+        let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = server.local_addr().unwrap();
+        let requested = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let paths = requested.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = server.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let read = stream.read(&mut buffer).await.unwrap();
+                    request.extend_from_slice(&buffer[..read]);
                 }
+                let request = String::from_utf8(request).unwrap();
+                let path = request.split_whitespace().nth(1).unwrap().to_string();
+                paths.lock().unwrap().push(path.clone());
+                let page: usize = path.split("page=").nth(1).unwrap().split('&').next().unwrap().parse().unwrap();
+                let skus: Vec<Value> = ((page - 1) * 2..(page * 2).min(5)).map(|sku| json!({"sku": sku.to_string()})).collect();
+                let body = json!({"count": 5, "data": [{"l2_products": skus}]}).to_string();
+                let response = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body);
+                stream.write_all(response.as_bytes()).await.unwrap();
             }
-            return Ok(all_products);
-        }
+        });
 
-        // If no pattern matches, return empty
-        warn!("No products found in response structure");
-        Ok(Vec::new())
+        let fetcher = UnifiedFetcher::new_with_env(ApiConfig::from_file("src/configs/krave_mart.toml").unwrap(), test_credentials).unwrap();
+        let products = fetcher.fetch_get_paginated(&format!("http://{}/products", addr)).await.unwrap();
+        let skus: Vec<&str> = products.iter().map(|product| product["sku"].as_str().unwrap()).collect();
+        assert_eq!(skus, vec!["0", "1", "2", "3", "4"]);
+        assert_eq!(requested.lock().unwrap().as_slice(), ["/products?page=1&limit=100", "/products?page=2&limit=100", "/products?page=3&limit=100"]);
     }
 }
@@ -1,5 +1,8 @@
 pub mod config;
 pub mod fetcher;
+pub mod metrics;
 pub mod models;
+pub mod notify;
 pub mod processor;
+pub mod sink;
 pub mod storage;
@@ -0,0 +1,242 @@
+use anyhow::{Context as _, Result};
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::{Event, Subscriber};
+use tracing_appender::rolling::{self, RollingFileAppender};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+use crate::cli::{LogArgs, LogFormat};
+
+/// Span field naming the source a log line belongs to, set by `run_sources`
+const SOURCE_FIELD: &str = "source";
+
+/// Install the global subscriber: `--log-format` lines on stdout, filtered by
+/// `RUST_LOG` (INFO by default), and with `--log-dir` a file per source
+pub fn init(args: &LogArgs) -> Result<()> {
+    let files = match &args.log_dir {
+        Some(dir) => {
+            std::fs::create_dir_all(dir).with_context(|| format!("Failed to create log dir {}", dir))?;
+            Some(SourceFiles::new(dir.into(), args.log_format))
+        }
+        None => None,
+    };
+    tracing_subscriber::registry()
+        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .with(stdout_layer(args.log_format, std::io::stdout))
+        .with(files)
+        .try_init()
+        .context("Failed to initialize logging")
+}
+
+/// The stdout layer for `format`. JSON lines carry the current span and the
+/// full span list, so every line of a fetch names its source, category and page.
+fn stdout_layer<S, W>(format: LogFormat, writer: W) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    match format {
+        LogFormat::Pretty => tracing_subscriber::fmt::layer().with_writer(writer).boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer().json().with_current_span(true).with_span_list(true).with_writer(writer).boxed(),
+    }
+}
+
+/// Name of the source a span was opened for, kept in its extensions
+struct SourceName(String);
+
+/// Writes the events inside each source's span to `<dir>/<source>.log`,
+/// rolled daily. Events outside any source only go to stdout.
+struct SourceFiles {
+    dir: PathBuf,
+    format: LogFormat,
+    files: Mutex<HashMap<String, RollingFileAppender>>,
+}
+
+impl SourceFiles {
+    fn new(dir: PathBuf, format: LogFormat) -> Self {
+        SourceFiles { dir, format, files: Mutex::new(HashMap::new()) }
+    }
+
+    fn line(&self, event: &Event<'_>, source: &str) -> String {
+        let mut fields = FieldMap::default();
+        event.record(&mut fields);
+        let metadata = event.metadata();
+        let timestamp = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true);
+        match self.format {
+            LogFormat::Json => {
+                let line = json!({
+                    "timestamp": timestamp,
+                    "level": metadata.level().as_str(),
+                    "target": metadata.target(),
+                    SOURCE_FIELD: source,
+                    "fields": fields.0,
+                });
+                format!("{}\n", line)
+            }
+            LogFormat::Pretty => {
+                let message = fields.0.remove("message").and_then(|message| message.as_str().map(str::to_string)).unwrap_or_default();
+                let extra: String = fields.0.iter().map(|(name, value)| format!(" {}={}", name, value)).collect();
+                format!("{} {:>5} {}: {}{}\n", timestamp, metadata.level(), metadata.target(), message, extra)
+            }
+        }
+    }
+}
+
+impl<S> Layer<S> for SourceFiles
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut fields = FieldMap::default();
+        attrs.record(&mut fields);
+        let Some(Value::String(source)) = fields.0.remove(SOURCE_FIELD) else {
+            return;
+        };
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SourceName(source));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let Some(source) = ctx
+            .event_scope(event)
+            .and_then(|scope| scope.into_iter().find_map(|span| span.extensions().get::<SourceName>().map(|name| name.0.clone())))
+        else {
+            return;
+        };
+        let line = self.line(event, &source);
+        let mut files = self.files.lock().unwrap();
+        let file = files.entry(source).or_insert_with_key(|source| rolling::daily(&self.dir, format!("{}.log", source)));
+        // A log line that cannot be written has nowhere better to be reported
+        let _ = file.write_all(line.as_bytes());
+    }
+}
+
+/// Collects the fields of a span or event as JSON values
+#[derive(Default)]
+struct FieldMap(Map<String, Value>);
+
+impl Visit for FieldMap {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name().to_string(), json!(format!("{:?}", value)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tracing::{info, info_span};
+
+    /// In-memory log sink
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(bytes)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'w> MakeWriter<'w> for Buffer {
+        type Writer = Buffer;
+
+        fn make_writer(&'w self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    fn json_lines(text: &str) -> Vec<Value> {
+        text.lines().map(|line| serde_json::from_str(line).unwrap_or_else(|e| panic!("unparseable line {:?}: {}", line, e))).collect()
+    }
+
+    fn log_a_fetch() {
+        let source = info_span!("source", source = "naheed");
+        let _source = source.enter();
+        let category = info_span!("category", category = "fruits_veg");
+        let _category = category.enter();
+        info_span!("page", page = 2u32).in_scope(|| info!(products = 20, "Fetched page"));
+    }
+
+    #[test]
+    fn test_json_lines_carry_the_source() {
+        let stdout = Buffer::default();
+        let subscriber = tracing_subscriber::registry().with(stdout_layer(LogFormat::Json, stdout.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            log_a_fetch();
+            info!("Outside any source");
+        });
+
+        let lines = json_lines(&String::from_utf8(stdout.0.lock().unwrap().clone()).unwrap());
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["fields"]["message"], "Fetched page");
+        assert_eq!(lines[0]["span"]["page"], 2);
+        let spans = lines[0]["spans"].as_array().unwrap();
+        assert_eq!(spans[0]["source"], "naheed");
+        assert_eq!(spans[1]["category"], "fruits_veg");
+        assert!(lines[1].get("spans").is_none());
+    }
+
+    #[test]
+    fn test_source_files() {
+        let dir = std::env::temp_dir().join(format!("pipeline-logs-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let subscriber = tracing_subscriber::registry().with(SourceFiles::new(dir.clone(), LogFormat::Json));
+        tracing::subscriber::with_default(subscriber, || {
+            log_a_fetch();
+            info_span!("source", source = "dealcart").in_scope(|| info!("Fetched category"));
+            info!("Outside any source");
+        });
+
+        let mut logs: Vec<(String, Vec<Value>)> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| {
+                let path = entry.unwrap().path();
+                (path.file_name().unwrap().to_string_lossy().to_string(), json_lines(&std::fs::read_to_string(&path).unwrap()))
+            })
+            .collect();
+        logs.sort_by(|a, b| a.0.cmp(&b.0));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(logs.len(), 2);
+        assert!(logs[0].0.starts_with("dealcart.log"));
+        assert!(logs[1].0.starts_with("naheed.log"));
+        let naheed = &logs[1].1;
+        assert_eq!(naheed.len(), 1);
+        assert_eq!((&naheed[0]["source"], &naheed[0]["fields"]["products"]), (&json!("naheed"), &json!(20)));
+    }
+}
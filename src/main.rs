@@ -1,60 +1,92 @@
 use anyhow::{Context, Result};
-use config::{ApiConfig, HtmlConfig, MinioConfig};
+use cli::{CleanupArgs, Cli, Command, ConfigArgs, DiffArgs, HistoryArgs, PipelineArgs, PipelineMode, SOURCES_FILE, ServeArgs, SinkKind, SourceArgs};
+use config::{AnomalyConfig, ApiConfig, BatchConfig, DatabaseConfig, DeadlineConfig, HtmlConfig, MatcherConfig, MinioConfig, NormalizerConfig, NotifyConfig, OutputConfig, RateLimitConfig, SourcesConfig, check_configs, choose_batch_size, parse_category_list};
 use dotenv;
-use fetcher::{UnifiedFetcher, HtmlFetcher};
+use fetcher::{Fetcher, HtmlFetcher, RateLimiter, Shutdown, UnifiedFetcher, merge_category_duplicates};
+use metrics::{Metrics, SourceMetrics, bind_metrics, serve_metrics};
+use notify::WebhookNotifier;
 use polars::prelude::*;
-use processor::{FieldClassifier, JsonFlattener, HtmlProcessor, RuleNormalizer};
-use storage::MinioStorage;
+use processor::{
+    Anomaly, AnomalyDetector, ClassificationReport, ColumnModel, DatasetMerger, DedupStep, DedupStrategy, DiffTarget, ExtractionFailure, FieldClassifier, JsonFlattener, MergeManifest, NameRules,
+    Pipeline, Processor, ProductCounts, ProductFilter, ProductMatcher, RAW_JSON_FIELD, RecordContext, RejectedRecord, RuleNormalizer, RunProvenance, RunReport, SchemaValidator, SnapshotStats, price_history, snapshot_diff, write_history,
+    canonical_order, drop_out_of_stock, encode_parquet, encode_parquet_with_metadata, hash_config_dir,
+};
+use sink::DatabaseSink;
+use scheduler::{Clock, InFlight, RunGuard, RunState, SourceTimer, StatusFile, SystemClock, sleep_until};
+use source_tasks::{ProcessedStore, SourceOutcome, StoreResult, run_sources_with_retry};
+use storage::{LimitedFetch, MinioStorage, OutputFormat, RawSnapshot, RawStoreOutcome};
 use tracing::{info, warn, error};
-use tracing_subscriber;
-use std::path::Path;
-use std::env;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
-mod config;
-mod fetcher;
-mod models;
-mod processor;
-mod storage;
+mod cli;
+mod logging;
+mod scheduler;
+mod source_tasks;
+
+use data_pipeline::{config, fetcher, metrics, notify, processor, sink, storage};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize logging
-    tracing_subscriber::fmt::init();
+    let cli = Cli::parse_with_sources();
+    logging::init(&cli.logging)?;
 
     // Load environment variables
     dotenv::dotenv().ok();
 
-    // Parse command line arguments
-    let args: Vec<String> = env::args().collect();
-    let from_storage = args.iter().any(|arg| arg == "--from-storage" || arg == "-s");
-
-    // Check for specific source argument
-    let specific_source = args.iter()
-        .position(|arg| arg == "--source")
-        .and_then(|pos| args.get(pos + 1))
-        .map(|s| s.as_str());
+    // The pipeline runs in two halves that can be scheduled separately:
+    // `fetch` stores every source's raw JSON and `process` turns the stored
+    // raw JSON into clean data. `run` does both.
+    match cli.into_command()? {
+        Command::Fetch(args) => run_once(args.into()).await,
+        Command::Process(args) => run_once(args.into()).await,
+        Command::Run(args) => run_once(args.into()).await,
+        Command::Reprocess(args) => run_once(args.into()).await,
+        Command::Diff(args) => diff_snapshots(&args).await,
+        Command::History(args) => collect_price_history(&args).await,
+        Command::Cleanup(args) => cleanup_storage(&args).await,
+        Command::CheckStorage(config) => check_storage(&config.config_dir).await,
+        Command::ValidateConfig(config) => validate_config(&config.config_dir),
+        Command::Serve(args) => serve(args).await,
+    }
+}
 
-    if from_storage {
-        info!("🚀 Starting Multi-Source Data Pipeline (Processing from S3/MinIO Storage)");
-    } else {
-        info!("🚀 Starting Multi-Source Data Pipeline (Fetching from APIs)");
+/// One run of the pipeline, stopped early by Ctrl-C or SIGTERM. Its metrics
+/// are served while it lasts with `--metrics-addr` and pushed at the end
+/// with `--push-gateway`.
+async fn run_once(args: PipelineArgs) -> Result<()> {
+    let metrics = Arc::new(Metrics::default());
+    if let Some(addr) = args.metrics.metrics_addr {
+        tokio::spawn(serve_metrics(bind_metrics(addr).await?, metrics.clone()));
+        info!("Serving metrics on http://{}/metrics", addr);
     }
+    let push_gateway = args.metrics.push_gateway.clone();
 
-    if let Some(source) = specific_source {
-        info!("🎯 Processing specific source: {}", source);
+    let result = run_pipeline(args, Shutdown::listen(), metrics.clone()).await.map(drop);
+    if let Some(gateway) = push_gateway {
+        match metrics.push(&gateway).await {
+            Ok(()) => info!("Pushed metrics to {}", gateway),
+            Err(e) => warn!("⚠️ {:#}", e),
+        }
     }
+    result
+}
 
-    // Define all available sources with their types
-    let sources = vec![
-        ("krave_mart", "src/configs/krave_mart.toml", "json"),
-        ("bazaar_app", "src/configs/bazaar_app.toml", "json"),
-        ("dealcart", "src/configs/dealcart.toml", "json"),
-        ("pandamart", "src/configs/pandamart.toml", "json"),
-        ("naheed", "src/configs/naheed.toml", "html"),
-    ];
+/// The selected sources of `<config-dir>/sources.toml` as `(name, config path, type)`
+fn select_sources(args: &SourceArgs) -> Result<Vec<(String, String, &'static str)>> {
+    let sources = SourcesConfig::from_file(&format!("{}/{}", args.config.config_dir, SOURCES_FILE))?;
+    Ok(sources
+        .select(args.source.as_deref())?
+        .into_iter()
+        .map(|source| (source.name.clone(), source.config_path.clone(), source.source_type.as_str()))
+        .collect())
+}
 
-    // Load MinIO configuration (shared across all sources)
-    let minio_config = MinioConfig::from_file("src/configs/minio.toml")
+/// Storage as configured in `<config-dir>/minio.toml`
+fn connect_storage(config_dir: &str) -> Result<MinioStorage> {
+    let minio_config = MinioConfig::from_file(&format!("{}/minio.toml", config_dir))
         .context("Failed to load MinIO configuration")?;
 
     info!(
@@ -62,128 +94,683 @@ async fn main() -> Result<()> {
         minio_config.endpoint, minio_config.bucket_name
     );
 
-    // Initialize shared components
-    let storage = MinioStorage::from_config(&minio_config)
+    MinioStorage::from_config(&minio_config)
         .context("Failed to initialize MinIO storage")
         .with_context(|| {
             "Please ensure MinIO server is running and environment variables are set. Run: ./scripts/setup-minio.sh for setup assistance"
-        })?;
+        })
+}
 
-    let flattener = JsonFlattener::new();
-    let classifier = FieldClassifier::new();
-    let normalizer = RuleNormalizer;
+/// `check-storage`: diagnose the storage setup stage by stage
+async fn check_storage(config_dir: &str) -> Result<()> {
+    let storage = connect_storage(config_dir)?;
+    let report = storage.health_check().await;
+    println!("{}", report);
 
-    // Ensure bucket exists
-    storage.ensure_bucket().await?;
+    if report.is_healthy() {
+        return Ok(());
+    }
+    let failure = report
+        .first_failure()
+        .map(|check| format!("'{}': {}", check.name, check.message))
+        .unwrap_or_default();
+    Err(anyhow::anyhow!("Storage health check failed at {}", failure))
+}
 
-    // Process each source
-    let mut total_products = 0;
-    let mut successful_sources = 0;
+/// `validate-config`: load every source config, print a pass/fail line per
+/// file and fail if any of them is invalid
+fn validate_config(config_dir: &str) -> Result<()> {
+    let report = check_configs(&format!("{}/{}", config_dir, SOURCES_FILE));
+    println!("{}", report);
+    if report.is_valid() {
+        return Ok(());
+    }
+    let invalid: Vec<&str> = report.failures().map(|check| check.name.as_str()).collect();
+    Err(anyhow::anyhow!("Invalid configs: {}", invalid.join(", ")))
+}
 
-    // Filter sources based on specific source argument
-    let sources_to_process: Vec<_> = if let Some(target_source) = specific_source {
-        let filtered: Vec<_> = sources.iter()
-            .filter(|(name, _, _)| *name == target_source)
-            .cloned()
-            .collect();
-
-        if filtered.is_empty() {
-            error!("❌ Source '{}' not found. Available sources: {}",
-                target_source,
-                sources.iter().map(|(name, _, _)| *name).collect::<Vec<_>>().join(", ")
-            );
-            return Ok(());
+/// `history`: a product's prices from every clean snapshot of the selected
+/// sources, printed or written to `--output`
+async fn collect_price_history(args: &HistoryArgs) -> Result<()> {
+    info!("🚀 Starting Price History (Reading all clean snapshots)");
+    let filter = match (&args.product_id, &args.name) {
+        (Some(id), None) => ProductFilter::ProductId(id.clone()),
+        (None, Some(name)) => ProductFilter::NameContains(name.clone()),
+        _ => return Err(anyhow::anyhow!("history takes either --product-id or --name")),
+    };
+    let since = args.days.map(|days| chrono::Utc::now().date_naive() - chrono::Days::new(days));
+    let storage = connect_storage(&args.sources.config.config_dir)?;
+
+    let mut history: Option<DataFrame> = None;
+    for (source_name, config_path, source_type) in select_sources(&args.sources)? {
+        for storage_name in storage_names_for_source(&config_path, source_type)? {
+            let source_history = price_history(&storage, &storage_name, &filter, since)
+                .await
+                .with_context(|| format!("Failed to read the price history of {}", source_name))?;
+            info!("Found {} price points in {}", source_history.height(), storage_name);
+            match history.as_mut() {
+                Some(history) => {
+                    history.vstack_mut(&source_history)?;
+                }
+                None => history = Some(source_history),
+            }
         }
-        filtered
-    } else {
-        sources
+    }
+
+    let Some(mut history) = history else {
+        return Ok(());
+    };
+    match &args.output {
+        Some(path) => {
+            write_history(&mut history, path)?;
+            info!("✅ Wrote {} price points to {}", history.height(), path);
+        }
+        None => println!("{}", history),
+    }
+    Ok(())
+}
+
+/// `diff`: compare the two most recent clean snapshots of each source, or
+/// those of the two `--date` days
+async fn diff_snapshots(args: &DiffArgs) -> Result<()> {
+    let target = match args.dates.as_slice() {
+        [] => DiffTarget::Latest,
+        [date_a, date_b] => DiffTarget::Dates(*date_a, *date_b),
+        _ => return Err(anyhow::anyhow!("diff takes either no --date or two of them")),
     };
+    match target {
+        DiffTarget::Dates(date_a, date_b) => info!("🚀 Starting Snapshot Diff (Comparing clean snapshots of {} and {})", date_a, date_b),
+        DiffTarget::Latest => info!("🚀 Starting Snapshot Diff (Comparing latest clean snapshots)"),
+    }
+
+    let storage = connect_storage(&args.sources.config.config_dir)?;
+    storage.ensure_bucket().await?;
+    let shutdown = Shutdown::listen();
+    let sources = select_sources(&args.sources)?;
+    let mut diffed_sources = 0;
 
-    if from_storage {
-        // Process from storage mode
-        for (source_name, _, _) in &sources_to_process {
-            info!("\n=== Processing Source from Storage: {} ===", source_name);
-
-            match process_source_from_storage(
-                source_name,
-                &storage,
-                &flattener,
-                &classifier,
-                &normalizer,
-            ).await {
-                Ok(products_count) => {
-                    info!("✅ Successfully processed {} with {} products from storage", source_name, products_count);
-                    total_products += products_count;
-                    successful_sources += 1;
+    for (source_name, config_path, source_type) in &sources {
+        if shutdown.is_requested() {
+            break;
+        }
+        info!("\n=== Diffing Snapshots: {} ===", source_name);
+
+        let storage_names = match storage_names_for_source(config_path, source_type) {
+            Ok(names) => names,
+            Err(e) => {
+                error!("❌ Failed to load config for {}: {}", source_name, e);
+                continue;
+            }
+        };
+
+        for api_name in &storage_names {
+            if shutdown.is_requested() {
+                break;
+            }
+            match snapshot_diff(&storage, api_name, target).await {
+                Ok(Some(buckets)) => {
+                    let changes_count = buckets.added.height() + buckets.removed.height() + buckets.changed.height();
+                    info!("✅ Found {} changes for {}", changes_count, api_name);
+                    diffed_sources += 1;
                 }
+                Ok(None) => {}
                 Err(e) => {
-                    error!("❌ Failed to process {} from storage: {}", source_name, e);
-                    // Continue with other sources even if one fails
+                    error!("❌ Failed to diff snapshots for {}: {}", api_name, e);
                 }
             }
         }
+    }
+
+    info!("\n=== Snapshot Diff Summary ===");
+    info!("✅ Diffed {} out of {} sources", diffed_sources, sources.len());
+    Ok(())
+}
+
+/// `cleanup`: delete the raw dumps the selected sources fetched on `--date`,
+/// or every object under `--prefix`
+async fn cleanup_storage(args: &CleanupArgs) -> Result<()> {
+    let prefixes = match (&args.prefix, args.date) {
+        (Some(prefix), _) => vec![prefix.clone()],
+        (None, Some(date)) => {
+            let mut prefixes = Vec::new();
+            for (_, config_path, source_type) in select_sources(&args.sources)? {
+                for storage_name in storage_names_for_source(&config_path, source_type)? {
+                    prefixes.push(format!("{}/raw/{}/", date.format("%Y/%m/%d"), storage_name));
+                }
+            }
+            prefixes
+        }
+        (None, None) => return Err(anyhow::anyhow!("cleanup needs --date or --prefix")),
+    };
+
+    let storage = connect_storage(&args.sources.config.config_dir)?;
+    let mut failed = 0;
+    for prefix in &prefixes {
+        info!("{} objects under {}", if args.dry_run { "Listing" } else { "Deleting" }, prefix);
+        let summary = storage.delete_prefix(prefix, args.dry_run).await?;
+        if args.dry_run {
+            for key in &summary.deleted {
+                println!("{}", key);
+            }
+        }
+        failed += summary.failed_count();
+    }
+
+    if failed > 0 {
+        return Err(anyhow::anyhow!("Failed to delete {} objects", failed));
+    }
+    Ok(())
+}
+
+/// What the source timers of `serve` share
+struct ServeContext {
+    args: ServeArgs,
+    clock: Arc<dyn Clock>,
+    in_flight: Arc<InFlight>,
+    status: StatusFile,
+    shutdown: Arc<Shutdown>,
+    /// Of every run, for `--metrics-addr`
+    metrics: Arc<Metrics>,
+}
+
+/// `serve`: run each scheduled source of sources.toml on its own timer until
+/// Ctrl-C or SIGTERM, then let the runs in flight finish storing what they fetched
+async fn serve(args: ServeArgs) -> Result<()> {
+    let path = format!("{}/{}", args.config.config_dir, SOURCES_FILE);
+    let sources = SourcesConfig::from_file(&path)?;
+    let jitter = Duration::from_secs(sources.jitter_seconds);
+    let mut timers = Vec::new();
+    for source in sources.select(None)? {
+        match source.schedule()? {
+            Some(schedule) => timers.push(SourceTimer::new(&source.name, schedule, jitter)),
+            None => info!("{} has no interval_minutes or cron, not scheduling it", source.name),
+        }
+    }
+    if timers.is_empty() {
+        return Err(anyhow::anyhow!("No enabled source in {} has interval_minutes or cron, nothing to serve", path));
+    }
+
+    let metrics = Arc::new(Metrics::default());
+    if let Some(addr) = args.metrics_addr {
+        tokio::spawn(serve_metrics(bind_metrics(addr).await?, metrics.clone()));
+        info!("Serving metrics on http://{}/metrics", addr);
+    }
+
+    info!("🚀 Serving {} scheduled sources, stop with Ctrl-C or SIGTERM", timers.len());
+    let serve = Arc::new(ServeContext {
+        status: StatusFile::new(args.status_file.as_ref().map(PathBuf::from)),
+        args,
+        clock: Arc::new(SystemClock),
+        in_flight: Arc::default(),
+        shutdown: Shutdown::listen(),
+        metrics,
+    });
+    let mut scheduled = tokio::task::JoinSet::new();
+    for timer in timers {
+        scheduled.spawn(serve_source(timer, serve.clone()));
+    }
+    while scheduled.join_next().await.is_some() {}
+    info!("Every scheduled run has finished, stopping");
+    Ok(())
+}
+
+/// Start a run of the timer's source at each of its slots until shutdown,
+/// skipping the slots that come up while the previous run is still going
+async fn serve_source(mut timer: SourceTimer, serve: Arc<ServeContext>) {
+    let source_name = timer.source_name.clone();
+    let mut runs = tokio::task::JoinSet::new();
+    loop {
+        let Some(run_at) = timer.next_run(serve.clock.as_ref(), &mut rand::thread_rng()) else {
+            warn!("The schedule of {} has no more runs", source_name);
+            break;
+        };
+        serve.status.update(&source_name, |status| status.next_run_at = Some(run_at));
+        info!("Next run of {} at {}", source_name, run_at);
+        tokio::select! {
+            _ = sleep_until(serve.clock.as_ref(), run_at) => {}
+            _ = serve.shutdown.wait() => break,
+        }
+
+        while runs.try_join_next().is_some() {}
+        match serve.in_flight.try_start(&source_name) {
+            Some(guard) => {
+                runs.spawn(run_scheduled(guard, serve.clone()));
+            }
+            None => {
+                warn!("Skipping the {} run of {}, the previous one is still going", run_at, source_name);
+                serve.status.update(&source_name, |status| status.skipped_runs += 1);
+            }
+        }
+    }
+    while runs.join_next().await.is_some() {}
+}
+
+/// One scheduled `run` of a source, recorded in the status file
+async fn run_scheduled(guard: RunGuard, serve: Arc<ServeContext>) {
+    let source_name = guard.source_name();
+    serve.status.update(source_name, |status| {
+        status.state = RunState::Running;
+        status.last_started_at = Some(serve.clock.now());
+    });
+
+    let result = run_pipeline(serve.args.run_of(source_name), serve.shutdown.clone(), serve.metrics.clone()).await;
+    serve.status.update(source_name, |status| {
+        status.last_finished_at = Some(serve.clock.now());
+        match &result {
+            Ok(report) => {
+                let succeeded = report.failures.is_empty() && !report.sources.is_empty();
+                status.state = if succeeded { RunState::Succeeded } else { RunState::Failed };
+                status.last_products = Some(report.total_products());
+                status.last_error = report.failures.first().map(|failure| failure.error.clone());
+            }
+            Err(e) => {
+                status.state = RunState::Failed;
+                status.last_error = Some(format!("{:#}", e));
+            }
+        }
+    });
+    if let Err(e) = result {
+        error!("❌ Scheduled run of {} failed: {:#}", source_name, e);
+    }
+}
+
+/// `fetch`, `process`, `run` and `reprocess`: fetch, process or reprocess
+/// every selected source, then write the merged dataset and run reports.
+/// Stops starting sources and categories once `shutdown` is requested.
+async fn run_pipeline(args: PipelineArgs, shutdown: Arc<Shutdown>, metrics: Arc<Metrics>) -> Result<RunReport> {
+    let only_fetch = args.mode == PipelineMode::Fetch;
+    let from_storage = args.mode == PipelineMode::Process;
+    let reprocess = args.mode == PipelineMode::Reprocess;
+    let config_dir = args.sources.config.config_dir.as_str();
+    let processing = &args.processing;
+    // Fetch and process without MinIO: print the result, and with --csv
+    // also write it to a local file
+    let csv_path = args.csv.clone();
+    let in_memory = csv_path.is_some() || args.stdout;
+    let keep_baseline_on_anomaly = args.keep_baseline_on_anomaly;
+
+    // Formats clean data is stored in besides Parquet, from
+    // <config-dir>/output.toml unless given as e.g. `--output-format arrow,csv`
+    let mut output_config = OutputConfig::from_file(&format!("{}/output.toml", config_dir))?;
+    if let Some(formats) = &processing.output_format {
+        output_config.output_formats = formats.clone();
+    }
+
+    // `--sink db` also upserts each source's processed products into the
+    // database in <config-dir>/database.toml
+    let mut database_sink = match processing.sink {
+        Some(SinkKind::Db) if args.dry_run => {
+            info!("DRY RUN: not writing to the database sink");
+            None
+        }
+        Some(SinkKind::Db) if !only_fetch => {
+            let database_config = DatabaseConfig::from_file(&format!("{}/database.toml", config_dir))?;
+            Some(DatabaseSink::connect(&database_config).await?)
+        }
+        _ => None,
+    };
+
+    // Trained column classifier consulted when the classification heuristics
+    // leave a column unmapped (see the train_column_classifier binary)
+    let column_model = processing.column_model.as_deref().map(ColumnModel::load).transpose()?;
+
+    // Batch sizes by source size, tunable per deployment
+    let batching = BatchConfig::from_file(&format!("{}/batching.toml", config_dir))?;
+    // Recorded in clean files so each can be traced to the configs that produced it
+    let config_hash = match hash_config_dir(config_dir) {
+        Ok(hash) => Some(hash),
+        Err(e) => {
+            warn!("Could not hash {}, clean files will not record a config hash: {}", config_dir, e);
+            None
+        }
+    };
+
+    let categories = args.fetch.categories.as_deref().map(parse_category_list).unwrap_or_default();
+    let limit = args.fetch.limit;
+    let options = ProcessOptions {
+        force: args.fetch.force,
+        only_fetch,
+        fail_on_errors: processing.fail_on_errors,
+        max_drop: processing.max_drop,
+        strict: processing.strict,
+        dedup: processing.dedup.unwrap_or_default(),
+        output: output_config,
+        // `_raw` only reaches the stored Parquet with --include-raw-in-parquet
+        keep_raw_json: processing.keep_raw_json || processing.include_raw_in_parquet,
+        include_raw_in_parquet: processing.include_raw_in_parquet,
+        store_rejected: processing.store_rejected,
+        explain_classification: processing.explain_classification,
+        drop_out_of_stock: processing.drop_out_of_stock,
+        batching,
+        provenance: RunProvenance::new(config_hash),
+        limited: LimitedFetch::for_run(limit, &categories),
+    };
+
+    let snapshot = match (args.snapshot.date, args.snapshot.key.clone()) {
+        (Some(_), Some(_)) => return Err(anyhow::anyhow!("--date and --key cannot be combined")),
+        (Some(date), None) => RawSnapshot::Date(date),
+        (None, Some(key)) => {
+            if MinioStorage::raw_file_api(&key).is_none() {
+                return Err(anyhow::anyhow!("--key must be a raw object key like raw/<api>/<YYYYMMDD-HHMMSS>.json, got {}", key));
+            }
+            RawSnapshot::Key(key)
+        }
+        (None, None) => RawSnapshot::Latest,
+    };
+
+    if reprocess {
+        info!("🚀 Starting Reprocessing (Re-normalizing latest clean snapshots)");
+    } else if from_storage {
+        info!("🚀 Starting Multi-Source Data Pipeline (Processing from S3/MinIO Storage)");
+    } else if in_memory {
+        info!("🚀 Starting Multi-Source Data Pipeline (Fetching from APIs, in memory without storage)");
+    } else if only_fetch {
+        info!("🚀 Starting Raw Data Collection (Fetching from APIs, storing raw JSON only)");
     } else {
-        // Process from APIs/HTML sources mode
-        for (source_name, config_path, source_type) in &sources_to_process {
-            info!("\n=== Processing Source from {}: {} ===", source_type.to_uppercase(), source_name);
+        info!("🚀 Starting Multi-Source Data Pipeline (Fetching from APIs)");
+    }
 
-            // Check if config file exists
-            if !Path::new(config_path).exists() {
-                warn!("Config file not found for {}: {}", source_name, config_path);
-                continue;
+    if args.dry_run && !in_memory {
+        info!("🧪 DRY RUN: fetching and processing as usual, storing nothing");
+    }
+
+    if let Some(source) = &args.sources.source {
+        info!("🎯 Processing specific source: {}", source);
+    }
+
+    if !categories.is_empty() {
+        info!("🎯 Limiting fetch to categories: {}", categories.join(", "));
+    }
+    if let Some(limit) = limit {
+        info!("🎯 Limiting fetch to {} products per store", limit);
+    }
+
+    match &snapshot {
+        RawSnapshot::Latest => {}
+        RawSnapshot::Date(date) => info!("📅 Processing raw snapshots from {}", date),
+        RawSnapshot::Key(key) => info!("📅 Processing raw snapshot {}", key),
+    }
+
+    let mut classifier = FieldClassifier::new();
+    if let Some(model) = column_model {
+        info!("Using trained column classifier with labels: {}", model.labels().join(", "));
+        classifier = classifier.with_column_model(model);
+    }
+    let normalizer_config = NormalizerConfig::from_file(&format!("{}/normalizer.toml", config_dir))?;
+    // Links the same SKU across sources in the merged dataset
+    let matcher = MatcherConfig::from_file(&format!("{}/matcher.toml", config_dir))?.matcher();
+    // Flags sources whose snapshot drifted too far from the previous one
+    let anomaly_detector = AnomalyConfig::from_file(&format!("{}/anomaly.toml", config_dir))?.detector();
+    // Post a run summary when a webhook is configured
+    let notify_config = NotifyConfig::from_optional_file(&format!("{}/notify.toml", config_dir))?;
+    // Name cleaning patterns for sources without their own rule file
+    let default_name_rules = NameRules::from_file(&format!("{}/normalizer_rules.toml", config_dir))?;
+    // One limiter for every fetcher so concurrent requests share each host's rate
+    let rate_limiter = Arc::new(RateLimiter::new(RateLimitConfig::from_file(&format!("{}/rate_limits.toml", config_dir))?));
+    // Bounds how long one stuck source can hold up the run
+    let deadlines = DeadlineConfig::from_file(&format!("{}/deadlines.toml", config_dir))?;
+
+    let sources_to_process = select_sources(&args.sources)?;
+    let sources_count = sources_to_process.len();
+
+    if in_memory {
+        // Fetch and process without MinIO, printing the merged result
+        let mut run_report = RunReport::new("in memory");
+        let mut processed_frames: Vec<(String, DataFrame)> = Vec::new();
+
+        for (source_name, config_path, source_type) in &sources_to_process {
+            if shutdown.is_requested() {
+                break;
             }
+            info!("\n=== Processing Source from {} in memory: {} ===", source_type.to_uppercase(), source_name);
+
+            let built = build_fetchers(source_type, config_path, &categories, limit, &rate_limiter, &shutdown, metrics.source(source_name)).and_then(|fetchers| {
+                let flattener = build_flattener(source_type, config_path, &normalizer_config)?.with_raw_json(options.keep_raw_json);
+                let normalizer = build_normalizer(source_name, source_type, config_path, config_dir, &normalizer_config, &default_name_rules)?
+                    .with_number_format(flattener.number_format());
+                Ok((fetchers, flattener, Pipeline::new().with(normalizer)))
+            });
+            let (fetchers, flattener, steps) = match built {
+                Ok(built) => built,
+                Err(e) => {
+                    warn!("Skipping {}: {}", source_name, e);
+                    continue;
+                }
+            };
 
-            let products_count = match source_type.as_ref() {
-                "json" => {
-                    // Process JSON API source
-                    match process_json_source(
-                        source_name,
-                        config_path,
-                        &storage,
-                        &flattener,
-                        &classifier,
-                        &normalizer,
-                    ).await {
-                        Ok(count) => count,
-                        Err(e) => {
-                            error!("❌ Failed to process JSON source {}: {}", source_name, e);
-                            continue;
-                        }
-                    }
+            let deadline = SourceDeadline::start(&deadlines, source_name);
+            for fetcher in &fetchers {
+                if shutdown.is_requested() {
+                    break;
                 }
-                "html" => {
-                    // Process HTML scraping source
-                    match process_html_source(
-                        source_name,
-                        config_path,
-                        &storage,
-                        &flattener,
-                        &classifier,
-                        &normalizer,
-                    ).await {
-                        Ok(count) => count,
-                        Err(e) => {
-                            error!("❌ Failed to process HTML source {}: {}", source_name, e);
-                            continue;
-                        }
+                let processing = process_source_in_memory(source_name, fetcher.as_ref(), &flattener, &classifier, &steps, &options);
+                let result = match until_deadline(deadline.as_ref(), processing).await {
+                    Ok(result) => result,
+                    Err(deadline) => Err(deadline.missed()),
+                };
+                match result {
+                    Ok(Some((df, counts))) => {
+                        run_report.add_source(source_name, fetcher.source_name(), df.height(), Some(&df), Some(counts));
+                        processed_frames.push((source_name.to_string(), df));
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        error!("❌ Failed to process {} source {}: {}", source_type.to_uppercase(), fetcher.source_name(), e);
+                        metrics.failure(source_name, args.mode.stage());
+                        run_report.add_failure(source_name, fetcher.source_name(), &e);
                     }
                 }
-                _ => {
-                    warn!("Unknown source type '{}' for {}", source_type, source_name);
+            }
+        }
+
+        if shutdown.is_requested() {
+            run_report.mark_interrupted();
+        }
+        info!("\n=== Data Quality ===\n{}", run_report);
+        info!("\n=== Quality Flags ===\n{}", run_report.data_quality());
+        if options.explain_classification {
+            return Ok(run_report);
+        }
+        if processed_frames.is_empty() {
+            warn!("⚠️ No sources were processed successfully in memory");
+            return Ok(run_report);
+        }
+        print_in_memory_result(&processed_frames, csv_path.as_deref())?;
+        return Ok(run_report);
+    }
+
+    let mut storage = connect_storage(config_dir)?;
+    if args.dry_run {
+        storage = storage.into_dry_run();
+    }
+
+    // Ensure bucket exists
+    storage.ensure_bucket().await?;
+
+    // Sources run as tasks of their own, --jobs at a time
+    let run = Arc::new(SharedRun {
+        storage,
+        classifier,
+        options,
+        normalizer_config,
+        default_name_rules,
+        anomaly_detector,
+        deadlines,
+        rate_limiter,
+        shutdown: shutdown.clone(),
+        metrics: metrics.clone(),
+        categories,
+        limit,
+        config_dir: config_dir.to_string(),
+        snapshot,
+        keep_baseline_on_anomaly,
+    });
+    let (storage, options, snapshot) = (&run.storage, &run.options, &run.snapshot);
+
+    let mode_str = if reprocess {
+        "from Clean Snapshots"
+    } else if from_storage {
+        "from Storage"
+    } else if only_fetch {
+        "Raw JSON from APIs"
+    } else {
+        "from APIs"
+    };
+
+    // Process each source
+    let mut run_report = RunReport::new(mode_str);
+    if args.dry_run {
+        run_report.mark_dry_run();
+    }
+    let mut successful_sources = 0;
+    // Clean DataFrames of successfully processed sources, for the merged dataset
+    let mut processed_frames: Vec<(String, DataFrame)> = Vec::new();
+
+    if reprocess {
+        // Re-run the processors on stored clean data, without fetching
+        for (source_name, config_path, source_type) in &sources_to_process {
+            if shutdown.is_requested() {
+                break;
+            }
+            info!("\n=== Reprocessing Clean Snapshots: {} ===", source_name);
+
+            let (steps, storage_names) = match build_normalizer(source_name, source_type, config_path, config_dir, &run.normalizer_config, &run.default_name_rules)
+                .and_then(|normalizer| Ok((Pipeline::new().with(normalizer), storage_names_for_source(config_path, source_type)?)))
+            {
+                Ok(result) => result,
+                Err(e) => {
+                    warn!("Skipping {}: {}", source_name, e);
                     continue;
                 }
             };
 
-            info!("✅ Successfully processed {} with {} products", source_name, products_count);
-            total_products += products_count;
-            successful_sources += 1;
+            let mut source_succeeded = false;
+            for storage_name in &storage_names {
+                if shutdown.is_requested() {
+                    break;
+                }
+                let reclassifier = args.reclassify.then_some(&run.classifier);
+                match reprocess_clean_snapshot(storage_name, storage, reclassifier, &steps, &options.provenance).await {
+                    Ok(Some(df)) => {
+                        info!("✅ Reprocessed {} rows of {}", df.height(), storage_name);
+                        run_report.add_source(source_name, storage_name, df.height(), Some(&df), None);
+                        source_succeeded = true;
+                        processed_frames.push((source_name.to_string(), df));
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        error!("❌ Failed to reprocess {}: {}", storage_name, e);
+                        metrics.failure(source_name, args.mode.stage());
+                        run_report.add_failure(source_name, storage_name, &e);
+                    }
+                }
+            }
+            if source_succeeded {
+                successful_sources += 1;
+            }
+        }
+    } else {
+        let source_name = |(name, _, _): &(String, String, &'static str)| name.clone();
+        let outcomes = if from_storage {
+            run_sources_with_retry(sources_to_process, args.jobs, source_name, args.retry, &shutdown, |source| {
+                process_storage_source(run.clone(), source)
+            })
+            .await
+        } else {
+            run_sources_with_retry(sources_to_process, args.jobs, source_name, args.retry, &shutdown, |source| {
+                process_api_source(run.clone(), source)
+            })
+            .await
+        };
+
+        // Recorded in the order of sources.toml, whichever task finished first
+        let mut matched_snapshot = false;
+        for outcome in outcomes {
+            matched_snapshot |= outcome.matched_snapshot;
+            outcome.record_metrics(&metrics, args.mode);
+            let recorded = outcome.record(&mut run_report);
+            for (storage_name, df) in recorded.frames {
+                let snapshot_date = snapshot.date().unwrap_or_else(|| chrono::Utc::now().date_naive());
+                sink_to_database(database_sink.as_mut(), &storage_name, snapshot_date, &df).await;
+                processed_frames.push((recorded.source_name.clone(), df));
+            }
+            if recorded.succeeded {
+                successful_sources += 1;
+            }
         }
+        if from_storage
+            && let RawSnapshot::Key(key) = snapshot
+            && !matched_snapshot
+        {
+            warn!("No selected source stores its raw data under {}", key);
+        }
+    }
+
+    if shutdown.is_requested() {
+        warn!("Run stopped after {} sources, writing a partial run report", successful_sources);
+        run_report.mark_interrupted();
     }
 
-    let mode_str = if from_storage { "from Storage" } else { "from APIs" };
-    info!("\n=== Multi-Source Pipeline Summary ({}) ===", mode_str);
-    info!("✅ Successfully processed {} out of {} sources", successful_sources, sources_to_process.len());
-    info!("📊 Total products processed: {}", total_products);
+    if options.explain_classification {
+        info!("Explained classification for {} sources, nothing was stored", successful_sources);
+        return Ok(run_report);
+    }
+
+    if args.merge_latest && !only_fetch && !run_report.interrupted && successful_sources > 0 {
+        if let Err(e) = merge_latest_snapshots(&args.sources.config, storage, &matcher, options).await {
+            error!("❌ Failed to write merged dataset: {}", e);
+        }
+    } else if processing.skip_merge || only_fetch {
+        info!("Skipping merged dataset ({})", if only_fetch { "fetch" } else { "--skip-merge" });
+    } else if run_report.interrupted {
+        warn!("Skipping merged dataset, the run was interrupted");
+    } else if processed_frames.is_empty() {
+        warn!("No processed sources to merge");
+    } else if let Err(e) = write_merged_dataset(&processed_frames, storage, &matcher, options).await {
+        error!("❌ Failed to write merged dataset: {}", e);
+    }
+
+    let dry_run_note = if args.dry_run { ", DRY RUN" } else { "" };
+    info!("\n=== Multi-Source Pipeline Summary ({}{}) ===", mode_str, dry_run_note);
+    info!(
+        "✅ Successfully processed {} out of {} sources ({} on retry)",
+        successful_sources,
+        sources_count,
+        run_report.retried.len()
+    );
+    info!("📊 Total products processed: {}", run_report.total_products());
+    info!("\n=== Data Quality ===\n{}", run_report);
+    match run_report.to_json() {
+        Ok(json) => {
+            if let Err(e) = storage.store_run_report(run_report.started_at, &json).await {
+                warn!("Failed to store run report: {}", e);
+            }
+        }
+        Err(e) => warn!("Failed to serialize run report: {}", e),
+    }
+    let data_quality = run_report.data_quality();
+    info!("\n=== Quality Flags ===\n{}", data_quality);
+    match data_quality.to_json() {
+        Ok(json) => {
+            if let Err(e) = storage.store_data_quality_report(data_quality.started_at, &json).await {
+                warn!("Failed to store data quality report: {}", e);
+            }
+        }
+        Err(e) => warn!("Failed to serialize data quality report: {}", e),
+    }
+
+    if args.dry_run && notify_config.notify_webhook.is_some() {
+        info!("DRY RUN: not posting the run summary to the notify webhook");
+    } else if let Some(url) = &notify_config.notify_webhook {
+        // A broken webhook must never fail an otherwise finished run
+        match WebhookNotifier::new(url.as_str()) {
+            Ok(notifier) => match notifier.notify(&run_report).await {
+                Ok(()) => info!("Posted run summary to the notify webhook"),
+                Err(e) => warn!("Failed to send run notification: {:#}", e),
+            },
+            Err(e) => warn!("Failed to set up run notification: {:#}", e),
+        }
+    }
 
     if successful_sources > 0 {
         info!("🎉 Multi-source pipeline {} completed successfully!", mode_str);
@@ -191,267 +778,1223 @@ async fn main() -> Result<()> {
         warn!("⚠️ No sources were processed successfully {}", mode_str);
     }
 
-    Ok(())
+    Ok(run_report)
+}
+
+/// Everything the source tasks of a run share
+struct SharedRun {
+    storage: MinioStorage,
+    classifier: FieldClassifier,
+    options: ProcessOptions,
+    normalizer_config: NormalizerConfig,
+    default_name_rules: NameRules,
+    anomaly_detector: AnomalyDetector,
+    deadlines: DeadlineConfig,
+    rate_limiter: Arc<RateLimiter>,
+    shutdown: Arc<Shutdown>,
+    metrics: Arc<Metrics>,
+    /// `--categories`, empty for every category
+    categories: Vec<String>,
+    /// `--limit` on the products fetched per store
+    limit: Option<usize>,
+    config_dir: String,
+    /// Raw snapshot processed from storage
+    snapshot: RawSnapshot,
+    keep_baseline_on_anomaly: bool,
 }
 
-async fn process_json_source(
+/// Fetch, store and process every store of one source from its API or pages
+async fn process_api_source(run: Arc<SharedRun>, (source_name, config_path, source_type): (String, String, &'static str)) -> SourceOutcome {
+    let mut outcome = SourceOutcome::skipped(&source_name);
+    if run.shutdown.is_requested() {
+        return outcome;
+    }
+    info!("\n=== Processing Source from {}: {} ===", source_type.to_uppercase(), source_name);
+
+    // Check if config file exists
+    if !Path::new(&config_path).exists() {
+        warn!("Config file not found for {}: {}", source_name, config_path);
+        return outcome;
+    }
+
+    let built = build_fetchers(source_type, &config_path, &run.categories, run.limit, &run.rate_limiter, &run.shutdown, run.metrics.source(&source_name)).and_then(|fetchers| {
+        let flattener = build_flattener(source_type, &config_path, &run.normalizer_config)?
+            .with_raw_json(run.options.keep_raw_json)
+            .with_rejected_records(run.options.store_rejected);
+        let normalizer = build_normalizer(&source_name, source_type, &config_path, &run.config_dir, &run.normalizer_config, &run.default_name_rules)?
+            .with_number_format(flattener.number_format());
+        Ok((fetchers, flattener, Pipeline::new().with(normalizer)))
+    });
+    let (fetchers, flattener, steps) = match built {
+        Ok(built) => built,
+        Err(e) => {
+            warn!("Skipping {}: {}", source_name, e);
+            return outcome;
+        }
+    };
+
+    // One fetcher per configured store; the source counts as processed if any store succeeds.
+    // The deadline covers all of them, so stores left when it passes fail without being fetched.
+    let deadline = SourceDeadline::start(&run.deadlines, &source_name);
+    for fetcher in &fetchers {
+        if run.shutdown.is_requested() {
+            break;
+        }
+        let storage_name = fetcher.source_name();
+        let mut progress = FetchProgress::default();
+        let processing = process_source(
+            fetcher.as_ref(),
+            &run.storage,
+            &flattener,
+            &run.classifier,
+            &steps,
+            &run.options,
+            &mut progress,
+        );
+        let result = match until_deadline(deadline.as_ref(), processing).await {
+            Ok(result) => result,
+            Err(deadline) => {
+                if deadline.store_partial {
+                    store_partial_fetch(&run.storage, storage_name, progress, &run.options).await;
+                }
+                Err(deadline.missed())
+            }
+        };
+
+        let result = match result {
+            Ok((products_count, clean_df, counts)) => {
+                info!("✅ Successfully processed {} with {} products", storage_name, products_count);
+                let anomalies = match &clean_df {
+                    // A limited fetch would look like most of the source vanished
+                    Some(_) if run.options.limited.is_some() => {
+                        info!("Not checking {} for anomalies, this run only fetched part of it", storage_name);
+                        Vec::new()
+                    }
+                    Some(df) => detect_anomalies(storage_name, df, &run.storage, &run.anomaly_detector, run.keep_baseline_on_anomaly).await,
+                    None => Vec::new(),
+                };
+                Ok(ProcessedStore { products_count, clean_df, counts, anomalies })
+            }
+            Err(e) => {
+                error!("❌ Failed to process {} source {}: {}", source_type.to_uppercase(), storage_name, e);
+                Err(e)
+            }
+        };
+        outcome.stores.push(StoreResult { storage_name: storage_name.to_string(), result });
+    }
+    outcome
+}
+
+/// Process the stored raw snapshot of every store of one source
+async fn process_storage_source(run: Arc<SharedRun>, (source_name, config_path, source_type): (String, String, &'static str)) -> SourceOutcome {
+    let mut outcome = SourceOutcome::skipped(&source_name);
+    if run.shutdown.is_requested() {
+        return outcome;
+    }
+    info!("\n=== Processing Source from Storage: {} ===", source_name);
+
+    let built = build_flattener(source_type, &config_path, &run.normalizer_config)
+        .map(|flattener| flattener.with_raw_json(run.options.keep_raw_json).with_rejected_records(run.options.store_rejected))
+        .and_then(|flattener| {
+            let normalizer = build_normalizer(&source_name, source_type, &config_path, &run.config_dir, &run.normalizer_config, &run.default_name_rules)?
+                .with_number_format(flattener.number_format());
+            Ok((flattener, Pipeline::new().with(normalizer), storage_names_for_source(&config_path, source_type)?))
+        });
+    let (flattener, steps, storage_names) = match built {
+        Ok(built) => built,
+        Err(e) => {
+            warn!("Skipping {}: {}", source_name, e);
+            return outcome;
+        }
+    };
+
+    // Multi-store sources are stored once per store; a --key selects the one store it was stored under
+    for storage_name in storage_names.iter().filter(|name| run.snapshot.applies_to(name)) {
+        if run.shutdown.is_requested() {
+            break;
+        }
+        outcome.matched_snapshot = true;
+        let result = process_source_from_storage(
+            storage_name,
+            &run.snapshot,
+            &run.storage,
+            &flattener,
+            &run.classifier,
+            &steps,
+            &run.options,
+        )
+        .await;
+
+        let result = match result {
+            Ok((products_count, clean_df, counts)) => {
+                info!("✅ Successfully processed {} with {} products from storage", storage_name, products_count);
+                Ok(ProcessedStore { products_count, clean_df, counts, anomalies: Vec::new() })
+            }
+            Err(e) => {
+                error!("❌ Failed to process {} from storage: {}", storage_name, e);
+                Err(e)
+            }
+        };
+        outcome.stores.push(StoreResult { storage_name: storage_name.clone(), result });
+    }
+    outcome
+}
+
+/// Build the fetchers for a source from its type and config file, one per
+/// configured store for multi-store APIs. Every fetcher shares the run's
+/// rate limiter, stops between categories once `shutdown` is requested,
+/// stops paginating once it fetched `limit` products and counts its
+/// requests in `metrics`
+fn build_fetchers(
+    source_type: &str,
+    config_path: &str,
+    categories: &[String],
+    limit: Option<usize>,
+    rate_limiter: &Arc<RateLimiter>,
+    shutdown: &Arc<Shutdown>,
+    metrics: SourceMetrics,
+) -> Result<Vec<Box<dyn Fetcher>>> {
+    match source_type {
+        "json" => {
+            let mut api_config = ApiConfig::from_file(config_path)
+                .with_context(|| format!("Failed to load config from {}", config_path))?;
+            info!("Loaded config: {} ({})", api_config.api.name, api_config.request.method);
+            api_config
+                .validate()
+                .with_context(|| format!("Invalid config in {}", config_path))?;
+            if api_config.request.method == "POST" && api_config.request.graphql_query.is_none() {
+                info!(
+                    "POST body for {}: productChannel={}, page size={}",
+                    api_config.api.name,
+                    api_config.post_product_channel(),
+                    api_config.post_page_size()
+                );
+            }
+            api_config.restrict_categories(categories);
+            if api_config.selected_categories().is_empty() {
+                return Err(anyhow::anyhow!("no categories selected in {}", config_path));
+            }
+            Ok(UnifiedFetcher::for_each_store(api_config)?
+                .into_iter()
+                .map(|fetcher| {
+                    let fetcher = fetcher
+                        .with_rate_limiter(rate_limiter.clone())
+                        .with_shutdown(shutdown.clone())
+                        .with_metrics(metrics.clone());
+                    Box::new(match limit {
+                        Some(limit) => fetcher.with_limit(limit),
+                        None => fetcher,
+                    }) as Box<dyn Fetcher>
+                })
+                .collect())
+        }
+        "html" => {
+            let mut html_config = HtmlConfig::from_file(config_path)
+                .with_context(|| format!("Failed to load HTML config from {}", config_path))?;
+            info!("Loaded HTML config: {}", html_config.site.name);
+            html_config
+                .validate()
+                .with_context(|| format!("Invalid config in {}", config_path))?;
+            html_config.restrict_categories(categories);
+            if html_config.get_enabled_categories().is_empty() {
+                return Err(anyhow::anyhow!("no categories selected in {}", config_path));
+            }
+            let fetcher = HtmlFetcher::new(html_config)?
+                .with_rate_limiter(rate_limiter.clone())
+                .with_shutdown(shutdown.clone())
+                .with_metrics(metrics);
+            Ok(vec![Box::new(match limit {
+                Some(limit) => fetcher.with_limit(limit),
+                None => fetcher,
+            })])
+        }
+        _ => Err(anyhow::anyhow!("Unknown source type '{}'", source_type)),
+    }
+}
+
+/// Build a `JsonFlattener` with the source's `[fields.extraction]` rules,
+/// `variants_path`, `in_stock_fields` and default currency, if it has any,
+/// stripping the `currency_markers` from `normalizer.toml` off prices
+fn build_flattener(source_type: &str, config_path: &str, normalizer_config: &NormalizerConfig) -> Result<JsonFlattener> {
+    let (rules, variants_path, schema_path, in_stock_paths, currency, number_format) = match source_type {
+        "json" => {
+            let config = ApiConfig::from_file(config_path)?;
+            let variants_path = config
+                .variants_path()
+                .with_context(|| format!("Invalid variants_path in {}", config_path))?;
+            let schema_path = config.schema_path().map(str::to_string);
+            let in_stock_paths = config
+                .in_stock_paths()
+                .with_context(|| format!("Invalid in_stock_fields in {}", config_path))?;
+            let api = &config.api;
+            (config.extraction_rules(), variants_path, schema_path, in_stock_paths, api.currency.clone(), api.number_format)
+        }
+        "html" => {
+            let config = HtmlConfig::from_file(config_path)?;
+            (config.extraction_rules(), None, None, Vec::new(), config.site.currency.clone(), config.site.number_format)
+        }
+        _ => return Err(anyhow::anyhow!("Unknown source type '{}'", source_type)),
+    };
+    let rules = rules.with_context(|| format!("Invalid field extraction rules in {}", config_path))?;
+
+    let mut flattener = JsonFlattener::new()
+        .with_rules(rules)
+        .with_number_format(number_format)
+        .with_in_stock_fields(in_stock_paths);
+    if let Some(path) = variants_path {
+        flattener = flattener.with_variants_path(path);
+    }
+    if let Some(markers) = &normalizer_config.currency_markers {
+        flattener = flattener.with_currency_markers(markers.clone());
+    }
+    if let Some(currency) = currency {
+        flattener = flattener
+            .with_currency(&currency)
+            .with_context(|| format!("Invalid currency in {}", config_path))?;
+    }
+    if let Some(path) = schema_path {
+        let validator = SchemaValidator::from_file(&path)?;
+        info!("Validating raw products against {}", path);
+        flattener = flattener.with_record_validator(move |record| validator.validate(record));
+    }
+    Ok(flattener)
+}
+
+/// Build the source's `RuleNormalizer`: the shared brands, descriptors and
+/// null placeholders, with the patterns of
+/// `<config-dir>/normalizer_rules/<source>.toml` when the source has such a
+/// file and the shared ones otherwise, and the `[[transforms]]` of its config
+fn build_normalizer(
     source_name: &str,
+    source_type: &str,
     config_path: &str,
+    config_dir: &str,
+    config: &NormalizerConfig,
+    default_rules: &NameRules,
+) -> Result<RuleNormalizer> {
+    let rules_path = format!("{}/normalizer_rules/{}.toml", config_dir, source_name);
+    let normalizer = if Path::new(&rules_path).exists() {
+        info!("Cleaning {} names with the rules in {}", source_name, rules_path);
+        RuleNormalizer::from_config(&rules_path)?
+    } else {
+        RuleNormalizer::new().with_rules(default_rules.clone())
+    };
+
+    let mut normalizer = normalizer
+        .with_known_brands(config.known_brands.clone())
+        .with_name_descriptors(config.name_descriptors.clone());
+    if let Some(columns) = &config.null_columns {
+        normalizer = normalizer.with_null_columns(columns.clone());
+    }
+    if let Some(placeholders) = &config.null_placeholders {
+        normalizer = normalizer.with_null_placeholders(placeholders.clone());
+    }
+    if let Some(max) = config.max_price_per_unit {
+        normalizer = normalizer.with_max_price_per_unit(max);
+    }
+    if let Some(markers) = &config.currency_markers {
+        normalizer = normalizer.with_currency_markers(markers.clone());
+    }
+    if let Some(lowercase) = config.lowercase_names {
+        normalizer = normalizer.with_lowercase_names(lowercase);
+    }
+    if let Some(action) = config.swapped_prices {
+        normalizer = normalizer.with_swapped_prices(action);
+    }
+    if let Some(action) = config.out_of_range_discounts {
+        normalizer = normalizer.with_out_of_range_discounts(action);
+    }
+
+    let transforms = match source_type {
+        "html" => HtmlConfig::from_file(config_path)?.transforms,
+        _ => ApiConfig::from_file(config_path)?.transforms,
+    };
+    if !transforms.is_empty() {
+        info!("Applying {} column transforms to {}", transforms.len(), source_name);
+    }
+    Ok(normalizer.with_transforms(transforms))
+}
+
+/// Command line switches shared by every processed source
+#[derive(Debug, Clone, Default)]
+struct ProcessOptions {
+    /// Store and process raw dumps even when unchanged (`--force`)
+    force: bool,
+    /// Stop once the raw dump is stored (`fetch`)
+    only_fetch: bool,
+    /// Maximum percentage of products allowed to fail extraction (`--fail-on-errors`)
+    fail_on_errors: Option<f64>,
+    /// Percentage of raw products that may be missing from the clean output (`--max-drop`)
+    max_drop: f64,
+    /// Fail sources exceeding `max_drop` instead of warning (`--strict`)
+    strict: bool,
+    /// Strategy for repeated product_ids within a source (`--dedup`)
+    dedup: DedupStrategy,
+    /// Formats clean data is stored in besides Parquet (`--output-format`)
+    output: OutputConfig,
+    /// Keep each product's source JSON in a `_raw` column (`--keep-raw-json`)
+    keep_raw_json: bool,
+    /// Write the `_raw` column to the stored Parquet (`--include-raw-in-parquet`)
+    include_raw_in_parquet: bool,
+    /// Store products that failed extraction in full (`--store-rejected`)
+    store_rejected: bool,
+    /// Print the classification report instead of storing results (`--explain-classification`)
+    explain_classification: bool,
+    /// Drop rows `in_stock` marks as out of stock (`--drop-out-of-stock`)
+    drop_out_of_stock: bool,
+    /// Batch sizes by source size, from `<config-dir>/batching.toml`
+    batching: BatchConfig,
+    /// Written into the metadata of every clean Parquet file
+    provenance: RunProvenance,
+    /// `--limit` / `--categories`, recorded next to the raw dumps stored
+    limited: Option<LimitedFetch>,
+}
+
+/// Compare a source's raw, flattened and clean product counts. A drop above
+/// `--max-drop` is logged, or fails the source under `--strict`.
+fn check_product_counts(source_name: &str, processed: &ProcessedSource, options: &ProcessOptions) -> Result<ProductCounts> {
+    let counts = ProductCounts {
+        raw: processed.total,
+        flattened: processed.total - processed.failures.len(),
+        clean: processed.dataframe.height(),
+        out_of_stock: processed.out_of_stock,
+    };
+    info!(
+        "{}: {} raw, {} flattened, {} clean products",
+        source_name, counts.raw, counts.flattened, counts.clean
+    );
+
+    let dropped = counts.drop_pct();
+    if dropped > options.max_drop {
+        let message = format!(
+            "{:.2}% of {} raw products from {} are missing from the clean output, above --max-drop {}%",
+            dropped, counts.raw, source_name, options.max_drop
+        );
+        if options.strict {
+            return Err(anyhow::anyhow!(message));
+        }
+        warn!("{}", message);
+    }
+    Ok(counts)
+}
+
+/// Fetch a source, store the raw JSON, then process it from storage into
+/// Parquet. Fetched products are kept in `progress` until the raw JSON is
+/// stored, so a source cut off by its deadline can still store them.
+async fn process_source(
+    fetcher: &dyn Fetcher,
     storage: &MinioStorage,
     flattener: &JsonFlattener,
     classifier: &FieldClassifier,
-    normalizer: &RuleNormalizer,
-) -> Result<usize> {
-    // Load source-specific configuration
-    let api_config = ApiConfig::from_file(config_path)
-        .with_context(|| format!("Failed to load config for {}", source_name))?;
-
-    info!("Loaded config for {}: {} ({})", source_name, api_config.api.name, api_config.request.method);
-
-    // Initialize fetcher for this source
-    let fetcher = UnifiedFetcher::new(api_config.clone())?;
+    steps: &Pipeline,
+    options: &ProcessOptions,
+    progress: &mut FetchProgress,
+) -> Result<(usize, Option<DataFrame>, Option<ProductCounts>)> {
+    let storage_name = fetcher.source_name();
 
     // Fetch data from all categories
-    info!("Fetching data from {} API", api_config.api.name);
-    let raw_data = fetcher.fetch_all_categories().await?;
+    info!("Fetching data from {}", storage_name);
+    fetcher.fetch_categories_into(&mut progress.products).await?;
+    progress.products = merge_category_duplicates(std::mem::take(&mut progress.products));
+    let raw_data = &progress.products;
     let products_count = raw_data.len();
 
-    info!("Fetched {} total products from {}", products_count, source_name);
+    info!("Fetched {} total products from {}", products_count, storage_name);
 
     if products_count == 0 {
-        warn!("No products fetched from {}", source_name);
-        return Ok(0);
+        warn!("No products fetched from {}", storage_name);
+        return Ok((0, None, None));
     }
 
     // Store raw JSON
-    let raw_json = serde_json::to_string(&raw_data)?;
-    let raw_key = storage
-        .store_raw_json(&api_config.api.name, &raw_json)
+    let raw_json = serde_json::to_string(raw_data)?;
+    let raw_outcome = storage
+        .store_raw_json_checked(storage_name, &raw_json, options.force)
         .await?;
-    info!("Stored raw data at: {}", raw_key);
+    progress.stored = true;
+    mark_limited_fetch(storage, &raw_outcome, options).await?;
+
+    if raw_outcome.is_unchanged() && options.only_fetch {
+        info!("{} unchanged since {}, nothing new to store", storage_name, raw_outcome.key());
+    } else if raw_outcome.is_unchanged() {
+        // Same data as last run: reuse its clean snapshot instead of re-processing
+        if let Some(clean_key) = storage.list_clean_files(storage_name).await?.into_iter().next() {
+            info!(
+                "{} unchanged since {}, reusing {} (pass --force to reprocess)",
+                storage_name,
+                raw_outcome.key(),
+                clean_key
+            );
+            let clean_df = storage.load_parquet(&clean_key).await?;
+            return Ok((products_count, Some(clean_df), None));
+        }
+        info!("{} unchanged but has no clean snapshot yet, processing it", storage_name);
+    } else {
+        info!("Stored raw data at: {}", raw_outcome.key());
+    }
+    if options.only_fetch {
+        return Ok((products_count, None, None));
+    }
 
     // Load raw data back from S3 for processing (ensuring consistency)
     info!("Loading raw data from S3 for processing");
 
     // Get metadata first to determine processing approach
-    let (file_path, total_products) = storage.get_latest_raw_data_info(&api_config.api.name).await
-        .with_context(|| format!("Failed to get raw data info for {} from storage", api_config.api.name))?;
+    let (file_path, total_products) = storage.get_latest_raw_data_info(storage_name).await
+        .with_context(|| format!("Failed to get raw data info for {} from storage", storage_name))?;
 
     info!("Found {} products in {} for processing", total_products, file_path);
 
-    // Determine batch size based on dataset size
-    let batch_size = if total_products <= 500 {
-        total_products  // Very small datasets: process all at once
-    } else if total_products <= 5000 {
-        500  // Small-medium datasets: 500 per batch
-    } else if total_products <= 50000 {
-        2000  // Medium datasets: 2K per batch
-    } else {
-        5000  // Large datasets: 5K per batch
-    };
+    // Small datasets are processed all at once, larger ones in batches
+    let batch_size = choose_batch_size(total_products, &options.batching);
 
     info!("Processing {} products in batches of {} for memory efficiency", total_products, batch_size);
 
-    let df = if batch_size >= total_products {
+    let context = raw_record_context(storage_name, &file_path);
+
+    let processed = if batch_size >= total_products {
         // Small dataset - use original method
         info!("Using standard processing for small dataset");
-        let raw_data_from_storage = storage.load_latest_raw_data(&api_config.api.name).await?;
-        flattener.flatten_to_dataframe(&raw_data_from_storage)?
+        let mut raw_data_from_storage = storage.load_latest_raw_data(storage_name).await?;
+        context.fill_missing(&mut raw_data_from_storage);
+        let output = flattener.flatten_to_dataframe(&raw_data_from_storage)?;
+        process_in_memory(output, storage_name, classifier, steps, options)?
     } else {
         // Large dataset - use batched processing
         info!("Using batched processing for large dataset");
-        let batches = storage.stream_latest_raw_data_batched(&api_config.api.name, batch_size).await?;
-        flattener.flatten_to_dataframe_batched(batches)?
+        let batches = storage.stream_latest_raw_data_batched(storage_name, batch_size).await?;
+        process_batched(with_record_context(batches, &context), storage_name, flattener, classifier, steps, options)?
     };
 
-    info!("Flattened to DataFrame with {} rows", df.height());
+    let today = chrono::Utc::now().date_naive();
+    if !report_classification(storage, storage_name, today, &processed.classification, options).await? {
+        return Ok((products_count, None, None));
+    }
+    report_extraction_failures(storage, storage_name, today, &processed, options.fail_on_errors).await?;
+    let counts = check_product_counts(storage_name, &processed, options)?;
+    let ProcessedSource { dataframe: processed_df, parquet: buf, .. } = processed;
 
-    // Apply processing pipeline
-    let mut processed_df = df;
+    // Store processed data
+    let clean_key = storage.store_parquet(storage_name, &buf).await?;
+    info!("Stored processed data at: {}", clean_key);
+    store_other_formats(storage, storage_name, &processed_df, options).await?;
 
-    // Apply ML classification
-    classifier.map_to_canonical_schema(&mut processed_df)?;
-    info!("Applied field classification");
+    Ok((products_count, Some(processed_df), Some(counts)))
+}
 
-    // Apply rule-based normalization
-    normalizer.normalize_dataframe(&mut processed_df)?;
-    info!("Applied normalization rules");
+/// What `process_source` fetched so far, owned by the caller so it survives
+/// the source being dropped at its deadline
+#[derive(Debug, Default)]
+struct FetchProgress {
+    products: Vec<serde_json::Value>,
+    /// The raw JSON was stored, nothing is left to save
+    stored: bool,
+}
 
-    // Convert to Parquet
-    info!("Converting to Parquet format");
-    let mut buf = Vec::new();
-    {
-        let writer = ParquetWriter::new(&mut buf);
-        writer.finish(&mut processed_df)?;
+/// When a source has to be done by, from `deadlines.toml`
+#[derive(Debug, Clone, Copy)]
+struct SourceDeadline {
+    limit: Duration,
+    at: tokio::time::Instant,
+    store_partial: bool,
+}
+
+impl SourceDeadline {
+    /// Start the clock of `source_name`, if it has a deadline
+    fn start(config: &DeadlineConfig, source_name: &str) -> Option<Self> {
+        config.deadline_for(source_name).map(|limit| SourceDeadline {
+            limit,
+            at: tokio::time::Instant::now() + limit,
+            store_partial: config.store_partial,
+        })
     }
 
-    // Store processed data
-    let clean_key = storage.store_parquet(&api_config.api.name, &buf).await?;
-    info!("Stored processed data at: {}", clean_key);
+    fn missed(&self) -> anyhow::Error {
+        anyhow::anyhow!("Source missed its deadline of {}s", self.limit.as_secs())
+    }
+}
 
-    Ok(products_count)
+/// Run `work` until the deadline, if there is one. Returns the deadline
+/// instead when it passed first and `work` was dropped; only awaits are
+/// interrupted, so a CPU-bound step runs to its end before the deadline hits.
+async fn until_deadline<T>(deadline: Option<&SourceDeadline>, work: impl Future<Output = T>) -> Result<T, &SourceDeadline> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline.at, work).await.map_err(|_| deadline),
+        None => Ok(work.await),
+    }
+}
+
+/// Store the products a store fetched before its source missed the deadline
+/// as its raw dump, unless its raw JSON was already stored
+async fn store_partial_fetch(storage: &MinioStorage, storage_name: &str, progress: FetchProgress, options: &ProcessOptions) {
+    if progress.stored || progress.products.is_empty() {
+        return;
+    }
+    let products = merge_category_duplicates(progress.products);
+    let stored = match serde_json::to_string(&products) {
+        Ok(raw_json) => storage.store_raw_json_checked(storage_name, &raw_json, options.force).await,
+        Err(e) => Err(e.into()),
+    };
+    let stored = match stored {
+        Ok(outcome) => mark_limited_fetch(storage, &outcome, options).await.map(|()| outcome),
+        Err(e) => Err(e),
+    };
+    match stored {
+        Ok(outcome) => warn!(
+            "Stored the {} products {} fetched before its deadline at {}",
+            products.len(),
+            storage_name,
+            outcome.key()
+        ),
+        Err(e) => error!("Failed to store the partial fetch of {}: {}", storage_name, e),
+    }
 }
 
-/// Process HTML-based source (web scraping)
-async fn process_html_source(
+/// Record next to a raw dump stored by a `--limit` or `--categories` run that
+/// it is not a full snapshot
+async fn mark_limited_fetch(storage: &MinioStorage, outcome: &RawStoreOutcome, options: &ProcessOptions) -> Result<()> {
+    if let (RawStoreOutcome::Stored(raw_key), Some(limited)) = (outcome, &options.limited) {
+        storage
+            .store_limited_fetch(raw_key, limited)
+            .await
+            .with_context(|| format!("Failed to mark {} as a limited fetch", raw_key))?;
+    }
+    Ok(())
+}
+
+/// Fetch and process a source without touching storage. `None` when nothing
+/// was fetched, or with `--explain-classification` once the report is printed.
+async fn process_source_in_memory(
     source_name: &str,
-    config_path: &str,
-    storage: &MinioStorage,
+    fetcher: &dyn Fetcher,
     flattener: &JsonFlattener,
     classifier: &FieldClassifier,
-    normalizer: &RuleNormalizer,
-) -> Result<usize> {
-    info!("Loading HTML config for {}: {}", source_name, config_path);
+    steps: &Pipeline,
+    options: &ProcessOptions,
+) -> Result<Option<(DataFrame, ProductCounts)>> {
+    let storage_name = fetcher.source_name();
+
+    info!("Fetching data from {}", storage_name);
+    let mut raw_data = fetcher.fetch_all_categories().await?;
+    info!("Fetched {} total products from {}", raw_data.len(), source_name);
+    if raw_data.is_empty() {
+        warn!("No products fetched from {}", source_name);
+        return Ok(None);
+    }
 
-    // Load HTML configuration
-    let html_config = HtmlConfig::from_file(config_path)
-        .with_context(|| format!("Failed to load HTML config from {}", config_path))?;
+    RecordContext::new(storage_name)
+        .with_fetched_at(chrono::Utc::now())
+        .fill_missing(&mut raw_data);
+    let output = flattener.flatten_to_dataframe(&raw_data)?;
+    let processed = process_in_memory(output, storage_name, classifier, steps, options)?;
 
-    info!("Loaded HTML config for {}: {}", source_name, html_config.site.name);
+    if options.explain_classification {
+        println!("\n=== Column classification: {} ===\n{}", storage_name, processed.classification);
+        return Ok(None);
+    }
+    info!("Column classification for {}:\n{}", storage_name, processed.classification);
+
+    if !processed.failures.is_empty() {
+        let failed_pct = processed.failures.len() as f64 / processed.total.max(1) as f64 * 100.0;
+        warn!(
+            "{} of {} products ({:.2}%) from {} failed extraction",
+            processed.failures.len(), processed.total, failed_pct, storage_name
+        );
+        if let Some(threshold) = options.fail_on_errors
+            && failed_pct > threshold
+        {
+            return Err(anyhow::anyhow!(
+                "{:.2}% of products failed extraction, above --fail-on-errors {}%",
+                failed_pct,
+                threshold
+            ));
+        }
+    }
 
-    // Store site name before moving config
-    let site_name = html_config.site.name.clone();
+    let counts = check_product_counts(storage_name, &processed, options)?;
+    Ok(Some((processed.dataframe, counts)))
+}
 
-    // Initialize HTML fetcher
-    let html_fetcher = HtmlFetcher::new(html_config)?;
+/// Rows of the in-memory result printed with `--stdout`
+const STDOUT_PREVIEW_ROWS: usize = 10;
 
-    // Scrape data from all categories
-    info!("Scraping data from {} website", site_name);
-    let scraped_products = html_fetcher.fetch_all_categories().await?;
-    let products_count = scraped_products.len();
+/// Merge the sources processed in memory, print the head and shape of the
+/// result and write it to `csv_path` when given
+fn print_in_memory_result(processed_frames: &[(String, DataFrame)], csv_path: Option<&str>) -> Result<()> {
+    let mut merged = DatasetMerger::new().merge(processed_frames)?;
+    if merged.column(RAW_JSON_FIELD).is_ok() {
+        merged = merged.drop(RAW_JSON_FIELD)?;
+    }
 
-    info!("Scraped {} total products from {}", products_count, source_name);
+    println!("{}", merged.head(Some(STDOUT_PREVIEW_ROWS)));
+    println!("shape: ({}, {})", merged.height(), merged.width());
 
-    if products_count == 0 {
-        warn!("No products scraped from {}", source_name);
-        return Ok(0);
+    if let Some(path) = csv_path {
+        let file = std::fs::File::create(path).with_context(|| format!("Failed to create {}", path))?;
+        CsvWriter::new(file)
+            .finish(&mut merged)
+            .with_context(|| format!("Failed to write {}", path))?;
+        info!("Wrote {} products to {}", merged.height(), path);
     }
+    Ok(())
+}
+
+async fn process_source_from_storage(
+    source_name: &str,
+    snapshot: &RawSnapshot,
+    storage: &MinioStorage,
+    flattener: &JsonFlattener,
+    classifier: &FieldClassifier,
+    steps: &Pipeline,
+    options: &ProcessOptions,
+) -> Result<(usize, Option<DataFrame>, Option<ProductCounts>)> {
+    info!("Loading raw data from storage for {}", source_name);
 
-    // Convert scraped products to JSON format for unified processing
-    let html_processor = HtmlProcessor::new();
-    let json_products = html_processor.process_scraped_products(scraped_products)?;
+    // Pick the requested key, the snapshot for the requested day, or the latest one
+    let file_path = storage.resolve_raw_file(source_name, snapshot).await?;
 
-    // Store raw JSON (converted from HTML)
-    let raw_json = serde_json::to_string(&json_products)?;
-    let raw_key = storage
-        .store_raw_json(&site_name, &raw_json)
-        .await?;
-    info!("Stored raw HTML data (as JSON) at: {}", raw_key);
+    // Get metadata first to determine if we need batching
+    let total_products = storage.count_raw_records(&file_path).await
+        .with_context(|| format!("Failed to get raw data info for {} from storage", source_name))?;
 
-    // Process through unified pipeline (same as JSON sources)
-    let total_products = json_products.len();
-    let batch_size = if total_products > 10000 { 1000 } else { total_products };
+    info!("Found {} products in {} for processing", total_products, file_path);
+
+    if total_products == 0 {
+        warn!("No products found in storage for {}", source_name);
+        return Ok((0, None, None));
+    }
+
+    // Small datasets are processed all at once, larger ones in batches
+    let batch_size = choose_batch_size(total_products, &options.batching);
 
     info!("Processing {} products in batches of {} for memory efficiency", total_products, batch_size);
 
-    let df = if batch_size >= total_products {
+    let context = raw_record_context(source_name, &file_path);
+
+    let processed = if batch_size >= total_products {
         // Small dataset - use original method
         info!("Using standard processing for small dataset");
-        flattener.flatten_to_dataframe(&json_products)?
+        let mut raw_data = storage.load_raw_file(&file_path).await?;
+        context.fill_missing(&mut raw_data);
+        let output = flattener.flatten_to_dataframe(&raw_data)?;
+        process_in_memory(output, source_name, classifier, steps, options)?
     } else {
         // Large dataset - use batched processing
         info!("Using batched processing for large dataset");
-        // For HTML sources, we'll process in memory since data is already loaded
-        flattener.flatten_to_dataframe(&json_products)?
+        let batches = storage.stream_raw_file_batched(&file_path, batch_size).await?;
+        process_batched(with_record_context(batches, &context), source_name, flattener, classifier, steps, options)?
+    };
+
+    let report_date = snapshot.date().unwrap_or_else(|| chrono::Utc::now().date_naive());
+    if !report_classification(storage, source_name, report_date, &processed.classification, options).await? {
+        return Ok((total_products, None, None));
+    }
+    report_extraction_failures(storage, source_name, report_date, &processed, options.fail_on_errors).await?;
+    let counts = check_product_counts(source_name, &processed, options)?;
+    let ProcessedSource { dataframe: processed_df, parquet: buf, .. } = processed;
+
+    // Store processed data with storage suffix to distinguish from API-sourced data
+    let processed_key = storage.store_parquet(&format!("{}_from_storage", source_name), &buf).await?;
+    info!("Stored processed data at: {}", processed_key);
+    store_other_formats(storage, &format!("{}_from_storage", source_name), &processed_df, options).await?;
+
+    Ok((total_products, Some(processed_df), Some(counts)))
+}
+
+/// Store `df` in the configured formats other than Parquet, leaving out the
+/// `_raw` column as the Parquet file does by default
+async fn store_other_formats(storage: &MinioStorage, storage_name: &str, df: &DataFrame, options: &ProcessOptions) -> Result<()> {
+    let formats = &options.output.output_formats;
+    if formats.iter().all(|format| *format == OutputFormat::Parquet) {
+        return Ok(());
+    }
+    let mut df = if options.include_raw_in_parquet || df.column(RAW_JSON_FIELD).is_err() {
+        df.clone()
+    } else {
+        df.drop(RAW_JSON_FIELD)?
     };
 
-    info!("Flattened to DataFrame with {} rows", df.height());
+    let today = chrono::Utc::now().date_naive();
+    if formats.contains(&OutputFormat::Arrow) {
+        storage.store_arrow_ipc(storage_name, today, &mut df, options.output.arrow_compression).await?;
+    }
+    if formats.contains(&OutputFormat::Csv) {
+        storage.store_csv(storage_name, today, &mut df, options.output.csv_delimiter as u8).await?;
+    }
+    if formats.contains(&OutputFormat::Ndjson) {
+        storage.store_ndjson(storage_name, today, &df, options.output.ndjson_explicit_nulls).await?;
+    }
+    Ok(())
+}
+
+/// Provenance for products loaded from a raw dump. The fetch time comes from
+/// the raw key, so reprocessing an old snapshot keeps its original timestamp.
+fn raw_record_context(storage_name: &str, raw_key: &str) -> RecordContext {
+    let context = RecordContext::new(storage_name);
+    match MinioStorage::raw_file_fetched_at(raw_key) {
+        Some(fetched_at) => context.with_fetched_at(fetched_at),
+        None => {
+            warn!("Could not read the fetch time from {}, leaving fetched_at empty", raw_key);
+            context
+        }
+    }
+}
+
+/// Fill in provenance the raw products of each batch do not carry yet
+fn with_record_context<'a>(
+    batches: impl Iterator<Item = Result<Vec<serde_json::Value>>> + 'a,
+    context: &'a RecordContext,
+) -> impl Iterator<Item = Result<Vec<serde_json::Value>>> + 'a {
+    batches.map(move |batch| {
+        batch.map(|mut products| {
+            context.fill_missing(&mut products);
+            products
+        })
+    })
+}
+
+/// A source's clean frame and its Parquet encoding, plus the products that
+/// could not be extracted along the way
+struct ProcessedSource {
+    dataframe: DataFrame,
+    parquet: Vec<u8>,
+    /// How the flattened columns were mapped to the canonical schema
+    classification: ClassificationReport,
+    failures: Vec<ExtractionFailure>,
+    /// The failed products in full, with `--store-rejected`
+    rejected: Vec<RejectedRecord>,
+    /// Number of raw products, extracted or not
+    total: usize,
+    /// Rows dropped by `--drop-out-of-stock`
+    out_of_stock: usize,
+}
+
+/// Log how a source's columns were classified and store it as
+/// `reports/<source>/<date>/classification.json`. With
+/// `--explain-classification` the report is printed instead and `false` is
+/// returned, telling the caller to stop before storing anything else.
+async fn report_classification(
+    storage: &MinioStorage,
+    source_name: &str,
+    date: chrono::NaiveDate,
+    report: &ClassificationReport,
+    options: &ProcessOptions,
+) -> Result<bool> {
+    if options.explain_classification {
+        println!("\n=== Column classification: {} ===\n{}", source_name, report);
+        return Ok(false);
+    }
+
+    info!("Column classification for {}:\n{}", source_name, report);
+    let key = storage
+        .store_classification_report(source_name, date, &report.to_json()?)
+        .await?;
+    info!("Stored classification report at: {}", key);
+    Ok(true)
+}
+
+/// Store `errors/<source>/<date>.json` when some products failed extraction,
+/// and with `--store-rejected` the products themselves as
+/// `rejected/<source>/<date>.json`, then abort the source if the failure
+/// share exceeds `fail_on_errors` percent
+async fn report_extraction_failures(
+    storage: &MinioStorage,
+    source_name: &str,
+    date: chrono::NaiveDate,
+    processed: &ProcessedSource,
+    fail_on_errors: Option<f64>,
+) -> Result<()> {
+    if processed.failures.is_empty() {
+        return Ok(());
+    }
+
+    let failed = processed.failures.len();
+    let failed_pct = failed as f64 / processed.total.max(1) as f64 * 100.0;
+    warn!(
+        "{} of {} products ({:.2}%) from {} failed extraction",
+        failed, processed.total, failed_pct, source_name
+    );
+
+    let report = serde_json::json!({
+        "source": source_name,
+        "date": date.format("%Y-%m-%d").to_string(),
+        "total": processed.total,
+        "failed": failed,
+        "failures": processed.failures,
+    });
+    let report_key = storage
+        .store_error_report(source_name, date, &serde_json::to_string_pretty(&report)?)
+        .await?;
+    info!("Stored extraction error report at: {}", report_key);
+    if !processed.rejected.is_empty() {
+        let rejected = serde_json::json!({
+            "source": source_name,
+            "date": date.format("%Y-%m-%d").to_string(),
+            "records": processed.rejected,
+        });
+        let rejected_key = storage
+            .store_rejected_records(source_name, date, &serde_json::to_string_pretty(&rejected)?)
+            .await?;
+        info!("Stored {} rejected records at: {}", processed.rejected.len(), rejected_key);
+    }
+
+    if let Some(threshold) = fail_on_errors
+        && failed_pct > threshold
+    {
+        return Err(anyhow::anyhow!(
+            "{:.2}% of products failed extraction, above --fail-on-errors {}% (see {})",
+            failed_pct,
+            threshold,
+            report_key
+        ));
+    }
+    Ok(())
+}
+
+/// Classify and normalize a flattened DataFrame, then encode it as Parquet
+/// with the provenance of `storage_name`
+fn process_in_memory(
+    output: processor::FlattenOutput,
+    storage_name: &str,
+    classifier: &FieldClassifier,
+    steps: &Pipeline,
+    options: &ProcessOptions,
+) -> Result<ProcessedSource> {
+    info!("Flattened to DataFrame with {} rows", output.dataframe.height());
 
     // Apply processing pipeline
-    let mut processed_df = df;
+    let mut processed_df = output.dataframe;
 
     // Apply ML classification
-    classifier.map_to_canonical_schema(&mut processed_df)?;
+    let classification = classifier.map_to_canonical_schema(&mut processed_df)?;
     info!("Applied field classification");
 
-    // Apply rule-based normalization
-    normalizer.normalize_dataframe(&mut processed_df)?;
+    // Apply rule-based normalization and any processors appended after it
+    steps.process(&mut processed_df)?;
     info!("Applied normalization rules");
 
+    let collapsed = DedupStep::new(options.dedup).apply(&mut processed_df)?;
+    info!("Collapsed {} duplicate products", collapsed);
+    let out_of_stock = filter_out_of_stock(&mut processed_df, options)?;
+    canonical_order(&mut processed_df)?;
+
     // Convert to Parquet
     info!("Converting to Parquet format");
-    let mut buf = Vec::new();
-    {
-        let writer = ParquetWriter::new(&mut buf);
-        writer.finish(&mut processed_df)?;
+    let metadata = options.provenance.for_file(storage_name, processed_df.height());
+    let buf = encode_parquet_with_metadata(&mut processed_df, options.include_raw_in_parquet, &metadata)?;
+
+    Ok(ProcessedSource {
+        dataframe: processed_df,
+        parquet: buf,
+        classification,
+        failures: output.failures,
+        rejected: output.rejected,
+        total: output.total,
+        out_of_stock,
+    })
+}
+
+/// Drop out-of-stock rows under `--drop-out-of-stock`, returning how many
+fn filter_out_of_stock(df: &mut DataFrame, options: &ProcessOptions) -> Result<usize> {
+    if !options.drop_out_of_stock {
+        return Ok(0);
     }
+    let dropped = drop_out_of_stock(df)?;
+    info!("Dropped {} out-of-stock products", dropped);
+    Ok(dropped)
+}
 
-    // Store processed data
-    let clean_key = storage.store_parquet(&site_name, &buf).await?;
-    info!("Stored processed data at: {}", clean_key);
+/// Flatten, classify and normalize batch by batch, streaming each one into
+/// the Parquet output so the whole source is never held as one DataFrame
+fn process_batched(
+    batches: impl Iterator<Item = Result<Vec<serde_json::Value>>>,
+    storage_name: &str,
+    flattener: &JsonFlattener,
+    classifier: &FieldClassifier,
+    steps: &Pipeline,
+    options: &ProcessOptions,
+) -> Result<ProcessedSource> {
+    let include_raw_json = options.include_raw_in_parquet;
+    let mut buf = Vec::new();
+    // Batches usually share a schema, so each column is reported once
+    let mut classification = ClassificationReport::default();
+    let summary = flattener.flatten_batched_to_parquet(batches, &mut buf, |batch_df| {
+        classification.absorb(classifier.map_to_canonical_schema(batch_df)?);
+        steps.process(batch_df)?;
+        if !include_raw_json && batch_df.column(RAW_JSON_FIELD).is_ok() {
+            *batch_df = batch_df.drop(RAW_JSON_FIELD)?;
+        }
+        Ok(())
+    })?;
+    info!(
+        "Classified, normalized and encoded {} rows across {} batches",
+        summary.rows, summary.batches
+    );
 
-    Ok(products_count)
+    // The merged dataset still needs the clean frame; decoding the compressed
+    // Parquet is far cheaper than keeping every batch around while flattening
+    let mut processed_df = ParquetReader::new(std::io::Cursor::new(&buf)).finish()?;
+
+    // Duplicates can span batches, so they are collapsed on the whole frame
+    let collapsed = DedupStep::new(options.dedup).apply(&mut processed_df)?;
+    info!("Collapsed {} duplicate products", collapsed);
+    let out_of_stock = filter_out_of_stock(&mut processed_df, options)?;
+    canonical_order(&mut processed_df)?;
+
+    // The provenance records the final row count, only known once every
+    // batch is written, so the stored file is encoded from the whole frame
+    let metadata = options.provenance.for_file(storage_name, processed_df.height());
+    buf = encode_parquet_with_metadata(&mut processed_df, include_raw_json, &metadata)?;
+
+    Ok(ProcessedSource {
+        dataframe: processed_df,
+        parquet: buf,
+        classification,
+        total: summary.successful + summary.failed,
+        failures: summary.failures,
+        rejected: summary.rejected,
+        out_of_stock,
+    })
 }
 
-async fn process_source_from_storage(
-    source_name: &str,
+/// Compare a freshly fetched snapshot with the source's stored baseline,
+/// logging an error for every threshold it breaches. The snapshot becomes
+/// the new baseline unless it is suspect and `keep_baseline` is set.
+/// Storage errors are logged and leave the source unchecked.
+async fn detect_anomalies(
+    storage_name: &str,
+    df: &DataFrame,
     storage: &MinioStorage,
-    flattener: &JsonFlattener,
-    classifier: &FieldClassifier,
-    normalizer: &RuleNormalizer,
-) -> Result<usize> {
-    info!("Loading raw data from storage for {}", source_name);
+    detector: &AnomalyDetector,
+    keep_baseline: bool,
+) -> Vec<Anomaly> {
+    let current = SnapshotStats::from_dataframe(df);
+    let anomalies = match storage.load_baseline_stats(storage_name).await {
+        Ok(Some(baseline)) => detector.detect(&baseline, &current),
+        Ok(None) => {
+            info!("No baseline stats for {} yet, storing this snapshot's", storage_name);
+            Vec::new()
+        }
+        Err(e) => {
+            warn!("Could not load baseline stats for {}: {}", storage_name, e);
+            Vec::new()
+        }
+    };
 
-    // Get metadata first to determine if we need batching
-    let (file_path, total_products) = storage.get_latest_raw_data_info(source_name).await
-        .with_context(|| format!("Failed to get raw data info for {} from storage", source_name))?;
+    for anomaly in &anomalies {
+        error!("🚨 {} looks suspect: {}", storage_name, anomaly);
+    }
+    if !anomalies.is_empty() && keep_baseline {
+        warn!("Keeping the previous baseline stats of {}", storage_name);
+    } else if let Err(e) = storage.store_baseline_stats(storage_name, &current).await {
+        warn!("Failed to store baseline stats for {}: {}", storage_name, e);
+    }
+    anomalies
+}
 
-    info!("Found {} products in {} for processing", total_products, file_path);
+/// Merge the clean DataFrames of all processed sources into one dataset and
+/// store it with a manifest and its cross-source product matches under
+/// `clean/_merged/date=<today>/`, plus copies in the other configured formats.
+async fn write_merged_dataset(
+    processed_frames: &[(String, DataFrame)],
+    storage: &MinioStorage,
+    matcher: &ProductMatcher,
+    options: &ProcessOptions,
+) -> Result<()> {
+    info!("\n=== Merging {} Sources ===", processed_frames.len());
 
-    if total_products == 0 {
-        warn!("No products found in storage for {}", source_name);
-        return Ok(0);
+    let mut merged = DatasetMerger::new().merge(processed_frames)?;
+    info!("Merged DataFrame has {} rows and {} columns", merged.height(), merged.width());
+
+    let buf = encode_parquet(&mut merged, options.include_raw_in_parquet)?;
+
+    let today = chrono::Utc::now().date_naive();
+    let merged_key = storage.store_merged_parquet(today, &buf).await?;
+    info!("Stored merged dataset at: {}", merged_key);
+    store_other_formats(storage, "_merged", &merged, options).await?;
+
+    let manifest = MergeManifest::new(today, &merged_key, &merged)?;
+    for (source, rows) in &manifest.rows_per_source {
+        info!("  {}: {} rows", source, rows);
     }
 
-    // Determine batch size based on dataset size
-    let batch_size = if total_products <= 500 {
-        total_products  // Very small datasets: process all at once
-    } else if total_products <= 5000 {
-        500  // Small-medium datasets: 500 per batch
-    } else if total_products <= 50000 {
-        2000  // Medium datasets: 2K per batch
-    } else {
-        5000  // Large datasets: 5K per batch
+    let manifest_key = storage
+        .store_merged_manifest(today, &serde_json::to_string_pretty(&manifest)?)
+        .await?;
+    info!("Stored merge manifest at: {}", manifest_key);
+
+    if let Err(e) = write_product_matches(&merged, storage, matcher, today).await {
+        error!("❌ Failed to match products across sources: {}", e);
+    }
+
+    Ok(())
+}
+
+/// Rebuild the merged dataset from the latest clean snapshot of every enabled
+/// source, for runs that only processed some of them
+async fn merge_latest_snapshots(config: &ConfigArgs, storage: &MinioStorage, matcher: &ProductMatcher, options: &ProcessOptions) -> Result<()> {
+    let every_source = SourceArgs {
+        source: None,
+        config: config.clone(),
     };
+    let mut sources = Vec::new();
+    for (source_name, config_path, source_type) in select_sources(&every_source)? {
+        match storage_names_for_source(&config_path, source_type) {
+            Ok(storage_names) => sources.push((source_name, storage_names)),
+            Err(e) => warn!("Leaving {} out of the merged dataset: {}", source_name, e),
+        }
+    }
 
-    info!("Processing {} products in batches of {} for memory efficiency", total_products, batch_size);
+    let frames = latest_clean_frames(storage, &sources).await?;
+    if frames.is_empty() {
+        warn!("No clean snapshots to merge");
+        return Ok(());
+    }
+    write_merged_dataset(&frames, storage, matcher, options).await
+}
 
-    let df = if batch_size >= total_products {
-        // Small dataset - use original method
-        info!("Using standard processing for small dataset");
-        let raw_data = storage.load_latest_raw_data(source_name).await?;
-        flattener.flatten_to_dataframe(&raw_data)?
-    } else {
-        // Large dataset - use batched processing
-        info!("Using batched processing for large dataset");
-        let batches = storage.stream_latest_raw_data_batched(source_name, batch_size).await?;
-        flattener.flatten_to_dataframe_batched(batches)?
+/// The latest clean snapshot of each store of `sources`, given as the source
+/// name and its storage names, tagged with the source. Stores without a clean
+/// snapshot yet are left out.
+async fn latest_clean_frames(storage: &MinioStorage, sources: &[(String, Vec<String>)]) -> Result<Vec<(String, DataFrame)>> {
+    let mut frames = Vec::new();
+    for (source_name, storage_names) in sources {
+        for storage_name in storage_names {
+            let Some(clean_key) = storage.list_clean_files(storage_name).await?.into_iter().next() else {
+                info!("{} has no clean snapshot to merge yet", storage_name);
+                continue;
+            };
+            info!("Merging {}", clean_key);
+            frames.push((source_name.clone(), storage.load_parquet(&clean_key).await?));
+        }
+    }
+    Ok(frames)
+}
+
+/// Match the merged dataset's products across sources and store the groups
+/// as `matches.parquet`, with the matches too close to call in
+/// `ambiguous_matches.json`
+async fn write_product_matches(
+    merged: &DataFrame,
+    storage: &MinioStorage,
+    matcher: &ProductMatcher,
+    date: chrono::NaiveDate,
+) -> Result<()> {
+    let mut output = matcher.match_products(merged)?;
+    let groups = output.matches.column(processor::MATCH_GROUP_ID_FIELD)?.n_unique()?;
+    info!("Matched {} products into {} cross-source groups", output.matches.height(), groups);
+
+    let buf = encode_parquet(&mut output.matches, false)?;
+    let matches_key = storage.store_matches(date, &buf).await?;
+    info!("Stored product matches at: {}", matches_key);
+
+    if !output.ambiguous.is_empty() {
+        warn!("{} products have several equally likely matches in another source", output.ambiguous.len());
+        let report_key = storage
+            .store_ambiguous_matches(date, &serde_json::to_string_pretty(&output.ambiguous)?)
+            .await?;
+        info!("Stored ambiguous matches at: {}", report_key);
+    }
+    Ok(())
+}
+
+/// Upsert a source's processed products into the `--sink db` database, if
+/// any. A failing database is logged and does not fail the source.
+async fn sink_to_database(sink: Option<&mut DatabaseSink>, storage_name: &str, snapshot_date: chrono::NaiveDate, df: &DataFrame) {
+    let Some(sink) = sink else {
+        return;
     };
+    if let Err(e) = sink.upsert(storage_name, snapshot_date, df).await {
+        error!("❌ Failed to write {} to the database: {}", storage_name, e);
+    }
+}
 
-    info!("Flattened to DataFrame with {} rows", df.height());
+/// Re-apply the source's processors (and the classifier, when given) to the latest
+/// clean snapshot of a source and store the result as a new clean file.
+/// `None` when the source has no clean snapshot yet.
+async fn reprocess_clean_snapshot(
+    storage_name: &str,
+    storage: &MinioStorage,
+    classifier: Option<&FieldClassifier>,
+    steps: &Pipeline,
+    provenance: &RunProvenance,
+) -> Result<Option<DataFrame>> {
+    let Some(clean_key) = storage.list_clean_files(storage_name).await?.into_iter().next() else {
+        warn!("No clean snapshot to reprocess for {}", storage_name);
+        return Ok(None);
+    };
 
-    // Apply processing pipeline
-    let mut processed_df = df;
+    info!("Reprocessing {}", clean_key);
+    match storage.load_parquet_metadata(&clean_key).await? {
+        Some(metadata) => info!(
+            "{} was written by pipeline {} at {} with {} rows (config {})",
+            clean_key,
+            metadata.pipeline_version,
+            metadata.run_at,
+            metadata.rows,
+            metadata.config_hash.as_deref().unwrap_or("unknown")
+        ),
+        None => info!("{} predates provenance metadata", clean_key),
+    }
+    let mut df = storage.load_parquet(&clean_key).await?;
 
-    // Apply ML classification
-    classifier.map_to_canonical_schema(&mut processed_df)?;
-    info!("Applied field classification");
+    if let Some(classifier) = classifier {
+        classifier.map_to_canonical_schema(&mut df)?;
+        info!("Re-applied field classification");
+    }
+    steps.process(&mut df)?;
+    info!("Re-applied normalization rules");
+    canonical_order(&mut df)?;
 
-    // Apply rule-based normalization
-    normalizer.normalize_dataframe(&mut processed_df)?;
-    info!("Applied normalization rules");
+    let metadata = provenance.for_file(storage_name, df.height());
+    let buf = encode_parquet_with_metadata(&mut df, true, &metadata)?;
+    let new_key = storage.store_parquet(storage_name, &buf).await?;
+    info!("Stored reprocessed data at: {}", new_key);
 
-    // Convert to Parquet
-    info!("Converting to Parquet format");
-    let mut buf = Vec::new();
-    {
-        let writer = ParquetWriter::new(&mut buf);
-        writer.finish(&mut processed_df)?;
+    Ok(Some(df))
+}
+
+/// Names a source's files are stored under (API name per store, or HTML site name)
+fn storage_names_for_source(config_path: &str, source_type: &str) -> Result<Vec<String>> {
+    match source_type {
+        "html" => Ok(vec![HtmlConfig::from_file(config_path)?.site.name]),
+        _ => Ok(ApiConfig::from_file(config_path)?.storage_names()),
     }
+}
 
-    // Store processed data with storage suffix to distinguish from API-sourced data
-    let processed_key = storage.store_parquet(&format!("{}_from_storage", source_name), &buf).await?;
-    info!("Stored processed data at: {}", processed_key);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use storage::backend::MemoryBackend;
+
+    /// Store a clean snapshot of `storage_name`, as a run of its source would
+    async fn store_clean(storage: &MinioStorage, storage_name: &str, product_ids: &[&str]) {
+        let mut df = df!("product_id" => product_ids).unwrap();
+        storage.store_parquet(storage_name, &encode_parquet(&mut df, false).unwrap()).await.unwrap();
+    }
 
-    Ok(total_products)
+    #[tokio::test]
+    async fn test_single_source_runs_merge_every_source() {
+        let storage = MinioStorage::with_backends(Box::new(MemoryBackend::new("raw")), Box::new(MemoryBackend::new("clean")));
+        let sources = vec![
+            ("naheed".to_string(), vec!["naheed".to_string()]),
+            ("krave_mart".to_string(), vec!["krave_mart".to_string()]),
+        ];
+        let (matcher, options) = (ProductMatcher::new(), ProcessOptions::default());
+
+        // Two scheduled runs, each of one source, each followed by a merge
+        for (storage_name, product_ids) in [("naheed", vec!["n1", "n2"]), ("krave_mart", vec!["k1"])] {
+            store_clean(&storage, storage_name, &product_ids).await;
+            let frames = latest_clean_frames(&storage, &sources).await.unwrap();
+            write_merged_dataset(&frames, &storage, &matcher, &options).await.unwrap();
+        }
+
+        let today = chrono::Utc::now().date_naive();
+        let merged_key = format!("clean/_merged/date={}/merged.parquet", today.format("%Y-%m-%d"));
+        let merged = storage.load_parquet(&merged_key).await.unwrap();
+        let manifest = MergeManifest::new(today, &merged_key, &merged).unwrap();
+        let expected = BTreeMap::from([("krave_mart".to_string(), 1), ("naheed".to_string(), 2)]);
+        assert_eq!(manifest.rows_per_source, expected);
+    }
 }
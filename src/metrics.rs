@@ -0,0 +1,301 @@
+use anyhow::{Context, Result, anyhow};
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::header::CONTENT_TYPE;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tracing::debug;
+use wreq::Client;
+
+/// Content type of the Prometheus text exposition format
+const TEXT_FORMAT: &str = "text/plain; version=0.0.4";
+
+/// Job the metrics of a one-shot run are pushed under
+const PUSH_JOB: &str = "data-pipeline";
+
+/// How long a push waits on the gateway before giving up
+const PUSH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Upper bounds, in seconds, of the `pipeline_source_duration_seconds` buckets
+const DURATION_BUCKETS: [f64; 10] = [1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0, 3600.0];
+
+/// Counters and histograms of the pipeline's runs, served on `/metrics` by
+/// `serve_metrics` and pushed by `push`. In `serve` they add up across runs.
+#[derive(Default)]
+pub struct Metrics {
+    state: Mutex<MetricState>,
+}
+
+#[derive(Default)]
+struct MetricState {
+    /// By source
+    products_fetched: BTreeMap<String, u64>,
+    /// By source and status, `error` for requests without a response
+    http_requests: BTreeMap<(String, String), u64>,
+    /// By source and stage
+    failures: BTreeMap<(String, String), u64>,
+    /// By source and stage
+    durations: BTreeMap<(String, String), Histogram>,
+}
+
+/// Observations counted into `DURATION_BUCKETS`, cumulatively
+#[derive(Default)]
+struct Histogram {
+    buckets: [u64; DURATION_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(DURATION_BUCKETS) {
+            if value <= bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += value;
+    }
+}
+
+impl Metrics {
+    pub fn products_fetched(&self, source: &str, count: usize) {
+        *self.state.lock().unwrap().products_fetched.entry(source.to_string()).or_default() += count as u64;
+    }
+
+    pub fn http_request(&self, source: &str, status: &str) {
+        *self.state.lock().unwrap().http_requests.entry((source.to_string(), status.to_string())).or_default() += 1;
+    }
+
+    pub fn failure(&self, source: &str, stage: &str) {
+        *self.state.lock().unwrap().failures.entry((source.to_string(), stage.to_string())).or_default() += 1;
+    }
+
+    pub fn source_duration(&self, source: &str, stage: &str, duration: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.durations.entry((source.to_string(), stage.to_string())).or_default().observe(duration.as_secs_f64());
+    }
+
+    /// The handle a source's fetchers count their requests with
+    pub fn source(self: &Arc<Self>, source: &str) -> SourceMetrics {
+        SourceMetrics { metrics: self.clone(), source: source.to_string() }
+    }
+
+    /// Every metric in the Prometheus text format
+    pub fn render(&self) -> String {
+        let state = self.state.lock().unwrap();
+        let mut text = String::new();
+
+        header(&mut text, "pipeline_products_fetched_total", "counter", "Products fetched from each source");
+        for (source, count) in &state.products_fetched {
+            sample(&mut text, "pipeline_products_fetched_total", &[("source", source)], *count);
+        }
+        header(&mut text, "pipeline_http_requests_total", "counter", "Requests sent to each source by response status");
+        for ((source, status), count) in &state.http_requests {
+            sample(&mut text, "pipeline_http_requests_total", &[("source", source), ("status", status)], *count);
+        }
+        header(&mut text, "pipeline_failures_total", "counter", "Stores of each source that failed, by pipeline stage");
+        for ((source, stage), count) in &state.failures {
+            sample(&mut text, "pipeline_failures_total", &[("source", source), ("stage", stage)], *count);
+        }
+
+        let name = "pipeline_source_duration_seconds";
+        header(&mut text, name, "histogram", "Time spent on each source, by pipeline stage");
+        for ((source, stage), histogram) in &state.durations {
+            let labels = [("source", source.as_str()), ("stage", stage.as_str())];
+            for (bound, count) in DURATION_BUCKETS.iter().zip(histogram.buckets) {
+                let le = bound.to_string();
+                sample(&mut text, &format!("{}_bucket", name), &[labels[0], labels[1], ("le", &le)], count);
+            }
+            sample(&mut text, &format!("{}_bucket", name), &[labels[0], labels[1], ("le", "+Inf")], histogram.count);
+            sample(&mut text, &format!("{}_sum", name), &labels, histogram.sum);
+            sample(&mut text, &format!("{}_count", name), &labels, histogram.count);
+        }
+        text
+    }
+
+    /// Replace the metrics of this job on a Prometheus Pushgateway at `gateway`,
+    /// e.g. `http://pushgateway:9091`
+    pub async fn push(&self, gateway: &str) -> Result<()> {
+        let url = format!("{}/metrics/job/{}", gateway.trim_end_matches('/'), PUSH_JOB);
+        let client = Client::builder().timeout(PUSH_TIMEOUT).build()?;
+        let response = client
+            .put(&url)
+            .header(CONTENT_TYPE.as_str(), TEXT_FORMAT)
+            .body(self.render())
+            .send()
+            .await
+            .with_context(|| format!("Failed to push metrics to {}", url))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(anyhow!("Pushgateway at {} returned {}", url, status));
+        }
+        Ok(())
+    }
+}
+
+/// `Metrics` of one source, for the code that only knows its fetcher
+#[derive(Clone)]
+pub struct SourceMetrics {
+    metrics: Arc<Metrics>,
+    source: String,
+}
+
+impl SourceMetrics {
+    /// Count a request by its response status, `None` when it got no response
+    pub fn http_request(&self, status: Option<u16>) {
+        let status = status.map_or_else(|| "error".to_string(), |status| status.to_string());
+        self.metrics.http_request(&self.source, &status);
+    }
+}
+
+fn header(text: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(text, "# HELP {} {}\n# TYPE {} {}", name, help, name, kind);
+}
+
+fn sample(text: &mut String, name: &str, labels: &[(&str, &str)], value: impl std::fmt::Display) {
+    let labels: Vec<String> = labels.iter().map(|(label, value)| format!("{}=\"{}\"", label, escape_label(value))).collect();
+    let _ = writeln!(text, "{}{{{}}} {}", name, labels.join(","), value);
+}
+
+/// Label values escape backslashes, quotes and newlines
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Listen for scrapes on `addr`. Binding is separate from `serve_metrics` so a
+/// port in use fails the command before anything runs.
+pub async fn bind_metrics(addr: SocketAddr) -> Result<TcpListener> {
+    TcpListener::bind(addr).await.with_context(|| format!("Failed to listen for metrics on {}", addr))
+}
+
+/// Answer `GET /metrics` on `listener` with the current metrics, until the
+/// process exits
+pub async fn serve_metrics(listener: TcpListener, metrics: Arc<Metrics>) {
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                debug!("Failed to accept a metrics connection: {}", e);
+                continue;
+            }
+        };
+        let metrics = metrics.clone();
+        tokio::spawn(async move {
+            let service = service_fn(move |request| {
+                let response = respond(&request, &metrics);
+                async move { Ok::<_, Infallible>(response) }
+            });
+            if let Err(e) = http1::Builder::new().serve_connection(TokioIo::new(stream), service).await {
+                debug!("Metrics connection closed: {}", e);
+            }
+        });
+    }
+}
+
+fn respond(request: &Request<Incoming>, metrics: &Metrics) -> Response<Full<Bytes>> {
+    let (status, body) = match (request.method(), request.uri().path()) {
+        (&Method::GET, "/metrics") => (StatusCode::OK, metrics.render()),
+        (_, "/metrics") => (StatusCode::METHOD_NOT_ALLOWED, String::new()),
+        _ => (StatusCode::NOT_FOUND, String::new()),
+    };
+    let mut response = Response::new(Full::new(Bytes::from(body)));
+    *response.status_mut() = status;
+    response.headers_mut().insert(CONTENT_TYPE, TEXT_FORMAT.parse().unwrap());
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// The metrics of a run with one source fetched and one failed
+    fn simulated_run() -> Arc<Metrics> {
+        let metrics = Arc::new(Metrics::default());
+        let krave_mart = metrics.source("krave_mart");
+        krave_mart.http_request(Some(200));
+        krave_mart.http_request(Some(200));
+        metrics.source("dealcart").http_request(None);
+        metrics.products_fetched("krave_mart", 120);
+        metrics.failure("dealcart", "run");
+        metrics.source_duration("krave_mart", "run", Duration::from_secs(12));
+        metrics.source_duration("dealcart", "run", Duration::from_millis(400));
+        metrics
+    }
+
+    #[tokio::test]
+    async fn test_scrape_after_a_run() {
+        let metrics = simulated_run();
+        let listener = bind_metrics("127.0.0.1:0".parse().unwrap()).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve_metrics(listener, metrics));
+
+        let client = Client::new();
+        let response = client.get(format!("http://{}/metrics", addr)).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let text = response.text().await.unwrap();
+        for line in [
+            "# TYPE pipeline_products_fetched_total counter",
+            "pipeline_products_fetched_total{source=\"krave_mart\"} 120",
+            "pipeline_http_requests_total{source=\"krave_mart\",status=\"200\"} 2",
+            "pipeline_http_requests_total{source=\"dealcart\",status=\"error\"} 1",
+            "pipeline_failures_total{source=\"dealcart\",stage=\"run\"} 1",
+            "# TYPE pipeline_source_duration_seconds histogram",
+            "pipeline_source_duration_seconds_bucket{source=\"krave_mart\",stage=\"run\",le=\"5\"} 0",
+            "pipeline_source_duration_seconds_bucket{source=\"krave_mart\",stage=\"run\",le=\"15\"} 1",
+            "pipeline_source_duration_seconds_bucket{source=\"krave_mart\",stage=\"run\",le=\"+Inf\"} 1",
+            "pipeline_source_duration_seconds_sum{source=\"krave_mart\",stage=\"run\"} 12",
+            "pipeline_source_duration_seconds_count{source=\"dealcart\",stage=\"run\"} 1",
+        ] {
+            assert!(text.lines().any(|text_line| text_line == line), "missing {:?} in\n{}", line, text);
+        }
+
+        let response = client.get(format!("http://{}/", addr)).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_push_to_gateway() {
+        let gateway = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = gateway.local_addr().unwrap();
+        let received = tokio::spawn(async move {
+            let (mut stream, _) = gateway.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0; 4096];
+            // Read until the body's last sample has arrived
+            while !String::from_utf8_lossy(&request).contains("_count{source=\"krave_mart\",stage=\"run\"} 1\n") {
+                let read = stream.read(&mut buffer).await.unwrap();
+                assert!(read > 0, "connection closed early");
+                request.extend_from_slice(&buffer[..read]);
+            }
+            stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n").await.unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        simulated_run().push(&format!("http://{}/", addr)).await.unwrap();
+        let request = received.await.unwrap();
+        assert!(request.starts_with("PUT /metrics/job/data-pipeline HTTP/1.1"));
+        assert!(request.contains("pipeline_products_fetched_total{source=\"krave_mart\"} 120"));
+
+        // Nothing listens on a dropped listener's port
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        assert!(simulated_run().push(&format!("http://127.0.0.1:{}", port)).await.is_err());
+    }
+
+    #[test]
+    fn test_label_values_are_escaped() {
+        let metrics = Metrics::default();
+        metrics.products_fetched("say \"hi\"\\", 1);
+        assert!(metrics.render().contains("{source=\"say \\\"hi\\\"\\\\\"} 1"));
+    }
+}
@@ -0,0 +1,135 @@
+use anyhow::{Context, Result, anyhow};
+use serde_json::{Value, json};
+use std::time::Duration;
+use wreq::Client;
+
+use crate::processor::RunReport;
+
+/// How long the run waits on the webhook before giving up
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Failed sources listed in full; more are summarized as a count so the
+/// message stays under Slack's block size limits
+const MAX_LISTED_FAILURES: usize = 10;
+
+/// Posts the end-of-run summary to a Slack-compatible incoming webhook
+pub struct WebhookNotifier {
+    client: Client,
+    url: String,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: impl Into<String>) -> Result<Self> {
+        let client = Client::builder().timeout(WEBHOOK_TIMEOUT).build()?;
+        Ok(WebhookNotifier { client, url: url.into() })
+    }
+
+    /// Post the summary of a run. Callers log the error rather than fail
+    /// the run on it.
+    pub async fn notify(&self, report: &RunReport) -> Result<()> {
+        let response = self
+            .client
+            .post(&self.url)
+            .json(&slack_payload(report))
+            .send()
+            .await
+            .context("Failed to post run summary to the notify webhook")?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Notify webhook returned {}: {}", status, body.trim()));
+        }
+        Ok(())
+    }
+}
+
+/// Slack message for a run: a `text` fallback for notifications plus blocks
+/// with the counts and the error of each failed source
+pub fn slack_payload(report: &RunReport) -> Value {
+    let succeeded = report.sources.len();
+    let failed = report.failures.len();
+    let (icon, outcome) = match (succeeded, failed) {
+        (_, 0) => (":white_check_mark:", "completed"),
+        (0, _) => (":x:", "failed"),
+        _ => (":warning:", "completed with failures"),
+    };
+    let headline = format!(
+        "{} Data pipeline run {} {}: {} succeeded, {} failed, {} products",
+        icon,
+        report.mode,
+        outcome,
+        succeeded,
+        failed,
+        report.total_products()
+    );
+
+    let mut blocks = vec![
+        json!({"type": "section", "text": {"type": "mrkdwn", "text": format!("*{}*", headline)}}),
+        json!({
+            "type": "section",
+            "fields": [
+                {"type": "mrkdwn", "text": format!("*Started*\n{}", report.started_at.format("%Y-%m-%d %H:%M:%S UTC"))},
+                {"type": "mrkdwn", "text": format!("*Products*\n{}", report.total_products())},
+                {"type": "mrkdwn", "text": format!("*Succeeded*\n{}", succeeded)},
+                {"type": "mrkdwn", "text": format!("*Failed*\n{}", failed)},
+            ]
+        }),
+    ];
+
+    if failed > 0 {
+        let mut lines: Vec<String> = report
+            .failures
+            .iter()
+            .take(MAX_LISTED_FAILURES)
+            .map(|failure| format!("• `{}`: {}", failure.storage_name, failure.error))
+            .collect();
+        if failed > MAX_LISTED_FAILURES {
+            lines.push(format!("…and {} more", failed - MAX_LISTED_FAILURES));
+        }
+        blocks.push(json!({"type": "section", "text": {"type": "mrkdwn", "text": lines.join("\n")}}));
+    }
+
+    json!({"text": headline, "blocks": blocks})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slack_payload() {
+        let mut report = RunReport::new("from APIs");
+        report.add_source("krave_mart", "krave_mart_1242164", 120, None, None);
+        report.add_source("naheed", "naheed", 30, None, None);
+
+        let payload = slack_payload(&report);
+        let text = payload["text"].as_str().unwrap();
+        assert!(text.starts_with(":white_check_mark:"));
+        assert!(text.contains("2 succeeded, 0 failed, 150 products"));
+        assert_eq!(payload["blocks"].as_array().unwrap().len(), 2);
+
+        report.add_failure("dealcart", "dealcart", &anyhow!("HTTP 503").context("Failed to fetch page 1"));
+        let payload = slack_payload(&report);
+        assert!(payload["text"].as_str().unwrap().contains("completed with failures: 2 succeeded, 1 failed"));
+        assert_eq!(payload["blocks"][2]["text"]["text"], "• `dealcart`: Failed to fetch page 1: HTTP 503");
+
+        let mut failed_run = RunReport::new("from Storage");
+        for i in 0..12 {
+            failed_run.add_failure("pandamart", &format!("pandamart_{}", i), &anyhow!("no raw data"));
+        }
+        let payload = slack_payload(&failed_run);
+        assert!(payload["text"].as_str().unwrap().starts_with(":x:"));
+        let failures = payload["blocks"][2]["text"]["text"].as_str().unwrap();
+        assert_eq!(failures.lines().count(), MAX_LISTED_FAILURES + 1);
+        assert!(failures.ends_with("…and 2 more"));
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_webhook_is_an_error() {
+        // Bind then drop a listener so the port is known to refuse connections
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let notifier = WebhookNotifier::new(format!("http://127.0.0.1:{}/hook", port)).unwrap();
+        assert!(notifier.notify(&RunReport::new("from APIs")).await.is_err());
+    }
+}
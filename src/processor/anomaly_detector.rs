@@ -0,0 +1,245 @@
+use chrono::{DateTime, Utc};
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Product count change, in percent of the baseline, from which a source is suspect
+pub const DEFAULT_MAX_PRODUCT_COUNT_CHANGE_PCT: f64 = 50.0;
+
+/// Median cost_price change, in percent of the baseline, from which a source is suspect
+pub const DEFAULT_MAX_MEDIAN_PRICE_CHANGE_PCT: f64 = 30.0;
+
+/// Rise of a column's null rate, in percentage points, from which a source is suspect
+pub const DEFAULT_MAX_NULL_RATE_INCREASE: f64 = 25.0;
+
+/// Column whose median is compared between snapshots
+const PRICE_COLUMN: &str = "cost_price";
+
+/// What a clean snapshot of one source looked like, kept as the baseline the
+/// next run is compared against
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotStats {
+    pub taken_at: DateTime<Utc>,
+    pub rows: usize,
+    /// Median `cost_price`, `None` without prices
+    pub median_price: Option<f64>,
+    /// Share of nulls per column, in percent. Internal `_` columns are left out.
+    pub null_rates: BTreeMap<String, f64>,
+}
+
+impl SnapshotStats {
+    pub fn from_dataframe(df: &DataFrame) -> Self {
+        let rows = df.height();
+        let median_price = df
+            .column(PRICE_COLUMN)
+            .ok()
+            .and_then(|column| column.cast(&DataType::Float64).ok())
+            .and_then(|column| column.f64().ok().and_then(|prices| prices.median()));
+        let null_rates = df
+            .get_columns()
+            .iter()
+            .filter(|column| !column.name().starts_with('_'))
+            .map(|column| {
+                let rate = if rows == 0 {
+                    0.0
+                } else {
+                    column.null_count() as f64 / rows as f64 * 100.0
+                };
+                (column.name().to_string(), rate)
+            })
+            .collect();
+
+        SnapshotStats { taken_at: Utc::now(), rows, median_price, null_rates }
+    }
+}
+
+/// What changed too much between the baseline and the current snapshot
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Anomaly {
+    ProductCount { previous: usize, current: usize },
+    MedianPrice { previous: f64, current: f64 },
+    /// Null rates in percent
+    NullRate { column: String, previous: f64, current: f64 },
+}
+
+impl fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Anomaly::ProductCount { previous, current } => write!(
+                f,
+                "product count {} -> {} ({:+.1}%)",
+                previous,
+                current,
+                change_pct(*previous as f64, *current as f64)
+            ),
+            Anomaly::MedianPrice { previous, current } => write!(
+                f,
+                "median {} {:.2} -> {:.2} ({:+.1}%)",
+                PRICE_COLUMN,
+                previous,
+                current,
+                change_pct(*previous, *current)
+            ),
+            Anomaly::NullRate { column, previous, current } => {
+                write!(f, "{} null rate {:.1}% -> {:.1}%", column, previous, current)
+            }
+        }
+    }
+}
+
+/// Compares a source's snapshot with the previous one, to catch broken
+/// selectors or API changes that still produce a (near-empty) file
+#[derive(Debug, Clone)]
+pub struct AnomalyDetector {
+    max_product_count_change_pct: f64,
+    max_median_price_change_pct: f64,
+    max_null_rate_increase: f64,
+}
+
+impl Default for AnomalyDetector {
+    fn default() -> Self {
+        AnomalyDetector {
+            max_product_count_change_pct: DEFAULT_MAX_PRODUCT_COUNT_CHANGE_PCT,
+            max_median_price_change_pct: DEFAULT_MAX_MEDIAN_PRICE_CHANGE_PCT,
+            max_null_rate_increase: DEFAULT_MAX_NULL_RATE_INCREASE,
+        }
+    }
+}
+
+impl AnomalyDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_product_count_change(mut self, pct: f64) -> Self {
+        self.max_product_count_change_pct = pct;
+        self
+    }
+
+    pub fn with_max_median_price_change(mut self, pct: f64) -> Self {
+        self.max_median_price_change_pct = pct;
+        self
+    }
+
+    /// In percentage points, e.g. 25 flags a column going from 10% to 40% null
+    pub fn with_max_null_rate_increase(mut self, points: f64) -> Self {
+        self.max_null_rate_increase = points;
+        self
+    }
+
+    /// Every threshold `current` breaches relative to `baseline`. Changes
+    /// against an empty baseline, and columns missing from either snapshot,
+    /// are not compared.
+    pub fn detect(&self, baseline: &SnapshotStats, current: &SnapshotStats) -> Vec<Anomaly> {
+        let mut anomalies = Vec::new();
+
+        if baseline.rows > 0
+            && change_pct(baseline.rows as f64, current.rows as f64).abs() > self.max_product_count_change_pct
+        {
+            anomalies.push(Anomaly::ProductCount { previous: baseline.rows, current: current.rows });
+        }
+
+        if let (Some(previous), Some(price)) = (baseline.median_price, current.median_price)
+            && previous > 0.0
+            && change_pct(previous, price).abs() > self.max_median_price_change_pct
+        {
+            anomalies.push(Anomaly::MedianPrice { previous, current: price });
+        }
+
+        for (column, previous) in &baseline.null_rates {
+            if let Some(rate) = current.null_rates.get(column)
+                && rate - previous > self.max_null_rate_increase
+            {
+                anomalies.push(Anomaly::NullRate { column: column.clone(), previous: *previous, current: *rate });
+            }
+        }
+
+        anomalies
+    }
+}
+
+fn change_pct(previous: f64, current: f64) -> f64 {
+    if previous == 0.0 {
+        return 0.0;
+    }
+    (current - previous) / previous * 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(rows: usize, median_price: Option<f64>, null_rates: &[(&str, f64)]) -> SnapshotStats {
+        SnapshotStats {
+            taken_at: Utc::now(),
+            rows,
+            median_price,
+            null_rates: null_rates.iter().map(|(column, rate)| (column.to_string(), *rate)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_snapshot_stats() {
+        let df = df!(
+            "name" => [Some("milk"), Some("eggs"), None, Some("tea")],
+            "cost_price" => [Some(100.0), Some(300.0), Some(200.0), None],
+            "_source_category" => [None::<&str>, None, None, None]
+        )
+        .unwrap();
+
+        let stats = SnapshotStats::from_dataframe(&df);
+        assert_eq!(stats.rows, 4);
+        assert_eq!(stats.median_price, Some(200.0));
+        assert_eq!(stats.null_rates.get("name"), Some(&25.0));
+        assert_eq!(stats.null_rates.get("cost_price"), Some(&25.0));
+        assert!(!stats.null_rates.contains_key("_source_category"));
+
+        let round_trip: SnapshotStats = serde_json::from_str(&serde_json::to_string(&stats).unwrap()).unwrap();
+        assert_eq!(round_trip, stats);
+    }
+
+    #[test]
+    fn test_product_count_change() {
+        let detector = AnomalyDetector::new();
+        let baseline = stats(1000, None, &[]);
+
+        assert!(detector.detect(&baseline, &stats(700, None, &[])).is_empty());
+        let anomalies = detector.detect(&baseline, &stats(12, None, &[]));
+        assert_eq!(anomalies, vec![Anomaly::ProductCount { previous: 1000, current: 12 }]);
+        assert_eq!(anomalies[0].to_string(), "product count 1000 -> 12 (-98.8%)");
+        // Sudden growth is as suspect as a drop
+        assert_eq!(detector.detect(&baseline, &stats(1600, None, &[])).len(), 1);
+        // Nothing to compare against an empty baseline
+        assert!(detector.detect(&stats(0, None, &[]), &stats(500, None, &[])).is_empty());
+    }
+
+    #[test]
+    fn test_median_price_change() {
+        let detector = AnomalyDetector::new().with_max_median_price_change(20.0);
+        let baseline = stats(100, Some(250.0), &[]);
+
+        assert!(detector.detect(&baseline, &stats(100, Some(290.0), &[])).is_empty());
+        let anomalies = detector.detect(&baseline, &stats(100, Some(25.0), &[]));
+        assert_eq!(anomalies, vec![Anomaly::MedianPrice { previous: 250.0, current: 25.0 }]);
+        assert_eq!(anomalies[0].to_string(), "median cost_price 250.00 -> 25.00 (-90.0%)");
+        assert!(detector.detect(&baseline, &stats(100, None, &[])).is_empty());
+    }
+
+    #[test]
+    fn test_null_rate_increase() {
+        let detector = AnomalyDetector::new().with_max_null_rate_increase(10.0);
+        let baseline = stats(100, None, &[("brand", 20.0), ("mrp", 0.0), ("sku", 50.0)]);
+        let current = stats(100, None, &[("brand", 25.0), ("mrp", 100.0), ("sku", 0.0)]);
+
+        let anomalies = detector.detect(&baseline, &current);
+        assert_eq!(
+            anomalies,
+            vec![Anomaly::NullRate { column: "mrp".to_string(), previous: 0.0, current: 100.0 }]
+        );
+        assert_eq!(anomalies[0].to_string(), "mrp null rate 0.0% -> 100.0%");
+        // Columns the current snapshot no longer has are not compared
+        assert!(detector.detect(&baseline, &stats(100, None, &[])).is_empty());
+    }
+}
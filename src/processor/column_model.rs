@@ -0,0 +1,383 @@
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use smartcore::ensemble::random_forest_classifier::{
+    RandomForestClassifier, RandomForestClassifierParameters,
+};
+use smartcore::linalg::basic::matrix::DenseMatrix;
+use std::collections::HashSet;
+
+use super::json_flattener::{detect_currency, strip_currency};
+
+/// Fragments of a column name, each adding one presence feature
+const NAME_TOKENS: [&str; 38] = [
+    "price", "cost", "mrp", "sale", "sell", "special", "actual", "original", "list", "retail",
+    "offer", "final", "discount", "percent", "off", "saving", "name", "title", "label", "product",
+    "item", "id", "sku", "code", "barcode", "category", "section", "department", "aisle", "brand",
+    "manufacturer", "image", "img", "photo", "pic", "thumb", "url", "link",
+];
+
+/// Vote share a prediction needs before `FieldClassifier` acts on it
+pub const DEFAULT_CONFIDENCE_THRESHOLD: f32 = 0.6;
+
+const DEFAULT_TREES: u16 = 25;
+const DEFAULT_SEED: u64 = 42;
+const ARTIFACT_VERSION: u32 = 1;
+
+/// Header of the labeled CSV read by `parse_labeled_csv`
+const CSV_HEADER: [&str; 3] = ["column_name", "sample_values", "canonical_label"];
+
+/// One training row: a source column and the canonical field it holds
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabeledColumn {
+    pub column_name: String,
+    pub sample_values: Vec<String>,
+    pub label: String,
+}
+
+/// Saved model. smartcore is built without its serde feature, so the
+/// artifact keeps the encoded training set and the forest is refit from it
+/// on load; the fixed seeds make that reproduce the same trees.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ModelArtifact {
+    version: u32,
+    labels: Vec<String>,
+    features: Vec<Vec<f32>>,
+    targets: Vec<i32>,
+    n_trees: u16,
+    seed: u64,
+    confidence_threshold: f32,
+}
+
+/// Random forest predicting a column's canonical name from its name tokens
+/// and sample values, for columns the heuristics in `FieldClassifier` miss.
+/// Each tree is a single-tree smartcore forest with its own seed, so the
+/// share of trees voting for a label can serve as its confidence.
+pub struct ColumnModel {
+    trees: Vec<RandomForestClassifier<f32, i32, DenseMatrix<f32>, Vec<i32>>>,
+    artifact: ModelArtifact,
+}
+
+impl ColumnModel {
+    /// Train on labeled columns; at least two distinct labels are needed
+    pub fn train(examples: &[LabeledColumn]) -> Result<Self> {
+        let mut labels: Vec<String> = examples.iter().map(|e| e.label.clone()).collect();
+        labels.sort();
+        labels.dedup();
+        if labels.len() < 2 {
+            return Err(anyhow!(
+                "Need examples of at least two labels to train a column classifier, got {}",
+                labels.len()
+            ));
+        }
+
+        let features = examples
+            .iter()
+            .map(|e| column_features(&e.column_name, &e.sample_values))
+            .collect();
+        let targets = examples
+            .iter()
+            .map(|e| labels.binary_search(&e.label).unwrap() as i32)
+            .collect();
+
+        Self::fit(ModelArtifact {
+            version: ARTIFACT_VERSION,
+            labels,
+            features,
+            targets,
+            n_trees: DEFAULT_TREES,
+            seed: DEFAULT_SEED,
+            confidence_threshold: DEFAULT_CONFIDENCE_THRESHOLD,
+        })
+    }
+
+    fn fit(artifact: ModelArtifact) -> Result<Self> {
+        let matrix = DenseMatrix::from_2d_vec(&artifact.features);
+        // Half the features per split instead of smartcore's square root: the
+        // name tokens are sparse, and with too few candidates the trees
+        // disagree so much that hardly any vote clears the threshold
+        let features_per_split = artifact.features.first().map_or(1, |row| (row.len() / 2).max(1));
+        let trees = (0..artifact.n_trees)
+            .map(|i| {
+                let params = RandomForestClassifierParameters::default()
+                    .with_n_trees(1)
+                    .with_m(features_per_split)
+                    .with_seed(artifact.seed + i as u64);
+                RandomForestClassifier::fit(&matrix, &artifact.targets, params)
+                    .map_err(|e| anyhow!("Failed to train column classifier: {}", e))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { trees, artifact })
+    }
+
+    pub fn with_confidence_threshold(mut self, threshold: f32) -> Self {
+        self.artifact.confidence_threshold = threshold;
+        self
+    }
+
+    pub fn confidence_threshold(&self) -> f32 {
+        self.artifact.confidence_threshold
+    }
+
+    /// Labels the model can predict
+    pub fn labels(&self) -> &[String] {
+        &self.artifact.labels
+    }
+
+    /// Most voted label and its share of the votes
+    pub fn predict(&self, column_name: &str, sample_values: &[String]) -> Option<(String, f32)> {
+        let row = DenseMatrix::from_2d_vec(&vec![column_features(column_name, sample_values)]);
+        let mut votes = vec![0usize; self.artifact.labels.len()];
+        for tree in &self.trees {
+            if let Ok(prediction) = tree.predict(&row)
+                && let Some(vote) = votes.get_mut(prediction[0] as usize)
+            {
+                *vote += 1;
+            }
+        }
+
+        let (best, count) = votes.iter().enumerate().max_by_key(|(_, count)| **count)?;
+        if *count == 0 {
+            return None;
+        }
+        Some((
+            self.artifact.labels[best].clone(),
+            *count as f32 / self.trees.len() as f32,
+        ))
+    }
+
+    /// Predicted label, if it clears the confidence threshold
+    pub fn classify(&self, column_name: &str, sample_values: &[String]) -> Option<String> {
+        self.predict(column_name, sample_values)
+            .filter(|(_, confidence)| *confidence >= self.artifact.confidence_threshold)
+            .map(|(label, _)| label)
+    }
+
+    pub fn save(&self, path: &str) -> Result<()> {
+        let json = serde_json::to_string(&self.artifact)?;
+        std::fs::write(path, json)
+            .with_context(|| format!("Failed to write column model to {}", path))
+    }
+
+    pub fn load(path: &str) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read column model from {}", path))?;
+        let artifact: ModelArtifact = serde_json::from_str(&json)
+            .with_context(|| format!("Invalid column model in {}", path))?;
+        if artifact.version != ARTIFACT_VERSION {
+            return Err(anyhow!(
+                "Column model {} has version {}, expected {}",
+                path,
+                artifact.version,
+                ARTIFACT_VERSION
+            ));
+        }
+        Self::fit(artifact)
+    }
+}
+
+/// Feature vector of a column: name token presence followed by sample
+/// statistics (numeric, currency, percent and URL ratios, average length and
+/// word count, distinct count and ratio, mean numeric value, digit ratio)
+pub fn column_features(column_name: &str, sample_values: &[String]) -> Vec<f32> {
+    let name: String = column_name
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect();
+    let mut features: Vec<f32> = NAME_TOKENS
+        .iter()
+        .map(|token| if name.contains(token) { 1.0 } else { 0.0 })
+        .collect();
+
+    let values: Vec<&str> = sample_values
+        .iter()
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .collect();
+    let count = values.len().max(1) as f32;
+    let ratio = |matches: usize| matches as f32 / count;
+
+    let numbers: Vec<f64> = values
+        .iter()
+        .filter_map(|v| strip_currency(v).trim_end_matches('%').trim().parse::<f64>().ok())
+        .collect();
+    let currency = values
+        .iter()
+        .filter(|v| detect_currency(v).is_some() || v.contains(['$', '₹', '€', '£']))
+        .count();
+    let percent = values
+        .iter()
+        .filter(|v| v.contains('%') || v.to_lowercase().contains("off"))
+        .count();
+    let urls = values.iter().filter(|v| v.contains("://")).count();
+    let total_chars: usize = values.iter().map(|v| v.chars().count()).sum();
+    let digits: usize = values
+        .iter()
+        .map(|v| v.chars().filter(|c| c.is_ascii_digit()).count())
+        .sum();
+    let words: usize = values.iter().map(|v| v.split_whitespace().count()).sum();
+    let distinct = values.iter().collect::<HashSet<_>>().len();
+    let mean = if numbers.is_empty() {
+        0.0
+    } else {
+        numbers.iter().sum::<f64>() / numbers.len() as f64
+    };
+
+    features.extend([
+        ratio(numbers.len()),
+        ratio(currency),
+        ratio(percent),
+        ratio(urls),
+        total_chars as f32 / count,
+        words as f32 / count,
+        distinct as f32,
+        ratio(distinct),
+        mean as f32,
+        if total_chars == 0 { 0.0 } else { digits as f32 / total_chars as f32 },
+    ]);
+    features
+}
+
+/// Parse a labeled CSV with a `column_name,sample_values,canonical_label`
+/// header; sample values are separated by `|` within their field
+pub fn parse_labeled_csv(content: &str) -> Result<Vec<LabeledColumn>> {
+    let mut lines = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+
+    let (_, header) = lines.next().ok_or_else(|| anyhow!("Labeled CSV is empty"))?;
+    let header = split_csv_line(header);
+    if header.iter().map(|h| h.trim()).ne(CSV_HEADER) {
+        return Err(anyhow!(
+            "Labeled CSV header must be {}, got {}",
+            CSV_HEADER.join(","),
+            header.join(",")
+        ));
+    }
+
+    lines
+        .map(|(index, line)| {
+            let fields = split_csv_line(line);
+            let [column_name, samples, label] = fields.as_slice() else {
+                return Err(anyhow!(
+                    "Line {}: expected 3 fields, got {}",
+                    index + 1,
+                    fields.len()
+                ));
+            };
+            Ok(LabeledColumn {
+                column_name: column_name.trim().to_string(),
+                sample_values: samples
+                    .split('|')
+                    .map(|v| v.trim().to_string())
+                    .filter(|v| !v.is_empty())
+                    .collect(),
+                label: label.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Split one CSV line, honouring double quotes and `""` escapes
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> Vec<LabeledColumn> {
+        let csv = std::fs::read_to_string("src/configs/column_labels.csv").unwrap();
+        parse_labeled_csv(&csv).unwrap()
+    }
+
+    fn samples(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_labeled_csv() {
+        let csv = "column_name,sample_values,canonical_label\n\
+                   price,\"Rs. 1,200|Rs. 99\",cost_price\n\
+                   \n\
+                   title,\"Tapal \"\"Danedar\"\" 950g\",name\n";
+        let rows = parse_labeled_csv(csv).unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].sample_values, vec!["Rs. 1,200", "Rs. 99"]);
+        assert_eq!(rows[1].sample_values, vec!["Tapal \"Danedar\" 950g"]);
+        assert_eq!(rows[1].label, "name");
+
+        assert!(parse_labeled_csv("name,label\nprice,cost_price").is_err());
+        assert!(parse_labeled_csv("column_name,sample_values,canonical_label\nprice,10").is_err());
+        assert!(fixture().len() >= 40);
+    }
+
+    #[test]
+    fn test_training_and_prediction() {
+        let model = ColumnModel::train(&fixture()).unwrap();
+        assert!(model.labels().contains(&"image_url".to_string()));
+
+        let img = samples(&["https://cdn.example.com/p/1.jpg", "https://cdn.example.com/p/2.jpg"]);
+        assert_eq!(model.classify("img", &img).as_deref(), Some("image_url"));
+
+        let pct = samples(&["10%", "25%", "5%"]);
+        assert_eq!(model.classify("pct", &pct).as_deref(), Some("discount"));
+
+        let labels = samples(&["Olpers Milk 1 Litre", "Tapal Danedar 950g", "Shan Biryani Masala 50g"]);
+        assert_eq!(model.classify("lbl", &labels).as_deref(), Some("name"));
+
+        let (_, confidence) = model.predict("img", &img).unwrap();
+        assert!(confidence > 0.0 && confidence <= 1.0);
+
+        // Nothing clears an unreachable threshold
+        let strict = model.with_confidence_threshold(1.01);
+        assert_eq!(strict.classify("img", &img), None);
+
+        let single_label: Vec<LabeledColumn> =
+            fixture().into_iter().filter(|e| e.label == "sku").collect();
+        assert!(ColumnModel::train(&single_label).is_err());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let model = ColumnModel::train(&fixture()).unwrap().with_confidence_threshold(0.5);
+        let path = std::env::temp_dir().join(format!("column_model_{}.json", uuid::Uuid::new_v4()));
+        let path = path.to_str().unwrap();
+        model.save(path).unwrap();
+
+        let loaded = ColumnModel::load(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(loaded.labels(), model.labels());
+        assert_eq!(loaded.confidence_threshold(), 0.5);
+
+        for example in fixture() {
+            assert_eq!(
+                loaded.predict(&example.column_name, &example.sample_values),
+                model.predict(&example.column_name, &example.sample_values)
+            );
+        }
+
+        assert!(ColumnModel::load("src/configs/column_labels.csv").is_err());
+    }
+}
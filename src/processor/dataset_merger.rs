@@ -0,0 +1,194 @@
+use anyhow::{Result, anyhow};
+use chrono::{NaiveDate, Utc};
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Name of the column identifying which source a merged row came from
+pub const SOURCE_COLUMN: &str = "source";
+
+/// Summary of a merged dataset, stored next to the merged Parquet file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeManifest {
+    pub date: NaiveDate,
+    pub merged_key: String,
+    pub total_rows: usize,
+    pub rows_per_source: BTreeMap<String, usize>,
+    pub created_at: String,
+}
+
+impl MergeManifest {
+    pub fn new(date: NaiveDate, merged_key: &str, merged: &DataFrame) -> Result<Self> {
+        let mut rows_per_source = BTreeMap::new();
+        for source in merged.column(SOURCE_COLUMN)?.str()?.into_iter().flatten() {
+            *rows_per_source.entry(source.to_string()).or_insert(0) += 1;
+        }
+
+        Ok(Self {
+            date,
+            merged_key: merged_key.to_string(),
+            total_rows: merged.height(),
+            rows_per_source,
+            created_at: Utc::now().to_rfc3339(),
+        })
+    }
+}
+
+/// Combines the clean DataFrames of several sources into one dataset
+pub struct DatasetMerger;
+
+impl DatasetMerger {
+    pub fn new() -> Self {
+        DatasetMerger
+    }
+
+    /// Tag each DataFrame with its source, align schemas and stack them.
+    ///
+    /// Columns missing from a source are filled with nulls; columns present in
+    /// several sources are cast to the type they have in the first one. Rows
+    /// that already carry a `source` provenance value keep it.
+    pub fn merge(&self, sources: &[(String, DataFrame)]) -> Result<DataFrame> {
+        if sources.is_empty() {
+            return Err(anyhow!("No DataFrames to merge"));
+        }
+
+        // Union of all columns, in first-seen order
+        let mut schema: Vec<(PlSmallStr, DataType)> = Vec::new();
+        for (_, df) in sources {
+            for column in df.get_columns() {
+                if column.name() != SOURCE_COLUMN && !schema.iter().any(|(name, _)| name == column.name()) {
+                    schema.push((column.name().clone(), column.dtype().clone()));
+                }
+            }
+        }
+
+        let mut merged: Option<DataFrame> = None;
+        for (source_name, df) in sources {
+            let height = df.height();
+
+            let mut columns: Vec<Column> = Vec::with_capacity(schema.len() + 1);
+            let tag = Series::new(SOURCE_COLUMN.into(), vec![source_name.as_str(); height]);
+            let source = match df.column(SOURCE_COLUMN) {
+                Ok(existing) => existing
+                    .cast(&DataType::String)?
+                    .as_materialized_series()
+                    .zip_with(&existing.is_not_null(), &tag)?,
+                Err(_) => tag,
+            };
+            columns.push(source.into());
+
+            for (name, dtype) in &schema {
+                let column = match df.column(name) {
+                    Ok(column) => column.cast(dtype)?,
+                    Err(_) => Series::full_null(name.clone(), height, dtype).into(),
+                };
+                columns.push(column);
+            }
+
+            let aligned = DataFrame::new(columns)?;
+            merged = Some(match merged {
+                Some(mut acc) => {
+                    acc.vstack_mut(&aligned)?;
+                    acc
+                }
+                None => aligned,
+            });
+        }
+
+        let mut merged = merged.ok_or_else(|| anyhow!("No DataFrames to merge"))?;
+        merged.align_chunks();
+        Ok(merged)
+    }
+}
+
+impl Default for DatasetMerger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_fills_missing_columns_with_nulls() {
+        let krave_mart = df!(
+            "name" => ["milk", "eggs"],
+            "cost_price" => [100.0, 200.0],
+            "units" => ["1 l", "12"]
+        )
+        .unwrap();
+        let naheed = df!(
+            "name" => ["bread"],
+            "cost_price" => [80.0],
+            "discount" => [10.0]
+        )
+        .unwrap();
+
+        let merger = DatasetMerger::new();
+        let merged = merger
+            .merge(&[
+                ("krave_mart".to_string(), krave_mart),
+                ("naheed".to_string(), naheed),
+            ])
+            .unwrap();
+
+        assert_eq!(merged.height(), 3);
+        assert_eq!(
+            merged.get_column_names_str(),
+            vec!["source", "name", "cost_price", "units", "discount"]
+        );
+
+        let sources: Vec<_> = merged.column("source").unwrap().str().unwrap().into_iter().collect();
+        assert_eq!(sources, vec![Some("krave_mart"), Some("krave_mart"), Some("naheed")]);
+
+        let units = merged.column("units").unwrap().str().unwrap();
+        assert_eq!(units.get(2), None);
+        let discount = merged.column("discount").unwrap().f64().unwrap();
+        assert_eq!(discount.null_count(), 2);
+        assert_eq!(discount.get(2), Some(10.0));
+    }
+
+    #[test]
+    fn test_merge_keeps_row_provenance() {
+        let krave_mart = df!(
+            "name" => ["milk", "eggs"],
+            "source" => [Some("krave_mart_1242164"), None]
+        )
+        .unwrap();
+        let naheed = df!("name" => ["bread"]).unwrap();
+
+        let merged = DatasetMerger::new()
+            .merge(&[
+                ("krave_mart".to_string(), krave_mart),
+                ("naheed".to_string(), naheed),
+            ])
+            .unwrap();
+
+        assert_eq!(merged.get_column_names_str(), vec!["source", "name"]);
+        let sources: Vec<_> = merged.column("source").unwrap().str().unwrap().into_no_null_iter().collect();
+        assert_eq!(sources, vec!["krave_mart_1242164", "krave_mart", "naheed"]);
+    }
+
+    #[test]
+    fn test_manifest_counts_rows_per_source() {
+        let a = df!("name" => ["milk", "eggs"]).unwrap();
+        let b = df!("name" => ["bread"]).unwrap();
+
+        let merged = DatasetMerger::new()
+            .merge(&[("a".to_string(), a), ("b".to_string(), b)])
+            .unwrap();
+
+        let date = NaiveDate::from_ymd_opt(2025, 9, 15).unwrap();
+        let manifest = MergeManifest::new(date, "clean/_merged/date=2025-09-15/merged.parquet", &merged).unwrap();
+        assert_eq!(manifest.total_rows, 3);
+        assert_eq!(manifest.rows_per_source.get("a"), Some(&2));
+        assert_eq!(manifest.rows_per_source.get("b"), Some(&1));
+    }
+
+    #[test]
+    fn test_merge_requires_input() {
+        assert!(DatasetMerger::new().merge(&[]).is_err());
+    }
+}
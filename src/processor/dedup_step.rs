@@ -0,0 +1,236 @@
+use anyhow::{Result, anyhow};
+use polars::prelude::*;
+use std::collections::HashMap;
+use std::str::FromStr;
+use tracing::info;
+
+use super::pipeline::Processor;
+
+/// Which row survives when a product appears more than once in a source
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DedupStrategy {
+    /// Keep the first row as-is
+    KeepFirst,
+    /// Keep the row with the lowest `cost_price` (rows without one last)
+    KeepLowestPrice,
+    /// Keep the first row, with the `category` values of all rows merged
+    #[default]
+    MergeCategories,
+}
+
+impl FromStr for DedupStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().replace('_', "-").as_str() {
+            "keep-first" | "first" => Ok(DedupStrategy::KeepFirst),
+            "keep-lowest-price" | "lowest-price" => Ok(DedupStrategy::KeepLowestPrice),
+            "merge-categories" | "merge" => Ok(DedupStrategy::MergeCategories),
+            _ => Err(anyhow!(
+                "Unknown dedup strategy '{}'. Supported: keep-first, keep-lowest-price, merge-categories",
+                s
+            )),
+        }
+    }
+}
+
+/// Collapses repeated products in a normalized DataFrame, e.g. a KraveMart
+/// product listed under several categories. Rows are keyed on `product_id`
+/// plus `source` when the frame has one; rows without an ID are left alone.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DedupStep {
+    strategy: DedupStrategy,
+}
+
+impl DedupStep {
+    pub fn new(strategy: DedupStrategy) -> Self {
+        DedupStep { strategy }
+    }
+
+    /// Deduplicate `df` in place, returning how many rows were collapsed
+    pub fn apply(&self, df: &mut DataFrame) -> Result<usize> {
+        let Ok(product_ids) = df.column("product_id") else {
+            return Ok(0);
+        };
+        let product_ids = product_ids.cast(&DataType::String)?;
+        let product_ids = product_ids.str()?;
+        let sources = match df.column("source") {
+            Ok(column) => Some(column.cast(&DataType::String)?),
+            Err(_) => None,
+        };
+        let sources = sources.as_ref().map(|column| column.str()).transpose()?;
+
+        // Rows per key, in order of first appearance
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        let mut group_of: HashMap<(Option<&str>, &str), usize> = HashMap::new();
+        for (row, product_id) in product_ids.into_iter().enumerate() {
+            let Some(product_id) = product_id.filter(|id| !id.trim().is_empty()) else {
+                groups.push(vec![row]);
+                continue;
+            };
+            let source = sources.and_then(|sources| sources.get(row));
+            match group_of.get(&(source, product_id)) {
+                Some(&group) => groups[group].push(row),
+                None => {
+                    group_of.insert((source, product_id), groups.len());
+                    groups.push(vec![row]);
+                }
+            }
+        }
+
+        let collapsed = df.height() - groups.len();
+        if collapsed == 0 {
+            return Ok(0);
+        }
+
+        let kept: Vec<IdxSize> = groups
+            .iter()
+            .map(|rows| self.pick(df, rows).map(|row| row as IdxSize))
+            .collect::<Result<_>>()?;
+        let merged_categories = match self.strategy {
+            DedupStrategy::MergeCategories => merge_categories(df, &groups)?,
+            _ => None,
+        };
+
+        let mut deduped = df.take(&IdxCa::from_vec("idx".into(), kept))?;
+        if let Some(categories) = merged_categories {
+            deduped.with_column(categories)?;
+        }
+        *df = deduped;
+        Ok(collapsed)
+    }
+
+    /// The row kept for one group of duplicates
+    fn pick(&self, df: &DataFrame, rows: &[usize]) -> Result<usize> {
+        if rows.len() == 1 || self.strategy != DedupStrategy::KeepLowestPrice {
+            return Ok(rows[0]);
+        }
+        let Ok(prices) = df.column("cost_price") else {
+            return Ok(rows[0]);
+        };
+        let prices = prices.cast(&DataType::Float64)?;
+        let prices = prices.f64()?;
+
+        let mut best = rows[0];
+        for &row in &rows[1..] {
+            match (prices.get(row), prices.get(best)) {
+                (Some(price), Some(best_price)) if price < best_price => best = row,
+                (Some(_), None) => best = row,
+                _ => {}
+            }
+        }
+        Ok(best)
+    }
+}
+
+/// Each group's distinct categories joined with ", ", in order of appearance
+fn merge_categories(df: &DataFrame, groups: &[Vec<usize>]) -> Result<Option<Series>> {
+    let Ok(categories) = df.column("category") else {
+        return Ok(None);
+    };
+    let categories = categories.cast(&DataType::String)?;
+    let categories = categories.str()?;
+
+    let merged: Vec<Option<String>> = groups
+        .iter()
+        .map(|rows| {
+            let mut seen: Vec<&str> = Vec::new();
+            for category in rows.iter().filter_map(|row| categories.get(*row)) {
+                for part in category.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+                    if !seen.contains(&part) {
+                        seen.push(part);
+                    }
+                }
+            }
+            (!seen.is_empty()).then(|| seen.join(", "))
+        })
+        .collect();
+    Ok(Some(Series::new("category".into(), merged)))
+}
+
+impl Processor for DedupStep {
+    fn name(&self) -> &str {
+        "dedup"
+    }
+
+    fn process(&self, df: &mut DataFrame) -> Result<()> {
+        let collapsed = self.apply(df)?;
+        info!("Collapsed {} duplicate products", collapsed);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn duplicated_products() -> DataFrame {
+        df!(
+            "product_id" => [Some("1"), Some("2"), Some("1"), None, Some("1"), None],
+            "name" => ["milk", "eggs", "milk", "loose", "milk", "loose"],
+            "cost_price" => [Some(280.0), Some(380.0), Some(260.0), Some(10.0), None, Some(10.0)],
+            "category" => [Some("dairy"), Some("eggs"), Some("flash deals, dairy"), None, Some("breakfast"), None]
+        )
+        .unwrap()
+    }
+
+    fn strings(df: &DataFrame, column: &str) -> Vec<Option<String>> {
+        df.column(column)
+            .unwrap()
+            .str()
+            .unwrap()
+            .into_iter()
+            .map(|v| v.map(str::to_string))
+            .collect()
+    }
+
+    #[test]
+    fn test_merge_categories() {
+        let mut df = duplicated_products();
+        let collapsed = DedupStep::new(DedupStrategy::MergeCategories).apply(&mut df).unwrap();
+
+        assert_eq!(collapsed, 2);
+        assert_eq!(df.height(), 4);
+        assert_eq!(
+            strings(&df, "category"),
+            vec![
+                Some("dairy, flash deals, breakfast".to_string()),
+                Some("eggs".to_string()),
+                None,
+                None
+            ]
+        );
+        // The first row's other fields are kept
+        assert_eq!(df.column("cost_price").unwrap().f64().unwrap().get(0), Some(280.0));
+    }
+
+    #[test]
+    fn test_keep_lowest_price() {
+        let mut df = duplicated_products();
+        let collapsed = DedupStep::new(DedupStrategy::KeepLowestPrice).apply(&mut df).unwrap();
+
+        assert_eq!(collapsed, 2);
+        let prices: Vec<Option<f64>> = df.column("cost_price").unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!(prices, vec![Some(260.0), Some(380.0), Some(10.0), Some(10.0)]);
+        assert_eq!(strings(&df, "category")[0], Some("flash deals, dairy".to_string()));
+    }
+
+    #[test]
+    fn test_source_is_part_of_the_key() {
+        let mut df = df!(
+            "source" => ["krave_mart", "dealcart", "krave_mart"],
+            "product_id" => ["1", "1", "1"]
+        )
+        .unwrap();
+        let collapsed = DedupStep::new(DedupStrategy::KeepFirst).apply(&mut df).unwrap();
+        assert_eq!(collapsed, 1);
+        assert_eq!(strings(&df, "source"), vec![Some("krave_mart".to_string()), Some("dealcart".to_string())]);
+    }
+
+    #[test]
+    fn test_parse_strategy() {
+        assert_eq!("keep_first".parse::<DedupStrategy>().unwrap(), DedupStrategy::KeepFirst);
+        assert_eq!("Keep-Lowest-Price".parse::<DedupStrategy>().unwrap(), DedupStrategy::KeepLowestPrice);
+        assert!("newest".parse::<DedupStrategy>().is_err());
+    }
+}
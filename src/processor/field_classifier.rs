@@ -1,63 +1,229 @@
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
+use tracing::info;
+
+use super::column_model::ColumnModel;
+use super::json_flattener::{CANONICAL_FIELDS, CURRENCY_FIELD, DERIVED_FIELDS, PROVENANCE_FIELDS, RAW_JSON_FIELD};
+
+/// Known source column names and the canonical name each maps to. When
+/// several columns of one DataFrame map to the same name they are merged,
+/// with earlier entries taking precedence.
+const FIELD_MAPPINGS: [(&str, &str); 41] = [
+    // Initialize with common field name patterns
+    ("cost_price", "cost_price"),
+    ("mrp", "mrp"),
+    ("name", "name"),
+    ("sku", "sku"),
+    ("product_id", "product_id"),
+    ("units_of_mass", "units_of_mass"),
+    ("image_url", "image_url"),
+    ("in_stock", "in_stock"),
+    // Written by RuleNormalizer; reclassified clean snapshots must keep them
+    ("quantity_value", "quantity_value"),
+    ("quantity_unit", "quantity_unit"),
+    ("price_per_unit", "price_per_unit"),
+    ("price_per_unit_basis", "price_per_unit_basis"),
+    ("price_per_base_unit", "price_per_base_unit"),
+    ("base_unit", "base_unit"),
+    ("quality_flags", "quality_flags"),
+    ("name_original", "name_original"),
+    ("sku_percent_off", "discount"),
+    ("category_name", "category"),
+    // Dealcart-specific field mappings
+    ("id", "product_id"),
+    ("dcImsMrp", "mrp"),
+    ("discountedPrice", "cost_price"),
+    ("productCategory", "category"),
+    // Pandamart-specific field mappings
+    ("productID", "product_id"),
+    ("originalPrice", "mrp"),
+    ("price", "cost_price"),
+    ("category_section", "category"),
+    // Add common variations
+    ("product_price", "mrp"),
+    ("special_price", "cost_price"),
+    ("selling_price", "cost_price"),
+    ("product_name", "name"),
+    ("item_name", "name"),
+    ("title", "name"),
+    ("item_id", "product_id"),
+    ("discount", "discount"),
+    ("discount_percent", "discount"),
+    ("percent_off", "discount"),
+    ("category", "category"),
+    ("product_category", "category"),
+    ("item_category", "category"),
+    // Multi-store sources: keep store_id as-is rather than fuzzy-matching it to product_id
+    ("store_id", "store_id"),
+    (CURRENCY_FIELD, CURRENCY_FIELD),
+];
 
 pub struct FieldClassifier {
     field_mappings: HashMap<String, String>,
+    /// Source column names in precedence order for merging collisions
+    mapping_order: Vec<String>,
+    /// Trained fallback for columns the heuristics leave unmapped
+    column_model: Option<ColumnModel>,
+}
+
+/// Which step of `classify_field` placed a column
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchRule {
+    /// The normalized name equals a known mapping
+    Exact,
+    /// The name contains, or is contained in, a known mapping
+    Fuzzy,
+    /// Name keywords or sample values
+    Content,
+    /// Prediction of the trained column model
+    Ml,
+    /// Nothing matched, the column keeps its name
+    Unmatched,
+}
+
+impl fmt::Display for MatchRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            MatchRule::Exact => "exact",
+            MatchRule::Fuzzy => "fuzzy",
+            MatchRule::Content => "content",
+            MatchRule::Ml => "ml",
+            MatchRule::Unmatched => "unmatched",
+        };
+        f.write_str(name)
+    }
+}
+
+/// How one source column was classified
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnClassification {
+    pub original_name: String,
+    pub canonical_name: String,
+    pub rule: MatchRule,
+    /// Values the content and model steps looked at
+    pub sample_values: Vec<String>,
+}
+
+/// What `map_to_canonical_schema` did to one column
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ColumnAction {
+    Renamed { from: String, to: String },
+    /// Several columns mapped to `into`; `sources` in precedence order, the
+    /// first non-null value of each row winning
+    Merged { into: String, sources: Vec<String> },
+    /// A lower-precedence column whose values were merged into `merged_into`
+    Dropped { column: String, merged_into: String },
+}
+
+/// How every column of a DataFrame was classified, and each rename, merge
+/// and drop made while mapping it to the canonical schema
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClassificationReport {
+    pub columns: Vec<ColumnClassification>,
+    pub actions: Vec<ColumnAction>,
+}
+
+impl ClassificationReport {
+    pub fn merges(&self) -> impl Iterator<Item = &ColumnAction> {
+        self.actions.iter().filter(|action| matches!(action, ColumnAction::Merged { .. }))
+    }
+
+    pub fn column(&self, original_name: &str) -> Option<&ColumnClassification> {
+        self.columns.iter().find(|column| column.original_name == original_name)
+    }
+
+    /// Add the columns and actions of another batch not seen yet
+    pub fn absorb(&mut self, other: ClassificationReport) {
+        for column in other.columns {
+            if self.column(&column.original_name).is_none() {
+                self.columns.push(column);
+            }
+        }
+        for action in other.actions {
+            if !self.actions.contains(&action) {
+                self.actions.push(action);
+            }
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+impl fmt::Display for ClassificationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "  {:<24} {:<20} {:<9} samples", "column", "canonical", "rule")?;
+        for column in &self.columns {
+            writeln!(
+                f,
+                "  {:<24} {:<20} {:<9} {}",
+                column.original_name,
+                column.canonical_name,
+                column.rule,
+                column.sample_values.join(" | ")
+            )?;
+        }
+        for action in &self.actions {
+            match action {
+                ColumnAction::Merged { into, sources } => {
+                    writeln!(f, "  merged {} into {}", sources.join(", "), into)?
+                }
+                ColumnAction::Dropped { column, merged_into } => {
+                    writeln!(f, "  dropped {} (merged into {})", column, merged_into)?
+                }
+                ColumnAction::Renamed { .. } => {}
+            }
+        }
+        Ok(())
+    }
 }
 
 impl FieldClassifier {
     pub fn new() -> Self {
-        let mut field_mappings = HashMap::new();
-
-        // Initialize with common field name patterns
-        field_mappings.insert("cost_price".to_string(), "cost_price".to_string());
-        field_mappings.insert("mrp".to_string(), "mrp".to_string());
-        field_mappings.insert("name".to_string(), "name".to_string());
-        field_mappings.insert("sku".to_string(), "sku".to_string());
-        field_mappings.insert("sku_percent_off".to_string(), "discount".to_string());
-        field_mappings.insert("category_name".to_string(), "category".to_string());
-
-        // Dealcart-specific field mappings
-        field_mappings.insert("id".to_string(), "product_id".to_string());
-        field_mappings.insert("dcImsMrp".to_string(), "mrp".to_string());
-        field_mappings.insert("discountedPrice".to_string(), "cost_price".to_string());
-        field_mappings.insert("productCategory".to_string(), "category".to_string());
-
-        // Pandamart-specific field mappings
-        field_mappings.insert("productID".to_string(), "product_id".to_string());
-        field_mappings.insert("originalPrice".to_string(), "mrp".to_string());
-        field_mappings.insert("price".to_string(), "cost_price".to_string());
-        field_mappings.insert("category_section".to_string(), "category".to_string());
-
-        // Add common variations
-        field_mappings.insert("price".to_string(), "cost_price".to_string());
-        field_mappings.insert("product_price".to_string(), "mrp".to_string());
-        field_mappings.insert("special_price".to_string(), "cost_price".to_string());
-        field_mappings.insert("selling_price".to_string(), "cost_price".to_string());
-        field_mappings.insert("product_name".to_string(), "name".to_string());
-        field_mappings.insert("item_name".to_string(), "name".to_string());
-        field_mappings.insert("title".to_string(), "name".to_string());
-        field_mappings.insert("product_id".to_string(), "product_id".to_string());
-        field_mappings.insert("item_id".to_string(), "product_id".to_string());
-        field_mappings.insert("id".to_string(), "product_id".to_string());
-        field_mappings.insert("discount".to_string(), "discount".to_string());
-        field_mappings.insert("discount_percent".to_string(), "discount".to_string());
-        field_mappings.insert("percent_off".to_string(), "discount".to_string());
-        field_mappings.insert("category".to_string(), "category".to_string());
-        field_mappings.insert("product_category".to_string(), "category".to_string());
-        field_mappings.insert("item_category".to_string(), "category".to_string());
-
-        FieldClassifier { field_mappings }
+        let mut classifier = FieldClassifier {
+            field_mappings: HashMap::new(),
+            mapping_order: Vec::new(),
+            column_model: None,
+        };
+        for (from, to) in FIELD_MAPPINGS {
+            classifier.add_field_mapping(from.to_string(), to.to_string());
+        }
+
+        // Columns the flattener already emits under their canonical name stay put
+        let provenance = PROVENANCE_FIELDS.iter().map(|(_, column)| column);
+        for field in CANONICAL_FIELDS.iter().chain(DERIVED_FIELDS.iter()).chain(provenance) {
+            if !classifier.field_mappings.contains_key(*field) {
+                classifier.add_field_mapping(field.to_string(), field.to_string());
+            }
+        }
+
+        classifier
     }
 
     pub fn classify_field(&self, field_name: &str, sample_values: &[String]) -> Result<String> {
+        Ok(self.explain_field(field_name, sample_values).0)
+    }
+
+    /// Canonical name of a column and the rule that produced it
+    pub fn explain_field(&self, field_name: &str, sample_values: &[String]) -> (String, MatchRule) {
+        // Columns already named after a canonical field are left alone, so a
+        // sample like "1 kg" cannot pull units_of_mass towards another field
+        if self.is_canonical_field(field_name) {
+            return (field_name.to_string(), MatchRule::Exact);
+        }
+
         let normalized_field = self.normalize_field_name(field_name);
 
         // Try rule-based classification first with exact matches
         for (pattern, canonical) in &self.field_mappings {
             let normalized_pattern = self.normalize_field_name(pattern);
             if normalized_field == normalized_pattern {
-                return Ok(canonical.clone());
+                return (canonical.clone(), MatchRule::Exact);
             }
         }
 
@@ -67,7 +233,7 @@ impl FieldClassifier {
             if normalized_field.contains(&normalized_pattern)
                 || normalized_pattern.contains(&normalized_field)
             {
-                return Ok(canonical.clone());
+                return (canonical.clone(), MatchRule::Fuzzy);
             }
         }
 
@@ -75,12 +241,19 @@ impl FieldClassifier {
         if !sample_values.is_empty() {
             let classification = self.classify_by_content(field_name, sample_values);
             if classification != field_name {
-                return Ok(classification);
+                return (classification, MatchRule::Content);
             }
         }
 
+        // Trained classifier, if one is loaded and confident enough
+        if let Some(model) = &self.column_model
+            && let Some(label) = model.classify(field_name, sample_values)
+        {
+            return (label, MatchRule::Ml);
+        }
+
         // If all else fails, return the original field name
-        Ok(field_name.to_string())
+        (field_name.to_string(), MatchRule::Unmatched)
     }
 
     fn normalize_field_name(&self, name: &str) -> String {
@@ -190,45 +363,131 @@ impl FieldClassifier {
 
     #[allow(dead_code)]
     pub fn add_field_mapping(&mut self, from: String, to: String) {
+        if !self.mapping_order.contains(&from) {
+            self.mapping_order.push(from.clone());
+        }
         self.field_mappings.insert(from, to);
     }
 
-    pub fn map_to_canonical_schema(&self, df: &mut polars::prelude::DataFrame) -> Result<()> {
-        use polars::prelude::*;
+    /// Consult a trained `ColumnModel` for columns the name and content
+    /// heuristics cannot place
+    pub fn with_column_model(mut self, model: ColumnModel) -> Self {
+        self.column_model = Some(model);
+        self
+    }
 
-        let column_names: Vec<String> = df
-            .get_column_names()
+    /// Give the listed source columns precedence, in order, over every other
+    /// column when several map to the same canonical name
+    pub fn with_merge_precedence(mut self, columns: Vec<String>) -> Self {
+        self.mapping_order.retain(|column| !columns.contains(column));
+        self.mapping_order.splice(0..0, columns);
+        self
+    }
+
+    /// Position of a source column in the merge precedence; columns without a
+    /// mapping come last
+    fn merge_rank(&self, column: &str) -> usize {
+        let normalized = self.normalize_field_name(column);
+        self.mapping_order
             .iter()
-            .map(|s| s.to_string())
-            .collect();
+            .position(|known| known == column)
+            .or_else(|| {
+                self.mapping_order
+                    .iter()
+                    .position(|known| self.normalize_field_name(known) == normalized)
+            })
+            .unwrap_or(usize::MAX)
+    }
+
+    /// Rename every column to its canonical name. Columns that land on the
+    /// same name are merged row by row, the first non-null value in
+    /// precedence order winning, and the rest are dropped.
+    pub fn map_to_canonical_schema(
+        &self,
+        df: &mut polars::prelude::DataFrame,
+    ) -> Result<ClassificationReport> {
+        use polars::prelude::*;
 
-        for col_name in column_names {
-            if let Ok(series) = df.column(&col_name) {
+        let mut report = ClassificationReport::default();
+        // Canonical name -> source columns, in order of first appearance
+        let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+        for series in df.get_columns() {
+            let col_name = series.name().to_string();
+            // The source JSON is kept verbatim for debugging, never classified
+            let canonical_name = if col_name == RAW_JSON_FIELD {
+                col_name.clone()
+            } else {
                 let sample_values: Vec<String> = match series.dtype() {
                     DataType::String => series
                         .str()
                         .unwrap()
-                        .into_no_null_iter()
+                        .into_iter()
+                        .flatten()
                         .take(5)
                         .map(|s| s.to_string())
                         .collect(),
                     _ => {
                         // Convert other types to string for analysis
                         (0..std::cmp::min(5, series.len()))
-                            .map(|i| format!("{:?}", series.get(i).unwrap()))
+                            .map(|i| series.get(i).unwrap().to_string())
                             .collect()
                     }
                 };
+                let (canonical_name, rule) = self.explain_field(&col_name, &sample_values);
+                report.columns.push(ColumnClassification {
+                    original_name: col_name.clone(),
+                    canonical_name: canonical_name.clone(),
+                    rule,
+                    sample_values,
+                });
+                canonical_name
+            };
+
+            match groups.iter_mut().find(|(target, _)| *target == canonical_name) {
+                Some((_, sources)) => sources.push(col_name),
+                None => groups.push((canonical_name, vec![col_name])),
+            }
+        }
 
-                if let Ok(canonical_name) = self.classify_field(&col_name, &sample_values) {
-                    if canonical_name != col_name {
-                        let _ = df.rename(&col_name, canonical_name.into());
-                    }
+        let mut columns = Vec::with_capacity(groups.len());
+        for (target, mut sources) in groups {
+            sources.sort_by_key(|column| self.merge_rank(column));
+
+            let mut merged = df.column(&sources[0])?.clone();
+            if sources.len() > 1 {
+                info!("Merging columns {:?} into '{}'", sources, target);
+                report.actions.push(ColumnAction::Merged {
+                    into: target.clone(),
+                    sources: sources.clone(),
+                });
+
+                let mut values = merged.as_materialized_series().clone();
+                for loser in &sources[1..] {
+                    let other = df
+                        .column(loser)?
+                        .as_materialized_series()
+                        .cast(values.dtype())?;
+                    let mask = values.is_not_null();
+                    values = values.zip_with(&mask, &other)?;
+                    report.actions.push(ColumnAction::Dropped {
+                        column: loser.clone(),
+                        merged_into: target.clone(),
+                    });
                 }
+                merged = values.into_column();
+            } else if sources[0] != target {
+                report.actions.push(ColumnAction::Renamed {
+                    from: sources[0].clone(),
+                    to: target.clone(),
+                });
             }
+
+            merged.rename(target.into());
+            columns.push(merged);
         }
 
-        Ok(())
+        *df = DataFrame::new(columns)?;
+        Ok(report)
     }
 
     #[allow(dead_code)]
@@ -239,7 +498,6 @@ impl FieldClassifier {
         canonical_fields
     }
 
-    #[allow(dead_code)]
     pub fn is_canonical_field(&self, field_name: &str) -> bool {
         self.field_mappings.values().any(|v| v == field_name)
     }
@@ -292,6 +550,217 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_optional_attribute_columns_keep_their_names() {
+        let classifier = FieldClassifier::new();
+
+        for (field, sample) in [
+            ("brand", "Dalda"),
+            ("description", "Pouch of 1.5 litres"),
+            ("image_url", "https://cdn.example.com/a.jpg"),
+            ("stock_quantity", "40"),
+            ("parent_product_id", "7001"),
+            ("name_original", "Kfresh Garma Melon - (800gm)"),
+        ] {
+            assert_eq!(
+                classifier.classify_field(field, &[sample.to_string()]).unwrap(),
+                field
+            );
+        }
+    }
+
+    #[test]
+    fn test_provenance_columns_are_untouched() {
+        use polars::prelude::*;
+
+        let classifier = FieldClassifier::new();
+        let mut df = df!(
+            "name" => ["milk"],
+            "source" => ["krave_mart"],
+            "source_category_key" => ["fruits_veg"],
+            "fetched_at" => ["2025-09-15T10:15:00Z"],
+            "_raw" => [r#"{"title":"milk","price":100}"#]
+        )
+        .unwrap();
+        let raw = df.column("_raw").unwrap().clone();
+        classifier.map_to_canonical_schema(&mut df).unwrap();
+
+        let names: Vec<&str> = df.get_column_names().iter().map(|s| s.as_str()).collect();
+        assert_eq!(names, vec!["name", "source", "source_category_key", "fetched_at", "_raw"]);
+        assert!(df.column("_raw").unwrap().equals_missing(&raw));
+    }
+
+    #[test]
+    fn test_canonical_columns_are_never_renamed() {
+        use super::super::json_flattener::JsonFlattener;
+
+        let classifier = FieldClassifier::new();
+        // Samples the content heuristics would otherwise send elsewhere
+        for (field, sample) in [
+            ("units_of_mass", "1 kg"),
+            ("units_of_mass", "500"),
+            ("sku", "KM-0012"),
+            ("product_id", "A1023"),
+            ("image_url", "https://images.kravemart.com/p/101.png"),
+            ("in_stock", "true"),
+            ("in_stock", "In Stock"),
+        ] {
+            assert_eq!(
+                classifier.explain_field(field, &[sample.to_string()]),
+                (field.to_string(), MatchRule::Exact)
+            );
+        }
+
+        let products = vec![serde_json::json!({
+            "product_id": "A1023",
+            "name": "Olpers Milk 1 Litre",
+            "sku": "KM-0012",
+            "cost_price": "Rs. 250",
+            "mrp": "Rs. 300",
+            "sku_percent_off": "16%",
+            "units_of_mass": "1 kg",
+            "brand": "Olpers",
+            "description": "Full cream milk",
+            "image_url": "https://images.kravemart.com/p/101.png",
+            "stock_quantity": 12,
+            "categories": [{"category_name": "Dairy"}],
+            "_source": "krave_mart",
+            "_source_category_key": "dairy",
+            "_fetched_at": "2025-09-15T10:15:00Z"
+        })];
+        let mut df = JsonFlattener::new().flatten_to_dataframe(&products).unwrap().dataframe;
+        assert!(df.column("units_of_mass").is_ok());
+
+        // Only the flattener names with a different canonical name move
+        let report = classifier.map_to_canonical_schema(&mut df).unwrap();
+        let mut renames: Vec<&ColumnAction> = report.actions.iter().collect();
+        renames.sort_by_key(|action| format!("{:?}", action));
+        assert_eq!(
+            renames,
+            vec![
+                &ColumnAction::Renamed { from: "category_name".into(), to: "category".into() },
+                &ColumnAction::Renamed { from: "sku_percent_off".into(), to: "discount".into() },
+            ]
+        );
+
+        // The resulting canonical column set maps onto itself
+        let columns = df.get_column_names_owned();
+        let report = classifier.map_to_canonical_schema(&mut df).unwrap();
+        assert!(report.actions.is_empty(), "unexpected actions: {:?}", report.actions);
+        assert!(report.columns.iter().all(|column| column.rule == MatchRule::Exact));
+        assert_eq!(df.get_column_names_owned(), columns);
+    }
+
+    #[test]
+    fn test_colliding_columns_are_merged() {
+        use polars::prelude::*;
+
+        let frame = || {
+            df!(
+                "title" => ["milk", "eggs", "bread"],
+                "price" => [Some(100.0), None, None],
+                "special_price" => [Some(90.0), Some(80.0), None]
+            )
+            .unwrap()
+        };
+
+        let mut df = frame();
+        let report = FieldClassifier::new().map_to_canonical_schema(&mut df).unwrap();
+
+        let names: Vec<&str> = df.get_column_names().iter().map(|s| s.as_str()).collect();
+        assert_eq!(names, vec!["name", "cost_price"]);
+        let prices: Vec<Option<f64>> = df.column("cost_price").unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!(prices, vec![Some(100.0), Some(80.0), None]);
+        assert_eq!(
+            report.actions,
+            vec![
+                ColumnAction::Renamed { from: "title".to_string(), to: "name".to_string() },
+                ColumnAction::Merged {
+                    into: "cost_price".to_string(),
+                    sources: vec!["price".to_string(), "special_price".to_string()],
+                },
+                ColumnAction::Dropped {
+                    column: "special_price".to_string(),
+                    merged_into: "cost_price".to_string(),
+                },
+            ]
+        );
+
+        // Precedence can be overridden per source
+        let mut df = frame();
+        FieldClassifier::new()
+            .with_merge_precedence(vec!["special_price".to_string()])
+            .map_to_canonical_schema(&mut df)
+            .unwrap();
+        let prices: Vec<Option<f64>> = df.column("cost_price").unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!(prices, vec![Some(90.0), Some(80.0), None]);
+    }
+
+    #[test]
+    fn test_column_model_fallback() {
+        use super::super::column_model::parse_labeled_csv;
+
+        let csv = std::fs::read_to_string("src/configs/column_labels.csv").unwrap();
+        let model = ColumnModel::train(&parse_labeled_csv(&csv).unwrap()).unwrap();
+        let urls = vec![
+            "https://cdn.example.com/milk.jpg".to_string(),
+            "https://cdn.example.com/eggs.jpg".to_string(),
+        ];
+
+        // The heuristics leave this column alone
+        assert_eq!(FieldClassifier::new().classify_field("img", &urls).unwrap(), "img");
+
+        let classifier = FieldClassifier::new().with_column_model(model);
+        assert_eq!(classifier.classify_field("img", &urls).unwrap(), "image_url");
+        // Heuristic matches still take precedence
+        assert_eq!(classifier.classify_field("special_price", &[]).unwrap(), "cost_price");
+    }
+
+    #[test]
+    fn test_classification_report_match_rules() {
+        use super::super::column_model::parse_labeled_csv;
+        use polars::prelude::*;
+
+        let csv = std::fs::read_to_string("src/configs/column_labels.csv").unwrap();
+        let model = ColumnModel::train(&parse_labeled_csv(&csv).unwrap()).unwrap();
+        let classifier = FieldClassifier::new().with_column_model(model);
+
+        let mut df = df!(
+            "special_price" => [234.0, 120.5],
+            "product_name_en" => ["Olpers Milk", "Tapal Danedar"],
+            "mystery_column" => ["50%", "20%"],
+            "img" => ["https://cdn.example.com/milk.jpg", "https://cdn.example.com/eggs.jpg"],
+            "_raw" => ["{}", "{}"]
+        )
+        .unwrap();
+        let report = classifier.map_to_canonical_schema(&mut df).unwrap();
+
+        let rule = |column: &str| {
+            let entry = report.column(column).unwrap();
+            (entry.canonical_name.as_str(), entry.rule)
+        };
+        assert_eq!(rule("special_price"), ("cost_price", MatchRule::Exact));
+        assert_eq!(rule("product_name_en"), ("name", MatchRule::Fuzzy));
+        assert_eq!(rule("mystery_column"), ("discount", MatchRule::Content));
+        assert_eq!(rule("img"), ("image_url", MatchRule::Ml));
+        // The raw JSON is never classified
+        assert!(report.column("_raw").is_none());
+        assert_eq!(report.column("special_price").unwrap().sample_values, vec!["234.0", "120.5"]);
+
+        let unmatched = FieldClassifier::new().explain_field("img", &["https://cdn.example.com/milk.jpg".to_string()]);
+        assert_eq!(unmatched, ("img".to_string(), MatchRule::Unmatched));
+
+        let printed = report.to_string();
+        assert!(printed.contains("mystery_column"));
+        assert!(printed.lines().any(|line| line.contains("img") && line.contains("image_url") && line.contains("ml")));
+
+        let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(json["columns"][0]["rule"], "exact");
+        assert_eq!(json["actions"][0]["action"], "renamed");
+        let parsed: ClassificationReport = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, report);
+    }
+
     #[test]
     fn test_normalization() {
         let classifier = FieldClassifier::new();
@@ -3,11 +3,18 @@ use serde_json::Value;
 use std::collections::HashMap;
 use tracing::{info, warn};
 
-use crate::fetcher::html_fetcher::ScrapedProduct;
+use crate::fetcher::ScrapedProduct;
+use crate::processor::RecordContext;
+use crate::processor::json_flattener::{CURRENCY_FIELD, NumberFormat, detect_currency};
+use crate::processor::rule_normalizer::{DEFAULT_KNOWN_BRANDS, brand_from_name};
 
 /// HTML-specific processor that converts scraped products to JSON format
 /// for unified processing through the existing pipeline
 pub struct HtmlProcessor {
+    /// Provenance stamped on every product, with the scraped category as key
+    context: Option<RecordContext>,
+    /// Separators of scraped price text
+    number_format: NumberFormat,
     // Future: ML model for enhanced extraction
     // ml_model: Option<ProductMLModel>,
 }
@@ -15,10 +22,24 @@ pub struct HtmlProcessor {
 impl HtmlProcessor {
     pub fn new() -> Self {
         Self {
+            context: None,
+            number_format: NumberFormat::default(),
             // ml_model: None,
         }
     }
 
+    /// Stamp the source (and each product's category key) on converted products
+    pub fn with_record_context(mut self, context: RecordContext) -> Self {
+        self.context = Some(context);
+        self
+    }
+
+    /// Read prices like "1.234,50" for `NumberFormat::Eu`
+    pub fn with_number_format(mut self, number_format: NumberFormat) -> Self {
+        self.number_format = number_format;
+        self
+    }
+
     /// Convert scraped products to JSON format compatible with JsonFlattener
     pub fn process_scraped_products(&self, products: Vec<ScrapedProduct>) -> Result<Vec<Value>> {
         let mut processed_products = Vec::new();
@@ -67,7 +88,7 @@ impl HtmlProcessor {
         let cleaned_price = self.clean_price(&product.price)?;
 
         // Create JSON object compatible with existing JsonFlattener
-        let json_product = serde_json::json!({
+        let mut json_product = serde_json::json!({
             "name": product.name.trim(),
             "price": cleaned_price,
             "product_id": product.product_id.trim(),
@@ -80,23 +101,28 @@ impl HtmlProcessor {
             "sku": product.product_id.trim(),
             "category_name": product.category.trim(),
             "units_of_mass": "N/A", // Will be extracted by rule normalizer if present in name
-            "sku_percent_off": "0.00" // Default, can be calculated later if MRP differs
+            "sku_percent_off": "0.00", // Default, can be calculated later if MRP differs
+            "_source_category": product.category.trim()
         });
 
+        // Sources without a currency in their price text fall back to the configured one
+        if let Some(currency) = detect_currency(&product.price) {
+            json_product[CURRENCY_FIELD] = Value::from(currency);
+        }
+
+        if let Some(ref context) = self.context {
+            context
+                .for_category(&product.category)
+                .stamp(std::slice::from_mut(&mut json_product));
+        }
+
         Ok(json_product)
     }
 
     /// Clean and normalize price text
     fn clean_price(&self, price_text: &str) -> Result<String> {
-        // Remove common price prefixes and suffixes
-        let cleaned = price_text
-            .replace("Rs.", "")
-            .replace("Rs", "")
-            .replace("PKR", "")
-            .replace("₨", "")
-            .replace(",", "")
-            .trim()
-            .to_string();
+        // Remove currency prefixes / suffixes and thousands separators
+        let cleaned = self.number_format.strip(price_text).trim().to_string();
 
         // Extract numeric value
         let numeric_part: String = cleaned
@@ -198,7 +224,7 @@ impl HtmlProcessor {
         }
 
         // Extract brand if present
-        if let Some(brand) = self.extract_brand_from_name(&product.name) {
+        if let Some(brand) = brand_from_name(&product.name, &DEFAULT_KNOWN_BRANDS) {
             metadata.insert("brand".to_string(), brand);
         }
 
@@ -243,41 +269,6 @@ impl HtmlProcessor {
 
         None
     }
-
-    /// Extract brand from product name (first word or known brands)
-    fn extract_brand_from_name(&self, name: &str) -> Option<String> {
-        let words: Vec<&str> = name.split_whitespace().collect();
-        
-        if words.is_empty() {
-            return None;
-        }
-
-        // Known brands (can be expanded)
-        let known_brands = [
-            "brightfarms",
-            "nestle",
-            "unilever",
-            "p&g",
-            "colgate",
-            "johnson",
-            "loreal",
-        ];
-
-        let name_lower = name.to_lowercase();
-        for brand in &known_brands {
-            if name_lower.contains(brand) {
-                return Some(brand.to_string());
-            }
-        }
-
-        // Fallback: use first word if it looks like a brand (capitalized)
-        let first_word = words[0];
-        if first_word.chars().next().unwrap_or('a').is_uppercase() && first_word.len() > 2 {
-            return Some(first_word.to_string());
-        }
-
-        None
-    }
 }
 
 impl Default for HtmlProcessor {
@@ -298,6 +289,12 @@ mod tests {
         assert_eq!(processor.clean_price("PKR 1,500").unwrap(), "1500");
         assert_eq!(processor.clean_price("₨ 99.50").unwrap(), "99.5");
         assert_eq!(processor.clean_price("2100").unwrap(), "2100");
+        assert_eq!(processor.clean_price("AED 12.50").unwrap(), "12.5");
+        assert_eq!(processor.clean_price("1,250 د.إ").unwrap(), "1250");
+
+        let eu = HtmlProcessor::new().with_number_format(NumberFormat::Eu);
+        assert_eq!(eu.clean_price("€ 1.234,50").unwrap(), "1234.5");
+        assert_eq!(eu.clean_price("3,20 €").unwrap(), "3.2");
 
         assert!(processor.clean_price("invalid").is_err());
         assert!(processor.clean_price("Rs. 0").is_err());
@@ -361,5 +358,13 @@ mod tests {
         assert_eq!(json["product_id"], "12345");
         assert_eq!(json["category"], "Fruits");
         assert_eq!(json["source_type"], "html");
+        assert_eq!(json["currency"], "PKR");
+
+        let unpriced_currency = ScrapedProduct {
+            price: "150".to_string(),
+            ..product
+        };
+        let json = processor.convert_to_json(&unpriced_currency).unwrap();
+        assert!(json.get("currency").is_none());
     }
 }
@@ -1,65 +1,717 @@
 use anyhow::{Result, anyhow};
 use polars::prelude::*;
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
+use std::io::Write;
+use std::sync::Arc;
 use tracing::{info, warn};
 
-pub struct JsonFlattener;
+use super::quality_flags::{PRICE_UNPARSEABLE_FLAG, QUALITY_FLAGS_FIELD};
 
-impl JsonFlattener {
-    pub fn new() -> Self {
-        JsonFlattener
+/// Canonical columns produced by `JsonFlattener`, in output order. Also the
+/// names `FieldClassifier` keeps as-is when mapping to the final schema.
+pub const CANONICAL_FIELDS: [&str; 12] = [
+    "cost_price",
+    "mrp",
+    "name",
+    "sku",
+    "product_id",
+    "sku_percent_off",
+    "category_name",
+    "units_of_mass",
+    "brand",
+    "description",
+    "image_url",
+    "stock_quantity",
+];
+
+/// Derived columns emitted after the canonical ones (`parent_product_id`
+/// only for sources with variants)
+pub const DERIVED_FIELDS: [&str; 2] = [PRODUCT_ID_NUMERIC_FIELD, PARENT_PRODUCT_ID_FIELD];
+
+/// `product_id` as an `Int64`, null when the ID is not a plain integer
+pub const PRODUCT_ID_NUMERIC_FIELD: &str = "product_id_numeric";
+
+/// Canonical columns emitted as `Float64`, parsed from the extracted text
+const FLOAT_FIELDS: [&str; 3] = ["cost_price", "mrp", "sku_percent_off"];
+
+/// Store / warehouse ID, passed through as an extra column for multi-store sources
+pub const STORE_ID_FIELD: &str = "store_id";
+
+/// Category a product was fetched under, stamped by the fetchers and used as
+/// `category_name` when the product JSON carries no category of its own
+pub const SOURCE_CATEGORY_FIELD: &str = "_source_category";
+
+/// Provenance fields stamped on raw products by `RecordContext`, and the
+/// columns they become: which source, category and fetch run a row came from
+pub const PROVENANCE_FIELDS: [(&str, &str); 3] = [
+    ("_source", "source"),
+    ("_source_category_key", "source_category_key"),
+    ("_fetched_at", "fetched_at"),
+];
+
+/// Compact JSON of the source record, only emitted when raw JSON is kept
+/// (see `JsonFlattener::with_raw_json`) and dropped from Parquet by default
+pub const RAW_JSON_FIELD: &str = "_raw";
+
+/// Parent product of a row exploded from a variants array, null for plain products
+pub const PARENT_PRODUCT_ID_FIELD: &str = "parent_product_id";
+
+/// ISO 4217 code of a row's prices, e.g. "PKR"
+pub const CURRENCY_FIELD: &str = "currency";
+
+/// Whether a product can be ordered, a `Boolean` column read from the
+/// source's `in_stock_fields`; null when none of them is present
+pub const IN_STOCK_FIELD: &str = "in_stock";
+
+/// Supported currencies by ISO code, with the tokens prices are written
+/// with. Longer tokens come first so "Rs." is stripped before "Rs".
+pub const CURRENCIES: [(&str, &[&str]); 4] = [
+    ("PKR", &["PKR", "Rs.", "Rs", "RS", "rs", "₨"]),
+    ("AED", &["AED", "د.إ", "Dhs", "DHS", "Dh", "DH"]),
+    ("EUR", &["EUR", "€"]),
+    ("USD", &["USD", "US$", "$"]),
+];
+
+/// Which separators a source writes numbers with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NumberFormat {
+    /// "1,234.50": `,` separates thousands, `.` decimals
+    #[default]
+    Us,
+    /// "1.234,50": `.` separates thousands, `,` decimals
+    Eu,
+}
+
+impl NumberFormat {
+    /// A number string without currency tokens, written with `.` decimals
+    /// and no thousands separators, e.g. "€ 1.234,50" -> " 1234.50" for `Eu`
+    pub fn strip(self, price: &str) -> String {
+        let stripped = strip_currency_tokens(price);
+        match self {
+            NumberFormat::Us => stripped.replace(',', ""),
+            NumberFormat::Eu => stripped.replace('.', "").replace(',', "."),
+        }
     }
 
-    pub fn flatten_to_dataframe(&self, json_data: &[Value]) -> Result<DataFrame> {
-        let mut records = Vec::new();
-        let mut successful_count = 0;
-        let mut failed_count = 0;
+    /// Parse a price or quantity string, `None` if it is not a number
+    pub fn parse(self, price: &str) -> Option<f64> {
+        self.strip(price).trim().parse().ok()
+    }
+}
 
-        for (index, item) in json_data.iter().enumerate() {
-            match self.extract_fields_directly(item) {
-                Ok(record) => {
-                    records.push(record);
-                    successful_count += 1;
-                }
-                Err(e) => {
-                    failed_count += 1;
-                    warn!(
-                        "Failed to extract fields from product at index {}: {}",
-                        index, e
-                    );
-
-                    // Log some details about the failed item
-                    if let Some(product_name) = item.get("name").and_then(|v| v.as_str()) {
-                        warn!("Failed product name: {}", product_name);
+/// Fields a variant overrides on its parent; everything else is inherited
+const VARIANT_FIELDS: [&str; 6] = [
+    "sku",
+    "cost_price",
+    "mrp",
+    "sku_percent_off",
+    "units_of_mass",
+    "stock_quantity",
+];
+
+/// Fields whose values are parsed and re-formatted as numbers
+const NUMERIC_FIELDS: [&str; 3] = ["cost_price", "mrp", "stock_quantity"];
+
+/// Field read by its first number, e.g. "12,5% off" -> "12.5"
+const DISCOUNT_FIELD: &str = "sku_percent_off";
+
+/// Fields flagged `PRICE_UNPARSEABLE` when they hold text that is not a
+/// number, or a negative one
+const PRICE_FIELDS: [&str; 2] = ["cost_price", "mrp"];
+
+/// Keys `cost_price` is read from without extraction rules, in order;
+/// `price` is Pandamart's
+const COST_PRICE_KEYS: [&str; 5] = ["cost_price", "special_price", "discountedPrice", "discounted_price", "price"];
+
+/// Keys `mrp` is read from without extraction rules, in order;
+/// `originalPrice` / `original_price` are Pandamart's
+const MRP_KEYS: [&str; 6] = ["mrp", "product_price", "actualPrice", "actual_price", "originalPrice", "original_price"];
+
+/// How the string values of a numeric field are read
+#[derive(Debug, Clone, Copy)]
+struct Numeric<'a> {
+    number_format: NumberFormat,
+    /// Stripped besides the `CURRENCIES` tokens, see `JsonFlattener::with_currency_markers`
+    currency_markers: &'a [String],
+    /// Read the first number in the string, e.g. "40% off", not the whole string
+    leading: bool,
+}
+
+impl Numeric<'_> {
+    fn parse(self, text: &str) -> Option<f64> {
+        let mut cleaned = text.to_string();
+        for marker in self.currency_markers {
+            cleaned = cleaned.replace(marker.as_str(), "");
+        }
+        if self.leading {
+            parse_float(&cleaned, self.number_format)
+        } else {
+            self.number_format.parse(&cleaned)
+        }
+    }
+}
+
+/// Counts from `JsonFlattener::flatten_batched_to_parquet`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BatchedParquetSummary {
+    pub rows: usize,
+    pub successful: usize,
+    pub failed: usize,
+    pub batches: usize,
+    /// Products that could not be extracted, indexed across all batches
+    pub failures: Vec<ExtractionFailure>,
+    /// The same products in full, see `JsonFlattener::with_rejected_records`
+    pub rejected: Vec<RejectedRecord>,
+}
+
+/// Longest raw JSON excerpt kept per failure in the error report
+const RAW_SNIPPET_CHARS: usize = 500;
+
+/// A product `JsonFlattener` could not turn into a record
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExtractionFailure {
+    /// Position of the product in the input
+    pub index: usize,
+    pub reason: String,
+    pub product_id: Option<String>,
+    pub name: Option<String>,
+    /// Start of the product's JSON, cut at `RAW_SNIPPET_CHARS`
+    pub raw_snippet: String,
+}
+
+impl ExtractionFailure {
+    fn new(index: usize, item: &Value, error: &anyhow::Error) -> Self {
+        let product_id = ["product_id", "productID", "id", "sku"]
+            .iter()
+            .find_map(|key| item.get(*key).and_then(identifier_string));
+        let name = item
+            .get("name")
+            .or_else(|| item.get("title"))
+            .and_then(|v| v.as_str())
+            .map(str::to_string);
+        let raw = item.to_string();
+        let raw_snippet = match raw.char_indices().nth(RAW_SNIPPET_CHARS) {
+            Some((cut, _)) => format!("{}...", &raw[..cut]),
+            None => raw,
+        };
+
+        ExtractionFailure {
+            index,
+            reason: error.to_string(),
+            product_id,
+            name,
+            raw_snippet,
+        }
+    }
+}
+
+/// A product `JsonFlattener` could not turn into a record, kept whole so
+/// extraction can be fixed against it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RejectedRecord {
+    /// Position of the product in the input, as in `ExtractionFailure`
+    pub index: usize,
+    pub reason: String,
+    pub record: Value,
+}
+
+/// Result of `JsonFlattener::flatten_to_dataframe`: the extracted rows plus
+/// every product that was skipped
+#[derive(Debug, Clone)]
+pub struct FlattenOutput {
+    pub dataframe: DataFrame,
+    pub failures: Vec<ExtractionFailure>,
+    /// Failed products in full, see `JsonFlattener::with_rejected_records`
+    pub rejected: Vec<RejectedRecord>,
+    /// Number of input products, extracted or not
+    pub total: usize,
+}
+
+
+/// Environment variable overriding the number of threads used for flattening
+pub const THREADS_ENV: &str = "PIPELINE_THREADS";
+
+/// Check run on each raw product before extraction, e.g. a JSON Schema
+pub type RecordValidator = Arc<dyn Fn(&Value) -> Result<()> + Send + Sync>;
+
+pub struct JsonFlattener {
+    rules: Option<FieldExtractionRules>,
+    /// Where a product's variants live; each variant becomes its own row
+    variants_path: Option<FieldPath>,
+    /// Products it rejects are reported as failures instead of flattened
+    validator: Option<RecordValidator>,
+    /// Emit each product's source JSON as a `_raw` column
+    keep_raw_json: bool,
+    /// Keep every failed product whole, see `with_rejected_records`
+    keep_rejected: bool,
+    /// Currency of products whose prices do not name one
+    currency: Option<String>,
+    /// Separators of price and stock strings
+    number_format: NumberFormat,
+    /// Extra currency markers stripped from price strings, longest first
+    currency_markers: Vec<String>,
+    /// Fields telling whether a product is in stock, see `with_in_stock_fields`
+    in_stock_paths: Vec<FieldPath>,
+    /// Dedicated pool when a thread count is configured, rayon's global pool otherwise
+    pool: Option<Arc<ThreadPool>>,
+}
+
+/// One step of a `FieldPath`
+#[derive(Debug, Clone, PartialEq)]
+enum PathSegment {
+    /// `key`
+    Key(String),
+    /// `[0]`
+    Index(usize),
+    /// `[*]`, every element of an array
+    All,
+    /// `[field=value]`, the first array element whose `field` equals `value`
+    Match { field: String, value: String },
+}
+
+/// A JSON path from a `[fields.extraction]` rule, e.g. `groupRanges[0].discountedPrice`,
+/// `attributes[key=sku].value` or `categories[*].category_name|lower`.
+/// Values matched by `[*]` are trimmed and joined with ", "; a `|lower`
+/// suffix lowercases the result.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldPath {
+    segments: Vec<PathSegment>,
+    lowercase: bool,
+}
+
+impl FieldPath {
+    pub fn parse(path: &str) -> Result<Self> {
+        let (path, lowercase) = match path.rsplit_once('|') {
+            Some((path, "lower")) => (path, true),
+            Some((_, modifier)) => return Err(anyhow!("Unknown path modifier '{}' in '{}'", modifier, path)),
+            None => (path, false),
+        };
+
+        let mut segments = Vec::new();
+        for part in path.split('.') {
+            let (key, mut rest) = match part.find('[') {
+                Some(pos) => part.split_at(pos),
+                None => (part, ""),
+            };
+            if !key.is_empty() {
+                segments.push(PathSegment::Key(key.to_string()));
+            }
+
+            while !rest.is_empty() {
+                let end = rest
+                    .find(']')
+                    .filter(|_| rest.starts_with('['))
+                    .ok_or_else(|| anyhow!("Malformed array lookup in path '{}'", path))?;
+                let inner = &rest[1..end];
+                segments.push(if inner == "*" {
+                    PathSegment::All
+                } else if let Ok(index) = inner.parse::<usize>() {
+                    PathSegment::Index(index)
+                } else if let Some((field, value)) = inner.split_once('=') {
+                    PathSegment::Match {
+                        field: field.trim().to_string(),
+                        value: value.trim().to_string(),
                     }
-                    if let Some(product_id) = item.get("product_id") {
-                        warn!("Failed product ID: {}", product_id);
+                } else {
+                    return Err(anyhow!("Unsupported array lookup '[{}]' in path '{}'", inner, path));
+                });
+                rest = &rest[end + 1..];
+            }
+        }
+
+        if segments.is_empty() {
+            return Err(anyhow!("Empty field path"));
+        }
+
+        Ok(FieldPath { segments, lowercase })
+    }
+
+    /// Extract the value at this path as a string, if present and non-empty
+    fn extract(&self, item: &Value, numeric: Option<Numeric<'_>>) -> Option<String> {
+        let current = self.resolve(item);
+
+        let extracted = if self.segments.contains(&PathSegment::All) {
+            let parts: Vec<String> = current
+                .into_iter()
+                .filter_map(|value| value_to_field_string(value, numeric))
+                .map(|part| part.trim().to_string())
+                .filter(|part| !part.is_empty())
+                .collect();
+            (!parts.is_empty()).then(|| parts.join(", "))
+        } else {
+            current
+                .into_iter()
+                .next()
+                .and_then(|value| value_to_field_string(value, numeric))
+        }?;
+
+        Some(if self.lowercase { extracted.to_lowercase() } else { extracted })
+    }
+
+    /// Objects at this path: the elements of the array it points to, or every
+    /// object matched when the path already ends in `[*]`
+    fn objects<'a>(&self, item: &'a Value) -> Vec<&'a Value> {
+        let values = self.resolve(item);
+        let values = match values.as_slice() {
+            [Value::Array(elements)] => elements.iter().collect(),
+            _ => values,
+        };
+        values.into_iter().filter(|value| value.is_object()).collect()
+    }
+
+    /// Every JSON value this path matches in `item`
+    fn resolve<'a>(&self, item: &'a Value) -> Vec<&'a Value> {
+        let mut current = vec![item];
+        for segment in &self.segments {
+            current = current
+                .into_iter()
+                .flat_map(|value| -> Vec<&Value> {
+                    match segment {
+                        PathSegment::Key(key) => value.get(key).into_iter().collect(),
+                        PathSegment::Index(index) => value.get(*index).into_iter().collect(),
+                        PathSegment::All => value.as_array().map(|arr| arr.iter().collect()).unwrap_or_default(),
+                        PathSegment::Match { field, value: expected } => value
+                            .as_array()
+                            .and_then(|arr| {
+                                arr.iter().find(|element| {
+                                    element.get(field).map(value_to_plain_string).as_deref()
+                                        == Some(expected.as_str())
+                                })
+                            })
+                            .into_iter()
+                            .collect(),
                     }
-                }
+                })
+                .collect();
+        }
+        current
+    }
+}
+
+/// Per-source extraction rules: an ordered list of paths for each canonical
+/// field, the first path yielding a value wins. Fields without rules keep
+/// the built-in extraction.
+#[derive(Debug, Clone, Default)]
+pub struct FieldExtractionRules {
+    paths: HashMap<String, Vec<FieldPath>>,
+    defaults: HashMap<String, String>,
+}
+
+impl FieldExtractionRules {
+    /// Build rules from a source's `[fields.extraction]` and `[fields.defaults]` tables
+    pub fn from_config(
+        extraction: &HashMap<String, Vec<String>>,
+        defaults: &HashMap<String, String>,
+    ) -> Result<Self> {
+        let mut paths = HashMap::new();
+        for (field, field_paths) in extraction {
+            if !CANONICAL_FIELDS.contains(&field.as_str()) {
+                return Err(anyhow!("Unknown canonical field '{}' in extraction rules", field));
+            }
+            let parsed = field_paths
+                .iter()
+                .map(|path| FieldPath::parse(path))
+                .collect::<Result<Vec<_>>>()
+                .map_err(|e| anyhow!("Invalid extraction rule for '{}': {}", field, e))?;
+            paths.insert(field.clone(), parsed);
+        }
+
+        for field in defaults.keys() {
+            if !CANONICAL_FIELDS.contains(&field.as_str()) {
+                return Err(anyhow!("Unknown canonical field '{}' in extraction defaults", field));
             }
         }
 
+        Ok(FieldExtractionRules {
+            paths,
+            defaults: defaults.clone(),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty() && self.defaults.is_empty()
+    }
+
+    fn has_rules_for(&self, field: &str) -> bool {
+        self.paths.contains_key(field) || self.defaults.contains_key(field)
+    }
+
+    fn extract(&self, field: &str, item: &Value, numeric: Option<Numeric<'_>>) -> Option<String> {
+        self.paths
+            .get(field)
+            .and_then(|paths| paths.iter().find_map(|path| path.extract(item, numeric)))
+            .or_else(|| self.defaults.get(field).cloned())
+    }
+}
+
+/// Format a number the way the flattener stores it: whole numbers without a decimal part
+fn format_number(f: f64) -> String {
+    if f.fract() == 0.0 {
+        (f as i64).to_string()
+    } else {
+        f.to_string()
+    }
+}
+
+fn value_to_plain_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Stable string form of a product identifier: trimmed strings, integers
+/// without a trailing `.0`. `None` for empty or non-scalar values.
+fn identifier_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.trim()).filter(|s| !s.is_empty()).map(|s| s.to_string()),
+        Value::Number(n) => match (n.as_i64(), n.as_u64(), n.as_f64()) {
+            (Some(i), _, _) => Some(i.to_string()),
+            (_, Some(u), _) => Some(u.to_string()),
+            (_, _, Some(f)) if f.fract() == 0.0 && f.abs() < i64::MAX as f64 => Some((f as i64).to_string()),
+            _ => Some(n.to_string()),
+        },
+        _ => None,
+    }
+}
+
+/// A field value as stored in a record. Strings of numeric fields are parsed
+/// in the source's number format.
+fn value_to_field_string(value: &Value, numeric: Option<Numeric<'_>>) -> Option<String> {
+    match value {
+        Value::Number(n) => Some(n.as_f64().map(format_number).unwrap_or_else(|| n.to_string())),
+        Value::String(s) if numeric.is_some() => numeric?.parse(s).map(format_number),
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        _ => None,
+    }
+}
+
+impl JsonFlattener {
+    pub fn new() -> Self {
+        let flattener = JsonFlattener {
+            rules: None,
+            variants_path: None,
+            validator: None,
+            keep_raw_json: false,
+            keep_rejected: false,
+            currency: None,
+            number_format: NumberFormat::default(),
+            currency_markers: Vec::new(),
+            in_stock_paths: Vec::new(),
+            pool: None,
+        };
+
+        match std::env::var(THREADS_ENV).ok().map(|v| v.trim().parse::<usize>()) {
+            Some(Ok(threads)) if threads > 0 => flattener.with_threads(threads),
+            Some(_) => {
+                warn!("Ignoring invalid {} value, using all cores", THREADS_ENV);
+                flattener
+            }
+            None => flattener,
+        }
+    }
+
+    /// Flatten on a dedicated pool of `threads` threads instead of all cores
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        match ThreadPoolBuilder::new().num_threads(threads).build() {
+            Ok(pool) => self.pool = Some(Arc::new(pool)),
+            Err(e) => warn!("Failed to build a {}-thread pool, using all cores: {}", threads, e),
+        }
+        self
+    }
+
+    /// Use source-specific extraction rules instead of the built-in fallbacks
+    pub fn with_rules(mut self, rules: FieldExtractionRules) -> Self {
+        self.rules = (!rules.is_empty()).then_some(rules);
+        self
+    }
+
+    /// Explode the array at `path` (e.g. `variants`) into one row per variant.
+    /// Variant rows inherit the parent's fields except `VARIANT_FIELDS` and
+    /// record the parent's ID in `parent_product_id`.
+    pub fn with_variants_path(mut self, path: FieldPath) -> Self {
+        self.variants_path = Some(path);
+        self
+    }
+
+    /// Reject products failing `validate` before extraction; their error
+    /// becomes the failure reason in `FlattenOutput::failures`
+    pub fn with_record_validator<F>(mut self, validate: F) -> Self
+    where
+        F: Fn(&Value) -> Result<()> + Send + Sync + 'static,
+    {
+        self.validator = Some(Arc::new(validate));
+        self
+    }
+
+    /// Add a `_raw` column with the compact JSON of each row's source product
+    /// (variant rows carry their parent's), for tracing a row back to its input
+    pub fn with_raw_json(mut self, keep_raw_json: bool) -> Self {
+        self.keep_raw_json = keep_raw_json;
+        self
+    }
+
+    /// Also return each product that fails extraction in full, with its
+    /// reason, as `rejected` next to the cut-down `failures`
+    pub fn with_rejected_records(mut self, keep_rejected: bool) -> Self {
+        self.keep_rejected = keep_rejected;
+        self
+    }
+
+    /// Fill `in_stock` from these fields, e.g. `is_enabled` and
+    /// `availableStock`. A product is in stock when every one of them it has
+    /// reads as available: `true`, a positive number, or a status such as
+    /// "in_stock"; a zero, `false` or "out_of_stock" marks it out of stock.
+    pub fn with_in_stock_fields(mut self, paths: Vec<FieldPath>) -> Self {
+        self.in_stock_paths = paths;
+        self
+    }
+
+    /// Currency recorded for products whose JSON has no `currency` field and
+    /// whose price strings carry no currency token. Accepts a code or token,
+    /// e.g. "PKR" or "Rs".
+    pub fn with_currency(mut self, currency: &str) -> Result<Self> {
+        let code = currency_code(currency)
+            .ok_or_else(|| anyhow!("Unsupported currency '{}'", currency))?;
+        self.currency = Some(code.to_string());
+        Ok(self)
+    }
+
+    /// Parse price and stock strings written with these separators
+    pub fn with_number_format(mut self, number_format: NumberFormat) -> Self {
+        self.number_format = number_format;
+        self
+    }
+
+    pub fn number_format(&self) -> NumberFormat {
+        self.number_format
+    }
+
+    /// Also strip these markers from price strings, e.g. "CHF" or "/-",
+    /// on top of the tokens in `CURRENCIES`
+    pub fn with_currency_markers(mut self, markers: Vec<String>) -> Self {
+        let mut markers: Vec<String> = markers
+            .iter()
+            .map(|marker| marker.trim().to_string())
+            .filter(|marker| !marker.is_empty())
+            .collect();
+        markers.sort_by_key(|marker| std::cmp::Reverse(marker.chars().count()));
+        self.currency_markers = markers;
+        self
+    }
+
+    /// How numeric strings are read, by their first number when `leading`
+    fn numeric(&self, leading: bool) -> Numeric<'_> {
+        Numeric {
+            number_format: self.number_format,
+            currency_markers: &self.currency_markers,
+            leading,
+        }
+    }
+
+    pub fn flatten_to_dataframe(&self, json_data: &[Value]) -> Result<FlattenOutput> {
+        let (records, failures, rejected) = self.extract_records(json_data, None, 0);
+
         info!(
             "Field extraction summary: {} successful, {} failed out of {} total",
-            successful_count,
-            failed_count,
+            json_data.len() - failures.len(),
+            failures.len(),
             json_data.len()
         );
 
-        self.records_to_dataframe(records)
+        Ok(FlattenOutput {
+            dataframe: self.records_to_dataframe(records)?,
+            failures,
+            rejected,
+            total: json_data.len(),
+        })
     }
 
-    /// Process JSON data in batches and return a combined DataFrame
-    /// This is more memory efficient for large datasets
+    /// Extract every item in parallel, keeping input order. Returns the
+    /// records (several per product with variants) along with the failures,
+    /// whose indices start at `offset`, and the rejected products if kept.
+    fn extract_records(
+        &self,
+        items: &[Value],
+        batch: Option<usize>,
+        offset: usize,
+    ) -> (Vec<HashMap<String, String>>, Vec<ExtractionFailure>, Vec<RejectedRecord>) {
+        type ProductRecords = std::result::Result<Vec<HashMap<String, String>>, Box<(ExtractionFailure, Option<RejectedRecord>)>>;
+        let extract = || -> Vec<ProductRecords> {
+            items
+                .par_iter()
+                .enumerate()
+                .map(|(index, item)| match self.extract_product_records(item) {
+                    Ok(records) => Ok(records),
+                    Err(e) => {
+                        match batch {
+                            Some(batch) => warn!(
+                                "Failed to extract fields from product at batch {} index {}: {}",
+                                batch, index, e
+                            ),
+                            None => warn!(
+                                "Failed to extract fields from product at index {}: {}",
+                                index, e
+                            ),
+                        }
+
+                        // Log some details about the failed item
+                        if let Some(product_name) = item.get("name").and_then(|v| v.as_str()) {
+                            warn!("Failed product name: {}", product_name);
+                        }
+                        if let Some(product_id) = item.get("product_id") {
+                            warn!("Failed product ID: {}", product_id);
+                        }
+                        let rejected = self.keep_rejected.then(|| RejectedRecord {
+                            index: offset + index,
+                            reason: e.to_string(),
+                            record: item.clone(),
+                        });
+                        Err(Box::new((ExtractionFailure::new(offset + index, item, &e), rejected)))
+                    }
+                })
+                .collect()
+        };
+
+        let results = match self.pool {
+            Some(ref pool) => pool.install(extract),
+            None => extract(),
+        };
+
+        let mut records = Vec::with_capacity(results.len());
+        let mut failures = Vec::new();
+        let mut rejected = Vec::new();
+        for result in results {
+            match result {
+                Ok(product_records) => records.extend(product_records),
+                Err(failed) => {
+                    let (failure, record) = *failed;
+                    failures.push(failure);
+                    rejected.extend(record);
+                }
+            }
+        }
+        (records, failures, rejected)
+    }
 
+    /// Process JSON data in batches and return a combined DataFrame.
+    /// Holds every batch in memory; prefer `flatten_batched_to_parquet` when
+    /// the result is only written out.
     pub fn flatten_to_dataframe_batched(
         &self,
         batches: impl Iterator<Item = Result<Vec<Value>>>,
-    ) -> Result<DataFrame> {
+    ) -> Result<FlattenOutput> {
         let mut all_dataframes = Vec::new();
+        let mut all_failures = Vec::new();
+        let mut all_rejected = Vec::new();
         let mut total_successful = 0;
         let mut total_failed = 0;
+        let mut total = 0;
         let mut batch_count = 0;
 
         for batch_result in batches {
@@ -72,28 +724,15 @@ impl JsonFlattener {
                 batch.len()
             );
 
-            let mut records = Vec::new();
-            let mut successful_count = 0;
-            let mut failed_count = 0;
-
-            for (index, item) in batch.iter().enumerate() {
-                match self.extract_fields_directly(item) {
-                    Ok(record) => {
-                        records.push(record);
-                        successful_count += 1;
-                    }
-                    Err(e) => {
-                        failed_count += 1;
-                        warn!(
-                            "Failed to extract fields from product at batch {} index {}: {}",
-                            batch_count, index, e
-                        );
-                    }
-                }
-            }
+            let (records, failures, rejected) = self.extract_records(&batch, Some(batch_count), total);
+            let failed_count = failures.len();
+            let successful_count = batch.len() - failed_count;
 
+            total += batch.len();
             total_successful += successful_count;
             total_failed += failed_count;
+            all_failures.extend(failures);
+            all_rejected.extend(rejected);
 
             if !records.is_empty() {
                 let batch_df = self.records_to_dataframe(records)?;
@@ -111,10 +750,10 @@ impl JsonFlattener {
         );
 
         // Combine all DataFrames
-        if all_dataframes.is_empty() {
-            Ok(DataFrame::empty())
+        let dataframe = if all_dataframes.is_empty() {
+            DataFrame::empty()
         } else if all_dataframes.len() == 1 {
-            Ok(all_dataframes.into_iter().next().unwrap())
+            all_dataframes.into_iter().next().unwrap()
         } else {
             // Concatenate all DataFrames
             let mut iter = all_dataframes.into_iter();
@@ -124,11 +763,296 @@ impl JsonFlattener {
                     .vstack(&df)
                     .map_err(|e| anyhow!("Failed to combine DataFrames: {}", e))?;
             }
-            Ok(combined)
+            combined
+        };
+
+        Ok(FlattenOutput {
+            dataframe,
+            failures: all_failures,
+            rejected: all_rejected,
+            total,
+        })
+    }
+
+    /// Flatten batches straight into one Parquet file, one row group per batch,
+    /// so only a single batch is held in memory at a time. `transform` runs on
+    /// each batch before it is written (classification, normalization, ...).
+    /// Batches whose columns differ from the first one are aligned to it.
+    pub fn flatten_batched_to_parquet<W: Write>(
+        &self,
+        batches: impl Iterator<Item = Result<Vec<Value>>>,
+        writer: W,
+        mut transform: impl FnMut(&mut DataFrame) -> Result<()>,
+    ) -> Result<BatchedParquetSummary> {
+        let mut summary = BatchedParquetSummary::default();
+        let mut writer = Some(writer);
+        let mut batched_writer = None;
+
+        for batch_result in batches {
+            let batch = batch_result?;
+            summary.batches += 1;
+
+            let offset = summary.successful + summary.failed;
+            let (records, failures, rejected) = self.extract_records(&batch, Some(summary.batches), offset);
+            let failed_count = failures.len();
+            let successful_count = batch.len() - failed_count;
+            drop(batch);
+            summary.successful += successful_count;
+            summary.failed += failed_count;
+            summary.failures.extend(failures);
+            summary.rejected.extend(rejected);
+
+            if records.is_empty() {
+                continue;
+            }
+
+            let mut batch_df = self.records_to_dataframe(records)?;
+            transform(&mut batch_df)?;
+
+            if batched_writer.is_none() {
+                let schema = batch_df.schema().clone();
+                let file_writer = writer.take().expect("writer is only taken once");
+                batched_writer = Some((ParquetWriter::new(file_writer).batched(&schema)?, schema));
+            }
+            let (batch_writer, schema) = batched_writer.as_mut().expect("writer initialised above");
+
+            let batch_df = if batch_df.schema() == schema {
+                batch_df
+            } else {
+                align_to_schema(&batch_df, schema)?
+            };
+            batch_writer
+                .write_batch(&batch_df)
+                .map_err(|e| anyhow!("Failed to write batch {}: {}", summary.batches, e))?;
+            summary.rows += batch_df.height();
+
+            info!(
+                "Batch {} written: {} successful, {} failed",
+                summary.batches, successful_count, failed_count
+            );
+        }
+
+        match (batched_writer, writer) {
+            (Some((batch_writer, _)), _) => {
+                batch_writer.finish()?;
+            }
+            (None, Some(file_writer)) => {
+                ParquetWriter::new(file_writer).finish(&mut DataFrame::empty())?;
+            }
+            (None, None) => unreachable!("writer is consumed only by the batched writer"),
+        }
+
+        info!(
+            "Streamed {} rows to Parquet: {} successful, {} failed across {} batches",
+            summary.rows, summary.successful, summary.failed, summary.batches
+        );
+        Ok(summary)
+    }
+
+    /// All rows for one product: the product itself, or one row per variant
+    /// when a variants path is configured and the product has variants
+    fn extract_product_records(&self, item: &Value) -> Result<Vec<HashMap<String, String>>> {
+        if let Some(ref validate) = self.validator {
+            validate(item)?;
+        }
+        let mut parent = self.extract_fields_directly(item)?;
+        if self.keep_raw_json {
+            parent.insert(RAW_JSON_FIELD.to_string(), item.to_string());
+        }
+        let variants = match self.variants_path {
+            Some(ref path) => path.objects(item),
+            None => Vec::new(),
+        };
+        if variants.is_empty() {
+            return Ok(vec![parent]);
+        }
+
+        variants
+            .into_iter()
+            .enumerate()
+            .map(|(index, variant)| self.variant_record(&parent, index, variant))
+            .collect()
+    }
+
+    /// A parent record with the variant-level fields of one variant applied
+    fn variant_record(
+        &self,
+        parent: &HashMap<String, String>,
+        index: usize,
+        variant: &Value,
+    ) -> Result<HashMap<String, String>> {
+        let own = self.extract_mapped_fields(variant)?;
+        let parent_id = parent.get("product_id").cloned().unwrap_or_default();
+
+        let mut record = parent.clone();
+        // Variants without an ID of their own are numbered after their parent
+        let product_id = match own.get("product_id") {
+            Some(id) if !id.trim().is_empty() && *id != parent_id => id.trim().to_string(),
+            _ => format!("{}-{}", parent_id, index + 1),
+        };
+        record.insert("sku".to_string(), format!("SKU_{}", product_id));
+        for field in VARIANT_FIELDS {
+            match own.get(field) {
+                // The built-in placeholder is not a value the variant provides
+                Some(value) if !(field == "units_of_mass" && value == "N/A") => {
+                    record.insert(field.to_string(), value.clone());
+                }
+                _ => {}
+            }
         }
+        // The parent's flag only stands while a price is still missing
+        let flagged = own.contains_key(QUALITY_FLAGS_FIELD) || parent.contains_key(QUALITY_FLAGS_FIELD);
+        if !(flagged && PRICE_FIELDS.iter().any(|field| !record.contains_key(*field))) {
+            record.remove(QUALITY_FLAGS_FIELD);
+        }
+        record.insert("product_id".to_string(), product_id);
+        record.insert(PARENT_PRODUCT_ID_FIELD.to_string(), parent_id);
+        Ok(record)
     }
 
     pub fn extract_fields_directly(&self, item: &Value) -> Result<HashMap<String, String>> {
+        let mut record = self.extract_mapped_fields(item)?;
+
+        if let Some(store_id) = item.get(STORE_ID_FIELD).filter(|v| !v.is_null()) {
+            record.insert(STORE_ID_FIELD.to_string(), value_to_plain_string(store_id));
+        }
+
+        if let Some(currency) = self.product_currency(item) {
+            record.insert(CURRENCY_FIELD.to_string(), currency.to_string());
+        }
+
+        if let Some(in_stock) = self.product_in_stock(item) {
+            record.insert(IN_STOCK_FIELD.to_string(), in_stock.to_string());
+        }
+
+        for (raw_field, column) in PROVENANCE_FIELDS {
+            if let Some(value) = item.get(raw_field).and_then(|v| v.as_str()).filter(|v| !v.is_empty()) {
+                record.insert(column.to_string(), value.to_string());
+            }
+        }
+
+        // Every record needs a key for dedup and snapshot diffs
+        match record.get("product_id").map(|id| id.trim().to_string()) {
+            Some(id) if !id.is_empty() => {
+                record.insert("product_id".to_string(), id);
+            }
+            _ => return Err(anyhow!("Product has no usable identifier")),
+        }
+
+        let has_category = record.get("category_name").is_some_and(|c| !c.trim().is_empty());
+        if !has_category
+            && let Some(category) = item
+                .get(SOURCE_CATEGORY_FIELD)
+                .and_then(|v| v.as_str())
+                .filter(|c| !c.trim().is_empty())
+        {
+            record.insert("category_name".to_string(), category.to_string());
+        }
+
+        Ok(record)
+    }
+
+    /// Currency of a product: its own `currency` field, else one named in its
+    /// price strings (e.g. "AED 12.50"), else the configured default
+    fn product_currency<'a>(&'a self, item: &Value) -> Option<&'a str> {
+        let explicit = item.get(CURRENCY_FIELD).and_then(|v| v.as_str()).and_then(currency_code);
+        let detected = || {
+            item.as_object()?
+                .iter()
+                .filter(|(key, _)| {
+                    let key = key.to_lowercase();
+                    key.contains("price") || key == "mrp"
+                })
+                .filter_map(|(_, value)| value.as_str())
+                .find_map(detect_currency)
+        };
+        explicit.or_else(detected).or(self.currency.as_deref())
+    }
+
+    /// Whether a product is in stock by its `in_stock_paths`, `None` when it
+    /// has none of them or none reads as a stock state
+    fn product_in_stock(&self, item: &Value) -> Option<bool> {
+        let states: Vec<bool> = self
+            .in_stock_paths
+            .iter()
+            .flat_map(|path| path.resolve(item))
+            .filter_map(stock_state)
+            .collect();
+        (!states.is_empty()).then(|| states.iter().all(|available| *available))
+    }
+
+    /// Canonical fields from the configured rules, falling back to the built-in extraction
+    fn extract_mapped_fields(&self, item: &Value) -> Result<HashMap<String, String>> {
+        let mut record = self.extract_builtin_fields(item)?;
+
+        if let Some(ref rules) = self.rules {
+            for field in CANONICAL_FIELDS {
+                if !rules.has_rules_for(field) {
+                    continue;
+                }
+                let numeric = if NUMERIC_FIELDS.contains(&field) {
+                    Some(self.numeric(false))
+                } else {
+                    (field == DISCOUNT_FIELD).then(|| self.numeric(true))
+                };
+                match rules.extract(field, item, numeric) {
+                    Some(value) => record.insert(field.to_string(), value),
+                    None => record.remove(field),
+                };
+            }
+
+            // Same derived fallbacks as the built-in extraction
+            if !record.contains_key("sku")
+                && let Some(id) = record.get("product_id").cloned()
+            {
+                record.insert("sku".to_string(), format!("SKU_{}", id));
+            }
+            record
+                .entry("units_of_mass".to_string())
+                .or_insert_with(|| "N/A".to_string());
+        }
+
+        // A price that is there but not a number, or is negative, is flagged
+        // and left null
+        let mut unparseable = false;
+        for field in PRICE_FIELDS {
+            let negative = record
+                .get(field)
+                .and_then(|value| parse_float(value, NumberFormat::Us))
+                .is_some_and(|price| price < 0.0);
+            if negative {
+                record.remove(field);
+                unparseable = true;
+            } else if !record.contains_key(field) && self.price_unparseable(field, item) {
+                unparseable = true;
+            }
+        }
+        if unparseable {
+            record.insert(QUALITY_FLAGS_FIELD.to_string(), PRICE_UNPARSEABLE_FLAG.to_string());
+        }
+
+        Ok(record)
+    }
+
+    /// Whether `item` has text for `field` at its configured paths (or
+    /// built-in keys) that is not a number, e.g. "Rs. ask"
+    fn price_unparseable(&self, field: &str, item: &Value) -> bool {
+        let candidates: Vec<&Value> = match self.rules.as_ref().and_then(|rules| rules.paths.get(field)) {
+            Some(paths) => paths.iter().flat_map(|path| path.resolve(item)).collect(),
+            None => {
+                let keys: &[&str] = if field == "mrp" { &MRP_KEYS } else { &COST_PRICE_KEYS };
+                keys.iter().filter_map(|key| item.get(*key)).collect()
+            }
+        };
+        let numeric = self.numeric(false);
+        candidates
+            .into_iter()
+            .filter_map(|value| value.as_str())
+            .any(|text| !text.trim().is_empty() && numeric.parse(text).is_none())
+    }
+
+    /// Built-in source-specific fallbacks, used for fields without configured rules
+    fn extract_builtin_fields(&self, item: &Value) -> Result<HashMap<String, String>> {
         let mut record = HashMap::new();
 
         // Helper function to safely extract string values
@@ -141,57 +1065,15 @@ impl JsonFlattener {
 
         // Helper function to safely extract number values
         let get_number = |key: &str| -> Option<String> {
-            item.get(key).and_then(|v| match v {
-                Value::Number(n) => {
-                    if let Some(f) = n.as_f64() {
-                        // Format as integer if it's a whole number
-                        if f.fract() == 0.0 {
-                            Some((f as i64).to_string())
-                        } else {
-                            Some(f.to_string())
-                        }
-                    } else {
-                        Some(n.to_string())
-                    }
-                }
-                Value::String(s) => {
-                    s.parse::<f64>().ok().map(|f| {
-                        // Format as integer if it's a whole number
-                        if f.fract() == 0.0 {
-                            (f as i64).to_string()
-                        } else {
-                            f.to_string()
-                        }
-                    })
-                }
-                _ => None,
-            })
+            item.get(key).and_then(|v| value_to_field_string(v, Some(self.numeric(false))))
         };
 
-        // Extract identifier - try multiple field names
-        let identifier = if let Some(product_id) = item.get("product_id").and_then(|v| v.as_u64()) {
-            Some(product_id.to_string())
-        } else if let Some(product_id) = item.get("productID").and_then(|v| v.as_str()) {
-            // Pandamart: productID field
-            Some(product_id.to_string())
-        } else {
-            let sku = get_string("sku");
-            if !sku.is_empty() {
-                Some(sku)
-            } else {
-                let id = get_string("id");
-                if !id.is_empty() {
-                    Some(id)
-                } else {
-                    let variant = get_string("variantTitleSlug");
-                    if !variant.is_empty() {
-                        Some(variant)
-                    } else {
-                        None
-                    }
-                }
-            }
-        };
+        // Extract identifier, first usable of: product_id, productID (Pandamart),
+        // sku, id (BazaarApp / Dealcart), variantTitleSlug. Numbers and strings
+        // are both accepted and normalized by `identifier_string`.
+        let identifier = ["product_id", "productID", "sku", "id", "variantTitleSlug"]
+            .iter()
+            .find_map(|key| item.get(*key).and_then(identifier_string));
 
         if let Some(ref id) = identifier {
             record.insert("product_id".to_string(), id.clone());
@@ -217,11 +1099,9 @@ impl JsonFlattener {
         }
 
         // Extract cost_price with multiple fallbacks
-        let cost_price = get_number("cost_price")
-            .or_else(|| get_number("special_price"))
-            .or_else(|| get_number("discountedPrice"))
-            .or_else(|| get_number("discounted_price"))
-            .or_else(|| get_number("price")) // Pandamart: price field
+        let cost_price = COST_PRICE_KEYS
+            .iter()
+            .find_map(|key| get_number(key))
             // Dealcart: Extract from groupRanges[0].discountedPrice
             .or_else(|| {
                 item.get("groupRanges")
@@ -236,12 +1116,9 @@ impl JsonFlattener {
         }
 
         // Extract mrp with multiple fallbacks
-        let mrp = get_number("mrp")
-            .or_else(|| get_number("product_price"))
-            .or_else(|| get_number("actualPrice"))
-            .or_else(|| get_number("actual_price"))
-            .or_else(|| get_number("originalPrice")) // Pandamart: originalPrice field
-            .or_else(|| get_number("original_price")) // Pandamart: original_price field
+        let mrp = MRP_KEYS
+            .iter()
+            .find_map(|key| get_number(key))
             // Dealcart: Extract from inventories[0].dcImsMrp
             .or_else(|| {
                 item.get("inventories")
@@ -286,22 +1163,10 @@ impl JsonFlattener {
         }
 
         // Extract discount percentage with multiple fallbacks
-        let discount = get_number("sku_percent_off")
-            .or_else(|| {
-                get_string("sku_percent_off")
-                    .parse::<f64>()
-                    .ok()
-                    .map(|f| f.to_string())
-            })
-            .or_else(|| {
-                // Handle string values like "40% off"
-                let sku_percent_str = get_string("sku_percent_off");
-                if !sku_percent_str.is_empty() {
-                    Some(sku_percent_str)
-                } else {
-                    None
-                }
-            })
+        // Strings like "40% off" are read by their first number
+        let discount = item
+            .get(DISCOUNT_FIELD)
+            .and_then(|v| value_to_field_string(v, Some(self.numeric(true))))
             .or_else(|| get_number("discount_percentage"))
             .or_else(|| get_number("discountPercentage"))
             // Pandamart: No discount field, default to 0.00
@@ -402,6 +1267,75 @@ impl JsonFlattener {
             record.insert("category_name".to_string(), category_names);
         }
 
+        // Extract optional attributes (brand, description, image, stock)
+        let first_string = |keys: &[&str]| -> Option<String> {
+            keys.iter().find_map(|key| {
+                item.get(*key)
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.trim())
+                    .filter(|s| !s.is_empty())
+                    .map(|s| s.to_string())
+            })
+        };
+
+        // BazaarApp: vendor; others: brand as a string or {"name": ...}
+        let brand = first_string(&["brand", "vendor", "brandName", "brand_name"]).or_else(|| {
+            item.get("brand")
+                .and_then(|brand| brand.get("name"))
+                .and_then(|v| v.as_str())
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+        });
+        if let Some(brand) = brand {
+            record.insert("brand".to_string(), brand);
+        }
+
+        if let Some(description) = first_string(&["description", "short_description"]) {
+            record.insert("description".to_string(), description);
+        }
+
+        // KraveMart: default_image; BazaarApp: imageUrl / mediaGallery; Pandamart: urls
+        let image_url = first_string(&["image_url", "imageUrl", "default_image", "image"])
+            .or_else(|| {
+                item.get("mediaGallery")
+                    .and_then(|gallery| gallery.as_array())
+                    .and_then(|arr| arr.first())
+                    .and_then(|first| first.get("imageUrl"))
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string())
+            })
+            .or_else(|| {
+                ["images", "urls"].iter().find_map(|key| {
+                    let first = item.get(*key)?.as_array()?.first()?;
+                    first
+                        .as_str()
+                        .or_else(|| first.get("url").and_then(|v| v.as_str()))
+                        .map(|s| s.to_string())
+                })
+            });
+        if let Some(image_url) = image_url {
+            record.insert("image_url".to_string(), image_url);
+        }
+
+        // KraveMart: inventories.quantity; BazaarApp: availableStock; Pandamart: stockAmount
+        let stock_quantity = get_number("stock_quantity")
+            .or_else(|| get_number("availableStock"))
+            .or_else(|| get_number("stockAmount"))
+            .or_else(|| get_number("stock"))
+            .or_else(|| {
+                let inventories = item.get("inventories")?;
+                let inventory = match inventories.as_array() {
+                    Some(arr) => arr.first()?,
+                    None => inventories,
+                };
+                inventory
+                    .get("quantity")
+                    .and_then(|v| value_to_field_string(v, Some(self.numeric(false))))
+            });
+        if let Some(stock_quantity) = stock_quantity {
+            record.insert("stock_quantity".to_string(), stock_quantity);
+        }
+
         Ok(record)
     }
 
@@ -411,31 +1345,161 @@ impl JsonFlattener {
         }
 
         let mut series_vec = Vec::new();
-        let fields = [
-            "cost_price",
-            "mrp",
-            "name",
-            "sku",
-            "product_id",
-            "sku_percent_off",
-            "category_name",
-            "units_of_mass",
-        ];
 
-        for field in fields.iter() {
-            let values: Vec<String> = records
-                .iter()
-                .map(|record| record.get(*field).cloned().unwrap_or_default())
-                .collect();
+        // store_id, variant parents, currency, flags and provenance only appear for rows that have them
+        let extra_fields: Vec<&str> = [STORE_ID_FIELD, PARENT_PRODUCT_ID_FIELD, CURRENCY_FIELD, IN_STOCK_FIELD, QUALITY_FLAGS_FIELD]
+            .into_iter()
+            .chain(PROVENANCE_FIELDS.iter().map(|(_, column)| *column))
+            .chain([RAW_JSON_FIELD])
+            .filter(|field| records.iter().any(|record| record.contains_key(*field)))
+            .collect();
 
-            let series = Series::new((*field).into(), values);
+        for field in CANONICAL_FIELDS.iter().chain(extra_fields.iter()) {
+            let series = if FLOAT_FIELDS.contains(field) {
+                let values: Vec<Option<f64>> = records
+                    .iter()
+                    // Records hold numbers with `.` decimals, see `format_number`
+                    .map(|record| record.get(*field).and_then(|value| parse_float(value, NumberFormat::Us)))
+                    .collect();
+                Series::new((*field).into(), values)
+            } else if *field == IN_STOCK_FIELD {
+                let values: Vec<Option<bool>> = records
+                    .iter()
+                    .map(|record| record.get(*field).and_then(|value| value.parse().ok()))
+                    .collect();
+                Series::new((*field).into(), values)
+            } else {
+                // Fields a product has no value for are null, not empty strings
+                let values: Vec<Option<String>> = records
+                    .iter()
+                    .map(|record| record.get(*field).cloned())
+                    .collect();
+                Series::new((*field).into(), values)
+            };
             series_vec.push(series.into());
         }
 
+        let product_ids: Vec<Option<i64>> = records
+            .iter()
+            .map(|record| record.get("product_id").and_then(|id| id.parse::<i64>().ok()))
+            .collect();
+        series_vec.push(Series::new(PRODUCT_ID_NUMERIC_FIELD.into(), product_ids).into());
+
         DataFrame::new(series_vec).map_err(|e| anyhow!("Failed to create DataFrame: {}", e))
     }
 }
 
+/// Encode `df` as Parquet, leaving out the `_raw` column unless
+/// `include_raw_json` is set since it can triple the file size
+pub fn encode_parquet(df: &mut DataFrame, include_raw_json: bool) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    if include_raw_json || df.column(RAW_JSON_FIELD).is_err() {
+        ParquetWriter::new(&mut buf).finish(df)?;
+    } else {
+        ParquetWriter::new(&mut buf).finish(&mut df.drop(RAW_JSON_FIELD)?)?;
+    }
+    Ok(buf)
+}
+
+/// ISO code for a currency code or token, e.g. "aed" or "Rs." -> "AED" / "PKR"
+pub fn currency_code(currency: &str) -> Option<&'static str> {
+    let currency = currency.trim();
+    CURRENCIES
+        .iter()
+        .find(|(code, tokens)| {
+            code.eq_ignore_ascii_case(currency) || tokens.iter().any(|token| token.eq_ignore_ascii_case(currency))
+        })
+        .map(|(code, _)| *code)
+}
+
+/// Currency named in a price string, e.g. "Rs. 1,250" -> "PKR" or
+/// "د.إ 12.50" -> "AED". `None` for bare numbers.
+pub fn detect_currency(price: &str) -> Option<&'static str> {
+    CURRENCIES
+        .iter()
+        .find(|(_, tokens)| tokens.iter().any(|token| price.contains(token)))
+        .map(|(code, _)| *code)
+}
+
+/// A price string without currency tokens or thousands separators, e.g.
+/// "Rs. 1,250" -> " 1250"
+pub fn strip_currency(price: &str) -> String {
+    NumberFormat::Us.strip(price)
+}
+
+/// A price string without currency tokens, separators left as written
+fn strip_currency_tokens(price: &str) -> String {
+    let mut stripped = price.to_string();
+    for (_, tokens) in CURRENCIES {
+        for token in tokens {
+            stripped = stripped.replace(token, "");
+        }
+    }
+    stripped
+}
+
+/// Whether a stock field reads as available: booleans as they are, numbers
+/// (or numeric strings) when positive, and the usual status words. `None`
+/// for anything else, e.g. null or an unknown status.
+fn stock_state(value: &Value) -> Option<bool> {
+    match value {
+        Value::Bool(available) => Some(*available),
+        Value::Number(number) => number.as_f64().map(|quantity| quantity > 0.0),
+        Value::String(text) => {
+            let text = text.trim().to_lowercase().replace([' ', '-'], "_");
+            match text.as_str() {
+                "true" | "yes" | "y" | "in_stock" | "instock" | "available" | "enabled" | "active" => Some(true),
+                "false" | "no" | "n" | "out_of_stock" | "outofstock" | "sold_out" | "unavailable" | "disabled"
+                | "inactive" => Some(false),
+                _ => text.parse::<f64>().ok().map(|quantity| quantity > 0.0),
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Parse the first number in an extracted value, e.g. `"40% off"` -> 40.0,
+/// `"Rs. 1,250.50"` -> 1250.5 or `"12,5%"` -> 12.5 for `Eu`. A `-` right
+/// before it is kept so negative discounts reach the range check and
+/// negative prices can be flagged. `None` when there is no number.
+fn parse_float(value: &str, number_format: NumberFormat) -> Option<f64> {
+    let value = number_format.strip(value);
+    let mut start = value.find(|c: char| c.is_ascii_digit())?;
+    if value[..start].ends_with('-') {
+        start -= 1;
+    }
+    let number: String = value[start..]
+        .chars()
+        .enumerate()
+        .take_while(|(i, c)| c.is_ascii_digit() || *c == '.' || (*i == 0 && *c == '-'))
+        .map(|(_, c)| c)
+        .collect();
+    number.trim_end_matches('.').parse().ok()
+}
+
+/// Cast and reorder `df` to `schema`, adding missing columns as nulls and
+/// dropping ones the schema does not have
+fn align_to_schema(df: &DataFrame, schema: &Schema) -> Result<DataFrame> {
+    let extra: Vec<_> = df
+        .get_column_names()
+        .into_iter()
+        .filter(|name| !schema.contains(name))
+        .collect();
+    if !extra.is_empty() {
+        warn!("Dropping columns missing from the first batch: {:?}", extra);
+    }
+
+    let columns = schema
+        .iter()
+        .map(|(name, dtype)| match df.column(name) {
+            Ok(column) => column.cast(dtype),
+            Err(_) => Ok(Series::full_null(name.clone(), df.height(), dtype).into()),
+        })
+        .collect::<PolarsResult<Vec<Column>>>()?;
+
+    DataFrame::new(columns).map_err(|e| anyhow!("Failed to align batch: {}", e))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -555,7 +1619,7 @@ mod tests {
         assert_eq!(result.get("mrp").unwrap(), "390"); // product_price -> mrp
         assert_eq!(result.get("name").unwrap(), "Kfresh Potatoes (Aalu) - 3 Kg");
         assert_eq!(result.get("sku").unwrap(), "BNDL7002230");
-        assert_eq!(result.get("sku_percent_off").unwrap(), "40% off");
+        assert_eq!(result.get("sku_percent_off").unwrap(), "40");
         assert_eq!(result.get("category_name").unwrap(), "fruits & vegetables");
     }
 
@@ -591,4 +1655,631 @@ mod tests {
         assert_eq!(result.get("sku_percent_off").unwrap(), "25"); // discount_percentage
         assert_eq!(result.get("category_name").unwrap(), "Fresh Fruits"); // category_section
     }
+
+    #[test]
+    fn test_store_id_column() {
+        let flattener = JsonFlattener::new();
+        let products = vec![
+            json!({"product_id": 1, "name": "Milk", "cost_price": 200, "store_id": "1242164"}),
+            json!({"product_id": 1, "name": "Milk", "cost_price": 210, "store_id": 1242165}),
+        ];
+
+        let df = flattener.flatten_to_dataframe(&products).unwrap().dataframe;
+        let store_ids: Vec<&str> = df.column("store_id").unwrap().str().unwrap().into_no_null_iter().collect();
+        assert_eq!(store_ids, vec!["1242164", "1242165"]);
+
+        // Sources without store IDs keep the canonical columns only
+        let df = flattener
+            .flatten_to_dataframe(&[json!({"product_id": 2, "name": "Eggs"})])
+            .unwrap()
+            .dataframe;
+        assert!(df.column("store_id").is_err());
+    }
+
+    fn synthetic_products(count: usize) -> Vec<Value> {
+        (0..count)
+            .map(|i| {
+                json!({
+                    "product_id": i,
+                    "name": format!("Product {}", i),
+                    "special_price": format!("{}.00", 100 + i % 50),
+                    "product_price": format!("{}.00", 150 + i % 50),
+                    "sku_percent_off": format!("{}% off", i % 40),
+                    "categories": [{"category_name": "Dairy"}, {"category_name": "Milk"}],
+                    "inventories": {"quantity": i % 20}
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parallel_flattening_matches_serial() {
+        let products = synthetic_products(5_000);
+
+        let serial = JsonFlattener::new().with_threads(1);
+        let parallel = JsonFlattener::new().with_threads(4);
+
+        let expected = serial.flatten_to_dataframe(&products).unwrap().dataframe;
+        assert_eq!(expected.height(), 5_000);
+        assert!(parallel.flatten_to_dataframe(&products).unwrap().dataframe.equals_missing(&expected));
+
+        let batches = products.chunks(1_200).map(|chunk| Ok(chunk.to_vec()));
+        assert!(parallel.flatten_to_dataframe_batched(batches).unwrap().dataframe.equals_missing(&expected));
+    }
+
+    #[test]
+    fn test_variants_are_exploded_into_rows() {
+        let flattener = JsonFlattener::new().with_variants_path(FieldPath::parse("variants").unwrap());
+        let products = vec![
+            json!({
+                "id": 7001,
+                "name": "Basmati Rice",
+                "category": "Rice",
+                "actualPrice": 400,
+                "discountedPrice": 380,
+                "variants": [
+                    {"sku": "RICE-1KG", "actualPrice": 400, "discountedPrice": 380, "unit": "1 kg"},
+                    {"sku": "RICE-5KG", "actualPrice": 1900, "discountedPrice": 1750, "unit": "5 kg"},
+                    {"actualPrice": 3700, "unit": "10 kg"}
+                ]
+            }),
+            json!({"id": 7002, "name": "Sugar", "category": "Baking", "actualPrice": 150}),
+        ];
+
+        let df = flattener.flatten_to_dataframe(&products).unwrap().dataframe;
+        assert_eq!(df.height(), 4);
+
+        let strings = |column: &str| -> Vec<Option<String>> {
+            df.column(column)
+                .unwrap()
+                .str()
+                .unwrap()
+                .into_iter()
+                .map(|v| v.map(str::to_string))
+                .collect()
+        };
+        let some = |values: &[&str]| -> Vec<Option<String>> {
+            values.iter().map(|v| Some(v.to_string())).collect()
+        };
+
+        assert_eq!(strings("product_id"), some(&["RICE-1KG", "RICE-5KG", "7001-3", "7002"]));
+        assert_eq!(strings("sku"), some(&["RICE-1KG", "RICE-5KG", "SKU_7001-3", "SKU_7002"]));
+        assert_eq!(
+            strings("parent_product_id"),
+            vec![Some("7001".to_string()), Some("7001".to_string()), Some("7001".to_string()), None]
+        );
+        // Inherited from the parent
+        assert_eq!(strings("name"), some(&["Basmati Rice", "Basmati Rice", "Basmati Rice", "Sugar"]));
+        // Overridden per variant; the third variant keeps the parent's discounted price
+        assert_eq!(strings("units_of_mass"), some(&["1 kg", "5 kg", "10 kg", "N/A"]));
+        let mrp: Vec<Option<f64>> = df.column("mrp").unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!(mrp, vec![Some(400.0), Some(1900.0), Some(3700.0), Some(150.0)]);
+        let cost: Vec<Option<f64>> = df.column("cost_price").unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!(cost, vec![Some(380.0), Some(1750.0), Some(380.0), None]);
+
+        // Without a variants path the same input stays one row per product
+        let df = JsonFlattener::new().flatten_to_dataframe(&products).unwrap().dataframe;
+        assert_eq!(df.height(), 2);
+        assert!(df.column("parent_product_id").is_err());
+    }
+
+    #[test]
+    fn test_failed_records_are_reported() {
+        let flattener = JsonFlattener::new();
+        let products = vec![
+            json!({"product_id": 1, "name": "Milk", "price": 100}),
+            json!({"name": "Mystery Box", "price": 50}),
+            json!({"product_id": 2, "name": "Eggs", "price": 200}),
+            json!({"product_id": "   ", "title": "Blank ID"}),
+            json!({"product_id": 3, "name": "Bread", "price": 80}),
+        ];
+
+        let output = flattener.flatten_to_dataframe(&products).unwrap();
+        assert_eq!(output.dataframe.height(), 3);
+        assert_eq!(output.total, 5);
+
+        let failures = &output.failures;
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures[0].index, 1);
+        assert_eq!(failures[0].reason, "Product has no usable identifier");
+        assert_eq!(failures[0].product_id, None);
+        assert_eq!(failures[0].name.as_deref(), Some("Mystery Box"));
+        assert!(failures[0].raw_snippet.contains("Mystery Box"));
+        assert_eq!(failures[1].index, 3);
+        assert_eq!(failures[1].name.as_deref(), Some("Blank ID"));
+
+        let report = serde_json::to_value(failures).unwrap();
+        assert_eq!(report[1]["index"], 3);
+        assert!(report[1]["product_id"].is_null());
+
+        // Batched paths index failures across the whole input
+        let batches = products.chunks(2).map(|chunk| Ok(chunk.to_vec()));
+        let batched = flattener.flatten_to_dataframe_batched(batches).unwrap();
+        assert_eq!(batched.failures, output.failures);
+
+        let batches = products.chunks(2).map(|chunk| Ok(chunk.to_vec()));
+        let summary = flattener
+            .flatten_batched_to_parquet(batches, Vec::new(), |_| Ok(()))
+            .unwrap();
+        assert_eq!(summary.failures, output.failures);
+        assert_eq!((summary.successful, summary.failed), (3, 2));
+        assert!(output.rejected.is_empty() && summary.rejected.is_empty());
+
+        // Kept whole on request, on every path
+        let flattener = JsonFlattener::new().with_rejected_records(true);
+        let rejected = flattener.flatten_to_dataframe(&products).unwrap().rejected;
+        assert_eq!(rejected.len(), 2);
+        assert_eq!((rejected[1].index, &rejected[1].record), (3, &products[3]));
+        assert_eq!(rejected[0].reason, "Product has no usable identifier");
+        let batches = products.chunks(2).map(|chunk| Ok(chunk.to_vec()));
+        assert_eq!(flattener.flatten_to_dataframe_batched(batches).unwrap().rejected, rejected);
+        let batches = products.chunks(2).map(|chunk| Ok(chunk.to_vec()));
+        let summary = flattener.flatten_batched_to_parquet(batches, Vec::new(), |_| Ok(())).unwrap();
+        assert_eq!(summary.rejected, rejected);
+
+        // Long products are cut in the report
+        let long = json!({"name": "x".repeat(2 * RAW_SNIPPET_CHARS)});
+        let output = flattener.flatten_to_dataframe(&[long]).unwrap();
+        assert_eq!(output.failures[0].raw_snippet.chars().count(), RAW_SNIPPET_CHARS + 3);
+    }
+
+    #[test]
+    fn test_batched_parquet_matches_in_memory_flattening() {
+        let flattener = JsonFlattener::new();
+        let mut products = synthetic_products(2_500);
+        // Only the last batch reports store IDs
+        for product in products.iter_mut().skip(2_000) {
+            product["store_id"] = json!("1242164");
+        }
+        let expected = flattener.flatten_to_dataframe(&products[..2_000]).unwrap().dataframe;
+
+        let mut buf = Vec::new();
+        let batches = products.chunks(500).map(|chunk| Ok(chunk.to_vec()));
+        let summary = flattener
+            .flatten_batched_to_parquet(batches, &mut buf, |_| Ok(()))
+            .unwrap();
+        assert_eq!(
+            summary,
+            BatchedParquetSummary { rows: 2_500, successful: 2_500, failed: 0, batches: 5, failures: vec![], rejected: vec![] }
+        );
+
+        let written = ParquetReader::new(std::io::Cursor::new(buf)).finish().unwrap();
+        assert_eq!(written.height(), 2_500);
+        assert!(written.column("store_id").is_err());
+        assert!(written.slice(0, 2_000).equals_missing(&expected));
+
+        // Nothing to write still produces a readable file
+        let mut buf = Vec::new();
+        let summary = flattener
+            .flatten_batched_to_parquet(std::iter::empty(), &mut buf, |_| Ok(()))
+            .unwrap();
+        assert_eq!(summary.rows, 0);
+        assert_eq!(ParquetReader::new(std::io::Cursor::new(buf)).finish().unwrap().height(), 0);
+    }
+
+    /// Run with `cargo test --release -- --ignored --nocapture bench_parallel`
+    #[test]
+    #[ignore]
+    fn bench_parallel_flattening_100k() {
+        let products = synthetic_products(100_000);
+
+        let start = std::time::Instant::now();
+        let serial = JsonFlattener::new().with_threads(1).flatten_to_dataframe(&products).unwrap().dataframe;
+        let serial_time = start.elapsed();
+
+        let start = std::time::Instant::now();
+        let parallel = JsonFlattener::new().flatten_to_dataframe(&products).unwrap().dataframe;
+        let parallel_time = start.elapsed();
+
+        println!(
+            "100k records: serial {:?}, parallel {:?} ({:.1}x)",
+            serial_time,
+            parallel_time,
+            serial_time.as_secs_f64() / parallel_time.as_secs_f64()
+        );
+        assert!(parallel.equals_missing(&serial));
+    }
+
+    #[test]
+    fn test_optional_attribute_columns() {
+        let flattener = JsonFlattener::new();
+        let samples = [
+            // BazaarApp
+            json!({
+                "id": "b-1",
+                "title": "Dalda Cooking Oil 1L",
+                "vendor": "Dalda",
+                "description": "Pouch",
+                "imageUrl": "https://cdn.bazaar/dalda.jpg",
+                "availableStock": 40,
+                "actualPrice": 600,
+                "discountedPrice": 560
+            }),
+            // KraveMart
+            json!({
+                "product_id": 103922,
+                "name": "Kfresh Potatoes (Aalu) - 3 Kg",
+                "description": "Farm fresh",
+                "default_image": "https://cdn.kravemart/potatoes.png",
+                "inventories": {"sku": "BNDL7002230", "store_id": 1242164, "quantity": 12},
+                "special_price": "234.00",
+                "product_price": "390.00"
+            }),
+            // Pandamart
+            json!({
+                "productID": "PM-1",
+                "name": "Nestle Milkpak 1L",
+                "description": "UHT milk",
+                "urls": ["https://images.pandamart/milkpak.jpg"],
+                "stockAmount": 7,
+                "price": 290
+            }),
+            // Dealcart
+            json!({
+                "id": "dc-9",
+                "name": "Sugar 1kg",
+                "brand": {"name": "Local"},
+                "inventories": [{"dcImsMrp": 160, "quantity": "25"}]
+            }),
+        ];
+
+        let df = flattener.flatten_to_dataframe(&samples).unwrap().dataframe;
+        let column = |name: &str| -> Vec<Option<String>> {
+            df.column(name)
+                .unwrap()
+                .str()
+                .unwrap()
+                .into_iter()
+                .map(|v| v.map(|s| s.to_string()))
+                .collect()
+        };
+        let some = |v: &str| Some(v.to_string());
+
+        assert_eq!(column("brand"), vec![some("Dalda"), None, None, some("Local")]);
+        assert_eq!(
+            column("description"),
+            vec![some("Pouch"), some("Farm fresh"), some("UHT milk"), None]
+        );
+        assert_eq!(
+            column("image_url"),
+            vec![
+                some("https://cdn.bazaar/dalda.jpg"),
+                some("https://cdn.kravemart/potatoes.png"),
+                some("https://images.pandamart/milkpak.jpg"),
+                None
+            ]
+        );
+        assert_eq!(
+            column("stock_quantity"),
+            vec![some("40"), some("12"), some("7"), some("25")]
+        );
+    }
+
+    #[test]
+    fn test_numeric_columns_are_float64() {
+        let flattener = JsonFlattener::new();
+        let products = vec![
+            json!({"product_id": 1, "name": "Tea", "cost_price": "99.50", "mrp": "1250.00", "sku_percent_off": "40% off"}),
+            json!({"product_id": 2, "name": "Salt", "cost_price": "n/a"}),
+        ];
+
+        let df = flattener.flatten_to_dataframe(&products).unwrap().dataframe;
+        assert_eq!(df.column("product_id").unwrap().dtype(), &DataType::String);
+
+        let cost_price = df.column("cost_price").unwrap().f64().unwrap();
+        assert_eq!(cost_price.get(0), Some(99.5));
+        assert_eq!(cost_price.get(1), None);
+        let flags: Vec<Option<&str>> = df.column(QUALITY_FLAGS_FIELD).unwrap().str().unwrap().into_iter().collect();
+        assert_eq!(flags, vec![None, Some(PRICE_UNPARSEABLE_FLAG)]);
+        assert_eq!(df.column("mrp").unwrap().f64().unwrap().get(0), Some(1250.0));
+        assert_eq!(df.column("sku_percent_off").unwrap().f64().unwrap().get(0), Some(40.0));
+
+        assert_eq!(parse_float("Rs. 1,250.50", NumberFormat::Us), Some(1250.5));
+        assert_eq!(parse_float("0%", NumberFormat::Us), Some(0.0));
+        assert_eq!(parse_float("", NumberFormat::Us), None);
+        assert_eq!(parse_float("12,5%", NumberFormat::Eu), Some(12.5));
+        assert_eq!(parse_float("-5%", NumberFormat::Us), Some(-5.0));
+        assert_eq!(parse_float("Rs.-250", NumberFormat::Us), Some(-250.0));
+        assert_eq!(parse_float("Rs. 1,250-", NumberFormat::Us), Some(1250.0));
+        assert_eq!(parse_float("€ 1.250,50", NumberFormat::Eu), Some(1250.5));
+    }
+
+    #[test]
+    fn test_negative_prices_are_flagged() {
+        let products = vec![
+            json!({"product_id": 1, "name": "Tea", "cost_price": "Rs.-250", "mrp": -300, "sku_percent_off": "-5%"}),
+            json!({"product_id": 2, "name": "Salt", "cost_price": "90", "mrp": "-100"}),
+        ];
+
+        let df = JsonFlattener::new().flatten_to_dataframe(&products).unwrap().dataframe;
+        let cost_price: Vec<Option<f64>> = df.column("cost_price").unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!(cost_price, vec![None, Some(90.0)]);
+        let mrp: Vec<Option<f64>> = df.column("mrp").unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!(mrp, vec![None, None]);
+        let flags: Vec<Option<&str>> = df.column(QUALITY_FLAGS_FIELD).unwrap().str().unwrap().into_iter().collect();
+        assert_eq!(flags, vec![Some(PRICE_UNPARSEABLE_FLAG), Some(PRICE_UNPARSEABLE_FLAG)]);
+        // Discounts keep their sign for the range check
+        assert_eq!(df.column("sku_percent_off").unwrap().f64().unwrap().get(0), Some(-5.0));
+    }
+
+    #[test]
+    fn test_raw_json_column() {
+        let products = vec![
+            json!({"product_id": 1, "name": "Tea", "mrp": "120", "tags": ["hot", "loose"], "meta": {"a": null}}),
+            json!({"product_id": 2, "name": "Salt"}),
+        ];
+
+        let plain = JsonFlattener::new().flatten_to_dataframe(&products).unwrap().dataframe;
+        assert!(plain.column(RAW_JSON_FIELD).is_err());
+
+        let mut df = JsonFlattener::new()
+            .with_raw_json(true)
+            .flatten_to_dataframe(&products)
+            .unwrap()
+            .dataframe;
+        let raw = df.column(RAW_JSON_FIELD).unwrap().str().unwrap().clone();
+        for (row, product) in products.iter().enumerate() {
+            let value = raw.get(row).unwrap();
+            assert_eq!(value, product.to_string());
+            assert_eq!(&serde_json::from_str::<Value>(value).unwrap(), product);
+        }
+
+        let read = |buf: Vec<u8>| ParquetReader::new(std::io::Cursor::new(buf)).finish().unwrap();
+        let default_output = read(encode_parquet(&mut df, false).unwrap());
+        assert!(default_output.column(RAW_JSON_FIELD).is_err());
+        assert_eq!(default_output.width(), df.width() - 1);
+
+        let with_raw = read(encode_parquet(&mut df, true).unwrap());
+        assert!(with_raw.column(RAW_JSON_FIELD).unwrap().equals_missing(df.column(RAW_JSON_FIELD).unwrap()));
+    }
+
+    #[test]
+    fn test_missing_fields_are_null() {
+        let flattener = JsonFlattener::new();
+        let products = vec![
+            json!({"product_id": 1, "name": "Tea", "mrp": 120, "category": "Beverages"}),
+            json!({"product_id": 2, "cost_price": 80}),
+        ];
+
+        let df = flattener.flatten_to_dataframe(&products).unwrap().dataframe;
+        assert_eq!(df.column("mrp").unwrap().f64().unwrap().get(1), None);
+        for column in ["name", "category_name"] {
+            let values = df.column(column).unwrap().str().unwrap();
+            assert!(values.get(0).is_some());
+            assert_eq!(values.get(1), None, "missing {} should be null, not \"\"", column);
+        }
+        assert_eq!(df.column("mrp").unwrap().null_count(), 1);
+        // Derived fields still get their fallbacks
+        assert_eq!(df.column("sku").unwrap().str().unwrap().get(1), Some("SKU_2"));
+        assert_eq!(df.column("units_of_mass").unwrap().str().unwrap().get(1), Some("N/A"));
+    }
+
+    #[test]
+    fn test_currency_column() {
+        let products = vec![
+            json!({"product_id": 1, "name": "Tea", "price": "Rs. 1,250", "mrp": "PKR 1,300"}),
+            json!({"product_id": 2, "name": "Dates", "special_price": "AED 12.50", "mrp": "د.إ 15"}),
+            json!({"product_id": 3, "name": "Salt", "cost_price": 80}),
+            json!({"product_id": 4, "name": "Oil", "cost_price": 500, "currency": "aed"}),
+        ];
+
+        let currencies = |df: &DataFrame| -> Vec<Option<String>> {
+            df.column(CURRENCY_FIELD)
+                .unwrap()
+                .str()
+                .unwrap()
+                .into_iter()
+                .map(|v| v.map(str::to_string))
+                .collect()
+        };
+        let pkr = Some("PKR".to_string());
+        let aed = Some("AED".to_string());
+
+        let detected = JsonFlattener::new().flatten_to_dataframe(&products).unwrap().dataframe;
+        assert_eq!(currencies(&detected), vec![pkr.clone(), aed.clone(), None, aed.clone()]);
+        let prices: Vec<Option<f64>> = detected.column("cost_price").unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!(prices, vec![Some(1250.0), Some(12.5), Some(80.0), Some(500.0)]);
+        assert_eq!(detected.column("mrp").unwrap().f64().unwrap().get(1), Some(15.0));
+
+        // The configured currency only fills in for products that name none
+        let configured = JsonFlattener::new()
+            .with_currency("Rs")
+            .unwrap()
+            .flatten_to_dataframe(&products)
+            .unwrap()
+            .dataframe;
+        assert_eq!(currencies(&configured), vec![pkr.clone(), aed.clone(), pkr, aed]);
+
+        assert!(JsonFlattener::new().with_currency("CHF").is_err());
+        let plain = JsonFlattener::new().flatten_to_dataframe(&products[2..3]).unwrap().dataframe;
+        assert!(plain.column(CURRENCY_FIELD).is_err());
+    }
+
+    #[test]
+    fn test_currency_helpers() {
+        assert_eq!(detect_currency("Rs. 150"), Some("PKR"));
+        assert_eq!(detect_currency("₨99"), Some("PKR"));
+        assert_eq!(detect_currency("12.50 د.إ"), Some("AED"));
+        assert_eq!(detect_currency("150"), None);
+        assert_eq!(currency_code(" pkr "), Some("PKR"));
+        assert_eq!(currency_code("Dhs"), Some("AED"));
+        assert_eq!(strip_currency("AED 1,250.50").trim(), "1250.50");
+        assert_eq!(strip_currency("Rs. 150").trim(), "150");
+        assert_eq!(detect_currency("€ 3,20"), Some("EUR"));
+    }
+
+    #[test]
+    fn test_currency_markers_are_stripped_from_prices() {
+        let products = vec![
+            json!({"product_id": 1, "name": "Tea", "cost_price": "CHF 12.50", "mrp": "Rs. 1,250/-", "sku_percent_off": "CHF 5 off"}),
+        ];
+        let prices = |flattener: JsonFlattener| {
+            let df = flattener.flatten_to_dataframe(&products).unwrap().dataframe;
+            ["cost_price", "mrp", "sku_percent_off"].map(|column| df.column(column).unwrap().f64().unwrap().get(0))
+        };
+
+        assert_eq!(prices(JsonFlattener::new()), [None, None, Some(5.0)]);
+        let configured = JsonFlattener::new().with_currency_markers(vec!["CHF".to_string(), " /- ".to_string()]);
+        assert_eq!(prices(configured), [Some(12.5), Some(1250.0), Some(5.0)]);
+    }
+
+    #[test]
+    fn test_number_formats() {
+        assert_eq!(NumberFormat::Us.parse("Rs. 1,234.50"), Some(1234.5));
+        assert_eq!(NumberFormat::Us.parse("$ 99"), Some(99.0));
+        assert_eq!(NumberFormat::Eu.parse("1.234,50"), Some(1234.5));
+        assert_eq!(NumberFormat::Eu.parse("€ 3,20"), Some(3.2));
+        assert_eq!(NumberFormat::Eu.parse("Rs. 1.250"), Some(1250.0));
+        assert_eq!(NumberFormat::Eu.parse("n/a"), None);
+
+        let product = serde_json::json!({"product_id": "1", "name": "Kaffee 500 g", "cost_price": "1.234,50"});
+        let us = JsonFlattener::new().extract_fields_directly(&product).unwrap();
+        let eu = JsonFlattener::new()
+            .with_number_format(NumberFormat::Eu)
+            .extract_fields_directly(&product)
+            .unwrap();
+        assert_eq!(us.get("cost_price").map(String::as_str), Some("1.2345"));
+        assert_eq!(eu.get("cost_price").map(String::as_str), Some("1234.5"));
+
+        let products = vec![
+            serde_json::json!({"product_id": "1", "name": "Kaffee", "sku_percent_off": "12,5%"}),
+            serde_json::json!({"product_id": "2", "name": "Tee", "sku_percent_off": 7.5}),
+        ];
+        let df = JsonFlattener::new()
+            .with_number_format(NumberFormat::Eu)
+            .flatten_to_dataframe(&products)
+            .unwrap()
+            .dataframe;
+        let discounts: Vec<Option<f64>> = df.column("sku_percent_off").unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!(discounts, vec![Some(12.5), Some(7.5)]);
+    }
+
+    #[test]
+    fn test_product_id_forms() {
+        let flattener = JsonFlattener::new();
+        let products = vec![
+            json!({"product_id": 103922, "name": "Potatoes"}),
+            json!({"product_id": " 103923 ", "name": "Onions"}),
+            json!({"product_id": 103924.0, "name": "Garlic"}),
+            json!({"productID": "PM-778", "name": "Milkpak"}),
+            json!({"product_id": "", "sku": "BNDL7002230", "name": "Bundle"}),
+            json!({"name": "No identifier"}),
+        ];
+
+        let df = flattener.flatten_to_dataframe(&products).unwrap().dataframe;
+        assert_eq!(df.height(), 5, "products without an identifier are rejected");
+
+        let ids: Vec<&str> = df.column("product_id").unwrap().str().unwrap().into_no_null_iter().collect();
+        assert_eq!(ids, vec!["103922", "103923", "103924", "PM-778", "BNDL7002230"]);
+
+        let numeric: Vec<Option<i64>> = df.column(PRODUCT_ID_NUMERIC_FIELD).unwrap().i64().unwrap().into_iter().collect();
+        assert_eq!(numeric, vec![Some(103922), Some(103923), Some(103924), None, None]);
+    }
+
+    #[test]
+    fn test_source_category_fills_missing_category() {
+        let flattener = JsonFlattener::new();
+
+        let tagged = json!({"product_id": 7, "name": "Apple", "_source_category": "Fresh Fruits, Flash Deals"});
+        let record = flattener.extract_fields_directly(&tagged).unwrap();
+        assert_eq!(record.get("category_name").unwrap(), "Fresh Fruits, Flash Deals");
+
+        // A category from the product itself wins over the fetch-time tag
+        let own = json!({
+            "product_id": 8,
+            "categories": [{"category_name": "Dairy"}],
+            "_source_category": "Flash Deals"
+        });
+        let record = flattener.extract_fields_directly(&own).unwrap();
+        assert_eq!(record.get("category_name").unwrap(), "dairy");
+    }
+
+    #[test]
+    fn test_in_stock_from_configured_fields() {
+        let paths = ["is_enabled", "display_in_store", "availableStock", "inventoryStatus"]
+            .iter()
+            .map(|path| FieldPath::parse(path).unwrap())
+            .collect();
+        let flattener = JsonFlattener::new().with_in_stock_fields(paths);
+        let products = vec![
+            json!({"product_id": 1, "name": "Milk", "is_enabled": 1, "display_in_store": 1}),
+            json!({"product_id": 2, "name": "Eggs", "is_enabled": 1, "display_in_store": 0}),
+            json!({"product_id": 3, "name": "Rice", "availableStock": "0"}),
+            json!({"product_id": 4, "name": "Oil", "inventoryStatus": "IN_STOCK"}),
+            json!({"product_id": 5, "name": "Salt", "inventoryStatus": "Out of stock"}),
+            json!({"product_id": 6, "name": "Tea"}),
+        ];
+
+        let df = flattener.flatten_to_dataframe(&products).unwrap().dataframe;
+        let in_stock: Vec<Option<bool>> = df.column(IN_STOCK_FIELD).unwrap().bool().unwrap().into_iter().collect();
+        assert_eq!(in_stock, vec![Some(true), Some(false), Some(false), Some(true), Some(false), None]);
+
+        // Without configured fields there is no column
+        let df = JsonFlattener::new().flatten_to_dataframe(&products).unwrap().dataframe;
+        assert!(df.column(IN_STOCK_FIELD).is_err());
+    }
+
+    #[test]
+    fn test_field_path_lookups() {
+        let item = json!({
+            "groupRanges": [{"discountedPrice": "234.00"}, {"discountedPrice": "200.00"}],
+            "attributes": [
+                {"key": "baseUnit", "value": "kg"},
+                {"key": "sku", "value": "PM-001"}
+            ],
+            "categories": [
+                {"category_name": " Fruits "},
+                {"category_name": "Vegetables"}
+            ],
+            "store": {"info": {"id": 42}}
+        });
+
+        let flattener = JsonFlattener::new();
+        let extract = |path: &str, numeric: bool| {
+            FieldPath::parse(path).unwrap().extract(&item, numeric.then(|| flattener.numeric(false)))
+        };
+
+        assert_eq!(extract("groupRanges[0].discountedPrice", true), Some("234".to_string()));
+        assert_eq!(extract("groupRanges[1].discountedPrice", false), Some("200.00".to_string()));
+        assert_eq!(extract("attributes[key=sku].value", false), Some("PM-001".to_string()));
+        assert_eq!(extract("store.info.id", false), Some("42".to_string()));
+        assert_eq!(
+            extract("categories[*].category_name|lower", false),
+            Some("fruits, vegetables".to_string())
+        );
+        assert_eq!(extract("attributes[key=color].value", false), None);
+        assert_eq!(extract("groupRanges[5].discountedPrice", true), None);
+
+        assert!(FieldPath::parse("attributes[key=sku.value").is_err());
+        assert!(FieldPath::parse("name|upper").is_err());
+        assert!(FieldPath::parse("items[abc]").is_err());
+    }
+
+    #[test]
+    fn test_extraction_rules_override_builtin_fields() {
+        let extraction = HashMap::from([
+            ("cost_price".to_string(), vec!["pricing.sale".to_string(), "pricing.list".to_string()]),
+            ("sku".to_string(), vec!["code".to_string()]),
+        ]);
+        let defaults = HashMap::from([("sku_percent_off".to_string(), "0.00".to_string())]);
+        let rules = FieldExtractionRules::from_config(&extraction, &defaults).unwrap();
+        let flattener = JsonFlattener::new().with_rules(rules);
+
+        let item = json!({
+            "product_id": 7,
+            "name": "Fresh Milk",
+            "price": 999,
+            "pricing": {"list": "220.00"}
+        });
+
+        let result = flattener.extract_fields_directly(&item).unwrap();
+        assert_eq!(result.get("cost_price").unwrap(), "220"); // configured path, not the built-in "price"
+        assert_eq!(result.get("name").unwrap(), "Fresh Milk"); // no rule, built-in extraction
+        assert_eq!(result.get("sku").unwrap(), "SKU_7"); // rule found nothing, derived fallback
+        assert_eq!(result.get("sku_percent_off").unwrap(), "0.00");
+        assert_eq!(result.get("units_of_mass").unwrap(), "N/A");
+
+        let unknown = HashMap::from([("colour".to_string(), vec!["color".to_string()])]);
+        assert!(FieldExtractionRules::from_config(&unknown, &HashMap::new()).is_err());
+    }
 }
@@ -1,9 +1,43 @@
+pub mod anomaly_detector;
+pub mod column_model;
+pub mod dataset_merger;
+pub mod dedup_step;
 pub mod field_classifier;
 pub mod html_processor;
 pub mod json_flattener;
+pub mod ndjson_export;
+pub mod output_order;
+pub mod parquet_metadata;
+pub mod pipeline;
+pub mod price_history;
+pub mod product_matcher;
+pub mod quality_flags;
+pub mod quality_report;
+pub mod record_context;
 pub mod rule_normalizer;
+pub mod run_report;
+pub mod schema_validator;
+pub mod snapshot_diff;
+pub mod stock_filter;
 
+pub use anomaly_detector::*;
+pub use column_model::*;
+pub use dataset_merger::*;
+pub use dedup_step::*;
 pub use field_classifier::*;
 pub use html_processor::*;
 pub use json_flattener::*;
+pub use ndjson_export::*;
+pub use output_order::*;
+pub use parquet_metadata::*;
+pub use pipeline::*;
+pub use price_history::*;
+pub use product_matcher::*;
+pub use quality_flags::*;
+pub use quality_report::*;
+pub use record_context::*;
 pub use rule_normalizer::*;
+pub use run_report::*;
+pub use schema_validator::*;
+pub use snapshot_diff::*;
+pub use stock_filter::*;
@@ -0,0 +1,110 @@
+use anyhow::Result;
+use polars::prelude::*;
+use std::fmt::Write;
+
+/// Encode each row of `df` as one JSON object per line, in row order, keyed
+/// by column name in column order. Internal `_` columns such as `_raw` are
+/// left out. Null values are written as `null` with `explicit_nulls` and
+/// omitted otherwise. Floats are written in plain decimal notation, never as
+/// `1e-7`, so consumers see the same text for the same price.
+pub fn encode_ndjson(df: &DataFrame, explicit_nulls: bool) -> Result<String> {
+    let columns: Vec<&Column> = df.get_columns().iter().filter(|column| !column.name().starts_with('_')).collect();
+    let keys: Vec<String> = columns
+        .iter()
+        .map(|column| serde_json::to_string(column.name().as_str()))
+        .collect::<Result<_, _>>()?;
+
+    let mut out = String::new();
+    for row in 0..df.height() {
+        out.push('{');
+        let mut first = true;
+        for (column, key) in columns.iter().zip(&keys) {
+            let value = column.get(row)?;
+            if value.is_null() && !explicit_nulls {
+                continue;
+            }
+            if !first {
+                out.push(',');
+            }
+            first = false;
+            out.push_str(key);
+            out.push(':');
+            write_value(&mut out, &value)?;
+        }
+        out.push_str("}\n");
+    }
+    Ok(out)
+}
+
+fn write_value(out: &mut String, value: &AnyValue) -> Result<()> {
+    match value {
+        AnyValue::Null => out.push_str("null"),
+        AnyValue::Boolean(value) => write!(out, "{}", value)?,
+        // Display never switches to exponent notation; non-finite floats have no JSON form
+        AnyValue::Float32(value) if value.is_finite() => write!(out, "{}", value)?,
+        AnyValue::Float64(value) if value.is_finite() => write!(out, "{}", value)?,
+        AnyValue::Float32(_) | AnyValue::Float64(_) => out.push_str("null"),
+        AnyValue::Int8(_)
+        | AnyValue::Int16(_)
+        | AnyValue::Int32(_)
+        | AnyValue::Int64(_)
+        | AnyValue::UInt8(_)
+        | AnyValue::UInt16(_)
+        | AnyValue::UInt32(_)
+        | AnyValue::UInt64(_) => write!(out, "{}", value)?,
+        AnyValue::String(text) => out.push_str(&serde_json::to_string(text)?),
+        AnyValue::StringOwned(text) => out.push_str(&serde_json::to_string(text.as_str())?),
+        other => out.push_str(&serde_json::to_string(&other.to_string())?),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Value, json};
+
+    fn products() -> DataFrame {
+        df!(
+            "name" => [Some("Tea, \"Danedar\" 950g"), Some("Eggs"), None],
+            "cost_price" => [Some(1450.5), None, Some(0.0000001)],
+            "mrp" => [Some(1e21), Some(120.0), Some(90.0)],
+            "stock_quantity" => [Some(3i64), None, Some(0)],
+            "_raw" => ["{}", "{}", "{}"]
+        )
+        .unwrap()
+    }
+
+    fn parse(lines: &str) -> Vec<Value> {
+        lines.lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_rows_round_trip_in_order() {
+        let df = products();
+        let encoded = encode_ndjson(&df, false).unwrap();
+        let rows = parse(&encoded);
+        assert_eq!(rows.len(), df.height());
+
+        for (i, row) in rows.iter().enumerate() {
+            let name = df.column("name").unwrap().str().unwrap().get(i);
+            assert_eq!(row.get("name").and_then(Value::as_str), name);
+            let price = df.column("cost_price").unwrap().f64().unwrap().get(i);
+            assert_eq!(row.get("cost_price").and_then(Value::as_f64), price);
+            let stock = df.column("stock_quantity").unwrap().i64().unwrap().get(i);
+            assert_eq!(row.get("stock_quantity").and_then(Value::as_i64), stock);
+            assert!(row.get("_raw").is_none());
+        }
+        // Nulls are left out
+        assert_eq!(rows[1], json!({"name": "Eggs", "mrp": 120}));
+    }
+
+    #[test]
+    fn test_explicit_nulls_and_plain_floats() {
+        let encoded = encode_ndjson(&products(), true).unwrap();
+        let lines: Vec<&str> = encoded.lines().collect();
+        assert_eq!(lines[1], r#"{"name":"Eggs","cost_price":null,"mrp":120,"stock_quantity":null}"#);
+        assert!(lines[2].contains(r#""cost_price":0.0000001"#), "{}", lines[2]);
+        assert!(lines[0].contains(r#""mrp":1000000000000000000000"#), "{}", lines[0]);
+    }
+}
@@ -0,0 +1,134 @@
+use anyhow::Result;
+use polars::prelude::*;
+
+use super::json_flattener::{
+    CURRENCY_FIELD, DERIVED_FIELDS, IN_STOCK_FIELD, PROVENANCE_FIELDS, RAW_JSON_FIELD, STORE_ID_FIELD,
+};
+use super::quality_flags::QUALITY_FLAGS_FIELD;
+use super::rule_normalizer::{
+    BASE_UNIT_FIELD, NAME_ORIGINAL_FIELD, PRICE_PER_BASE_UNIT_FIELD, PRICE_PER_UNIT_BASIS_FIELD, PRICE_PER_UNIT_FIELD,
+    QUANTITY_UNIT_FIELD, QUANTITY_VALUE_FIELD,
+};
+
+/// Columns of a clean file in output order: the canonical schema, then what
+/// the normalizer adds. Derived and provenance columns follow, then any other
+/// column by name, with `_raw` always last.
+const COLUMN_ORDER: [&str; 20] = [
+    "product_id",
+    "name",
+    "brand",
+    "category",
+    "cost_price",
+    "mrp",
+    "discount",
+    CURRENCY_FIELD,
+    "units_of_mass",
+    IN_STOCK_FIELD,
+    "stock_quantity",
+    "sku",
+    "description",
+    "image_url",
+    STORE_ID_FIELD,
+    QUANTITY_VALUE_FIELD,
+    QUANTITY_UNIT_FIELD,
+    PRICE_PER_UNIT_FIELD,
+    PRICE_PER_UNIT_BASIS_FIELD,
+    PRICE_PER_BASE_UNIT_FIELD,
+];
+
+/// Normalizer columns after `COLUMN_ORDER` that are not about prices
+const NORMALIZER_FIELDS: [&str; 3] = [BASE_UNIT_FIELD, NAME_ORIGINAL_FIELD, QUALITY_FLAGS_FIELD];
+
+/// Put the columns of `df` in the canonical order and its rows in
+/// `product_id` order, so identical inputs give identical Parquet no matter
+/// which order categories, batches or stores arrived in. Rows without an ID
+/// go last, ordered by their remaining columns.
+pub fn canonical_order(df: &mut DataFrame) -> Result<()> {
+    let provenance = PROVENANCE_FIELDS.iter().map(|(_, column)| *column);
+    let known: Vec<&str> = COLUMN_ORDER
+        .into_iter()
+        .chain(NORMALIZER_FIELDS)
+        .chain(DERIVED_FIELDS)
+        .chain(provenance)
+        .collect();
+
+    let names: Vec<String> = df.get_column_names().into_iter().map(|name| name.to_string()).collect();
+    let mut order: Vec<&str> = known.iter().copied().filter(|field| names.iter().any(|name| name == field)).collect();
+    let mut others: Vec<&str> = names
+        .iter()
+        .map(String::as_str)
+        .filter(|name| !known.contains(name) && *name != RAW_JSON_FIELD)
+        .collect();
+    others.sort_unstable();
+    order.extend(others);
+    if names.iter().any(|name| name == RAW_JSON_FIELD) {
+        order.push(RAW_JSON_FIELD);
+    }
+    *df = df.select(order.iter().copied())?;
+
+    if df.height() < 2 || df.column("product_id").is_err() {
+        return Ok(());
+    }
+    // product_id comes first, and every other sortable column breaks ties so
+    // rows without an ID also land in the same place each time
+    let sort_by: Vec<PlSmallStr> = df
+        .get_columns()
+        .iter()
+        .filter(|column| !matches!(column.dtype(), DataType::List(_) | DataType::Struct(_) | DataType::Null))
+        .map(|column| column.name().clone())
+        .collect();
+    *df = df.sort(
+        sort_by,
+        SortMultipleOptions::default().with_nulls_last(true).with_maintain_order(true),
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::{CleanFileMetadata, encode_parquet_with_metadata};
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_shuffled_input_gives_identical_parquet() {
+        let mut first = df!(
+            "mrp" => [Some(120.0), Some(80.0), None, Some(45.0)],
+            "zeta_extra" => ["a", "b", "c", "d"],
+            "product_id" => [Some("2"), Some("1"), None, None],
+            "_raw" => ["{}", "{}", "{}", "{}"],
+            "alpha_extra" => [1, 2, 3, 4],
+            "name" => ["Milk 1L", "Bread", "Eggs", "Butter"],
+        )
+        .unwrap();
+        let mut second = df!(
+            "name" => ["Butter", "Bread", "Milk 1L", "Eggs"],
+            "alpha_extra" => [4, 2, 1, 3],
+            "product_id" => [None, Some("1"), Some("2"), None],
+            "mrp" => [Some(45.0), Some(80.0), Some(120.0), None],
+            "_raw" => ["{}", "{}", "{}", "{}"],
+            "zeta_extra" => ["d", "b", "a", "c"],
+        )
+        .unwrap();
+
+        canonical_order(&mut first).unwrap();
+        canonical_order(&mut second).unwrap();
+
+        let columns: Vec<&str> = first.get_column_names().into_iter().map(|name| name.as_str()).collect();
+        assert_eq!(columns, vec!["product_id", "name", "mrp", "alpha_extra", "zeta_extra", "_raw"]);
+        let names: Vec<Option<&str>> = first.column("name").unwrap().str().unwrap().into_iter().collect();
+        assert_eq!(names, vec![Some("Bread"), Some("Milk 1L"), Some("Butter"), Some("Eggs")]);
+
+        let metadata = CleanFileMetadata {
+            source: "naheed".to_string(),
+            pipeline_version: "0.1.0".to_string(),
+            run_at: Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap(),
+            rows: 4,
+            config_hash: None,
+        };
+        assert_eq!(
+            encode_parquet_with_metadata(&mut first, true, &metadata).unwrap(),
+            encode_parquet_with_metadata(&mut second, true, &metadata).unwrap()
+        );
+    }
+}
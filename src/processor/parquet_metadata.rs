@@ -0,0 +1,198 @@
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, SecondsFormat, Utc};
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Cursor;
+use std::path::Path;
+
+use super::json_flattener::RAW_JSON_FIELD;
+
+/// Prefix of the Parquet key-value metadata written by the pipeline
+pub const METADATA_PREFIX: &str = "data_pipeline.";
+
+/// What every clean file of one run shares: the pipeline build, when the run
+/// started and which configuration it loaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunProvenance {
+    pub pipeline_version: String,
+    pub run_at: DateTime<Utc>,
+    /// See `hash_config_dir`
+    pub config_hash: Option<String>,
+}
+
+impl RunProvenance {
+    pub fn new(config_hash: Option<String>) -> Self {
+        RunProvenance {
+            pipeline_version: env!("CARGO_PKG_VERSION").to_string(),
+            run_at: Utc::now(),
+            config_hash,
+        }
+    }
+
+    /// Metadata of a clean file of `source` with `rows` products
+    pub fn for_file(&self, source: &str, rows: usize) -> CleanFileMetadata {
+        CleanFileMetadata {
+            source: source.to_string(),
+            pipeline_version: self.pipeline_version.clone(),
+            run_at: self.run_at,
+            rows,
+            config_hash: self.config_hash.clone(),
+        }
+    }
+}
+
+impl Default for RunProvenance {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+/// Provenance of a clean Parquet file, stored in its key-value metadata as
+/// `data_pipeline.<field>`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CleanFileMetadata {
+    /// Storage name of the source, e.g. "krave_mart_1242164"
+    pub source: String,
+    /// `CARGO_PKG_VERSION` of the pipeline that wrote the file
+    pub pipeline_version: String,
+    pub run_at: DateTime<Utc>,
+    pub rows: usize,
+    pub config_hash: Option<String>,
+}
+
+impl CleanFileMetadata {
+    fn key_values(&self) -> Vec<(String, String)> {
+        let mut pairs = vec![
+            ("source", self.source.clone()),
+            ("pipeline_version", self.pipeline_version.clone()),
+            ("run_at", self.run_at.to_rfc3339_opts(SecondsFormat::Secs, true)),
+            ("rows", self.rows.to_string()),
+        ];
+        if let Some(hash) = &self.config_hash {
+            pairs.push(("config_hash", hash.clone()));
+        }
+        pairs
+            .into_iter()
+            .map(|(key, value)| (format!("{}{}", METADATA_PREFIX, key), value))
+            .collect()
+    }
+
+    /// Metadata from a file's key-value pairs; `None` for files written before
+    /// the pipeline recorded any
+    fn from_key_values<'a>(pairs: impl Iterator<Item = (&'a str, Option<&'a str>)>) -> Result<Option<Self>> {
+        let fields: std::collections::HashMap<&str, &str> = pairs
+            .filter_map(|(key, value)| Some((key.strip_prefix(METADATA_PREFIX)?, value?)))
+            .collect();
+        if fields.is_empty() {
+            return Ok(None);
+        }
+
+        let field = |name: &str| fields.get(name).copied().ok_or_else(|| anyhow!("Parquet metadata is missing {}{}", METADATA_PREFIX, name));
+        Ok(Some(CleanFileMetadata {
+            source: field("source")?.to_string(),
+            pipeline_version: field("pipeline_version")?.to_string(),
+            run_at: DateTime::parse_from_rfc3339(field("run_at")?)
+                .context("Invalid run_at in Parquet metadata")?
+                .with_timezone(&Utc),
+            rows: field("rows")?.parse().context("Invalid rows in Parquet metadata")?,
+            config_hash: fields.get("config_hash").map(|hash| hash.to_string()),
+        }))
+    }
+}
+
+/// Like `encode_parquet`, also writing `metadata` into the file's key-value metadata
+pub fn encode_parquet_with_metadata(df: &mut DataFrame, include_raw_json: bool, metadata: &CleanFileMetadata) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    let writer = ParquetWriter::new(&mut buf).with_key_value_metadata(Some(KeyValueMetadata::from_static(metadata.key_values())));
+    if include_raw_json || df.column(RAW_JSON_FIELD).is_err() {
+        writer.finish(df)?;
+    } else {
+        writer.finish(&mut df.drop(RAW_JSON_FIELD)?)?;
+    }
+    Ok(buf)
+}
+
+/// Provenance recorded in a Parquet file, `None` if it has none
+pub fn read_parquet_metadata(bytes: &[u8]) -> Result<Option<CleanFileMetadata>> {
+    let mut reader = ParquetReader::new(Cursor::new(bytes));
+    let metadata = reader.get_metadata()?;
+    let pairs = metadata
+        .key_value_metadata
+        .iter()
+        .flatten()
+        .map(|pair| (pair.key.as_str(), pair.value.as_deref()));
+    CleanFileMetadata::from_key_values(pairs)
+}
+
+/// SHA-256 over the path and contents of every file under `dir`, in path
+/// order, so a change to any source config or rule file changes the hash
+pub fn hash_config_dir(dir: impl AsRef<Path>) -> Result<String> {
+    fn collect(dir: &Path, files: &mut Vec<std::path::PathBuf>) -> Result<()> {
+        for entry in std::fs::read_dir(dir).with_context(|| format!("Failed to list {}", dir.display()))? {
+            let path = entry?.path();
+            if path.is_dir() {
+                collect(&path, files)?;
+            } else {
+                files.push(path);
+            }
+        }
+        Ok(())
+    }
+
+    let dir = dir.as_ref();
+    let mut files = Vec::new();
+    collect(dir, &mut files)?;
+    files.sort();
+
+    let mut hasher = Sha256::new();
+    for path in files {
+        let relative = path.strip_prefix(dir).unwrap_or(&path);
+        hasher.update(relative.to_string_lossy().as_bytes());
+        hasher.update(std::fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metadata_round_trips_through_parquet() {
+        let run = RunProvenance::new(Some("abc123".to_string()));
+        let mut df = df!("name" => ["milk", "eggs"], "_raw" => ["{}", "{}"]).unwrap();
+        let metadata = run.for_file("krave_mart_1242164", df.height());
+
+        let bytes = encode_parquet_with_metadata(&mut df, false, &metadata).unwrap();
+        let read = read_parquet_metadata(&bytes).unwrap().unwrap();
+        assert_eq!(read.source, "krave_mart_1242164");
+        assert_eq!(read.pipeline_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(read.rows, 2);
+        assert_eq!(read.config_hash.as_deref(), Some("abc123"));
+        assert_eq!(read.run_at.timestamp(), run.run_at.timestamp());
+
+        let stored = ParquetReader::new(Cursor::new(&bytes)).finish().unwrap();
+        assert_eq!(stored.get_column_names_str(), vec!["name"]);
+
+        // Files from before provenance was recorded
+        let mut plain = Vec::new();
+        ParquetWriter::new(&mut plain).finish(&mut df).unwrap();
+        assert_eq!(read_parquet_metadata(&plain).unwrap(), None);
+    }
+
+    #[test]
+    fn test_config_hash_changes_with_files() {
+        let dir = std::env::temp_dir().join(format!("config_hash_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("schemas")).unwrap();
+        std::fs::write(dir.join("a.toml"), "x = 1").unwrap();
+        std::fs::write(dir.join("schemas/b.json"), "{}").unwrap();
+
+        let first = hash_config_dir(&dir).unwrap();
+        assert_eq!(hash_config_dir(&dir).unwrap(), first);
+        std::fs::write(dir.join("schemas/b.json"), "{\"type\": \"object\"}").unwrap();
+        assert_ne!(hash_config_dir(&dir).unwrap(), first);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,121 @@
+use anyhow::{Context, Result};
+use polars::prelude::*;
+use tracing::debug;
+
+/// One step of a `Pipeline`, transforming a source's DataFrame in place,
+/// e.g. a computed column or an enrichment from a lookup table
+pub trait Processor: Send + Sync {
+    /// Name the step is logged and reported under
+    fn name(&self) -> &str;
+
+    fn process(&self, df: &mut DataFrame) -> Result<()>;
+}
+
+/// Ordered chain of processors run over each DataFrame. The pipeline runs
+/// each source's normalizer through one, so custom steps can be appended
+/// after it without touching the fixed stages.
+#[derive(Default)]
+pub struct Pipeline {
+    processors: Vec<Box<dyn Processor>>,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append `processor`, run after every step added so far
+    pub fn with(mut self, processor: impl Processor + 'static) -> Self {
+        self.processors.push(Box::new(processor));
+        self
+    }
+
+    /// Names of the steps in the order they run
+    pub fn names(&self) -> Vec<&str> {
+        self.processors.iter().map(|processor| processor.name()).collect()
+    }
+}
+
+impl Processor for Pipeline {
+    fn name(&self) -> &str {
+        "pipeline"
+    }
+
+    /// Run every step in order, stopping at the first that fails
+    fn process(&self, df: &mut DataFrame) -> Result<()> {
+        for processor in &self.processors {
+            processor
+                .process(df)
+                .with_context(|| format!("Processor '{}' failed", processor.name()))?;
+            debug!("Applied {} ({} rows)", processor.name(), df.height());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    /// Keeps rows with a `cost_price`
+    struct Priced;
+
+    impl Processor for Priced {
+        fn name(&self) -> &str {
+            "priced"
+        }
+
+        fn process(&self, df: &mut DataFrame) -> Result<()> {
+            let mask = df.column("cost_price")?.is_not_null();
+            *df = df.filter(&mask)?;
+            Ok(())
+        }
+    }
+
+    /// Adds `mrp - cost_price` as `savings`
+    struct Savings;
+
+    impl Processor for Savings {
+        fn name(&self) -> &str {
+            "savings"
+        }
+
+        fn process(&self, df: &mut DataFrame) -> Result<()> {
+            let savings = (df.column("mrp")? - df.column("cost_price")?)?.with_name("savings".into());
+            df.with_column(savings)?;
+            Ok(())
+        }
+    }
+
+    struct Fails;
+
+    impl Processor for Fails {
+        fn name(&self) -> &str {
+            "lookup"
+        }
+
+        fn process(&self, _df: &mut DataFrame) -> Result<()> {
+            Err(anyhow!("lookup table missing"))
+        }
+    }
+
+    #[test]
+    fn test_custom_processors_run_in_order() {
+        let pipeline = Pipeline::new().with(Priced).with(Savings);
+        assert_eq!(pipeline.names(), vec!["priced", "savings"]);
+
+        let mut df = df!(
+            "product_id" => ["1", "2", "3"],
+            "cost_price" => [Some(90.0), None, Some(40.0)],
+            "mrp" => [100.0, 100.0, 50.0],
+        )
+        .unwrap();
+        pipeline.process(&mut df).unwrap();
+        let savings: Vec<Option<f64>> = df.column("savings").unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!(savings, vec![Some(10.0), Some(10.0)]);
+
+        let error = Pipeline::new().with(Savings).with(Fails).process(&mut df).unwrap_err();
+        assert_eq!(format!("{:#}", error), "Processor 'lookup' failed: lookup table missing");
+    }
+}
@@ -0,0 +1,231 @@
+use anyhow::{Context, Result, anyhow};
+use chrono::NaiveDate;
+use polars::prelude::*;
+use std::collections::BTreeMap;
+use std::path::Path;
+use tracing::info;
+
+use crate::storage::MinioStorage;
+
+/// Columns of a price history, one row per matching product per snapshot day
+pub const HISTORY_COLUMNS: [&str; 7] = ["date", "source", "product_id", "name", "cost_price", "mrp", "discount"];
+
+/// Columns read from each clean snapshot; older files may lack some, which
+/// then come out null
+const TEXT_COLUMNS: [&str; 2] = ["product_id", "name"];
+const PRICE_COLUMNS: [&str; 3] = ["cost_price", "mrp", "discount"];
+
+/// Which products a history follows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProductFilter {
+    ProductId(String),
+    /// Substring of the product name, ignoring case
+    NameContains(String),
+}
+
+impl ProductFilter {
+    /// Which rows of `snapshot` match; none when it lacks the column filtered on
+    fn matches(&self, snapshot: &DataFrame) -> Result<Series> {
+        let column = match self {
+            ProductFilter::ProductId(_) => "product_id",
+            ProductFilter::NameContains(_) => "name",
+        };
+        let Ok(values) = snapshot.column(column) else {
+            return Ok(BooleanChunked::full(column.into(), false, snapshot.height()).into_series());
+        };
+
+        let mask: BooleanChunked = values
+            .cast(&DataType::String)?
+            .str()?
+            .into_iter()
+            .map(|value| value.is_some_and(|value| self.matches_value(value)))
+            .collect();
+        Ok(mask.into_series())
+    }
+
+    fn matches_value(&self, value: &str) -> bool {
+        match self {
+            ProductFilter::ProductId(id) => value == id,
+            ProductFilter::NameContains(text) => value.to_lowercase().contains(&text.to_lowercase()),
+        }
+    }
+}
+
+/// The matching rows of one clean snapshot, in `HISTORY_COLUMNS` order.
+/// Columns the snapshot lacks are null and prices are read as floats, so
+/// snapshots written before a schema change line up with newer ones.
+pub fn snapshot_history(snapshot: DataFrame, source: &str, date: NaiveDate, filter: &ProductFilter) -> Result<LazyFrame> {
+    let matches = filter.matches(&snapshot)?;
+    let present = |column: &str| snapshot.column(column).is_ok();
+    let mut columns = vec![lit(date.to_string()).alias("date"), lit(source).alias("source")];
+    for column in TEXT_COLUMNS {
+        let value = if present(column) { col(column) } else { lit(NULL) };
+        columns.push(value.cast(DataType::String).alias(column));
+    }
+    for column in PRICE_COLUMNS {
+        let value = if present(column) { col(column) } else { lit(NULL) };
+        columns.push(value.cast(DataType::Float64).alias(column));
+    }
+
+    Ok(snapshot.lazy().select(columns).filter(lit(matches)))
+}
+
+/// Price history of the products matching `filter` in every clean snapshot
+/// of `source`, oldest first, from `since` on when given. Days with several
+/// snapshots use the latest one; snapshots without the product add no rows.
+pub async fn price_history(
+    storage: &MinioStorage,
+    source: &str,
+    filter: &ProductFilter,
+    since: Option<NaiveDate>,
+) -> Result<DataFrame> {
+    // Newest first, so the first key seen for a day is its latest snapshot
+    let mut by_day: BTreeMap<NaiveDate, String> = BTreeMap::new();
+    for key in storage.list_clean_files(source).await? {
+        if let Some(date) = MinioStorage::raw_file_date(&key)
+            && since.is_none_or(|since| date >= since)
+        {
+            by_day.entry(date).or_insert(key);
+        }
+    }
+    info!("Reading {} daily snapshots of {}", by_day.len(), source);
+
+    let mut histories = Vec::with_capacity(by_day.len());
+    for (date, key) in &by_day {
+        let snapshot = storage.load_parquet(key).await?;
+        histories.push(snapshot_history(snapshot, source, *date, filter)?);
+    }
+    if histories.is_empty() {
+        return Ok(empty_history());
+    }
+
+    concat(histories, UnionArgs::default())?
+        .collect()
+        .with_context(|| format!("Failed to build the price history of {}", source))
+}
+
+/// A history without rows, for sources without snapshots
+fn empty_history() -> DataFrame {
+    let columns = HISTORY_COLUMNS.map(|name| {
+        let dtype = if PRICE_COLUMNS.contains(&name) { DataType::Float64 } else { DataType::String };
+        Column::new_empty(name.into(), &dtype)
+    });
+    DataFrame::new(columns.to_vec()).expect("history columns have distinct names")
+}
+
+/// Write a history as CSV or Parquet, by the extension of `path`
+pub fn write_history(history: &mut DataFrame, path: &str) -> Result<()> {
+    let extension = Path::new(path).extension().and_then(|extension| extension.to_str());
+    let file = || std::fs::File::create(path).with_context(|| format!("Failed to create {}", path));
+    match extension {
+        Some("csv") => {
+            CsvWriter::new(file()?).finish(history)?;
+        }
+        Some("parquet") => {
+            ParquetWriter::new(file()?).finish(history)?;
+        }
+        _ => return Err(anyhow!("History output must end in .csv or .parquet, got {}", path)),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::backend::{MemoryBackend, ObjectBackend};
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 9, day).unwrap()
+    }
+
+    /// Three days of one source: the tea is missing on the 14th, and the
+    /// 13th predates the discount column and stored prices as text
+    async fn storage_with_snapshots() -> MinioStorage {
+        let clean = MemoryBackend::new("pipeline-clean");
+        let storage = MinioStorage::with_backends(Box::new(MemoryBackend::new("pipeline-raw")), Box::new(clean.clone()));
+        let snapshots = [
+            (
+                "clean/naheed/20250913-080000.parquet",
+                df!(
+                    "product_id" => ["1", "2"],
+                    "name" => ["Tapal Danedar Tea 950g", "Nestle Milk 1L"],
+                    "cost_price" => ["1450", "290"],
+                    "mrp" => ["1500", "300"]
+                ),
+            ),
+            (
+                "clean/naheed/20250914-080000.parquet",
+                df!(
+                    "product_id" => ["2"],
+                    "name" => ["Nestle Milk 1L"],
+                    "cost_price" => [280.0],
+                    "mrp" => [300.0],
+                    "discount" => [6.67]
+                ),
+            ),
+            (
+                "clean/naheed/20250915-080000.parquet",
+                df!(
+                    "product_id" => ["1", "2"],
+                    "name" => ["Tapal Danedar Tea 950g", "Nestle Milk 1L"],
+                    "cost_price" => [1500.0, 300.0],
+                    "mrp" => [1500.0, 300.0],
+                    "discount" => [None, None::<f64>]
+                ),
+            ),
+        ];
+        for (key, df) in snapshots {
+            let mut buf = Vec::new();
+            ParquetWriter::new(&mut buf).finish(&mut df.unwrap()).unwrap();
+            clean.put_object(key, &buf).await.unwrap();
+        }
+        storage
+    }
+
+    #[tokio::test]
+    async fn test_history_by_product_id() {
+        let storage = storage_with_snapshots().await;
+        let history = price_history(&storage, "naheed", &ProductFilter::ProductId("1".to_string()), None)
+            .await
+            .unwrap();
+
+        let columns: Vec<&str> = history.get_column_names().iter().map(|name| name.as_str()).collect();
+        assert_eq!(columns, HISTORY_COLUMNS);
+        let dates: Vec<Option<&str>> = history.column("date").unwrap().str().unwrap().into_iter().collect();
+        assert_eq!(dates, vec![Some("2025-09-13"), Some("2025-09-15")]);
+        let prices: Vec<Option<f64>> = history.column("cost_price").unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!(prices, vec![Some(1450.0), Some(1500.0)]);
+        assert_eq!(history.column("discount").unwrap().null_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_history_by_name_since_a_date() {
+        let storage = storage_with_snapshots().await;
+        let filter = ProductFilter::NameContains("nestle MILK".to_string());
+
+        let history = price_history(&storage, "naheed", &filter, None).await.unwrap();
+        let prices: Vec<Option<f64>> = history.column("cost_price").unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!(prices, vec![Some(290.0), Some(280.0), Some(300.0)]);
+
+        let history = price_history(&storage, "naheed", &filter, Some(date(14))).await.unwrap();
+        assert_eq!(history.height(), 2);
+
+        let none = price_history(&storage, "dealcart", &filter, None).await.unwrap();
+        assert_eq!((none.height(), none.width()), (0, HISTORY_COLUMNS.len()));
+    }
+
+    #[test]
+    fn test_write_history() {
+        let dir = std::env::temp_dir().join(format!("price_history_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut history = empty_history();
+
+        let csv = dir.join("history.csv");
+        write_history(&mut history, csv.to_str().unwrap()).unwrap();
+        let header = std::fs::read_to_string(&csv).unwrap();
+        assert_eq!(header.trim(), HISTORY_COLUMNS.join(","));
+
+        assert!(write_history(&mut history, dir.join("history.json").to_str().unwrap()).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
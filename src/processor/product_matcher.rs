@@ -0,0 +1,397 @@
+use anyhow::Result;
+use polars::prelude::*;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+use super::dataset_merger::SOURCE_COLUMN;
+use super::rule_normalizer::{QUANTITY_UNIT_FIELD, QUANTITY_VALUE_FIELD};
+
+/// Column of `matches.parquet` naming the group of products that are the same SKU
+pub const MATCH_GROUP_ID_FIELD: &str = "match_group_id";
+/// Column of `matches.parquet` with the name similarity that put a product in its group
+pub const MATCH_SCORE_FIELD: &str = "match_score";
+
+/// Names at least this similar are taken for the same product
+pub const DEFAULT_MATCH_THRESHOLD: f64 = 0.5;
+/// A product whose two best candidates in another source score within this
+/// of each other is reported as ambiguous
+pub const DEFAULT_AMBIGUITY_MARGIN: f64 = 0.05;
+
+/// Relative difference under which two quantities are the same, e.g. 1 kg and 1000 g
+const QUANTITY_TOLERANCE: f64 = 1e-6;
+
+/// A product of the merged dataset taking part in matching
+#[derive(Debug, Clone)]
+struct Candidate {
+    source: String,
+    product_id: String,
+    name: String,
+    quantity: Option<f64>,
+    trigrams: HashSet<String>,
+}
+
+/// Candidates in another source that scored too close to call
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AmbiguousMatch {
+    pub source: String,
+    pub product_id: String,
+    pub name: String,
+    pub other_source: String,
+    /// Best first
+    pub candidates: Vec<MatchCandidate>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MatchCandidate {
+    pub product_id: String,
+    pub name: String,
+    pub score: f64,
+}
+
+/// Result of `ProductMatcher::match_products`
+pub struct MatchOutput {
+    /// One row per matched product: group id, source, product id, name and score
+    pub matches: DataFrame,
+    pub ambiguous: Vec<AmbiguousMatch>,
+}
+
+/// Links the same SKU across sources in the merged dataset.
+///
+/// Products are only compared within a block of the same canonical category
+/// and `quantity_unit`, and must have the same quantity. Within a block, names
+/// are scored by trigram similarity; pairs above the threshold are grouped
+/// best first, with at most one product per source in a group.
+pub struct ProductMatcher {
+    threshold: f64,
+    ambiguity_margin: f64,
+    /// Lowercased category -> canonical category, for stores naming the same
+    /// category differently
+    category_aliases: HashMap<String, String>,
+}
+
+impl ProductMatcher {
+    pub fn new() -> Self {
+        ProductMatcher {
+            threshold: DEFAULT_MATCH_THRESHOLD,
+            ambiguity_margin: DEFAULT_AMBIGUITY_MARGIN,
+            category_aliases: HashMap::new(),
+        }
+    }
+
+    /// Match names at least this similar (0–1) instead of `DEFAULT_MATCH_THRESHOLD`
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn with_ambiguity_margin(mut self, margin: f64) -> Self {
+        self.ambiguity_margin = margin;
+        self
+    }
+
+    /// Block categories under these canonical names, e.g. "vegetables" ->
+    /// "fruits & vegetables"
+    pub fn with_category_aliases(mut self, aliases: HashMap<String, String>) -> Self {
+        self.category_aliases = aliases
+            .into_iter()
+            .map(|(alias, canonical)| (alias.trim().to_lowercase(), canonical.trim().to_lowercase()))
+            .collect();
+        self
+    }
+
+    /// Group the products of `merged` that are the same SKU in different
+    /// sources. Rows without a name, category or quantity unit are not matched.
+    pub fn match_products(&self, merged: &DataFrame) -> Result<MatchOutput> {
+        let blocks = self.blocks(merged)?;
+
+        let mut pairs: Vec<(usize, usize, f64)> = Vec::new();
+        let mut ambiguous = Vec::new();
+        let mut candidates: Vec<Candidate> = Vec::new();
+        for block in blocks.into_values() {
+            let offset = candidates.len();
+            for (i, a) in block.iter().enumerate() {
+                // Best scores of `a` against each other source, for the ambiguity report
+                let mut by_source: HashMap<&str, Vec<(usize, f64)>> = HashMap::new();
+                for (j, b) in block.iter().enumerate() {
+                    if i == j || a.source == b.source || !same_quantity(a.quantity, b.quantity) {
+                        continue;
+                    }
+                    let score = trigram_similarity(&a.trigrams, &b.trigrams);
+                    if score >= self.threshold {
+                        by_source.entry(b.source.as_str()).or_default().push((j, score));
+                        if i < j {
+                            pairs.push((offset + i, offset + j, score));
+                        }
+                    }
+                }
+
+                for (other_source, mut scored) in by_source {
+                    scored.sort_by(|x, y| y.1.total_cmp(&x.1));
+                    if scored.len() > 1 && scored[0].1 - scored[1].1 <= self.ambiguity_margin {
+                        ambiguous.push(AmbiguousMatch {
+                            source: a.source.clone(),
+                            product_id: a.product_id.clone(),
+                            name: a.name.clone(),
+                            other_source: other_source.to_string(),
+                            candidates: scored
+                                .iter()
+                                .map(|&(j, score)| MatchCandidate {
+                                    product_id: block[j].product_id.clone(),
+                                    name: block[j].name.clone(),
+                                    score,
+                                })
+                                .collect(),
+                        });
+                    }
+                }
+            }
+            candidates.extend(block);
+        }
+
+        let groups = group_pairs(&candidates, pairs);
+        ambiguous.sort_by(|a, b| (&a.source, &a.product_id, &a.other_source).cmp(&(&b.source, &b.product_id, &b.other_source)));
+        Ok(MatchOutput { matches: matches_frame(&candidates, &groups)?, ambiguous })
+    }
+
+    /// Candidates by canonical category and quantity unit
+    fn blocks(&self, merged: &DataFrame) -> Result<HashMap<(String, String), Vec<Candidate>>> {
+        let mut blocks: HashMap<(String, String), Vec<Candidate>> = HashMap::new();
+        let (Ok(names), Ok(categories), Ok(units)) = (
+            merged.column("name"),
+            merged.column("category"),
+            merged.column(QUANTITY_UNIT_FIELD),
+        ) else {
+            return Ok(blocks);
+        };
+
+        let string_column = |column: &Column| -> Result<StringChunked> {
+            Ok(column.cast(&DataType::String)?.str()?.clone())
+        };
+        let names = string_column(names)?;
+        let categories = string_column(categories)?;
+        let units = string_column(units)?;
+        let sources = string_column(merged.column(SOURCE_COLUMN)?)?;
+        let product_ids = match merged.column("product_id") {
+            Ok(column) => string_column(column)?,
+            Err(_) => StringChunked::full_null("product_id".into(), merged.height()),
+        };
+        let quantities = match merged.column(QUANTITY_VALUE_FIELD) {
+            Ok(column) => column.cast(&DataType::Float64)?.f64()?.clone(),
+            Err(_) => Float64Chunked::full_null(QUANTITY_VALUE_FIELD.into(), merged.height()),
+        };
+
+        for row in 0..merged.height() {
+            let (Some(name), Some(category), Some(unit), Some(source)) =
+                (names.get(row), categories.get(row), units.get(row), sources.get(row))
+            else {
+                continue;
+            };
+            let Some(category) = self.canonical_category(category) else {
+                continue;
+            };
+            let trigrams = name_trigrams(name);
+            if trigrams.is_empty() {
+                continue;
+            }
+
+            blocks.entry((category, unit.to_string())).or_default().push(Candidate {
+                source: source.to_string(),
+                product_id: product_ids.get(row).unwrap_or_default().to_string(),
+                name: name.to_string(),
+                quantity: quantities.get(row),
+                trigrams,
+            });
+        }
+        Ok(blocks)
+    }
+
+    /// The first of a product's comma-separated categories, under its alias if it has one
+    fn canonical_category(&self, category: &str) -> Option<String> {
+        let first = category.split(',').next()?.trim().to_lowercase();
+        if first.is_empty() {
+            return None;
+        }
+        Some(self.category_aliases.get(&first).cloned().unwrap_or(first))
+    }
+}
+
+impl Default for ProductMatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether two quantities are the same, treating a missing one as unknown
+fn same_quantity(a: Option<f64>, b: Option<f64>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => (a - b).abs() <= QUANTITY_TOLERANCE * a.abs().max(b.abs()).max(1.0),
+        _ => true,
+    }
+}
+
+/// Character trigrams of each word of a name, ignoring case, punctuation
+/// and numbers (the quantity is compared separately)
+fn name_trigrams(name: &str) -> HashSet<String> {
+    let lowered = name.to_lowercase();
+    let mut trigrams = HashSet::new();
+    for word in lowered.split(|c: char| !c.is_alphabetic()).filter(|word| !word.is_empty()) {
+        let padded: Vec<char> = format!(" {} ", word).chars().collect();
+        for window in padded.windows(3) {
+            trigrams.insert(window.iter().collect());
+        }
+    }
+    trigrams
+}
+
+/// Dice coefficient of two trigram sets, 0–1
+fn trigram_similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    2.0 * a.intersection(b).count() as f64 / (a.len() + b.len()) as f64
+}
+
+/// Groups of candidate indices with the score that joined each, taking the
+/// best pairs first and never putting two products of one source in a group
+fn group_pairs(candidates: &[Candidate], mut pairs: Vec<(usize, usize, f64)>) -> Vec<Vec<(usize, f64)>> {
+    pairs.sort_by(|x, y| y.2.total_cmp(&x.2).then((x.0, x.1).cmp(&(y.0, y.1))));
+
+    let mut group_of: Vec<Option<usize>> = vec![None; candidates.len()];
+    let mut groups: Vec<Vec<(usize, f64)>> = Vec::new();
+    let has_source = |group: &[(usize, f64)], source: &str| group.iter().any(|&(i, _)| candidates[i].source == source);
+
+    for (a, b, score) in pairs {
+        match (group_of[a], group_of[b]) {
+            (None, None) => {
+                group_of[a] = Some(groups.len());
+                group_of[b] = Some(groups.len());
+                groups.push(vec![(a, score), (b, score)]);
+            }
+            (Some(g), None) | (None, Some(g)) => {
+                let new = if group_of[a].is_none() { a } else { b };
+                if !has_source(&groups[g], &candidates[new].source) {
+                    group_of[new] = Some(g);
+                    groups[g].push((new, score));
+                }
+            }
+            (Some(g), Some(h)) if g != h => {
+                let disjoint = groups[h].iter().all(|&(i, _)| !has_source(&groups[g], &candidates[i].source));
+                if disjoint {
+                    let moved = std::mem::take(&mut groups[h]);
+                    for &(i, _) in &moved {
+                        group_of[i] = Some(g);
+                    }
+                    groups[g].extend(moved);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    groups.retain(|group| !group.is_empty());
+    for group in &mut groups {
+        group.sort_by(|x, y| (&candidates[x.0].source, x.0).cmp(&(&candidates[y.0].source, y.0)));
+    }
+    groups
+}
+
+fn matches_frame(candidates: &[Candidate], groups: &[Vec<(usize, f64)>]) -> Result<DataFrame> {
+    let rows = groups.iter().enumerate().flat_map(|(id, group)| group.iter().map(move |&(i, score)| (id as u32, i, score)));
+    let (mut ids, mut sources, mut product_ids, mut names, mut scores) = (Vec::new(), Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    for (id, i, score) in rows {
+        ids.push(id);
+        sources.push(candidates[i].source.as_str());
+        product_ids.push(candidates[i].product_id.as_str());
+        names.push(candidates[i].name.as_str());
+        scores.push(score);
+    }
+
+    Ok(DataFrame::new(vec![
+        Series::new(MATCH_GROUP_ID_FIELD.into(), ids).into(),
+        Series::new(SOURCE_COLUMN.into(), sources).into(),
+        Series::new("product_id".into(), product_ids).into(),
+        Series::new("name".into(), names).into(),
+        Series::new(MATCH_SCORE_FIELD.into(), scores).into(),
+    ])?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn merged() -> DataFrame {
+        df!(
+            SOURCE_COLUMN => ["krave_mart", "krave_mart", "krave_mart", "naheed", "naheed", "naheed"],
+            "product_id" => ["k1", "k2", "k3", "n1", "n2", "n3"],
+            "name" => ["potatoes", "tapal danedar tea", "dawn white bread", "potato (aloo)", "tapal danedar black tea", "bake parlor milky bread"],
+            "category" => ["vegetables", "tea", "bakery", "fruits & vegetables", "tea, beverages", "bakery"],
+            QUANTITY_VALUE_FIELD => [3000.0, 950.0, 1.0, 3000.0, 950.0, 1.0],
+            QUANTITY_UNIT_FIELD => ["g", "g", "piece", "g", "g", "piece"]
+        )
+        .unwrap()
+    }
+
+    fn groups(matches: &DataFrame) -> Vec<Vec<String>> {
+        let ids = matches.column(MATCH_GROUP_ID_FIELD).unwrap().u32().unwrap();
+        let product_ids = matches.column("product_id").unwrap().str().unwrap();
+        let mut groups: Vec<Vec<String>> = Vec::new();
+        for (id, product_id) in ids.into_no_null_iter().zip(product_ids.into_no_null_iter()) {
+            if groups.len() <= id as usize {
+                groups.push(Vec::new());
+            }
+            groups[id as usize].push(product_id.to_string());
+        }
+        groups.sort();
+        groups
+    }
+
+    #[test]
+    fn test_matches_same_products_across_sources() {
+        let matcher = ProductMatcher::new().with_category_aliases(HashMap::from([(
+            "Vegetables".to_string(),
+            "Fruits & Vegetables".to_string(),
+        )]));
+        let output = matcher.match_products(&merged()).unwrap();
+
+        // The two breads share a block but not a name
+        assert_eq!(groups(&output.matches), vec![vec!["k1", "n1"], vec!["k2", "n2"]]);
+        assert!(output.ambiguous.is_empty());
+        let scores = output.matches.column(MATCH_SCORE_FIELD).unwrap().f64().unwrap();
+        assert!(scores.into_no_null_iter().all(|score| score >= DEFAULT_MATCH_THRESHOLD));
+
+        // Without the alias the potatoes are in different blocks
+        let unaliased = ProductMatcher::new().match_products(&merged()).unwrap();
+        assert_eq!(groups(&unaliased.matches), vec![vec!["k2", "n2"]]);
+    }
+
+    #[test]
+    fn test_refuses_dissimilar_or_different_sized_products() {
+        let output = ProductMatcher::new().with_threshold(0.9).match_products(&merged()).unwrap();
+        assert_eq!(output.matches.height(), 0);
+
+        let mut resized = merged();
+        let quantities = Series::new(QUANTITY_VALUE_FIELD.into(), [3000.0, 475.0, 1.0, 3000.0, 950.0, 1.0]);
+        resized.with_column(quantities).unwrap();
+        let output = ProductMatcher::new().match_products(&resized).unwrap();
+        assert!(groups(&output.matches).iter().all(|group| group[0] != "k2"));
+    }
+
+    #[test]
+    fn test_reports_ambiguous_matches() {
+        let df = df!(
+            SOURCE_COLUMN => ["krave_mart", "naheed", "naheed"],
+            "product_id" => ["k1", "n1", "n2"],
+            "name" => ["olpers milk", "olpers milk", "olpers milk"],
+            "category" => ["dairy", "dairy", "dairy"],
+            QUANTITY_VALUE_FIELD => [1000.0, 1000.0, 1000.0],
+            QUANTITY_UNIT_FIELD => ["ml", "ml", "ml"]
+        )
+        .unwrap();
+
+        let output = ProductMatcher::new().match_products(&df).unwrap();
+        assert_eq!(output.matches.height(), 2);
+        assert_eq!(output.ambiguous.len(), 1);
+        assert_eq!(output.ambiguous[0].product_id, "k1");
+        assert_eq!(output.ambiguous[0].candidates.len(), 2);
+    }
+}
@@ -0,0 +1,50 @@
+use anyhow::Result;
+use polars::prelude::*;
+
+/// Comma-separated problems found while normalizing a row, null when none
+pub const QUALITY_FLAGS_FIELD: &str = "quality_flags";
+
+/// A cost_price or mrp that was present but not a number, or was negative
+pub const PRICE_UNPARSEABLE_FLAG: &str = "PRICE_UNPARSEABLE";
+
+/// cost_price and mrp were swapped back, see `SwappedPriceAction::Swap`
+pub const PRICE_SWAPPED_FLAG: &str = "PRICE_SWAPPED";
+
+/// cost_price was left above mrp, see `SwappedPriceAction::Flag`
+pub const PRICE_SUSPECT_FLAG: &str = "PRICE_SUSPECT";
+
+/// A discount outside 0–100 that was recomputed, nulled or clamped
+pub const DISCOUNT_OUT_OF_RANGE_FLAG: &str = "DISCOUNT_OUT_OF_RANGE";
+
+/// Neither the source nor the product name gave a unit
+pub const UNIT_MISSING_FLAG: &str = "UNIT_MISSING";
+
+/// A unit that could not be parsed into a quantity
+pub const UNIT_UNPARSEABLE_FLAG: &str = "UNIT_UNPARSEABLE";
+
+/// A quantity of zero, so no price per unit
+pub const ZERO_QUANTITY_FLAG: &str = "ZERO_QUANTITY";
+
+/// A price per unit above the configured maximum, dropped as a parse error
+pub const PRICE_PER_UNIT_ABOVE_MAX_FLAG: &str = "PRICE_PER_UNIT_ABOVE_MAX";
+
+/// Append `flags` to the rows' `quality_flags`, comma-separated, skipping
+/// flags a row already has so normalizing clean data again adds nothing
+pub fn add_quality_flags(df: &mut DataFrame, flags: &[Option<&str>]) -> Result<()> {
+    let existing: Vec<Option<String>> = match df.column(QUALITY_FLAGS_FIELD) {
+        Ok(column) => column.str()?.into_iter().map(|value| value.map(str::to_string)).collect(),
+        Err(_) => vec![None; df.height()],
+    };
+
+    let merged: Vec<Option<String>> = existing
+        .into_iter()
+        .zip(flags)
+        .map(|(existing, flag)| match (existing, flag) {
+            (Some(existing), Some(flag)) if existing.split(',').any(|present| present == *flag) => Some(existing),
+            (Some(existing), Some(flag)) => Some(format!("{},{}", existing, flag)),
+            (existing, flag) => existing.or(flag.map(str::to_string)),
+        })
+        .collect();
+    df.with_column(Series::new(QUALITY_FLAGS_FIELD.into(), merged))?;
+    Ok(())
+}
@@ -0,0 +1,141 @@
+use polars::prelude::*;
+use serde::Serialize;
+use std::fmt;
+
+/// Completeness and value statistics for one column
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ColumnQuality {
+    pub column: String,
+    pub null_count: usize,
+    /// Share of non-null values, in percent (0 for an empty frame)
+    pub completeness: f64,
+    /// Distinct non-null values
+    pub distinct_count: usize,
+    /// Only set for numeric columns
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub mean: Option<f64>,
+}
+
+/// Per-column data quality of a processed DataFrame
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QualityReport {
+    pub rows: usize,
+    pub columns: Vec<ColumnQuality>,
+}
+
+impl QualityReport {
+    pub fn column(&self, name: &str) -> Option<&ColumnQuality> {
+        self.columns.iter().find(|column| column.column == name)
+    }
+}
+
+/// Null counts, completeness and distinct counts for every column of a
+/// normalized DataFrame, plus min / max / mean for the numeric ones
+pub fn quality_report(df: &DataFrame) -> QualityReport {
+    let rows = df.height();
+    let columns = df
+        .get_columns()
+        .iter()
+        .map(|column| column_quality(column, rows))
+        .collect();
+
+    QualityReport { rows, columns }
+}
+
+fn column_quality(column: &Column, rows: usize) -> ColumnQuality {
+    let series = column.as_materialized_series();
+    let null_count = series.null_count();
+    let completeness = if rows == 0 {
+        0.0
+    } else {
+        (rows - null_count) as f64 / rows as f64 * 100.0
+    };
+    let distinct_count = series.drop_nulls().n_unique().unwrap_or(0);
+
+    let numeric = series
+        .dtype()
+        .is_primitive_numeric()
+        .then(|| series.cast(&DataType::Float64).ok())
+        .flatten();
+    let stat = |f: fn(&Float64Chunked) -> Option<f64>| {
+        numeric.as_ref().and_then(|s| s.f64().ok()).and_then(f)
+    };
+
+    ColumnQuality {
+        column: column.name().to_string(),
+        null_count,
+        completeness,
+        distinct_count,
+        min: stat(|values| values.min()),
+        max: stat(|values| values.max()),
+        mean: stat(|values| values.mean()),
+    }
+}
+
+impl fmt::Display for QualityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} rows", self.rows)?;
+        for column in &self.columns {
+            write!(
+                f,
+                "  {:<20} {:>5.1}% complete ({} null, {} distinct)",
+                column.column, column.completeness, column.null_count, column.distinct_count
+            )?;
+            if let (Some(min), Some(max), Some(mean)) = (column.min, column.max, column.mean) {
+                write!(f, " min={:.2} max={:.2} mean={:.2}", min, max, mean)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quality_report_columns() {
+        let df = df!(
+            "name" => ["milk", "eggs", "milk", "bread"],
+            "cost_price" => [Some(280.0), None, Some(300.0), Some(100.0)],
+            "category" => [Some("dairy"), Some("dairy"), None, None]
+        )
+        .unwrap();
+
+        let report = quality_report(&df);
+        assert_eq!(report.rows, 4);
+        assert_eq!(report.columns.len(), 3);
+
+        let name = report.column("name").unwrap();
+        assert_eq!((name.null_count, name.distinct_count), (0, 3));
+        assert_eq!(name.completeness, 100.0);
+        assert_eq!(name.min, None);
+
+        let price = report.column("cost_price").unwrap();
+        assert_eq!((price.null_count, price.distinct_count), (1, 3));
+        assert_eq!(price.completeness, 75.0);
+        assert_eq!((price.min, price.max), (Some(100.0), Some(300.0)));
+        assert!((price.mean.unwrap() - 226.666).abs() < 0.01);
+
+        let category = report.column("category").unwrap();
+        assert_eq!((category.completeness, category.distinct_count), (50.0, 1));
+
+        let printed = report.to_string();
+        assert!(printed.contains("cost_price"));
+        assert!(printed.contains("75.0% complete"));
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["columns"][1]["max"], 300.0);
+        assert!(json["columns"][0]["mean"].is_null());
+    }
+
+    #[test]
+    fn test_quality_report_of_empty_frame() {
+        let df = df!("mrp" => Vec::<f64>::new()).unwrap();
+        let report = quality_report(&df);
+        assert_eq!(report.rows, 0);
+        assert_eq!(report.columns[0].completeness, 0.0);
+        assert_eq!(report.columns[0].mean, None);
+    }
+}
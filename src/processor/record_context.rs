@@ -0,0 +1,151 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::Value;
+
+use super::json_flattener::PROVENANCE_FIELDS;
+
+/// Where and when products were fetched. Stamped onto the raw products as
+/// `_source`, `_source_category_key` and `_fetched_at`, which `JsonFlattener`
+/// turns into the `source`, `source_category_key` and `fetched_at` columns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordContext {
+    pub source_name: String,
+    pub category_key: Option<String>,
+    pub fetched_at: Option<DateTime<Utc>>,
+}
+
+impl RecordContext {
+    pub fn new(source_name: impl Into<String>) -> Self {
+        RecordContext {
+            source_name: source_name.into(),
+            category_key: None,
+            fetched_at: None,
+        }
+    }
+
+    /// The same context for products fetched under one category
+    pub fn for_category(&self, category_key: &str) -> Self {
+        RecordContext {
+            category_key: Some(category_key.to_string()),
+            ..self.clone()
+        }
+    }
+
+    pub fn with_fetched_at(mut self, fetched_at: DateTime<Utc>) -> Self {
+        self.fetched_at = Some(fetched_at);
+        self
+    }
+
+    /// Raw field name and value for each provenance column this context knows
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        let values = [
+            Some(self.source_name.clone()),
+            self.category_key.clone(),
+            self.fetched_at
+                .map(|at| at.to_rfc3339_opts(SecondsFormat::Secs, true)),
+        ];
+        PROVENANCE_FIELDS
+            .iter()
+            .zip(values)
+            .filter_map(|((raw_field, _), value)| Some((*raw_field, value?)))
+            .collect()
+    }
+
+    /// Set the provenance fields on every product, replacing existing ones
+    pub fn stamp(&self, products: &mut [Value]) {
+        self.apply(products, true);
+    }
+
+    /// Set only the provenance fields a product does not carry yet, e.g. for
+    /// raw dumps stored before the fetchers stamped them
+    pub fn fill_missing(&self, products: &mut [Value]) {
+        self.apply(products, false);
+    }
+
+    fn apply(&self, products: &mut [Value], overwrite: bool) {
+        let fields = self.fields();
+        for product in products {
+            let Some(object) = product.as_object_mut() else {
+                continue;
+            };
+            for (raw_field, value) in &fields {
+                if overwrite || !object.contains_key(*raw_field) {
+                    object.insert(raw_field.to_string(), Value::String(value.clone()));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Timelike};
+    use serde_json::json;
+
+    #[test]
+    fn test_stamp_and_fill_missing() {
+        let context = RecordContext::new("krave_mart").for_category("fruits_veg");
+        let mut products = vec![json!({"product_id": 1}), json!("not a product")];
+        context.stamp(&mut products);
+        assert_eq!(products[0]["_source"], "krave_mart");
+        assert_eq!(products[0]["_source_category_key"], "fruits_veg");
+        assert!(products[0].get("_fetched_at").is_none());
+        assert_eq!(products[1], "not a product");
+
+        let fetched_at = Utc.with_ymd_and_hms(2025, 9, 15, 10, 15, 0).unwrap();
+        RecordContext::new("other")
+            .with_fetched_at(fetched_at)
+            .fill_missing(&mut products);
+        assert_eq!(products[0]["_source"], "krave_mart");
+        assert_eq!(products[0]["_fetched_at"], "2025-09-15T10:15:00Z");
+    }
+
+    #[test]
+    fn test_provenance_columns_per_source() {
+        use crate::processor::{FieldClassifier, JsonFlattener};
+
+        let fetched_at = Utc.with_ymd_and_hms(2025, 9, 15, 10, 15, 0).unwrap();
+        let flattener = JsonFlattener::new();
+        let classifier = FieldClassifier::new();
+
+        let mut frames = Vec::new();
+        for (source, category, hour) in [("krave_mart", "dairy", 10), ("dealcart", "bakery", 11)] {
+            let mut products: Vec<Value> = (1..=3)
+                .map(|i| json!({"product_id": i, "name": format!("item {}", i), "price": 100}))
+                .collect();
+            RecordContext::new(source)
+                .for_category(category)
+                .with_fetched_at(fetched_at.with_hour(hour).unwrap())
+                .stamp(&mut products);
+
+            let mut df = flattener.flatten_to_dataframe(&products).unwrap().dataframe;
+            classifier.map_to_canonical_schema(&mut df).unwrap();
+            frames.push(df);
+        }
+
+        let column_values = |df: &polars::prelude::DataFrame, column: &str| -> Vec<String> {
+            let mut values: Vec<String> = df
+                .column(column)
+                .unwrap()
+                .str()
+                .unwrap()
+                .into_no_null_iter()
+                .map(str::to_string)
+                .collect();
+            assert_eq!(values.len(), df.height(), "{} has nulls", column);
+            values.dedup();
+            values
+        };
+
+        for (_, column) in PROVENANCE_FIELDS {
+            // Constant within a source, different across sources
+            let first = column_values(&frames[0], column);
+            let second = column_values(&frames[1], column);
+            assert_eq!(first.len(), 1, "{} varies within a source", column);
+            assert_eq!(second.len(), 1, "{} varies within a source", column);
+            assert_ne!(first, second, "{} is the same for both sources", column);
+        }
+        assert_eq!(column_values(&frames[0], "fetched_at"), vec!["2025-09-15T10:15:00Z"]);
+        assert_eq!(column_values(&frames[1], "source_category_key"), vec!["bakery"]);
+    }
+}
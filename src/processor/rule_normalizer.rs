@@ -1,11 +1,416 @@
-use anyhow::Result;
+use anyhow::{Context, Result, anyhow};
 use polars::prelude::*;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use std::str::FromStr;
+use tracing::{info, warn};
 
-pub struct RuleNormalizer;
+use super::json_flattener::NumberFormat;
+use super::pipeline::Processor;
+use super::quality_flags::{
+    DISCOUNT_OUT_OF_RANGE_FLAG, PRICE_PER_UNIT_ABOVE_MAX_FLAG, PRICE_SUSPECT_FLAG, PRICE_SWAPPED_FLAG,
+    PRICE_UNPARSEABLE_FLAG, UNIT_MISSING_FLAG, UNIT_UNPARSEABLE_FLAG, ZERO_QUANTITY_FLAG, add_quality_flags,
+};
+
+/// Brands recognised in product names when no list is configured
+pub const DEFAULT_KNOWN_BRANDS: [&str; 12] = [
+    "BrightFarms",
+    "Nestle",
+    "Unilever",
+    "P&G",
+    "Colgate",
+    "Johnson",
+    "L'Oreal",
+    "Olper's",
+    "Shan",
+    "National",
+    "K&N's",
+    "Tapal",
+];
+
+/// Capitalized first words that describe the product rather than name its brand
+const NON_BRAND_WORDS: [&str; 24] = [
+    "fresh", "organic", "premium", "pure", "natural", "special", "new", "imported",
+    "local", "desi", "large", "small", "medium", "big", "mini", "classic",
+    "original", "whole", "red", "green", "white", "black", "brown", "golden",
+];
+
+/// Words that make a parenthetical a quantity, e.g. "(800gm)" or "(Pack of 6)"
+const UNIT_WORDS: [&str; 26] = [
+    "g", "gm", "gms", "gram", "grams", "kg", "kgs", "kilogram", "kilograms",
+    "ml", "l", "ltr", "litre", "litres", "liter", "liters", "milliliter", "milliliters",
+    "piece", "pieces", "pcs", "pack", "packs", "bundle", "bundles", "dozen",
+];
+
+/// Values that stand for "no value" in text columns, compared ignoring case
+/// and surrounding whitespace
+pub const DEFAULT_NULL_PLACEHOLDERS: [&str; 3] = ["N/A", "", "null"];
+
+/// Currency markers removed from price strings before parsing when none are
+/// configured, on top of the tokens of the supported currencies
+pub const DEFAULT_CURRENCY_MARKERS: [&str; 6] = ["Rs.", "Rs", "PKR", "₨", "$", "€"];
+
+/// Columns whose placeholders are turned into nulls when none are configured
+pub const DEFAULT_NULL_COLUMNS: [&str; 1] = ["units_of_mass"];
+
+/// The product name as the source wrote it, before `name` is cleaned
+pub const NAME_ORIGINAL_FIELD: &str = "name_original";
+
+/// Amount parsed from `units_of_mass`, in `QUANTITY_UNIT_FIELD` units
+pub const QUANTITY_VALUE_FIELD: &str = "quantity_value";
+
+/// Canonical unit of `QUANTITY_VALUE_FIELD`, see `QuantityUnit`
+pub const QUANTITY_UNIT_FIELD: &str = "quantity_unit";
+
+/// `cost_price` per 100 g, per 100 ml or per piece, see `PRICE_PER_UNIT_BASIS_FIELD`
+pub const PRICE_PER_UNIT_FIELD: &str = "price_per_unit";
+
+/// What `PRICE_PER_UNIT_FIELD` is per: "100g", "100ml" or "piece"
+pub const PRICE_PER_UNIT_BASIS_FIELD: &str = "price_per_unit_basis";
+
+/// Unrounded `cost_price` per gram or per millilitre, for weights and volumes only
+pub const PRICE_PER_BASE_UNIT_FIELD: &str = "price_per_base_unit";
+
+/// What `PRICE_PER_BASE_UNIT_FIELD` is per: "g" or "ml"
+pub const BASE_UNIT_FIELD: &str = "base_unit";
+
+/// How far cost_price may exceed mrp before the pair is treated as swapped,
+/// so rounding differences between the two are left alone
+const PRICE_SWAP_EPSILON: f64 = 0.01;
+
+/// What to do with rows whose cost_price is above their mrp
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SwappedPriceAction {
+    /// Swap the two prices back and flag the row `PRICE_SWAPPED`
+    #[default]
+    Swap,
+    /// Keep the prices, null the discount and flag the row `PRICE_SUSPECT`
+    Flag,
+}
+
+/// Discounts are percentages; values outside this range are parse errors,
+/// e.g. a price that ended up in the discount field
+const DISCOUNT_RANGE: std::ops::RangeInclusive<f64> = 0.0..=100.0;
+
+/// What to do with discounts outside 0–100 that can't be recomputed from prices
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutOfRangeDiscountAction {
+    /// Drop the discount
+    #[default]
+    Null,
+    /// Clamp it to 0 or 100
+    Clamp,
+}
+
+/// A source-specific cleanup of one text column, applied after the built-in
+/// steps, e.g. `{ column = "sku", op = "regex_replace", pattern = "^BNDL", replacement = "" }`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnTransform {
+    pub column: String,
+    #[serde(flatten)]
+    pub op: TransformOp,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum TransformOp {
+    Trim,
+    Lowercase,
+    Uppercase,
+    /// Replace every match; the replacement may refer to groups as `$1`
+    RegexReplace {
+        #[serde(with = "serde_regex")]
+        pattern: Regex,
+        #[serde(default)]
+        replacement: String,
+    },
+    StripPrefix { prefix: String },
+    StripSuffix { suffix: String },
+}
+
+impl TransformOp {
+    fn apply(&self, value: &str) -> String {
+        match self {
+            TransformOp::Trim => value.trim().to_string(),
+            TransformOp::Lowercase => value.to_lowercase(),
+            TransformOp::Uppercase => value.to_uppercase(),
+            TransformOp::RegexReplace { pattern, replacement } => {
+                pattern.replace_all(value, replacement.as_str()).into_owned()
+            }
+            TransformOp::StripPrefix { prefix } => value.strip_prefix(prefix.as_str()).unwrap_or(value).to_string(),
+            TransformOp::StripSuffix { suffix } => value.strip_suffix(suffix.as_str()).unwrap_or(value).to_string(),
+        }
+    }
+}
+
+/// Regex patterns kept as their source text in config files
+mod serde_regex {
+    use regex::Regex;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(pattern: &Regex, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(pattern.as_str())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Regex, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        Regex::new(&pattern).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Prices per unit above this are treated as parse errors when none is configured
+pub const DEFAULT_MAX_PRICE_PER_UNIT: f64 = 100_000.0;
+
+/// Unit patterns used when no rule file is loaded, most specific first. Each
+/// captures the unit text in group 1.
+const DEFAULT_UNIT_PATTERNS: [&str; 5] = [
+    // Parenthetical weight/volume units: (800gm), (1 Kg), (500ml), etc.
+    r"(?i)\s*[-–]?\s*\(\s*(\d+(?:\.\d+)?\s*(?:gm|g|kg|ml|l|gram|grams|kilogram|kilograms|liter|liters|milliliter|milliliters)(?:\s*-\s*\d+(?:\.\d+)?\s*(?:gm|g|kg|ml|l|gram|grams|kilogram|kilograms|liter|liters|milliliter|milliliters))?)\s*\)",
+    // Parenthetical count/pack units: (pack of 6), (1 piece), (1 bundles), etc.
+    r"(?i)\s*[-–]?\s*\(\s*(pack\s+of\s+\d+|\d+\s+(?:piece|pieces|bundle|bundles|dozen|half\s+dozen))\s*\)",
+    // Dash-separated count units: - 1 piece, - 1 bundles, - half dozen, etc.
+    r"(?i)\s*[-–]\s*(pack\s+of\s+\d+|\d+\s+(?:piece|pieces|bundle|bundles|dozen)|half\s+dozen)\s*",
+    // Dash-separated weight/volume units: - 800gm, - 1 kg, etc.
+    r"(?i)\s*[-–]\s*(\d+(?:\.\d+)?\s*(?:gm|g|kg|ml|l|gram|grams|kilogram|kilograms|liter|liters|milliliter|milliliters))\s*",
+    // Space-separated units at end: 3 Kg, 1 kg, etc.
+    r"(?i)\s+(\d+(?:\.\d+)?\s*(?:gm|g|kg|ml|l|gram|grams|kilogram|kilograms|liter|liters|milliliter|milliliters))\s*$",
+];
+
+/// Promotional suffix removed from names before units are looked for,
+/// e.g. "| Ramzan Offer"
+const DEFAULT_PROMO_PATTERN: &str = r"\s*\|\s*.*$";
+
+/// Patterns and words `RuleNormalizer` cleans product names with. The
+/// built-ins are tuned for Pakistani grocery naming; a rule file (see
+/// `src/configs/normalizer_rules.toml`) replaces any of them.
+#[derive(Debug, Clone)]
+pub struct NameRules {
+    /// Tried in order; the first match is cut from the name and its group 1
+    /// becomes `units_of_mass`
+    unit_patterns: Vec<Regex>,
+    promo_pattern: Regex,
+    /// Lowercased words that make a parenthetical a quantity rather than a
+    /// description to strip
+    unit_words: Vec<String>,
+}
+
+/// `normalizer_rules.toml` as written; keys left out keep the built-ins
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NameRulesFile {
+    unit_patterns: Option<Vec<toml::Spanned<String>>>,
+    promo_pattern: Option<toml::Spanned<String>>,
+    unit_words: Option<Vec<String>>,
+}
+
+impl Default for NameRules {
+    fn default() -> Self {
+        NameRules {
+            unit_patterns: DEFAULT_UNIT_PATTERNS
+                .iter()
+                .map(|pattern| Regex::new(pattern).expect("built-in unit pattern"))
+                .collect(),
+            promo_pattern: Regex::new(DEFAULT_PROMO_PATTERN).expect("built-in promo pattern"),
+            unit_words: UNIT_WORDS.iter().map(|word| word.to_string()).collect(),
+        }
+    }
+}
+
+impl NameRules {
+    pub fn from_file(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read normalizer rules file: {}", path))?;
+        Self::from_toml(&content, path)
+    }
+
+    /// Parse and compile a rule file. Invalid patterns are reported with the
+    /// line they are on; `origin` names the file in errors.
+    pub fn from_toml(content: &str, origin: &str) -> Result<Self> {
+        let file: NameRulesFile = toml::from_str(content)
+            .with_context(|| format!("Failed to parse normalizer rules file: {}", origin))?;
+        let line_of = |pattern: &toml::Spanned<String>| content[..pattern.span().start].matches('\n').count() + 1;
+        let compile = |pattern: &toml::Spanned<String>| -> Result<Regex> {
+            Regex::new(pattern.get_ref()).map_err(|e| {
+                anyhow!("{}:{}: invalid pattern '{}': {}", origin, line_of(pattern), pattern.get_ref(), e)
+            })
+        };
+
+        let mut rules = NameRules::default();
+        if let Some(patterns) = &file.unit_patterns {
+            rules.unit_patterns = Vec::with_capacity(patterns.len());
+            for pattern in patterns {
+                let regex = compile(pattern)?;
+                if regex.captures_len() < 2 {
+                    return Err(anyhow!(
+                        "{}:{}: unit pattern '{}' must capture the unit in a group",
+                        origin,
+                        line_of(pattern),
+                        pattern.get_ref()
+                    ));
+                }
+                rules.unit_patterns.push(regex);
+            }
+        }
+        if let Some(pattern) = &file.promo_pattern {
+            rules.promo_pattern = compile(pattern)?;
+        }
+        if let Some(words) = file.unit_words {
+            rules.unit_words = words
+                .iter()
+                .map(|word| word.trim().to_lowercase())
+                .filter(|word| !word.is_empty())
+                .collect();
+        }
+        Ok(rules)
+    }
+}
+
+pub struct RuleNormalizer {
+    /// Brands looked for in product names, in their display spelling
+    known_brands: Vec<String>,
+    /// Lowercased parenthetical terms always stripped from names
+    name_descriptors: Vec<String>,
+    /// Unit and promo patterns names are cleaned with
+    rules: NameRules,
+    /// Text columns whose placeholder values become nulls
+    null_columns: Vec<String>,
+    /// Lowercased, trimmed placeholder values
+    null_placeholders: Vec<String>,
+    /// Larger prices per unit are dropped and flagged
+    max_price_per_unit: f64,
+    /// Stripped from price strings, longest first so "Rs." goes before "Rs"
+    currency_markers: Vec<String>,
+    /// Separators price strings are written with
+    number_format: NumberFormat,
+    /// Lowercase cleaned names; off keeps the source's casing
+    lowercase_names: bool,
+    swapped_prices: SwappedPriceAction,
+    out_of_range_discounts: OutOfRangeDiscountAction,
+    /// Source-specific cleanups, in order, after the built-in steps
+    transforms: Vec<ColumnTransform>,
+}
+
+impl Default for RuleNormalizer {
+    fn default() -> Self {
+        RuleNormalizer {
+            known_brands: DEFAULT_KNOWN_BRANDS.iter().map(|brand| brand.to_string()).collect(),
+            name_descriptors: Vec::new(),
+            rules: NameRules::default(),
+            null_columns: DEFAULT_NULL_COLUMNS.iter().map(|column| column.to_string()).collect(),
+            null_placeholders: DEFAULT_NULL_PLACEHOLDERS.iter().map(|value| value.to_lowercase()).collect(),
+            max_price_per_unit: DEFAULT_MAX_PRICE_PER_UNIT,
+            currency_markers: Vec::new(),
+            number_format: NumberFormat::default(),
+            lowercase_names: true,
+            swapped_prices: SwappedPriceAction::default(),
+            out_of_range_discounts: OutOfRangeDiscountAction::default(),
+            transforms: Vec::new(),
+        }
+        .with_currency_markers(DEFAULT_CURRENCY_MARKERS.iter().map(|marker| marker.to_string()).collect())
+    }
+}
 
 impl RuleNormalizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Clean names with the unit and promo patterns of a rule file instead
+    /// of the built-ins
+    pub fn from_config(path: &str) -> Result<Self> {
+        Ok(Self::new().with_rules(NameRules::from_file(path)?))
+    }
+
+    pub fn with_rules(mut self, rules: NameRules) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Look for these brands in product names instead of `DEFAULT_KNOWN_BRANDS`
+    pub fn with_known_brands(mut self, known_brands: Vec<String>) -> Self {
+        self.known_brands = known_brands;
+        self
+    }
+
+    /// Also strip parentheticals matching one of these terms, e.g. "Eid Special
+    /// 2025", which the no-digit rule would keep
+    pub fn with_name_descriptors(mut self, descriptors: Vec<String>) -> Self {
+        self.name_descriptors = descriptors
+            .iter()
+            .map(|term| term.trim().to_lowercase())
+            .filter(|term| !term.is_empty())
+            .collect();
+        self
+    }
+
+    /// Turn placeholders into nulls in these columns instead of `DEFAULT_NULL_COLUMNS`
+    pub fn with_null_columns(mut self, columns: Vec<String>) -> Self {
+        self.null_columns = columns;
+        self
+    }
+
+    /// Treat these values as missing instead of `DEFAULT_NULL_PLACEHOLDERS`
+    pub fn with_null_placeholders(mut self, placeholders: Vec<String>) -> Self {
+        self.null_placeholders = placeholders
+            .iter()
+            .map(|value| value.trim().to_lowercase())
+            .collect();
+        self
+    }
+
+    /// Drop and flag prices per unit above `max` instead of `DEFAULT_MAX_PRICE_PER_UNIT`
+    pub fn with_max_price_per_unit(mut self, max: f64) -> Self {
+        self.max_price_per_unit = max;
+        self
+    }
+
+    /// Strip these markers from prices instead of `DEFAULT_CURRENCY_MARKERS`
+    pub fn with_currency_markers(mut self, markers: Vec<String>) -> Self {
+        let mut markers: Vec<String> = markers
+            .iter()
+            .map(|marker| marker.trim().to_string())
+            .filter(|marker| !marker.is_empty())
+            .collect();
+        markers.sort_by_key(|marker| std::cmp::Reverse(marker.chars().count()));
+        self.currency_markers = markers;
+        self
+    }
+
+    /// Parse price strings written with these separators, e.g. "1.234,50"
+    /// for `NumberFormat::Eu`
+    pub fn with_number_format(mut self, number_format: NumberFormat) -> Self {
+        self.number_format = number_format;
+        self
+    }
+
+    /// Keep the casing of cleaned names, e.g. for title-case display, instead
+    /// of lowercasing them
+    pub fn with_lowercase_names(mut self, lowercase: bool) -> Self {
+        self.lowercase_names = lowercase;
+        self
+    }
+
+    /// Handle cost_price above mrp this way instead of swapping the prices
+    pub fn with_swapped_prices(mut self, action: SwappedPriceAction) -> Self {
+        self.swapped_prices = action;
+        self
+    }
+
+    /// Clamp discounts outside 0–100 that have no prices to recompute them
+    /// from, instead of dropping them
+    pub fn with_out_of_range_discounts(mut self, action: OutOfRangeDiscountAction) -> Self {
+        self.out_of_range_discounts = action;
+        self
+    }
+
+    /// Apply these column transforms, in order, after the built-in steps
+    pub fn with_transforms(mut self, transforms: Vec<ColumnTransform>) -> Self {
+        self.transforms = transforms;
+        self
+    }
+
     pub fn normalize_dataframe(&self, df: &mut DataFrame) -> Result<()> {
         // Normalize price columns
         self.normalize_price_column(df, "cost_price")?;
@@ -13,6 +418,7 @@ impl RuleNormalizer {
 
         // Normalize name and extract units
         self.normalize_name_and_extract_units(df)?;
+        self.normalize_quantities(df)?;
 
         // Normalize other string columns
         if df.column("category").is_ok() {
@@ -24,48 +430,111 @@ impl RuleNormalizer {
             self.normalize_discount_column(df, "discount")?;
         }
 
+        // Before discounts are derived from the two prices
+        self.repair_swapped_prices(df)?;
+
         // Calculate missing discounts from price difference
         self.calculate_missing_discounts(df)?;
 
+        // Comparable prices across products and sources
+        self.compute_price_per_unit(df)?;
+
+        // Last, as the steps above still write "N/A" for units they did not find
+        for column in &self.null_columns {
+            self.null_out_placeholders(df, column)?;
+        }
+
+        for transform in &self.transforms {
+            self.apply_transform(df, transform)?;
+        }
+
+        Ok(())
+    }
+
+    /// Transform the values of a text column, leaving nulls alone. Columns the
+    /// frame lacks are skipped, as not every category has every column.
+    fn apply_transform(&self, df: &mut DataFrame, transform: &ColumnTransform) -> Result<()> {
+        let Ok(series) = df.column(&transform.column).cloned() else {
+            return Ok(());
+        };
+        // An all-null column may come through untyped; nothing to transform
+        if series.dtype() == &DataType::Null {
+            return Ok(());
+        }
+        if series.dtype() != &DataType::String {
+            warn!("Skipping {:?} on {}: not a text column ({})", transform.op, transform.column, series.dtype());
+            return Ok(());
+        }
+
+        let transformed: Vec<Option<String>> = series
+            .str()?
+            .into_iter()
+            .map(|value| value.map(|value| transform.op.apply(value)))
+            .collect();
+        df.with_column(Series::new(transform.column.as_str().into(), transformed))?;
+        Ok(())
+    }
+
+    /// Replace placeholder values of a text column with nulls, so they count
+    /// as missing in completeness metrics and group-bys
+    fn null_out_placeholders(&self, df: &mut DataFrame, col_name: &str) -> Result<()> {
+        let Ok(series) = df.column(col_name).cloned() else {
+            return Ok(());
+        };
+        if series.dtype() != &DataType::String {
+            return Ok(());
+        }
+
+        let cleaned: Vec<Option<String>> = series
+            .str()?
+            .into_iter()
+            .map(|value| {
+                value
+                    .filter(|value| !self.null_placeholders.contains(&value.trim().to_lowercase()))
+                    .map(str::to_string)
+            })
+            .collect();
+        df.with_column(Series::new(col_name.into(), cleaned))?;
         Ok(())
     }
 
     fn normalize_name_and_extract_units(&self, df: &mut DataFrame) -> Result<()> {
-        let name_series = df.column("name")?.str()?;
+        // Names are cleaned from the source's spelling, kept in `name_original`
+        // the first time through so later passes start from it again
+        let name_series = match df.column(NAME_ORIGINAL_FIELD) {
+            Ok(original) if original.dtype() == &DataType::String => original.str()?.clone(),
+            _ => {
+                let original = df.column("name")?.str()?.clone().with_name(NAME_ORIGINAL_FIELD.into());
+                let position = df.get_column_index("name").map_or(df.width(), |index| index + 1);
+                df.insert_column(position, original.clone().into_series())?;
+                original
+            }
+        };
+        let name_series = &name_series;
+        // Units already known, e.g. from the source's unit field or an earlier
+        // normalization pass whose names no longer carry them
+        let known_units = df
+            .column("units_of_mass")
+            .ok()
+            .and_then(|column| column.str().ok());
 
         let mut units = Vec::with_capacity(name_series.len());
         let mut cleaned_names = Vec::with_capacity(name_series.len());
+        let mut name_brands = Vec::with_capacity(name_series.len());
 
-        // Enhanced regex patterns for better unit extraction (order matters - most specific first)
-        let unit_patterns = vec![
-            // Parenthetical weight/volume units: (800gm), (1 Kg), (500ml), etc.
-            Regex::new(r"(?i)\s*[-–]?\s*\(\s*(\d+(?:\.\d+)?\s*(?:gm|g|kg|ml|l|gram|grams|kilogram|kilograms|liter|liters|milliliter|milliliters)(?:\s*-\s*\d+(?:\.\d+)?\s*(?:gm|g|kg|ml|l|gram|grams|kilogram|kilograms|liter|liters|milliliter|milliliters))?)\s*\)")?,
-            // Parenthetical count/pack units: (pack of 6), (1 piece), (1 bundles), etc.
-            Regex::new(r"(?i)\s*[-–]?\s*\(\s*(pack\s+of\s+\d+|\d+\s+(?:piece|pieces|bundle|bundles|dozen|half\s+dozen))\s*\)")?,
-            // Dash-separated count units: - 1 piece, - 1 bundles, - half dozen, etc.
-            Regex::new(r"(?i)\s*[-–]\s*(pack\s+of\s+\d+|\d+\s+(?:piece|pieces|bundle|bundles|dozen)|half\s+dozen)\s*")?,
-            // Dash-separated weight/volume units: - 800gm, - 1 kg, etc.
-            Regex::new(r"(?i)\s*[-–]\s*(\d+(?:\.\d+)?\s*(?:gm|g|kg|ml|l|gram|grams|kilogram|kilograms|liter|liters|milliliter|milliliters))\s*")?,
-            // Space-separated units at end: 3 Kg, 1 kg, etc.
-            Regex::new(r"(?i)\s+(\d+(?:\.\d+)?\s*(?:gm|g|kg|ml|l|gram|grams|kilogram|kilograms|liter|liters|milliliter|milliliters))\s*$")?,
-        ];
-
-        // Regex for removing promotional text and extra info
-        let promo_regex = Regex::new(r"\s*\|\s*.*$")?;
-
-        // Regex for cleaning parenthetical descriptions (like translations)
-        let description_regex = Regex::new(r"\s*\(\s*(aalu|pyaaz|kheera|sabzi|dal|atta|masala|spice|powder|paste|sauce|pickle|jam|honey|sugar|salt|tea|coffee|milk|butter|cheese|paneer|curd|yogurt|bread|biscuit|cake|sweet|namkeen|snack|chips|noodles|pasta|soup|juice|water|cold drink|soda|[a-zA-Z\s]+)\s*\)")?;
+        // Innermost parentheticals, checked one by one with `is_name_descriptor`
+        let parenthetical_regex = Regex::new(r"\s*\(([^()]*)\)")?;
 
-        for name_opt in name_series.into_iter() {
+        for (index, name_opt) in name_series.into_iter().enumerate() {
             if let Some(name) = name_opt {
                 let mut unit_found = "N/A".to_string();
                 let mut cleaned_name = name.to_string();
 
                 // Remove promotional text first
-                cleaned_name = promo_regex.replace(&cleaned_name, "").to_string();
+                cleaned_name = self.rules.promo_pattern.replace(&cleaned_name, "").to_string();
 
                 // Try to extract units using different patterns
-                for pattern in &unit_patterns {
+                for pattern in &self.rules.unit_patterns {
                     if let Some(captures) = pattern.captures(&cleaned_name) {
                         if let Some(unit_match) = captures.get(1) {
                             unit_found = unit_match.as_str().trim().to_string();
@@ -75,28 +544,40 @@ impl RuleNormalizer {
                     }
                 }
 
-                // Remove descriptive parentheses (like translations) - but only if no units were found in them
-                if unit_found == "N/A" {
-                    cleaned_name = description_regex.replace(&cleaned_name, "").to_string();
-                } else {
-                    // If units were found, only remove non-unit parentheses
-                    let non_unit_desc_regex = Regex::new(r"\s*\(\s*(aalu|pyaaz|kheera|sabzi|dal|atta|masala|spice|powder|paste|sauce|pickle|jam|honey|sugar|salt|tea|coffee|milk|butter|cheese|paneer|curd|yogurt|bread|biscuit|cake|sweet|namkeen|snack|chips|noodles|pasta|soup|juice|water|cold drink|soda|[a-zA-Z\s]*[a-zA-Z])\s*\)")?;
-                    cleaned_name = non_unit_desc_regex.replace(&cleaned_name, "").to_string();
+                if unit_found == "N/A"
+                    && let Some(known) = known_units
+                        .and_then(|known| known.get(index))
+                        .filter(|unit| !unit.is_empty() && *unit != "N/A")
+                {
+                    unit_found = known.to_string();
                 }
 
+                // Remove descriptive parentheses (like translations), keeping quantities
+                cleaned_name = parenthetical_regex
+                    .replace_all(&cleaned_name, |captures: &regex::Captures| {
+                        if self.is_name_descriptor(&captures[1]) {
+                            String::new()
+                        } else {
+                            captures[0].to_string()
+                        }
+                    })
+                    .to_string();
+
+                // Brands are read before lowercasing, which the first-word heuristic needs
+                name_brands.push(brand_from_name(&cleaned_name, &self.known_brands));
+
                 // Clean up extra spaces and normalize
-                cleaned_name = cleaned_name
-                    .trim()
-                    .split_whitespace()
-                    .collect::<Vec<_>>()
-                    .join(" ")
-                    .to_lowercase();
+                cleaned_name = cleaned_name.split_whitespace().collect::<Vec<_>>().join(" ");
+                if self.lowercase_names {
+                    cleaned_name = cleaned_name.to_lowercase();
+                }
 
                 units.push(unit_found);
-                cleaned_names.push(cleaned_name);
+                cleaned_names.push(Some(cleaned_name));
             } else {
                 units.push("N/A".to_string());
-                cleaned_names.push("".to_string());
+                cleaned_names.push(None);
+                name_brands.push(None);
             }
         }
 
@@ -105,37 +586,104 @@ impl RuleNormalizer {
 
         df.with_column(cleaned_names_series)?;
         df.with_column(units_series)?;
+        self.fill_missing_brands(df, name_brands)?;
 
         Ok(())
     }
 
+    /// Whether a parenthetical only describes the product, e.g. "Aalu" or
+    /// "Ramzan Offer": it names no unit and either has no digits or is one of
+    /// the configured descriptors. "800gm" or "Pack of 6" never are.
+    fn is_name_descriptor(&self, text: &str) -> bool {
+        let text = text.trim().to_lowercase();
+        let names_unit = text
+            .split(|c: char| !c.is_alphabetic())
+            .any(|word| self.rules.unit_words.iter().any(|unit| unit == word));
+        if names_unit {
+            return false;
+        }
+        !text.chars().any(|c| c.is_ascii_digit()) || self.name_descriptors.contains(&text)
+    }
+
+    /// Use the brand found in the name where the source reported none
+    fn fill_missing_brands(&self, df: &mut DataFrame, name_brands: Vec<Option<String>>) -> Result<()> {
+        let brands: Vec<Option<String>> = match df.column("brand") {
+            Ok(column) => {
+                let column = column.cast(&DataType::String)?;
+                column
+                    .str()?
+                    .into_iter()
+                    .zip(name_brands)
+                    .map(|(reported, from_name)| {
+                        reported
+                            .map(str::trim)
+                            .filter(|brand| !brand.is_empty())
+                            .map(str::to_string)
+                            .or(from_name)
+                    })
+                    .collect()
+            }
+            Err(_) => name_brands,
+        };
+
+        df.with_column(Series::new("brand".into(), brands))?;
+        Ok(())
+    }
+
     fn normalize_price_column(&self, df: &mut DataFrame, col_name: &str) -> Result<()> {
         if let Ok(series) = df.column(col_name).cloned() {
-            let normalized: Vec<Option<f64>> = series
-                .str()
-                .unwrap()
-                .into_no_null_iter()
-                .map(|s| {
-                    let cleaned = s.replace("$", "").replace(",", "");
-                    let trimmed = cleaned.trim();
-                    f64::from_str(trimmed).ok()
-                })
+            // The flattener already emits Float64; older string columns are parsed
+            if series.dtype().is_primitive_numeric() {
+                df.with_column(series.cast(&DataType::Float64)?)?;
+                return Ok(());
+            }
+
+            let values = series.str()?;
+            let normalized: Vec<Option<f64>> = values.into_iter().map(|s| self.parse_price(s?)).collect();
+
+            let flags: Vec<Option<&str>> = normalized
+                .iter()
+                .zip(values)
+                .map(|(price, raw)| (price.is_none() && raw.is_some()).then_some(PRICE_UNPARSEABLE_FLAG))
                 .collect();
+            let failed = flags.iter().flatten().count();
+            if failed > 0 {
+                warn!(
+                    "{} of {} {} values could not be parsed as prices",
+                    failed,
+                    values.len() - values.null_count(),
+                    col_name
+                );
+            }
 
             let new_series = Series::new(col_name.into(), normalized);
             df.with_column(new_series)?;
+            add_quality_flags(df, &flags)?;
         }
 
         Ok(())
     }
 
+    /// A price string as a number, e.g. "Rs. 140" or "PKR 1,250"
+    fn parse_price(&self, text: &str) -> Option<f64> {
+        let mut cleaned = text.to_string();
+        for marker in &self.currency_markers {
+            cleaned = cleaned.replace(marker.as_str(), "");
+        }
+        self.number_format.parse(&cleaned)
+    }
+
     fn normalize_string_column(&self, df: &mut DataFrame, col_name: &str) -> Result<()> {
         if let Ok(series) = df.column(col_name).cloned() {
-            let normalized: Vec<String> = series
-                .str()
-                .unwrap()
-                .into_no_null_iter()
-                .map(|s| s.trim().to_lowercase())
+            // An all-null column may come through untyped; nothing to lowercase
+            if series.dtype() != &DataType::String {
+                return Ok(());
+            }
+
+            let normalized: Vec<Option<String>> = series
+                .str()?
+                .into_iter()
+                .map(|s| s.map(|s| s.trim().to_lowercase()))
                 .collect();
 
             let new_series = Series::new(col_name.into(), normalized);
@@ -147,11 +695,16 @@ impl RuleNormalizer {
 
     fn normalize_discount_column(&self, df: &mut DataFrame, col_name: &str) -> Result<()> {
         if let Ok(series) = df.column(col_name).cloned() {
+            if series.dtype().is_primitive_numeric() {
+                df.with_column(series.cast(&DataType::Float64)?)?;
+                return Ok(());
+            }
+
             let normalized: Vec<Option<f64>> = series
-                .str()
-                .unwrap()
-                .into_no_null_iter()
+                .str()?
+                .into_iter()
                 .map(|s| {
+                    let s = s?;
                     // Handle various discount formats: "40% off", "25%", "30 percent off", etc.
                     let cleaned = s
                         .to_lowercase()
@@ -164,7 +717,8 @@ impl RuleNormalizer {
                         .to_string();
 
                     // Extract the first number found
-                    let re = Regex::new(r"(\d+(?:\.\d+)?)").unwrap();
+                    // Keep the sign so negative discounts are caught as out of range
+                    let re = Regex::new(r"(-?\d+(?:\.\d+)?)").unwrap();
                     if let Some(captures) = re.captures(&cleaned) {
                         if let Some(number_match) = captures.get(1) {
                             return f64::from_str(number_match.as_str()).ok();
@@ -183,6 +737,188 @@ impl RuleNormalizer {
         Ok(())
     }
 
+    /// Parse `units_of_mass` into `quantity_value` and `quantity_unit`,
+    /// keeping the original text. Values that cannot be parsed get null
+    /// quantities and are counted in the log.
+    fn normalize_quantities(&self, df: &mut DataFrame) -> Result<()> {
+        let Ok(units) = df.column("units_of_mass").and_then(|column| column.str()) else {
+            return Ok(());
+        };
+
+        let mut values = Vec::with_capacity(units.len());
+        let mut quantity_units = Vec::with_capacity(units.len());
+        let mut flags = Vec::with_capacity(units.len());
+        let mut parsed = 0;
+        let mut unparseable = Vec::new();
+        for unit in units {
+            let unit = unit.filter(|unit| !self.null_placeholders.contains(&unit.trim().to_lowercase()));
+            let quantity = unit.and_then(parse_quantity);
+            flags.push(match (unit, quantity) {
+                (_, Some(_)) => {
+                    parsed += 1;
+                    None
+                }
+                (Some(unit), None) => {
+                    unparseable.push(unit.to_string());
+                    Some(UNIT_UNPARSEABLE_FLAG)
+                }
+                (None, None) => Some(UNIT_MISSING_FLAG),
+            });
+            values.push(quantity.map(|(value, _)| value));
+            quantity_units.push(quantity.map(|(_, unit)| unit.as_str()));
+        }
+
+        if !unparseable.is_empty() {
+            unparseable.sort();
+            unparseable.dedup();
+            warn!(
+                "Could not parse {} distinct units_of_mass values into quantities, e.g. {:?}",
+                unparseable.len(),
+                &unparseable[..unparseable.len().min(5)]
+            );
+        }
+        info!("Parsed {} of {} units_of_mass values into quantities", parsed, units.len());
+
+        df.with_column(Series::new(QUANTITY_VALUE_FIELD.into(), values))?;
+        df.with_column(Series::new(QUANTITY_UNIT_FIELD.into(), quantity_units))?;
+        add_quality_flags(df, &flags)
+    }
+
+    /// Divide `cost_price` by the parsed quantity: per 100 g or 100 ml for
+    /// weights and volumes, per piece for counts (a dozen being 12 pieces).
+    /// Weights and volumes also get the unrounded price per gram or
+    /// millilitre. Rows missing either input get nulls; zero quantities and
+    /// results above the configured maximum get nulls and a `quality_flags` entry.
+    fn compute_price_per_unit(&self, df: &mut DataFrame) -> Result<()> {
+        let height = df.height();
+        let (Ok(prices), Ok(values), Ok(units)) = (
+            df.column("cost_price"),
+            df.column(QUANTITY_VALUE_FIELD),
+            df.column(QUANTITY_UNIT_FIELD),
+        ) else {
+            return Ok(());
+        };
+        let prices = prices.cast(&DataType::Float64)?;
+        let (prices, values, units) = (prices.f64()?, values.f64()?, units.str()?);
+
+        let mut per_unit = Vec::with_capacity(height);
+        let mut bases = Vec::with_capacity(height);
+        let mut per_base_unit = Vec::with_capacity(height);
+        let mut base_units = Vec::with_capacity(height);
+        let mut flags = Vec::with_capacity(height);
+        for ((price, value), unit) in prices.into_iter().zip(values).zip(units) {
+            let (Some(price), Some(value), Some((_, unit))) =
+                (price, value, unit.and_then(QuantityUnit::from_word))
+            else {
+                per_unit.push(None);
+                bases.push(None);
+                per_base_unit.push(None);
+                base_units.push(None);
+                flags.push(None);
+                continue;
+            };
+
+            let (units_in_basis, basis) = match unit {
+                QuantityUnit::Gram => (value / 100.0, "100g"),
+                QuantityUnit::Millilitre => (value / 100.0, "100ml"),
+                QuantityUnit::Piece | QuantityUnit::Pack => (value, "piece"),
+                QuantityUnit::Dozen => (value * 12.0, "piece"),
+            };
+            if units_in_basis <= 0.0 {
+                per_unit.push(None);
+                bases.push(None);
+                per_base_unit.push(None);
+                base_units.push(None);
+                flags.push(Some(ZERO_QUANTITY_FLAG));
+                continue;
+            }
+
+            let price_per_unit = ((price / units_in_basis) * 100.0).round() / 100.0;
+            if !price_per_unit.is_finite() || price_per_unit > self.max_price_per_unit {
+                per_unit.push(None);
+                bases.push(None);
+                per_base_unit.push(None);
+                base_units.push(None);
+                flags.push(Some(PRICE_PER_UNIT_ABOVE_MAX_FLAG));
+                continue;
+            }
+            per_unit.push(Some(price_per_unit));
+            bases.push(Some(basis));
+            let base_unit = matches!(unit, QuantityUnit::Gram | QuantityUnit::Millilitre).then(|| unit.as_str());
+            per_base_unit.push(base_unit.map(|_| price / value));
+            base_units.push(base_unit);
+            flags.push(None);
+        }
+
+        let flagged = flags.iter().flatten().count();
+        if flagged > 0 {
+            warn!("Flagged {} rows whose price per unit could not be trusted", flagged);
+        }
+
+        df.with_column(Series::new(PRICE_PER_UNIT_FIELD.into(), per_unit))?;
+        df.with_column(Series::new(PRICE_PER_UNIT_BASIS_FIELD.into(), bases))?;
+        df.with_column(Series::new(PRICE_PER_BASE_UNIT_FIELD.into(), per_base_unit))?;
+        df.with_column(Series::new(BASE_UNIT_FIELD.into(), base_units))?;
+        add_quality_flags(df, &flags)?;
+        Ok(())
+    }
+
+    /// Rows whose cost_price exceeds their mrp usually have the two swapped
+    /// by the source. Swap them back, or keep them and null their discount,
+    /// depending on `swapped_prices`; either way the row is flagged.
+    fn repair_swapped_prices(&self, df: &mut DataFrame) -> Result<()> {
+        let (Ok(cost_prices), Ok(mrps)) = (df.column("cost_price"), df.column("mrp")) else {
+            return Ok(());
+        };
+        let (cost_prices, mrps) = (cost_prices.f64()?, mrps.f64()?);
+
+        let swapped: Vec<bool> = cost_prices
+            .into_iter()
+            .zip(mrps)
+            .map(|pair| match pair {
+                (Some(cost), Some(mrp)) => mrp > 0.0 && cost > mrp + PRICE_SWAP_EPSILON,
+                _ => false,
+            })
+            .collect();
+        let count = swapped.iter().filter(|swapped| **swapped).count();
+        if count == 0 {
+            return Ok(());
+        }
+
+        let flag = match self.swapped_prices {
+            SwappedPriceAction::Swap => {
+                let pick = |swap_in: &Float64Chunked, keep: &Float64Chunked| -> Vec<Option<f64>> {
+                    keep.into_iter()
+                        .zip(swap_in)
+                        .zip(&swapped)
+                        .map(|((keep, swap_in), swapped)| if *swapped { swap_in } else { keep })
+                        .collect()
+                };
+                let (new_cost_prices, new_mrps) = (pick(mrps, cost_prices), pick(cost_prices, mrps));
+                df.with_column(Series::new("cost_price".into(), new_cost_prices))?;
+                df.with_column(Series::new("mrp".into(), new_mrps))?;
+                info!("Swapped cost_price and mrp back in {} rows where cost_price was higher", count);
+                PRICE_SWAPPED_FLAG
+            }
+            SwappedPriceAction::Flag => {
+                if let Ok(discounts) = df.column("discount") {
+                    let discounts: Vec<Option<f64>> = discounts
+                        .f64()?
+                        .into_iter()
+                        .zip(&swapped)
+                        .map(|(discount, swapped)| discount.filter(|_| !swapped))
+                        .collect();
+                    df.with_column(Series::new("discount".into(), discounts))?;
+                }
+                warn!("Flagged {} rows whose cost_price is higher than their mrp", count);
+                PRICE_SUSPECT_FLAG
+            }
+        };
+
+        let flags: Vec<Option<&str>> = swapped.iter().map(|swapped| swapped.then_some(flag)).collect();
+        add_quality_flags(df, &flags)
+    }
+
     fn calculate_missing_discounts(&self, df: &mut DataFrame) -> Result<()> {
         // Only proceed if we have the required columns
         if let (Ok(cost_price_col), Ok(mrp_col), Ok(discount_col)) =
@@ -192,37 +928,819 @@ impl RuleNormalizer {
             let mrps = mrp_col.f64()?;
             let discounts = discount_col.f64()?;
 
-            let calculated_discounts: Vec<Option<f64>> = discounts
-                .into_iter()
-                .zip(cost_prices.into_iter())
-                .zip(mrps.into_iter())
-                .map(|((existing_discount, cost_opt), mrp_opt)| {
-                    // If discount already exists and is valid, keep it
-                    if let Some(discount_val) = existing_discount {
-                        if !discount_val.is_nan() {
-                            return Some(discount_val);
-                        }
+            let from_prices = |cost_opt: Option<f64>, mrp_opt: Option<f64>| {
+                // Calculate discount from price difference
+                if let (Some(cost), Some(mrp)) = (cost_opt, mrp_opt) {
+                    if mrp > 0.0 && cost < mrp {
+                        // Discount percentage = ((MRP - Cost Price) / MRP) * 100
+                        let discount_percentage = ((mrp - cost) / mrp) * 100.0;
+                        Some((discount_percentage * 100.0).round() / 100.0) // Round to 2 decimal places
+                    } else if mrp > 0.0 && cost > mrp + PRICE_SWAP_EPSILON {
+                        None // Suspect prices kept by `repair_swapped_prices`
+                    } else {
+                        Some(0.0) // No discount if cost >= mrp
                     }
+                } else {
+                    None // Missing price data
+                }
+            };
 
-                    // Calculate discount from price difference
-                    if let (Some(cost), Some(mrp)) = (cost_opt, mrp_opt) {
-                        if mrp > 0.0 && cost < mrp {
-                            // Discount percentage = ((MRP - Cost Price) / MRP) * 100
-                            let discount_percentage = ((mrp - cost) / mrp) * 100.0;
-                            Some((discount_percentage * 100.0).round() / 100.0) // Round to 2 decimal places
-                        } else {
-                            Some(0.0) // No discount if cost >= mrp
+            let mut flags = Vec::with_capacity(discounts.len());
+            let mut calculated_discounts: Vec<Option<f64>> = Vec::with_capacity(discounts.len());
+            for ((existing_discount, cost_opt), mrp_opt) in discounts.into_iter().zip(cost_prices).zip(mrps) {
+                let existing_discount = existing_discount.filter(|discount| !discount.is_nan());
+                let out_of_range = existing_discount.is_some_and(|discount| !DISCOUNT_RANGE.contains(&discount));
+                flags.push(out_of_range.then_some(DISCOUNT_OUT_OF_RANGE_FLAG));
+                let discount = match existing_discount {
+                    // If discount already exists and is valid, keep it
+                    Some(discount) if !out_of_range => Some(discount),
+                    // Out of range: prefer the prices, then the configured fallback
+                    Some(discount) => match (cost_opt, mrp_opt, self.out_of_range_discounts) {
+                        (Some(_), Some(_), _) => from_prices(cost_opt, mrp_opt),
+                        (_, _, OutOfRangeDiscountAction::Null) => None,
+                        (_, _, OutOfRangeDiscountAction::Clamp) => {
+                            Some(discount.clamp(*DISCOUNT_RANGE.start(), *DISCOUNT_RANGE.end()))
                         }
-                    } else {
-                        None // Missing price data
-                    }
-                })
-                .collect();
+                    },
+                    None => from_prices(cost_opt, mrp_opt),
+                };
+                calculated_discounts.push(discount);
+            }
+            let sanitized = flags.iter().flatten().count();
+            if sanitized > 0 {
+                warn!("Sanitized {} discounts outside 0-100", sanitized);
+            }
 
             let new_discount_series = Series::new("discount".into(), calculated_discounts);
             df.with_column(new_discount_series)?;
+            add_quality_flags(df, &flags)?;
         }
 
         Ok(())
     }
 }
+
+/// Unit a product quantity is expressed in. Weights are in grams and volumes
+/// in millilitres; counts keep the unit they were written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuantityUnit {
+    Gram,
+    Millilitre,
+    Piece,
+    Pack,
+    Dozen,
+}
+
+impl QuantityUnit {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuantityUnit::Gram => "g",
+            QuantityUnit::Millilitre => "ml",
+            QuantityUnit::Piece => "piece",
+            QuantityUnit::Pack => "pack",
+            QuantityUnit::Dozen => "dozen",
+        }
+    }
+
+    /// Canonical unit of a unit word and how many of it one such word is,
+    /// e.g. "kg" is 1000 g
+    fn from_word(word: &str) -> Option<(f64, QuantityUnit)> {
+        let unit = match word {
+            "g" | "gm" | "gms" | "gr" | "gram" | "grams" => (1.0, QuantityUnit::Gram),
+            "kg" | "kgs" | "kilo" | "kilogram" | "kilograms" => (1000.0, QuantityUnit::Gram),
+            "ml" | "milliliter" | "milliliters" | "millilitre" | "millilitres" => (1.0, QuantityUnit::Millilitre),
+            "l" | "ltr" | "ltrs" | "liter" | "liters" | "litre" | "litres" => (1000.0, QuantityUnit::Millilitre),
+            "pc" | "pcs" | "piece" | "pieces" => (1.0, QuantityUnit::Piece),
+            "pack" | "packs" | "bundle" | "bundles" => (1.0, QuantityUnit::Pack),
+            "dozen" => (1.0, QuantityUnit::Dozen),
+            _ => return None,
+        };
+        Some(unit)
+    }
+}
+
+/// Parse a unit string such as "3 Kg", "(500gm-600gm)", "half dozen",
+/// "Pack of 6" or "6 x 250ml" into an amount and its canonical unit. kg and
+/// l become g and ml, ranges take their midpoint and pack counts multiply
+/// the quantity they pack.
+pub fn parse_quantity(text: &str) -> Option<(f64, QuantityUnit)> {
+    let text = text.trim().trim_start_matches('(').trim_end_matches(')').to_lowercase();
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    if text == "half dozen" {
+        return Some((0.5, QuantityUnit::Dozen));
+    }
+    if let Some(rest) = text.strip_prefix("pack of ") {
+        return match rest.split_once('x') {
+            Some((count, measure)) => multiply(count, measure),
+            None => Some((parse_number(rest)?, QuantityUnit::Pack)),
+        };
+    }
+    // "6 x 250ml" or "250ml x 6"
+    if let Some((left, right)) = text.split_once('x') {
+        return multiply(left, right).or_else(|| multiply(right, left));
+    }
+    parse_measure(&text)
+}
+
+/// `count` packs of `measure`
+fn multiply(count: &str, measure: &str) -> Option<(f64, QuantityUnit)> {
+    let count = parse_number(count)?;
+    let (amount, unit) = parse_measure(measure.trim())?;
+    Some((count * amount, unit))
+}
+
+/// An amount with its unit, or a range of them such as "500gm-600gm" or
+/// "1-2 kg", in which case the midpoint
+fn parse_measure(text: &str) -> Option<(f64, QuantityUnit)> {
+    let Some((low, high)) = text.split_once('-').or_else(|| text.split_once(" to ")) else {
+        let (number, factor, unit) = parse_amount(text)?;
+        return Some((number * factor, unit));
+    };
+
+    let (high, factor, unit) = parse_amount(high.trim())?;
+    // A bare lower bound is in the unit written after the upper one
+    let low = match parse_number(low) {
+        Some(low) => low * factor,
+        None => {
+            let (low, low_factor, low_unit) = parse_amount(low.trim())?;
+            if low_unit != unit {
+                return None;
+            }
+            low * low_factor
+        }
+    };
+    Some(((low + high * factor) / 2.0, unit))
+}
+
+/// A number followed by a unit word, e.g. "800gm" or "1.5 litre", as the
+/// number, the unit word's factor and its canonical unit
+fn parse_amount(text: &str) -> Option<(f64, f64, QuantityUnit)> {
+    let split = text.find(|c: char| !(c.is_ascii_digit() || c == '.'))?;
+    let (number, word) = text.split_at(split);
+    let (factor, unit) = QuantityUnit::from_word(word.trim())?;
+    Some((parse_number(number)?, factor, unit))
+}
+
+fn parse_number(text: &str) -> Option<f64> {
+    text.trim().parse::<f64>().ok().filter(|value| value.is_finite() && *value > 0.0)
+}
+
+/// Brand named in a product name: the first of `known_brands` appearing as
+/// whole words (ignoring case), else a capitalized first word such as
+/// "Kfresh" in "Kfresh Potatoes". Single-word names have no brand.
+pub fn brand_from_name<S: AsRef<str>>(name: &str, known_brands: &[S]) -> Option<String> {
+    let padded_name = format!(" {} ", brand_words(name));
+    if let Some(brand) = known_brands
+        .iter()
+        .map(|brand| brand.as_ref().trim())
+        .filter(|brand| !brand.is_empty())
+        .find(|brand| padded_name.contains(&format!(" {} ", brand_words(brand))))
+    {
+        return Some(brand.to_string());
+    }
+
+    let mut words = name.split_whitespace();
+    let first_word = words.next()?.trim_matches(|c: char| !c.is_alphanumeric());
+    words.next()?;
+    let looks_like_brand = first_word.chars().count() > 2
+        && first_word.starts_with(char::is_uppercase)
+        && first_word.chars().all(|c| c.is_alphabetic() || c == '&' || c == '\'')
+        && !NON_BRAND_WORDS.contains(&first_word.to_lowercase().as_str());
+    looks_like_brand.then(|| first_word.to_string())
+}
+
+/// Lowercased words of `text`, keeping the `&` and `'` brand names use
+fn brand_words(text: &str) -> String {
+    text.to_lowercase()
+        .split(|c: char| !(c.is_alphanumeric() || c == '&' || c == '\''))
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl Processor for RuleNormalizer {
+    fn name(&self) -> &str {
+        "normalizer"
+    }
+
+    fn process(&self, df: &mut DataFrame) -> Result<()> {
+        self.normalize_dataframe(df)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::json_flattener::JsonFlattener;
+    use super::super::quality_flags::QUALITY_FLAGS_FIELD;
+
+    #[test]
+    fn test_price_columns_accept_float_and_string() {
+        let mut typed = df!(
+            "name" => ["Tea 1 Kg"],
+            "cost_price" => [Some(99.5)],
+            "mrp" => [Some(120.0)],
+            "discount" => [None::<f64>]
+        )
+        .unwrap();
+        let mut legacy = df!(
+            "name" => ["Tea 1 Kg"],
+            "cost_price" => ["99.50"],
+            "mrp" => ["120"],
+            "discount" => [None::<&str>]
+        )
+        .unwrap();
+
+        RuleNormalizer::new().normalize_dataframe(&mut typed).unwrap();
+        RuleNormalizer::new().normalize_dataframe(&mut legacy).unwrap();
+
+        for df in [&typed, &legacy] {
+            assert_eq!(df.column("cost_price").unwrap().f64().unwrap().get(0), Some(99.5));
+            assert_eq!(df.column("discount").unwrap().f64().unwrap().get(0), Some(17.08));
+        }
+    }
+
+    #[test]
+    fn test_nulls_keep_their_rows() {
+        let mut df = df!(
+            "cost_price" => [Some("100"), None, Some("Rs 200")],
+            "category" => [Some(" Dairy "), None, Some("FRUITS")],
+            "discount" => [Some("10% off"), None, Some("25")]
+        )
+        .unwrap();
+        let normalizer = RuleNormalizer::new();
+        normalizer.normalize_price_column(&mut df, "cost_price").unwrap();
+        normalizer.normalize_string_column(&mut df, "category").unwrap();
+        normalizer.normalize_discount_column(&mut df, "discount").unwrap();
+
+        assert_eq!(df.height(), 3);
+        let floats = |column: &str| -> Vec<Option<f64>> {
+            df.column(column).unwrap().f64().unwrap().into_iter().collect()
+        };
+        assert_eq!(floats("cost_price"), vec![Some(100.0), None, Some(200.0)]);
+        assert_eq!(floats("discount"), vec![Some(10.0), None, Some(25.0)]);
+        let categories: Vec<Option<&str>> = df.column("category").unwrap().str().unwrap().into_iter().collect();
+        assert_eq!(categories, vec![Some("dairy"), None, Some("fruits")]);
+
+        // Typed and untyped columns pass through unchanged
+        let mut typed = df!(
+            "cost_price" => [Some(100.0), None, Some(200.0)],
+            "category" => [None::<&str>, None, None]
+        )
+        .unwrap();
+        let category = typed.column("category").unwrap().cast(&DataType::Null).unwrap();
+        typed.with_column(category).unwrap();
+        normalizer.normalize_price_column(&mut typed, "cost_price").unwrap();
+        normalizer.normalize_string_column(&mut typed, "category").unwrap();
+        assert_eq!(typed.column("cost_price").unwrap().f64().unwrap().into_iter().collect::<Vec<_>>(), vec![Some(100.0), None, Some(200.0)]);
+        assert_eq!(typed.column("category").unwrap().null_count(), 3);
+    }
+
+    #[test]
+    fn test_currency_markers_are_stripped() {
+        let mut df = df!("cost_price" => ["Rs. 140", "PKR 1,250", "₨99.50", "250", "€ 3.20", "USD 4", "CHF 5"]).unwrap();
+        RuleNormalizer::new().normalize_price_column(&mut df, "cost_price").unwrap();
+        let prices: Vec<Option<f64>> = df.column("cost_price").unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!(prices, vec![Some(140.0), Some(1250.0), Some(99.5), Some(250.0), Some(3.2), Some(4.0), None]);
+
+        let mut df = df!("cost_price" => ["CHF 4", "US$ 5"]).unwrap();
+        RuleNormalizer::new()
+            .with_currency_markers(vec!["$".to_string(), "CHF".to_string(), "US$".to_string()])
+            .normalize_price_column(&mut df, "cost_price")
+            .unwrap();
+        let prices: Vec<Option<f64>> = df.column("cost_price").unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!(prices, vec![Some(4.0), Some(5.0)]);
+    }
+
+    #[test]
+    fn test_eu_number_format() {
+        let mut df = df!("cost_price" => ["€ 1.234,50", "3,20 €", "Rs. 1.250", "12"]).unwrap();
+        RuleNormalizer::new()
+            .with_number_format(NumberFormat::Eu)
+            .normalize_price_column(&mut df, "cost_price")
+            .unwrap();
+        let prices: Vec<Option<f64>> = df.column("cost_price").unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!(prices, vec![Some(1234.5), Some(3.2), Some(1250.0), Some(12.0)]);
+
+        // The same strings read as us numbers
+        let mut df = df!("cost_price" => ["1,234.50", "1.234,50"]).unwrap();
+        RuleNormalizer::new().normalize_price_column(&mut df, "cost_price").unwrap();
+        let prices: Vec<Option<f64>> = df.column("cost_price").unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!(prices, vec![Some(1234.5), Some(1.2345)]);
+    }
+
+    #[test]
+    fn test_prices_with_currency_tokens() {
+        let mut df = df!(
+            "name" => ["Tea 1 Kg", "Tea 1 Kg", "Tea 1 Kg", "Tea 1 Kg", "Tea 1 Kg"],
+            "cost_price" => ["Rs. 150", "PKR 1,250.50", "₨99", "AED 12.50", "د.إ 7"],
+            "mrp" => ["$200", "1,300", "100 Rs", "Dhs 15", "7.00 د.إ"]
+        )
+        .unwrap();
+
+        RuleNormalizer::new().normalize_dataframe(&mut df).unwrap();
+        let values = |column: &str| -> Vec<Option<f64>> {
+            df.column(column).unwrap().f64().unwrap().into_iter().collect()
+        };
+        assert_eq!(values("cost_price"), vec![Some(150.0), Some(1250.5), Some(99.0), Some(12.5), Some(7.0)]);
+        assert_eq!(values("mrp"), vec![Some(200.0), Some(1300.0), Some(100.0), Some(15.0), Some(7.0)]);
+    }
+
+    #[test]
+    fn test_raw_json_column_is_left_alone() {
+        let raw = r#"{"name":"Tea (1 Kg) | Sale","mrp":"Rs. 1,200"}"#;
+        let mut df = df!(
+            "name" => ["Tea (1 Kg) | Sale"],
+            "mrp" => ["1,200"],
+            "_raw" => [raw]
+        )
+        .unwrap();
+
+        RuleNormalizer::new().normalize_dataframe(&mut df).unwrap();
+        assert_eq!(df.column("name").unwrap().str().unwrap().get(0), Some("tea"));
+        assert_eq!(df.column("_raw").unwrap().str().unwrap().get(0), Some(raw));
+    }
+
+    #[test]
+    fn test_brand_from_name() {
+        let cases = [
+            ("Nestle Milkpak UHT Milk 1 Litre", Some("Nestle")),
+            ("Olper's Full Cream Milk", Some("Olper's")),
+            ("K&N's Chicken Nuggets 1000g", Some("K&N's")),
+            ("Shan Biryani Masala 50g", Some("Shan")),
+            ("Kfresh Potatoes (Aalu)", Some("Kfresh")),
+            ("Dates Premium by national foods", Some("National")),
+            ("Fresh Bananas", None),
+            ("Eggs", None),
+            ("potatoes loose", None),
+            ("1 Kg Sugar", None),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(brand_from_name(name, &DEFAULT_KNOWN_BRANDS).as_deref(), expected, "{}", name);
+        }
+
+        // Known brands must match whole words
+        assert_eq!(brand_from_name("Shandaar Daal Chana", &["Shan"]).as_deref(), Some("Shandaar"));
+        assert_eq!(brand_from_name("tea by lipton", &["Lipton"]).as_deref(), Some("Lipton"));
+    }
+
+    #[test]
+    fn test_brand_column_keeps_reported_brands() {
+        let mut df = df!(
+            "name" => ["Tapal Danedar Tea (950 gm)", "Kfresh Potatoes - 3 Kg", "Fresh Bananas", "Dawn Bread"],
+            "brand" => [None, Some("KraveMart"), None, Some(" ")]
+        )
+        .unwrap();
+
+        RuleNormalizer::new()
+            .with_known_brands(vec!["Tapal".to_string(), "Dawn".to_string()])
+            .normalize_dataframe(&mut df)
+            .unwrap();
+        let brands: Vec<Option<&str>> = df.column("brand").unwrap().str().unwrap().into_iter().collect();
+        assert_eq!(brands, vec![Some("Tapal"), Some("KraveMart"), None, Some("Dawn")]);
+        assert_eq!(df.column("name").unwrap().str().unwrap().get(0), Some("tapal danedar tea"));
+
+        let mut without_brand = df!("name" => ["Shan Karahi Masala"]).unwrap();
+        RuleNormalizer::new().normalize_dataframe(&mut without_brand).unwrap();
+        assert_eq!(without_brand.column("brand").unwrap().str().unwrap().get(0), Some("Shan"));
+    }
+
+    fn normalized_names(normalizer: &RuleNormalizer, names: &[&str]) -> (Vec<String>, Vec<Option<String>>) {
+        let mut df = df!("name" => names).unwrap();
+        normalizer.normalize_dataframe(&mut df).unwrap();
+        let column = |name: &str| -> Vec<Option<String>> {
+            df.column(name).unwrap().str().unwrap().into_iter().map(|s| s.map(str::to_string)).collect()
+        };
+        (column("name").into_iter().flatten().collect(), column("units_of_mass"))
+    }
+
+    fn some(values: &[&str]) -> Vec<Option<String>> {
+        values.iter().map(|value| Some(value.to_string())).collect()
+    }
+
+    #[test]
+    fn test_descriptor_parentheticals_are_stripped_and_quantities_kept() {
+        let (names, units) = normalized_names(
+            &RuleNormalizer::new(),
+            &[
+                "Potatoes (Aalu) (800gm)",
+                "Kfresh Onions (Pyaaz) - 1 Kg",
+                "Milk (1 L) (Pack of 6)",
+                "Potatoes (آلو) 2 kg",
+                "Eggs (Dozen)",
+                "Dates (Eid Special 2025)",
+            ],
+        );
+        assert_eq!(
+            names,
+            vec!["potatoes", "kfresh onions", "milk (pack of 6)", "potatoes", "eggs (dozen)", "dates (eid special 2025)"]
+        );
+        assert_eq!(units, [some(&["800gm", "1 Kg", "1 L", "2 kg"]), vec![None, None]].concat());
+
+        let normalizer = RuleNormalizer::new().with_name_descriptors(vec![" Eid Special 2025 ".to_string()]);
+        assert!(normalizer.is_name_descriptor("eid special 2025"));
+        assert!(normalizer.is_name_descriptor(" Aalu "));
+        assert!(!normalizer.is_name_descriptor("800gm"));
+        assert!(!normalizer.is_name_descriptor("Pack of 6"));
+        let (names, _) = normalized_names(&normalizer, &["Dates (Eid Special 2025)"]);
+        assert_eq!(names, vec!["dates"]);
+    }
+
+    #[test]
+    fn test_original_name_is_kept() {
+        let mut df = df!("name" => [Some("Kfresh Garma Melon - (800gm)"), None]).unwrap();
+        RuleNormalizer::new().normalize_dataframe(&mut df).unwrap();
+
+        assert_eq!(df.get_column_names_str()[..2], ["name", NAME_ORIGINAL_FIELD]);
+        let names: Vec<Option<&str>> = df.column("name").unwrap().str().unwrap().into_iter().collect();
+        let originals: Vec<Option<&str>> = df.column(NAME_ORIGINAL_FIELD).unwrap().str().unwrap().into_iter().collect();
+        assert_eq!(names, vec![Some("kfresh garma melon"), None]);
+        assert_eq!(originals, vec![Some("Kfresh Garma Melon - (800gm)"), None]);
+
+        // Cased names are cleaned from the original again
+        RuleNormalizer::new().with_lowercase_names(false).normalize_dataframe(&mut df).unwrap();
+        let names: Vec<Option<&str>> = df.column("name").unwrap().str().unwrap().into_iter().collect();
+        assert_eq!(names, vec![Some("Kfresh Garma Melon"), None]);
+        assert_eq!(df.column("units_of_mass").unwrap().str().unwrap().get(0), Some("800gm"));
+    }
+
+    #[test]
+    fn test_rule_file_adds_unit_words() {
+        let shipped = NameRules::from_file("src/configs/normalizer_rules.toml").unwrap();
+        let patterns = |rules: &NameRules| -> Vec<String> {
+            rules.unit_patterns.iter().map(|regex| regex.as_str().to_string()).collect()
+        };
+        assert_eq!(patterns(&shipped), patterns(&NameRules::default()));
+        assert_eq!(shipped.promo_pattern.as_str(), DEFAULT_PROMO_PATTERN);
+        assert_eq!(shipped.unit_words, NameRules::default().unit_words);
+
+        let (_, units) = normalized_names(&RuleNormalizer::new(), &["Shampoo (1 sachet)"]);
+        assert_eq!(units, vec![None]);
+
+        let path = std::env::temp_dir().join(format!("normalizer_rules_{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"
+unit_patterns = [
+    '(?i)\s*\(\s*(\d+\s*(?:sachet|sachets|ltr))\s*\)',
+    '(?i)\s+(\d+(?:\.\d+)?\s*(?:kg|ltr))\s*$',
+]
+unit_words = ["kg", "ltr", "sachet", "sachets"]
+"#,
+        )
+        .unwrap();
+        let normalizer = RuleNormalizer::from_config(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let (names, units) = normalized_names(
+            &normalizer,
+            &["Sunsilk Shampoo (1 Sachet)", "Dalda Cooking Oil 5 ltr", "Potatoes (Aalu) | Deal"],
+        );
+        assert_eq!(names, vec!["sunsilk shampoo", "dalda cooking oil", "potatoes"]);
+        assert_eq!(units, [some(&["1 Sachet", "5 ltr"]), vec![None]].concat());
+    }
+
+    #[test]
+    fn test_invalid_rule_files_report_the_line() {
+        let err = NameRules::from_toml("promo_pattern = '\\|'\nunit_patterns = [\n    '(\\d+ kg',\n]\n", "rules.toml")
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("rules.toml:3: invalid pattern '(\\d+ kg'"), "{}", err);
+
+        let err = NameRules::from_toml("unit_patterns = ['\\d+ kg']", "rules.toml").unwrap_err().to_string();
+        assert!(err.contains("rules.toml:1") && err.contains("capture"), "{}", err);
+
+        assert!(NameRules::from_toml("unit_pattern = []", "rules.toml").is_err());
+    }
+
+    #[test]
+    fn test_normalizing_clean_data_again_is_a_no_op() {
+        let mut df = df!(
+            "name" => ["Basmati Rice (5 Kg) | Free delivery", "Eggs", "Olpers Milk"],
+            "cost_price" => [Some(1750.0), Some(380.0), None],
+            "mrp" => [Some(1900.0), Some(380.0), Some(280.0)],
+            "units_of_mass" => ["N/A", "12 pcs", "N/A"],
+            "category" => [" Rice ", "Eggs", "Dairy"],
+            "discount" => [None::<f64>, None, None]
+        )
+        .unwrap();
+
+        RuleNormalizer::new().normalize_dataframe(&mut df).unwrap();
+        let units: Vec<Option<&str>> = df.column("units_of_mass").unwrap().str().unwrap().into_iter().collect();
+        assert_eq!(units, vec![Some("5 Kg"), Some("12 pcs"), None]);
+
+        let once = df.clone();
+        RuleNormalizer::new().normalize_dataframe(&mut df).unwrap();
+        assert!(df.equals_missing(&once));
+    }
+
+    #[test]
+    fn test_placeholders_become_nulls_in_configured_columns() {
+        let frame = || {
+            df!(
+                "name" => ["Eggs", "Olpers Milk", "Rice"],
+                "units_of_mass" => ["12 pcs", " n/a ", "NULL"],
+                "description" => [Some("-"), None, Some("Long grain")]
+            )
+            .unwrap()
+        };
+
+        // By default only units_of_mass is cleaned
+        let mut df = frame();
+        RuleNormalizer::new().normalize_dataframe(&mut df).unwrap();
+        let strings = |df: &DataFrame, column: &str| -> Vec<Option<String>> {
+            df.column(column).unwrap().str().unwrap().into_iter().map(|s| s.map(str::to_string)).collect()
+        };
+        assert_eq!(strings(&df, "units_of_mass"), vec![Some("12 pcs".to_string()), None, None]);
+        assert_eq!(strings(&df, "description"), vec![Some("-".to_string()), None, Some("Long grain".to_string())]);
+        assert_eq!(df.column("units_of_mass").unwrap().null_count(), 2);
+
+        let mut df = frame();
+        RuleNormalizer::new()
+            .with_null_columns(vec!["units_of_mass".into(), "description".into(), "missing".into()])
+            .with_null_placeholders(vec!["N/A".into(), "-".into()])
+            .normalize_dataframe(&mut df)
+            .unwrap();
+        assert_eq!(strings(&df, "units_of_mass"), vec![Some("12 pcs".to_string()), None, Some("NULL".to_string())]);
+        assert_eq!(strings(&df, "description"), vec![None, None, Some("Long grain".to_string())]);
+    }
+
+    #[test]
+    fn test_parse_quantity() {
+        use QuantityUnit::*;
+
+        assert_eq!(parse_quantity("3 Kg"), Some((3000.0, Gram)));
+        assert_eq!(parse_quantity("800gm"), Some((800.0, Gram)));
+        assert_eq!(parse_quantity("1.5 litre"), Some((1500.0, Millilitre)));
+        assert_eq!(parse_quantity("(500gm-600gm)"), Some((550.0, Gram)));
+        assert_eq!(parse_quantity("1-2 kg"), Some((1500.0, Gram)));
+        assert_eq!(parse_quantity("half dozen"), Some((0.5, Dozen)));
+        assert_eq!(parse_quantity("1 Dozen"), Some((1.0, Dozen)));
+        assert_eq!(parse_quantity("Pack of 6"), Some((6.0, Pack)));
+        assert_eq!(parse_quantity("pack of 6 x 250ml"), Some((1500.0, Millilitre)));
+        assert_eq!(parse_quantity("2 x 1 L"), Some((2000.0, Millilitre)));
+        assert_eq!(parse_quantity("12 pcs"), Some((12.0, Piece)));
+
+        for garbage in ["N/A", "", "large", "500", "5 boxes", "1 kg - 500 ml", "0 g", "pack of many"] {
+            assert_eq!(parse_quantity(garbage), None, "{:?}", garbage);
+        }
+    }
+
+    #[test]
+    fn test_units_are_split_into_quantity_columns() {
+        let mut df = df!(
+            "name" => ["Basmati Rice 3 Kg", "Mangoes (500gm-600gm)", "Eggs - half dozen", "Juice (Pack of 6)", "Bread", "Tissue"],
+            "units_of_mass" => ["N/A", "N/A", "N/A", "N/A", "N/A", "Jumbo roll"]
+        )
+        .unwrap();
+        RuleNormalizer::new().normalize_dataframe(&mut df).unwrap();
+
+        let values: Vec<Option<f64>> = df.column(QUANTITY_VALUE_FIELD).unwrap().f64().unwrap().into_iter().collect();
+        let units: Vec<Option<&str>> = df.column(QUANTITY_UNIT_FIELD).unwrap().str().unwrap().into_iter().collect();
+        assert_eq!(values, vec![Some(3000.0), Some(550.0), Some(0.5), Some(6.0), None, None]);
+        assert_eq!(units, vec![Some("g"), Some("g"), Some("dozen"), Some("pack"), None, None]);
+
+        // The original text is kept
+        let original: Vec<Option<&str>> = df.column("units_of_mass").unwrap().str().unwrap().into_iter().collect();
+        assert_eq!(original, vec![Some("3 Kg"), Some("500gm-600gm"), Some("half dozen"), Some("Pack of 6"), None, Some("Jumbo roll")]);
+    }
+
+    #[test]
+    fn test_price_per_unit_for_each_unit_family() {
+        let mut df = df!(
+            "name" => [
+                "Basmati Rice 5 kg",
+                "Olpers Milk (1.5 liter)",
+                "Eggs - 1 dozen",
+                "Juice (Pack of 6)",
+                "Bananas - 12 pieces",
+                "Mystery Box",
+                "Saffron 1 g",
+            ],
+            "cost_price" => [Some(1750.0), Some(420.0), Some(360.0), Some(600.0), Some(240.0), Some(99.0), None]
+        )
+        .unwrap();
+        RuleNormalizer::new().normalize_dataframe(&mut df).unwrap();
+
+        let per_unit: Vec<Option<f64>> = df.column(PRICE_PER_UNIT_FIELD).unwrap().f64().unwrap().into_iter().collect();
+        let bases: Vec<Option<&str>> = df.column(PRICE_PER_UNIT_BASIS_FIELD).unwrap().str().unwrap().into_iter().collect();
+        assert_eq!(per_unit, vec![Some(35.0), Some(28.0), Some(30.0), Some(100.0), Some(20.0), None, None]);
+        assert_eq!(
+            bases,
+            vec![Some("100g"), Some("100ml"), Some("piece"), Some("piece"), Some("piece"), None, None]
+        );
+        // A missing price is not a quality problem; a missing unit is flagged by itself
+        let flags: Vec<Option<&str>> = df.column(QUALITY_FLAGS_FIELD).unwrap().str().unwrap().into_iter().collect();
+        assert_eq!(flags, vec![None, None, None, None, None, Some(UNIT_MISSING_FLAG), None]);
+    }
+
+    #[test]
+    fn test_price_per_base_unit_for_weights_and_volumes() {
+        let mut df = df!(
+            "name" => ["Sugar 1 Kg", "Cooking Oil 500 ml", "Eggs - 1 dozen", "Mystery Box"],
+            "cost_price" => [100.0, 300.0, 360.0, 99.0]
+        )
+        .unwrap();
+        RuleNormalizer::new().normalize_dataframe(&mut df).unwrap();
+
+        let per_base_unit: Vec<Option<f64>> =
+            df.column(PRICE_PER_BASE_UNIT_FIELD).unwrap().f64().unwrap().into_iter().collect();
+        let base_units: Vec<Option<&str>> = df.column(BASE_UNIT_FIELD).unwrap().str().unwrap().into_iter().collect();
+        // 1 Kg @ 100 is 0.1 per gram
+        assert_eq!(per_base_unit, vec![Some(0.1), Some(0.6), None, None]);
+        assert_eq!(base_units, vec![Some("g"), Some("ml"), None, None]);
+    }
+
+    #[test]
+    fn test_zero_quantities_and_absurd_prices_are_flagged() {
+        let mut df = df!(
+            "cost_price" => [250.0, 250.0, 90_000.0],
+            QUANTITY_VALUE_FIELD => [0.0, 500.0, 1.0],
+            QUANTITY_UNIT_FIELD => ["g", "g", "g"]
+        )
+        .unwrap();
+        RuleNormalizer::new().with_max_price_per_unit(50_000.0).compute_price_per_unit(&mut df).unwrap();
+
+        let per_unit: Vec<Option<f64>> = df.column(PRICE_PER_UNIT_FIELD).unwrap().f64().unwrap().into_iter().collect();
+        let flags: Vec<Option<&str>> = df.column(QUALITY_FLAGS_FIELD).unwrap().str().unwrap().into_iter().collect();
+        assert_eq!(per_unit, vec![None, Some(50.0), None]);
+        assert_eq!(flags, vec![Some("ZERO_QUANTITY"), None, Some("PRICE_PER_UNIT_ABOVE_MAX")]);
+    }
+
+    #[test]
+    fn test_swapped_prices_are_repaired() {
+        let prices = || {
+            df!(
+                "name" => ["Tea 1 Kg", "Tea 1 Kg", "Tea 1 Kg"],
+                // swapped, equal, normal
+                "cost_price" => [Some(200.0), Some(150.0), Some(90.0)],
+                "mrp" => [Some(150.0), Some(150.0), Some(100.0)],
+                "discount" => [None::<f64>, None, None]
+            )
+            .unwrap()
+        };
+        let floats = |df: &DataFrame, column: &str| -> Vec<Option<f64>> {
+            df.column(column).unwrap().f64().unwrap().into_iter().collect()
+        };
+        let flags = |df: &DataFrame| -> Vec<Option<String>> {
+            df.column(QUALITY_FLAGS_FIELD)
+                .unwrap()
+                .str()
+                .unwrap()
+                .into_iter()
+                .map(|flag| flag.map(str::to_string))
+                .collect()
+        };
+
+        let mut df = prices();
+        RuleNormalizer::new().normalize_dataframe(&mut df).unwrap();
+        assert_eq!(floats(&df, "cost_price"), vec![Some(150.0), Some(150.0), Some(90.0)]);
+        assert_eq!(floats(&df, "mrp"), vec![Some(200.0), Some(150.0), Some(100.0)]);
+        assert_eq!(floats(&df, "discount"), vec![Some(25.0), Some(0.0), Some(10.0)]);
+        assert_eq!(flags(&df), vec![Some(PRICE_SWAPPED_FLAG.to_string()), None, None]);
+
+        // Normalizing the clean data again changes nothing
+        RuleNormalizer::new().normalize_dataframe(&mut df).unwrap();
+        assert_eq!(flags(&df), vec![Some(PRICE_SWAPPED_FLAG.to_string()), None, None]);
+
+        let mut df = prices();
+        RuleNormalizer::new()
+            .with_swapped_prices(SwappedPriceAction::Flag)
+            .normalize_dataframe(&mut df)
+            .unwrap();
+        assert_eq!(floats(&df, "cost_price"), vec![Some(200.0), Some(150.0), Some(90.0)]);
+        assert_eq!(floats(&df, "discount"), vec![None, Some(0.0), Some(10.0)]);
+        assert_eq!(flags(&df), vec![Some(PRICE_SUSPECT_FLAG.to_string()), None, None]);
+    }
+
+    #[test]
+    fn test_out_of_range_discounts_are_sanitized() {
+        let discounts = || {
+            df!(
+                "name" => ["Tea 1 Kg", "Tea 1 Kg", "Tea 1 Kg", "Tea 1 Kg"],
+                "cost_price" => [None, None, None, Some(90.0)],
+                "mrp" => [None, None, None, Some(120.0)],
+                "discount" => ["250%", "-5", "40% off", "2500"]
+            )
+            .unwrap()
+        };
+        let values = |df: &DataFrame| -> Vec<Option<f64>> {
+            df.column("discount").unwrap().f64().unwrap().into_iter().collect()
+        };
+
+        let mut df = discounts();
+        RuleNormalizer::new().normalize_dataframe(&mut df).unwrap();
+        // The last row's discount is recomputed from its prices
+        assert_eq!(values(&df), vec![None, None, Some(40.0), Some(25.0)]);
+
+        let mut df = discounts();
+        RuleNormalizer::new()
+            .with_out_of_range_discounts(OutOfRangeDiscountAction::Clamp)
+            .normalize_dataframe(&mut df)
+            .unwrap();
+        assert_eq!(values(&df), vec![Some(100.0), Some(0.0), Some(40.0), Some(25.0)]);
+    }
+
+    #[test]
+    fn test_negative_discount_from_raw_json() {
+        let products = vec![
+            serde_json::json!({"product_id": 1, "name": "Tea 1 Kg", "sku_percent_off": "-5%"}),
+            serde_json::json!({"product_id": 2, "name": "Tea 1 Kg", "cost_price": 90, "mrp": 120, "sku_percent_off": "-5"}),
+        ];
+        let mut df = JsonFlattener::new().flatten_to_dataframe(&products).unwrap().dataframe;
+        assert_eq!(df.column("sku_percent_off").unwrap().f64().unwrap().get(0), Some(-5.0));
+
+        df.rename("sku_percent_off", "discount".into()).unwrap();
+        RuleNormalizer::new().normalize_dataframe(&mut df).unwrap();
+        let discounts: Vec<Option<f64>> = df.column("discount").unwrap().f64().unwrap().into_iter().collect();
+        // Nulled when out of range, recomputed when both prices are present
+        assert_eq!(discounts, vec![None, Some(25.0)]);
+    }
+
+    #[test]
+    fn test_unparseable_price_from_raw_json_is_flagged() {
+        let products = vec![
+            serde_json::json!({"product_id": 1, "name": "Tea 1 Kg", "cost_price": "Rs. ask", "mrp": "Rs. 100"}),
+            serde_json::json!({"product_id": 2, "name": "Tea 1 Kg", "cost_price": "Rs. 90", "mrp": "Rs. 100"}),
+        ];
+        let mut df = JsonFlattener::new().flatten_to_dataframe(&products).unwrap().dataframe;
+        RuleNormalizer::new().normalize_dataframe(&mut df).unwrap();
+
+        let cost_prices: Vec<Option<f64>> = df.column("cost_price").unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!(cost_prices, vec![None, Some(90.0)]);
+        let flags: Vec<Option<&str>> = df.column(QUALITY_FLAGS_FIELD).unwrap().str().unwrap().into_iter().collect();
+        assert_eq!(flags, vec![Some("PRICE_UNPARSEABLE"), None]);
+    }
+
+    #[test]
+    fn test_quality_flags_per_row() {
+        let mut df = df!(
+            "name" => ["Tea 1 Kg", "Eggs", "Milk 1 liter", "Rice 5 Kg"],
+            "cost_price" => ["Rs. ask", "Rs. 120", "Rs. 90", "Rs. 500"],
+            "mrp" => ["Rs. 100", "Rs. 100", "Rs. 100", "Rs. 600"],
+            "discount" => ["40%", "", "250%", "10"]
+        )
+        .unwrap();
+        RuleNormalizer::new().normalize_dataframe(&mut df).unwrap();
+
+        let flags: Vec<Option<&str>> = df.column(QUALITY_FLAGS_FIELD).unwrap().str().unwrap().into_iter().collect();
+        assert_eq!(
+            flags,
+            vec![
+                Some("PRICE_UNPARSEABLE"),
+                Some("UNIT_MISSING,PRICE_SWAPPED"),
+                Some("DISCOUNT_OUT_OF_RANGE"),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_column_transforms_run_after_built_in_steps() {
+        #[derive(Deserialize)]
+        struct Transforms {
+            transforms: Vec<ColumnTransform>,
+        }
+        let config: Transforms = toml::from_str(
+            r#"
+            [[transforms]]
+            column = "sku"
+            op = "regex_replace"
+            pattern = "^BNDL-?"
+
+            [[transforms]]
+            column = "sku"
+            op = "strip_suffix"
+            suffix = "/x"
+
+            [[transforms]]
+            column = "category"
+            op = "uppercase"
+
+            [[transforms]]
+            column = "not_a_column"
+            op = "trim"
+            "#,
+        )
+        .unwrap();
+
+        let mut df = df!(
+            "name" => ["Tea 1 Kg", "Eggs 12 pieces"],
+            "cost_price" => ["100", "200"],
+            "sku" => [Some("BNDL-123/x"), None],
+            "category" => ["Nashta ", "Dairy"]
+        )
+        .unwrap();
+        RuleNormalizer::new().with_transforms(config.transforms).normalize_dataframe(&mut df).unwrap();
+
+        let sku: Vec<Option<&str>> = df.column("sku").unwrap().str().unwrap().into_iter().collect();
+        assert_eq!(sku, vec![Some("123"), None]);
+        // Uppercased after the built-in lowercasing and trimming of categories
+        let category: Vec<Option<&str>> = df.column("category").unwrap().str().unwrap().into_iter().collect();
+        assert_eq!(category, vec![Some("NASHTA"), Some("DAIRY")]);
+
+        let invalid = toml::from_str::<Transforms>("[[transforms]]\ncolumn = \"sku\"\nop = \"regex_replace\"\npattern = \"(\"");
+        assert!(invalid.is_err());
+    }
+}
@@ -0,0 +1,424 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use polars::prelude::DataFrame;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::{Anomaly, PRICE_SUSPECT_FLAG, PRICE_SWAPPED_FLAG, QUALITY_FLAGS_FIELD, QualityReport, quality_report};
+
+/// What one pipeline run processed, per stored source
+#[derive(Debug, Clone, Serialize)]
+pub struct RunReport {
+    /// e.g. "from APIs" or "from Storage"
+    pub mode: String,
+    pub started_at: DateTime<Utc>,
+    pub sources: Vec<SourceReport>,
+    /// Sources or stores that could not be processed
+    pub failures: Vec<SourceFailure>,
+    /// Sources that failed at first and succeeded when run again at the end
+    pub retried: Vec<String>,
+    /// Stopped by Ctrl-C before every selected source was processed
+    pub interrupted: bool,
+    /// A `--dry-run`: nothing the run produced was stored
+    pub dry_run: bool,
+    /// Stored separately, see `data_quality`
+    #[serde(skip)]
+    flags: Vec<SourceFlags>,
+}
+
+/// One processed source; multi-store sources get one entry per store
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceReport {
+    pub source: String,
+    pub storage_name: String,
+    pub products: usize,
+    /// Data quality of the clean DataFrame, when one was produced
+    pub quality: Option<QualityReport>,
+    /// Rows whose cost_price was above their mrp, swapped back or flagged
+    pub price_repairs: usize,
+    /// Products at each stage, when the source was processed from raw data
+    pub counts: Option<ProductCounts>,
+    /// How the snapshot differs from the previous one; a source with any is suspect
+    pub anomalies: Vec<Anomaly>,
+}
+
+impl SourceReport {
+    pub fn is_suspect(&self) -> bool {
+        !self.anomalies.is_empty()
+    }
+}
+
+/// Products of a source at each stage of processing, to catch products
+/// silently lost between the raw dump and the clean output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ProductCounts {
+    /// Products in the raw dump
+    pub raw: usize,
+    /// Products the flattener extracted; the rest failed extraction
+    pub flattened: usize,
+    /// Rows of the clean DataFrame, after variants are expanded and
+    /// duplicates collapsed
+    pub clean: usize,
+    /// Rows left out on purpose by `--drop-out-of-stock`, not counted as dropped
+    pub out_of_stock: usize,
+}
+
+impl ProductCounts {
+    /// Share of raw products missing from the clean output, in percent.
+    /// Variant rows can make up for lost products, so this is a lower bound.
+    pub fn drop_pct(&self) -> f64 {
+        if self.raw == 0 {
+            return 0.0;
+        }
+        self.raw.saturating_sub(self.clean + self.out_of_stock) as f64 / self.raw as f64 * 100.0
+    }
+}
+
+/// `quality_flags` of the clean rows of every source in a run
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DataQualityReport {
+    pub started_at: DateTime<Utc>,
+    pub sources: Vec<SourceFlags>,
+}
+
+/// How often each `quality_flags` code occurs in one source's clean rows
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SourceFlags {
+    pub storage_name: String,
+    pub rows: usize,
+    /// Rows with at least one flag
+    pub flagged_rows: usize,
+    /// Rows per flag code; a row with several flags counts once for each
+    pub flags: BTreeMap<String, usize>,
+}
+
+impl SourceFlags {
+    pub fn from_dataframe(storage_name: &str, df: &DataFrame) -> Self {
+        let mut flagged_rows = 0;
+        let mut flags = BTreeMap::new();
+        if let Ok(column) = df.column(QUALITY_FLAGS_FIELD).and_then(|column| column.str().cloned()) {
+            for row_flags in column.into_iter().flatten() {
+                flagged_rows += 1;
+                for flag in row_flags.split(',') {
+                    *flags.entry(flag.to_string()).or_insert(0) += 1;
+                }
+            }
+        }
+
+        SourceFlags {
+            storage_name: storage_name.to_string(),
+            rows: df.height(),
+            flagged_rows,
+            flags,
+        }
+    }
+}
+
+/// A source, or one store of it, that failed, with the error that stopped it
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceFailure {
+    pub source: String,
+    pub storage_name: String,
+    pub error: String,
+}
+
+impl RunReport {
+    pub fn new(mode: impl Into<String>) -> Self {
+        RunReport {
+            mode: mode.into(),
+            started_at: Utc::now(),
+            sources: Vec::new(),
+            failures: Vec::new(),
+            retried: Vec::new(),
+            interrupted: false,
+            dry_run: false,
+            flags: Vec::new(),
+        }
+    }
+
+    /// Record a processed source, with the quality of its clean DataFrame and
+    /// its product counts per stage
+    pub fn add_source(
+        &mut self,
+        source: &str,
+        storage_name: &str,
+        products: usize,
+        clean_df: Option<&DataFrame>,
+        counts: Option<ProductCounts>,
+    ) {
+        self.sources.push(SourceReport {
+            source: source.to_string(),
+            storage_name: storage_name.to_string(),
+            products,
+            quality: clean_df.map(quality_report),
+            price_repairs: clean_df.map(count_price_repairs).unwrap_or(0),
+            counts,
+            anomalies: Vec::new(),
+        });
+        if let Some(df) = clean_df {
+            self.flags.push(SourceFlags::from_dataframe(storage_name, df));
+        }
+    }
+
+    /// Mark a recorded source as suspect because of `anomalies`
+    pub fn mark_suspect(&mut self, storage_name: &str, anomalies: Vec<Anomaly>) {
+        if let Some(source) = self.sources.iter_mut().rev().find(|source| source.storage_name == storage_name) {
+            source.anomalies = anomalies;
+        }
+    }
+
+    /// Record a source that failed; `error` is the full anyhow chain
+    pub fn add_failure(&mut self, source: &str, storage_name: &str, error: &anyhow::Error) {
+        self.failures.push(SourceFailure {
+            source: source.to_string(),
+            storage_name: storage_name.to_string(),
+            error: format!("{:#}", error),
+        });
+    }
+
+    /// Record that `source` only succeeded on its retry
+    pub fn mark_retried(&mut self, source: &str) {
+        self.retried.push(source.to_string());
+    }
+
+    pub fn mark_interrupted(&mut self) {
+        self.interrupted = true;
+    }
+
+    pub fn mark_dry_run(&mut self) {
+        self.dry_run = true;
+    }
+
+    pub fn total_products(&self) -> usize {
+        self.sources.iter().map(|source| source.products).sum()
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Flag counts of the sources that produced a clean DataFrame
+    pub fn data_quality(&self) -> DataQualityReport {
+        DataQualityReport {
+            started_at: self.started_at,
+            sources: self.flags.clone(),
+        }
+    }
+}
+
+impl DataQualityReport {
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+impl fmt::Display for DataQualityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for source in &self.sources {
+            write!(f, "{}: {} of {} rows flagged", source.storage_name, source.flagged_rows, source.rows)?;
+            if !source.flags.is_empty() {
+                let counts: Vec<String> = source.flags.iter().map(|(flag, count)| format!("{} {}", flag, count)).collect();
+                write!(f, " ({})", counts.join(", "))?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for RunReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Run {} started {}: {} products from {} sources",
+            self.mode,
+            self.started_at.format("%Y-%m-%d %H:%M:%S"),
+            self.total_products(),
+            self.sources.len()
+        )?;
+        if self.dry_run {
+            writeln!(f, "DRY RUN: nothing was stored")?;
+        }
+        if self.interrupted {
+            writeln!(f, "Interrupted before all sources were processed")?;
+        }
+        for source in &self.sources {
+            if source.price_repairs > 0 {
+                writeln!(
+                    f,
+                    "{} ({} products, {} with cost_price above mrp)",
+                    source.storage_name, source.products, source.price_repairs
+                )?;
+            } else {
+                writeln!(f, "{} ({} products)", source.storage_name, source.products)?;
+            }
+            if source.is_suspect() {
+                let anomalies: Vec<String> = source.anomalies.iter().map(|anomaly| anomaly.to_string()).collect();
+                writeln!(f, "  SUSPECT: {}", anomalies.join("; "))?;
+            }
+            if let Some(counts) = source.counts {
+                writeln!(
+                    f,
+                    "  raw {} -> flattened {} -> clean {} ({:.2}% dropped)",
+                    counts.raw,
+                    counts.flattened,
+                    counts.clean,
+                    counts.drop_pct()
+                )?;
+                if counts.out_of_stock > 0 {
+                    writeln!(f, "  {} out-of-stock rows left out", counts.out_of_stock)?;
+                }
+            }
+            if let Some(ref quality) = source.quality {
+                write!(f, "{}", quality)?;
+            }
+        }
+        for source in &self.retried {
+            writeln!(f, "{} succeeded on retry", source)?;
+        }
+        for failure in &self.failures {
+            writeln!(f, "{} failed: {}", failure.storage_name, failure.error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Rows flagged by `RuleNormalizer` for a cost_price above their mrp
+fn count_price_repairs(df: &DataFrame) -> usize {
+    let Ok(flags) = df.column(QUALITY_FLAGS_FIELD).and_then(|column| column.str().cloned()) else {
+        return 0;
+    };
+    flags
+        .into_iter()
+        .flatten()
+        .filter(|flags| flags.split(',').any(|flag| flag == PRICE_SWAPPED_FLAG || flag == PRICE_SUSPECT_FLAG))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polars::prelude::*;
+
+    #[test]
+    fn test_run_report_embeds_quality_per_source() {
+        let df = df!(
+            "name" => ["milk", "eggs"],
+            "mrp" => [Some(280.0), None]
+        )
+        .unwrap();
+
+        let mut report = RunReport::new("from APIs");
+        report.add_source("krave_mart", "krave_mart_1242164", 2, Some(&df), None);
+        report.add_source("naheed", "naheed", 10, None, None);
+        assert_eq!(report.total_products(), 12);
+
+        let mrp = report.sources[0].quality.as_ref().unwrap().column("mrp").unwrap();
+        assert_eq!(mrp.completeness, 50.0);
+
+        let printed = report.to_string();
+        assert!(printed.contains("12 products from 2 sources"));
+        assert!(printed.contains("krave_mart_1242164 (2 products)"));
+
+        let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(json["sources"][0]["quality"]["rows"], 2);
+        assert!(json["sources"][1]["quality"].is_null());
+        assert_eq!(json["failures"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_run_report_counts_price_repairs() {
+        let df = df!(
+            "name" => ["milk", "eggs", "tea"],
+            QUALITY_FLAGS_FIELD => [Some("ZERO_QUANTITY,PRICE_SWAPPED"), None, Some("ZERO_QUANTITY")]
+        )
+        .unwrap();
+
+        let mut report = RunReport::new("from APIs");
+        report.add_source("krave_mart", "krave_mart_1242164", 3, Some(&df), None);
+        assert_eq!(report.sources[0].price_repairs, 1);
+        assert!(report.to_string().contains("krave_mart_1242164 (3 products, 1 with cost_price above mrp)"));
+    }
+
+    #[test]
+    fn test_data_quality_counts_flags_per_source() {
+        let df = df!(
+            "name" => ["milk", "eggs", "tea"],
+            QUALITY_FLAGS_FIELD => [Some("UNIT_MISSING,PRICE_SWAPPED"), None, Some("UNIT_MISSING")]
+        )
+        .unwrap();
+
+        let mut report = RunReport::new("from APIs");
+        report.add_source("krave_mart", "krave_mart_1242164", 3, Some(&df), None);
+        report.add_source("naheed", "naheed", 2, Some(&df!("name" => ["rice", "oil"]).unwrap()), None);
+        report.add_source("dealcart", "dealcart", 5, None, None);
+
+        let quality = report.data_quality();
+        assert_eq!(quality.sources.len(), 2);
+        assert_eq!(quality.sources[0].flagged_rows, 2);
+        assert_eq!(
+            quality.sources[0].flags,
+            BTreeMap::from([("PRICE_SWAPPED".to_string(), 1), ("UNIT_MISSING".to_string(), 2)])
+        );
+        assert_eq!((quality.sources[1].rows, quality.sources[1].flagged_rows), (2, 0));
+
+        let printed = quality.to_string();
+        assert!(printed.contains("krave_mart_1242164: 2 of 3 rows flagged (PRICE_SWAPPED 1, UNIT_MISSING 2)"));
+        assert!(printed.contains("naheed: 0 of 2 rows flagged\n"));
+        let json: serde_json::Value = serde_json::from_str(&quality.to_json().unwrap()).unwrap();
+        assert_eq!(json["sources"][0]["flags"]["UNIT_MISSING"], 2);
+        // Kept out of the run report itself
+        assert!(serde_json::from_str::<serde_json::Value>(&report.to_json().unwrap()).unwrap()["flags"].is_null());
+    }
+
+    #[test]
+    fn test_run_report_product_counts() {
+        let counts = ProductCounts { raw: 200, flattened: 190, clean: 180, out_of_stock: 0 };
+        assert_eq!(counts.drop_pct(), 10.0);
+        assert_eq!(ProductCounts { raw: 10, flattened: 10, clean: 14, out_of_stock: 0 }.drop_pct(), 0.0);
+        assert_eq!(ProductCounts { raw: 0, flattened: 0, clean: 0, out_of_stock: 0 }.drop_pct(), 0.0);
+        // Out-of-stock rows dropped on request are not lost products
+        assert_eq!(ProductCounts { raw: 200, flattened: 200, clean: 150, out_of_stock: 50 }.drop_pct(), 0.0);
+
+        let mut report = RunReport::new("from APIs");
+        report.add_source("dealcart", "dealcart", 200, None, Some(counts));
+        assert!(report.to_string().contains("raw 200 -> flattened 190 -> clean 180 (10.00% dropped)"));
+        let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(json["sources"][0]["counts"]["flattened"], 190);
+    }
+
+    #[test]
+    fn test_run_report_marks_suspect_sources() {
+        let mut report = RunReport::new("from APIs");
+        report.add_source("naheed", "naheed", 12, None, None);
+        report.add_source("dealcart", "dealcart", 900, None, None);
+        report.mark_suspect("naheed", vec![Anomaly::ProductCount { previous: 1500, current: 12 }]);
+
+        assert!(report.sources[0].is_suspect());
+        assert!(!report.sources[1].is_suspect());
+        assert!(report.to_string().contains("naheed (12 products)\n  SUSPECT: product count 1500 -> 12 (-99.2%)"));
+        let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(json["sources"][0]["anomalies"][0]["kind"], "product_count");
+    }
+
+    #[test]
+    fn test_run_report_records_failures() {
+        let mut report = RunReport::new("from APIs");
+        report.add_source("krave_mart", "krave_mart_1242164", 2, None, None);
+        let error = anyhow::anyhow!("HTTP 503").context("Failed to fetch page 1");
+        report.add_failure("dealcart", "dealcart", &error);
+
+        assert_eq!(report.failures[0].error, "Failed to fetch page 1: HTTP 503");
+        assert!(report.to_string().contains("dealcart failed: Failed to fetch page 1: HTTP 503"));
+
+        let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(json["failures"][0]["source"], "dealcart");
+        assert_eq!(json["interrupted"], false);
+
+        report.mark_interrupted();
+        assert!(report.to_string().contains("Interrupted before all sources were processed"));
+        report.mark_dry_run();
+        assert!(report.to_string().contains("DRY RUN: nothing was stored"));
+    }
+}
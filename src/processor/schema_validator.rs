@@ -0,0 +1,120 @@
+use anyhow::{Context, Result, anyhow};
+use jsonschema::Validator;
+use serde_json::Value;
+
+/// Validation errors listed per record before the rest are summarized
+const MAX_REPORTED_ERRORS: usize = 3;
+
+/// Checks raw records against a source's JSON Schema before flattening.
+/// Plugged into `JsonFlattener::with_record_validator`, so invalid records
+/// end up in the failed-record report instead of the dataframe.
+pub struct SchemaValidator {
+    validator: Validator,
+}
+
+impl SchemaValidator {
+    pub fn from_file(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read JSON schema {}", path))?;
+        let schema: Value = serde_json::from_str(&content)
+            .with_context(|| format!("JSON schema {} is not valid JSON", path))?;
+        Self::from_value(&schema).with_context(|| format!("Invalid JSON schema {}", path))
+    }
+
+    pub fn from_value(schema: &Value) -> Result<Self> {
+        let validator = jsonschema::validator_for(schema).map_err(|e| anyhow!("{}", e))?;
+        Ok(SchemaValidator { validator })
+    }
+
+    /// Check one record, describing the first few violations on failure
+    pub fn validate(&self, record: &Value) -> Result<()> {
+        let errors: Vec<String> = self
+            .validator
+            .iter_errors(record)
+            .map(|error| {
+                let path = error.instance_path.as_str();
+                let path = if path.is_empty() { "/" } else { path };
+                format!("{}: {}", path, error)
+            })
+            .collect();
+        if errors.is_empty() {
+            return Ok(());
+        }
+
+        let mut message = errors[..errors.len().min(MAX_REPORTED_ERRORS)].join("; ");
+        if errors.len() > MAX_REPORTED_ERRORS {
+            message.push_str(&format!(" (and {} more)", errors.len() - MAX_REPORTED_ERRORS));
+        }
+        Err(anyhow!("Schema validation failed: {}", message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::JsonFlattener;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn assert_schema(path: &str, valid: Value, invalid: Value, expected_error: &str) {
+        let validator = SchemaValidator::from_file(path).unwrap();
+        assert!(validator.validate(&valid).is_ok(), "{} rejects {}", path, valid);
+        let err = validator.validate(&invalid).unwrap_err().to_string();
+        assert!(err.contains(expected_error), "unexpected error for {}: {}", path, err);
+    }
+
+    #[test]
+    fn test_krave_mart_schema() {
+        assert_schema(
+            "src/configs/schemas/krave_mart.schema.json",
+            json!({
+                "product_id": 103922,
+                "sku": "BNDL7002230",
+                "name": "Kfresh Potatoes (Aalu) - 3 Kg",
+                "product_price": "390.00",
+                "special_price": "234.00",
+                "mrp": null,
+                "cost_price": null,
+                "categories": [{"category_name": "Fruits & Vegetables", "category_id": 4960}]
+            }),
+            json!({"product_id": 103923, "cost_price": {"amount": 120}}),
+            "\"name\" is a required property",
+        );
+    }
+
+    #[test]
+    fn test_bazaar_app_schema() {
+        assert_schema(
+            "src/configs/schemas/bazaar_app.schema.json",
+            json!({
+                "id": 7001,
+                "productName": "Basmati Rice",
+                "category": "Rice",
+                "variants": [{"sku": "RICE-1KG", "actualPrice": 400, "discountedPrice": 380}]
+            }),
+            json!({"id": 7002, "name": "Sugar", "actualPrice": [150]}),
+            "/actualPrice",
+        );
+    }
+
+    #[test]
+    fn test_invalid_records_are_reported_as_failures() {
+        let validator = Arc::new(
+            SchemaValidator::from_file("src/configs/schemas/krave_mart.schema.json").unwrap(),
+        );
+        let flattener =
+            JsonFlattener::new().with_record_validator(move |record| validator.validate(record));
+        let products = vec![
+            json!({"product_id": 1, "name": "Milk 1 L", "cost_price": 280}),
+            json!({"product_id": 2, "cost_price": 150}),
+            json!({"product_id": 3, "name": "Eggs", "mrp": 380}),
+        ];
+
+        let output = flattener.flatten_to_dataframe(&products).unwrap();
+        assert_eq!(output.dataframe.height(), 2);
+        assert_eq!(output.failures.len(), 1);
+        assert_eq!(output.failures[0].index, 1);
+        assert_eq!(output.failures[0].product_id.as_deref(), Some("2"));
+        assert!(output.failures[0].reason.starts_with("Schema validation failed"));
+    }
+}
@@ -0,0 +1,440 @@
+use anyhow::{Context, Result, anyhow};
+use chrono::NaiveDate;
+use polars::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use tracing::{info, warn};
+
+use crate::storage::MinioStorage;
+
+/// Change types emitted in the `change_type` column
+pub const CHANGE_ADDED: &str = "added";
+pub const CHANGE_REMOVED: &str = "removed";
+pub const CHANGE_PRICE_CHANGED: &str = "price_changed";
+
+/// Price snapshot of a single product, keyed by `product_id`
+#[derive(Debug, Clone, PartialEq)]
+struct ProductSnapshot {
+    name: Option<String>,
+    cost_price: Option<f64>,
+    mrp: Option<f64>,
+    discount: Option<f64>,
+}
+
+/// A diff split by change type, each frame with the columns of `SnapshotDiff::diff`
+#[derive(Debug, Clone)]
+pub struct DiffBuckets {
+    pub added: DataFrame,
+    pub removed: DataFrame,
+    /// Products whose cost_price, mrp or discount moved
+    pub changed: DataFrame,
+}
+
+/// Compares two clean snapshots of the same source by `product_id`
+pub struct SnapshotDiff;
+
+impl SnapshotDiff {
+    pub fn new() -> Self {
+        SnapshotDiff
+    }
+
+    /// Build a changes DataFrame from the previous and current clean snapshots.
+    ///
+    /// One row per added product, removed product, or product whose
+    /// `cost_price`, `mrp` or `discount` moved, with before/after values,
+    /// deltas and the price changes in percent of the previous price.
+    pub fn diff(&self, previous: &DataFrame, current: &DataFrame) -> Result<DataFrame> {
+        let previous = Self::index_by_product_id(previous)?;
+        let current = Self::index_by_product_id(current)?;
+
+        let mut product_ids = Vec::new();
+        let mut change_types = Vec::new();
+        let mut names = Vec::new();
+        let mut previous_cost_prices = Vec::new();
+        let mut current_cost_prices = Vec::new();
+        let mut cost_price_deltas = Vec::new();
+        let mut previous_mrps = Vec::new();
+        let mut current_mrps = Vec::new();
+        let mut mrp_deltas = Vec::new();
+        let mut cost_price_change_pcts = Vec::new();
+        let mut mrp_change_pcts = Vec::new();
+        let mut previous_discounts = Vec::new();
+        let mut current_discounts = Vec::new();
+
+        let mut push = |product_id: &str,
+                        change_type: &str,
+                        before: Option<&ProductSnapshot>,
+                        after: Option<&ProductSnapshot>| {
+            let before_cost = before.and_then(|p| p.cost_price);
+            let after_cost = after.and_then(|p| p.cost_price);
+            let before_mrp = before.and_then(|p| p.mrp);
+            let after_mrp = after.and_then(|p| p.mrp);
+
+            product_ids.push(product_id.to_string());
+            change_types.push(change_type.to_string());
+            names.push(
+                after
+                    .and_then(|p| p.name.clone())
+                    .or_else(|| before.and_then(|p| p.name.clone())),
+            );
+            previous_cost_prices.push(before_cost);
+            current_cost_prices.push(after_cost);
+            cost_price_deltas.push(Self::delta(before_cost, after_cost));
+            previous_mrps.push(before_mrp);
+            current_mrps.push(after_mrp);
+            mrp_deltas.push(Self::delta(before_mrp, after_mrp));
+            cost_price_change_pcts.push(Self::change_pct(before_cost, after_cost));
+            mrp_change_pcts.push(Self::change_pct(before_mrp, after_mrp));
+            previous_discounts.push(before.and_then(|p| p.discount));
+            current_discounts.push(after.and_then(|p| p.discount));
+        };
+
+        for (product_id, after) in &current {
+            match previous.get(product_id) {
+                None => push(product_id, CHANGE_ADDED, None, Some(after)),
+                Some(before) => {
+                    if Self::price_changed(before.cost_price, after.cost_price)
+                        || Self::price_changed(before.mrp, after.mrp)
+                        || Self::price_changed(before.discount, after.discount)
+                    {
+                        push(product_id, CHANGE_PRICE_CHANGED, Some(before), Some(after));
+                    }
+                }
+            }
+        }
+
+        for (product_id, before) in &previous {
+            if !current.contains_key(product_id) {
+                push(product_id, CHANGE_REMOVED, Some(before), None);
+            }
+        }
+
+        let df = DataFrame::new(vec![
+            Series::new("product_id".into(), product_ids).into(),
+            Series::new("change_type".into(), change_types).into(),
+            Series::new("name".into(), names).into(),
+            Series::new("previous_cost_price".into(), previous_cost_prices).into(),
+            Series::new("current_cost_price".into(), current_cost_prices).into(),
+            Series::new("cost_price_delta".into(), cost_price_deltas).into(),
+            Series::new("previous_mrp".into(), previous_mrps).into(),
+            Series::new("current_mrp".into(), current_mrps).into(),
+            Series::new("mrp_delta".into(), mrp_deltas).into(),
+            Series::new("cost_price_change_pct".into(), cost_price_change_pcts).into(),
+            Series::new("mrp_change_pct".into(), mrp_change_pcts).into(),
+            Series::new("previous_discount".into(), previous_discounts).into(),
+            Series::new("current_discount".into(), current_discounts).into(),
+        ])?;
+
+        Ok(df)
+    }
+
+    /// Split the rows of `diff` into added, removed and changed products
+    pub fn split(&self, changes: &DataFrame) -> Result<DiffBuckets> {
+        let of_type = |change_type: &str| -> Result<DataFrame> {
+            let mask = changes.column("change_type")?.str()?.equal(change_type);
+            Ok(changes.filter(&mask)?)
+        };
+
+        Ok(DiffBuckets {
+            added: of_type(CHANGE_ADDED)?,
+            removed: of_type(CHANGE_REMOVED)?,
+            changed: of_type(CHANGE_PRICE_CHANGED)?,
+        })
+    }
+
+    /// Count rows per change type, for logging
+    pub fn summarize(&self, changes: &DataFrame) -> Result<HashMap<String, usize>> {
+        let mut counts = HashMap::new();
+        for change_type in changes.column("change_type")?.str()?.into_iter().flatten() {
+            *counts.entry(change_type.to_string()).or_insert(0) += 1;
+        }
+        Ok(counts)
+    }
+
+    fn index_by_product_id(df: &DataFrame) -> Result<BTreeMap<String, ProductSnapshot>> {
+        let product_ids = df
+            .column("product_id")
+            .map_err(|_| anyhow!("Snapshot is missing the product_id column"))?
+            .cast(&DataType::String)?;
+        let product_ids = product_ids.str()?;
+
+        let names = Self::optional_string_column(df, "name")?;
+        let cost_prices = Self::optional_price_column(df, "cost_price")?;
+        let mrps = Self::optional_price_column(df, "mrp")?;
+        let discounts = Self::optional_price_column(df, "discount")?;
+
+        let mut index = BTreeMap::new();
+        for (i, product_id) in product_ids.into_iter().enumerate() {
+            let Some(product_id) = product_id.map(str::trim).filter(|id| !id.is_empty()) else {
+                continue;
+            };
+
+            // Later rows win if a snapshot contains the same product twice
+            index.insert(
+                product_id.to_string(),
+                ProductSnapshot {
+                    name: names.as_ref().and_then(|c| c.get(i).map(str::to_string)),
+                    cost_price: cost_prices.as_ref().and_then(|c| c.get(i)),
+                    mrp: mrps.as_ref().and_then(|c| c.get(i)),
+                    discount: discounts.as_ref().and_then(|c| c.get(i)),
+                },
+            );
+        }
+
+        Ok(index)
+    }
+
+    fn optional_string_column(df: &DataFrame, name: &str) -> Result<Option<StringChunked>> {
+        match df.column(name) {
+            Ok(column) => Ok(Some(column.cast(&DataType::String)?.str()?.clone())),
+            Err(_) => Ok(None),
+        }
+    }
+
+    fn optional_price_column(df: &DataFrame, name: &str) -> Result<Option<Float64Chunked>> {
+        match df.column(name) {
+            Ok(column) => Ok(Some(column.cast(&DataType::Float64)?.f64()?.clone())),
+            Err(_) => Ok(None),
+        }
+    }
+
+    fn price_changed(before: Option<f64>, after: Option<f64>) -> bool {
+        match (before, after) {
+            (Some(b), Some(a)) => (a - b).abs() > f64::EPSILON,
+            (None, None) => false,
+            _ => true,
+        }
+    }
+
+    fn delta(before: Option<f64>, after: Option<f64>) -> Option<f64> {
+        match (before, after) {
+            (Some(b), Some(a)) => Some(a - b),
+            _ => None,
+        }
+    }
+
+    /// Rounded to two decimals; `None` without a non-zero previous price
+    fn change_pct(before: Option<f64>, after: Option<f64>) -> Option<f64> {
+        match (before, after) {
+            (Some(b), Some(a)) if b != 0.0 => Some(((a - b) / b * 10_000.0).round() / 100.0),
+            _ => None,
+        }
+    }
+}
+
+/// Which clean snapshots of a source `snapshot_diff` compares
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffTarget {
+    /// The two most recent, stored as `changes/<source>/<today>.parquet`
+    Latest,
+    /// The last of each of two days, stored as
+    /// `reports/<source>/diff_<a>_<b>.parquet`
+    Dates(NaiveDate, NaiveDate),
+}
+
+/// Diff two clean snapshots of `source` picked by `target`, log how many
+/// products were added, removed and changed, and store the changes (with the
+/// columns of `SnapshotDiff::diff`) under the key `target` names. `None` when
+/// `Latest` finds fewer than two snapshots.
+pub async fn snapshot_diff(storage: &MinioStorage, source: &str, target: DiffTarget) -> Result<Option<DiffBuckets>> {
+    let (key_a, key_b) = match target {
+        DiffTarget::Latest => {
+            let clean_files = storage.list_clean_files(source).await?;
+            match clean_files.as_slice() {
+                [current, previous, ..] => (previous.clone(), current.clone()),
+                _ => {
+                    warn!("Need at least two clean snapshots to diff {}, found {}", source, clean_files.len());
+                    return Ok(None);
+                }
+            }
+        }
+        DiffTarget::Dates(date_a, date_b) => (
+            storage.get_clean_file_for_date(source, date_a).await?,
+            storage.get_clean_file_for_date(source, date_b).await?,
+        ),
+    };
+    info!("Comparing {} against {}", key_b, key_a);
+    let previous = storage.load_parquet(&key_a).await?;
+    let current = storage.load_parquet(&key_b).await?;
+
+    let differ = SnapshotDiff::new();
+    let mut changes = differ.diff(&previous, &current)?;
+    let buckets = differ.split(&changes)?;
+    info!(
+        "{}: {} added, {} removed, {} changed",
+        source,
+        buckets.added.height(),
+        buckets.removed.height(),
+        buckets.changed.height()
+    );
+
+    let mut buf = Vec::new();
+    ParquetWriter::new(&mut buf).finish(&mut changes)?;
+    let stored = match target {
+        DiffTarget::Latest => storage.store_changes(source, chrono::Utc::now().date_naive(), &buf).await,
+        DiffTarget::Dates(date_a, date_b) => storage.store_snapshot_diff(source, date_a, date_b, &buf).await,
+    };
+    let key = stored.with_context(|| format!("Failed to store the diff of {}", source))?;
+    info!("Stored snapshot diff at: {}", key);
+
+    Ok(Some(buckets))
+}
+
+impl Default for SnapshotDiff {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(df: &DataFrame, column: &str) -> Vec<Option<f64>> {
+        df.column(column).unwrap().f64().unwrap().into_iter().collect()
+    }
+
+    fn snapshot(ids: &[&str], cost_prices: &[Option<f64>], mrps: &[Option<f64>]) -> DataFrame {
+        let names: Vec<String> = ids.iter().map(|id| format!("product {}", id)).collect();
+        DataFrame::new(vec![
+            Series::new("product_id".into(), ids.to_vec()).into(),
+            Series::new("name".into(), names).into(),
+            Series::new("cost_price".into(), cost_prices.to_vec()).into(),
+            Series::new("mrp".into(), mrps.to_vec()).into(),
+        ])
+        .unwrap()
+    }
+
+    fn change_type_of(changes: &DataFrame, product_id: &str) -> Option<String> {
+        let ids = changes.column("product_id").unwrap().str().unwrap().clone();
+        let types = changes.column("change_type").unwrap().str().unwrap().clone();
+        ids.into_iter()
+            .position(|id| id == Some(product_id))
+            .and_then(|i| types.get(i).map(str::to_string))
+    }
+
+    #[test]
+    fn test_diff_detects_added_removed_and_price_changes() {
+        let previous = snapshot(
+            &["1", "2", "3"],
+            &[Some(100.0), Some(50.0), Some(20.0)],
+            &[Some(120.0), Some(60.0), Some(25.0)],
+        );
+        let current = snapshot(
+            &["1", "2", "4"],
+            &[Some(90.0), Some(50.0), Some(10.0)],
+            &[Some(120.0), Some(60.0), Some(12.0)],
+        );
+
+        let differ = SnapshotDiff::new();
+        let changes = differ.diff(&previous, &current).unwrap();
+
+        assert_eq!(changes.height(), 3);
+        assert_eq!(change_type_of(&changes, "1").as_deref(), Some(CHANGE_PRICE_CHANGED));
+        assert_eq!(change_type_of(&changes, "2"), None);
+        assert_eq!(change_type_of(&changes, "3").as_deref(), Some(CHANGE_REMOVED));
+        assert_eq!(change_type_of(&changes, "4").as_deref(), Some(CHANGE_ADDED));
+
+        let summary = differ.summarize(&changes).unwrap();
+        assert_eq!(summary.get(CHANGE_ADDED), Some(&1));
+        assert_eq!(summary.get(CHANGE_REMOVED), Some(&1));
+        assert_eq!(summary.get(CHANGE_PRICE_CHANGED), Some(&1));
+    }
+
+    #[test]
+    fn test_diff_price_deltas() {
+        let previous = snapshot(&["1"], &[Some(100.0)], &[Some(120.0)]);
+        let current = snapshot(&["1"], &[Some(90.0)], &[Some(130.0)]);
+
+        let changes = SnapshotDiff::new().diff(&previous, &current).unwrap();
+
+        let cost_delta = changes.column("cost_price_delta").unwrap().f64().unwrap().get(0);
+        let mrp_delta = changes.column("mrp_delta").unwrap().f64().unwrap().get(0);
+        assert_eq!(cost_delta, Some(-10.0));
+        assert_eq!(mrp_delta, Some(10.0));
+    }
+
+    #[test]
+    fn test_diff_requires_product_id() {
+        let df = DataFrame::new(vec![Series::new("name".into(), vec!["Milk"]).into()]).unwrap();
+        assert!(SnapshotDiff::new().diff(&df, &df).is_err());
+    }
+
+    #[test]
+    fn test_diff_buckets() {
+        let previous = df!(
+            "product_id" => ["1", "2", "3", "5"],
+            "name" => ["Milk", "Eggs", "Tea", "Rice"],
+            "cost_price" => [Some(200.0), Some(300.0), Some(500.0), Some(90.0)],
+            "mrp" => [Some(250.0), Some(300.0), Some(600.0), Some(100.0)],
+            "discount" => [Some(20.0), Some(0.0), Some(16.67), Some(10.0)]
+        )
+        .unwrap();
+        let current = df!(
+            "product_id" => ["1", "2", "4", "5"],
+            "name" => ["Milk", "Eggs", "Bread", "Rice"],
+            "cost_price" => [Some(250.0), Some(300.0), Some(120.0), Some(90.0)],
+            "mrp" => [Some(250.0), Some(300.0), Some(150.0), Some(100.0)],
+            "discount" => [Some(0.0), Some(0.0), Some(20.0), Some(5.0)]
+        )
+        .unwrap();
+
+        let differ = SnapshotDiff::new();
+        let buckets = differ.split(&differ.diff(&previous, &current).unwrap()).unwrap();
+        let ids = |df: &DataFrame| -> Vec<Option<String>> {
+            df.column("product_id").unwrap().str().unwrap().into_iter().map(|id| id.map(str::to_string)).collect()
+        };
+
+        assert_eq!(ids(&buckets.added), vec![Some("4".to_string())]);
+        assert_eq!(values(&buckets.added, "current_cost_price"), vec![Some(120.0)]);
+        assert_eq!(values(&buckets.added, "previous_cost_price"), vec![None]);
+
+        assert_eq!(ids(&buckets.removed), vec![Some("3".to_string())]);
+        assert_eq!(values(&buckets.removed, "previous_mrp"), vec![Some(600.0)]);
+
+        // Rice only changed its discount
+        assert_eq!(ids(&buckets.changed), vec![Some("1".to_string()), Some("5".to_string())]);
+        assert_eq!(values(&buckets.changed, "previous_cost_price"), vec![Some(200.0), Some(90.0)]);
+        assert_eq!(values(&buckets.changed, "current_cost_price"), vec![Some(250.0), Some(90.0)]);
+        assert_eq!(values(&buckets.changed, "cost_price_change_pct"), vec![Some(25.0), Some(0.0)]);
+        assert_eq!(values(&buckets.changed, "mrp_change_pct"), vec![Some(0.0), Some(0.0)]);
+        assert_eq!(values(&buckets.changed, "previous_discount"), vec![Some(20.0), Some(10.0)]);
+        assert_eq!(values(&buckets.changed, "current_discount"), vec![Some(0.0), Some(5.0)]);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_diff_between_dates() {
+        use crate::storage::backend::{MemoryBackend, ObjectBackend};
+
+        let clean = MemoryBackend::new("pipeline-clean");
+        let storage = MinioStorage::with_backends(Box::new(MemoryBackend::new("pipeline-raw")), Box::new(clean.clone()));
+        let snapshots = [
+            ("clean/naheed/20250914-080000.parquet", snapshot(&["1", "2"], &[Some(100.0), Some(50.0)], &[None, None])),
+            ("clean/naheed/20250915-080000.parquet", snapshot(&["1"], &[Some(80.0)], &[None])),
+            ("clean/naheed/20250915-200000.parquet", snapshot(&["1", "3"], &[Some(100.0), Some(10.0)], &[None, None])),
+        ];
+        for (key, mut df) in snapshots {
+            let mut buf = Vec::new();
+            ParquetWriter::new(&mut buf).finish(&mut df).unwrap();
+            clean.put_object(key, &buf).await.unwrap();
+        }
+
+        let date_a = NaiveDate::from_ymd_opt(2025, 9, 14).unwrap();
+        let date_b = NaiveDate::from_ymd_opt(2025, 9, 15).unwrap();
+        // The evening snapshot of the 15th is compared, in which product 1 is back to 100
+        let buckets = snapshot_diff(&storage, "naheed", DiffTarget::Dates(date_a, date_b)).await.unwrap().unwrap();
+        assert_eq!((buckets.added.height(), buckets.removed.height(), buckets.changed.height()), (1, 1, 0));
+        let by_dates = storage.load_parquet("reports/naheed/diff_2025-09-14_2025-09-15.parquet").await.unwrap();
+
+        // Without dates the two latest snapshots of the 15th are compared
+        let buckets = snapshot_diff(&storage, "naheed", DiffTarget::Latest).await.unwrap().unwrap();
+        assert_eq!((buckets.added.height(), buckets.removed.height(), buckets.changed.height()), (1, 0, 1));
+        let today = chrono::Utc::now().date_naive();
+        let latest = storage.load_parquet(&format!("changes/naheed/{}.parquet", today.format("%Y-%m-%d"))).await.unwrap();
+        assert_eq!(latest.schema(), by_dates.schema());
+
+        let missing = NaiveDate::from_ymd_opt(2025, 9, 16).unwrap();
+        let error = snapshot_diff(&storage, "naheed", DiffTarget::Dates(date_a, missing)).await.unwrap_err().to_string();
+        assert!(error.contains("Available dates: 2025-09-14, 2025-09-15"), "{}", error);
+        assert!(snapshot_diff(&storage, "krave_mart", DiffTarget::Latest).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,42 @@
+use anyhow::Result;
+use polars::prelude::*;
+
+use super::json_flattener::IN_STOCK_FIELD;
+
+/// Drop the rows `in_stock` marks as out of stock (`--drop-out-of-stock`),
+/// returning how many were dropped. Rows whose stock is unknown are kept, as
+/// is every row of a source without an `in_stock` column.
+pub fn drop_out_of_stock(df: &mut DataFrame) -> Result<usize> {
+    let Ok(in_stock) = df.column(IN_STOCK_FIELD) else {
+        return Ok(0);
+    };
+    let in_stock = in_stock.cast(&DataType::Boolean)?;
+    let keep: BooleanChunked = in_stock
+        .bool()?
+        .into_iter()
+        .map(|available| available != Some(false))
+        .collect();
+    let before = df.height();
+    *df = df.filter(&keep)?;
+    Ok(before - df.height())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drops_only_rows_known_to_be_out_of_stock() {
+        let mut df = df!(
+            "product_id" => ["1", "2", "3", "4"],
+            IN_STOCK_FIELD => [Some(true), Some(false), None, Some(false)],
+        )
+        .unwrap();
+        assert_eq!(drop_out_of_stock(&mut df).unwrap(), 2);
+        let ids: Vec<Option<&str>> = df.column("product_id").unwrap().str().unwrap().into_iter().collect();
+        assert_eq!(ids, vec![Some("1"), Some("3")]);
+
+        let mut no_stock = df!("product_id" => ["1"]).unwrap();
+        assert_eq!(drop_out_of_stock(&mut no_stock).unwrap(), 0);
+    }
+}
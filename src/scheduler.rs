@@ -0,0 +1,261 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::warn;
+
+use crate::config::SourceSchedule;
+
+/// Where `serve` gets the time from, so schedules can be tested without waiting
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Sleep until `at` by `clock`, straight away if it has passed
+pub async fn sleep_until(clock: &dyn Clock, at: DateTime<Utc>) {
+    if let Ok(wait) = (at - clock.now()).to_std() {
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// When one scheduled source runs next
+pub struct SourceTimer {
+    pub source_name: String,
+    schedule: SourceSchedule,
+    jitter: Duration,
+    /// Slot of the previous run, before jitter, so jitter never adds up
+    previous: Option<DateTime<Utc>>,
+}
+
+impl SourceTimer {
+    pub fn new(source_name: &str, schedule: SourceSchedule, jitter: Duration) -> Self {
+        SourceTimer {
+            source_name: source_name.to_string(),
+            schedule,
+            jitter,
+            previous: None,
+        }
+    }
+
+    /// When to start the next run: the schedule's next slot plus up to
+    /// `jitter` of random delay. `None` once the schedule has no more slots.
+    pub fn next_run(&mut self, clock: &dyn Clock, rng: &mut impl Rng) -> Option<DateTime<Utc>> {
+        let slot = self.schedule.next_run(self.previous, clock.now())?;
+        self.previous = Some(slot);
+        let jitter_ms = rng.gen_range(0..=self.jitter.as_millis() as u64);
+        Some(slot + chrono::Duration::milliseconds(jitter_ms as i64))
+    }
+}
+
+/// Sources with a run in flight, so a source still running when its next
+/// slot comes up skips that slot instead of running twice at once
+#[derive(Debug, Default)]
+pub struct InFlight {
+    running: Mutex<HashSet<String>>,
+}
+
+impl InFlight {
+    /// Mark `source_name` as running until the returned guard is dropped;
+    /// `None` if it already is
+    pub fn try_start(self: &Arc<Self>, source_name: &str) -> Option<RunGuard> {
+        if !self.running.lock().unwrap().insert(source_name.to_string()) {
+            return None;
+        }
+        Some(RunGuard {
+            in_flight: self.clone(),
+            source_name: source_name.to_string(),
+        })
+    }
+}
+
+/// A source's run in flight, see `InFlight::try_start`
+pub struct RunGuard {
+    in_flight: Arc<InFlight>,
+    source_name: String,
+}
+
+impl RunGuard {
+    pub fn source_name(&self) -> &str {
+        &self.source_name
+    }
+}
+
+impl Drop for RunGuard {
+    fn drop(&mut self) {
+        self.in_flight.running.lock().unwrap().remove(&self.source_name);
+    }
+}
+
+/// How the latest scheduled run of a source went
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunState {
+    /// No run has started yet
+    Waiting,
+    Running,
+    Succeeded,
+    /// The run failed, or some of the source's stores did
+    Failed,
+}
+
+/// What `--status-file` records for each scheduled source
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceStatus {
+    pub state: RunState,
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub last_started_at: Option<DateTime<Utc>>,
+    pub last_finished_at: Option<DateTime<Utc>>,
+    /// Products stored by the last finished run
+    pub last_products: Option<usize>,
+    pub last_error: Option<String>,
+    /// Slots skipped because the previous run was still going
+    pub skipped_runs: usize,
+}
+
+impl Default for SourceStatus {
+    fn default() -> Self {
+        SourceStatus {
+            state: RunState::Waiting,
+            next_run_at: None,
+            last_started_at: None,
+            last_finished_at: None,
+            last_products: None,
+            last_error: None,
+            skipped_runs: 0,
+        }
+    }
+}
+
+/// The status of every scheduled source, rewritten to `--status-file` on
+/// each change so it can be read while `serve` runs
+#[derive(Debug, Default)]
+pub struct StatusFile {
+    path: Option<PathBuf>,
+    sources: Mutex<BTreeMap<String, SourceStatus>>,
+}
+
+impl StatusFile {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        StatusFile {
+            path,
+            sources: Mutex::default(),
+        }
+    }
+
+    /// Apply `change` to the status of `source_name` and write the file.
+    /// A failed write is logged, it never stops the schedule.
+    pub fn update(&self, source_name: &str, change: impl FnOnce(&mut SourceStatus)) {
+        let mut sources = self.sources.lock().unwrap();
+        change(sources.entry(source_name.to_string()).or_default());
+        if let Err(e) = self.write(&sources) {
+            warn!("Failed to update the status file: {:#}", e);
+        }
+    }
+
+    fn write(&self, sources: &BTreeMap<String, SourceStatus>) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        // Written next to the file and renamed over it, so readers never see half of it
+        let partial = path.with_extension("partial");
+        std::fs::write(&partial, serde_json::to_string_pretty(sources)?)
+            .with_context(|| format!("Failed to write {}", partial.display()))?;
+        std::fs::rename(&partial, path).with_context(|| format!("Failed to replace {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    /// A clock that only moves when told to
+    struct ManualClock(Mutex<DateTime<Utc>>);
+
+    impl ManualClock {
+        fn set(&self, time: DateTime<Utc>) {
+            *self.0.lock().unwrap() = time;
+        }
+    }
+
+    impl Clock for ManualClock {
+        fn now(&self) -> DateTime<Utc> {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    fn at(time: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_timer_adds_jitter_without_drifting() {
+        let clock = ManualClock(Mutex::new(at("2025-09-15T10:00:00Z")));
+        let mut rng = StdRng::seed_from_u64(7);
+        let schedule = SourceSchedule::Every(Duration::from_secs(3600));
+        let mut timer = SourceTimer::new("dealcart", schedule, Duration::from_secs(60));
+
+        let mut slot = at("2025-09-15T10:00:00Z");
+        for _ in 0..5 {
+            let run_at = timer.next_run(&clock, &mut rng).unwrap();
+            assert!(run_at >= slot && run_at <= slot + chrono::Duration::seconds(60), "{} for slot {}", run_at, slot);
+            clock.set(run_at);
+            slot += chrono::Duration::hours(1);
+        }
+
+        // Without jitter a run starts right on its slot
+        let schedule = "30 2 * * *".parse().unwrap();
+        let mut timer = SourceTimer::new("naheed", schedule, Duration::ZERO);
+        clock.set(at("2025-09-15T10:00:00Z"));
+        assert_eq!(timer.next_run(&clock, &mut rng), Some(at("2025-09-16T02:30:00Z")));
+        clock.set(at("2025-09-16T02:30:00Z"));
+        assert_eq!(timer.next_run(&clock, &mut rng), Some(at("2025-09-17T02:30:00Z")));
+    }
+
+    #[test]
+    fn test_overlapping_runs_of_a_source_are_refused() {
+        let in_flight = Arc::new(InFlight::default());
+        let naheed = in_flight.try_start("naheed").unwrap();
+        assert_eq!(naheed.source_name(), "naheed");
+        assert!(in_flight.try_start("naheed").is_none());
+
+        // Other sources are not held up
+        let dealcart = in_flight.try_start("dealcart");
+        assert!(dealcart.is_some());
+
+        drop(naheed);
+        assert!(in_flight.try_start("naheed").is_some());
+    }
+
+    #[test]
+    fn test_status_file_is_rewritten_on_each_change() {
+        let path = std::env::temp_dir().join(format!("serve_status_{}.json", std::process::id()));
+        let status = StatusFile::new(Some(path.clone()));
+        status.update("naheed", |source| source.state = RunState::Running);
+        status.update("naheed", |source| {
+            source.state = RunState::Failed;
+            source.last_error = Some("HTTP 503".to_string());
+        });
+        status.update("dealcart", |source| source.skipped_runs += 1);
+
+        let written: BTreeMap<String, SourceStatus> = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written["naheed"].state, RunState::Failed);
+        assert_eq!(written["naheed"].last_error.as_deref(), Some("HTTP 503"));
+        assert_eq!(written["dealcart"], SourceStatus { skipped_runs: 1, ..SourceStatus::default() });
+        assert_eq!(written["dealcart"].state, RunState::Waiting);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
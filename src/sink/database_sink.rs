@@ -0,0 +1,356 @@
+use anyhow::{Result, anyhow};
+use chrono::NaiveDate;
+use polars::prelude::*;
+use std::collections::HashMap;
+use tracing::warn;
+
+use super::sqlite_sink::SqliteSink;
+use crate::config::DatabaseConfig;
+
+#[cfg(feature = "postgres")]
+use super::postgres_sink::PostgresSink;
+
+/// Table the processed products are upserted into, one row per snapshot
+pub const PRODUCTS_TABLE: &str = "products";
+
+/// Table holding the latest snapshot of every product, one row per
+/// `(source, product_id)`
+pub const CURRENT_PRODUCTS_TABLE: &str = "products_current";
+
+/// Rows per INSERT statement and transaction, well below the parameter
+/// limits of both SQLite (32766) and Postgres (65535)
+pub(crate) const UPSERT_BATCH_ROWS: usize = 500;
+
+/// SQL flavour a statement is written for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Dialect {
+    Sqlite,
+    #[cfg_attr(not(feature = "postgres"), allow(dead_code))]
+    Postgres,
+}
+
+/// A table the products are upserted into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ProductsTable {
+    /// `PRODUCTS_TABLE`, keyed on `(source, product_id, snapshot_date)`
+    Snapshots,
+    /// `CURRENT_PRODUCTS_TABLE`, keyed on `(source, product_id)`. A row is
+    /// only replaced by the same or a later snapshot, so re-running an old
+    /// day leaves the current state alone.
+    Current,
+}
+
+impl ProductsTable {
+    pub(crate) const ALL: [ProductsTable; 2] = [ProductsTable::Snapshots, ProductsTable::Current];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            ProductsTable::Snapshots => PRODUCTS_TABLE,
+            ProductsTable::Current => CURRENT_PRODUCTS_TABLE,
+        }
+    }
+
+    /// Number of leading `COLUMNS` forming the primary key
+    fn key_len(self) -> usize {
+        match self {
+            ProductsTable::Snapshots => KEY_COLUMNS,
+            ProductsTable::Current => KEY_COLUMNS - 1,
+        }
+    }
+
+    fn key_names(self) -> Vec<&'static str> {
+        COLUMNS[..self.key_len()].iter().map(|(name, _)| *name).collect()
+    }
+}
+
+/// How a column is stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnKind {
+    Text,
+    Price,
+    Date,
+}
+
+/// Columns written to the tables, in order: the canonical product columns
+/// plus where and when they were seen. The first `KEY_COLUMNS` are set on
+/// every row and key the snapshot table.
+const COLUMNS: [(&str, ColumnKind); 12] = [
+    ("source", ColumnKind::Text),
+    ("product_id", ColumnKind::Text),
+    ("snapshot_date", ColumnKind::Date),
+    ("name", ColumnKind::Text),
+    ("sku", ColumnKind::Text),
+    ("category", ColumnKind::Text),
+    ("units_of_mass", ColumnKind::Text),
+    ("brand", ColumnKind::Text),
+    ("currency", ColumnKind::Text),
+    ("cost_price", ColumnKind::Price),
+    ("mrp", ColumnKind::Price),
+    ("discount", ColumnKind::Price),
+];
+
+/// `(source, product_id, snapshot_date)`
+const KEY_COLUMNS: usize = 3;
+
+/// Values of one column for the rows being written
+pub(crate) enum ColumnValues {
+    Text(Vec<Option<String>>),
+    Price(Vec<Option<f64>>),
+}
+
+/// Upserts processed products into a `products` table keyed on
+/// `(source, product_id, snapshot_date)`, creating the table when it does not
+/// exist. Re-running a day updates its rows instead of duplicating them.
+/// `products_current` keeps each product's latest snapshot, keyed on
+/// `(source, product_id)`.
+pub enum DatabaseSink {
+    Sqlite(SqliteSink),
+    #[cfg(feature = "postgres")]
+    Postgres(PostgresSink),
+}
+
+impl DatabaseSink {
+    pub async fn connect(config: &DatabaseConfig) -> Result<Self> {
+        match config {
+            DatabaseConfig::Sqlite { path } => Ok(DatabaseSink::Sqlite(SqliteSink::open(path)?)),
+            #[cfg(feature = "postgres")]
+            DatabaseConfig::Postgres { url } => {
+                let sink = match url {
+                    Some(url) => PostgresSink::connect(url).await?,
+                    None => PostgresSink::from_env().await?,
+                };
+                Ok(DatabaseSink::Postgres(sink))
+            }
+            #[cfg(not(feature = "postgres"))]
+            DatabaseConfig::Postgres { .. } => {
+                Err(anyhow!("The Postgres sink needs a build with `--features postgres`"))
+            }
+        }
+    }
+
+    /// Insert or update every product of one source's `snapshot_date`
+    /// snapshot. Returns the number of rows written.
+    pub async fn upsert(&mut self, source: &str, snapshot_date: NaiveDate, df: &DataFrame) -> Result<usize> {
+        match self {
+            DatabaseSink::Sqlite(sink) => sink.upsert(source, snapshot_date, df),
+            #[cfg(feature = "postgres")]
+            DatabaseSink::Postgres(sink) => sink.upsert(source, snapshot_date, df).await,
+        }
+    }
+}
+
+/// `CREATE TABLE IF NOT EXISTS` for every table in `ProductsTable::ALL`
+pub(crate) fn create_tables_sql(dialect: Dialect) -> String {
+    ProductsTable::ALL.iter().map(|table| create_table_sql(dialect, *table)).collect::<Vec<_>>().join(";\n")
+}
+
+fn create_table_sql(dialect: Dialect, table: ProductsTable) -> String {
+    let columns: Vec<String> = COLUMNS
+        .iter()
+        .enumerate()
+        .map(|(i, (name, kind))| {
+            let sql_type = match (dialect, kind) {
+                (_, ColumnKind::Text) => "TEXT",
+                (Dialect::Sqlite, ColumnKind::Price) => "REAL",
+                (Dialect::Postgres, ColumnKind::Price) => "NUMERIC(12, 2)",
+                (Dialect::Sqlite, ColumnKind::Date) => "TEXT",
+                (Dialect::Postgres, ColumnKind::Date) => "DATE",
+            };
+            let not_null = if i < KEY_COLUMNS { " NOT NULL" } else { "" };
+            format!("{} {}{}", name, sql_type, not_null)
+        })
+        .collect();
+    let updated_at = match dialect {
+        Dialect::Sqlite => "updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP",
+        Dialect::Postgres => "updated_at TIMESTAMPTZ NOT NULL DEFAULT now()",
+    };
+
+    format!(
+        "CREATE TABLE IF NOT EXISTS {} (\n    {},\n    {},\n    PRIMARY KEY ({})\n)",
+        table.name(),
+        columns.join(",\n    "),
+        updated_at,
+        table.key_names().join(", ")
+    )
+}
+
+/// Multi-row upsert for `rows` rows. Postgres receives prices as float8 and
+/// dates as text, cast to the table's column types on insert: a date
+/// parameter is declared `text` first, as Postgres would otherwise infer it
+/// as `date`, which a `String` cannot be sent as.
+pub(crate) fn upsert_sql(dialect: Dialect, table: ProductsTable, rows: usize) -> String {
+    let names: Vec<&str> = COLUMNS.iter().map(|(name, _)| *name).collect();
+    let tuples: Vec<String> = (0..rows)
+        .map(|row| {
+            let placeholders: Vec<String> = COLUMNS
+                .iter()
+                .enumerate()
+                .map(|(i, (_, kind))| {
+                    let index = row * COLUMNS.len() + i + 1;
+                    match (dialect, kind) {
+                        (Dialect::Sqlite, _) => format!("?{}", index),
+                        (Dialect::Postgres, ColumnKind::Text) => format!("${}", index),
+                        (Dialect::Postgres, ColumnKind::Price) => format!("${}::float8", index),
+                        (Dialect::Postgres, ColumnKind::Date) => format!("${}::text::date", index),
+                    }
+                })
+                .collect();
+            format!("({})", placeholders.join(", "))
+        })
+        .collect();
+    let updates: Vec<String> = names[table.key_len()..]
+        .iter()
+        .map(|name| format!("{} = excluded.{}", name, name))
+        .collect();
+    let now = match dialect {
+        Dialect::Sqlite => "CURRENT_TIMESTAMP",
+        Dialect::Postgres => "now()",
+    };
+    let condition = match table {
+        ProductsTable::Snapshots => String::new(),
+        ProductsTable::Current => format!(" WHERE {}.snapshot_date <= excluded.snapshot_date", table.name()),
+    };
+
+    format!(
+        "INSERT INTO {} ({}) VALUES {} ON CONFLICT ({}) DO UPDATE SET {}, updated_at = {}{}",
+        table.name(),
+        names.join(", "),
+        tuples.join(", "),
+        table.key_names().join(", "),
+        updates.join(", "),
+        now,
+        condition
+    )
+}
+
+/// The values of every column of `COLUMNS` for the rows of `df` to write,
+/// and how many rows that is. Each row gets `source` and `snapshot_date`;
+/// rows without a `product_id` are skipped and, as one statement cannot
+/// update the same key twice, only the last row of a repeated ID is kept.
+pub(crate) fn table_values(source: &str, snapshot_date: NaiveDate, df: &DataFrame) -> Result<(Vec<ColumnValues>, usize)> {
+    let rows = unique_rows(df)?;
+    if rows.len() < df.height() {
+        warn!(
+            "Skipping {} rows of {} without a product_id or with a duplicate one",
+            df.height() - rows.len(),
+            source
+        );
+    }
+
+    let values = COLUMNS
+        .iter()
+        .map(|(name, kind)| match *name {
+            "source" => Ok(ColumnValues::Text(vec![Some(source.to_string()); rows.len()])),
+            "snapshot_date" => Ok(ColumnValues::Text(vec![Some(snapshot_date.to_string()); rows.len()])),
+            _ => column_values(df, name, *kind, &rows),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok((values, rows.len()))
+}
+
+/// Indices of the rows to write: the last row for each `product_id`
+fn unique_rows(df: &DataFrame) -> Result<Vec<usize>> {
+    let product_ids = df
+        .column("product_id")
+        .map_err(|_| anyhow!("DataFrame has no 'product_id' column to key rows on"))?
+        .cast(&DataType::String)?;
+
+    let mut last_row: HashMap<&str, usize> = HashMap::new();
+    for (row, product_id) in product_ids.str()?.into_iter().enumerate() {
+        if let Some(product_id) = product_id
+            && !product_id.trim().is_empty()
+        {
+            last_row.insert(product_id, row);
+        }
+    }
+
+    let mut rows: Vec<usize> = last_row.into_values().collect();
+    rows.sort_unstable();
+    Ok(rows)
+}
+
+/// A column's values for `rows`, null when the DataFrame lacks the column
+fn column_values(df: &DataFrame, name: &str, kind: ColumnKind, rows: &[usize]) -> Result<ColumnValues> {
+    let column = df.column(name).ok();
+    Ok(match kind {
+        ColumnKind::Text | ColumnKind::Date => {
+            let values = match column {
+                Some(column) => {
+                    let values = column.cast(&DataType::String)?;
+                    let values = values.str()?;
+                    rows.iter().map(|row| values.get(*row).map(str::to_string)).collect()
+                }
+                None => vec![None; rows.len()],
+            };
+            ColumnValues::Text(values)
+        }
+        ColumnKind::Price => {
+            let values = match column {
+                Some(column) => {
+                    let values = column.cast(&DataType::Float64)?;
+                    let values = values.f64()?;
+                    rows.iter().map(|row| values.get(*row).filter(|v| v.is_finite())).collect()
+                }
+                None => vec![None; rows.len()],
+            };
+            ColumnValues::Price(values)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_and_upsert_sql() {
+        let create = create_table_sql(Dialect::Postgres, ProductsTable::Snapshots);
+        assert!(create.starts_with("CREATE TABLE IF NOT EXISTS products ("));
+        assert!(create.contains("product_id TEXT NOT NULL"));
+        assert!(create.contains("snapshot_date DATE NOT NULL"));
+        assert!(create.contains("mrp NUMERIC(12, 2)"));
+        assert!(create.contains("PRIMARY KEY (source, product_id, snapshot_date)"));
+        assert!(create_table_sql(Dialect::Sqlite, ProductsTable::Snapshots).contains("mrp REAL"));
+
+        let upsert = upsert_sql(Dialect::Postgres, ProductsTable::Snapshots, 2);
+        assert!(upsert.contains("VALUES ($1, $2, $3::text::date, $4, $5, $6, $7, $8, $9, $10::float8, $11::float8, $12::float8), ($13,"));
+        assert!(upsert.contains("$24::float8)"));
+        assert!(upsert.contains("ON CONFLICT (source, product_id, snapshot_date) DO UPDATE SET name = excluded.name"));
+        assert!(!upsert.contains("source = excluded.source"));
+        assert!(!upsert.contains("WHERE"));
+        assert!(upsert_sql(Dialect::Sqlite, ProductsTable::Snapshots, 1).contains("VALUES (?1, ?2, ?3, ?4,"));
+
+        let current = create_tables_sql(Dialect::Postgres);
+        assert!(current.contains("CREATE TABLE IF NOT EXISTS products_current ("));
+        assert!(current.contains("PRIMARY KEY (source, product_id)\n)"));
+        let upsert = upsert_sql(Dialect::Postgres, ProductsTable::Current, 1);
+        assert!(upsert.starts_with("INSERT INTO products_current "));
+        assert!(upsert.contains("ON CONFLICT (source, product_id) DO UPDATE SET snapshot_date = excluded.snapshot_date, name"));
+        assert!(upsert.ends_with("updated_at = now() WHERE products_current.snapshot_date <= excluded.snapshot_date"));
+    }
+
+    #[test]
+    fn test_rows_are_keyed_on_product_id() {
+        let df = df!(
+            "product_id" => [Some("1"), Some("1"), Some("2"), None],
+            "name" => ["milk", "milk 1l", "bread", "no id"],
+            "mrp" => [Some(280.0), Some(300.0), None, Some(50.0)]
+        )
+        .unwrap();
+
+        let rows = unique_rows(&df).unwrap();
+        assert_eq!(rows, vec![1, 2]);
+
+        let ColumnValues::Price(mrp) = column_values(&df, "mrp", ColumnKind::Price, &rows).unwrap() else {
+            panic!("mrp should be a price column");
+        };
+        assert_eq!(mrp, vec![Some(300.0), None]);
+        let ColumnValues::Text(brand) = column_values(&df, "brand", ColumnKind::Text, &rows).unwrap() else {
+            panic!("brand should be a text column");
+        };
+        assert_eq!(brand, vec![None, None]);
+
+        let unkeyed = df!("name" => ["milk"]).unwrap();
+        assert!(unique_rows(&unkeyed).unwrap_err().to_string().contains("'product_id'"));
+    }
+}
@@ -0,0 +1,6 @@
+pub mod database_sink;
+#[cfg(feature = "postgres")]
+pub mod postgres_sink;
+pub mod sqlite_sink;
+
+pub use database_sink::DatabaseSink;
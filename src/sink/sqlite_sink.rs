@@ -0,0 +1,150 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use polars::prelude::*;
+use rusqlite::types::ToSql;
+use rusqlite::{Connection, params_from_iter};
+use tracing::info;
+
+use super::database_sink::{
+    ColumnValues, Dialect, PRODUCTS_TABLE, ProductsTable, UPSERT_BATCH_ROWS, create_tables_sql, table_values,
+    upsert_sql,
+};
+
+impl ColumnValues {
+    fn sqlite_param(&self, row: usize) -> &dyn ToSql {
+        match self {
+            ColumnValues::Text(values) => &values[row],
+            ColumnValues::Price(values) => &values[row],
+        }
+    }
+}
+
+/// `DatabaseSink` backed by a local SQLite file
+pub struct SqliteSink {
+    connection: Connection,
+}
+
+impl SqliteSink {
+    /// Open (or create) the database at `path`
+    pub fn open(path: &str) -> Result<Self> {
+        if let Some(dir) = std::path::Path::new(path).parent()
+            && !dir.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let connection =
+            Connection::open(path).with_context(|| format!("Failed to open SQLite database {}", path))?;
+        Self::with_connection(connection)
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Self> {
+        connection
+            .execute_batch(&create_tables_sql(Dialect::Sqlite))
+            .with_context(|| format!("Failed to create the {} tables", PRODUCTS_TABLE))?;
+        Ok(SqliteSink { connection })
+    }
+
+    /// See `DatabaseSink::upsert`
+    pub fn upsert(&mut self, source: &str, snapshot_date: NaiveDate, df: &DataFrame) -> Result<usize> {
+        let (values, rows) = table_values(source, snapshot_date, df)?;
+
+        for batch_start in (0..rows).step_by(UPSERT_BATCH_ROWS) {
+            let batch_end = (batch_start + UPSERT_BATCH_ROWS).min(rows);
+            let transaction = self.connection.transaction()?;
+            for table in ProductsTable::ALL {
+                let params = (batch_start..batch_end)
+                    .flat_map(|row| values.iter().map(move |column| column.sqlite_param(row)));
+                transaction
+                    .execute(&upsert_sql(Dialect::Sqlite, table, batch_end - batch_start), params_from_iter(params))
+                    .with_context(|| format!("Failed to upsert into {}", table.name()))?;
+            }
+            transaction.commit()?;
+        }
+
+        info!("Upserted {} products of {} into SQLite table {}", rows, source, PRODUCTS_TABLE);
+        Ok(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sink::database_sink::CURRENT_PRODUCTS_TABLE;
+
+    fn count(sink: &SqliteSink, filter: &str) -> i64 {
+        count_in(sink, PRODUCTS_TABLE, filter)
+    }
+
+    fn count_in(sink: &SqliteSink, table: &str, filter: &str) -> i64 {
+        let sql = format!("SELECT COUNT(*) FROM {} WHERE {}", table, filter);
+        sink.connection.query_row(&sql, [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn test_upsert_rows_and_rerun() {
+        let mut sink = SqliteSink::open_in_memory().unwrap();
+        let day = NaiveDate::from_ymd_opt(2025, 9, 15).unwrap();
+        let df = df!(
+            "product_id" => [Some("1"), Some("2"), None],
+            "name" => ["milk", "bread", "no id"],
+            "cost_price" => [Some(280.0), None, Some(50.0)]
+        )
+        .unwrap();
+
+        assert_eq!(sink.upsert("naheed", day, &df).unwrap(), 2);
+        assert_eq!(count(&sink, "1 = 1"), 2);
+
+        // Re-running the same day updates its rows
+        let repriced = df!("product_id" => ["1"], "name" => ["milk 1l"], "cost_price" => [300.0]).unwrap();
+        sink.upsert("naheed", day, &repriced).unwrap();
+        assert_eq!(count(&sink, "1 = 1"), 2);
+        assert_eq!(count(&sink, "product_id = '1' AND name = 'milk 1l' AND cost_price = 300.0"), 1);
+        assert_eq!(count(&sink, "product_id = '2' AND cost_price IS NULL"), 1);
+
+        // Another day or another source adds rows
+        sink.upsert("naheed", day.succ_opt().unwrap(), &repriced).unwrap();
+        sink.upsert("krave_mart", day, &repriced).unwrap();
+        assert_eq!(count(&sink, "1 = 1"), 4);
+        assert_eq!(count(&sink, "snapshot_date = '2025-09-16'"), 1);
+    }
+
+    #[test]
+    fn test_current_table_keeps_the_latest_snapshot() {
+        let mut sink = SqliteSink::open_in_memory().unwrap();
+        let day = NaiveDate::from_ymd_opt(2025, 9, 15).unwrap();
+        let next_day = day.succ_opt().unwrap();
+        let current = |sink: &SqliteSink, filter: &str| count_in(sink, CURRENT_PRODUCTS_TABLE, filter);
+
+        let df = df!("product_id" => ["1", "2"], "cost_price" => [280.0, 90.0]).unwrap();
+        sink.upsert("naheed", day, &df).unwrap();
+        let repriced = df!("product_id" => ["1"], "cost_price" => [300.0]).unwrap();
+        sink.upsert("naheed", next_day, &repriced).unwrap();
+        sink.upsert("krave_mart", day, &repriced).unwrap();
+
+        // One row per (source, product_id), holding its latest snapshot
+        assert_eq!(count(&sink, "1 = 1"), 4);
+        assert_eq!(current(&sink, "1 = 1"), 3);
+        assert_eq!(current(&sink, "source = 'naheed' AND product_id = '1' AND snapshot_date = '2025-09-16'"), 1);
+        assert_eq!(current(&sink, "source = 'naheed' AND product_id = '2' AND snapshot_date = '2025-09-15'"), 1);
+
+        // Re-running an older day leaves the current state alone
+        sink.upsert("naheed", day, &df).unwrap();
+        assert_eq!(current(&sink, "source = 'naheed' AND product_id = '1' AND cost_price = 300.0"), 1);
+    }
+
+    #[test]
+    fn test_upsert_in_batches() {
+        let mut sink = SqliteSink::open_in_memory().unwrap();
+        let day = NaiveDate::from_ymd_opt(2025, 9, 15).unwrap();
+        let ids: Vec<String> = (0..UPSERT_BATCH_ROWS * 2 + 7).map(|id| id.to_string()).collect();
+        let df = df!("product_id" => ids).unwrap();
+
+        assert_eq!(sink.upsert("dealcart", day, &df).unwrap(), df.height());
+        assert_eq!(count(&sink, "source = 'dealcart'"), df.height() as i64);
+        assert_eq!(count_in(&sink, CURRENT_PRODUCTS_TABLE, "source = 'dealcart'"), df.height() as i64);
+    }
+}
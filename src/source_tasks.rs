@@ -0,0 +1,323 @@
+use anyhow::{Result, anyhow};
+use polars::prelude::*;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+use tracing::{Instrument, error, warn};
+
+use crate::cli::PipelineMode;
+use crate::fetcher::{Shutdown, is_response_too_large};
+use crate::metrics::Metrics;
+use crate::processor::{Anomaly, ProductCounts, RunReport};
+
+/// A store of a source that was processed
+pub struct ProcessedStore {
+    pub products_count: usize,
+    pub clean_df: Option<DataFrame>,
+    pub counts: Option<ProductCounts>,
+    pub anomalies: Vec<Anomaly>,
+}
+
+/// How one store of a source went
+pub struct StoreResult {
+    pub storage_name: String,
+    pub result: Result<ProcessedStore>,
+}
+
+/// What a source task produced, recorded in the run report once every task
+/// is done so the report keeps the order of `sources.toml`
+pub struct SourceOutcome {
+    pub source_name: String,
+    pub stores: Vec<StoreResult>,
+    /// `--key` named one of the source's stores
+    pub matched_snapshot: bool,
+    /// The outcome of a second run, after the first failed
+    pub retried: bool,
+    /// How long the source's task took
+    pub elapsed: Duration,
+}
+
+impl SourceOutcome {
+    /// A source that was skipped before any of its stores ran
+    pub fn skipped(source_name: &str) -> Self {
+        SourceOutcome {
+            source_name: source_name.to_string(),
+            stores: Vec::new(),
+            matched_snapshot: false,
+            retried: false,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Add the source's duration, failed stores and, when `mode` fetches,
+    /// fetched products to `metrics`
+    pub fn record_metrics(&self, metrics: &Metrics, mode: PipelineMode) {
+        metrics.source_duration(&self.source_name, mode.stage(), self.elapsed);
+        for store in &self.stores {
+            match &store.result {
+                Ok(processed) if matches!(mode, PipelineMode::Fetch | PipelineMode::Run) => {
+                    metrics.products_fetched(&self.source_name, processed.products_count)
+                }
+                Ok(_) => {}
+                Err(_) => metrics.failure(&self.source_name, mode.stage()),
+            }
+        }
+    }
+
+    /// Every store of the source failed, so running it again may help.
+    /// Sources skipped before any store ran are not failed, nor are those
+    /// with an oversized response, which a second run would fetch again.
+    pub fn failed(&self) -> bool {
+        !self.stores.is_empty() && self.stores.iter().all(|store| store.result.as_ref().is_err_and(|e| !is_response_too_large(e)))
+    }
+
+    /// Add every store to `run_report`, returning the clean DataFrames of
+    /// the processed stores by storage name. A source counts as processed
+    /// when any of its stores was.
+    pub fn record(self, run_report: &mut RunReport) -> RecordedSource {
+        let mut recorded = RecordedSource {
+            source_name: self.source_name,
+            succeeded: false,
+            frames: Vec::new(),
+        };
+        for store in self.stores {
+            match store.result {
+                Ok(processed) => {
+                    run_report.add_source(
+                        &recorded.source_name,
+                        &store.storage_name,
+                        processed.products_count,
+                        processed.clean_df.as_ref(),
+                        processed.counts,
+                    );
+                    run_report.mark_suspect(&store.storage_name, processed.anomalies);
+                    recorded.succeeded = true;
+                    if let Some(df) = processed.clean_df {
+                        recorded.frames.push((store.storage_name, df));
+                    }
+                }
+                Err(e) => run_report.add_failure(&recorded.source_name, &store.storage_name, &e),
+            }
+        }
+        if self.retried && recorded.succeeded {
+            run_report.mark_retried(&recorded.source_name);
+        }
+        recorded
+    }
+}
+
+/// A source once it is in the run report
+pub struct RecordedSource {
+    pub source_name: String,
+    pub succeeded: bool,
+    pub frames: Vec<(String, DataFrame)>,
+}
+
+/// Run `task` for every source, at most `jobs` at a time, each in a tracing
+/// span named after its source. Outcomes come back in the order of `sources`;
+/// a task that panics becomes a failed outcome without stopping the others.
+pub async fn run_sources<S, F, Fut>(sources: Vec<S>, jobs: usize, name: impl Fn(&S) -> String, task: F) -> Vec<SourceOutcome>
+where
+    F: Fn(S) -> Fut,
+    Fut: Future<Output = SourceOutcome> + Send + 'static,
+{
+    let mut names = Vec::with_capacity(sources.len());
+    let mut outcomes: Vec<Option<SourceOutcome>> = Vec::new();
+    let mut running = JoinSet::new();
+    let mut pending = sources.into_iter().enumerate();
+
+    loop {
+        while running.len() < jobs.max(1) {
+            let Some((index, source)) = pending.next() else {
+                break;
+            };
+            let source_name = name(&source);
+            let span = tracing::info_span!("source", source = %source_name);
+            names.push(source_name);
+            outcomes.push(None);
+            let work = task(source);
+            running.spawn(
+                async move {
+                    let started = Instant::now();
+                    let outcome = work.await;
+                    (index, SourceOutcome { elapsed: started.elapsed(), ..outcome })
+                }
+                .instrument(span),
+            );
+        }
+
+        let Some(joined) = running.join_next().await else {
+            break;
+        };
+        match joined {
+            Ok((index, outcome)) => outcomes[index] = Some(outcome),
+            Err(e) => error!("❌ A source task stopped unexpectedly: {}", e),
+        }
+    }
+
+    // Tasks that panicked never reported back
+    outcomes
+        .into_iter()
+        .zip(names)
+        .map(|(outcome, source_name)| {
+            outcome.unwrap_or_else(|| SourceOutcome {
+                stores: vec![StoreResult {
+                    result: Err(anyhow!("The task processing {} panicked", source_name)),
+                    storage_name: source_name.clone(),
+                }],
+                ..SourceOutcome::skipped(&source_name)
+            })
+        })
+        .collect()
+}
+
+/// `run_sources`, then once `retry_after` has passed, the sources that
+/// failed run a second time through the same `task`, unless `shutdown` is
+/// requested in between. A retried source's outcome replaces its first one
+/// and is marked `retried`. Without `retry_after` this is `run_sources`.
+pub async fn run_sources_with_retry<S, F, Fut>(
+    sources: Vec<S>,
+    jobs: usize,
+    name: impl Fn(&S) -> String,
+    retry_after: Option<Duration>,
+    shutdown: &Shutdown,
+    task: F,
+) -> Vec<SourceOutcome>
+where
+    S: Clone,
+    F: Fn(S) -> Fut,
+    Fut: Future<Output = SourceOutcome> + Send + 'static,
+{
+    let mut outcomes = run_sources(sources.clone(), jobs, &name, &task).await;
+    let Some(delay) = retry_after else {
+        return outcomes;
+    };
+    let failed: Vec<usize> = (0..outcomes.len()).filter(|&index| outcomes[index].failed()).collect();
+    if failed.is_empty() {
+        return outcomes;
+    }
+
+    let names: Vec<&str> = failed.iter().map(|&index| outcomes[index].source_name.as_str()).collect();
+    warn!("🔁 Retrying {} failed sources in {}s: {}", failed.len(), delay.as_secs(), names.join(", "));
+    tokio::select! {
+        _ = tokio::time::sleep(delay) => {}
+        _ = shutdown.wait() => {}
+    }
+    if shutdown.is_requested() {
+        return outcomes;
+    }
+
+    let retried = run_sources(failed.iter().map(|&index| sources[index].clone()).collect(), jobs, &name, &task).await;
+    for (index, outcome) in failed.into_iter().zip(retried) {
+        outcomes[index] = SourceOutcome { retried: true, ..outcome };
+    }
+    outcomes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    use crate::fetcher::client::ResponseTooLarge;
+
+    fn processed(products_count: usize) -> Result<ProcessedStore> {
+        Ok(ProcessedStore {
+            products_count,
+            clean_df: Some(df!("product_id" => (0..products_count as u32).collect::<Vec<_>>()).unwrap()),
+            counts: None,
+            anomalies: Vec::new(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_sources_run_concurrently_and_add_up() {
+        // The slower source is listed first and must still be reported first
+        let sources = vec![("krave_mart", 40), ("naheed", 5), ("dealcart", 1)];
+        let outcomes = run_sources(sources, 2, |(name, _)| name.to_string(), |(name, delay_ms)| async move {
+            tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+            let stores = match name {
+                "krave_mart" => vec![
+                    StoreResult { storage_name: "krave_mart_1".to_string(), result: processed(3) },
+                    StoreResult { storage_name: "krave_mart_2".to_string(), result: Err(anyhow!("timed out")) },
+                ],
+                "naheed" => vec![StoreResult { storage_name: "naheed".to_string(), result: processed(2) }],
+                _ => panic!("dealcart is down"),
+            };
+            SourceOutcome { stores, ..SourceOutcome::skipped(name) }
+        })
+        .await;
+        assert!(outcomes[0].elapsed >= Duration::from_millis(40));
+
+        let metrics = Metrics::default();
+        outcomes.iter().for_each(|outcome| outcome.record_metrics(&metrics, PipelineMode::Run));
+        let text = metrics.render();
+        assert!(text.contains("pipeline_products_fetched_total{source=\"krave_mart\"} 3\n"));
+        assert!(text.contains("pipeline_failures_total{source=\"dealcart\",stage=\"run\"} 1\n"));
+        assert!(text.contains("pipeline_source_duration_seconds_count{source=\"naheed\",stage=\"run\"} 1\n"));
+
+        let mut run_report = RunReport::new("from APIs");
+        let recorded: Vec<RecordedSource> = outcomes.into_iter().map(|outcome| outcome.record(&mut run_report)).collect();
+
+        let names: Vec<&str> = recorded.iter().map(|source| source.source_name.as_str()).collect();
+        assert_eq!(names, vec!["krave_mart", "naheed", "dealcart"]);
+        assert_eq!(recorded.iter().filter(|source| source.succeeded).count(), 2);
+        assert_eq!(recorded[0].frames.len(), 1);
+        assert_eq!(run_report.total_products(), 5);
+        assert_eq!(run_report.failures.len(), 2);
+        assert!(run_report.failures[1].error.contains("panicked"));
+    }
+
+    #[tokio::test]
+    async fn test_failed_sources_are_retried_once() {
+        // dealcart fails its first call only, pandamart every call
+        let calls: Arc<Mutex<HashMap<&str, usize>>> = Arc::default();
+        let task = |name: &'static str| {
+            let calls = calls.clone();
+            async move {
+                let call = {
+                    let mut calls = calls.lock().unwrap();
+                    let call = calls.entry(name).or_default();
+                    *call += 1;
+                    *call
+                };
+                let result = match (name, call) {
+                    ("dealcart", 1) => Err(anyhow!("HTTP 502")),
+                    ("pandamart", _) => Err(anyhow!("HTTP 403")),
+                    _ => processed(1),
+                };
+                SourceOutcome {
+                    stores: vec![StoreResult { storage_name: name.to_string(), result }],
+                    ..SourceOutcome::skipped(name)
+                }
+            }
+        };
+        let sources = vec!["krave_mart", "dealcart", "pandamart"];
+        let shutdown = Shutdown::new();
+
+        let outcomes = run_sources_with_retry(sources.clone(), 2, |name| name.to_string(), Some(Duration::ZERO), &shutdown, task).await;
+        assert_eq!(*calls.lock().unwrap(), HashMap::from([("krave_mart", 1), ("dealcart", 2), ("pandamart", 2)]));
+
+        let mut run_report = RunReport::new("from APIs");
+        let recorded: Vec<RecordedSource> = outcomes.into_iter().map(|outcome| outcome.record(&mut run_report)).collect();
+        let succeeded: Vec<bool> = recorded.iter().map(|source| source.succeeded).collect();
+        assert_eq!(succeeded, vec![true, true, false]);
+        assert_eq!(run_report.retried, vec!["dealcart"]);
+        assert_eq!(run_report.failures.len(), 1);
+        assert!(run_report.to_string().contains("dealcart succeeded on retry\npandamart failed: HTTP 403"));
+
+        // --no-retry
+        calls.lock().unwrap().clear();
+        let outcomes = run_sources_with_retry(sources, 2, |name| name.to_string(), None, &shutdown, task).await;
+        assert!(outcomes[1].failed() && !outcomes[1].retried);
+        assert_eq!(calls.lock().unwrap()["dealcart"], 1);
+
+        let oversized = ResponseTooLarge { url: "https://api.dealcart.io/products".to_string(), max_bytes: 10, bytes: 11 };
+        let outcome = SourceOutcome {
+            stores: vec![StoreResult { storage_name: "dealcart".to_string(), result: Err(anyhow::Error::from(oversized).context("page 2")) }],
+            ..SourceOutcome::skipped("dealcart")
+        };
+        assert!(!outcome.failed());
+    }
+}
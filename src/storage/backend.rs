@@ -0,0 +1,441 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
+use s3::bucket::Bucket;
+use s3::command::Command;
+use s3::error::S3Error;
+use s3::request::Request;
+use s3::request::tokio_backend::HyperRequest;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::{TcpStream, lookup_host};
+use tracing::info;
+
+/// Body of a download, chunk by chunk as it arrives
+pub type ObjectStream = BoxStream<'static, Result<Vec<u8>>>;
+
+/// Minimal set of bucket operations used by `MinioStorage`.
+///
+/// Abstracting these lets the storage layer route objects to different
+/// buckets and be exercised against an in-memory backend in tests.
+#[async_trait]
+pub trait ObjectBackend: Send + Sync {
+    /// Name of the bucket this backend reads from and writes to
+    fn bucket_name(&self) -> &str;
+
+    /// Check that the storage endpoint resolves and accepts connections,
+    /// returning a short description of what was reached
+    async fn check_connectivity(&self) -> Result<String> {
+        Ok(format!("no network check needed for '{}'", self.bucket_name()))
+    }
+
+    async fn bucket_exists(&self) -> Result<bool>;
+
+    async fn create_bucket(&self) -> Result<()>;
+
+    /// Upload an object, returning the HTTP status code
+    async fn put_object(&self, key: &str, data: &[u8]) -> Result<u16>;
+
+    /// Download an object, returning the HTTP status code and body
+    async fn get_object(&self, key: &str) -> Result<(u16, Vec<u8>)>;
+
+    /// Download an object from byte `offset` on, returning the HTTP status
+    /// code before the body so the caller knows whether the range was
+    /// honoured (206) or the whole object is coming (200). A transfer that
+    /// breaks off mid-stream ends the body with an error; HTTP errors come
+    /// back as their status with an empty body.
+    async fn get_object_from(&self, key: &str, offset: u64) -> Result<(u16, ObjectStream)> {
+        let (status, data) = self.get_object(key).await?;
+        if status != 200 || offset == 0 {
+            return Ok((status, stream::once(async move { Ok(data) }).boxed()));
+        }
+        if offset as usize >= data.len() {
+            return Ok((416, stream::empty().boxed()));
+        }
+        let rest = data[offset as usize..].to_vec();
+        Ok((206, stream::once(async move { Ok(rest) }).boxed()))
+    }
+
+    /// Size of an object in bytes
+    async fn object_size(&self, key: &str) -> Result<u64> {
+        let (_, data) = self.get_object(key).await?;
+        Ok(data.len() as u64)
+    }
+
+    /// List all object keys starting with `prefix`
+    async fn list_keys(&self, prefix: &str) -> Result<Vec<String>>;
+
+    /// Delete an object, returning the HTTP status code
+    async fn delete_object(&self, key: &str) -> Result<u16>;
+
+    /// Delete many objects, returning the keys that could not be deleted
+    async fn delete_objects(&self, keys: &[String]) -> Result<Vec<String>> {
+        let mut failed = Vec::new();
+        for key in keys {
+            match self.delete_object(key).await {
+                Ok(200) | Ok(204) => {}
+                _ => failed.push(key.clone()),
+            }
+        }
+        Ok(failed)
+    }
+}
+
+/// Timeout for the TCP connection made by `S3Backend::check_connectivity`
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Number of concurrent single-object deletes issued by `S3Backend::delete_objects`
+const S3_DELETE_CONCURRENCY: usize = 16;
+
+/// S3/MinIO backend built on a `rust-s3` bucket handle
+pub struct S3Backend {
+    bucket: Bucket,
+}
+
+impl S3Backend {
+    pub fn new(bucket: Bucket) -> Self {
+        Self { bucket }
+    }
+}
+
+#[async_trait]
+impl ObjectBackend for S3Backend {
+    fn bucket_name(&self) -> &str {
+        &self.bucket.name
+    }
+
+    async fn check_connectivity(&self) -> Result<String> {
+        let host = self.bucket.region.host();
+        let host = host.split('/').next().unwrap_or_default();
+        let address = if host.contains(':') {
+            host.to_string()
+        } else if self.bucket.region.scheme() == "http" {
+            format!("{}:80", host)
+        } else {
+            format!("{}:443", host)
+        };
+
+        let resolved = lookup_host(&address)
+            .await
+            .map_err(|e| anyhow!("DNS lookup failed for {}: {}", address, e))?
+            .next()
+            .ok_or_else(|| anyhow!("DNS lookup returned no addresses for {}", address))?;
+
+        tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect(resolved))
+            .await
+            .map_err(|_| anyhow!("Connection to {} ({}) timed out", address, resolved))?
+            .map_err(|e| anyhow!("Connection to {} ({}) failed: {}", address, resolved, e))?;
+
+        Ok(format!("reached {} ({})", address, resolved))
+    }
+
+    async fn bucket_exists(&self) -> Result<bool> {
+        Ok(self.bucket.exists().await?)
+    }
+
+    async fn create_bucket(&self) -> Result<()> {
+        let config = s3::BucketConfiguration::default();
+        Bucket::create(
+            &self.bucket.name,
+            self.bucket.region.clone(),
+            self.bucket.credentials().await?,
+            config,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn put_object(&self, key: &str, data: &[u8]) -> Result<u16> {
+        let response = self.bucket.put_object(key, data).await?;
+        Ok(response.status_code())
+    }
+
+    async fn get_object(&self, key: &str) -> Result<(u16, Vec<u8>)> {
+        let response = self.bucket.get_object(key).await?;
+        Ok((response.status_code(), response.bytes().to_vec()))
+    }
+
+    async fn get_object_from(&self, key: &str, offset: u64) -> Result<(u16, ObjectStream)> {
+        // An empty object has no byte 0 to start a range at, so the first
+        // attempt is a plain get
+        let result = if offset == 0 {
+            self.bucket.get_object_stream(key).await
+        } else {
+            let range = Command::GetObjectRange { start: offset, end: None };
+            HyperRequest::new(&self.bucket, key, range).await?.response_data_to_stream().await
+        };
+        match result {
+            Ok(response) => {
+                let body = response.bytes.map(|chunk| chunk.map(|bytes| bytes.to_vec()).map_err(anyhow::Error::from));
+                Ok((response.status_code, body.boxed()))
+            }
+            Err(S3Error::HttpFailWithBody(status, _)) => Ok((status, stream::empty().boxed())),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn object_size(&self, key: &str) -> Result<u64> {
+        let (head, _) = self.bucket.head_object(key).await?;
+        head.content_length
+            .and_then(|length| u64::try_from(length).ok())
+            .ok_or_else(|| anyhow!("No content length for {}", key))
+    }
+
+    /// `Bucket::list` requests page after page (at most 1000 keys each),
+    /// following the continuation tokens until the listing is complete
+    async fn list_keys(&self, prefix: &str) -> Result<Vec<String>> {
+        let list = self.bucket.list(prefix.to_string(), None).await?;
+
+        let mut keys = Vec::new();
+        for result in list {
+            for object in result.contents {
+                keys.push(object.key);
+            }
+        }
+
+        Ok(keys)
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<u16> {
+        let response = self.bucket.delete_object(key).await?;
+        Ok(response.status_code())
+    }
+
+    async fn delete_objects(&self, keys: &[String]) -> Result<Vec<String>> {
+        // rust-s3 0.35 doesn't expose the multi-object delete API, so fan
+        // out single deletes with bounded concurrency instead
+        let bucket = &self.bucket;
+        let results: Vec<Option<String>> = stream::iter(keys.to_vec())
+            .map(|key| async move {
+                match bucket.delete_object(&key).await {
+                    Ok(response) if matches!(response.status_code(), 200 | 204) => None,
+                    _ => Some(key),
+                }
+            })
+            .buffer_unordered(S3_DELETE_CONCURRENCY)
+            .collect()
+            .await;
+
+        Ok(results.into_iter().flatten().collect())
+    }
+}
+
+/// In-memory backend for tests and local runs without a MinIO server.
+///
+/// Clones share the same underlying object map, so a test can keep a handle
+/// and inspect what the storage layer wrote.
+#[derive(Clone)]
+pub struct MemoryBackend {
+    name: String,
+    state: Arc<Mutex<MemoryState>>,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    exists: bool,
+    objects: BTreeMap<String, Vec<u8>>,
+}
+
+impl MemoryBackend {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            state: Arc::default(),
+        }
+    }
+
+    /// All stored keys in lexicographic order
+    pub fn keys(&self) -> Vec<String> {
+        self.state.lock().unwrap().objects.keys().cloned().collect()
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.state.lock().unwrap().objects.contains_key(key)
+    }
+}
+
+#[async_trait]
+impl ObjectBackend for MemoryBackend {
+    fn bucket_name(&self) -> &str {
+        &self.name
+    }
+
+    async fn bucket_exists(&self) -> Result<bool> {
+        Ok(self.state.lock().unwrap().exists)
+    }
+
+    async fn create_bucket(&self) -> Result<()> {
+        self.state.lock().unwrap().exists = true;
+        Ok(())
+    }
+
+    async fn put_object(&self, key: &str, data: &[u8]) -> Result<u16> {
+        self.state
+            .lock()
+            .unwrap()
+            .objects
+            .insert(key.to_string(), data.to_vec());
+        Ok(200)
+    }
+
+    async fn get_object(&self, key: &str) -> Result<(u16, Vec<u8>)> {
+        self.state
+            .lock()
+            .unwrap()
+            .objects
+            .get(key)
+            .map(|data| (200, data.clone()))
+            .ok_or_else(|| anyhow!("Object not found: {}", key))
+    }
+
+    async fn get_object_from(&self, key: &str, offset: u64) -> Result<(u16, ObjectStream)> {
+        let state = self.state.lock().unwrap();
+        let Some(data) = state.objects.get(key) else {
+            return Ok((404, stream::empty().boxed()));
+        };
+        // Like S3, a range starting at or past the end is not satisfiable
+        let status = match offset {
+            0 => 200,
+            offset if (offset as usize) < data.len() => 206,
+            _ => return Ok((416, stream::empty().boxed())),
+        };
+        let rest = data[offset as usize..].to_vec();
+        Ok((status, stream::once(async move { Ok(rest) }).boxed()))
+    }
+
+    async fn object_size(&self, key: &str) -> Result<u64> {
+        self.state
+            .lock()
+            .unwrap()
+            .objects
+            .get(key)
+            .map(|data| data.len() as u64)
+            .ok_or_else(|| anyhow!("Object not found: {}", key))
+    }
+
+    async fn list_keys(&self, prefix: &str) -> Result<Vec<String>> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .objects
+            .keys()
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .collect())
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<u16> {
+        self.state.lock().unwrap().objects.remove(key);
+        Ok(204)
+    }
+}
+
+/// Wraps a backend for `--dry-run`: reads reach the real bucket, while
+/// uploads, deletes and bucket creation are only logged and recorded.
+///
+/// Recorded uploads are read back as if they had been stored, so a run can
+/// fetch a source, "store" its raw dump and process it as usual. Clones
+/// share the record, like `MemoryBackend` clones share their objects.
+#[derive(Clone)]
+pub struct DryRunBackend {
+    inner: Arc<dyn ObjectBackend>,
+    state: Arc<Mutex<DryRunState>>,
+}
+
+#[derive(Default)]
+struct DryRunState {
+    /// The bucket does not exist yet, so there is nothing to read from it
+    bucket_missing: bool,
+    writes: BTreeMap<String, Vec<u8>>,
+}
+
+impl DryRunBackend {
+    pub fn new(inner: Box<dyn ObjectBackend>) -> Self {
+        Self {
+            inner: Arc::from(inner),
+            state: Arc::default(),
+        }
+    }
+
+    /// Keys the run would have stored and their sizes in bytes, by key
+    pub fn intended_writes(&self) -> Vec<(String, usize)> {
+        self.state
+            .lock()
+            .unwrap()
+            .writes
+            .iter()
+            .map(|(key, data)| (key.clone(), data.len()))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl ObjectBackend for DryRunBackend {
+    fn bucket_name(&self) -> &str {
+        self.inner.bucket_name()
+    }
+
+    async fn check_connectivity(&self) -> Result<String> {
+        self.inner.check_connectivity().await
+    }
+
+    async fn bucket_exists(&self) -> Result<bool> {
+        self.inner.bucket_exists().await
+    }
+
+    async fn create_bucket(&self) -> Result<()> {
+        info!("DRY RUN: would create bucket {}", self.bucket_name());
+        self.state.lock().unwrap().bucket_missing = true;
+        Ok(())
+    }
+
+    async fn put_object(&self, key: &str, data: &[u8]) -> Result<u16> {
+        info!("DRY RUN: would store {} ({} bytes)", key, data.len());
+        self.state
+            .lock()
+            .unwrap()
+            .writes
+            .insert(key.to_string(), data.to_vec());
+        Ok(200)
+    }
+
+    async fn get_object(&self, key: &str) -> Result<(u16, Vec<u8>)> {
+        let bucket_missing = {
+            let state = self.state.lock().unwrap();
+            if let Some(data) = state.writes.get(key) {
+                return Ok((200, data.clone()));
+            }
+            state.bucket_missing
+        };
+        if bucket_missing {
+            return Err(anyhow!("Object not found: {}", key));
+        }
+        self.inner.get_object(key).await
+    }
+
+    async fn list_keys(&self, prefix: &str) -> Result<Vec<String>> {
+        let mut keys = if self.state.lock().unwrap().bucket_missing {
+            Vec::new()
+        } else {
+            self.inner.list_keys(prefix).await?
+        };
+        // Recorded uploads are listed after the bucket's own keys
+        let state = self.state.lock().unwrap();
+        let written = state
+            .writes
+            .keys()
+            .filter(|key| key.starts_with(prefix) && !keys.contains(key))
+            .cloned()
+            .collect::<Vec<_>>();
+        keys.extend(written);
+        Ok(keys)
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<u16> {
+        info!("DRY RUN: would delete {}", key);
+        self.state.lock().unwrap().writes.remove(key);
+        Ok(204)
+    }
+}
@@ -0,0 +1,145 @@
+use std::fmt;
+use std::time::Duration;
+
+/// Outcome of a single storage health check stage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Passed,
+    Failed,
+    /// Not run because an earlier stage failed
+    Skipped,
+}
+
+/// One stage of the storage health check (connectivity, auth, bucket, ...)
+#[derive(Debug, Clone)]
+pub struct HealthCheck {
+    pub name: String,
+    pub status: CheckStatus,
+    pub latency: Duration,
+    pub message: String,
+}
+
+impl HealthCheck {
+    pub fn passed(name: &str, latency: Duration, message: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: CheckStatus::Passed,
+            latency,
+            message: message.into(),
+        }
+    }
+
+    pub fn failed(name: &str, latency: Duration, message: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: CheckStatus::Failed,
+            latency,
+            message: message.into(),
+        }
+    }
+
+    pub fn skipped(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            status: CheckStatus::Skipped,
+            latency: Duration::ZERO,
+            message: "skipped: an earlier check failed".to_string(),
+        }
+    }
+}
+
+/// Result of `MinioStorage::health_check`, one entry per stage in run order
+#[derive(Debug, Clone, Default)]
+pub struct HealthReport {
+    pub checks: Vec<HealthCheck>,
+}
+
+impl HealthReport {
+    pub fn is_healthy(&self) -> bool {
+        self.checks
+            .iter()
+            .all(|check| check.status == CheckStatus::Passed)
+    }
+
+    /// The first stage that failed, which is where troubleshooting should start
+    pub fn first_failure(&self) -> Option<&HealthCheck> {
+        self.checks
+            .iter()
+            .find(|check| check.status == CheckStatus::Failed)
+    }
+
+    /// Whether a stage has already failed, so later stages should be skipped
+    pub fn has_failed(&self) -> bool {
+        self.first_failure().is_some()
+    }
+
+    pub fn push(&mut self, check: HealthCheck) {
+        self.checks.push(check);
+    }
+}
+
+impl fmt::Display for HealthReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Storage health check:")?;
+        for check in &self.checks {
+            let icon = match check.status {
+                CheckStatus::Passed => "✅",
+                CheckStatus::Failed => "❌",
+                CheckStatus::Skipped => "⏭️",
+            };
+            writeln!(
+                f,
+                "  {} {:<28} {:>6}ms  {}",
+                icon,
+                check.name,
+                check.latency.as_millis(),
+                check.message
+            )?;
+        }
+
+        match self.first_failure() {
+            Some(failure) => write!(f, "Result: UNHEALTHY (failed at '{}')", failure.name),
+            None => write!(f, "Result: HEALTHY"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_formatting() {
+        let mut report = HealthReport::default();
+        report.push(HealthCheck::passed(
+            "connectivity",
+            Duration::from_millis(12),
+            "reached localhost:9000",
+        ));
+        report.push(HealthCheck::failed(
+            "authentication",
+            Duration::from_millis(40),
+            "Authentication failed",
+        ));
+        report.push(HealthCheck::skipped("bucket"));
+
+        assert!(!report.is_healthy());
+        assert_eq!(report.first_failure().unwrap().name, "authentication");
+
+        let output = report.to_string();
+        assert!(output.contains("✅ connectivity"));
+        assert!(output.contains("12ms"));
+        assert!(output.contains("❌ authentication"));
+        assert!(output.contains("skipped"));
+        assert!(output.ends_with("Result: UNHEALTHY (failed at 'authentication')"));
+    }
+
+    #[test]
+    fn test_healthy_report() {
+        let mut report = HealthReport::default();
+        report.push(HealthCheck::passed("connectivity", Duration::ZERO, "ok"));
+
+        assert!(report.is_healthy());
+        assert!(report.to_string().ends_with("Result: HEALTHY"));
+    }
+}
@@ -1,13 +1,225 @@
 use crate::config::MinioConfig;
-use anyhow::{Result, anyhow};
-use chrono::Utc;
+use crate::processor::anomaly_detector::SnapshotStats;
+use crate::processor::encode_ndjson;
+use crate::processor::parquet_metadata::{CleanFileMetadata, read_parquet_metadata};
+use crate::storage::backend::{DryRunBackend, ObjectBackend, S3Backend};
+use crate::storage::health::{HealthCheck, HealthReport};
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use futures::StreamExt;
+use polars::prelude::*;
 use s3::bucket::Bucket;
 use s3::creds::Credentials;
 use s3::region::Region;
-use tracing::info;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::future::Future;
+use std::io::{Cursor, Write};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Which storage tier an object belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageTier {
+    Raw,
+    Clean,
+}
+
+impl StorageTier {
+    /// Infer the tier from an object key (processed outputs live under
+    /// `clean/`, `clean_arrow/`, `clean_csv/`, `clean_ndjson/`, `changes/`,
+    /// `errors/`, `rejected/` and `reports/`)
+    pub fn for_key(key: &str) -> Self {
+        let clean_prefixes = [
+            "clean/",
+            "clean_arrow/",
+            "clean_csv/",
+            "clean_ndjson/",
+            "changes/",
+            "errors/",
+            "rejected/",
+            "reports/",
+        ];
+        if clean_prefixes.iter().any(|prefix| key.starts_with(prefix)) {
+            StorageTier::Clean
+        } else {
+            StorageTier::Raw
+        }
+    }
+}
+
+/// Formats clean data is stored in. Parquet is always written, as later runs
+/// read it back; `Arrow` also stores an Arrow IPC copy for consumers that
+/// read Arrow directly, `Csv` a CSV copy for spreadsheets and `Ndjson` a
+/// gzipped JSON Lines copy for services ingesting one record per line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Parquet,
+    #[serde(alias = "ipc", alias = "feather")]
+    Arrow,
+    Csv,
+    #[serde(alias = "jsonl")]
+    Ndjson,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "parquet" => Ok(OutputFormat::Parquet),
+            "arrow" | "ipc" | "feather" => Ok(OutputFormat::Arrow),
+            "csv" => Ok(OutputFormat::Csv),
+            "ndjson" | "jsonl" => Ok(OutputFormat::Ndjson),
+            _ => Err(anyhow!("Unknown output format '{}'. Supported: parquet, arrow, csv, ndjson", s)),
+        }
+    }
+}
+
+/// Compression of Arrow IPC output. Uncompressed files can be memory-mapped
+/// by readers without copying; LZ4 and Zstd trade that for smaller objects.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArrowCompression {
+    #[default]
+    None,
+    Lz4,
+    Zstd,
+}
+
+impl ArrowCompression {
+    fn ipc_compression(self) -> Option<IpcCompression> {
+        match self {
+            ArrowCompression::None => None,
+            ArrowCompression::Lz4 => Some(IpcCompression::LZ4),
+            ArrowCompression::Zstd => Some(IpcCompression::ZSTD),
+        }
+    }
+}
+
+/// Which raw dump of a source to process from storage
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum RawSnapshot {
+    /// The most recent dump
+    #[default]
+    Latest,
+    /// The most recent dump fetched on that day
+    Date(NaiveDate),
+    /// One raw object, by key
+    Key(String),
+}
+
+impl RawSnapshot {
+    /// Whether this snapshot can belong to a source: a key only belongs to
+    /// the source it was stored under
+    pub fn applies_to(&self, api_name: &str) -> bool {
+        match self {
+            RawSnapshot::Key(key) => MinioStorage::raw_file_api(key) == Some(api_name),
+            _ => true,
+        }
+    }
+
+    /// Day the selected data was fetched, when the snapshot pins one
+    pub fn date(&self) -> Option<NaiveDate> {
+        match self {
+            RawSnapshot::Latest => None,
+            RawSnapshot::Date(date) => Some(*date),
+            RawSnapshot::Key(key) => MinioStorage::raw_file_date(key),
+        }
+    }
+}
+
+/// Maximum number of keys handed to a backend in one bulk delete call
+pub const DELETE_BATCH_SIZE: usize = 1000;
+
+/// Attempts `get_object` makes before giving up on a download
+const GET_ATTEMPTS: usize = 3;
+
+/// Pause before resuming a broken download, growing with each attempt
+const GET_RETRY_DELAY: Duration = Duration::from_millis(250);
+
+/// Outcome of a bulk delete. In dry-run mode `deleted` lists the keys that
+/// would have been removed and nothing is touched.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeleteSummary {
+    pub deleted: Vec<String>,
+    pub failed: Vec<String>,
+    pub dry_run: bool,
+}
+
+impl DeleteSummary {
+    pub fn deleted_count(&self) -> usize {
+        self.deleted.len()
+    }
+
+    pub fn failed_count(&self) -> usize {
+        self.failed.len()
+    }
+}
+
+/// Result of `MinioStorage::store_raw_json_checked`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RawStoreOutcome {
+    /// The payload was uploaded under this key
+    Stored(String),
+    /// The payload matched the latest dump, stored under this key
+    Unchanged(String),
+}
+
+impl RawStoreOutcome {
+    pub fn key(&self) -> &str {
+        match self {
+            RawStoreOutcome::Stored(key) | RawStoreOutcome::Unchanged(key) => key,
+        }
+    }
+
+    pub fn is_unchanged(&self) -> bool {
+        matches!(self, RawStoreOutcome::Unchanged(_))
+    }
+}
+
+/// Hex-encoded SHA-256 of a payload
+fn content_hash(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+/// Sidecar object holding the content hash of a raw dump
+fn raw_hash_key(raw_key: &str) -> String {
+    format!("{}.sha256", raw_key)
+}
+
+/// Sidecar object marking a raw dump as fetched by a limited run
+fn raw_limited_key(raw_key: &str) -> String {
+    format!("{}.limited", raw_key)
+}
+
+/// How a run narrowed down what it fetched (`--limit`, `--categories`),
+/// recorded next to its raw dumps so they are never taken for full snapshots
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LimitedFetch {
+    /// Products fetched at most per store
+    pub limit: Option<usize>,
+    /// Categories fetched, empty for every category
+    pub categories: Vec<String>,
+}
+
+impl LimitedFetch {
+    /// `None` for a run that fetches everything
+    pub fn for_run(limit: Option<usize>, categories: &[String]) -> Option<Self> {
+        if limit.is_none() && categories.is_empty() {
+            return None;
+        }
+        Some(LimitedFetch { limit, categories: categories.to_vec() })
+    }
+}
 
 pub struct MinioStorage {
-    bucket: Bucket,
+    raw: Box<dyn ObjectBackend>,
+    clean: Box<dyn ObjectBackend>,
 }
 
 impl MinioStorage {
@@ -40,30 +252,88 @@ impl MinioStorage {
         // Configure for path-style access (required for MinIO)
         let bucket = *bucket.with_path_style();
 
-        Ok(MinioStorage { bucket })
+        Ok(Self::with_backends(
+            Box::new(S3Backend::new(bucket.clone())),
+            Box::new(S3Backend::new(bucket)),
+        ))
+    }
+
+    /// Build storage from explicit raw and clean tier backends
+    pub fn with_backends(raw: Box<dyn ObjectBackend>, clean: Box<dyn ObjectBackend>) -> Self {
+        MinioStorage { raw, clean }
+    }
+
+    /// The same storage for `--dry-run`: everything is read as usual but
+    /// nothing is written, see `DryRunBackend`
+    pub fn into_dry_run(self) -> Self {
+        Self::with_backends(
+            Box::new(DryRunBackend::new(self.raw)),
+            Box::new(DryRunBackend::new(self.clean)),
+        )
     }
 
     pub fn from_config(config: &MinioConfig) -> Result<Self> {
         // Validate configuration
         config.validate()?;
 
-        // Create custom region for MinIO endpoint
-        let region = Region::Custom {
+        let credentials = Self::credentials_from_config(config)?;
+        let raw_bucket = Self::bucket_from_config(config, config.get_raw_bucket_name(), credentials.clone())?;
+        let clean_bucket = Self::bucket_from_config(config, config.get_clean_bucket_name(), credentials)?;
+
+        Ok(Self::with_backends(
+            Box::new(S3Backend::new(raw_bucket)),
+            Box::new(S3Backend::new(clean_bucket)),
+        ))
+    }
+
+    /// Amazon S3 endpoints use the named region so requests are signed and
+    /// routed like AWS expects; anything else is a custom MinIO-style region
+    fn region_from_config(config: &MinioConfig) -> Result<Region> {
+        let endpoint = config.endpoint_url()?;
+        if config.is_aws_endpoint() {
+            match config.get_region().parse::<Region>()? {
+                Region::Custom { .. } => warn!(
+                    "Unknown AWS region '{}', treating {} as a custom endpoint",
+                    config.get_region(),
+                    endpoint
+                ),
+                region => return Ok(region),
+            }
+        }
+
+        Ok(Region::Custom {
             region: config.get_region().to_owned(),
-            endpoint: config.endpoint.clone(),
-        };
+            endpoint,
+        })
+    }
 
-        // Create credentials from config
-        let credentials = Credentials::new(
+    /// Static keys from the environment, or the AWS credential chain
+    /// (environment, shared credentials file, ECS / EC2 instance metadata)
+    /// when the config falls back to it. Chain credentials are refreshed by
+    /// the bucket once they expire.
+    fn credentials_from_config(config: &MinioConfig) -> Result<Credentials> {
+        if config.uses_credential_chain() {
+            let profile = config.aws_profile();
+            let credentials = Credentials::new(None, None, None, None, profile.as_deref())
+                .context("No credentials found in the AWS credential chain")?;
+            info!("Using MinIO credentials from the AWS credential chain");
+            return Ok(credentials);
+        }
+
+        Ok(Credentials::new(
             Some(config.get_access_key()?),
             Some(config.get_secret_key()?),
             None, // security_token
             None, // session_token
-            None, // expiration
-        )?;
+            None, // profile
+        )?)
+    }
+
+    fn bucket_from_config(config: &MinioConfig, bucket_name: &str, credentials: Credentials) -> Result<Bucket> {
+        let region = Self::region_from_config(config)?;
 
         // Create bucket instance
-        let bucket = Bucket::new(&config.bucket_name, region, credentials)?;
+        let bucket = Bucket::new(bucket_name, region, credentials)?;
 
         // Configure path-style if specified
         let bucket = if config.is_path_style() {
@@ -72,7 +342,7 @@ impl MinioStorage {
             *bucket
         };
 
-        Ok(MinioStorage { bucket })
+        Ok(bucket)
     }
 
     pub fn from_config_file(config_path: &str) -> Result<Self> {
@@ -86,25 +356,37 @@ impl MinioStorage {
         Self::from_config(&config)
     }
 
+    fn backend(&self, tier: StorageTier) -> &dyn ObjectBackend {
+        match tier {
+            StorageTier::Raw => self.raw.as_ref(),
+            StorageTier::Clean => self.clean.as_ref(),
+        }
+    }
+
+    fn has_separate_tiers(&self) -> bool {
+        self.raw.bucket_name() != self.clean.bucket_name()
+    }
+
+    /// Make sure the raw and clean buckets exist, creating them if needed
     pub async fn ensure_bucket(&self) -> Result<()> {
+        Self::ensure_backend_bucket(self.raw.as_ref()).await?;
+        if self.has_separate_tiers() {
+            Self::ensure_backend_bucket(self.clean.as_ref()).await?;
+        }
+        Ok(())
+    }
+
+    async fn ensure_backend_bucket(backend: &dyn ObjectBackend) -> Result<()> {
         // Check if bucket exists
-        match self.bucket.exists().await {
+        match backend.bucket_exists().await {
             Ok(true) => {
-                info!("Bucket '{}' already exists", self.bucket.name);
+                info!("Bucket '{}' already exists", backend.bucket_name());
             }
             Ok(false) => {
                 // Try to create the bucket
-                let config = s3::BucketConfiguration::default();
-                let response = s3::Bucket::create(
-                    &self.bucket.name,
-                    self.bucket.region.clone(),
-                    self.bucket.credentials().await?,
-                    config,
-                )
-                .await;
-                match response {
+                match backend.create_bucket().await {
                     Ok(_) => {
-                        info!("Created bucket: {}", self.bucket.name);
+                        info!("Created bucket: {}", backend.bucket_name());
                     }
                     Err(e) => {
                         return Err(anyhow!("Failed to create bucket: {}", e));
@@ -119,6 +401,30 @@ impl MinioStorage {
     }
 
     pub async fn store_raw_json(&self, api_name: &str, data: &str) -> Result<String> {
+        Ok(self.store_raw_json_checked(api_name, data, false).await?.key().to_string())
+    }
+
+    /// Store a raw dump unless it is identical to the latest one for this API.
+    ///
+    /// The SHA-256 of every stored dump is kept in a `.sha256` sidecar next to
+    /// it. When the new payload hashes the same as the latest dump, nothing is
+    /// uploaded and the existing key is returned. `force` always stores.
+    pub async fn store_raw_json_checked(
+        &self,
+        api_name: &str,
+        data: &str,
+        force: bool,
+    ) -> Result<RawStoreOutcome> {
+        let hash = content_hash(data.as_bytes());
+
+        if !force
+            && let Some(latest_key) = self.get_latest_raw_file(api_name).await?
+            && self.stored_raw_hash(&latest_key).await.as_deref() == Some(hash.as_str())
+        {
+            info!("Raw data for {} unchanged since {}, skipping upload", api_name, latest_key);
+            return Ok(RawStoreOutcome::Unchanged(latest_key));
+        }
+
         let date = Utc::now().format("%Y/%m/%d").to_string();
         let timestamp = Utc::now().format("%H%M%S").to_string();
         let file_name = format!(
@@ -129,16 +435,47 @@ impl MinioStorage {
         );
         let key = format!("{}/{}", date, file_name);
 
-        let response = self.bucket.put_object(&key, data.as_bytes()).await?;
+        let status = self.raw.put_object(&key, data.as_bytes()).await?;
 
-        if response.status_code() == 200 {
+        if status == 200 {
             info!("Stored raw JSON: {}", key);
-            Ok(key)
+            // A missing sidecar only means the next identical dump is stored again
+            if let Err(e) = self.raw.put_object(&raw_hash_key(&key), hash.as_bytes()).await {
+                warn!("Failed to store content hash for {}: {}", key, e);
+            }
+            Ok(RawStoreOutcome::Stored(key))
         } else {
-            Err(anyhow!(
-                "Failed to store object: HTTP {}",
-                response.status_code()
-            ))
+            Err(anyhow!("Failed to store object: HTTP {}", status))
+        }
+    }
+
+    /// Content hash recorded when a raw dump was stored, if any
+    async fn stored_raw_hash(&self, raw_key: &str) -> Option<String> {
+        match self.raw.get_object(&raw_hash_key(raw_key)).await {
+            Ok((200, body)) => String::from_utf8(body).ok().map(|hash| hash.trim().to_string()),
+            _ => None,
+        }
+    }
+
+    /// Mark the raw dump at `raw_key` as fetched by a limited run in a
+    /// `.limited` sidecar
+    pub async fn store_limited_fetch(&self, raw_key: &str, limited: &LimitedFetch) -> Result<()> {
+        let key = raw_limited_key(raw_key);
+        let status = self.raw.put_object(&key, serde_json::to_string(limited)?.as_bytes()).await?;
+        if status != 200 {
+            return Err(anyhow!("Failed to store {}: HTTP {}", key, status));
+        }
+        Ok(())
+    }
+
+    /// How the raw dump at `raw_key` was limited, `None` for a full snapshot
+    pub async fn load_limited_fetch(&self, raw_key: &str) -> Result<Option<LimitedFetch>> {
+        let key = raw_limited_key(raw_key);
+        match self.raw.get_object(&key).await {
+            Ok((200, body)) => Ok(Some(
+                serde_json::from_slice(&body).with_context(|| format!("Invalid limited fetch sidecar {}", key))?,
+            )),
+            _ => Ok(None),
         }
     }
 
@@ -152,45 +489,264 @@ impl MinioStorage {
             timestamp
         );
 
-        let response = self.bucket.put_object(&key, data).await?;
+        let status = self.clean.put_object(&key, data).await?;
 
-        if response.status_code() == 200 {
+        if status == 200 {
             info!("Stored Parquet file: {}", key);
             Ok(key)
         } else {
-            Err(anyhow!(
-                "Failed to store parquet file: HTTP {}",
-                response.status_code()
-            ))
+            Err(anyhow!("Failed to store parquet file: HTTP {}", status))
+        }
+    }
+
+    /// Store a clean DataFrame as Arrow IPC (Feather v2) under
+    /// `clean_arrow/{api}/date=YYYY-MM-DD/data.arrow`, replacing that day's
+    /// earlier copy. Column types, including categorical dictionaries, are
+    /// kept as they are in the Parquet file.
+    pub async fn store_arrow_ipc(
+        &self,
+        api_name: &str,
+        date: NaiveDate,
+        df: &mut DataFrame,
+        compression: ArrowCompression,
+    ) -> Result<String> {
+        let key = format!("clean_arrow/{}/date={}/data.arrow", api_name, date.format("%Y-%m-%d"));
+        let mut buf = Vec::new();
+        IpcWriter::new(&mut buf)
+            .with_compression(compression.ipc_compression())
+            .finish(df)
+            .with_context(|| format!("Failed to encode {} as Arrow IPC", api_name))?;
+
+        let status = self.clean.put_object(&key, &buf).await?;
+        if status == 200 {
+            info!("Stored Arrow IPC file: {}", key);
+            Ok(key)
+        } else {
+            Err(anyhow!("Failed to store Arrow IPC file: HTTP {}", status))
+        }
+    }
+
+    /// Store a clean DataFrame as CSV under `clean_csv/{api}/date=YYYY-MM-DD/data.csv`,
+    /// replacing that day's earlier copy. Fields are quoted only when they
+    /// contain the delimiter, a quote or a newline, with quotes doubled (RFC 4180).
+    pub async fn store_csv(&self, api_name: &str, date: NaiveDate, df: &mut DataFrame, delimiter: u8) -> Result<String> {
+        let key = format!("clean_csv/{}/date={}/data.csv", api_name, date.format("%Y-%m-%d"));
+        let mut buf = Vec::new();
+        CsvWriter::new(&mut buf)
+            .with_separator(delimiter)
+            .with_quote_style(QuoteStyle::Necessary)
+            .finish(df)
+            .with_context(|| format!("Failed to encode {} as CSV", api_name))?;
+
+        let status = self.clean.put_object(&key, &buf).await?;
+        if status == 200 {
+            info!("Stored CSV file: {}", key);
+            Ok(key)
+        } else {
+            Err(anyhow!("Failed to store CSV file: HTTP {}", status))
+        }
+    }
+
+    /// Store a clean DataFrame as gzipped JSON Lines under
+    /// `clean_ndjson/{api}/date=YYYY-MM-DD/data.ndjson.gz`, one object per
+    /// row in row order (see `encode_ndjson`), replacing that day's earlier copy
+    pub async fn store_ndjson(&self, api_name: &str, date: NaiveDate, df: &DataFrame, explicit_nulls: bool) -> Result<String> {
+        let key = format!("clean_ndjson/{}/date={}/data.ndjson.gz", api_name, date.format("%Y-%m-%d"));
+        let lines = encode_ndjson(df, explicit_nulls).with_context(|| format!("Failed to encode {} as NDJSON", api_name))?;
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(lines.as_bytes())?;
+        let buf = encoder.finish()?;
+
+        let status = self.clean.put_object(&key, &buf).await?;
+        if status == 200 {
+            info!("Stored NDJSON file: {}", key);
+            Ok(key)
+        } else {
+            Err(anyhow!("Failed to store NDJSON file: HTTP {}", status))
+        }
+    }
+
+    /// Store a snapshot diff as `changes/{api}/YYYY-MM-DD.parquet`
+    pub async fn store_changes(&self, api_name: &str, date: NaiveDate, data: &[u8]) -> Result<String> {
+        let key = format!("changes/{}/{}.parquet", api_name, date.format("%Y-%m-%d"));
+
+        let status = self.clean.put_object(&key, data).await?;
+
+        if status == 200 {
+            info!("Stored changes file: {}", key);
+            Ok(key)
+        } else {
+            Err(anyhow!("Failed to store changes file: HTTP {}", status))
+        }
+    }
+
+    /// Store a diff between two days' clean snapshots as
+    /// `reports/{api}/diff_YYYY-MM-DD_YYYY-MM-DD.parquet`
+    pub async fn store_snapshot_diff(&self, api_name: &str, date_a: NaiveDate, date_b: NaiveDate, data: &[u8]) -> Result<String> {
+        let key = format!(
+            "reports/{}/diff_{}_{}.parquet",
+            api_name,
+            date_a.format("%Y-%m-%d"),
+            date_b.format("%Y-%m-%d")
+        );
+        self.put_clean_object(&key, data).await
+    }
+
+    /// Store the products a run failed to extract as `errors/{api}/YYYY-MM-DD.json`
+    pub async fn store_error_report(&self, api_name: &str, date: NaiveDate, report_json: &str) -> Result<String> {
+        let key = format!("errors/{}/{}.json", api_name, date.format("%Y-%m-%d"));
+        self.put_clean_object(&key, report_json.as_bytes()).await
+    }
+
+    /// Store the products a run failed to extract, in full, as
+    /// `rejected/{api}/YYYY-MM-DD.json`
+    pub async fn store_rejected_records(&self, api_name: &str, date: NaiveDate, records_json: &str) -> Result<String> {
+        let key = format!("rejected/{}/{}.json", api_name, date.format("%Y-%m-%d"));
+        self.put_clean_object(&key, records_json.as_bytes()).await
+    }
+
+    /// Store how a source's columns were classified as
+    /// `reports/{api}/YYYY-MM-DD/classification.json`
+    pub async fn store_classification_report(&self, api_name: &str, date: NaiveDate, report_json: &str) -> Result<String> {
+        let key = format!("reports/{}/{}/classification.json", api_name, date.format("%Y-%m-%d"));
+        self.put_clean_object(&key, report_json.as_bytes()).await
+    }
+
+    /// Store the stats the next snapshot of a source is compared against as
+    /// `reports/{api}/baseline_stats.json`, replacing the previous baseline
+    pub async fn store_baseline_stats(&self, api_name: &str, stats: &SnapshotStats) -> Result<String> {
+        let key = Self::baseline_stats_key(api_name);
+        self.put_clean_object(&key, serde_json::to_string_pretty(stats)?.as_bytes()).await
+    }
+
+    /// The stats stored by `store_baseline_stats`, `None` before a source's first run
+    pub async fn load_baseline_stats(&self, api_name: &str) -> Result<Option<SnapshotStats>> {
+        let key = Self::baseline_stats_key(api_name);
+        if !self.clean.list_keys(&key).await?.contains(&key) {
+            return Ok(None);
+        }
+        let bytes = self.get_object(&key).await?;
+        let stats = serde_json::from_slice(&bytes).with_context(|| format!("Invalid baseline stats in {}", key))?;
+        Ok(Some(stats))
+    }
+
+    fn baseline_stats_key(api_name: &str) -> String {
+        format!("reports/{}/baseline_stats.json", api_name)
+    }
+
+    /// Store a pipeline run's report as `reports/YYYY-MM-DD/run_HHMMSS.json`
+    pub async fn store_run_report(&self, started_at: DateTime<Utc>, report_json: &str) -> Result<String> {
+        let key = format!("reports/{}/run_{}.json", started_at.format("%Y-%m-%d"), started_at.format("%H%M%S"));
+        self.put_clean_object(&key, report_json.as_bytes()).await
+    }
+
+    /// Store a run's quality flag counts as `reports/YYYY-MM-DD/quality_HHMMSS.json`
+    pub async fn store_data_quality_report(&self, started_at: DateTime<Utc>, report_json: &str) -> Result<String> {
+        let key = format!("reports/{}/quality_{}.json", started_at.format("%Y-%m-%d"), started_at.format("%H%M%S"));
+        self.put_clean_object(&key, report_json.as_bytes()).await
+    }
+
+    /// Store the merged multi-source dataset as `clean/_merged/date=YYYY-MM-DD/merged.parquet`
+    pub async fn store_merged_parquet(&self, date: NaiveDate, data: &[u8]) -> Result<String> {
+        let key = format!("{}/merged.parquet", Self::merged_prefix(date));
+        self.put_clean_object(&key, data).await
+    }
+
+    /// Store the manifest describing a merged dataset next to it
+    pub async fn store_merged_manifest(&self, date: NaiveDate, manifest_json: &str) -> Result<String> {
+        let key = format!("{}/manifest.json", Self::merged_prefix(date));
+        self.put_clean_object(&key, manifest_json.as_bytes()).await
+    }
+
+    /// Store the cross-source product matches of a merged dataset next to it
+    pub async fn store_matches(&self, date: NaiveDate, data: &[u8]) -> Result<String> {
+        let key = format!("{}/matches.parquet", Self::merged_prefix(date));
+        self.put_clean_object(&key, data).await
+    }
+
+    /// Store the products matching was unsure about next to the matches
+    pub async fn store_ambiguous_matches(&self, date: NaiveDate, report_json: &str) -> Result<String> {
+        let key = format!("{}/ambiguous_matches.json", Self::merged_prefix(date));
+        self.put_clean_object(&key, report_json.as_bytes()).await
+    }
+
+    fn merged_prefix(date: NaiveDate) -> String {
+        format!("clean/_merged/date={}", date.format("%Y-%m-%d"))
+    }
+
+    async fn put_clean_object(&self, key: &str, data: &[u8]) -> Result<String> {
+        let status = self.clean.put_object(key, data).await?;
+
+        if status == 200 {
+            info!("Stored object: {}", key);
+            Ok(key.to_string())
+        } else {
+            Err(anyhow!("Failed to store object: HTTP {}", status))
         }
     }
 
     #[allow(dead_code)]
     pub async fn list_objects(&self, prefix: Option<&str>) -> Result<Vec<String>> {
-        let prefix_str = prefix.unwrap_or("").to_string();
-        let list = self.bucket.list(prefix_str, None).await?;
+        let prefix_str = prefix.unwrap_or("");
 
-        let mut object_names = Vec::new();
-        for result in list {
-            for object in result.contents {
-                object_names.push(object.key);
-            }
+        let mut object_names = self.raw.list_keys(prefix_str).await?;
+        if self.has_separate_tiers() {
+            object_names.extend(self.clean.list_keys(prefix_str).await?);
         }
 
         Ok(object_names)
     }
 
+    /// Download an object. A transfer that breaks off is resumed from the
+    /// bytes already received, up to `GET_ATTEMPTS` times in all, so a flaky
+    /// connection does not restart large raw dumps from scratch.
     pub async fn get_object(&self, object_name: &str) -> Result<Vec<u8>> {
-        let response = self.bucket.get_object(object_name).await?;
+        let backend = self.backend(StorageTier::for_key(object_name));
+        let mut bytes = Vec::new();
+        let mut attempt = 1;
+        loop {
+            match Self::get_object_attempt(backend, object_name, &mut bytes).await {
+                Ok(None) => return Ok(bytes),
+                Ok(Some(status)) => return Err(anyhow!("Failed to get object: HTTP {}", status)),
+                Err(e) if attempt < GET_ATTEMPTS => {
+                    warn!(
+                        "Download of {} broke off after {} bytes (attempt {}/{}): {}, resuming",
+                        object_name,
+                        bytes.len(),
+                        attempt,
+                        GET_ATTEMPTS,
+                        e
+                    );
+                    tokio::time::sleep(GET_RETRY_DELAY * attempt as u32).await;
+                    attempt += 1;
+                }
+                Err(e) => {
+                    return Err(e.context(format!("Failed to get {} after {} attempts ({} bytes received)", object_name, attempt, bytes.len())));
+                }
+            }
+        }
+    }
 
-        if response.status_code() == 200 {
-            Ok(response.bytes().to_vec())
-        } else {
-            Err(anyhow!(
-                "Failed to get object: HTTP {}",
-                response.status_code()
-            ))
+    /// One download attempt from the bytes received so far, returning the
+    /// HTTP status it failed with, if any. A resumed attempt appends only a
+    /// 206 partial body: a 200 means the range was ignored and the whole
+    /// object is coming, so it replaces the partial bytes. A 416 once every
+    /// byte has arrived means the last attempt broke off after the end.
+    async fn get_object_attempt(backend: &dyn ObjectBackend, object_name: &str, bytes: &mut Vec<u8>) -> Result<Option<u16>> {
+        let offset = bytes.len() as u64;
+        let (status, mut body) = backend.get_object_from(object_name, offset).await?;
+        if status == 416 && offset > 0 && backend.object_size(object_name).await? == offset {
+            return Ok(None);
         }
+        match status {
+            200 => bytes.clear(),
+            206 if offset > 0 => {}
+            status => return Ok(Some(status)),
+        }
+        while let Some(chunk) = body.next().await {
+            bytes.extend_from_slice(&chunk?);
+        }
+        Ok(None)
     }
 
     /// Get raw JSON data as string from S3/MinIO
@@ -204,15 +760,13 @@ impl MinioStorage {
     /// List all raw JSON files for a specific API source
     pub async fn list_raw_files(&self, api_name: &str) -> Result<Vec<String>> {
         // List all objects and filter for raw files of this API
-        let list = self.bucket.list("".to_string(), None).await?;
+        let keys = self.raw.list_keys("").await?;
 
         let mut raw_files = Vec::new();
-        for result in list {
-            for object in result.contents {
-                // Check if this is a raw JSON file for the specified API
-                if object.key.contains(&format!("raw/{}/", api_name)) && object.key.ends_with(".json") {
-                    raw_files.push(object.key);
-                }
+        for key in keys {
+            // Check if this is a raw JSON file for the specified API
+            if key.contains(&format!("raw/{}/", api_name)) && key.ends_with(".json") {
+                raw_files.push(key);
             }
         }
 
@@ -221,19 +775,174 @@ impl MinioStorage {
         Ok(raw_files)
     }
 
+    /// List all clean Parquet files for a specific API source, most recent first
+    pub async fn list_clean_files(&self, api_name: &str) -> Result<Vec<String>> {
+        let prefix = format!("clean/{}/", api_name);
+        let mut clean_files: Vec<String> = self
+            .clean
+            .list_keys(&prefix)
+            .await?
+            .into_iter()
+            .filter(|key| key.ends_with(".parquet"))
+            .collect();
+
+        // Keys embed the run timestamp, so reverse lexical order is newest first
+        clean_files.sort_by(|a, b| b.cmp(a));
+        Ok(clean_files)
+    }
+
+    /// Load a clean Parquet file into a DataFrame
+    pub async fn load_parquet(&self, object_name: &str) -> Result<DataFrame> {
+        let bytes = self.get_object(object_name).await?;
+        let df = ParquetReader::new(Cursor::new(bytes))
+            .finish()
+            .with_context(|| format!("Failed to read parquet file: {}", object_name))?;
+        Ok(df)
+    }
+
+    /// Provenance a clean Parquet file was stored with, `None` for files
+    /// written before it was recorded
+    pub async fn load_parquet_metadata(&self, object_name: &str) -> Result<Option<CleanFileMetadata>> {
+        let bytes = self.get_object(object_name).await?;
+        read_parquet_metadata(&bytes).with_context(|| format!("Failed to read parquet metadata: {}", object_name))
+    }
+
     /// Get the most recent raw JSON file for a specific API source
     pub async fn get_latest_raw_file(&self, api_name: &str) -> Result<Option<String>> {
         let raw_files = self.list_raw_files(api_name).await?;
         Ok(raw_files.into_iter().next())
     }
 
+    /// Get the raw JSON file fetched on a specific date. If several runs
+    /// happened that day the latest one wins.
+    pub async fn get_raw_file_for_date(&self, api_name: &str, date: NaiveDate) -> Result<String> {
+        let raw_files = self.list_raw_files(api_name).await?;
+        let same_day: Vec<&String> = raw_files
+            .iter()
+            .filter(|key| Self::raw_file_date(key) == Some(date))
+            .collect();
+
+        match same_day.first() {
+            Some(key) => {
+                if same_day.len() > 1 {
+                    info!(
+                        "Found {} raw snapshots for {} on {}, using latest: {}",
+                        same_day.len(), api_name, date, key
+                    );
+                }
+                Ok(key.to_string())
+            }
+            None => {
+                let mut available: Vec<String> = raw_files
+                    .iter()
+                    .filter_map(|key| Self::raw_file_date(key))
+                    .map(|d| d.to_string())
+                    .collect();
+                available.sort();
+                available.dedup();
+
+                if available.is_empty() {
+                    Err(anyhow!("No raw data files found for API: {}", api_name))
+                } else {
+                    Err(anyhow!(
+                        "No raw data for API {} on {}. Available dates: {}",
+                        api_name, date, available.join(", ")
+                    ))
+                }
+            }
+        }
+    }
+
+    /// The last clean Parquet file a source was stored under on `date`
+    pub async fn get_clean_file_for_date(&self, api_name: &str, date: NaiveDate) -> Result<String> {
+        let clean_files = self.list_clean_files(api_name).await?;
+        if let Some(key) = clean_files.iter().find(|key| Self::raw_file_date(key) == Some(date)) {
+            return Ok(key.clone());
+        }
+
+        let mut available: Vec<String> = clean_files
+            .iter()
+            .filter_map(|key| Self::raw_file_date(key))
+            .map(|d| d.to_string())
+            .collect();
+        available.sort();
+        available.dedup();
+        if available.is_empty() {
+            Err(anyhow!("No clean snapshots found for API: {}", api_name))
+        } else {
+            Err(anyhow!(
+                "No clean snapshot for API {} on {}. Available dates: {}",
+                api_name, date, available.join(", ")
+            ))
+        }
+    }
+
+    /// Raw file of a source selected by `snapshot`. A key must be one of the
+    /// source's raw files.
+    pub async fn resolve_raw_file(&self, api_name: &str, snapshot: &RawSnapshot) -> Result<String> {
+        match snapshot {
+            RawSnapshot::Latest => self
+                .get_latest_raw_file(api_name)
+                .await?
+                .ok_or_else(|| anyhow!("No raw data files found for API: {}", api_name)),
+            RawSnapshot::Date(date) => self.get_raw_file_for_date(api_name, *date).await,
+            RawSnapshot::Key(key) => {
+                if self.list_raw_files(api_name).await?.contains(key) {
+                    Ok(key.clone())
+                } else {
+                    Err(anyhow!("Raw object {} not found for API {}", key, api_name))
+                }
+            }
+        }
+    }
+
+    /// API a raw file was stored for, from its `.../raw/{api}/{file}` key
+    pub fn raw_file_api(key: &str) -> Option<&str> {
+        let mut segments = key.rsplit('/');
+        segments.next()?;
+        let api_name = segments.next()?;
+        (segments.next()? == "raw" && !api_name.is_empty()).then_some(api_name)
+    }
+
+    /// Date a raw or clean file was written, taken from its
+    /// `{YYYYMMDD}-{HHMMSS}` file name
+    pub fn raw_file_date(key: &str) -> Option<NaiveDate> {
+        let file_name = key.rsplit('/').next()?;
+        let date_part = file_name.split('-').next()?;
+        NaiveDate::parse_from_str(date_part, "%Y%m%d").ok()
+    }
+
+    /// When a raw file was fetched, from its `{YYYYMMDD}-{HHMMSS}.json` file name (UTC)
+    pub fn raw_file_fetched_at(key: &str) -> Option<DateTime<Utc>> {
+        let file_name = key.rsplit('/').next()?;
+        let stem = file_name.strip_suffix(".json").unwrap_or(file_name);
+        NaiveDateTime::parse_from_str(stem, "%Y%m%d-%H%M%S")
+            .ok()
+            .map(|at| at.and_utc())
+    }
+
     /// Load and parse raw JSON data from the most recent file for an API source
     pub async fn load_latest_raw_data(&self, api_name: &str) -> Result<Vec<serde_json::Value>> {
         let latest_file = self.get_latest_raw_file(api_name).await?
             .ok_or_else(|| anyhow!("No raw data files found for API: {}", api_name))?;
 
-        info!("Loading raw data from: {}", latest_file);
-        let json_str = self.get_raw_json(&latest_file).await?;
+        self.load_raw_file(&latest_file).await
+    }
+
+    /// Load and parse raw JSON data fetched on a specific date
+    pub async fn load_raw_data_for_date(
+        &self,
+        api_name: &str,
+        date: NaiveDate,
+    ) -> Result<Vec<serde_json::Value>> {
+        let file = self.get_raw_file_for_date(api_name, date).await?;
+        self.load_raw_file(&file).await
+    }
+
+    /// Load and parse a raw JSON file
+    pub async fn load_raw_file(&self, object_name: &str) -> Result<Vec<serde_json::Value>> {
+        info!("Loading raw data from: {}", object_name);
+        let json_str = self.get_raw_json(object_name).await?;
         let data: Vec<serde_json::Value> = serde_json::from_str(&json_str)
             .map_err(|e| anyhow!("Failed to parse JSON data: {}", e))?;
 
@@ -250,8 +959,17 @@ impl MinioStorage {
         let latest_file = self.get_latest_raw_file(api_name).await?
             .ok_or_else(|| anyhow!("No raw data files found for API: {}", api_name))?;
 
-        info!("Streaming raw data in batches of {} from: {}", batch_size, latest_file);
-        let json_str = self.get_raw_json(&latest_file).await?;
+        self.stream_raw_file_batched(&latest_file, batch_size).await
+    }
+
+    /// Stream a raw JSON file in batches
+    pub async fn stream_raw_file_batched(
+        &self,
+        object_name: &str,
+        batch_size: usize
+    ) -> Result<std::vec::IntoIter<Result<Vec<serde_json::Value>>>> {
+        info!("Streaming raw data in batches of {} from: {}", batch_size, object_name);
+        let json_str = self.get_raw_json(object_name).await?;
 
         // Parse the entire JSON array first (we need to do this to get individual items)
         let data: Vec<serde_json::Value> = serde_json::from_str(&json_str)
@@ -272,44 +990,231 @@ impl MinioStorage {
         let latest_file = self.get_latest_raw_file(api_name).await?
             .ok_or_else(|| anyhow!("No raw data files found for API: {}", api_name))?;
 
+        let total = self.count_raw_records(&latest_file).await?;
+        Ok((latest_file, total))
+    }
+
+    /// Number of records in a raw JSON file
+    pub async fn count_raw_records(&self, object_name: &str) -> Result<usize> {
         // Get file size by loading just the JSON structure
-        let json_str = self.get_raw_json(&latest_file).await?;
+        let json_str = self.get_raw_json(object_name).await?;
         let data: Vec<serde_json::Value> = serde_json::from_str(&json_str)
             .map_err(|e| anyhow!("Failed to parse JSON data: {}", e))?;
 
-        Ok((latest_file, data.len()))
+        Ok(data.len())
     }
 
     #[allow(dead_code)]
     pub async fn delete_object(&self, object_name: &str) -> Result<()> {
-        let response = self.bucket.delete_object(object_name).await?;
+        let backend = self.backend(StorageTier::for_key(object_name));
+        let status = backend.delete_object(object_name).await?;
 
-        if response.status_code() == 204 || response.status_code() == 200 {
+        if status == 204 || status == 200 {
             info!("Deleted object: {}", object_name);
             Ok(())
         } else {
-            Err(anyhow!(
-                "Failed to delete object: HTTP {}",
-                response.status_code()
-            ))
+            Err(anyhow!("Failed to delete object: HTTP {}", status))
         }
     }
 
-    #[allow(dead_code)]
-    pub fn get_bucket_name(&self) -> &str {
-        &self.bucket.name
-    }
-}
+    /// Delete many objects at once, in batches of `DELETE_BATCH_SIZE`
+    pub async fn delete_objects(&self, keys: &[String], dry_run: bool) -> Result<DeleteSummary> {
+        let mut summary = DeleteSummary {
+            dry_run,
+            ..Default::default()
+        };
 
-impl Default for MinioStorage {
-    fn default() -> Self {
-        // Try to create from default config file first
-        if let Ok(storage) = Self::from_config_file("src/configs/minio.toml") {
-            return storage;
+        if self.has_separate_tiers() {
+            let (clean_keys, raw_keys): (Vec<String>, Vec<String>) = keys
+                .iter()
+                .cloned()
+                .partition(|key| StorageTier::for_key(key) == StorageTier::Clean);
+            Self::delete_from_backend(self.raw.as_ref(), &raw_keys, &mut summary).await?;
+            Self::delete_from_backend(self.clean.as_ref(), &clean_keys, &mut summary).await?;
+        } else {
+            Self::delete_from_backend(self.raw.as_ref(), keys, &mut summary).await?;
         }
 
-        // Fallback to hardcoded defaults if config file doesn't exist
-        Self::new(
+        Self::log_delete_summary(&summary);
+        Ok(summary)
+    }
+
+    /// Delete every object whose key starts with `prefix`
+    pub async fn delete_prefix(&self, prefix: &str, dry_run: bool) -> Result<DeleteSummary> {
+        let mut summary = DeleteSummary {
+            dry_run,
+            ..Default::default()
+        };
+
+        let raw_keys = self.raw.list_keys(prefix).await?;
+        Self::delete_from_backend(self.raw.as_ref(), &raw_keys, &mut summary).await?;
+
+        if self.has_separate_tiers() {
+            let clean_keys = self.clean.list_keys(prefix).await?;
+            Self::delete_from_backend(self.clean.as_ref(), &clean_keys, &mut summary).await?;
+        }
+
+        Self::log_delete_summary(&summary);
+        Ok(summary)
+    }
+
+    async fn delete_from_backend(
+        backend: &dyn ObjectBackend,
+        keys: &[String],
+        summary: &mut DeleteSummary,
+    ) -> Result<()> {
+        for batch in keys.chunks(DELETE_BATCH_SIZE) {
+            if summary.dry_run {
+                summary.deleted.extend_from_slice(batch);
+                continue;
+            }
+
+            let failed = backend.delete_objects(batch).await?;
+            summary
+                .deleted
+                .extend(batch.iter().filter(|key| !failed.contains(key)).cloned());
+            summary.failed.extend(failed);
+        }
+        Ok(())
+    }
+
+    fn log_delete_summary(summary: &DeleteSummary) {
+        if summary.dry_run {
+            info!("Dry run: would delete {} objects", summary.deleted_count());
+        } else {
+            info!(
+                "Deleted {} objects ({} failed)",
+                summary.deleted_count(),
+                summary.failed_count()
+            );
+        }
+    }
+
+    /// Verify connectivity, credentials, bucket existence and a
+    /// write/read/delete round trip, reporting each stage separately
+    pub async fn health_check(&self) -> HealthReport {
+        let mut report = HealthReport::default();
+
+        Self::run_check(&mut report, "connectivity", || async {
+            self.raw
+                .check_connectivity()
+                .await
+                .map_err(|e| anyhow!("Cannot reach storage endpoint (DNS/network): {}", e))
+        })
+        .await;
+
+        Self::run_check(&mut report, "authentication", || async {
+            self.raw
+                .bucket_exists()
+                .await
+                .map(|_| "credentials accepted".to_string())
+                .map_err(|e| {
+                    anyhow!(
+                        "Authentication failed, check MINIO_ACCESS_KEY/MINIO_SECRET_KEY: {}",
+                        e
+                    )
+                })
+        })
+        .await;
+
+        Self::check_bucket_round_trip(self.raw.as_ref(), &mut report).await;
+        if self.has_separate_tiers() {
+            Self::check_bucket_round_trip(self.clean.as_ref(), &mut report).await;
+        }
+
+        report
+    }
+
+    async fn check_bucket_round_trip(backend: &dyn ObjectBackend, report: &mut HealthReport) {
+        let bucket = backend.bucket_name();
+        let probe_key = format!("_healthcheck/probe-{}.txt", uuid::Uuid::new_v4());
+        let payload = b"data-pipeline health check";
+
+        Self::run_check(report, &format!("bucket [{}]", bucket), || async {
+            match backend.bucket_exists().await {
+                Ok(true) => Ok("bucket exists".to_string()),
+                Ok(false) => Err(anyhow!(
+                    "Bucket '{}' does not exist, create it or run the pipeline once",
+                    bucket
+                )),
+                Err(e) => Err(anyhow!("Failed to check bucket '{}': {}", bucket, e)),
+            }
+        })
+        .await;
+
+        Self::run_check(report, &format!("write [{}]", bucket), || async {
+            match backend.put_object(&probe_key, payload).await {
+                Ok(200) => Ok(format!("wrote {}", probe_key)),
+                Ok(status) => Err(anyhow!("Write to '{}' failed: HTTP {}", bucket, status)),
+                Err(e) => Err(anyhow!("Write to '{}' failed: {}", bucket, e)),
+            }
+        })
+        .await;
+
+        Self::run_check(report, &format!("read [{}]", bucket), || async {
+            match backend.get_object(&probe_key).await {
+                Ok((200, data)) if data == payload => Ok("probe object read back".to_string()),
+                Ok((200, _)) => Err(anyhow!("Read from '{}' returned unexpected content", bucket)),
+                Ok((status, _)) => Err(anyhow!("Read from '{}' failed: HTTP {}", bucket, status)),
+                Err(e) => Err(anyhow!("Read from '{}' failed: {}", bucket, e)),
+            }
+        })
+        .await;
+
+        Self::run_check(report, &format!("delete [{}]", bucket), || async {
+            match backend.delete_object(&probe_key).await {
+                Ok(200) | Ok(204) => Ok("probe object deleted".to_string()),
+                Ok(status) => Err(anyhow!("Delete from '{}' failed: HTTP {}", bucket, status)),
+                Err(e) => Err(anyhow!("Delete from '{}' failed: {}", bucket, e)),
+            }
+        })
+        .await;
+    }
+
+    /// Run one health check stage, skipping it if an earlier stage failed
+    async fn run_check<F, Fut>(report: &mut HealthReport, name: &str, check: F)
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<String>>,
+    {
+        if report.has_failed() {
+            report.push(HealthCheck::skipped(name));
+            return;
+        }
+
+        let started = Instant::now();
+        let result = check().await;
+        let latency = started.elapsed();
+
+        report.push(match result {
+            Ok(message) => HealthCheck::passed(name, latency, message),
+            Err(e) => HealthCheck::failed(name, latency, e.to_string()),
+        });
+    }
+
+    #[allow(dead_code)]
+    pub fn get_bucket_name(&self) -> &str {
+        self.raw.bucket_name()
+    }
+
+    pub fn get_raw_bucket_name(&self) -> &str {
+        self.raw.bucket_name()
+    }
+
+    pub fn get_clean_bucket_name(&self) -> &str {
+        self.clean.bucket_name()
+    }
+}
+
+impl Default for MinioStorage {
+    fn default() -> Self {
+        // Try to create from default config file first
+        if let Ok(storage) = Self::from_config_file("src/configs/minio.toml") {
+            return storage;
+        }
+
+        // Fallback to hardcoded defaults if config file doesn't exist
+        Self::new(
             "http://localhost:9000",
             "minioadmin",
             "minioadmin",
@@ -322,6 +1227,10 @@ impl Default for MinioStorage {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::storage::backend::{MemoryBackend, ObjectStream};
+    use crate::storage::health::CheckStatus;
+    use async_trait::async_trait;
+    use chrono::TimeZone;
     use std::env;
 
     #[test]
@@ -392,6 +1301,35 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_bulk_delete_objects() {
+        // This test requires a running MinIO instance
+        if std::env::var("MINIO_TEST_ENABLED").is_ok() {
+            let storage = MinioStorage::new(
+                "http://localhost:9000",
+                "minioadmin",
+                "minioadmin",
+                "test-bucket",
+            )
+            .unwrap();
+            storage.ensure_bucket().await.unwrap();
+
+            let keys: Vec<String> = (0..25)
+                .map(|i| format!("bulk-delete-test/object-{:02}.json", i))
+                .collect();
+            for key in &keys {
+                storage.raw.put_object(key, b"{}").await.unwrap();
+            }
+
+            let summary = storage.delete_objects(&keys, false).await.unwrap();
+            assert_eq!(summary.deleted_count(), 25);
+            assert_eq!(summary.failed_count(), 0);
+
+            let remaining = storage.list_objects(Some("bulk-delete-test/")).await.unwrap();
+            assert!(remaining.is_empty());
+        }
+    }
+
     #[test]
     fn test_endpoint_parsing() {
         // Test HTTP endpoint
@@ -404,4 +1342,719 @@ mod tests {
             MinioStorage::new("https://minio.example.com", "key", "secret", "bucket").unwrap();
         assert_eq!(storage.get_bucket_name(), "bucket");
     }
+
+    #[test]
+    fn test_storage_tier_for_key() {
+        assert_eq!(
+            StorageTier::for_key("2025/09/15/raw/krave_mart/20250915-101500.json"),
+            StorageTier::Raw
+        );
+        assert_eq!(
+            StorageTier::for_key("clean/krave_mart/20250915-101500.parquet"),
+            StorageTier::Clean
+        );
+        assert_eq!(StorageTier::for_key("errors/krave_mart/2025-09-15.json"), StorageTier::Clean);
+        assert_eq!(StorageTier::for_key("rejected/krave_mart/2025-09-15.json"), StorageTier::Clean);
+        assert_eq!(StorageTier::for_key("reports/2025-09-15/run_101500.json"), StorageTier::Clean);
+        assert_eq!(StorageTier::for_key("clean_arrow/krave_mart/date=2025-09-15/data.arrow"), StorageTier::Clean);
+        assert_eq!(StorageTier::for_key("clean_csv/krave_mart/date=2025-09-15/data.csv"), StorageTier::Clean);
+        assert_eq!(StorageTier::for_key("clean_ndjson/krave_mart/date=2025-09-15/data.ndjson.gz"), StorageTier::Clean);
+    }
+
+    #[test]
+    fn test_from_config_uses_tier_buckets() {
+        let mut config = MinioConfig::default();
+        config.access_key = Some("test_access".to_string());
+        config.secret_key = Some("test_secret".to_string());
+        config.raw_bucket_name = Some("pipeline-raw".to_string());
+        config.clean_bucket_name = Some("pipeline-clean".to_string());
+
+        let storage = MinioStorage::from_config(&config).unwrap();
+        assert_eq!(storage.get_raw_bucket_name(), "pipeline-raw");
+        assert_eq!(storage.get_clean_bucket_name(), "pipeline-clean");
+    }
+
+    #[test]
+    fn test_region_from_config() {
+        let mut config = MinioConfig::default();
+        config.endpoint = "localhost:9000".to_string();
+        match MinioStorage::region_from_config(&config).unwrap() {
+            Region::Custom { region, endpoint } => {
+                assert_eq!(region, "us-east-1");
+                assert_eq!(endpoint, "http://localhost:9000");
+            }
+            other => panic!("expected a custom region, got {:?}", other),
+        }
+
+        config.endpoint = "https://s3.eu-west-1.amazonaws.com".to_string();
+        config.region = Some("eu-west-1".to_string());
+        assert_eq!(MinioStorage::region_from_config(&config).unwrap(), Region::EuWest1);
+
+        config.endpoint = "http://s3.eu-west-1.amazonaws.com".to_string();
+        config.ssl = Some(true);
+        config.access_key = Some("test_access".to_string());
+        config.secret_key = Some("test_secret".to_string());
+        let err = MinioStorage::from_config(&config).err().unwrap().to_string();
+        assert!(err.contains("does not match ssl = true"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_tier_routing_with_separate_buckets() {
+        let raw = MemoryBackend::new("pipeline-raw");
+        let clean = MemoryBackend::new("pipeline-clean");
+        let storage = MinioStorage::with_backends(Box::new(raw.clone()), Box::new(clean.clone()));
+
+        storage.ensure_bucket().await.unwrap();
+        assert!(raw.bucket_exists().await.unwrap());
+        assert!(clean.bucket_exists().await.unwrap());
+
+        let raw_key = storage
+            .store_raw_json("test-api", r#"[{"name": "Milk"}]"#)
+            .await
+            .unwrap();
+        let clean_key = storage.store_parquet("test-api", b"PAR1").await.unwrap();
+
+        // Raw dumps land in the raw bucket only, parquet in the clean bucket only
+        assert!(raw.contains(&raw_key));
+        assert!(!clean.contains(&raw_key));
+        assert!(clean.contains(&clean_key));
+        assert!(!raw.contains(&clean_key));
+
+        // Reads are routed to the same tier they were written to
+        let data = storage.load_latest_raw_data("test-api").await.unwrap();
+        assert_eq!(data.len(), 1);
+        assert_eq!(storage.get_object(&clean_key).await.unwrap(), b"PAR1");
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_records_writes_without_storing() {
+        let raw = MemoryBackend::new("pipeline-raw");
+        raw.create_bucket().await.unwrap();
+        raw.put_object("2024/03/04/raw/test-api/20240304-090000.json", b"[]").await.unwrap();
+        let dry_raw = DryRunBackend::new(Box::new(raw.clone()));
+        let dry_clean = DryRunBackend::new(Box::new(MemoryBackend::new("pipeline-clean")));
+        let storage = MinioStorage::with_backends(Box::new(dry_raw.clone()), Box::new(dry_clean.clone()));
+
+        // The clean bucket is only created in the dry run, so it reads as empty
+        storage.ensure_bucket().await.unwrap();
+        assert!(storage.list_clean_files("test-api").await.unwrap().is_empty());
+
+        let raw_key = storage.store_raw_json("test-api", r#"[{"name": "Milk"}]"#).await.unwrap();
+        let clean_key = storage.store_parquet("test-api", b"PAR1").await.unwrap();
+        assert_eq!(raw.keys(), vec!["2024/03/04/raw/test-api/20240304-090000.json"]);
+        assert_eq!(dry_clean.intended_writes(), vec![(clean_key.clone(), 4)]);
+        let raw_writes: Vec<String> = dry_raw.intended_writes().into_iter().map(|(key, _)| key).collect();
+        assert_eq!(raw_writes, vec![raw_key.clone(), format!("{}.sha256", raw_key)]);
+
+        // What the run would have stored is read back as the latest snapshot
+        assert_eq!(storage.get_latest_raw_file("test-api").await.unwrap(), Some(raw_key));
+        assert_eq!(storage.load_latest_raw_data("test-api").await.unwrap().len(), 1);
+        assert_eq!(storage.list_clean_files("test-api").await.unwrap(), vec![clean_key]);
+    }
+
+    #[tokio::test]
+    async fn test_raw_file_for_date_picks_latest_that_day() {
+        let raw = MemoryBackend::new("pipeline-raw");
+        let storage = MinioStorage::with_backends(
+            Box::new(raw.clone()),
+            Box::new(MemoryBackend::new("pipeline-clean")),
+        );
+
+        for key in [
+            "2024/03/04/raw/test-api/20240304-090000.json",
+            "2024/03/05/raw/test-api/20240305-080000.json",
+            "2024/03/05/raw/test-api/20240305-193000.json",
+            "2024/03/06/raw/test-api/20240306-070000.json",
+            "2024/03/05/raw/other-api/20240305-235959.json",
+        ] {
+            raw.put_object(key, br#"[{"name": "Milk"}]"#).await.unwrap();
+        }
+
+        let date = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+        let key = storage.get_raw_file_for_date("test-api", date).await.unwrap();
+        assert_eq!(key, "2024/03/05/raw/test-api/20240305-193000.json");
+
+        let first = NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+        let key = storage.get_raw_file_for_date("test-api", first).await.unwrap();
+        assert_eq!(key, "2024/03/04/raw/test-api/20240304-090000.json");
+
+        let data = storage.load_raw_data_for_date("test-api", date).await.unwrap();
+        assert_eq!(data.len(), 1);
+
+        // A missing date lists what is available instead
+        let missing = NaiveDate::from_ymd_opt(2024, 3, 7).unwrap();
+        let err = storage
+            .get_raw_file_for_date("test-api", missing)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("2024-03-07"));
+        assert!(err.contains("Available dates: 2024-03-04, 2024-03-05, 2024-03-06"));
+        let fetched_at = MinioStorage::raw_file_fetched_at(&key).unwrap();
+        assert_eq!(fetched_at.to_rfc3339(), "2024-03-04T09:00:00+00:00");
+        assert!(MinioStorage::raw_file_fetched_at("clean/test-api/latest.parquet").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_raw_file_by_snapshot() {
+        let raw = MemoryBackend::new("pipeline-raw");
+        let storage = MinioStorage::with_backends(
+            Box::new(raw.clone()),
+            Box::new(MemoryBackend::new("pipeline-clean")),
+        );
+        for key in [
+            "2024/03/05/raw/test-api/20240305-080000.json",
+            "2024/03/06/raw/test-api/20240306-070000.json",
+            "2024/03/05/raw/other-api/20240305-235959.json",
+        ] {
+            raw.put_object(key, br#"[{"name": "Milk"}]"#).await.unwrap();
+        }
+
+        let latest = storage.resolve_raw_file("test-api", &RawSnapshot::Latest).await.unwrap();
+        assert_eq!(latest, "2024/03/06/raw/test-api/20240306-070000.json");
+        let date = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+        let on_date = storage.resolve_raw_file("test-api", &RawSnapshot::Date(date)).await.unwrap();
+        assert_eq!(on_date, "2024/03/05/raw/test-api/20240305-080000.json");
+
+        let key = RawSnapshot::Key("2024/03/05/raw/test-api/20240305-080000.json".to_string());
+        assert_eq!(storage.resolve_raw_file("test-api", &key).await.unwrap(), on_date);
+        assert!(key.applies_to("test-api"));
+        assert!(!key.applies_to("other-api"));
+        assert_eq!(key.date(), Some(date));
+        assert!(RawSnapshot::Latest.applies_to("other-api"));
+
+        // A key of another source, or one that does not exist, is refused
+        let other = RawSnapshot::Key("2024/03/05/raw/other-api/20240305-235959.json".to_string());
+        assert!(storage.resolve_raw_file("test-api", &other).await.is_err());
+        let missing = RawSnapshot::Key("2024/03/07/raw/test-api/20240307-000000.json".to_string());
+        let err = storage.resolve_raw_file("test-api", &missing).await.unwrap_err().to_string();
+        assert!(err.contains("20240307-000000.json"));
+
+        assert_eq!(MinioStorage::raw_file_api("raw/krave_mart_1242164/20250915-101500.json"), Some("krave_mart_1242164"));
+        assert_eq!(MinioStorage::raw_file_api("clean/test-api/latest.parquet"), None);
+        assert_eq!(MinioStorage::raw_file_api("20250915-101500.json"), None);
+    }
+
+    #[tokio::test]
+    async fn test_single_bucket_routing_unchanged() {
+        let bucket = MemoryBackend::new("data-pipeline");
+        let storage =
+            MinioStorage::with_backends(Box::new(bucket.clone()), Box::new(bucket.clone()));
+
+        let raw_key = storage.store_raw_json("test-api", "[]").await.unwrap();
+        let clean_key = storage.store_parquet("test-api", b"PAR1").await.unwrap();
+
+        // Raw dump, its content hash sidecar and the parquet file
+        assert_eq!(bucket.keys().len(), 3);
+        assert!(bucket.contains(&raw_key));
+        assert!(bucket.contains(&raw_hash_key(&raw_key)));
+        assert!(bucket.contains(&clean_key));
+        assert_eq!(storage.list_objects(None).await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_raw_dump_is_not_stored_again() {
+        let raw = MemoryBackend::new("pipeline-raw");
+        let storage = MinioStorage::with_backends(
+            Box::new(raw.clone()),
+            Box::new(MemoryBackend::new("pipeline-clean")),
+        );
+
+        let first = storage
+            .store_raw_json_checked("test-api", r#"[{"name": "Milk"}]"#, false)
+            .await
+            .unwrap();
+        assert!(!first.is_unchanged());
+
+        let second = storage
+            .store_raw_json_checked("test-api", r#"[{"name": "Milk"}]"#, false)
+            .await
+            .unwrap();
+        assert_eq!(second, RawStoreOutcome::Unchanged(first.key().to_string()));
+        assert_eq!(raw.keys().len(), 2);
+
+        // --force stores even identical payloads
+        let forced = storage
+            .store_raw_json_checked("test-api", r#"[{"name": "Milk"}]"#, true)
+            .await
+            .unwrap();
+        assert!(!forced.is_unchanged());
+
+        // Dumps stored before hashing was introduced have no sidecar
+        raw.delete_object(&raw_hash_key(first.key())).await.unwrap();
+        let legacy = storage
+            .store_raw_json_checked("test-api", r#"[{"name": "Milk"}]"#, false)
+            .await
+            .unwrap();
+        assert!(!legacy.is_unchanged());
+    }
+
+    #[tokio::test]
+    async fn test_limited_fetch_sidecar() {
+        let storage = MinioStorage::with_backends(
+            Box::new(MemoryBackend::new("pipeline-raw")),
+            Box::new(MemoryBackend::new("pipeline-clean")),
+        );
+        assert_eq!(LimitedFetch::for_run(None, &[]), None);
+
+        let full_key = storage.store_raw_json("test-api", "[]").await.unwrap();
+        assert_eq!(storage.load_limited_fetch(&full_key).await.unwrap(), None);
+
+        let limited = LimitedFetch::for_run(Some(50), &["beverages".to_string()]).unwrap();
+        storage.store_limited_fetch(&full_key, &limited).await.unwrap();
+        assert_eq!(storage.load_limited_fetch(&full_key).await.unwrap(), Some(limited));
+        // The sidecar is never listed as a raw dump of its own
+        assert_eq!(storage.get_latest_raw_file("test-api").await.unwrap(), Some(full_key));
+    }
+
+    #[tokio::test]
+    async fn test_clean_snapshots_and_changes() {
+        let raw = MemoryBackend::new("pipeline-raw");
+        let clean = MemoryBackend::new("pipeline-clean");
+        let storage = MinioStorage::with_backends(Box::new(raw.clone()), Box::new(clean.clone()));
+
+        let mut df = df!("product_id" => ["1", "2"], "cost_price" => [10.0, 20.0]).unwrap();
+        let mut buf = Vec::new();
+        ParquetWriter::new(&mut buf).finish(&mut df).unwrap();
+
+        for key in [
+            "clean/test-api/20250914-080000.parquet",
+            "clean/test-api/20250915-080000.parquet",
+            "clean/other-api/20250916-080000.parquet",
+        ] {
+            clean.put_object(key, &buf).await.unwrap();
+        }
+
+        let files = storage.list_clean_files("test-api").await.unwrap();
+        assert_eq!(
+            files,
+            vec![
+                "clean/test-api/20250915-080000.parquet",
+                "clean/test-api/20250914-080000.parquet",
+            ]
+        );
+
+        let loaded = storage.load_parquet(&files[0]).await.unwrap();
+        assert_eq!(loaded.height(), 2);
+
+        let date = NaiveDate::from_ymd_opt(2025, 9, 15).unwrap();
+        let key = storage.store_changes("test-api", date, &buf).await.unwrap();
+        assert_eq!(key, "changes/test-api/2025-09-15.parquet");
+        assert!(clean.contains(&key));
+        assert!(!raw.contains(&key));
+        let key = storage.store_error_report("test-api", date, "{}").await.unwrap();
+        assert_eq!(key, "errors/test-api/2025-09-15.json");
+        assert!(clean.contains(&key));
+        let key = storage.store_rejected_records("test-api", date, "{}").await.unwrap();
+        assert_eq!(key, "rejected/test-api/2025-09-15.json");
+        assert_eq!(StorageTier::for_key(&key), StorageTier::Clean);
+        assert!(clean.contains(&key));
+        assert!(!raw.contains(&key));
+        assert_eq!(storage.get_object(&key).await.unwrap(), b"{}");
+        storage.delete_object(&key).await.unwrap();
+        assert!(!clean.contains(&key));
+
+        let key = storage.store_classification_report("test-api", date, "{}").await.unwrap();
+        assert_eq!(key, "reports/test-api/2025-09-15/classification.json");
+        assert!(clean.contains(&key));
+
+        let started_at = Utc.with_ymd_and_hms(2025, 9, 15, 10, 15, 0).unwrap();
+        let key = storage.store_run_report(started_at, "{}").await.unwrap();
+        assert_eq!(key, "reports/2025-09-15/run_101500.json");
+        assert!(clean.contains(&key));
+        let key = storage.store_data_quality_report(started_at, "{}").await.unwrap();
+        assert_eq!(key, "reports/2025-09-15/quality_101500.json");
+        assert!(clean.contains(&key));
+
+        assert_eq!(storage.load_baseline_stats("test-api").await.unwrap(), None);
+        let stats = SnapshotStats::from_dataframe(&df!("name" => ["milk"]).unwrap());
+        let key = storage.store_baseline_stats("test-api", &stats).await.unwrap();
+        assert_eq!(key, "reports/test-api/baseline_stats.json");
+        assert!(clean.contains(&key));
+        assert_eq!(storage.load_baseline_stats("test-api").await.unwrap(), Some(stats));
+    }
+
+    #[tokio::test]
+    async fn test_store_merged_dataset() {
+        let raw = MemoryBackend::new("pipeline-raw");
+        let clean = MemoryBackend::new("pipeline-clean");
+        let storage = MinioStorage::with_backends(Box::new(raw.clone()), Box::new(clean.clone()));
+
+        let date = NaiveDate::from_ymd_opt(2025, 9, 15).unwrap();
+        let data_key = storage.store_merged_parquet(date, b"PAR1").await.unwrap();
+        let manifest_key = storage.store_merged_manifest(date, "{}").await.unwrap();
+
+        assert_eq!(data_key, "clean/_merged/date=2025-09-15/merged.parquet");
+        assert_eq!(manifest_key, "clean/_merged/date=2025-09-15/manifest.json");
+        assert!(clean.contains(&data_key));
+        assert!(clean.contains(&manifest_key));
+        assert!(raw.keys().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_store_arrow_ipc_matches_parquet() {
+        let raw = MemoryBackend::new("pipeline-raw");
+        let clean = MemoryBackend::new("pipeline-clean");
+        let storage = MinioStorage::with_backends(Box::new(raw.clone()), Box::new(clean.clone()));
+        let date = NaiveDate::from_ymd_opt(2025, 9, 15).unwrap();
+
+        let mut df = df!(
+            "name" => ["milk", "eggs", "milk"],
+            "cost_price" => [Some(290.0), None, Some(300.0)],
+            "stock_quantity" => [Some(3i64), None, Some(0)],
+            "in_stock" => [true, false, true]
+        )
+        .unwrap();
+        let mut parquet = Vec::new();
+        ParquetWriter::new(&mut parquet).finish(&mut df).unwrap();
+        let parquet_schema = ParquetReader::new(Cursor::new(parquet)).finish().unwrap().schema().clone();
+
+        for compression in [ArrowCompression::None, ArrowCompression::Lz4, ArrowCompression::Zstd] {
+            let key = storage.store_arrow_ipc("naheed", date, &mut df, compression).await.unwrap();
+            assert_eq!(key, "clean_arrow/naheed/date=2025-09-15/data.arrow");
+            assert!(clean.contains(&key));
+
+            let bytes = storage.get_object(&key).await.unwrap();
+            let stored = IpcReader::new(Cursor::new(bytes)).finish().unwrap();
+            assert!(stored.equals_missing(&df), "{:?}: {}", compression, stored);
+            assert_eq!(stored.schema(), &parquet_schema, "{:?}", compression);
+        }
+        assert!(raw.keys().is_empty());
+        assert_eq!("feather".parse::<OutputFormat>().unwrap(), OutputFormat::Arrow);
+        assert!("xlsx".parse::<OutputFormat>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_store_csv_round_trips_quoted_names() {
+        let clean = MemoryBackend::new("pipeline-clean");
+        let storage = MinioStorage::with_backends(Box::new(MemoryBackend::new("pipeline-raw")), Box::new(clean.clone()));
+        let date = NaiveDate::from_ymd_opt(2025, 9, 15).unwrap();
+        let mut df = df!(
+            "name" => ["Tea, 950g", "The \"Best\" Milk", "Eggs; 12 pieces"],
+            "cost_price" => [Some(1450.5), None, Some(420.0)]
+        )
+        .unwrap();
+
+        for delimiter in [b',', b';'] {
+            let key = storage.store_csv("naheed", date, &mut df, delimiter).await.unwrap();
+            assert_eq!(key, "clean_csv/naheed/date=2025-09-15/data.csv");
+
+            let bytes = storage.get_object(&key).await.unwrap();
+            let text = String::from_utf8(bytes.clone()).unwrap();
+            assert!(text.contains("\"The \"\"Best\"\" Milk\""), "{}", text);
+
+            let read = CsvReadOptions::default()
+                .with_parse_options(CsvParseOptions::default().with_separator(delimiter))
+                .into_reader_with_file_handle(Cursor::new(bytes))
+                .finish()
+                .unwrap();
+            assert!(read.equals_missing(&df), "{}", read);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_store_ndjson_gzips_one_row_per_line() {
+        use flate2::read::GzDecoder;
+        use std::io::Read;
+
+        let clean = MemoryBackend::new("pipeline-clean");
+        let storage = MinioStorage::with_backends(Box::new(MemoryBackend::new("pipeline-raw")), Box::new(clean.clone()));
+        let date = NaiveDate::from_ymd_opt(2025, 9, 15).unwrap();
+        let df = df!("name" => ["Tea", "Eggs"], "cost_price" => [Some(1450.5), None]).unwrap();
+
+        let key = storage.store_ndjson("naheed", date, &df, false).await.unwrap();
+        assert_eq!(key, "clean_ndjson/naheed/date=2025-09-15/data.ndjson.gz");
+
+        let mut lines = String::new();
+        GzDecoder::new(Cursor::new(storage.get_object(&key).await.unwrap()))
+            .read_to_string(&mut lines)
+            .unwrap();
+        assert_eq!(lines, "{\"name\":\"Tea\",\"cost_price\":1450.5}\n{\"name\":\"Eggs\"}\n");
+    }
+
+    #[tokio::test]
+    async fn test_delete_prefix_and_dry_run() {
+        let raw = MemoryBackend::new("pipeline-raw");
+        let clean = MemoryBackend::new("pipeline-clean");
+        let storage = MinioStorage::with_backends(Box::new(raw.clone()), Box::new(clean.clone()));
+
+        for i in 0..3 {
+            raw.put_object(&format!("2025/09/15/raw/test-api/{}.json", i), b"[]")
+                .await
+                .unwrap();
+        }
+        clean
+            .put_object("clean/test-api/20250915-080000.parquet", b"PAR1")
+            .await
+            .unwrap();
+
+        // Dry run reports the matching keys without deleting anything
+        let summary = storage.delete_prefix("2025/09/15/", true).await.unwrap();
+        assert!(summary.dry_run);
+        assert_eq!(summary.deleted_count(), 3);
+        assert_eq!(raw.keys().len(), 3);
+
+        let summary = storage.delete_prefix("2025/09/15/", false).await.unwrap();
+        assert_eq!(summary.deleted_count(), 3);
+        assert_eq!(summary.failed_count(), 0);
+        assert!(raw.keys().is_empty());
+        assert_eq!(clean.keys().len(), 1);
+
+        // Bulk delete routes keys to their tier
+        let keys = vec!["clean/test-api/20250915-080000.parquet".to_string()];
+        let summary = storage.delete_objects(&keys, false).await.unwrap();
+        assert_eq!(summary.deleted, keys);
+        assert!(clean.keys().is_empty());
+    }
+
+    /// Backend that delegates to memory but fails at a chosen stage
+    struct FailingBackend {
+        inner: MemoryBackend,
+        fail_at: &'static str,
+    }
+
+    #[async_trait]
+    impl ObjectBackend for FailingBackend {
+        fn bucket_name(&self) -> &str {
+            self.inner.bucket_name()
+        }
+
+        async fn check_connectivity(&self) -> Result<String> {
+            if self.fail_at == "connectivity" {
+                return Err(anyhow!("DNS lookup failed for minio.invalid:9000"));
+            }
+            self.inner.check_connectivity().await
+        }
+
+        async fn bucket_exists(&self) -> Result<bool> {
+            if self.fail_at == "authentication" {
+                return Err(anyhow!("HTTP 403: InvalidAccessKeyId"));
+            }
+            self.inner.bucket_exists().await
+        }
+
+        async fn create_bucket(&self) -> Result<()> {
+            self.inner.create_bucket().await
+        }
+
+        async fn put_object(&self, key: &str, data: &[u8]) -> Result<u16> {
+            if self.fail_at == "write" {
+                return Ok(403);
+            }
+            self.inner.put_object(key, data).await
+        }
+
+        async fn get_object(&self, key: &str) -> Result<(u16, Vec<u8>)> {
+            self.inner.get_object(key).await
+        }
+
+        async fn list_keys(&self, prefix: &str) -> Result<Vec<String>> {
+            self.inner.list_keys(prefix).await
+        }
+
+        async fn delete_object(&self, key: &str) -> Result<u16> {
+            self.inner.delete_object(key).await
+        }
+    }
+
+    async fn failing_storage(fail_at: &'static str) -> MinioStorage {
+        let inner = MemoryBackend::new("data-pipeline");
+        inner.create_bucket().await.unwrap();
+        let backend = || Box::new(FailingBackend { inner: inner.clone(), fail_at });
+        MinioStorage::with_backends(backend(), backend())
+    }
+
+    /// Backend whose downloads break off once `chunk` bytes have arrived,
+    /// recording the offset each attempt resumed from
+    struct FlakyBackend {
+        inner: MemoryBackend,
+        chunk: usize,
+        offsets: Arc<std::sync::Mutex<Vec<u64>>>,
+    }
+
+    #[async_trait]
+    impl ObjectBackend for FlakyBackend {
+        fn bucket_name(&self) -> &str {
+            self.inner.bucket_name()
+        }
+
+        async fn bucket_exists(&self) -> Result<bool> {
+            self.inner.bucket_exists().await
+        }
+
+        async fn create_bucket(&self) -> Result<()> {
+            self.inner.create_bucket().await
+        }
+
+        async fn put_object(&self, key: &str, data: &[u8]) -> Result<u16> {
+            self.inner.put_object(key, data).await
+        }
+
+        async fn get_object(&self, key: &str) -> Result<(u16, Vec<u8>)> {
+            self.inner.get_object(key).await
+        }
+
+        async fn get_object_from(&self, key: &str, offset: u64) -> Result<(u16, ObjectStream)> {
+            self.offsets.lock().unwrap().push(offset);
+            let (status, body) = self.inner.get_object_from(key, offset).await?;
+            let mut rest: Vec<u8> = body.map(|chunk| chunk.unwrap()).concat().await;
+            if rest.len() >= self.chunk {
+                rest.truncate(self.chunk);
+                let chunks = vec![Ok(rest), Err(anyhow!("connection reset by peer"))];
+                return Ok((status, futures::stream::iter(chunks).boxed()));
+            }
+            Ok((status, futures::stream::once(async move { Ok(rest) }).boxed()))
+        }
+
+        async fn object_size(&self, key: &str) -> Result<u64> {
+            self.inner.object_size(key).await
+        }
+
+        async fn list_keys(&self, prefix: &str) -> Result<Vec<String>> {
+            self.inner.list_keys(prefix).await
+        }
+
+        async fn delete_object(&self, key: &str) -> Result<u16> {
+            self.inner.delete_object(key).await
+        }
+    }
+
+    /// Flaky backend behind a proxy that drops the Range header, answering
+    /// resumed downloads with 200 and the whole object
+    struct RangeIgnoringBackend {
+        flaky: FlakyBackend,
+    }
+
+    #[async_trait]
+    impl ObjectBackend for RangeIgnoringBackend {
+        fn bucket_name(&self) -> &str {
+            self.flaky.bucket_name()
+        }
+
+        async fn bucket_exists(&self) -> Result<bool> {
+            self.flaky.bucket_exists().await
+        }
+
+        async fn create_bucket(&self) -> Result<()> {
+            self.flaky.create_bucket().await
+        }
+
+        async fn put_object(&self, key: &str, data: &[u8]) -> Result<u16> {
+            self.flaky.put_object(key, data).await
+        }
+
+        async fn get_object(&self, key: &str) -> Result<(u16, Vec<u8>)> {
+            self.flaky.get_object(key).await
+        }
+
+        async fn get_object_from(&self, key: &str, offset: u64) -> Result<(u16, ObjectStream)> {
+            if offset == 0 {
+                return self.flaky.get_object_from(key, 0).await;
+            }
+            self.flaky.offsets.lock().unwrap().push(offset);
+            self.flaky.inner.get_object_from(key, 0).await
+        }
+
+        async fn list_keys(&self, prefix: &str) -> Result<Vec<String>> {
+            self.flaky.list_keys(prefix).await
+        }
+
+        async fn delete_object(&self, key: &str) -> Result<u16> {
+            self.flaky.delete_object(key).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_object_resumes_broken_downloads() {
+        let key = "raw/dealcart/20250915-080000.json";
+        let body = b"[{\"id\": 1}, {\"id\": 2}]".to_vec();
+        let get_in_chunks = |chunk: usize, ignore_range: bool, key: &'static str| {
+            let inner = MemoryBackend::new("data-pipeline");
+            let body = body.clone();
+            async move {
+                inner.put_object("raw/dealcart/20250915-080000.json", &body).await.unwrap();
+                let offsets = Arc::new(std::sync::Mutex::new(Vec::new()));
+                let backend = FlakyBackend { inner: inner.clone(), chunk, offsets: offsets.clone() };
+                let backend: Box<dyn ObjectBackend> = if ignore_range {
+                    Box::new(RangeIgnoringBackend { flaky: backend })
+                } else {
+                    Box::new(backend)
+                };
+                let result = MinioStorage::with_backends(backend, Box::new(inner)).get_object(key).await;
+                let offsets = offsets.lock().unwrap().clone();
+                (result, offsets)
+            }
+        };
+
+        // Each attempt picks up where the last one broke off
+        let (bytes, offsets) = get_in_chunks(10, false, key).await;
+        assert_eq!(bytes.unwrap(), body);
+        assert_eq!(offsets, vec![0, 10, 20]);
+
+        // A server ignoring the range sends the whole object again, which
+        // replaces the partial bytes instead of being appended to them
+        let (bytes, offsets) = get_in_chunks(10, true, key).await;
+        assert_eq!(bytes.unwrap(), body);
+        assert_eq!(offsets, vec![0, 10]);
+
+        // Breaking off after the last byte leaves nothing to resume: the
+        // range is not satisfiable and the download is complete
+        let (bytes, offsets) = get_in_chunks(body.len(), false, key).await;
+        assert_eq!(bytes.unwrap(), body);
+        assert_eq!(offsets, vec![0, body.len() as u64]);
+
+        let (result, offsets) = get_in_chunks(5, false, key).await;
+        let error = format!("{:#}", result.unwrap_err());
+        assert!(error.contains("after 3 attempts (15 bytes received)"), "{}", error);
+        assert_eq!(offsets.len(), GET_ATTEMPTS);
+
+        // A missing object is not retried
+        let (result, offsets) = get_in_chunks(5, false, "raw/dealcart/missing.json").await;
+        assert!(result.unwrap_err().to_string().contains("HTTP 404"));
+        assert_eq!(offsets, vec![0]);
+    }
+
+    #[tokio::test]
+    async fn test_health_check_passes_and_cleans_up() {
+        let raw = MemoryBackend::new("pipeline-raw");
+        let clean = MemoryBackend::new("pipeline-clean");
+        let storage = MinioStorage::with_backends(Box::new(raw.clone()), Box::new(clean.clone()));
+        storage.ensure_bucket().await.unwrap();
+
+        let report = storage.health_check().await;
+        assert!(report.is_healthy(), "{}", report);
+        // connectivity + auth, then bucket/write/read/delete for each tier
+        assert_eq!(report.checks.len(), 10);
+        assert!(raw.keys().is_empty());
+        assert!(clean.keys().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_health_check_reports_failing_stage() {
+        for (fail_at, expected_stage, expected_message) in [
+            ("connectivity", "connectivity", "DNS/network"),
+            ("authentication", "authentication", "Authentication failed"),
+            ("write", "write [data-pipeline]", "HTTP 403"),
+        ] {
+            let report = failing_storage(fail_at).await.health_check().await;
+
+            let failure = report.first_failure().unwrap();
+            assert_eq!(failure.name, expected_stage);
+            assert!(failure.message.contains(expected_message), "{}", failure.message);
+            assert_eq!(report.checks.last().unwrap().status, CheckStatus::Skipped);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_health_check_missing_bucket() {
+        let storage = MinioStorage::with_backends(
+            Box::new(MemoryBackend::new("missing")),
+            Box::new(MemoryBackend::new("missing")),
+        );
+
+        let report = storage.health_check().await;
+        let failure = report.first_failure().unwrap();
+        assert_eq!(failure.name, "bucket [missing]");
+        assert!(failure.message.contains("does not exist"));
+    }
 }
@@ -1,3 +1,5 @@
+pub mod backend;
+pub mod health;
 pub mod minio_client;
 #[allow(dead_code)]
 pub mod storage_manager;
@@ -0,0 +1,381 @@
+//! End-to-end fetch -> flatten -> classify -> normalize runs against a local
+//! mock API serving canned, paginated JSON.
+
+use std::sync::{Arc, Mutex};
+
+use data_pipeline::config::ApiConfig;
+use data_pipeline::fetcher::UnifiedFetcher;
+use data_pipeline::processor::{FieldClassifier, JsonFlattener, RuleNormalizer};
+use polars::prelude::*;
+use serde_json::{Value, json};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// A request the mock API received
+#[derive(Debug, Clone)]
+struct Recorded {
+    method: String,
+    path: String,
+    /// Header names lowercased
+    headers: Vec<(String, String)>,
+    body: Value,
+}
+
+impl Recorded {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(header, _)| header == name).map(|(_, value)| value.as_str())
+    }
+}
+
+type Handler = dyn Fn(&Recorded) -> (u16, Value) + Send + Sync;
+
+/// Minimal HTTP/1.1 server answering each request with the handler's status
+/// and JSON body, one request per connection
+struct MockApi {
+    base_url: String,
+    requests: Arc<Mutex<Vec<Recorded>>>,
+}
+
+impl MockApi {
+    async fn start(handler: impl Fn(&Recorded) -> (u16, Value) + Send + Sync + 'static) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let handler: Arc<Handler> = Arc::new(handler);
+
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let handler = handler.clone();
+                let recorded = recorded.clone();
+                tokio::spawn(async move { serve(stream, handler.as_ref(), &recorded).await });
+            }
+        });
+
+        MockApi { base_url, requests }
+    }
+
+    fn requests(&self) -> Vec<Recorded> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+async fn serve(
+    mut stream: TcpStream,
+    handler: &Handler,
+    recorded: &Mutex<Vec<Recorded>>,
+) -> Option<()> {
+    let mut raw = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let header_end = loop {
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        raw.extend_from_slice(&chunk[..read]);
+        if let Some(end) = raw.windows(4).position(|window| window == b"\r\n\r\n") {
+            break end + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&raw[..header_end]).to_string();
+    let mut request_line = head.lines().next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.to_string();
+    let headers: Vec<(String, String)> = head
+        .lines()
+        .skip(1)
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+    let content_length = headers
+        .iter()
+        .find(|(name, _)| name == "content-length")
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    while raw.len() < header_end + content_length {
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            break;
+        }
+        raw.extend_from_slice(&chunk[..read]);
+    }
+
+    let body = serde_json::from_slice(&raw[header_end..]).unwrap_or(Value::Null);
+    let request = Recorded { method, path, headers, body };
+    let (status, response) = handler(&request);
+    // Recorded before replying so the fetcher never sees a response the
+    // test cannot yet account for
+    recorded.lock().unwrap().push(request);
+    let payload = response.to_string();
+    let reply = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        payload.len(),
+        payload
+    );
+    stream.write_all(reply.as_bytes()).await.ok()?;
+    stream.shutdown().await.ok()
+}
+
+/// Page number from a `?page=N` query string
+fn page_param(path: &str) -> Option<u32> {
+    path.split_once("?page=")?.1.parse().ok()
+}
+
+/// Flatten, classify and normalize fetched products the way `main` does
+fn process(config: &ApiConfig, products: &[Value]) -> DataFrame {
+    let flattener = JsonFlattener::new().with_rules(config.extraction_rules().unwrap());
+    let mut df = flattener.flatten_to_dataframe(products).unwrap().dataframe;
+    FieldClassifier::new().map_to_canonical_schema(&mut df).unwrap();
+    RuleNormalizer::new().normalize_dataframe(&mut df).unwrap();
+    df
+}
+
+fn strings(df: &DataFrame, column: &str) -> Vec<Option<String>> {
+    df.column(column)
+        .unwrap()
+        .cast(&DataType::String)
+        .unwrap()
+        .str()
+        .unwrap()
+        .into_iter()
+        .map(|value| value.map(str::to_string))
+        .collect()
+}
+
+fn floats(df: &DataFrame, column: &str) -> Vec<Option<f64>> {
+    df.column(column)
+        .unwrap()
+        .cast(&DataType::Float64)
+        .unwrap()
+        .f64()
+        .unwrap()
+        .into_iter()
+        .collect()
+}
+
+#[tokio::test]
+async fn test_get_source_end_to_end() {
+    // Page 2 fails and page 4 is empty, neither ends pagination on its own;
+    // pages 4 and 5 are the two consecutive empty pages that do
+    let api = MockApi::start(|request| match page_param(&request.path) {
+        Some(1) => (
+            200,
+            json!({"data": [{"krave_mart_products": [
+                {"product_id": "101", "name": "Olpers Milk 1 Litre", "cost_price": "Rs. 280", "mrp": "300", "sku": "MILK-1"},
+                {"product_id": "102", "name": "Fresh Bananas (Kela)", "cost_price": "150", "mrp": "150", "sku": "BAN-12"}
+            ]}]}),
+        ),
+        Some(2) => (500, json!({"error": "upstream timeout"})),
+        Some(3) => (
+            200,
+            json!({"data": [{"krave_mart_products": [
+                {"product_id": "103", "name": "Nestle Water 1.5 Litre", "cost_price": "Rs. 90", "mrp": "100", "sku": "WAT-15"}
+            ]}]}),
+        ),
+        _ => (200, json!({"data": [{"krave_mart_products": []}]})),
+    })
+    .await;
+
+    let config: ApiConfig = toml::from_str(&format!(
+        r#"
+        [api]
+        name = "mock_get"
+        base_url = "{}"
+        auth_token = ""
+        currency = "PKR"
+
+        [request]
+        method = "GET"
+        authorization = "Bearer test-token"
+
+        [request.headers]
+
+        [response]
+        data_path = "data[].krave_mart_products[]"
+
+        [pagination]
+        type = "page"
+
+        [fields]
+        target_fields = ["cost_price", "mrp", "name"]
+
+        [fields.extraction]
+        product_id = ["product_id"]
+        name = ["name"]
+        cost_price = ["cost_price"]
+        mrp = ["mrp"]
+        sku = ["sku"]
+
+        [categories]
+        grocery = {{ name = "Grocery", category_ids = "11,12" }}
+        "#,
+        api.base_url
+    ))
+    .unwrap();
+
+    let products = UnifiedFetcher::new(config.clone())
+        .unwrap()
+        .fetch_all_categories()
+        .await
+        .unwrap();
+    assert_eq!(products.len(), 3);
+    assert!(products.iter().all(|product| product["_source_category"] == "Grocery"));
+
+    let mut requests = api.requests();
+    requests.sort_by_key(|request| page_param(&request.path));
+    let pages: Vec<Option<u32>> = requests.iter().map(|request| page_param(&request.path)).collect();
+    assert_eq!(pages, vec![Some(1), Some(2), Some(3), Some(4), Some(5)]);
+    assert!(requests.iter().all(|request| request.method == "GET"
+        && request.path.starts_with("/api/v2/es/categories/11,12/products/")));
+
+    let df = process(&config, &products);
+    assert_eq!(df.height(), 3);
+    for column in ["product_id", "name", "cost_price", "mrp", "sku", "source"] {
+        assert!(df.column(column).is_ok(), "missing column {}", column);
+    }
+    assert_eq!(
+        strings(&df, "product_id"),
+        vec![Some("101".into()), Some("102".into()), Some("103".into())]
+    );
+    assert_eq!(floats(&df, "cost_price"), vec![Some(280.0), Some(150.0), Some(90.0)]);
+    assert_eq!(floats(&df, "mrp"), vec![Some(300.0), Some(150.0), Some(100.0)]);
+    assert_eq!(strings(&df, "source"), vec![Some("mock_get".into()); 3]);
+}
+
+#[tokio::test]
+async fn test_post_source_end_to_end() {
+    let api = MockApi::start(|request| {
+        match request.body["paginationRequestDTO"]["page"].as_u64() {
+            Some(0) => (
+                200,
+                json!([
+                    {"sku": "RICE-5", "productName": "Guard Basmati Rice 5kg", "discountedPrice": 1450, "actualPrice": 1600, "category": "Rice"},
+                    {"sku": "ATTA-10", "productName": "Sunridge Chakki Atta 10kg", "discountedPrice": 1200, "actualPrice": 1200, "category": "Flour"}
+                ]),
+            ),
+            Some(1) => (
+                200,
+                json!([
+                    {"sku": "OIL-1", "productName": "Dalda Cooking Oil 1 Litre", "discountedPrice": 560, "actualPrice": 600, "category": "Oil"}
+                ]),
+            ),
+            _ => (200, json!([])),
+        }
+    })
+    .await;
+
+    let config: ApiConfig = toml::from_str(&format!(
+        r#"
+        [api]
+        name = "mock_post"
+        base_url = "{}"
+        auth_token = ""
+
+        [request]
+        method = "POST"
+        endpoint = "/api/products/core-category"
+        product_channel = "WEB_APP"
+        page_size = 2
+
+        [request.headers]
+
+        [response]
+
+        [pagination]
+        type = "post_body"
+
+        [fields]
+        target_fields = ["discountedPrice", "actualPrice", "category", "sku"]
+
+        [fields.extraction]
+        product_id = ["sku"]
+        name = ["productName"]
+        cost_price = ["discountedPrice"]
+        mrp = ["actualPrice"]
+        sku = ["sku"]
+        category_name = ["category"]
+
+        [categories]
+        staples = {{ name = "Staples", core_category_slug = "staples" }}
+        "#,
+        api.base_url
+    ))
+    .unwrap();
+
+    let products = UnifiedFetcher::new(config.clone())
+        .unwrap()
+        .fetch_all_categories()
+        .await
+        .unwrap();
+    assert_eq!(products.len(), 3);
+
+    // Pages 0 and 1 have products, 2 and 3 are the consecutive empty pages
+    let requests = api.requests();
+    let mut pages: Vec<u64> = requests
+        .iter()
+        .filter_map(|request| request.body["paginationRequestDTO"]["page"].as_u64())
+        .collect();
+    pages.sort_unstable();
+    assert_eq!(pages, vec![0, 1, 2, 3]);
+    assert!(requests.iter().all(|request| request.method == "POST"
+        && request.path == "/api/products/core-category"
+        && request.body["coreCategorySlug"] == "staples"
+        && request.body["paginationRequestDTO"]["size"] == 2));
+
+    let df = process(&config, &products);
+    assert_eq!(df.height(), 3);
+    assert_eq!(
+        strings(&df, "sku"),
+        vec![Some("RICE-5".into()), Some("ATTA-10".into()), Some("OIL-1".into())]
+    );
+    assert_eq!(floats(&df, "cost_price"), vec![Some(1450.0), Some(1200.0), Some(560.0)]);
+    assert_eq!(floats(&df, "mrp"), vec![Some(1600.0), Some(1200.0), Some(600.0)]);
+    assert_eq!(
+        strings(&df, "category"),
+        vec![Some("rice".into()), Some("flour".into()), Some("oil".into())]
+    );
+}
+
+#[tokio::test]
+async fn test_credential_from_env_is_sent() {
+    let api = MockApi::start(|_| (200, json!({"data": [{"krave_mart_products": [{"product_id": "101", "name": "Olpers Milk 1 Litre"}]}]}))).await;
+    let config: ApiConfig = toml::from_str(&format!(
+        r#"
+        [api]
+        name = "mock_auth"
+        base_url = "{}"
+
+        [request]
+        method = "GET"
+        auth_token_env = "FETCH_PIPELINE_TEST_TOKEN"
+
+        [request.headers]
+
+        [response]
+        data_path = "data[].krave_mart_products[]"
+
+        [pagination]
+        type = "none"
+
+        [fields]
+        target_fields = ["name"]
+
+        [categories]
+        grocery = {{ name = "Grocery", category_ids = "11" }}
+        "#,
+        api.base_url
+    ))
+    .unwrap();
+
+    assert!(config.validate().unwrap_err().to_string().contains("$FETCH_PIPELINE_TEST_TOKEN"));
+    let env = |var: &str| (var == "FETCH_PIPELINE_TEST_TOKEN").then(|| "Bearer from-env".to_string());
+    config.validate_with_env(env).unwrap();
+
+    let products = UnifiedFetcher::new_with_env(config, env).unwrap().fetch_all_categories().await.unwrap();
+    assert_eq!(products.len(), 1);
+    let requests = api.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].header("authorization"), Some("Bearer from-env"));
+}